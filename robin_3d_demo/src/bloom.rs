@@ -0,0 +1,616 @@
+// Robin Engine 2.0 - Bloom
+// Makes bright surfaces glow into their surroundings. Pixels brighter than a
+// threshold are downsampled to a quarter-resolution target and blurred with
+// a separable Gaussian in two compute passes, horizontal then vertical, and
+// the blur is added back onto the HDR scene before it's resolved.
+
+/// Scene texels per bloom texel along each axis
+const DOWNSAMPLE: u32 = 4;
+const WORKGROUP_SIZE: u32 = 8;
+/// Format of the quarter-resolution blur targets
+const BLOOM_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+const BLUR_SHADER: &str = r#"
+struct BloomParams {
+    threshold: f32,
+    intensity: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: BloomParams;
+@group(0) @binding(1)
+var source: texture_2d<f32>;
+@group(0) @binding(2)
+var source_sampler: sampler;
+@group(0) @binding(3)
+var destination: texture_storage_2d<rgba16float, write>;
+
+// Normalised 9-tap Gaussian, centre tap in the middle
+const KERNEL = array<f32, 9>(0.016216, 0.054054, 0.121622, 0.194595, 0.227027, 0.194595, 0.121622, 0.054054, 0.016216);
+
+// Rec. 709 luminance of a linear colour
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// The part of the scene above the threshold, averaged over the block of
+// scene texels behind bloom texel `texel`. Four bilinear samples cover the
+// 4x4 block.
+fn bright_pass(texel: vec2<i32>) -> vec3<f32> {
+    let scene_size = vec2<f32>(textureDimensions(source));
+    let center = (vec2<f32>(texel) + 0.5) * 4.0;
+    var total = vec3<f32>(0.0);
+    for (var i = 0; i < 4; i++) {
+        let offset = vec2<f32>(f32(i % 2) * 2.0 - 1.0, f32(i / 2) * 2.0 - 1.0);
+        let color = textureSampleLevel(source, source_sampler, (center + offset) / scene_size, 0.0).rgb;
+        total += select(vec3<f32>(0.0), color, luminance(color) > params.threshold);
+    }
+    return total * 0.25;
+}
+
+@compute @workgroup_size(8, 8)
+fn cs_downsample_horizontal(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = vec2<i32>(textureDimensions(destination));
+    let texel = vec2<i32>(id.xy);
+    if texel.x >= size.x || texel.y >= size.y {
+        return;
+    }
+    var kernel = KERNEL;
+    var total = vec3<f32>(0.0);
+    for (var k = 0; k < 9; k++) {
+        let x = clamp(texel.x + k - 4, 0, size.x - 1);
+        total += bright_pass(vec2<i32>(x, texel.y)) * kernel[k];
+    }
+    textureStore(destination, texel, vec4<f32>(total, 1.0));
+}
+
+@compute @workgroup_size(8, 8)
+fn cs_vertical(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = vec2<i32>(textureDimensions(destination));
+    let texel = vec2<i32>(id.xy);
+    if texel.x >= size.x || texel.y >= size.y {
+        return;
+    }
+    var kernel = KERNEL;
+    var total = vec3<f32>(0.0);
+    for (var k = 0; k < 9; k++) {
+        let y = clamp(texel.y + k - 4, 0, size.y - 1);
+        total += textureLoad(source, vec2<i32>(texel.x, y), 0).rgb * kernel[k];
+    }
+    textureStore(destination, texel, vec4<f32>(total, 1.0));
+}
+"#;
+
+const COMPOSITE_SHADER: &str = r#"
+struct BloomParams {
+    threshold: f32,
+    intensity: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: BloomParams;
+@group(0) @binding(1)
+var bloom: texture_2d<f32>;
+@group(0) @binding(2)
+var bloom_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// One triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+    return out;
+}
+
+// Added onto the scene by the pipeline's blend state
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let glow = textureSampleLevel(bloom, bloom_sampler, in.uv, 0.0).rgb;
+    return vec4<f32>(glow * params.intensity, 0.0);
+}
+"#;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct BloomParams {
+    threshold: f32,
+    intensity: f32,
+    _padding: [f32; 2],
+}
+
+/// Blur targets and the bind groups that read the scene and them; all
+/// depend on the scene size, so they're rebuilt together on resize
+struct BloomTargets {
+    // Kept alive alongside their views
+    #[allow(dead_code)]
+    horizontal_texture: wgpu::Texture,
+    #[allow(dead_code)]
+    blurred_texture: wgpu::Texture,
+    size: (u32, u32),
+    horizontal_bind_group: wgpu::BindGroup,
+    vertical_bind_group: wgpu::BindGroup,
+    composite_bind_group: wgpu::BindGroup,
+}
+
+/// Adds a glow around the bright parts of an HDR scene texture
+pub struct Bloom {
+    /// Luminance a scene pixel must exceed to glow
+    pub threshold: f32,
+    /// Strength of the glow added back onto the scene
+    pub intensity: f32,
+    params_buffer: wgpu::Buffer,
+    sampler: wgpu::Sampler,
+    blur_layout: wgpu::BindGroupLayout,
+    composite_layout: wgpu::BindGroupLayout,
+    horizontal_pipeline: wgpu::ComputePipeline,
+    vertical_pipeline: wgpu::ComputePipeline,
+    composite_pipeline: wgpu::RenderPipeline,
+    targets: BloomTargets,
+}
+
+impl Bloom {
+    /// Blooms `scene_view`, a `scene_format` texture of the given size
+    pub fn new(
+        device: &wgpu::Device,
+        scene_view: &wgpu::TextureView,
+        scene_format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("bloom_params"),
+            size: std::mem::size_of::<BloomParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // Linear filtering averages the scene while downsampling and smooths
+        // the blur when it's stretched back over the scene
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("bloom_sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let params_entry = |visibility| wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let texture_entry = |visibility| wgpu::BindGroupLayoutEntry {
+            binding: 1,
+            visibility,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let sampler_entry = |visibility| wgpu::BindGroupLayoutEntry {
+            binding: 2,
+            visibility,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        };
+
+        let blur_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                params_entry(wgpu::ShaderStages::COMPUTE),
+                texture_entry(wgpu::ShaderStages::COMPUTE),
+                sampler_entry(wgpu::ShaderStages::COMPUTE),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: BLOOM_FORMAT,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("bloom_blur_bind_group_layout"),
+        });
+        let composite_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                params_entry(wgpu::ShaderStages::FRAGMENT),
+                texture_entry(wgpu::ShaderStages::FRAGMENT),
+                sampler_entry(wgpu::ShaderStages::FRAGMENT),
+            ],
+            label: Some("bloom_composite_bind_group_layout"),
+        });
+
+        let blur_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Bloom Blur Shader"),
+            source: wgpu::ShaderSource::Wgsl(BLUR_SHADER.into()),
+        });
+        let blur_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Bloom Blur Pipeline Layout"),
+            bind_group_layouts: &[&blur_layout],
+            push_constant_ranges: &[],
+        });
+        let blur_pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&blur_pipeline_layout),
+                module: &blur_shader,
+                entry_point,
+                compilation_options: Default::default(),
+            })
+        };
+        let horizontal_pipeline = blur_pipeline("cs_downsample_horizontal");
+        let vertical_pipeline = blur_pipeline("cs_vertical");
+
+        let composite_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Bloom Composite Shader"),
+            source: wgpu::ShaderSource::Wgsl(COMPOSITE_SHADER.into()),
+        });
+        let composite_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Bloom Composite Pipeline Layout"),
+            bind_group_layouts: &[&composite_layout],
+            push_constant_ranges: &[],
+        });
+        let composite_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Bloom Composite Pipeline"),
+            layout: Some(&composite_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &composite_shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &composite_shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: scene_format,
+                    // Adds the glow, leaving the scene's alpha alone
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::Zero,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        let targets = create_targets(
+            device,
+            &blur_layout,
+            &composite_layout,
+            &params_buffer,
+            &sampler,
+            scene_view,
+            width,
+            height,
+        );
+
+        Self {
+            threshold: 0.9,
+            intensity: 0.8,
+            params_buffer,
+            sampler,
+            blur_layout,
+            composite_layout,
+            horizontal_pipeline,
+            vertical_pipeline,
+            composite_pipeline,
+            targets,
+        }
+    }
+
+    /// Rebuilds the blur targets for a scene texture that was recreated at a
+    /// new size
+    pub fn resize(&mut self, device: &wgpu::Device, scene_view: &wgpu::TextureView, width: u32, height: u32) {
+        self.targets = create_targets(
+            device,
+            &self.blur_layout,
+            &self.composite_layout,
+            &self.params_buffer,
+            &self.sampler,
+            scene_view,
+            width,
+            height,
+        );
+    }
+
+    /// Blurs the bright parts of the scene and adds them back onto
+    /// `scene_view`, which must be the view the bloom was built for
+    pub fn apply(&self, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder, scene_view: &wgpu::TextureView) {
+        let params = BloomParams {
+            threshold: self.threshold,
+            intensity: self.intensity,
+            _padding: [0.0; 2],
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
+
+        let (width, height) = self.targets.size;
+        let groups = (width.div_ceil(WORKGROUP_SIZE), height.div_ceil(WORKGROUP_SIZE));
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Bloom Blur Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.horizontal_pipeline);
+            pass.set_bind_group(0, &self.targets.horizontal_bind_group, &[]);
+            pass.dispatch_workgroups(groups.0, groups.1, 1);
+            pass.set_pipeline(&self.vertical_pipeline);
+            pass.set_bind_group(0, &self.targets.vertical_bind_group, &[]);
+            pass.dispatch_workgroups(groups.0, groups.1, 1);
+        }
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Bloom Composite Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: scene_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.composite_pipeline);
+        render_pass.set_bind_group(0, &self.targets.composite_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_targets(
+    device: &wgpu::Device,
+    blur_layout: &wgpu::BindGroupLayout,
+    composite_layout: &wgpu::BindGroupLayout,
+    params_buffer: &wgpu::Buffer,
+    sampler: &wgpu::Sampler,
+    scene_view: &wgpu::TextureView,
+    width: u32,
+    height: u32,
+) -> BloomTargets {
+    let size = ((width / DOWNSAMPLE).max(1), (height / DOWNSAMPLE).max(1));
+    let create_texture = |label| {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: BLOOM_FORMAT,
+            usage: wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            label: Some(label),
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (texture, view)
+    };
+    let (horizontal_texture, horizontal_view) = create_texture("bloom_horizontal_texture");
+    let (blurred_texture, blurred_view) = create_texture("bloom_blurred_texture");
+
+    let blur_bind_group = |label, source, destination| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: blur_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(destination),
+                },
+            ],
+            label: Some(label),
+        })
+    };
+    let horizontal_bind_group = blur_bind_group("bloom_horizontal_bind_group", scene_view, &horizontal_view);
+    let vertical_bind_group = blur_bind_group("bloom_vertical_bind_group", &horizontal_view, &blurred_view);
+    let composite_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: composite_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&blurred_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+        label: Some("bloom_composite_bind_group"),
+    });
+
+    BloomTargets {
+        horizontal_texture,
+        blurred_texture,
+        size,
+        horizontal_bind_group,
+        vertical_bind_group,
+        composite_bind_group,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: u32 = 64;
+    /// The white quad covers scene texels 24..40 on both axes
+    const QUAD: std::ops::Range<u32> = 24..40;
+    /// Half-precision 1.0
+    const F16_ONE: u16 = 0x3c00;
+
+    /// Reads back a 64x64 Rgba16Float texture as one red channel per texel
+    fn read_red(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture) -> Vec<u16> {
+        let bytes_per_row = SIZE * 8;
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (bytes_per_row * SIZE) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            texture.size(),
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+        readback.slice(..).map_async(wgpu::MapMode::Read, |_| {});
+        device.poll(wgpu::Maintain::Wait);
+        let data = readback.slice(..).get_mapped_range();
+        bytemuck::cast_slice::<u8, u16>(&data).iter().step_by(4).copied().collect()
+    }
+
+    #[test]
+    fn test_bright_quad_glows_into_its_surroundings() {
+        let instance = wgpu::Instance::default();
+        let adapter = match pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(
+            &instance, None,
+        )) {
+            Some(adapter) => adapter,
+            None => {
+                eprintln!("No GPU adapter available, skipping headless render test");
+                return;
+            }
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                required_features: wgpu::Features::empty(),
+                required_limits: wgpu::Limits::downlevel_defaults(),
+            },
+            None,
+        ))
+        .unwrap();
+
+        let format = wgpu::TextureFormat::Rgba16Float;
+        let scene = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: SIZE,
+                height: SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            label: Some("test_scene"),
+            view_formats: &[],
+        });
+        let scene_view = scene.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // A white quad on black
+        let mut texels = vec![0u16; (SIZE * SIZE * 4) as usize];
+        for y in QUAD {
+            for x in QUAD {
+                let index = ((y * SIZE + x) * 4) as usize;
+                texels[index..index + 4].copy_from_slice(&[F16_ONE; 4]);
+            }
+        }
+        queue.write_texture(
+            scene.as_image_copy(),
+            bytemuck::cast_slice(&texels),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(SIZE * 8),
+                rows_per_image: None,
+            },
+            scene.size(),
+        );
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let bloom = Bloom::new(&device, &scene_view, format, SIZE, SIZE);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Test Encoder"),
+        });
+        bloom.apply(&queue, &mut encoder, &scene_view);
+        queue.submit(std::iter::once(encoder.finish()));
+        device.poll(wgpu::Maintain::Wait);
+        let error = pollster::block_on(device.pop_error_scope());
+        assert!(error.is_none(), "validation error in bloom passes: {:?}", error);
+
+        let red = read_red(&device, &queue, &scene);
+        let at = |x: u32, y: u32| red[(y * SIZE + x) as usize];
+        // Just outside the quad on every side, and at its corners
+        for (x, y) in [(22, 32), (41, 32), (32, 22), (32, 41), (22, 22), (41, 41)] {
+            assert!(at(x, y) > 0, "no glow at ({}, {})", x, y);
+        }
+        // Brighter than white inside, and dark well away from it
+        assert!(at(32, 32) > F16_ONE);
+        assert_eq!(at(0, 0), 0);
+        assert_eq!(at(63, 63), 0);
+    }
+}
@@ -3,13 +3,18 @@
 // Shows actual 3D graphics, not text-based rendering
 
 use std::iter;
+use std::sync::Arc;
 use wgpu::util::DeviceExt;
 use winit::{
     event::*,
     event_loop::{ControlFlow, EventLoop},
+    keyboard::{KeyCode, PhysicalKey},
     window::{Window, WindowBuilder},
 };
-use cgmath::prelude::*;
+
+mod bloom;
+mod post_processing;
+use post_processing::{PostProcessingPipeline, SCENE_FORMAT};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -101,10 +106,6 @@ struct Camera {
     eye: cgmath::Point3<f32>,
     target: cgmath::Point3<f32>,
     up: cgmath::Vector3<f32>,
-    aspect: f32,
-    fovy: f32,
-    znear: f32,
-    zfar: f32,
 }
 
 impl Camera {
@@ -145,8 +146,62 @@ impl Projection {
     }
 }
 
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Creates a depth texture matching the given surface dimensions, along with
+/// the view used as the render pass depth attachment.
+fn create_depth_texture(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        label: Some("depth_texture"),
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+/// Where frames end up: the window's surface, or a texture when rendering
+/// without a window
+enum RenderTarget {
+    Surface(wgpu::Surface<'static>),
+    Texture(wgpu::Texture),
+}
+
+impl RenderTarget {
+    /// A texture the size and format of `config`
+    fn texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> Self {
+        RenderTarget::Texture(device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: config.usage,
+            label: Some("Offscreen Target"),
+            view_formats: &[],
+        }))
+    }
+}
+
 struct State {
-    surface: wgpu::Surface,
+    target: RenderTarget,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
@@ -160,21 +215,26 @@ struct State {
     uniforms: Uniforms,
     uniform_buffer: wgpu::Buffer,
     uniform_bind_group: wgpu::BindGroup,
+    // Kept alive alongside its view; recreated only when the surface resizes
+    #[allow(dead_code)]
+    depth_texture: wgpu::Texture,
+    depth_view: wgpu::TextureView,
+    post_processing: PostProcessingPipeline,
     start_time: std::time::Instant,
 }
 
 impl State {
-    async fn new(window: &Window) -> State {
+    async fn new(window: Arc<Window>) -> State {
         let size = window.inner_size();
 
         // The instance is a handle to our GPU
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
-            dx12_shader_compiler: Default::default(),
+            ..Default::default()
         });
         
-        // Surface needs to live as long as the window that created it
-        let surface = unsafe { instance.create_surface(&window) }.unwrap();
+        // The surface shares the window, so it can't outlive it
+        let surface = instance.create_surface(window).unwrap();
 
         let adapter = instance.request_adapter(
             &wgpu::RequestAdapterOptions {
@@ -186,8 +246,8 @@ impl State {
 
         let (device, queue) = adapter.request_device(
             &wgpu::DeviceDescriptor {
-                features: wgpu::Features::empty(),
-                limits: wgpu::Limits::default(),
+                required_features: wgpu::Features::empty(),
+                required_limits: wgpu::Limits::default(),
                 label: None,
             },
             None, // Trace path
@@ -196,7 +256,7 @@ impl State {
         let surface_caps = surface.get_capabilities(&adapter);
         let surface_format = surface_caps.formats.iter()
             .copied()
-            .find(|f| f.describe().srgb)
+            .find(|f| f.is_srgb())
             .unwrap_or(surface_caps.formats[0]);
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -206,9 +266,53 @@ impl State {
             present_mode: surface_caps.present_modes[0],
             alpha_mode: surface_caps.alpha_modes[0],
             view_formats: vec![],
+            desired_maximum_frame_latency: 2,
         };
         surface.configure(&device, &config);
 
+        Self::with_target(RenderTarget::Surface(surface), device, queue, config)
+    }
+
+    /// Renders `width` × `height` frames into a texture instead of a window.
+    /// None when there's no adapter; `WGPU_BACKEND` and `WGPU_ADAPTER_NAME`
+    /// pick which one is used.
+    #[cfg(test)]
+    async fn headless(width: u32, height: u32) -> Option<State> {
+        let instance = wgpu::Instance::default();
+        let adapter = wgpu::util::initialize_adapter_from_env_or_default(&instance, None).await?;
+        let (device, queue) = adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                required_features: wgpu::Features::empty(),
+                required_limits: wgpu::Limits::downlevel_defaults(),
+                label: None,
+            },
+            None,
+        ).await.unwrap();
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        let target = RenderTarget::texture(&device, &config);
+        Some(Self::with_target(target, device, queue, config))
+    }
+
+    fn with_target(
+        target: RenderTarget,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        config: wgpu::SurfaceConfiguration,
+    ) -> State {
+        let size = winit::dpi::PhysicalSize::new(config.width, config.height);
+        let (depth_texture, depth_view) = create_depth_texture(&device, config.width, config.height);
+        let post_processing = PostProcessingPipeline::new(&device, config.format, config.width, config.height);
+
         // Create shader
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Robin 3D Shader"),
@@ -220,10 +324,6 @@ impl State {
             eye: (0.0, 5.0, 10.0).into(),
             target: (0.0, 0.0, 0.0).into(),
             up: cgmath::Vector3::unit_y(),
-            aspect: config.width as f32 / config.height as f32,
-            fovy: 45.0,
-            znear: 0.1,
-            zfar: 100.0,
         };
         
         let projection = Projection::new(config.width, config.height, cgmath::Deg(45.0), 0.1, 100.0);
@@ -279,12 +379,14 @@ impl State {
                 module: &shader,
                 entry_point: "vs_main",
                 buffers: &[Vertex::desc()],
+                compilation_options: Default::default(),
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
                 entry_point: "fs_main",
+                compilation_options: Default::default(),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
+                    format: SCENE_FORMAT,
                     blend: Some(wgpu::BlendState::REPLACE),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -299,7 +401,7 @@ impl State {
                 conservative: false,
             },
             depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
+                format: DEPTH_FORMAT,
                 depth_write_enabled: true,
                 depth_compare: wgpu::CompareFunction::Less,
                 stencil: wgpu::StencilState::default(),
@@ -330,7 +432,7 @@ impl State {
         let num_indices = INDICES.len() as u32;
 
         Self {
-            surface,
+            target,
             device,
             queue,
             config,
@@ -344,6 +446,9 @@ impl State {
             uniforms,
             uniform_buffer,
             uniform_bind_group,
+            depth_texture,
+            depth_view,
+            post_processing,
             start_time: std::time::Instant::now(),
         }
     }
@@ -353,13 +458,50 @@ impl State {
             self.size = new_size;
             self.config.width = new_size.width;
             self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
+            match &self.target {
+                RenderTarget::Surface(surface) => surface.configure(&self.device, &self.config),
+                RenderTarget::Texture(_) => self.target = RenderTarget::texture(&self.device, &self.config),
+            }
             self.projection.resize(new_size.width, new_size.height);
+
+            let (depth_texture, depth_view) =
+                create_depth_texture(&self.device, new_size.width, new_size.height);
+            self.depth_texture = depth_texture;
+            self.depth_view = depth_view;
+            self.post_processing.resize(&self.device, new_size.width, new_size.height);
         }
     }
 
     fn input(&mut self, event: &WindowEvent) -> bool {
-        false
+        match event {
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::KeyF),
+                        ..
+                    },
+                ..
+            } => {
+                let enabled = self.post_processing.toggle_fxaa();
+                println!("FXAA {}", if enabled { "enabled" } else { "disabled" });
+                true
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::KeyB),
+                        ..
+                    },
+                ..
+            } => {
+                let enabled = self.post_processing.toggle_bloom();
+                println!("Bloom {}", if enabled { "enabled" } else { "disabled" });
+                true
+            }
+            _ => false,
+        }
     }
 
     fn update(&mut self) {
@@ -380,25 +522,14 @@ impl State {
     }
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
-        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        // Create depth texture
-        let depth_texture = self.device.create_texture(&wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width: self.config.width,
-                height: self.config.height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth32Float,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            label: Some("depth_texture"),
-            view_formats: &[],
-        });
-        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let (output, view) = match &self.target {
+            RenderTarget::Surface(surface) => {
+                let output = surface.get_current_texture()?;
+                let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+                (Some(output), view)
+            }
+            RenderTarget::Texture(texture) => (None, texture.create_view(&wgpu::TextureViewDescriptor::default())),
+        };
 
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
@@ -408,7 +539,7 @@ impl State {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: self.post_processing.scene_view(),
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color {
@@ -417,17 +548,19 @@ impl State {
                             b: 0.3,
                             a: 1.0,
                         }),
-                        store: true,
+                        store: wgpu::StoreOp::Store,
                     },
                 })],
                 depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &depth_view,
+                    view: &self.depth_view,
                     depth_ops: Some(wgpu::Operations {
                         load: wgpu::LoadOp::Clear(1.0),
-                        store: true,
+                        store: wgpu::StoreOp::Store,
                     }),
                     stencil_ops: None,
                 }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
             });
 
             render_pass.set_pipeline(&self.render_pipeline);
@@ -437,8 +570,13 @@ impl State {
             render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
         }
 
+        self.post_processing.apply_bloom(&self.queue, &mut encoder);
+        self.post_processing.resolve(&mut encoder, &view);
+
         self.queue.submit(iter::once(encoder.finish()));
-        output.present();
+        if let Some(output) = output {
+            output.present();
+        }
 
         Ok(())
     }
@@ -447,14 +585,17 @@ impl State {
 pub async fn run() {
     env_logger::init();
 
-    let event_loop = EventLoop::new();
-    let window = WindowBuilder::new()
-        .with_title("🚀 Robin Engine 2.0 - 3D Graphics Showcase")
-        .with_inner_size(winit::dpi::PhysicalSize::new(1024, 768))
-        .build(&event_loop)
-        .unwrap();
-
-    let mut state = State::new(&window).await;
+    let event_loop = EventLoop::new().unwrap();
+    // Shared with the surface, which must not outlive it
+    let window = Arc::new(
+        WindowBuilder::new()
+            .with_title("🚀 Robin Engine 2.0 - 3D Graphics Showcase")
+            .with_inner_size(winit::dpi::PhysicalSize::new(1024, 768))
+            .build(&event_loop)
+            .unwrap(),
+    );
+
+    let mut state = State::new(window.clone()).await;
     
     println!("🌟 Robin Engine 2.0 - 3D Graphics Demo Started!");
     println!("✨ Features showcased:");
@@ -464,58 +605,89 @@ pub async fn run() {
     println!("  💡 Proper 3D projection and depth testing");
     println!("  🖥️ Cross-platform windowing with winit");
     println!("  🔄 60 FPS real-time rendering");
-    println!("");
+    println!("  🔍 FXAA anti-aliasing post-process (press F to toggle)");
+    println!("  ✨ Bloom around bright surfaces (press B to toggle)");
+    println!();
     println!("👀 Watch the rotating 3D cube!");
     println!("❌ Close the window to exit");
 
-    event_loop.run(move |event, _, control_flow| {
+    event_loop.set_control_flow(ControlFlow::Poll);
+    event_loop.run(move |event, target| {
         match event {
             Event::WindowEvent {
                 ref event,
                 window_id,
-            } if window_id == window.id() => {
-                if !state.input(event) {
-                    match event {
-                        WindowEvent::CloseRequested
-                        | WindowEvent::KeyboardInput {
-                            input:
-                                KeyboardInput {
-                                    state: ElementState::Pressed,
-                                    virtual_keycode: Some(VirtualKeyCode::Escape),
-                                    ..
-                                },
-                            ..
-                        } => {
-                            println!("🎉 Thank you for experiencing Robin Engine 2.0!");
-                            *control_flow = ControlFlow::Exit
-                        }
-                        WindowEvent::Resized(physical_size) => {
-                            state.resize(*physical_size);
-                        }
-                        WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                            state.resize(**new_inner_size);
+            } if window_id == window.id() && !state.input(event) => {
+                match event {
+                    WindowEvent::CloseRequested
+                    | WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                state: ElementState::Pressed,
+                                physical_key: PhysicalKey::Code(KeyCode::Escape),
+                                ..
+                            },
+                        ..
+                    } => {
+                        println!("🎉 Thank you for experiencing Robin Engine 2.0!");
+                        target.exit();
+                    }
+                    // Also sent after the scale factor changes
+                    WindowEvent::Resized(physical_size) => {
+                        state.resize(*physical_size);
+                    }
+                    WindowEvent::RedrawRequested => {
+                        state.update();
+                        match state.render() {
+                            Ok(_) => {}
+                            Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
+                            Err(wgpu::SurfaceError::OutOfMemory) => target.exit(),
+                            Err(e) => eprintln!("{:?}", e),
                         }
-                        _ => {}
                     }
+                    _ => {}
                 }
             }
-            Event::RedrawRequested(window_id) if window_id == window.id() => {
-                state.update();
-                match state.render() {
-                    Ok(_) => {}
-                    Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
-                    Err(wgpu::SurfaceError::OutOfMemory) => *control_flow = ControlFlow::Exit,
-                    Err(e) => eprintln!("{:?}", e),
-                }
-            }
-            Event::MainEventsCleared => {
+            Event::AboutToWait => {
                 window.request_redraw();
             }
             _ => {}
         }
-    });
+    }).unwrap();
 }
 
 fn main() {
     pollster::block_on(run());
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_reuses_the_depth_texture_across_frames() {
+        let Some(mut state) = pollster::block_on(State::headless(64, 48)) else {
+            eprintln!("No GPU adapter available, skipping headless render test");
+            return;
+        };
+        let depth_texture = state.depth_texture.global_id();
+
+        state.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        for _ in 0..5 {
+            state.update();
+            state.render().unwrap();
+        }
+        assert_eq!(state.depth_texture.global_id(), depth_texture);
+
+        // Resizing replaces it with one matching the new size
+        state.resize(winit::dpi::PhysicalSize::new(128, 96));
+        assert_ne!(state.depth_texture.global_id(), depth_texture);
+        assert_eq!((state.depth_texture.width(), state.depth_texture.height()), (128, 96));
+        for _ in 0..2 {
+            state.update();
+            state.render().unwrap();
+        }
+        state.device.poll(wgpu::Maintain::Wait);
+        assert!(pollster::block_on(state.device.pop_error_scope()).is_none());
+    }
+}
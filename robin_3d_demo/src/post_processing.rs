@@ -0,0 +1,486 @@
+// Robin Engine 2.0 - Post-Processing
+// The scene is rendered to an offscreen HDR texture, optionally bloomed,
+// then resolved to the swapchain through a fullscreen FXAA 3.11 pass (or a
+// plain copy when off).
+
+use crate::bloom::Bloom;
+
+/// Format of the scene texture; floating point so bright surfaces can go
+/// past 1.0 and bloom
+pub const SCENE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+const FXAA_SHADER: &str = r#"
+@group(0) @binding(0)
+var scene: texture_2d<f32>;
+@group(0) @binding(1)
+var scene_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// One triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+    return out;
+}
+
+@fragment
+fn fs_copy(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSampleLevel(scene, scene_sampler, in.uv, 0.0);
+}
+
+const EDGE_THRESHOLD_MIN: f32 = 0.0312;
+const EDGE_THRESHOLD_MAX: f32 = 0.125;
+const SUBPIXEL_QUALITY: f32 = 0.75;
+const SEARCH_STEPS: i32 = 12;
+
+// Distance (in texels) the edge search advances on each step
+fn search_step(i: i32) -> f32 {
+    if i < 5 {
+        return 1.0;
+    }
+    if i == 5 {
+        return 1.5;
+    }
+    if i < 10 {
+        return 2.0;
+    }
+    if i == 10 {
+        return 4.0;
+    }
+    return 8.0;
+}
+
+// The scene texture holds linear colour; sqrt approximates the
+// perceptual luma FXAA is tuned for
+fn luma(rgb: vec3<f32>) -> f32 {
+    return sqrt(dot(rgb, vec3<f32>(0.299, 0.587, 0.114)));
+}
+
+fn luma_at(uv: vec2<f32>) -> f32 {
+    return luma(textureSampleLevel(scene, scene_sampler, uv, 0.0).rgb);
+}
+
+@fragment
+fn fs_fxaa(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(scene));
+    let color_center = textureSampleLevel(scene, scene_sampler, in.uv, 0.0);
+
+    // Local contrast; skip pixels that aren't on an edge
+    let luma_center = luma(color_center.rgb);
+    let luma_down = luma_at(in.uv + vec2<f32>(0.0, texel.y));
+    let luma_up = luma_at(in.uv - vec2<f32>(0.0, texel.y));
+    let luma_left = luma_at(in.uv - vec2<f32>(texel.x, 0.0));
+    let luma_right = luma_at(in.uv + vec2<f32>(texel.x, 0.0));
+
+    let luma_min = min(luma_center, min(min(luma_down, luma_up), min(luma_left, luma_right)));
+    let luma_max = max(luma_center, max(max(luma_down, luma_up), max(luma_left, luma_right)));
+    let luma_range = luma_max - luma_min;
+    if luma_range < max(EDGE_THRESHOLD_MIN, luma_max * EDGE_THRESHOLD_MAX) {
+        return color_center;
+    }
+
+    let luma_down_left = luma_at(in.uv + vec2<f32>(-texel.x, texel.y));
+    let luma_up_right = luma_at(in.uv + vec2<f32>(texel.x, -texel.y));
+    let luma_up_left = luma_at(in.uv - texel);
+    let luma_down_right = luma_at(in.uv + texel);
+
+    let luma_down_up = luma_down + luma_up;
+    let luma_left_right = luma_left + luma_right;
+    let luma_left_corners = luma_down_left + luma_up_left;
+    let luma_down_corners = luma_down_left + luma_down_right;
+    let luma_right_corners = luma_down_right + luma_up_right;
+    let luma_up_corners = luma_up_right + luma_up_left;
+
+    // Edge orientation from the 3x3 neighbourhood
+    let edge_horizontal = abs(-2.0 * luma_left + luma_left_corners)
+        + abs(-2.0 * luma_center + luma_down_up) * 2.0
+        + abs(-2.0 * luma_right + luma_right_corners);
+    let edge_vertical = abs(-2.0 * luma_up + luma_up_corners)
+        + abs(-2.0 * luma_center + luma_left_right) * 2.0
+        + abs(-2.0 * luma_down + luma_down_corners);
+    let is_horizontal = edge_horizontal >= edge_vertical;
+
+    // Which side of the pixel the edge lies on
+    let luma1 = select(luma_left, luma_up, is_horizontal);
+    let luma2 = select(luma_right, luma_down, is_horizontal);
+    let gradient1 = luma1 - luma_center;
+    let gradient2 = luma2 - luma_center;
+    let is_1_steepest = abs(gradient1) >= abs(gradient2);
+    let gradient_scaled = 0.25 * max(abs(gradient1), abs(gradient2));
+
+    var step_length = select(texel.x, texel.y, is_horizontal);
+    var luma_local_average = 0.5 * (luma2 + luma_center);
+    if is_1_steepest {
+        step_length = -step_length;
+        luma_local_average = 0.5 * (luma1 + luma_center);
+    }
+
+    // Walk along the edge in both directions until its end is found
+    var current_uv = in.uv;
+    if is_horizontal {
+        current_uv.y += step_length * 0.5;
+    } else {
+        current_uv.x += step_length * 0.5;
+    }
+
+    let offset = select(vec2<f32>(0.0, texel.y), vec2<f32>(texel.x, 0.0), is_horizontal);
+    var uv1 = current_uv - offset;
+    var uv2 = current_uv + offset;
+    var luma_end1 = luma_at(uv1) - luma_local_average;
+    var luma_end2 = luma_at(uv2) - luma_local_average;
+    var reached1 = abs(luma_end1) >= gradient_scaled;
+    var reached2 = abs(luma_end2) >= gradient_scaled;
+    if !reached1 {
+        uv1 -= offset;
+    }
+    if !reached2 {
+        uv2 += offset;
+    }
+
+    if !(reached1 && reached2) {
+        for (var i = 2; i < SEARCH_STEPS; i++) {
+            if !reached1 {
+                luma_end1 = luma_at(uv1) - luma_local_average;
+            }
+            if !reached2 {
+                luma_end2 = luma_at(uv2) - luma_local_average;
+            }
+            reached1 = abs(luma_end1) >= gradient_scaled;
+            reached2 = abs(luma_end2) >= gradient_scaled;
+            if !reached1 {
+                uv1 -= offset * search_step(i);
+            }
+            if !reached2 {
+                uv2 += offset * search_step(i);
+            }
+            if reached1 && reached2 {
+                break;
+            }
+        }
+    }
+
+    let distance1 = select(in.uv.y - uv1.y, in.uv.x - uv1.x, is_horizontal);
+    let distance2 = select(uv2.y - in.uv.y, uv2.x - in.uv.x, is_horizontal);
+    let is_direction1 = distance1 < distance2;
+    let distance_final = min(distance1, distance2);
+    let edge_thickness = distance1 + distance2;
+    let pixel_offset = -distance_final / edge_thickness + 0.5;
+
+    // Only blend if the luma at the nearer edge end varies the same way as the centre
+    let is_luma_center_smaller = luma_center < luma_local_average;
+    let end_is_smaller = select(luma_end2 < 0.0, luma_end1 < 0.0, is_direction1);
+    var final_offset = select(0.0, pixel_offset, end_is_smaller != is_luma_center_smaller);
+
+    // Sub-pixel aliasing from the full neighbourhood average
+    let luma_average = (1.0 / 12.0) * (2.0 * (luma_down_up + luma_left_right) + luma_left_corners + luma_right_corners);
+    let sub_pixel_offset1 = clamp(abs(luma_average - luma_center) / luma_range, 0.0, 1.0);
+    let sub_pixel_offset2 = (-2.0 * sub_pixel_offset1 + 3.0) * sub_pixel_offset1 * sub_pixel_offset1;
+    final_offset = max(final_offset, sub_pixel_offset2 * sub_pixel_offset2 * SUBPIXEL_QUALITY);
+
+    var final_uv = in.uv;
+    if is_horizontal {
+        final_uv.y += final_offset * step_length;
+    } else {
+        final_uv.x += final_offset * step_length;
+    }
+    return textureSampleLevel(scene, scene_sampler, final_uv, 0.0);
+}
+"#;
+
+/// Owns the offscreen scene target and the pass that resolves it to the
+/// swapchain. Render the scene into `scene_view()`, then call `apply_bloom`
+/// and `resolve`.
+pub struct PostProcessingPipeline {
+    /// When false the scene is copied to the output unchanged
+    pub fxaa_enabled: bool,
+    pub bloom_enabled: bool,
+    /// Threshold and intensity of the glow around bright surfaces
+    pub bloom: Bloom,
+    // Kept alive alongside its view; recreated only when the surface resizes
+    #[allow(dead_code)]
+    scene_texture: wgpu::Texture,
+    scene_view: wgpu::TextureView,
+    sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    fxaa_pipeline: wgpu::RenderPipeline,
+    copy_pipeline: wgpu::RenderPipeline,
+}
+
+impl PostProcessingPipeline {
+    /// `format` is the output target's; the scene is rendered in `SCENE_FORMAT`
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
+        let (scene_texture, scene_view) = create_scene_texture(device, SCENE_FORMAT, width, height);
+        let bloom = Bloom::new(device, &scene_view, SCENE_FORMAT, width, height);
+
+        // Linear filtering lets FXAA sample between texels
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("post_processing_sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("post_processing_bind_group_layout"),
+        });
+        let bind_group = create_bind_group(device, &bind_group_layout, &scene_view, &sampler);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("FXAA Shader"),
+            source: wgpu::ShaderSource::Wgsl(FXAA_SHADER.into()),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post-Processing Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let fxaa_pipeline = create_fullscreen_pipeline(device, &layout, &shader, "fs_fxaa", format);
+        let copy_pipeline = create_fullscreen_pipeline(device, &layout, &shader, "fs_copy", format);
+
+        Self {
+            fxaa_enabled: true,
+            bloom_enabled: true,
+            bloom,
+            scene_texture,
+            scene_view,
+            sampler,
+            bind_group_layout,
+            bind_group,
+            fxaa_pipeline,
+            copy_pipeline,
+        }
+    }
+
+    /// Target for the scene pass
+    pub fn scene_view(&self) -> &wgpu::TextureView {
+        &self.scene_view
+    }
+
+    pub fn toggle_fxaa(&mut self) -> bool {
+        self.fxaa_enabled = !self.fxaa_enabled;
+        self.fxaa_enabled
+    }
+
+    pub fn toggle_bloom(&mut self) -> bool {
+        self.bloom_enabled = !self.bloom_enabled;
+        self.bloom_enabled
+    }
+
+    /// Recreates the scene texture and bloom targets to match the new
+    /// surface size
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        let (scene_texture, scene_view) = create_scene_texture(device, SCENE_FORMAT, width, height);
+        self.bind_group = create_bind_group(device, &self.bind_group_layout, &scene_view, &self.sampler);
+        self.bloom.resize(device, &scene_view, width, height);
+        self.scene_texture = scene_texture;
+        self.scene_view = scene_view;
+    }
+
+    /// Adds the glow around bright surfaces to the scene texture, if bloom
+    /// is enabled
+    pub fn apply_bloom(&self, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder) {
+        if self.bloom_enabled {
+            self.bloom.apply(queue, encoder, &self.scene_view);
+        }
+    }
+
+    /// Draws the scene texture into `output`, anti-aliased if FXAA is enabled
+    pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Post-Processing Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        let pipeline = if self.fxaa_enabled {
+            &self.fxaa_pipeline
+        } else {
+            &self.copy_pipeline
+        };
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+fn create_scene_texture(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        label: Some("scene_texture"),
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    scene_view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(scene_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+        label: Some("post_processing_bind_group"),
+    })
+}
+
+fn create_fullscreen_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    fragment_entry: &str,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(fragment_entry),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: fragment_entry,
+            compilation_options: Default::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fxaa_and_copy_resolve_without_validation_errors() {
+        let instance = wgpu::Instance::default();
+        let adapter = match pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(
+            &instance, None,
+        )) {
+            Some(adapter) => adapter,
+            None => {
+                eprintln!("No GPU adapter available, skipping headless render test");
+                return;
+            }
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                required_features: wgpu::Features::empty(),
+                required_limits: wgpu::Limits::downlevel_defaults(),
+            },
+            None,
+        ))
+        .unwrap();
+
+        let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let mut post_processing = PostProcessingPipeline::new(&device, format, 64, 48);
+        // Resizing swaps the scene texture the bind group points at
+        post_processing.resize(&device, 80, 60);
+        let (_output, output_view) = create_scene_texture(&device, format, 80, 60);
+
+        for _ in 0..2 {
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Test Encoder"),
+            });
+            post_processing.apply_bloom(&queue, &mut encoder);
+            post_processing.resolve(&mut encoder, &output_view);
+            queue.submit(std::iter::once(encoder.finish()));
+            device.poll(wgpu::Maintain::Wait);
+            post_processing.toggle_fxaa();
+            post_processing.toggle_bloom();
+        }
+        let error = pollster::block_on(device.pop_error_scope());
+        assert!(error.is_none(), "validation error in post-processing pass: {:?}", error);
+        assert!(post_processing.fxaa_enabled);
+        assert!(post_processing.bloom_enabled);
+    }
+}
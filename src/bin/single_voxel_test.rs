@@ -394,6 +394,7 @@ impl State {
         );
     }
 
+    #[tracing::instrument(skip_all, fields(frame = self.frame_count + 1))]
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         self.frame_count += 1;
         self.update();
@@ -439,7 +440,7 @@ impl State {
         if self.frame_count % 60 == 0 {
             let elapsed = self.start_time.elapsed().as_secs_f32();
             let fps = self.frame_count as f32 / elapsed;
-            println!("🎬 Frame {} | FPS: {:.2}", self.frame_count, fps);
+            tracing::info!(fps, "🎬 Frame {}", self.frame_count);
         }
 
         Ok(())
@@ -450,7 +451,9 @@ fn main() {
     println!("🚀 Robin Engine - Single Voxel Test");
     println!("====================================");
 
-    env_logger::init();
+    if let Err(e) = robin::engine::telemetry::init_tracing(None) {
+        eprintln!("⚠️  Tracing disabled: {}", e);
+    }
 
     let event_loop = EventLoop::new().expect("Failed to create event loop");
 
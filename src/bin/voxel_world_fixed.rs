@@ -77,8 +77,125 @@ impl VoxelType {
             VoxelType::Grass => [0.2, 0.6, 0.2],
         }
     }
+
+    /// Colour of one face with static lighting baked in, for worlds without
+    /// dynamic lights: lit from above, with the sides progressively darker
+    fn color_for_face(&self, face: FaceDirection) -> [f32; 3] {
+        let shade = face.shade();
+        self.get_color().map(|channel| channel * shade)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FaceDirection {
+    Top,
+    Bottom,
+    Front,
+    Back,
+    Right,
+    Left,
+}
+
+impl FaceDirection {
+    fn normal(self) -> [f32; 3] {
+        match self {
+            FaceDirection::Top => [0.0, 1.0, 0.0],
+            FaceDirection::Bottom => [0.0, -1.0, 0.0],
+            FaceDirection::Front => [0.0, 0.0, 1.0],
+            FaceDirection::Back => [0.0, 0.0, -1.0],
+            FaceDirection::Right => [1.0, 0.0, 0.0],
+            FaceDirection::Left => [-1.0, 0.0, 0.0],
+        }
+    }
+
+    /// Brightness multiplier standing in for a light overhead
+    fn shade(self) -> f32 {
+        match self {
+            FaceDirection::Top => 1.0,
+            FaceDirection::Front | FaceDirection::Back => 0.8,
+            FaceDirection::Left | FaceDirection::Right => 0.7,
+            FaceDirection::Bottom => 0.5,
+        }
+    }
+}
+
+/// How faces are lit
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RenderMode {
+    /// Per-fragment Phong lighting from the light in the uniforms
+    Phong,
+    /// Per-face shading baked into vertex colours; the fragment shader just
+    /// passes the colour through
+    BakedLighting,
+}
+
+impl RenderMode {
+    fn toggled(self) -> Self {
+        match self {
+            RenderMode::Phong => RenderMode::BakedLighting,
+            RenderMode::BakedLighting => RenderMode::Phong,
+        }
+    }
+}
+
+const SHADER_SOURCE: &str = r#"
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    view_pos: vec4<f32>,
+    light_pos: vec4<f32>,
+    time: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+    @location(1) world_pos: vec3<f32>,
+    @location(2) normal: vec3<f32>,
 }
 
+@vertex
+fn vs_main(model: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = uniforms.view_proj * vec4<f32>(model.position, 1.0);
+    out.color = model.color;
+    out.world_pos = model.position;
+    out.normal = model.normal;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let light_dir = normalize(uniforms.light_pos.xyz - in.world_pos);
+    let view_dir = normalize(uniforms.view_pos.xyz - in.world_pos);
+
+    // Simple Phong shading
+    let ambient = 0.15;
+    let diffuse = max(dot(in.normal, light_dir), 0.0) * 0.7;
+    let specular_strength = 0.5;
+    let reflect_dir = reflect(-light_dir, in.normal);
+    let spec = pow(max(dot(view_dir, reflect_dir), 0.0), 32.0);
+    let specular = specular_strength * spec;
+
+    let final_color = in.color * (ambient + diffuse) + vec3<f32>(specular);
+    return vec4<f32>(final_color, 1.0);
+}
+
+// Lighting is already baked into the vertex colours
+@fragment
+fn fs_baked(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
+"#;
+
 const CHUNK_SIZE: usize = 16;
 
 struct VoxelChunk {
@@ -111,6 +228,13 @@ impl VoxelChunk {
         Self { position, voxels }
     }
 
+    fn empty(position: (i32, i32, i32)) -> Self {
+        Self {
+            position,
+            voxels: vec![VoxelType::Air; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE],
+        }
+    }
+
     fn index(x: usize, y: usize, z: usize) -> usize {
         x + y * CHUNK_SIZE + z * CHUNK_SIZE * CHUNK_SIZE
     }
@@ -142,7 +266,25 @@ impl VoxelWorld {
         Self { chunks }
     }
 
-    fn generate_mesh(&self) -> (Vec<Vertex>, Vec<u32>) {
+    /// A cube of `chunks_per_axis` chunks on each side, with terrain in the
+    /// bottom layer of chunks and air above it
+    fn cube(chunks_per_axis: i32) -> Self {
+        let mut chunks = HashMap::new();
+        for cx in 0..chunks_per_axis {
+            for cy in 0..chunks_per_axis {
+                for cz in 0..chunks_per_axis {
+                    let position = (cx, cy, cz);
+                    let chunk = if cy == 0 { VoxelChunk::new(position) } else { VoxelChunk::empty(position) };
+                    chunks.insert(position, chunk);
+                }
+            }
+        }
+        Self { chunks }
+    }
+
+    /// Builds the world's mesh. With `RenderMode::BakedLighting` each face's
+    /// shading is baked into its vertex colours.
+    fn generate_mesh(&self, render_mode: RenderMode) -> (Vec<Vertex>, Vec<u32>) {
         let mut vertices = Vec::new();
         let mut indices = Vec::new();
 
@@ -161,7 +303,6 @@ impl VoxelWorld {
                             continue;
                         }
 
-                        let color = voxel.get_color();
                         let world_x = chunk_offset.0 + x as i32;
                         let world_y = chunk_offset.1 + y as i32;
                         let world_z = chunk_offset.2 + z as i32;
@@ -170,7 +311,7 @@ impl VoxelWorld {
                         let faces = [
                             // Top (Y+)
                             (y == CHUNK_SIZE - 1 || chunk.get_voxel(x, y + 1, z) == VoxelType::Air,
-                             [0.0, 1.0, 0.0],
+                             FaceDirection::Top,
                              [
                                  [world_x as f32 - 0.5, world_y as f32 + 0.5, world_z as f32 - 0.5],
                                  [world_x as f32 + 0.5, world_y as f32 + 0.5, world_z as f32 - 0.5],
@@ -179,7 +320,7 @@ impl VoxelWorld {
                              ]),
                             // Bottom (Y-)
                             (y == 0 || chunk.get_voxel(x, y - 1, z) == VoxelType::Air,
-                             [0.0, -1.0, 0.0],
+                             FaceDirection::Bottom,
                              [
                                  [world_x as f32 - 0.5, world_y as f32 - 0.5, world_z as f32 + 0.5],
                                  [world_x as f32 + 0.5, world_y as f32 - 0.5, world_z as f32 + 0.5],
@@ -188,7 +329,7 @@ impl VoxelWorld {
                              ]),
                             // Front (Z+)
                             (z == CHUNK_SIZE - 1 || chunk.get_voxel(x, y, z + 1) == VoxelType::Air,
-                             [0.0, 0.0, 1.0],
+                             FaceDirection::Front,
                              [
                                  [world_x as f32 - 0.5, world_y as f32 - 0.5, world_z as f32 + 0.5],
                                  [world_x as f32 + 0.5, world_y as f32 - 0.5, world_z as f32 + 0.5],
@@ -197,7 +338,7 @@ impl VoxelWorld {
                              ]),
                             // Back (Z-)
                             (z == 0 || chunk.get_voxel(x, y, z - 1) == VoxelType::Air,
-                             [0.0, 0.0, -1.0],
+                             FaceDirection::Back,
                              [
                                  [world_x as f32 + 0.5, world_y as f32 - 0.5, world_z as f32 - 0.5],
                                  [world_x as f32 - 0.5, world_y as f32 - 0.5, world_z as f32 - 0.5],
@@ -206,7 +347,7 @@ impl VoxelWorld {
                              ]),
                             // Right (X+)
                             (x == CHUNK_SIZE - 1 || chunk.get_voxel(x + 1, y, z) == VoxelType::Air,
-                             [1.0, 0.0, 0.0],
+                             FaceDirection::Right,
                              [
                                  [world_x as f32 + 0.5, world_y as f32 - 0.5, world_z as f32 + 0.5],
                                  [world_x as f32 + 0.5, world_y as f32 - 0.5, world_z as f32 - 0.5],
@@ -215,7 +356,7 @@ impl VoxelWorld {
                              ]),
                             // Left (X-)
                             (x == 0 || chunk.get_voxel(x - 1, y, z) == VoxelType::Air,
-                             [-1.0, 0.0, 0.0],
+                             FaceDirection::Left,
                              [
                                  [world_x as f32 - 0.5, world_y as f32 - 0.5, world_z as f32 - 0.5],
                                  [world_x as f32 - 0.5, world_y as f32 - 0.5, world_z as f32 + 0.5],
@@ -224,16 +365,20 @@ impl VoxelWorld {
                              ]),
                         ];
 
-                        for (visible, normal, positions) in faces {
+                        for (visible, face, positions) in faces {
                             if visible {
                                 let base_idx = vertices.len() as u32;
+                                let color = match render_mode {
+                                    RenderMode::Phong => voxel.get_color(),
+                                    RenderMode::BakedLighting => voxel.color_for_face(face),
+                                };
 
                                 // Add 4 vertices for the face
                                 for pos in positions {
                                     vertices.push(Vertex {
                                         position: pos,
                                         color,
-                                        normal,
+                                        normal: face.normal(),
                                     });
                                 }
 
@@ -311,13 +456,105 @@ impl Camera {
     }
 }
 
+/// The uniform buffer, holding `uniforms` to start with, and the bind group
+/// the shader reads it through
+fn create_uniform_binding(device: &wgpu::Device, uniforms: &Uniforms) -> (wgpu::Buffer, wgpu::BindGroupLayout, wgpu::BindGroup) {
+    let uniform_buffer = device.create_buffer_init(
+        &wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer"),
+            contents: bytemuck::cast_slice(std::slice::from_ref(uniforms)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        }
+    );
+
+    let uniform_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }
+        ],
+        label: Some("uniform_bind_group_layout"),
+    });
+
+    let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &uniform_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }
+        ],
+        label: Some("uniform_bind_group"),
+    });
+
+    (uniform_buffer, uniform_bind_group_layout, uniform_bind_group)
+}
+
+/// The world's pipeline, with the fragment shader `render_mode` needs
+fn create_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    render_mode: RenderMode,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Voxel Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[Vertex::desc()],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: match render_mode {
+                RenderMode::Phong => "fs_main",
+                RenderMode::BakedLighting => "fs_baked",
+            },
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
 struct State {
     surface: wgpu::Surface<'static>,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
     size: PhysicalSize<u32>,
-    render_pipeline: wgpu::RenderPipeline,
+    phong_pipeline: wgpu::RenderPipeline,
+    baked_pipeline: wgpu::RenderPipeline,
+    render_mode: RenderMode,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
     num_indices: u32,
@@ -388,14 +625,16 @@ impl State {
 
         // Create voxel world and mesh
         let voxel_world = VoxelWorld::new();
-        let (vertices, indices) = voxel_world.generate_mesh();
+        let render_mode = RenderMode::Phong;
+        let (vertices, indices) = voxel_world.generate_mesh(render_mode);
 
         // Create buffers
         let vertex_buffer = device.create_buffer_init(
             &wgpu::util::BufferInitDescriptor {
                 label: Some("Vertex Buffer"),
                 contents: bytemuck::cast_slice(&vertices),
-                usage: wgpu::BufferUsages::VERTEX,
+                // Rewritten when the render mode changes the baked colours
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
             }
         );
 
@@ -416,97 +655,11 @@ impl State {
             _padding: [0.0; 3],
         };
 
-        let uniform_buffer = device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Uniform Buffer"),
-                contents: bytemuck::cast_slice(&[uniforms]),
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            }
-        );
-
-        let uniform_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }
-            ],
-            label: Some("uniform_bind_group_layout"),
-        });
-
-        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &uniform_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: uniform_buffer.as_entire_binding(),
-                }
-            ],
-            label: Some("uniform_bind_group"),
-        });
-
-        // Shader
-        let shader_source = r#"
-struct Uniforms {
-    view_proj: mat4x4<f32>,
-    view_pos: vec4<f32>,
-    light_pos: vec4<f32>,
-    time: f32,
-}
-
-@group(0) @binding(0)
-var<uniform> uniforms: Uniforms;
-
-struct VertexInput {
-    @location(0) position: vec3<f32>,
-    @location(1) color: vec3<f32>,
-    @location(2) normal: vec3<f32>,
-}
-
-struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
-    @location(0) color: vec3<f32>,
-    @location(1) world_pos: vec3<f32>,
-    @location(2) normal: vec3<f32>,
-}
-
-@vertex
-fn vs_main(model: VertexInput) -> VertexOutput {
-    var out: VertexOutput;
-    out.clip_position = uniforms.view_proj * vec4<f32>(model.position, 1.0);
-    out.color = model.color;
-    out.world_pos = model.position;
-    out.normal = model.normal;
-    return out;
-}
-
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let light_dir = normalize(uniforms.light_pos.xyz - in.world_pos);
-    let view_dir = normalize(uniforms.view_pos.xyz - in.world_pos);
-
-    // Simple Phong shading
-    let ambient = 0.15;
-    let diffuse = max(dot(in.normal, light_dir), 0.0) * 0.7;
-    let specular_strength = 0.5;
-    let reflect_dir = reflect(-light_dir, in.normal);
-    let spec = pow(max(dot(view_dir, reflect_dir), 0.0), 32.0);
-    let specular = specular_strength * spec;
-
-    let final_color = in.color * (ambient + diffuse) + vec3<f32>(specular);
-    return vec4<f32>(final_color, 1.0);
-}
-"#;
+        let (uniform_buffer, uniform_bind_group_layout, uniform_bind_group) = create_uniform_binding(&device, &uniforms);
 
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Voxel Shader"),
-            source: wgpu::ShaderSource::Wgsl(shader_source.into()),
+            source: wgpu::ShaderSource::Wgsl(SHADER_SOURCE.into()),
         });
 
         // Create render pipeline
@@ -516,42 +669,9 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
             push_constant_ranges: &[],
         });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Voxel Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[Vertex::desc()],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-        });
+        let phong_pipeline = create_pipeline(&device, &render_pipeline_layout, &shader, config.format, RenderMode::Phong);
+        let baked_pipeline =
+            create_pipeline(&device, &render_pipeline_layout, &shader, config.format, RenderMode::BakedLighting);
 
         println!("✅ Voxel world initialized with {} indices", indices.len());
 
@@ -561,7 +681,9 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
             queue,
             config,
             size,
-            render_pipeline,
+            phong_pipeline,
+            baked_pipeline,
+            render_mode,
             vertex_buffer,
             index_buffer,
             num_indices: indices.len() as u32,
@@ -583,6 +705,15 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
         }
     }
 
+    /// Switches between Phong and baked lighting, rebuilding the vertex
+    /// colours for the new mode
+    fn toggle_render_mode(&mut self) {
+        self.render_mode = self.render_mode.toggled();
+        let (vertices, _) = self.voxel_world.generate_mesh(self.render_mode);
+        self.queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+        println!("💡 Render mode: {:?}", self.render_mode);
+    }
+
     fn update(&mut self) {
         let elapsed = self.start_time.elapsed().as_secs_f32();
 
@@ -641,7 +772,10 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
                 timestamp_writes: None,
             });
 
-            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_pipeline(match self.render_mode {
+                RenderMode::Phong => &self.phong_pipeline,
+                RenderMode::BakedLighting => &self.baked_pipeline,
+            });
             render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
             render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
             render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
@@ -655,12 +789,142 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     }
 }
 
+/// Frames timed per render mode by `--benchmark`
+const BENCHMARK_FRAMES: u32 = 500;
+/// Chunks along each side of the benchmark world, making it 32³ voxels
+const BENCHMARK_CHUNKS: i32 = 2;
+
+/// Renders a 32³ world offscreen in each render mode and reports the average
+/// frame time of each, waiting for the GPU to finish every frame
+async fn benchmark() {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    });
+    let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions::default()).await
+        .expect("No GPU adapter to benchmark on");
+    let (device, queue) = adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            required_features: wgpu::Features::empty(),
+            required_limits: wgpu::Limits::default(),
+            label: Some("Benchmark Device"),
+        },
+        None,
+    ).await.unwrap();
+    println!("🖥️  {}", adapter.get_info().name);
+
+    let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+    let target = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Benchmark Target"),
+        size: wgpu::Extent3d { width: 1280, height: 720, depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let extent = (BENCHMARK_CHUNKS * CHUNK_SIZE as i32) as f32;
+    let camera = Camera {
+        eye: Point3::new(extent * 1.25, extent, extent * 1.25),
+        target: Point3::new(extent / 2.0, 4.0, extent / 2.0),
+        up: Vector3::unit_y(),
+        aspect: 1280.0 / 720.0,
+        fovy: 60.0,
+        znear: 0.1,
+        zfar: 1000.0,
+    };
+    let uniforms = Uniforms {
+        view_proj: camera.build_view_projection_matrix().into(),
+        view_pos: [camera.eye.x, camera.eye.y, camera.eye.z, 1.0],
+        light_pos: [20.0, 30.0, 20.0, 1.0],
+        time: 0.0,
+        _padding: [0.0; 3],
+    };
+    let (_uniform_buffer, uniform_bind_group_layout, uniform_bind_group) = create_uniform_binding(&device, &uniforms);
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Voxel Shader"),
+        source: wgpu::ShaderSource::Wgsl(SHADER_SOURCE.into()),
+    });
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Render Pipeline Layout"),
+        bind_group_layouts: &[&uniform_bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    let world = VoxelWorld::cube(BENCHMARK_CHUNKS);
+    let mut frame_times = Vec::new();
+    for render_mode in [RenderMode::Phong, RenderMode::BakedLighting] {
+        let pipeline = create_pipeline(&device, &layout, &shader, format, render_mode);
+        let (vertices, indices) = world.generate_mesh(render_mode);
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let render_frame = || {
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Benchmark Encoder"),
+            });
+            {
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Benchmark Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    occlusion_query_set: None,
+                    timestamp_writes: None,
+                });
+                render_pass.set_pipeline(&pipeline);
+                render_pass.set_bind_group(0, &uniform_bind_group, &[]);
+                render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..indices.len() as u32, 0, 0..1);
+            }
+            queue.submit(std::iter::once(encoder.finish()));
+            device.poll(wgpu::Maintain::Wait);
+        };
+
+        // Let the driver settle before timing
+        for _ in 0..20 {
+            render_frame();
+        }
+        let start = Instant::now();
+        for _ in 0..BENCHMARK_FRAMES {
+            render_frame();
+        }
+        let frame_ms = start.elapsed().as_secs_f64() * 1000.0 / BENCHMARK_FRAMES as f64;
+        println!("   {:?}: {:.3} ms/frame", render_mode, frame_ms);
+        frame_times.push(frame_ms);
+    }
+    println!("⏱️  Baked lighting saves {:.1}% of frame time", (1.0 - frame_times[1] / frame_times[0]) * 100.0);
+}
+
 fn main() {
     println!("🚀 Robin Engine - Fixed Voxel World Demo");
     println!("=========================================");
 
     env_logger::init();
 
+    if std::env::args().any(|arg| arg == "--benchmark") {
+        pollster::block_on(benchmark());
+        return;
+    }
+
     let event_loop = EventLoop::new().expect("Failed to create event loop");
 
     let window = Arc::new(WindowBuilder::new()
@@ -677,6 +941,7 @@ fn main() {
     let mut state = pollster::block_on(State::new(Arc::clone(&window)));
 
     println!("\n🎮 Controls:");
+    println!("   B   - Toggle baked lighting");
     println!("   ESC - Exit");
     println!("\n🌍 Explore the voxel world!");
 
@@ -695,8 +960,10 @@ fn main() {
                     }
                     WindowEvent::KeyboardInput { event, .. } => {
                         if event.state == ElementState::Pressed {
-                            if let PhysicalKey::Code(KeyCode::Escape) = event.physical_key {
-                                elwt.exit();
+                            match event.physical_key {
+                                PhysicalKey::Code(KeyCode::Escape) => elwt.exit(),
+                                PhysicalKey::Code(KeyCode::KeyB) => state.toggle_render_mode(),
+                                _ => {}
                             }
                         }
                     }
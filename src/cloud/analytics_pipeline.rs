@@ -0,0 +1,721 @@
+// Robin Engine 2.0 - Global Analytics Pipeline
+// Partitioned time-series store of educational events with a small query DSL
+
+use crate::engine::error::RobinResult;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, SystemTime};
+
+/// One recorded learning event. `fields` carries event-specific values such
+/// as `engagement`, `age_group` or `intervention_type`.
+#[derive(Debug, Clone)]
+pub struct AnalyticsEvent {
+    pub timestamp: SystemTime,
+    pub student_id: String,
+    pub region: String,
+    pub activity_type: String,
+    pub fields: HashMap<String, Value>,
+}
+
+impl AnalyticsEvent {
+    pub fn new(student_id: &str, region: &str, activity_type: &str) -> Self {
+        Self {
+            timestamp: SystemTime::now(),
+            student_id: student_id.to_string(),
+            region: region.to_string(),
+            activity_type: activity_type.to_string(),
+            fields: HashMap::new(),
+        }
+    }
+
+    pub fn with_field(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.fields.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// Column value by name; the partition columns come first, then `fields`
+    pub fn field(&self, name: &str) -> Option<Value> {
+        match name {
+            "student_id" => Some(Value::from(self.student_id.as_str())),
+            "region" => Some(Value::from(self.region.as_str())),
+            "activity_type" => Some(Value::from(self.activity_type.as_str())),
+            _ => self.fields.get(name).cloned(),
+        }
+    }
+}
+
+/// Pipeline notifications surfaced through `CloudPlatformManager::update`
+#[derive(Debug, Clone)]
+pub enum PipelineEvent {
+    RecordsExpired { count: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+/// Aggregate computed per group. Numeric metrics skip events without a
+/// numeric value for the field; booleans count as 0 or 1.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricType {
+    Count,
+    Average(String),
+    Sum(String),
+    Min(String),
+    Max(String),
+    DistinctCount(String),
+}
+
+impl MetricType {
+    /// Column name of this metric in result rows
+    pub fn name(&self) -> String {
+        match self {
+            MetricType::Count => "count".to_string(),
+            MetricType::Average(field) => format!("avg_{}", field),
+            MetricType::Sum(field) => format!("sum_{}", field),
+            MetricType::Min(field) => format!("min_{}", field),
+            MetricType::Max(field) => format!("max_{}", field),
+            MetricType::DistinctCount(field) => format!("distinct_{}", field),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AnalyticsQuery {
+    pub filters: Vec<(String, FilterOp, Value)>,
+    pub group_by: Vec<String>,
+    pub metrics: Vec<MetricType>,
+    pub time_window: Option<Duration>,
+    pub time_range: Option<(SystemTime, SystemTime)>,
+}
+
+impl AnalyticsQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn filter(mut self, field: &str, op: FilterOp, value: impl Into<Value>) -> Self {
+        self.filters.push((field.to_string(), op, value.into()));
+        self
+    }
+
+    pub fn group_by(mut self, field: &str) -> Self {
+        self.group_by.push(field.to_string());
+        self
+    }
+
+    pub fn metric(mut self, metric: MetricType) -> Self {
+        self.metrics.push(metric);
+        self
+    }
+
+    /// Only events newer than `window` before now are aggregated
+    pub fn time_window(mut self, window: Duration) -> Self {
+        self.time_window = Some(window);
+        self
+    }
+
+    /// Only events from `start` up to but not including `end` are aggregated
+    pub fn between(mut self, start: SystemTime, end: SystemTime) -> Self {
+        self.time_range = Some((start, end));
+        self
+    }
+
+    /// Whether `event` passes the time bounds and every filter. `cutoff` is
+    /// the start of the time window, resolved once per query.
+    fn includes(&self, event: &AnalyticsEvent, cutoff: Option<SystemTime>) -> bool {
+        if cutoff.is_some_and(|cutoff| event.timestamp < cutoff) {
+            return false;
+        }
+        if self
+            .time_range
+            .is_some_and(|(start, end)| event.timestamp < start || event.timestamp >= end)
+        {
+            return false;
+        }
+        self.filters
+            .iter()
+            .all(|(field, op, value)| event.field(field).is_some_and(|actual| compare(&actual, *op, value)))
+    }
+
+    /// Mean engagement per region
+    pub fn engagement_by_region() -> Self {
+        Self::new()
+            .group_by("region")
+            .metric(MetricType::Average("engagement".to_string()))
+            .metric(MetricType::Count)
+    }
+
+    /// Mean skill gain per age group, from assessment events
+    pub fn skill_progression_by_age_group() -> Self {
+        Self::new()
+            .filter("activity_type", FilterOp::Eq, "assessment")
+            .group_by("age_group")
+            .metric(MetricType::Average("skill_delta".to_string()))
+            .metric(MetricType::DistinctCount("student_id".to_string()))
+    }
+
+    /// Share of interventions marked effective, per intervention type
+    pub fn intervention_effectiveness_by_type() -> Self {
+        Self::new()
+            .filter("activity_type", FilterOp::Eq, "intervention")
+            .group_by("intervention_type")
+            .metric(MetricType::Average("effective".to_string()))
+            .metric(MetricType::Count)
+    }
+}
+
+/// One row per group, holding the group-by values and each metric by `MetricType::name`
+#[derive(Debug, Clone, Default)]
+pub struct AnalyticsResult {
+    pub rows: Vec<HashMap<String, Value>>,
+}
+
+impl AnalyticsResult {
+    /// Row whose group-by `field` equals `value`
+    pub fn row(&self, field: &str, value: impl Into<Value>) -> Option<&HashMap<String, Value>> {
+        let value = value.into();
+        self.rows.iter().find(|row| row.get(field) == Some(&value))
+    }
+}
+
+/// Skill an assessment event measured, from its `skill` field
+pub type SkillDomain = String;
+/// Kind of support an intervention event gave, from its `intervention_type` field
+pub type InterventionType = String;
+/// Calendar day in UTC
+pub type Date = chrono::NaiveDate;
+
+/// Number of skills `ClassDashboard` lists at each end of the ranking
+pub const DASHBOARD_SKILL_COUNT: usize = 3;
+/// Dropout risk above which a student is flagged to their teacher
+pub const AT_RISK_THRESHOLD: f32 = 0.6;
+
+/// One student's activity over a dashboard's time range
+#[derive(Debug, Clone, PartialEq)]
+pub struct StudentSummary {
+    pub student_id: String,
+    pub activity_count: usize,
+    /// Mean `engagement` of the student's events in range, if any recorded one
+    pub average_engagement: Option<f32>,
+    /// Latest event at or before the end of the range, in range or not
+    pub last_active: Option<SystemTime>,
+    pub dropout_risk: f32,
+}
+
+/// What a teacher sees for one class. Class membership comes from the
+/// `teacher_id` and `class_id` fields of any retained event, so students
+/// with nothing in range are still listed.
+#[derive(Debug, Clone, Default)]
+pub struct ClassDashboard {
+    pub student_summaries: Vec<StudentSummary>,
+    /// Skills with the highest mean `skill_delta`, best first
+    pub top_skills: Vec<(SkillDomain, f32)>,
+    /// Skills with the lowest mean `skill_delta`, worst first
+    pub bottom_skills: Vec<(SkillDomain, f32)>,
+    /// Share of interventions marked effective, per intervention type
+    pub intervention_effectiveness: HashMap<InterventionType, f32>,
+    /// Mean engagement per day that had any, in date order
+    pub engagement_by_day: Vec<(Date, f32)>,
+    /// Students whose dropout risk exceeds `AT_RISK_THRESHOLD`, by id
+    pub at_risk_students: Vec<String>,
+}
+
+/// Estimates how likely a student is to stop playing from how long they've
+/// been inactive and how engaged they were when they last played
+#[derive(Debug, Clone)]
+pub struct PredictiveModelingSystem {
+    /// Idle time at which inactivity alone counts as a 50% risk
+    pub inactivity_half_life: Duration,
+    /// Share of the risk that comes from inactivity; the rest comes from low engagement
+    pub inactivity_weight: f32,
+}
+
+impl PredictiveModelingSystem {
+    pub fn new() -> Self {
+        Self {
+            inactivity_half_life: Duration::from_secs(7 * 24 * 3600),
+            inactivity_weight: 0.7,
+        }
+    }
+
+    /// Dropout probability in [0, 1] as of `now`. A student who has never
+    /// been active is certain to drop out; unknown engagement counts as 0.5.
+    pub fn dropout_risk(&self, last_active: Option<SystemTime>, average_engagement: Option<f32>, now: SystemTime) -> f32 {
+        let Some(last_active) = last_active else {
+            return 1.0;
+        };
+        let idle = now.duration_since(last_active).unwrap_or_default();
+        let inactivity = 1.0 - 0.5f32.powf(idle.as_secs_f32() / self.inactivity_half_life.as_secs_f32());
+        let disengagement = 1.0 - average_engagement.unwrap_or(0.5).clamp(0.0, 1.0);
+        (self.inactivity_weight * inactivity + (1.0 - self.inactivity_weight) * disengagement).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for PredictiveModelingSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Events are partitioned by (region, activity type, student) so filters on
+/// those columns skip whole partitions.
+type PartitionKey = (String, String, String);
+
+#[derive(Debug)]
+pub struct GlobalAnalyticsPipeline {
+    /// Events older than this are dropped on update
+    pub retention: Duration,
+    pub predictive_model: PredictiveModelingSystem,
+    partitions: HashMap<PartitionKey, Vec<AnalyticsEvent>>,
+}
+
+impl GlobalAnalyticsPipeline {
+    pub fn new() -> Self {
+        Self {
+            retention: Duration::from_secs(90 * 24 * 3600),
+            predictive_model: PredictiveModelingSystem::new(),
+            partitions: HashMap::new(),
+        }
+    }
+
+    pub fn initialize(&mut self) -> RobinResult<()> {
+        Ok(())
+    }
+
+    pub fn update(&mut self, _delta_time: f32) -> RobinResult<Vec<PipelineEvent>> {
+        let Some(cutoff) = SystemTime::now().checked_sub(self.retention) else {
+            return Ok(Vec::new());
+        };
+
+        let mut count = 0;
+        for events in self.partitions.values_mut() {
+            let before = events.len();
+            events.retain(|event| event.timestamp >= cutoff);
+            count += before - events.len();
+        }
+        self.partitions.retain(|_, events| !events.is_empty());
+
+        Ok(if count > 0 {
+            vec![PipelineEvent::RecordsExpired { count }]
+        } else {
+            Vec::new()
+        })
+    }
+
+    pub fn ingest(&mut self, event: AnalyticsEvent) {
+        let key = (event.region.clone(), event.activity_type.clone(), event.student_id.clone());
+        self.partitions.entry(key).or_default().push(event);
+    }
+
+    pub fn event_count(&self) -> usize {
+        self.partitions.values().map(Vec::len).sum()
+    }
+
+    pub fn aggregate(&self, query: &AnalyticsQuery) -> AnalyticsResult {
+        let cutoff = query.time_window.and_then(|window| SystemTime::now().checked_sub(window));
+
+        // Group key is the JSON text of each group-by value, keeping rows in a stable order
+        let mut groups: BTreeMap<Vec<String>, (Vec<Value>, Vec<&AnalyticsEvent>)> = BTreeMap::new();
+        for ((region, activity_type, student_id), events) in &self.partitions {
+            if !partition_may_match(query, region, activity_type, student_id) {
+                continue;
+            }
+            for event in events {
+                if !query.includes(event, cutoff) {
+                    continue;
+                }
+
+                let values: Vec<Value> = query
+                    .group_by
+                    .iter()
+                    .map(|field| event.field(field).unwrap_or(Value::Null))
+                    .collect();
+                let key = values.iter().map(Value::to_string).collect();
+                groups.entry(key).or_insert_with(|| (values, Vec::new())).1.push(event);
+            }
+        }
+
+        let rows = groups
+            .into_values()
+            .map(|(values, events)| {
+                let mut row: HashMap<String, Value> = query.group_by.iter().cloned().zip(values).collect();
+                for metric in &query.metrics {
+                    row.insert(metric.name(), compute_metric(metric, &events));
+                }
+                row
+            })
+            .collect();
+        AnalyticsResult { rows }
+    }
+
+    /// Summary of one class's progress over `time_range`, with students at
+    /// risk of dropping out assessed as of the end of the range
+    pub fn class_dashboard(&self, teacher_id: &str, class_id: &str, time_range: (SystemTime, SystemTime)) -> ClassDashboard {
+        let (start, end) = time_range;
+        let class = AnalyticsQuery::new()
+            .filter("teacher_id", FilterOp::Eq, teacher_id)
+            .filter("class_id", FilterOp::Eq, class_id);
+        let in_range = class.clone().between(start, end);
+
+        // Roster from all retained events, in-range activity per student
+        let mut students: BTreeMap<&str, (Vec<&AnalyticsEvent>, Option<SystemTime>)> = BTreeMap::new();
+        let mut engagement_by_day: BTreeMap<Date, Vec<f64>> = BTreeMap::new();
+        for event in self.partitions.values().flatten() {
+            if !class.includes(event, None) {
+                continue;
+            }
+            let (events, last_active) = students.entry(event.student_id.as_str()).or_default();
+            if event.timestamp < end {
+                *last_active = (*last_active).max(Some(event.timestamp));
+            }
+            if in_range.includes(event, None) {
+                events.push(event);
+                if let Some(engagement) = event.field("engagement").as_ref().and_then(numeric) {
+                    let day = chrono::DateTime::<chrono::Utc>::from(event.timestamp).date_naive();
+                    engagement_by_day.entry(day).or_default().push(engagement);
+                }
+            }
+        }
+
+        let student_summaries: Vec<StudentSummary> = students
+            .into_iter()
+            .map(|(student_id, (events, last_active))| {
+                let average_engagement = compute_metric(&MetricType::Average("engagement".to_string()), &events)
+                    .as_f64()
+                    .map(|engagement| engagement as f32);
+                StudentSummary {
+                    student_id: student_id.to_string(),
+                    activity_count: events.len(),
+                    average_engagement,
+                    last_active,
+                    dropout_risk: self.predictive_model.dropout_risk(last_active, average_engagement, end),
+                }
+            })
+            .collect();
+        let at_risk_students = student_summaries
+            .iter()
+            .filter(|summary| summary.dropout_risk > AT_RISK_THRESHOLD)
+            .map(|summary| summary.student_id.clone())
+            .collect();
+
+        let mut skills = self.metric_by(
+            &in_range.clone().filter("activity_type", FilterOp::Eq, "assessment"),
+            "skill",
+            MetricType::Average("skill_delta".to_string()),
+        );
+        skills.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let top_skills = skills.iter().take(DASHBOARD_SKILL_COUNT).cloned().collect();
+        let bottom_skills = skills.iter().rev().take(DASHBOARD_SKILL_COUNT).cloned().collect();
+
+        let intervention_effectiveness = self
+            .metric_by(
+                &in_range.filter("activity_type", FilterOp::Eq, "intervention"),
+                "intervention_type",
+                MetricType::Average("effective".to_string()),
+            )
+            .into_iter()
+            .collect();
+
+        ClassDashboard {
+            student_summaries,
+            top_skills,
+            bottom_skills,
+            intervention_effectiveness,
+            engagement_by_day: engagement_by_day
+                .into_iter()
+                .map(|(day, values)| (day, (values.iter().sum::<f64>() / values.len() as f64) as f32))
+                .collect(),
+            at_risk_students,
+        }
+    }
+
+    /// `metric` for each string value of `field` among events matching
+    /// `query`, skipping groups where it has no value
+    fn metric_by(&self, query: &AnalyticsQuery, field: &str, metric: MetricType) -> Vec<(String, f32)> {
+        let name = metric.name();
+        let result = self.aggregate(&query.clone().group_by(field).metric(metric));
+        result
+            .rows
+            .iter()
+            .filter_map(|row| Some((row.get(field)?.as_str()?.to_string(), row.get(&name)?.as_f64()? as f32)))
+            .collect()
+    }
+}
+
+impl Default for GlobalAnalyticsPipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Cheap pre-check of equality filters on the partition columns
+fn partition_may_match(query: &AnalyticsQuery, region: &str, activity_type: &str, student_id: &str) -> bool {
+    query.filters.iter().all(|(field, op, value)| {
+        let column = match field.as_str() {
+            "region" => region,
+            "activity_type" => activity_type,
+            "student_id" => student_id,
+            _ => return true,
+        };
+        *op != FilterOp::Eq || value.as_str() == Some(column)
+    })
+}
+
+fn numeric(value: &Value) -> Option<f64> {
+    match value {
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => value.as_f64(),
+    }
+}
+
+fn compare(actual: &Value, op: FilterOp, expected: &Value) -> bool {
+    let ordering = match (numeric(actual), numeric(expected)) {
+        (Some(a), Some(b)) => a.partial_cmp(&b),
+        _ => match (actual.as_str(), expected.as_str()) {
+            (Some(a), Some(b)) => Some(a.cmp(b)),
+            _ => None,
+        },
+    };
+    match op {
+        FilterOp::Eq => actual == expected || ordering == Some(std::cmp::Ordering::Equal),
+        FilterOp::Ne => !(actual == expected || ordering == Some(std::cmp::Ordering::Equal)),
+        FilterOp::Gt => ordering == Some(std::cmp::Ordering::Greater),
+        FilterOp::Gte => matches!(ordering, Some(std::cmp::Ordering::Greater | std::cmp::Ordering::Equal)),
+        FilterOp::Lt => ordering == Some(std::cmp::Ordering::Less),
+        FilterOp::Lte => matches!(ordering, Some(std::cmp::Ordering::Less | std::cmp::Ordering::Equal)),
+    }
+}
+
+fn compute_metric(metric: &MetricType, events: &[&AnalyticsEvent]) -> Value {
+    let values = |field: &str| -> Vec<f64> {
+        events
+            .iter()
+            .filter_map(|event| event.field(field).as_ref().and_then(numeric))
+            .collect()
+    };
+    let number = |n: Option<f64>| n.and_then(serde_json::Number::from_f64).map_or(Value::Null, Value::Number);
+
+    match metric {
+        MetricType::Count => Value::from(events.len()),
+        MetricType::Average(field) => {
+            let values = values(field);
+            number((!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64))
+        }
+        MetricType::Sum(field) => number(Some(values(field).iter().sum())),
+        MetricType::Min(field) => number(values(field).into_iter().reduce(f64::min)),
+        MetricType::Max(field) => number(values(field).into_iter().reduce(f64::max)),
+        MetricType::DistinctCount(field) => {
+            let mut distinct: Vec<String> = events
+                .iter()
+                .filter_map(|event| event.field(field))
+                .map(|value| value.to_string())
+                .collect();
+            distinct.sort();
+            distinct.dedup();
+            Value::from(distinct.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REGIONS: [&str; 4] = ["us-east-1", "eu-west-1", "ap-southeast-1", "sa-east-1"];
+    const AGE_GROUPS: [&str; 3] = ["6-8", "9-11", "12-14"];
+    const INTERVENTIONS: [&str; 2] = ["hint", "encouragement"];
+
+    /// 1000 events: 500 building sessions, 300 assessments and 200 interventions
+    /// with values chosen so each aggregate has a known answer
+    fn synthetic_pipeline() -> GlobalAnalyticsPipeline {
+        let mut pipeline = GlobalAnalyticsPipeline::new();
+        for i in 0..1000 {
+            let student = format!("student-{}", i % 50);
+            let region = REGIONS[i % 4];
+            let event = if i < 500 {
+                // Engagement is 0.5 + 0.1 * region index
+                AnalyticsEvent::new(&student, region, "building").with_field("engagement", 0.5 + 0.1 * (i % 4) as f64)
+            } else if i < 800 {
+                // Older students gain more per assessment
+                let age = i % 3;
+                AnalyticsEvent::new(&student, region, "assessment")
+                    .with_field("age_group", AGE_GROUPS[age])
+                    .with_field("skill_delta", 0.02 * (age + 1) as f64)
+            } else {
+                // Hints work 3 times out of 4, encouragement 1 in 4
+                let kind = i % 2;
+                let effective = if kind == 0 { i % 8 != 0 } else { i % 8 == 1 };
+                AnalyticsEvent::new(&student, region, "intervention")
+                    .with_field("intervention_type", INTERVENTIONS[kind])
+                    .with_field("effective", effective)
+            };
+            pipeline.ingest(event);
+        }
+        pipeline
+    }
+
+    fn approx(value: &Value, expected: f64) -> bool {
+        value.as_f64().is_some_and(|v| (v - expected).abs() < 1e-9)
+    }
+
+    #[test]
+    fn test_engagement_by_region() {
+        let pipeline = synthetic_pipeline();
+        assert_eq!(pipeline.event_count(), 1000);
+
+        let result = pipeline.aggregate(&AnalyticsQuery::engagement_by_region());
+        assert_eq!(result.rows.len(), 4);
+        for (index, region) in REGIONS.iter().enumerate() {
+            let row = result.row("region", *region).unwrap();
+            // Every region also has assessment and intervention events without engagement
+            assert_eq!(row["count"], Value::from(250));
+            assert!(approx(&row["avg_engagement"], 0.5 + 0.1 * index as f64));
+        }
+    }
+
+    #[test]
+    fn test_skill_progression_by_age_group() {
+        let pipeline = synthetic_pipeline();
+        let result = pipeline.aggregate(&AnalyticsQuery::skill_progression_by_age_group());
+
+        assert_eq!(result.rows.len(), 3);
+        for (index, age_group) in AGE_GROUPS.iter().enumerate() {
+            let row = result.row("age_group", *age_group).unwrap();
+            assert!(approx(&row["avg_skill_delta"], 0.02 * (index + 1) as f64));
+            assert_eq!(row["distinct_student_id"], Value::from(50));
+        }
+    }
+
+    #[test]
+    fn test_intervention_effectiveness_by_type() {
+        let pipeline = synthetic_pipeline();
+        let result = pipeline.aggregate(&AnalyticsQuery::intervention_effectiveness_by_type());
+
+        assert_eq!(result.rows.len(), 2);
+        let hint = result.row("intervention_type", "hint").unwrap();
+        let encouragement = result.row("intervention_type", "encouragement").unwrap();
+        assert_eq!(hint["count"], Value::from(100));
+        assert!(approx(&hint["avg_effective"], 0.75));
+        assert!(approx(&encouragement["avg_effective"], 0.25));
+    }
+
+    #[test]
+    fn test_filters_and_time_window() {
+        let mut pipeline = synthetic_pipeline();
+        let mut old = AnalyticsEvent::new("student-0", "us-east-1", "building").with_field("engagement", 0.0);
+        old.timestamp = SystemTime::now() - Duration::from_secs(7200);
+        pipeline.ingest(old);
+
+        let recent = AnalyticsQuery::new()
+            .filter("region", FilterOp::Eq, "us-east-1")
+            .filter("engagement", FilterOp::Gte, 0.5)
+            .metric(MetricType::Count)
+            .metric(MetricType::Min("engagement".to_string()))
+            .time_window(Duration::from_secs(3600));
+        let result = pipeline.aggregate(&recent);
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0]["count"], Value::from(125));
+        assert!(approx(&result.rows[0]["min_engagement"], 0.5));
+
+        let all_time = AnalyticsQuery::new()
+            .filter("region", FilterOp::Eq, "us-east-1")
+            .filter("activity_type", FilterOp::Eq, "building")
+            .metric(MetricType::Count);
+        assert_eq!(pipeline.aggregate(&all_time).rows[0]["count"], Value::from(126));
+    }
+
+    #[test]
+    fn test_update_expires_old_records() {
+        let mut pipeline = synthetic_pipeline();
+        let mut old = AnalyticsEvent::new("student-0", "us-east-1", "building");
+        old.timestamp = SystemTime::now() - pipeline.retention - Duration::from_secs(60);
+        pipeline.ingest(old);
+
+        let events = pipeline.update(0.0).unwrap();
+        assert!(matches!(events[..], [PipelineEvent::RecordsExpired { count: 1 }]));
+        assert_eq!(pipeline.event_count(), 1000);
+    }
+
+    #[test]
+    fn test_class_dashboard_flags_inactive_students() {
+        const DAY: u64 = 24 * 3600;
+        let now = SystemTime::now();
+        let at = |days_ago: u64, event: AnalyticsEvent| AnalyticsEvent {
+            timestamp: now - Duration::from_secs(days_ago * DAY),
+            ..event
+        };
+        let class_event = |student: usize, activity_type: &str| {
+            AnalyticsEvent::new(&format!("student-{:02}", student), "us-east-1", activity_type)
+                .with_field("teacher_id", "teacher-1")
+                .with_field("class_id", "class-a")
+        };
+
+        // 15 students played over the last three days; the last 5 haven't
+        // played for a month
+        let mut pipeline = GlobalAnalyticsPipeline::new();
+        for student in 0..20 {
+            if student >= 15 {
+                pipeline.ingest(at(30, class_event(student, "building").with_field("engagement", 0.9)));
+                continue;
+            }
+            for days_ago in 1..=3 {
+                let engagement = 0.6 + 0.1 * days_ago as f64;
+                pipeline.ingest(at(days_ago, class_event(student, "building").with_field("engagement", engagement)));
+            }
+            for (skill, delta) in [("geometry", 0.08), ("logic", 0.05), ("planning", 0.02), ("materials", 0.01)] {
+                pipeline.ingest(at(
+                    2,
+                    class_event(student, "assessment").with_field("skill", skill).with_field("skill_delta", delta),
+                ));
+            }
+            let (kind, effective) = if student % 2 == 0 { ("hint", student % 4 == 0) } else { ("encouragement", true) };
+            pipeline.ingest(at(
+                1,
+                class_event(student, "intervention")
+                    .with_field("intervention_type", kind)
+                    .with_field("effective", effective),
+            ));
+        }
+        // Another teacher's inactive student doesn't show up
+        pipeline.ingest(at(
+            30,
+            AnalyticsEvent::new("student-99", "us-east-1", "building")
+                .with_field("teacher_id", "teacher-2")
+                .with_field("class_id", "class-a"),
+        ));
+
+        let dashboard = pipeline.class_dashboard("teacher-1", "class-a", (now - Duration::from_secs(7 * DAY), now));
+
+        assert_eq!(dashboard.student_summaries.len(), 20);
+        let expected: Vec<String> = (15..20).map(|student| format!("student-{:02}", student)).collect();
+        assert_eq!(dashboard.at_risk_students, expected);
+        let inactive = &dashboard.student_summaries[15];
+        assert_eq!(inactive.activity_count, 0);
+        assert_eq!(inactive.average_engagement, None);
+        assert!(dashboard.student_summaries[..15].iter().all(|summary| summary.activity_count == 8));
+
+        let skills: Vec<&str> = dashboard.top_skills.iter().map(|(skill, _)| skill.as_str()).collect();
+        assert_eq!(skills, ["geometry", "logic", "planning"]);
+        let skills: Vec<&str> = dashboard.bottom_skills.iter().map(|(skill, _)| skill.as_str()).collect();
+        assert_eq!(skills, ["materials", "planning", "logic"]);
+        assert!((dashboard.top_skills[0].1 - 0.08).abs() < 1e-6);
+
+        assert_eq!(dashboard.intervention_effectiveness.len(), 2);
+        assert!((dashboard.intervention_effectiveness["hint"] - 0.5).abs() < 1e-6);
+        assert!((dashboard.intervention_effectiveness["encouragement"] - 1.0).abs() < 1e-6);
+
+        // Three days of play, oldest first; the month-old events are out of range
+        let engagement: Vec<f32> = dashboard.engagement_by_day.iter().map(|&(_, engagement)| engagement).collect();
+        assert_eq!(engagement.len(), 3);
+        for (actual, expected) in engagement.iter().zip([0.9, 0.8, 0.7]) {
+            assert!((actual - expected).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+// Robin Engine 2.0 - Regional Compliance Checks
+// Validates a region's data handling against the privacy regulations it is
+// subject to before anything is deployed there
+
+use super::{DeploymentRegion, PrivacyRegulation};
+use serde::{Serialize, Deserialize};
+
+/// How much learner data a region collects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DataCollectionLevel {
+    Minimal,               // Only what the platform needs to run
+    Standard,              // Progress and session analytics
+    Comprehensive,         // Full interaction and behavioural telemetry
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnonymizationLevel {
+    None,                  // Records carry learner identities
+    Pseudonymized,         // Identities replaced with stable tokens
+    Anonymized,            // No link back to a learner is kept
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParentalControls {
+    pub verifiable_consent: bool,
+    pub activity_reports: bool,
+}
+
+/// How a region collects, protects and shares learner data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegionDataPolicy {
+    pub data_collection: DataCollectionLevel,
+    pub anonymization_level: AnonymizationLevel,
+    /// Youngest students the region serves
+    pub minimum_student_age: u8,
+    pub parental_controls: Option<ParentalControls>,
+    pub share_with_researchers: bool,
+    /// Whether students, or their parents, have explicitly agreed to
+    /// their records being shared with researchers
+    pub research_consent_obtained: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ComplianceViolationSeverity {
+    Low,
+    Medium,
+    High,
+    Critical,              // Deployment to the region is blocked
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComplianceViolation {
+    pub regulation: PrivacyRegulation,
+    pub description: String,
+    pub severity: ComplianceViolationSeverity,
+    pub remediation: String,
+}
+
+/// Students younger than this need parental controls under COPPA
+const COPPA_AGE: u8 = 13;
+
+pub struct ComplianceChecker;
+
+impl ComplianceChecker {
+    /// Every way the region's data policy breaks a regulation it is subject
+    /// to, in the order the regulations are listed
+    pub fn validate_region(region: &DeploymentRegion) -> Vec<ComplianceViolation> {
+        let compliance = &region.regulatory_compliance;
+        let policy = &compliance.data_policy;
+        let mut violations = Vec::new();
+
+        for &regulation in &compliance.privacy_regulations {
+            let violation = match regulation {
+                PrivacyRegulation::GDPR
+                    if policy.data_collection == DataCollectionLevel::Comprehensive
+                        && policy.anonymization_level == AnonymizationLevel::None =>
+                {
+                    Some((
+                        "Comprehensive data collection keeps learner identities",
+                        "Pseudonymize or anonymize collected data, or reduce the collection level",
+                    ))
+                }
+                PrivacyRegulation::COPPA
+                    if policy.minimum_student_age < COPPA_AGE && policy.parental_controls.is_none() =>
+                {
+                    Some((
+                        "Students under 13 are served without parental controls",
+                        "Enable parental controls for the region",
+                    ))
+                }
+                PrivacyRegulation::FERPA
+                    if policy.share_with_researchers && !policy.research_consent_obtained =>
+                {
+                    Some((
+                        "Student records are shared with researchers without explicit consent",
+                        "Obtain explicit consent before sharing, or stop sharing with researchers",
+                    ))
+                }
+                _ => None,
+            };
+
+            if let Some((description, remediation)) = violation {
+                violations.push(ComplianceViolation {
+                    regulation,
+                    description: description.to_string(),
+                    severity: ComplianceViolationSeverity::Critical,
+                    remediation: remediation.to_string(),
+                });
+            }
+        }
+
+        violations
+    }
+
+    /// Whether any violation is severe enough to stop a deployment
+    pub fn blocks_deployment(violations: &[ComplianceViolation]) -> bool {
+        violations.iter().any(|v| v.severity == ComplianceViolationSeverity::Critical)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cloud::{CloudPlatformManager, DeploymentStatus};
+
+    /// A region from the default global setup, subject only to `regulation`
+    fn region(regulation: PrivacyRegulation) -> DeploymentRegion {
+        let mut manager = CloudPlatformManager::new();
+        manager.setup_global_regions().unwrap();
+        let mut region = manager.deployment_regions.remove("us-east-1").unwrap();
+        region.regulatory_compliance.privacy_regulations = vec![regulation];
+        region
+    }
+
+    fn violated(region: &DeploymentRegion) -> Vec<PrivacyRegulation> {
+        ComplianceChecker::validate_region(region).iter().map(|v| v.regulation).collect()
+    }
+
+    #[test]
+    fn test_gdpr_needs_anonymization_for_comprehensive_collection() {
+        let mut region = region(PrivacyRegulation::GDPR);
+        region.regulatory_compliance.data_policy.data_collection = DataCollectionLevel::Comprehensive;
+        region.regulatory_compliance.data_policy.anonymization_level = AnonymizationLevel::Pseudonymized;
+        assert!(violated(&region).is_empty());
+
+        region.regulatory_compliance.data_policy.anonymization_level = AnonymizationLevel::None;
+        assert_eq!(violated(&region), [PrivacyRegulation::GDPR]);
+
+        // Without comprehensive collection, identities may be kept
+        region.regulatory_compliance.data_policy.data_collection = DataCollectionLevel::Standard;
+        assert!(violated(&region).is_empty());
+    }
+
+    #[test]
+    fn test_coppa_needs_parental_controls_for_under_13s() {
+        let mut region = region(PrivacyRegulation::COPPA);
+        assert!(violated(&region).is_empty());
+
+        region.regulatory_compliance.data_policy.parental_controls = None;
+        assert_eq!(violated(&region), [PrivacyRegulation::COPPA]);
+
+        region.regulatory_compliance.data_policy.minimum_student_age = 13;
+        assert!(violated(&region).is_empty());
+    }
+
+    #[test]
+    fn test_ferpa_needs_consent_to_share_with_researchers() {
+        let mut region = region(PrivacyRegulation::FERPA);
+        region.regulatory_compliance.data_policy.share_with_researchers = true;
+        region.regulatory_compliance.data_policy.research_consent_obtained = true;
+        assert!(violated(&region).is_empty());
+
+        region.regulatory_compliance.data_policy.research_consent_obtained = false;
+        let violations = ComplianceChecker::validate_region(&region);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].regulation, PrivacyRegulation::FERPA);
+        assert!(ComplianceChecker::blocks_deployment(&violations));
+    }
+
+    #[test]
+    fn test_regulations_a_region_is_not_subject_to_are_ignored() {
+        let mut region = region(PrivacyRegulation::PDPA);
+        let policy = &mut region.regulatory_compliance.data_policy;
+        policy.data_collection = DataCollectionLevel::Comprehensive;
+        policy.anonymization_level = AnonymizationLevel::None;
+        policy.parental_controls = None;
+        policy.share_with_researchers = true;
+        assert!(violated(&region).is_empty());
+    }
+
+    #[test]
+    fn test_deploy_globally_skips_regions_with_critical_violations() {
+        let mut manager = CloudPlatformManager::new();
+        manager.setup_global_regions().unwrap();
+        manager.deployment_regions.get_mut("eu-west-1").unwrap()
+            .regulatory_compliance.data_policy.anonymization_level = AnonymizationLevel::None;
+        manager.deployment_regions.get_mut("eu-west-1").unwrap()
+            .regulatory_compliance.data_policy.data_collection = DataCollectionLevel::Comprehensive;
+
+        let status = manager.deploy_globally().unwrap();
+        assert_eq!(status.failed_deployments, 1);
+        assert_eq!(status.successful_deployments, status.total_regions - 1);
+        let blocked = status.deployment_details.iter().find(|d| d.region_id == "eu-west-1").unwrap();
+        assert_eq!(blocked.status, DeploymentStatus::Failed);
+        assert!(blocked.error_message.as_ref().unwrap().contains("GDPR"));
+    }
+}
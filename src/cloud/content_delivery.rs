@@ -0,0 +1,434 @@
+// Robin Engine 2.0 - Content Delivery Network
+// Per-edge-node request logs, cache-hit ratio monitoring and time-limited
+// signed download URLs
+
+use crate::engine::error::RobinResult;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Nodes serving less than this fraction of requests from cache raise an alert
+pub const LOW_CACHE_HIT_RATIO: f32 = 0.6;
+
+/// One request served by an edge node
+#[derive(Debug, Clone)]
+pub struct RequestRecord {
+    pub content_id: String,
+    pub served_from_cache: bool,
+    pub latency_ms: f32,
+    pub timestamp: Instant,
+}
+
+/// What the holder of a signed URL may do with the content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentPermissions {
+    Read,
+    ReadWrite,
+}
+
+impl ContentPermissions {
+    /// Value of the `permissions` query parameter
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContentPermissions::Read => "read",
+            ContentPermissions::ReadWrite => "read-write",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "read" => Some(ContentPermissions::Read),
+            "read-write" => Some(ContentPermissions::ReadWrite),
+            _ => None,
+        }
+    }
+}
+
+/// A download URL that stops working at `expiry`. `url` carries the content
+/// id, expiry, permissions and signature, so it can be checked on its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedUrl {
+    pub url: String,
+    /// Hex HMAC-SHA256 of the content id, expiry and permissions
+    pub signature: String,
+    /// Whole seconds, matching the timestamp in `url`
+    pub expiry: SystemTime,
+}
+
+#[derive(Debug, Clone)]
+pub enum CDNEvent {
+    LowCacheHitRatio { node_id: String, hit_ratio: f32 },
+}
+
+#[derive(Debug)]
+pub struct ContentDeliveryNetwork {
+    /// Requests kept per node; the oldest are dropped beyond this
+    pub request_log_capacity: usize,
+    /// Window the hit ratio is averaged over when checking for alerts
+    pub alert_window: Duration,
+    /// Content URLs are `base_url/<content id>`
+    pub base_url: String,
+    /// Server-side key signed URLs are signed with; random per instance by default
+    pub signing_key: Vec<u8>,
+    request_logs: HashMap<String, VecDeque<RequestRecord>>,
+    /// Nodes currently below the threshold, so each drop alerts only once
+    low_ratio_nodes: HashSet<String>,
+}
+
+impl ContentDeliveryNetwork {
+    pub fn new() -> Self {
+        Self {
+            request_log_capacity: 10_000,
+            alert_window: Duration::from_secs(300),
+            base_url: "https://cdn.robin.education/content".to_string(),
+            signing_key: rand::random::<[u8; 32]>().to_vec(),
+            request_logs: HashMap::new(),
+            low_ratio_nodes: HashSet::new(),
+        }
+    }
+
+    pub fn initialize(&mut self) -> RobinResult<()> {
+        Ok(())
+    }
+
+    /// Emits `LowCacheHitRatio` for each node that has dropped below
+    /// `LOW_CACHE_HIT_RATIO` since the last update
+    pub fn update(&mut self, _delta_time: f32) -> RobinResult<Vec<CDNEvent>> {
+        let mut events = Vec::new();
+        let node_ids: Vec<String> = self.request_logs.keys().cloned().collect();
+        for node_id in node_ids {
+            let hit_ratio = self.cache_hit_ratio(Some(&node_id), self.alert_window);
+            if hit_ratio < LOW_CACHE_HIT_RATIO {
+                if self.low_ratio_nodes.insert(node_id.clone()) {
+                    events.push(CDNEvent::LowCacheHitRatio { node_id, hit_ratio });
+                }
+            } else {
+                self.low_ratio_nodes.remove(&node_id);
+            }
+        }
+        Ok(events)
+    }
+
+    pub fn record_request(&mut self, content_id: &str, served_from_cache: bool, edge_node_id: &str, latency_ms: f32) {
+        self.record_request_at(content_id, served_from_cache, edge_node_id, latency_ms, Instant::now());
+    }
+
+    fn record_request_at(
+        &mut self,
+        content_id: &str,
+        served_from_cache: bool,
+        edge_node_id: &str,
+        latency_ms: f32,
+        timestamp: Instant,
+    ) {
+        let log = self.request_logs.entry(edge_node_id.to_string()).or_default();
+        if log.len() >= self.request_log_capacity {
+            log.pop_front();
+        }
+        log.push_back(RequestRecord {
+            content_id: content_id.to_string(),
+            served_from_cache,
+            latency_ms,
+            timestamp,
+        });
+    }
+
+    /// Fraction of requests within `window` served from cache, for one node or
+    /// across all nodes. With no requests in the window there is nothing to
+    /// miss, so the ratio is 1.
+    pub fn cache_hit_ratio(&self, node_id: Option<&str>, window: Duration) -> f32 {
+        let (hits, total) = self
+            .recent_requests(node_id, window)
+            .fold((0, 0), |(hits, total), record| (hits + record.served_from_cache as usize, total + 1));
+        if total == 0 {
+            1.0
+        } else {
+            hits as f32 / total as f32
+        }
+    }
+
+    /// Content ids whose hit ratio across all nodes over the last hour is
+    /// below `threshold`, sorted
+    pub fn identify_cache_misses(&self, threshold: f32) -> Vec<String> {
+        let mut per_content: HashMap<&str, (usize, usize)> = HashMap::new();
+        for record in self.recent_requests(None, Duration::from_secs(3600)) {
+            let (hits, total) = per_content.entry(record.content_id.as_str()).or_default();
+            *hits += record.served_from_cache as usize;
+            *total += 1;
+        }
+
+        let mut misses: Vec<String> = per_content
+            .into_iter()
+            .filter(|&(_, (hits, total))| (hits as f32 / total as f32) < threshold)
+            .map(|(content_id, _)| content_id.to_string())
+            .collect();
+        misses.sort();
+        misses
+    }
+
+    /// A URL for `content_id` that's valid for `expires_in`. Content ids
+    /// must be URL path segments.
+    pub fn presign_url(&self, content_id: &str, expires_in: Duration, permissions: ContentPermissions) -> SignedUrl {
+        self.presign_url_at(content_id, expires_in, permissions, SystemTime::now())
+    }
+
+    fn presign_url_at(
+        &self,
+        content_id: &str,
+        expires_in: Duration,
+        permissions: ContentPermissions,
+        now: SystemTime,
+    ) -> SignedUrl {
+        let expiry_secs = (now + expires_in).duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let signature = url_signature(&self.signing_key, content_id, expiry_secs, permissions);
+        SignedUrl {
+            url: format!(
+                "{}/{}?expires={}&permissions={}&signature={}",
+                self.base_url,
+                content_id,
+                expiry_secs,
+                permissions.as_str(),
+                signature
+            ),
+            signature,
+            expiry: UNIX_EPOCH + Duration::from_secs(expiry_secs),
+        }
+    }
+
+    /// Whether `url` was signed with `key`, hasn't expired and hasn't been
+    /// altered, including its content id, expiry and permissions
+    pub fn validate_signed_url(url: &SignedUrl, key: &[u8]) -> bool {
+        Self::validate_signed_url_at(url, key, SystemTime::now())
+    }
+
+    fn validate_signed_url_at(url: &SignedUrl, key: &[u8], now: SystemTime) -> bool {
+        let Some((path, query)) = url.url.split_once('?') else {
+            return false;
+        };
+        let Some((_, content_id)) = path.rsplit_once('/') else {
+            return false;
+        };
+        let parameter = |name: &str| {
+            query
+                .split('&')
+                .find_map(|pair| pair.split_once('=').filter(|(key, _)| *key == name).map(|(_, value)| value))
+        };
+        let (Some(expiry_secs), Some(permissions), Some(signature)) = (
+            parameter("expires").and_then(|value| value.parse::<u64>().ok()),
+            parameter("permissions").and_then(ContentPermissions::parse),
+            parameter("signature"),
+        ) else {
+            return false;
+        };
+
+        let expiry = UNIX_EPOCH + Duration::from_secs(expiry_secs);
+        let expected = url_signature(key, content_id, expiry_secs, permissions);
+        expiry == url.expiry
+            && now < expiry
+            && signature == url.signature
+            && constant_time_eq(expected.as_bytes(), signature.as_bytes())
+    }
+
+    fn recent_requests<'a>(
+        &'a self,
+        node_id: Option<&'a str>,
+        window: Duration,
+    ) -> impl Iterator<Item = &'a RequestRecord> + 'a {
+        let now = Instant::now();
+        self.request_logs
+            .iter()
+            .filter(move |(id, _)| node_id.is_none_or(|node_id| id.as_str() == node_id))
+            .flat_map(|(_, log)| log.iter())
+            .filter(move |record| now.duration_since(record.timestamp) <= window)
+    }
+}
+
+impl Default for ContentDeliveryNetwork {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Hex HMAC-SHA256 of `content_id`, the expiry timestamp and the permissions
+fn url_signature(key: &[u8], content_id: &str, expiry_secs: u64, permissions: ContentPermissions) -> String {
+    let message = format!("{}{}{}", content_id, expiry_secs, permissions.as_str());
+    hmac_sha256(key, message.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// HMAC as defined in RFC 2104, over SHA-256
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let inner = Sha256::new()
+        .chain_update(block.map(|byte| byte ^ 0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(block.map(|byte| byte ^ 0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+/// Compares without stopping at the first difference, so the time taken
+/// doesn't reveal how much of a guessed signature was right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |difference, (x, y)| difference | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_hit_ratio_per_node_and_global() {
+        let mut cdn = ContentDeliveryNetwork::new();
+        for i in 0..10 {
+            cdn.record_request("world-1", i < 8, "edge-a", 12.0);
+            cdn.record_request("world-1", i < 2, "edge-b", 80.0);
+        }
+
+        let window = Duration::from_secs(60);
+        assert!((cdn.cache_hit_ratio(Some("edge-a"), window) - 0.8).abs() < 1e-6);
+        assert!((cdn.cache_hit_ratio(Some("edge-b"), window) - 0.2).abs() < 1e-6);
+        assert!((cdn.cache_hit_ratio(None, window) - 0.5).abs() < 1e-6);
+        assert_eq!(cdn.cache_hit_ratio(Some("edge-c"), window), 1.0);
+    }
+
+    #[test]
+    fn test_window_excludes_old_requests() {
+        let mut cdn = ContentDeliveryNetwork::new();
+        let two_hours_ago = Instant::now() - Duration::from_secs(7200);
+        for _ in 0..10 {
+            cdn.record_request_at("asset", false, "edge-a", 50.0, two_hours_ago);
+        }
+        cdn.record_request("asset", true, "edge-a", 5.0);
+
+        assert_eq!(cdn.cache_hit_ratio(Some("edge-a"), Duration::from_secs(3600)), 1.0);
+        assert!(cdn.cache_hit_ratio(Some("edge-a"), Duration::from_secs(3 * 3600)) < 0.1);
+        assert!(cdn.identify_cache_misses(0.5).is_empty());
+    }
+
+    #[test]
+    fn test_ring_buffer_drops_oldest() {
+        let mut cdn = ContentDeliveryNetwork::new();
+        cdn.request_log_capacity = 4;
+        for _ in 0..4 {
+            cdn.record_request("asset", false, "edge-a", 50.0);
+        }
+        for _ in 0..4 {
+            cdn.record_request("asset", true, "edge-a", 5.0);
+        }
+
+        assert_eq!(cdn.request_logs["edge-a"].len(), 4);
+        assert_eq!(cdn.cache_hit_ratio(Some("edge-a"), Duration::from_secs(60)), 1.0);
+    }
+
+    #[test]
+    fn test_identify_cache_misses() {
+        let mut cdn = ContentDeliveryNetwork::new();
+        for i in 0..10 {
+            cdn.record_request("popular", true, "edge-a", 5.0);
+            cdn.record_request("cold", i == 0, "edge-a", 90.0);
+            cdn.record_request("lukewarm", i % 2 == 0, "edge-b", 40.0);
+        }
+
+        assert_eq!(cdn.identify_cache_misses(0.6), ["cold", "lukewarm"]);
+        assert_eq!(cdn.identify_cache_misses(0.3), ["cold"]);
+    }
+
+    #[test]
+    fn test_low_hit_ratio_alerts_once_per_drop() {
+        let mut cdn = ContentDeliveryNetwork::new();
+        for i in 0..10 {
+            cdn.record_request("asset", i < 3, "edge-a", 40.0);
+            cdn.record_request("asset", true, "edge-b", 5.0);
+        }
+
+        let events = cdn.update(0.016).unwrap();
+        assert!(matches!(
+            &events[..],
+            [CDNEvent::LowCacheHitRatio { node_id, hit_ratio }] if node_id == "edge-a" && (*hit_ratio - 0.3).abs() < 1e-6
+        ));
+        assert!(cdn.update(0.016).unwrap().is_empty());
+
+        // Recovering clears the alert so the next drop is reported again
+        for _ in 0..30 {
+            cdn.record_request("asset", true, "edge-a", 5.0);
+        }
+        assert!(cdn.update(0.016).unwrap().is_empty());
+        for _ in 0..60 {
+            cdn.record_request("asset", false, "edge-a", 40.0);
+        }
+        assert_eq!(cdn.update(0.016).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_hmac_sha256_matches_rfc_4231() {
+        let hex = |bytes: [u8; 32]| bytes.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+        assert_eq!(
+            hex(hmac_sha256(&[0x0b; 20], b"Hi There")),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        // Keys longer than a block are hashed first
+        assert_eq!(
+            hex(hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First")),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn test_signed_url_validation() {
+        let cdn = ContentDeliveryNetwork::new();
+        let key = cdn.signing_key.clone();
+        let now = SystemTime::now();
+        let signed = cdn.presign_url_at("world-42", Duration::from_secs(600), ContentPermissions::Read, now);
+        assert!(signed.url.starts_with("https://cdn.robin.education/content/world-42?expires="));
+
+        // Valid until the expiry, and only with the signing key
+        assert!(ContentDeliveryNetwork::validate_signed_url_at(&signed, &key, now));
+        assert!(ContentDeliveryNetwork::validate_signed_url_at(&signed, &key, now + Duration::from_secs(599)));
+        assert!(!ContentDeliveryNetwork::validate_signed_url_at(&signed, &key, now + Duration::from_secs(601)));
+        assert!(!ContentDeliveryNetwork::validate_signed_url_at(&signed, b"another key", now));
+        assert!(ContentDeliveryNetwork::validate_signed_url(&signed, &key));
+
+        // Changing the signature, content or expiry breaks it
+        let flipped = if signed.signature.starts_with('0') { "1" } else { "0" };
+        let tampered_signature = format!("{}{}", flipped, &signed.signature[1..]);
+        let tampered = SignedUrl {
+            url: signed.url.replace(&signed.signature, &tampered_signature),
+            signature: tampered_signature,
+            ..signed.clone()
+        };
+        assert!(!ContentDeliveryNetwork::validate_signed_url_at(&tampered, &key, now));
+        let other_content = SignedUrl { url: signed.url.replace("world-42", "world-43"), ..signed.clone() };
+        assert!(!ContentDeliveryNetwork::validate_signed_url_at(&other_content, &key, now));
+        let expiry_secs = signed.expiry.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let extended = SignedUrl {
+            url: signed.url.replace(&expiry_secs.to_string(), &(expiry_secs + 3600).to_string()),
+            expiry: signed.expiry + Duration::from_secs(3600),
+            ..signed.clone()
+        };
+        assert!(!ContentDeliveryNetwork::validate_signed_url_at(&extended, &key, now));
+    }
+
+    #[test]
+    fn test_permissions_are_signed() {
+        let cdn = ContentDeliveryNetwork::new();
+        let now = SystemTime::now();
+        let read = cdn.presign_url_at("world-42", Duration::from_secs(600), ContentPermissions::Read, now);
+        let write = cdn.presign_url_at("world-42", Duration::from_secs(600), ContentPermissions::ReadWrite, now);
+        assert_ne!(read.signature, write.signature);
+
+        // Upgrading a read URL's permissions invalidates it
+        let upgraded = SignedUrl { url: read.url.replace("permissions=read", "permissions=read-write"), ..read };
+        assert!(!ContentDeliveryNetwork::validate_signed_url_at(&upgraded, &cdn.signing_key, now));
+        assert!(ContentDeliveryNetwork::validate_signed_url_at(&write, &cdn.signing_key, now));
+    }
+}
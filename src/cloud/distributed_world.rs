@@ -0,0 +1,436 @@
+// Robin Engine 2.0 - Distributed World System
+// Sharded voxel world state replicated across nodes with a delta-sync protocol
+
+use crate::engine::error::{RobinError, RobinResult};
+use crate::engine::generation::voxel_system::VoxelType;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc;
+
+/// Edge length of a shard in voxels
+pub const SHARD_SIZE: i32 = 32;
+
+/// Number of shards along each axis when no layout is given
+pub const DEFAULT_SHARD_COUNTS: [u32; 3] = [8, 2, 8];
+
+/// World-space voxel position
+pub type VoxelCoord = [i32; 3];
+
+/// Dense block of voxels backing one shard
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxelChunk {
+    pub origin: VoxelCoord,
+    voxels: Vec<VoxelType>,
+}
+
+impl VoxelChunk {
+    pub fn new(origin: VoxelCoord) -> Self {
+        Self {
+            origin,
+            voxels: vec![VoxelType::Air; (SHARD_SIZE * SHARD_SIZE * SHARD_SIZE) as usize],
+        }
+    }
+
+    /// Voxel at a world position, or `None` if it lies outside this chunk
+    pub fn get(&self, position: VoxelCoord) -> Option<VoxelType> {
+        self.index(position).map(|index| self.voxels[index])
+    }
+
+    /// Writes a voxel and returns whether the stored value changed
+    pub fn set(&mut self, position: VoxelCoord, voxel_type: VoxelType) -> bool {
+        match self.index(position) {
+            Some(index) if self.voxels[index] != voxel_type => {
+                self.voxels[index] = voxel_type;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn count_solid_voxels(&self) -> usize {
+        self.voxels.iter().filter(|&&voxel| voxel != VoxelType::Air).count()
+    }
+
+    fn index(&self, position: VoxelCoord) -> Option<usize> {
+        let local = [
+            position[0] - self.origin[0],
+            position[1] - self.origin[1],
+            position[2] - self.origin[2],
+        ];
+        if local.iter().any(|c| !(0..SHARD_SIZE).contains(c)) {
+            return None;
+        }
+        Some((local[0] + local[1] * SHARD_SIZE + local[2] * SHARD_SIZE * SHARD_SIZE) as usize)
+    }
+}
+
+/// One shard of the world. Every node keeps a replica of every shard, but
+/// only `owner_node` applies edits to it; everyone else follows its deltas.
+#[derive(Debug)]
+pub struct WorldShard {
+    pub shard_id: u32,
+    pub voxel_data: Arc<RwLock<VoxelChunk>>,
+    pub owner_node: String,
+    pub subscribers: Vec<String>,
+    /// Sequence number of the last delta produced (owner) or applied (replica)
+    pub sequence: u64,
+}
+
+/// A single voxel change requested by a client
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct VoxelEdit {
+    pub position: VoxelCoord,
+    pub voxel_type: VoxelType,
+}
+
+/// Changed voxels of one shard, broadcast by its owner after applying edits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorldDelta {
+    pub shard_id: u32,
+    pub source_node: String,
+    pub sequence: u64,
+    pub changes: Vec<(VoxelCoord, VoxelType)>,
+}
+
+impl WorldDelta {
+    pub fn encode(&self) -> RobinResult<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| RobinError::InvalidData {
+            field: "world_delta".to_string(),
+            reason: e.to_string(),
+        })
+    }
+
+    pub fn decode(bytes: &[u8]) -> RobinResult<Self> {
+        bincode::deserialize(bytes).map_err(|e| RobinError::InvalidData {
+            field: "world_delta".to_string(),
+            reason: e.to_string(),
+        })
+    }
+}
+
+/// Messages exchanged between nodes
+#[derive(Debug, Clone)]
+pub enum NodeMessage {
+    /// An edit forwarded to the shard's owner
+    Edit(VoxelEdit),
+    /// A binary-encoded `WorldDelta`
+    Delta(Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+pub enum DistributedWorldEvent {
+    EditApplied { shard_id: u32, position: VoxelCoord },
+    EditForwarded { shard_id: u32, owner_node: String },
+    DeltaBroadcast { shard_id: u32, subscribers: usize, bytes: usize },
+    DeltaApplied { shard_id: u32, source_node: String, changes: usize },
+    StaleDeltaDropped { shard_id: u32, sequence: u64 },
+}
+
+#[derive(Debug)]
+pub struct DistributedWorldSystem {
+    pub node_id: String,
+    pub shards: HashMap<u32, WorldShard>,
+    shard_counts: [u32; 3],
+    peers: HashMap<String, mpsc::UnboundedSender<NodeMessage>>,
+    sender: mpsc::UnboundedSender<NodeMessage>,
+    receiver: mpsc::UnboundedReceiver<NodeMessage>,
+    pending_events: Vec<DistributedWorldEvent>,
+}
+
+impl DistributedWorldSystem {
+    pub fn new() -> Self {
+        Self::with_layout("local", DEFAULT_SHARD_COUNTS)
+    }
+
+    /// A node named `node_id` over a world of `shard_counts` shards per axis
+    pub fn with_layout(node_id: &str, shard_counts: [u32; 3]) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            node_id: node_id.to_string(),
+            shards: HashMap::new(),
+            shard_counts,
+            peers: HashMap::new(),
+            sender,
+            receiver,
+            pending_events: Vec::new(),
+        }
+    }
+
+    /// Creates every shard, owned by this node until reassigned
+    pub fn initialize(&mut self) -> RobinResult<()> {
+        let [nx, ny, nz] = self.shard_counts;
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let shard_id = x + y * nx + z * nx * ny;
+                    let origin = [x as i32 * SHARD_SIZE, y as i32 * SHARD_SIZE, z as i32 * SHARD_SIZE];
+                    self.shards.entry(shard_id).or_insert_with(|| WorldShard {
+                        shard_id,
+                        voxel_data: Arc::new(RwLock::new(VoxelChunk::new(origin))),
+                        owner_node: self.node_id.clone(),
+                        subscribers: Vec::new(),
+                        sequence: 0,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Channel peers use to send messages to this node
+    pub fn sender(&self) -> mpsc::UnboundedSender<NodeMessage> {
+        self.sender.clone()
+    }
+
+    pub fn connect_peer(&mut self, node_id: &str, sender: mpsc::UnboundedSender<NodeMessage>) {
+        self.peers.insert(node_id.to_string(), sender);
+    }
+
+    pub fn set_shard_owner(&mut self, shard_id: u32, owner_node: &str) -> RobinResult<()> {
+        self.shard_mut(shard_id)?.owner_node = owner_node.to_string();
+        Ok(())
+    }
+
+    pub fn subscribe(&mut self, shard_id: u32, node_id: &str) -> RobinResult<()> {
+        let shard = self.shard_mut(shard_id)?;
+        if !shard.subscribers.iter().any(|s| s == node_id) {
+            shard.subscribers.push(node_id.to_string());
+        }
+        Ok(())
+    }
+
+    /// Shard containing a world position, if it lies inside the world
+    pub fn shard_for(&self, position: VoxelCoord) -> Option<u32> {
+        let [nx, ny, nz] = self.shard_counts;
+        let shard = [
+            position[0].div_euclid(SHARD_SIZE),
+            position[1].div_euclid(SHARD_SIZE),
+            position[2].div_euclid(SHARD_SIZE),
+        ];
+        if shard.iter().any(|&c| c < 0) || shard[0] as u32 >= nx || shard[1] as u32 >= ny || shard[2] as u32 >= nz {
+            return None;
+        }
+        Some(shard[0] as u32 + shard[1] as u32 * nx + shard[2] as u32 * nx * ny)
+    }
+
+    pub fn get_voxel(&self, position: VoxelCoord) -> Option<VoxelType> {
+        let shard = self.shards.get(&self.shard_for(position)?)?;
+        let chunk = shard.voxel_data.read().ok()?;
+        chunk.get(position)
+    }
+
+    /// Applies an edit if this node owns the target shard and broadcasts the
+    /// resulting delta to the shard's subscribers; otherwise forwards the
+    /// edit to the owner.
+    pub fn apply_edit(&mut self, edit: VoxelEdit) -> RobinResult<()> {
+        let shard_id = self.shard_for(edit.position).ok_or_else(|| {
+            RobinError::InvalidInput(format!("Voxel {:?} is outside the distributed world", edit.position))
+        })?;
+        let shard = self
+            .shards
+            .get_mut(&shard_id)
+            .ok_or_else(|| RobinError::NotFound(format!("Shard {} has not been initialized", shard_id)))?;
+
+        if shard.owner_node != self.node_id {
+            let owner_node = shard.owner_node.clone();
+            Self::send(&self.peers, &owner_node, NodeMessage::Edit(edit))?;
+            self.pending_events.push(DistributedWorldEvent::EditForwarded { shard_id, owner_node });
+            return Ok(());
+        }
+
+        let changed = shard
+            .voxel_data
+            .write()
+            .map_err(|_| RobinError::ThreadError(format!("Shard {} lock poisoned", shard_id)))?
+            .set(edit.position, edit.voxel_type);
+        if !changed {
+            return Ok(());
+        }
+        self.pending_events.push(DistributedWorldEvent::EditApplied { shard_id, position: edit.position });
+
+        shard.sequence += 1;
+        let bytes = WorldDelta {
+            shard_id,
+            source_node: self.node_id.clone(),
+            sequence: shard.sequence,
+            changes: vec![(edit.position, edit.voxel_type)],
+        }
+        .encode()?;
+
+        for subscriber in &shard.subscribers {
+            Self::send(&self.peers, subscriber, NodeMessage::Delta(bytes.clone()))?;
+        }
+        self.pending_events.push(DistributedWorldEvent::DeltaBroadcast {
+            shard_id,
+            subscribers: shard.subscribers.len(),
+            bytes: bytes.len(),
+        });
+        Ok(())
+    }
+
+    /// Drains messages from peers: forwarded edits are applied (this node is
+    /// their owner) and deltas update the local replicas.
+    pub fn update(&mut self, _delta_time: f32) -> RobinResult<Vec<DistributedWorldEvent>> {
+        while let Ok(message) = self.receiver.try_recv() {
+            match message {
+                NodeMessage::Edit(edit) => self.apply_edit(edit)?,
+                NodeMessage::Delta(bytes) => self.apply_delta(WorldDelta::decode(&bytes)?)?,
+            }
+        }
+        Ok(std::mem::take(&mut self.pending_events))
+    }
+
+    fn apply_delta(&mut self, delta: WorldDelta) -> RobinResult<()> {
+        let shard = self.shard_mut(delta.shard_id)?;
+        if delta.sequence <= shard.sequence {
+            let event = DistributedWorldEvent::StaleDeltaDropped {
+                shard_id: delta.shard_id,
+                sequence: delta.sequence,
+            };
+            self.pending_events.push(event);
+            return Ok(());
+        }
+
+        {
+            let mut chunk = shard
+                .voxel_data
+                .write()
+                .map_err(|_| RobinError::ThreadError(format!("Shard {} lock poisoned", delta.shard_id)))?;
+            for &(position, voxel_type) in &delta.changes {
+                chunk.set(position, voxel_type);
+            }
+        }
+        shard.sequence = delta.sequence;
+
+        self.pending_events.push(DistributedWorldEvent::DeltaApplied {
+            shard_id: delta.shard_id,
+            changes: delta.changes.len(),
+            source_node: delta.source_node,
+        });
+        Ok(())
+    }
+
+    fn shard_mut(&mut self, shard_id: u32) -> RobinResult<&mut WorldShard> {
+        self.shards
+            .get_mut(&shard_id)
+            .ok_or_else(|| RobinError::NotFound(format!("Shard {} has not been initialized", shard_id)))
+    }
+
+    fn send(
+        peers: &HashMap<String, mpsc::UnboundedSender<NodeMessage>>,
+        node_id: &str,
+        message: NodeMessage,
+    ) -> RobinResult<()> {
+        peers
+            .get(node_id)
+            .ok_or_else(|| RobinError::MultiplayerError(format!("No connection to node '{}'", node_id)))?
+            .send(message)
+            .map_err(|_| RobinError::MultiplayerError(format!("Node '{}' has disconnected", node_id)))
+    }
+}
+
+impl Default for DistributedWorldSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two nodes over a two-shard world: "a" owns shard 0, "b" owns shard 1,
+    /// and each subscribes to the other's shard.
+    fn connected_pair() -> (DistributedWorldSystem, DistributedWorldSystem) {
+        let mut a = DistributedWorldSystem::with_layout("a", [2, 1, 1]);
+        let mut b = DistributedWorldSystem::with_layout("b", [2, 1, 1]);
+        for node in [&mut a, &mut b] {
+            node.initialize().unwrap();
+            node.set_shard_owner(0, "a").unwrap();
+            node.set_shard_owner(1, "b").unwrap();
+        }
+        a.subscribe(0, "b").unwrap();
+        b.subscribe(1, "a").unwrap();
+        a.connect_peer("b", b.sender());
+        b.connect_peer("a", a.sender());
+        (a, b)
+    }
+
+    fn chunk_of(node: &DistributedWorldSystem, shard_id: u32) -> VoxelChunk {
+        node.shards[&shard_id].voxel_data.read().unwrap().clone()
+    }
+
+    #[test]
+    fn test_shard_routing() {
+        let node = DistributedWorldSystem::with_layout("a", [2, 2, 2]);
+        assert_eq!(node.shard_for([0, 0, 0]), Some(0));
+        assert_eq!(node.shard_for([SHARD_SIZE, 0, 0]), Some(1));
+        assert_eq!(node.shard_for([0, SHARD_SIZE, 0]), Some(2));
+        assert_eq!(node.shard_for([0, 0, SHARD_SIZE]), Some(4));
+        assert_eq!(node.shard_for([-1, 0, 0]), None);
+        assert_eq!(node.shard_for([2 * SHARD_SIZE, 0, 0]), None);
+    }
+
+    #[test]
+    fn test_delta_round_trip() {
+        let delta = WorldDelta {
+            shard_id: 3,
+            source_node: "a".to_string(),
+            sequence: 7,
+            changes: vec![([1, 2, 3], VoxelType::Stone), ([4, 5, 6], VoxelType::Air)],
+        };
+        assert_eq!(WorldDelta::decode(&delta.encode().unwrap()).unwrap(), delta);
+        assert!(WorldDelta::decode(&[1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_nodes_converge_on_non_overlapping_edits() {
+        let (mut a, mut b) = connected_pair();
+
+        // "a" edits its own shard and one owned by "b"; "b" edits its own
+        a.apply_edit(VoxelEdit { position: [1, 1, 1], voxel_type: VoxelType::Stone }).unwrap();
+        a.apply_edit(VoxelEdit { position: [SHARD_SIZE + 2, 0, 0], voxel_type: VoxelType::Wood }).unwrap();
+        b.apply_edit(VoxelEdit { position: [SHARD_SIZE + 5, 3, 3], voxel_type: VoxelType::Glass }).unwrap();
+
+        // Forwarded edits need one round trip before their deltas come back
+        for _ in 0..2 {
+            a.update(0.016).unwrap();
+            b.update(0.016).unwrap();
+        }
+
+        for shard_id in 0..2 {
+            assert_eq!(chunk_of(&a, shard_id), chunk_of(&b, shard_id));
+        }
+        assert_eq!(a.get_voxel([1, 1, 1]), Some(VoxelType::Stone));
+        assert_eq!(b.get_voxel([SHARD_SIZE + 2, 0, 0]), Some(VoxelType::Wood));
+        assert_eq!(a.get_voxel([SHARD_SIZE + 5, 3, 3]), Some(VoxelType::Glass));
+    }
+
+    #[test]
+    fn test_stale_deltas_are_ignored() {
+        let (mut a, mut b) = connected_pair();
+        a.apply_edit(VoxelEdit { position: [0, 0, 0], voxel_type: VoxelType::Stone }).unwrap();
+        b.update(0.016).unwrap();
+
+        let replay = WorldDelta {
+            shard_id: 0,
+            source_node: "a".to_string(),
+            sequence: 1,
+            changes: vec![([0, 0, 0], VoxelType::Air)],
+        };
+        a.peers["b"].send(NodeMessage::Delta(replay.encode().unwrap())).unwrap();
+        let events = b.update(0.016).unwrap();
+
+        assert!(matches!(events[..], [DistributedWorldEvent::StaleDeltaDropped { shard_id: 0, sequence: 1 }]));
+        assert_eq!(b.get_voxel([0, 0, 0]), Some(VoxelType::Stone));
+    }
+
+    #[test]
+    fn test_edit_outside_world_is_rejected() {
+        let mut node = DistributedWorldSystem::with_layout("a", [1, 1, 1]);
+        node.initialize().unwrap();
+        let result = node.apply_edit(VoxelEdit { position: [0, -1, 0], voxel_type: VoxelType::Stone });
+        assert!(matches!(result, Err(RobinError::InvalidInput(_))));
+    }
+}
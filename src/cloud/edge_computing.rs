@@ -0,0 +1,323 @@
+// Robin Engine 2.0 - Edge Computing Network
+// Demand prediction and cache pre-warming for edge nodes
+
+use super::{CachedContent, CachedContentType, EdgeNode};
+use crate::engine::error::{RobinError, RobinResult};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+const BYTES_PER_GB: u64 = 1024 * 1024 * 1024;
+
+/// One request for a piece of content, as recorded by an edge node
+#[derive(Debug, Clone)]
+pub struct AccessLogEntry {
+    pub content_id: String,
+    pub timestamp: SystemTime,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentDemandPrediction {
+    pub content_id: String,
+    pub predicted_requests_per_hour: f32,
+}
+
+/// Content available from the origin servers for edge nodes to fetch
+#[derive(Debug, Clone)]
+pub struct OriginAsset {
+    pub content_type: CachedContentType,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub enum EdgeEvent {
+    CachePrewarmed { node_id: String, fetched: usize },
+    ContentEvicted { node_id: String, content_id: String },
+}
+
+#[derive(Debug)]
+pub struct EdgeComputingNetwork {
+    pub origin_catalog: HashMap<String, OriginAsset>,
+    /// Most predictions fetched in a single pre-warm
+    pub prewarm_limit: usize,
+    /// Smoothing factor of the demand moving average; higher reacts faster
+    pub ema_alpha: f32,
+    pending_events: Vec<EdgeEvent>,
+}
+
+impl EdgeComputingNetwork {
+    pub fn new() -> Self {
+        Self {
+            origin_catalog: HashMap::new(),
+            prewarm_limit: 20,
+            ema_alpha: 0.3,
+            pending_events: Vec::new(),
+        }
+    }
+
+    pub fn initialize(&mut self) -> RobinResult<()> {
+        Ok(())
+    }
+
+    pub fn update(&mut self, _delta_time: f32) -> RobinResult<Vec<EdgeEvent>> {
+        Ok(std::mem::take(&mut self.pending_events))
+    }
+
+    pub fn register_origin_content(&mut self, content_id: &str, content_type: CachedContentType, size_bytes: u64) {
+        self.origin_catalog
+            .insert(content_id.to_string(), OriginAsset { content_type, size_bytes });
+    }
+
+    /// Forecasts requests per hour for every content id in the log. Requests
+    /// are bucketed by hour and smoothed with a trend-adjusted exponential
+    /// moving average, extrapolated `horizon_hours` ahead. Sorted by demand,
+    /// highest first.
+    pub fn predict_demand(&self, access_log: &[AccessLogEntry], horizon_hours: u32) -> Vec<ContentDemandPrediction> {
+        let Some(latest) = access_log.iter().map(|entry| entry.timestamp).max() else {
+            return Vec::new();
+        };
+        let hours_before_latest = |timestamp: SystemTime| {
+            (latest.duration_since(timestamp).unwrap_or(Duration::ZERO).as_secs() / 3600) as usize
+        };
+        let history_hours = access_log
+            .iter()
+            .map(|entry| hours_before_latest(entry.timestamp))
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        let mut hourly_counts: HashMap<&str, Vec<f32>> = HashMap::new();
+        for entry in access_log {
+            let counts = hourly_counts
+                .entry(entry.content_id.as_str())
+                .or_insert_with(|| vec![0.0; history_hours]);
+            // Oldest hour first
+            counts[history_hours - 1 - hours_before_latest(entry.timestamp)] += 1.0;
+        }
+
+        let alpha = self.ema_alpha.clamp(0.01, 1.0);
+        let mut predictions: Vec<ContentDemandPrediction> = hourly_counts
+            .into_iter()
+            .map(|(content_id, counts)| {
+                let mut level = counts[0];
+                let mut trend = 0.0;
+                for &count in &counts[1..] {
+                    let previous = level;
+                    level = alpha * count + (1.0 - alpha) * (level + trend);
+                    trend = alpha * (level - previous) + (1.0 - alpha) * trend;
+                }
+                ContentDemandPrediction {
+                    content_id: content_id.to_string(),
+                    predicted_requests_per_hour: (level + trend * horizon_hours as f32).max(0.0),
+                }
+            })
+            .collect();
+
+        predictions.sort_by(|a, b| {
+            b.predicted_requests_per_hour
+                .total_cmp(&a.predicted_requests_per_hour)
+                .then_with(|| a.content_id.cmp(&b.content_id))
+        });
+        predictions
+    }
+
+    /// Fetches the top predicted assets from origin into the node's cache,
+    /// then evicts least recently used entries until the cache fits in
+    /// `capacity.cache_gb`. Assets fetched together share a timestamp, so
+    /// within one pre-warm the lowest-demand asset is evicted first.
+    pub fn prewarm_cache(&mut self, node: &mut EdgeNode, predictions: &[ContentDemandPrediction]) -> RobinResult<()> {
+        let mut ranked: Vec<&ContentDemandPrediction> = predictions.iter().collect();
+        ranked.sort_by(|a, b| b.predicted_requests_per_hour.total_cmp(&a.predicted_requests_per_hour));
+
+        let now = SystemTime::now();
+        let mut fetched = 0;
+        for prediction in ranked.into_iter().take(self.prewarm_limit) {
+            let asset = self.origin_catalog.get(&prediction.content_id).ok_or_else(|| {
+                RobinError::NotFound(format!("Content '{}' is not available from origin", prediction.content_id))
+            })?;
+
+            match node
+                .cached_content
+                .iter_mut()
+                .find(|cached| cached.content_id == prediction.content_id)
+            {
+                Some(cached) => {
+                    cached.cache_time = now;
+                    cached.popularity_score = prediction.predicted_requests_per_hour;
+                }
+                None => {
+                    node.cached_content.push(CachedContent {
+                        content_id: prediction.content_id.clone(),
+                        content_type: asset.content_type,
+                        size_bytes: asset.size_bytes,
+                        cache_time: now,
+                        access_count: 0,
+                        popularity_score: prediction.predicted_requests_per_hour,
+                    });
+                    fetched += 1;
+                }
+            }
+        }
+
+        let capacity_bytes = node.capacity.cache_gb as u64 * BYTES_PER_GB;
+        while node.cached_content.iter().map(|cached| cached.size_bytes).sum::<u64>() > capacity_bytes {
+            let victim = node
+                .cached_content
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| {
+                    a.cache_time
+                        .cmp(&b.cache_time)
+                        .then_with(|| a.popularity_score.total_cmp(&b.popularity_score))
+                })
+                .map(|(index, _)| index)
+                .expect("an over-capacity cache holds at least one entry");
+            let evicted = node.cached_content.remove(victim);
+            self.pending_events.push(EdgeEvent::ContentEvicted {
+                node_id: node.node_id.clone(),
+                content_id: evicted.content_id,
+            });
+        }
+
+        self.pending_events.push(EdgeEvent::CachePrewarmed {
+            node_id: node.node_id.clone(),
+            fetched,
+        });
+        Ok(())
+    }
+}
+
+impl Default for EdgeComputingNetwork {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cloud::{EdgeCapacity, EdgeNodeType, GeographicLocation, LocalProcessingCapabilities};
+
+    fn edge_node(cache_gb: u16) -> EdgeNode {
+        EdgeNode {
+            node_id: "test-edge-0".to_string(),
+            location: GeographicLocation {
+                continent: "".to_string(),
+                country: "".to_string(),
+                region: "test".to_string(),
+                timezone: "".to_string(),
+                coordinates: (0.0, 0.0),
+                regulatory_zone: "".to_string(),
+            },
+            node_type: EdgeNodeType::CacheOnly,
+            capacity: EdgeCapacity {
+                cpu_cores: 4,
+                memory_gb: 16,
+                storage_gb: 100,
+                cache_gb,
+                concurrent_connections: 100,
+                bandwidth_mbps: 100,
+            },
+            cached_content: Vec::new(),
+            local_processing: LocalProcessingCapabilities {
+                ai_inference: false,
+                physics_simulation: false,
+                content_compression: false,
+                real_time_translation: false,
+                voice_processing: false,
+                image_processing: false,
+                collaborative_filtering: false,
+            },
+            connected_users: 0,
+        }
+    }
+
+    fn requests(content_id: &str, hours_ago: u64, count: usize, now: SystemTime) -> Vec<AccessLogEntry> {
+        (0..count)
+            .map(|_| AccessLogEntry {
+                content_id: content_id.to_string(),
+                timestamp: now - Duration::from_secs(hours_ago * 3600),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_predict_demand_follows_recent_trend() {
+        let network = EdgeComputingNetwork::new();
+        let now = SystemTime::now();
+        let mut log = Vec::new();
+        // "rising" grows each hour, "fading" shrinks, "steady" stays flat
+        for hours_ago in 0..6 {
+            log.extend(requests("rising", hours_ago, 2 + 4 * (5 - hours_ago as usize), now));
+            log.extend(requests("fading", hours_ago, 2 + 4 * hours_ago as usize, now));
+            log.extend(requests("steady", hours_ago, 10, now));
+        }
+
+        let predictions = network.predict_demand(&log, 2);
+        let ids: Vec<&str> = predictions.iter().map(|p| p.content_id.as_str()).collect();
+        assert_eq!(ids, ["rising", "steady", "fading"]);
+        assert!((predictions[1].predicted_requests_per_hour - 10.0).abs() < 0.01);
+        assert!(network.predict_demand(&[], 2).is_empty());
+    }
+
+    #[test]
+    fn test_prewarm_keeps_top_predictions_within_capacity() {
+        let mut network = EdgeComputingNetwork::new();
+        for id in ["a", "b", "c", "d"] {
+            network.register_origin_content(id, CachedContentType::AssetBundles, BYTES_PER_GB);
+        }
+        let predictions: Vec<ContentDemandPrediction> = [("a", 40.0), ("b", 10.0), ("c", 30.0), ("d", 20.0)]
+            .iter()
+            .map(|&(id, rate)| ContentDemandPrediction {
+                content_id: id.to_string(),
+                predicted_requests_per_hour: rate,
+            })
+            .collect();
+
+        let mut node = edge_node(3);
+        network.prewarm_cache(&mut node, &predictions).unwrap();
+
+        let mut cached: Vec<&str> = node.cached_content.iter().map(|c| c.content_id.as_str()).collect();
+        cached.sort();
+        assert_eq!(cached, ["a", "c", "d"]);
+
+        let events = network.update(0.0).unwrap();
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, EdgeEvent::ContentEvicted { content_id, .. } if content_id == "b")));
+    }
+
+    #[test]
+    fn test_prewarm_evicts_least_recently_used_first() {
+        let mut network = EdgeComputingNetwork::new();
+        network.register_origin_content("new", CachedContentType::WorldData, BYTES_PER_GB);
+
+        let mut node = edge_node(1);
+        node.cached_content.push(CachedContent {
+            content_id: "old".to_string(),
+            content_type: CachedContentType::WorldData,
+            size_bytes: BYTES_PER_GB,
+            cache_time: SystemTime::now() - Duration::from_secs(3600),
+            access_count: 50,
+            popularity_score: 100.0,
+        });
+        let predictions = [ContentDemandPrediction {
+            content_id: "new".to_string(),
+            predicted_requests_per_hour: 5.0,
+        }];
+        network.prewarm_cache(&mut node, &predictions).unwrap();
+
+        assert_eq!(node.cached_content.len(), 1);
+        assert_eq!(node.cached_content[0].content_id, "new");
+    }
+
+    #[test]
+    fn test_prewarm_unknown_content_fails() {
+        let mut network = EdgeComputingNetwork::new();
+        let predictions = [ContentDemandPrediction {
+            content_id: "missing".to_string(),
+            predicted_requests_per_hour: 1.0,
+        }];
+        let result = network.prewarm_cache(&mut edge_node(1), &predictions);
+        assert!(matches!(result, Err(RobinError::NotFound(_))));
+    }
+}
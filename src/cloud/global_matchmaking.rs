@@ -0,0 +1,370 @@
+// Robin Engine 2.0 - Global Matchmaking Service
+// Classroom and group formation from player profiles
+
+use crate::engine::ai_game::{CollaborationStyle, PlayerProfile, PrimaryPlayStyle};
+use crate::engine::error::RobinResult;
+
+/// How students are distributed across groups
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchingStrategy {
+    /// Every group gets a similar mean skill
+    SkillBalanced,
+    /// Each group mixes as many play styles as possible
+    LearningStyleDiverse,
+    /// Students join the group whose collaboration styles suit them best
+    CollaborationCompatible,
+}
+
+#[derive(Debug, Clone)]
+pub enum MatchmakingEvent {
+    ClassroomFormed { strategy: MatchingStrategy, groups: usize, quality: f32 },
+}
+
+#[derive(Debug, Default)]
+pub struct GlobalMatchmakingService {
+    pending_events: Vec<MatchmakingEvent>,
+}
+
+impl GlobalMatchmakingService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn initialize(&mut self) -> RobinResult<()> {
+        Ok(())
+    }
+
+    pub fn update(&mut self, _delta_time: f32) -> RobinResult<Vec<MatchmakingEvent>> {
+        Ok(std::mem::take(&mut self.pending_events))
+    }
+
+    /// Splits students into `ceil(len / target_size)` groups of at most
+    /// `target_size` members using the given strategy.
+    pub fn form_classroom(
+        &mut self,
+        students: Vec<PlayerProfile>,
+        target_size: usize,
+        strategy: MatchingStrategy,
+    ) -> Vec<Vec<PlayerProfile>> {
+        if students.is_empty() {
+            return Vec::new();
+        }
+        let target_size = target_size.max(1);
+        let group_count = students.len().div_ceil(target_size);
+        let mut groups: Vec<Vec<PlayerProfile>> = vec![Vec::new(); group_count];
+
+        match strategy {
+            MatchingStrategy::SkillBalanced => {
+                // Greedy bin packing: strongest students first, each into the
+                // open group with the lowest total skill so far
+                let mut students = students;
+                students.sort_by(|a, b| skill_of(b).total_cmp(&skill_of(a)));
+                let mut totals = vec![0.0f32; group_count];
+                for student in students {
+                    let group = (0..group_count)
+                        .filter(|&g| groups[g].len() < target_size)
+                        .min_by(|&a, &b| totals[a].total_cmp(&totals[b]))
+                        .expect("group_count * target_size covers every student");
+                    totals[group] += skill_of(&student);
+                    groups[group].push(student);
+                }
+            }
+            MatchingStrategy::LearningStyleDiverse => {
+                // Interleave students by play style, then deal them out so
+                // neighbouring (different-style) students land in different groups
+                let mut by_style: Vec<Vec<PlayerProfile>> = vec![Vec::new(); PLAY_STYLE_COUNT];
+                for student in students {
+                    by_style[style_index(&student.play_style.primary_style)].push(student);
+                }
+                let mut interleaved = Vec::new();
+                while by_style.iter().any(|bucket| !bucket.is_empty()) {
+                    for bucket in &mut by_style {
+                        if let Some(student) = bucket.pop() {
+                            interleaved.push(student);
+                        }
+                    }
+                }
+                for (i, student) in interleaved.into_iter().enumerate() {
+                    groups[i % group_count].push(student);
+                }
+            }
+            MatchingStrategy::CollaborationCompatible => {
+                for student in students {
+                    let group = (0..group_count)
+                        .filter(|&g| groups[g].len() < target_size)
+                        .max_by(|&a, &b| {
+                            let fit = |g: usize| group_fit(&student, &groups[g]);
+                            // Prefer emptier groups on ties so members spread out
+                            fit(a)
+                                .total_cmp(&fit(b))
+                                .then_with(|| groups[b].len().cmp(&groups[a].len()))
+                        })
+                        .expect("group_count * target_size covers every student");
+                    groups[group].push(student);
+                }
+            }
+        }
+
+        self.pending_events.push(MatchmakingEvent::ClassroomFormed {
+            strategy,
+            groups: groups.len(),
+            quality: ClassroomQuality::evaluate(&groups),
+        });
+        groups
+    }
+}
+
+/// Scores a grouping in [0, 1]
+pub struct ClassroomQuality;
+
+impl ClassroomQuality {
+    /// Equal-weighted mean of skill balance across groups, play-style
+    /// diversity within groups and pairwise collaboration compatibility
+    pub fn evaluate(groups: &[Vec<PlayerProfile>]) -> f32 {
+        let groups: Vec<&Vec<PlayerProfile>> = groups.iter().filter(|group| !group.is_empty()).collect();
+        if groups.is_empty() {
+            return 0.0;
+        }
+
+        let means: Vec<f32> = groups
+            .iter()
+            .map(|group| group.iter().map(skill_of).sum::<f32>() / group.len() as f32)
+            .collect();
+        // Skills lie in [0, 1], so a spread of 0.5 is as unbalanced as it gets
+        let balance = 1.0 - (variance(&means).sqrt() / 0.5).min(1.0);
+
+        let diversity = groups
+            .iter()
+            .map(|group| {
+                let mut seen = [false; PLAY_STYLE_COUNT];
+                for student in group.iter() {
+                    seen[style_index(&student.play_style.primary_style)] = true;
+                }
+                let distinct = seen.iter().filter(|&&s| s).count();
+                distinct as f32 / group.len().min(PLAY_STYLE_COUNT) as f32
+            })
+            .sum::<f32>()
+            / groups.len() as f32;
+
+        let compatibility = groups
+            .iter()
+            .map(|group| {
+                let mut total = 0.0;
+                let mut pairs = 0;
+                for (i, a) in group.iter().enumerate() {
+                    for b in &group[i + 1..] {
+                        total += compatibility(
+                            &a.social_preferences.collaboration_style,
+                            &b.social_preferences.collaboration_style,
+                        );
+                        pairs += 1;
+                    }
+                }
+                if pairs == 0 {
+                    1.0
+                } else {
+                    total / pairs as f32
+                }
+            })
+            .sum::<f32>()
+            / groups.len() as f32;
+
+        (balance + diversity + compatibility) / 3.0
+    }
+}
+
+const PLAY_STYLE_COUNT: usize = 7;
+
+fn style_index(style: &PrimaryPlayStyle) -> usize {
+    match style {
+        PrimaryPlayStyle::Builder => 0,
+        PrimaryPlayStyle::Explorer => 1,
+        PrimaryPlayStyle::Engineer => 2,
+        PrimaryPlayStyle::Artist => 3,
+        PrimaryPlayStyle::Collaborator => 4,
+        PrimaryPlayStyle::Competitor => 5,
+        PrimaryPlayStyle::Experimenter => 6,
+    }
+}
+
+/// Mean current level across the student's skills, 0 when none are assessed
+fn skill_of(student: &PlayerProfile) -> f32 {
+    if student.skill_levels.is_empty() {
+        return 0.0;
+    }
+    student.skill_levels.values().map(|skill| skill.current_level).sum::<f32>() / student.skill_levels.len() as f32
+}
+
+/// How well two collaboration styles work together, in [0, 1]
+fn compatibility(a: &CollaborationStyle, b: &CollaborationStyle) -> f32 {
+    use CollaborationStyle::*;
+    match (a, b) {
+        (Mentoring, Learning) | (Learning, Mentoring) => 1.0,
+        (Cooperative, Cooperative) | (Competitive, Competitive) => 0.9,
+        (Cooperative, Mentoring | Learning) | (Mentoring | Learning, Cooperative) => 0.8,
+        (Mentoring, Mentoring) | (Learning, Learning) => 0.6,
+        (Independent, Independent) => 0.5,
+        (Competitive, _) | (_, Competitive) => 0.3,
+        (Independent, _) | (_, Independent) => 0.2,
+    }
+}
+
+/// Fit of an empty group: joining an existing group has to beat this, so
+/// lukewarm matches start a new group while one is still free
+const NEW_GROUP_FIT: f32 = 0.7;
+
+/// Mean compatibility of `student` with the group's members
+fn group_fit(student: &PlayerProfile, group: &[PlayerProfile]) -> f32 {
+    if group.is_empty() {
+        return NEW_GROUP_FIT;
+    }
+    group
+        .iter()
+        .map(|member| {
+            compatibility(
+                &student.social_preferences.collaboration_style,
+                &member.social_preferences.collaboration_style,
+            )
+        })
+        .sum::<f32>()
+        / group.len() as f32
+}
+
+fn variance(values: &[f32]) -> f32 {
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::ai_game::SkillLevel;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+
+    fn student(id: usize, skill: f32, style: PrimaryPlayStyle, collaboration: CollaborationStyle) -> PlayerProfile {
+        let mut profile = PlayerProfile::default();
+        profile.player_id = format!("student-{}", id);
+        profile.skill_levels.insert(
+            "building".to_string(),
+            SkillLevel {
+                current_level: skill,
+                progression_rate: 0.0,
+                consistency: 0.0,
+                peak_performance: skill,
+                practice_time: 0.0,
+                last_assessment: chrono::Utc::now(),
+                stability: 1.0,
+                last_reviewed: std::time::SystemTime::now(),
+            },
+        );
+        profile.play_style.primary_style = style;
+        profile.social_preferences.collaboration_style = collaboration;
+        profile
+    }
+
+    fn cohort(size: usize, seed: u64) -> Vec<PlayerProfile> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..size)
+            .map(|id| {
+                let style = match rng.gen_range(0..PLAY_STYLE_COUNT) {
+                    0 => PrimaryPlayStyle::Builder,
+                    1 => PrimaryPlayStyle::Explorer,
+                    2 => PrimaryPlayStyle::Engineer,
+                    3 => PrimaryPlayStyle::Artist,
+                    4 => PrimaryPlayStyle::Collaborator,
+                    5 => PrimaryPlayStyle::Competitor,
+                    _ => PrimaryPlayStyle::Experimenter,
+                };
+                let collaboration = match rng.gen_range(0..5) {
+                    0 => CollaborationStyle::Independent,
+                    1 => CollaborationStyle::Cooperative,
+                    2 => CollaborationStyle::Competitive,
+                    3 => CollaborationStyle::Mentoring,
+                    _ => CollaborationStyle::Learning,
+                };
+                student(id, rng.gen_range(0.0..1.0), style, collaboration)
+            })
+            .collect()
+    }
+
+    fn group_mean_variance(groups: &[Vec<PlayerProfile>]) -> f32 {
+        let means: Vec<f32> = groups
+            .iter()
+            .map(|group| group.iter().map(skill_of).sum::<f32>() / group.len() as f32)
+            .collect();
+        variance(&means)
+    }
+
+    #[test]
+    fn test_skill_balanced_beats_random_assignment() {
+        let mut service = GlobalMatchmakingService::new();
+        let students = cohort(30, 7);
+
+        let balanced = service.form_classroom(students.clone(), 5, MatchingStrategy::SkillBalanced);
+        assert_eq!(balanced.len(), 6);
+        assert!(balanced.iter().all(|group| group.len() == 5));
+
+        let mut shuffled = students;
+        shuffled.shuffle(&mut StdRng::seed_from_u64(11));
+        let random: Vec<Vec<PlayerProfile>> = shuffled.chunks(5).map(|chunk| chunk.to_vec()).collect();
+
+        assert!(group_mean_variance(&balanced) < group_mean_variance(&random));
+    }
+
+    #[test]
+    fn test_learning_style_diverse_spreads_styles() {
+        let mut service = GlobalMatchmakingService::new();
+        // Three builders and three artists into three pairs
+        let students: Vec<PlayerProfile> = (0..6)
+            .map(|id| {
+                let style = if id < 3 { PrimaryPlayStyle::Builder } else { PrimaryPlayStyle::Artist };
+                student(id, 0.5, style, CollaborationStyle::Cooperative)
+            })
+            .collect();
+
+        let groups = service.form_classroom(students, 2, MatchingStrategy::LearningStyleDiverse);
+        assert_eq!(groups.len(), 3);
+        for group in &groups {
+            assert_ne!(group[0].play_style.primary_style, group[1].play_style.primary_style);
+        }
+    }
+
+    #[test]
+    fn test_collaboration_compatible_pairs_mentors_with_learners() {
+        let mut service = GlobalMatchmakingService::new();
+        let students = vec![
+            student(0, 0.5, PrimaryPlayStyle::Builder, CollaborationStyle::Mentoring),
+            student(1, 0.5, PrimaryPlayStyle::Builder, CollaborationStyle::Mentoring),
+            student(2, 0.5, PrimaryPlayStyle::Builder, CollaborationStyle::Learning),
+            student(3, 0.5, PrimaryPlayStyle::Builder, CollaborationStyle::Learning),
+        ];
+
+        let groups = service.form_classroom(students, 2, MatchingStrategy::CollaborationCompatible);
+        for group in &groups {
+            let styles: Vec<f32> = group
+                .windows(2)
+                .map(|pair| {
+                    compatibility(
+                        &pair[0].social_preferences.collaboration_style,
+                        &pair[1].social_preferences.collaboration_style,
+                    )
+                })
+                .collect();
+            assert_eq!(styles, [1.0]);
+        }
+    }
+
+    #[test]
+    fn test_classroom_quality_range() {
+        let mut service = GlobalMatchmakingService::new();
+        let groups = service.form_classroom(cohort(30, 3), 6, MatchingStrategy::SkillBalanced);
+        let quality = ClassroomQuality::evaluate(&groups);
+        assert!((0.0..=1.0).contains(&quality));
+        assert_eq!(ClassroomQuality::evaluate(&[]), 0.0);
+
+        let events = service.update(0.0).unwrap();
+        assert!(matches!(events[..], [MatchmakingEvent::ClassroomFormed { groups: 5, .. }]));
+    }
+}
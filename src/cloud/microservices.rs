@@ -0,0 +1,300 @@
+// Robin Engine 2.0 - Microservices Orchestrator
+// Routes calls to platform services behind per-service circuit breakers
+
+use super::DeployedService;
+use crate::engine::error::{RobinError, RobinResult};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls flow normally
+    Closed,
+    /// Too many failures; calls are rejected until the timeout passes
+    Open,
+    /// Timeout passed; the next call is a probe that decides whether to close
+    HalfOpen,
+}
+
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    pub state: CircuitState,
+    /// Consecutive failures while closed
+    pub failure_count: u32,
+    pub success_count: u32,
+    pub last_state_change: Instant,
+    pub threshold: u32,
+    pub timeout: Duration,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, timeout: Duration) -> Self {
+        Self {
+            state: CircuitState::Closed,
+            failure_count: 0,
+            success_count: 0,
+            last_state_change: Instant::now(),
+            threshold: threshold.max(1),
+            timeout,
+        }
+    }
+
+    /// Whether a call may go through. An open circuit whose timeout has
+    /// elapsed moves to half-open and lets one probe through.
+    pub fn allow_request(&mut self) -> bool {
+        match self.state {
+            CircuitState::Closed | CircuitState::HalfOpen => true,
+            CircuitState::Open => {
+                if self.last_state_change.elapsed() >= self.timeout {
+                    self.transition(CircuitState::HalfOpen);
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    pub fn record_success(&mut self) {
+        self.success_count += 1;
+        self.failure_count = 0;
+        if self.state == CircuitState::HalfOpen {
+            self.transition(CircuitState::Closed);
+        }
+    }
+
+    pub fn record_failure(&mut self) {
+        self.failure_count += 1;
+        match self.state {
+            CircuitState::HalfOpen => self.transition(CircuitState::Open),
+            CircuitState::Closed if self.failure_count >= self.threshold => self.transition(CircuitState::Open),
+            _ => {}
+        }
+    }
+
+    fn transition(&mut self, state: CircuitState) {
+        self.state = state;
+        self.last_state_change = Instant::now();
+        if state == CircuitState::Closed {
+            self.failure_count = 0;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ServiceRequest {
+    pub operation: String,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ServiceResponse {
+    pub payload: Vec<u8>,
+    pub latency: Duration,
+}
+
+pub type ServiceHandler = Box<dyn Fn(&ServiceRequest) -> RobinResult<ServiceResponse> + Send + Sync>;
+
+pub struct MicroservicesOrchestrator {
+    /// Consecutive failures that open a service's circuit
+    pub failure_threshold: u32,
+    /// How long an open circuit rejects calls before probing again
+    pub open_timeout: Duration,
+    handlers: HashMap<DeployedService, ServiceHandler>,
+    breakers: HashMap<DeployedService, CircuitBreaker>,
+}
+
+impl MicroservicesOrchestrator {
+    pub fn new() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_timeout: Duration::from_secs(30),
+            handlers: HashMap::new(),
+            breakers: HashMap::new(),
+        }
+    }
+
+    pub fn initialize(&mut self) -> RobinResult<()> {
+        Ok(())
+    }
+
+    pub fn register_service(&mut self, service: DeployedService, handler: ServiceHandler) {
+        self.handlers.insert(service, handler);
+        self.breakers
+            .insert(service, CircuitBreaker::new(self.failure_threshold, self.open_timeout));
+    }
+
+    pub fn circuit_breaker(&self, service: DeployedService) -> Option<&CircuitBreaker> {
+        self.breakers.get(&service)
+    }
+
+    /// Forwards the request to the service unless its circuit is open, and
+    /// records the outcome on the circuit.
+    pub fn call_service(&mut self, service: DeployedService, request: ServiceRequest) -> RobinResult<ServiceResponse> {
+        let handler = self
+            .handlers
+            .get(&service)
+            .ok_or_else(|| RobinError::NotFound(format!("No handler registered for {:?}", service)))?;
+        let breaker = self
+            .breakers
+            .entry(service)
+            .or_insert_with(|| CircuitBreaker::new(self.failure_threshold, self.open_timeout));
+
+        if !breaker.allow_request() {
+            return Err(RobinError::ServiceUnavailable(format!("{:?} circuit is open", service)));
+        }
+
+        let start = Instant::now();
+        match handler(&request) {
+            Ok(mut response) => {
+                breaker.record_success();
+                response.latency = start.elapsed();
+                Ok(response)
+            }
+            Err(error) => {
+                breaker.record_failure();
+                Err(error)
+            }
+        }
+    }
+}
+
+impl Default for MicroservicesOrchestrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for MicroservicesOrchestrator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MicroservicesOrchestrator")
+            .field("failure_threshold", &self.failure_threshold)
+            .field("open_timeout", &self.open_timeout)
+            .field("services", &self.handlers.keys().collect::<Vec<_>>())
+            .field("breakers", &self.breakers)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    /// Orchestrator with one Analytics service that fails while `healthy` is false
+    fn orchestrator(healthy: Arc<AtomicBool>, calls: Arc<AtomicU32>) -> MicroservicesOrchestrator {
+        let mut orchestrator = MicroservicesOrchestrator::new();
+        orchestrator.failure_threshold = 3;
+        orchestrator.register_service(
+            DeployedService::Analytics,
+            Box::new(move |_request| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                if healthy.load(Ordering::SeqCst) {
+                    Ok(ServiceResponse::default())
+                } else {
+                    Err(RobinError::NetworkError {
+                        operation: "call".to_string(),
+                        endpoint: "analytics".to_string(),
+                        reason: "connection refused".to_string(),
+                    })
+                }
+            }),
+        );
+        orchestrator
+    }
+
+    fn state(orchestrator: &MicroservicesOrchestrator) -> CircuitState {
+        orchestrator.circuit_breaker(DeployedService::Analytics).unwrap().state
+    }
+
+    /// Pretends the open timeout has already elapsed
+    fn expire_timeout(orchestrator: &mut MicroservicesOrchestrator) {
+        let breaker = orchestrator.breakers.get_mut(&DeployedService::Analytics).unwrap();
+        breaker.last_state_change = Instant::now() - breaker.timeout;
+    }
+
+    #[test]
+    fn test_closed_to_open_after_threshold() {
+        let healthy = Arc::new(AtomicBool::new(false));
+        let calls = Arc::new(AtomicU32::new(0));
+        let mut orchestrator = orchestrator(healthy, calls.clone());
+
+        for _ in 0..2 {
+            assert!(orchestrator.call_service(DeployedService::Analytics, ServiceRequest::default()).is_err());
+            assert_eq!(state(&orchestrator), CircuitState::Closed);
+        }
+        assert!(orchestrator.call_service(DeployedService::Analytics, ServiceRequest::default()).is_err());
+        assert_eq!(state(&orchestrator), CircuitState::Open);
+
+        // Open circuits reject without reaching the service
+        let result = orchestrator.call_service(DeployedService::Analytics, ServiceRequest::default());
+        assert!(matches!(result, Err(RobinError::ServiceUnavailable(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_successes_reset_failure_count() {
+        let healthy = Arc::new(AtomicBool::new(false));
+        let mut orchestrator = orchestrator(healthy.clone(), Arc::new(AtomicU32::new(0)));
+
+        for _ in 0..2 {
+            orchestrator.call_service(DeployedService::Analytics, ServiceRequest::default()).ok();
+        }
+        healthy.store(true, Ordering::SeqCst);
+        orchestrator.call_service(DeployedService::Analytics, ServiceRequest::default()).unwrap();
+        healthy.store(false, Ordering::SeqCst);
+        for _ in 0..2 {
+            orchestrator.call_service(DeployedService::Analytics, ServiceRequest::default()).ok();
+        }
+
+        assert_eq!(state(&orchestrator), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_half_open_probe_success_closes() {
+        let healthy = Arc::new(AtomicBool::new(false));
+        let mut orchestrator = orchestrator(healthy.clone(), Arc::new(AtomicU32::new(0)));
+        for _ in 0..3 {
+            orchestrator.call_service(DeployedService::Analytics, ServiceRequest::default()).ok();
+        }
+        assert_eq!(state(&orchestrator), CircuitState::Open);
+
+        expire_timeout(&mut orchestrator);
+        let breaker = orchestrator.breakers.get_mut(&DeployedService::Analytics).unwrap();
+        assert!(breaker.allow_request());
+        assert_eq!(breaker.state, CircuitState::HalfOpen);
+
+        healthy.store(true, Ordering::SeqCst);
+        orchestrator.call_service(DeployedService::Analytics, ServiceRequest::default()).unwrap();
+        assert_eq!(state(&orchestrator), CircuitState::Closed);
+        assert_eq!(orchestrator.circuit_breaker(DeployedService::Analytics).unwrap().failure_count, 0);
+    }
+
+    #[test]
+    fn test_half_open_probe_failure_reopens() {
+        let healthy = Arc::new(AtomicBool::new(false));
+        let calls = Arc::new(AtomicU32::new(0));
+        let mut orchestrator = orchestrator(healthy, calls.clone());
+        for _ in 0..3 {
+            orchestrator.call_service(DeployedService::Analytics, ServiceRequest::default()).ok();
+        }
+
+        expire_timeout(&mut orchestrator);
+        assert!(orchestrator.call_service(DeployedService::Analytics, ServiceRequest::default()).is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        assert_eq!(state(&orchestrator), CircuitState::Open);
+
+        // The timeout restarts, so the next call is rejected again
+        let result = orchestrator.call_service(DeployedService::Analytics, ServiceRequest::default());
+        assert!(matches!(result, Err(RobinError::ServiceUnavailable(_))));
+    }
+
+    #[test]
+    fn test_unregistered_service() {
+        let mut orchestrator = MicroservicesOrchestrator::new();
+        let result = orchestrator.call_service(DeployedService::VoiceChat, ServiceRequest::default());
+        assert!(matches!(result, Err(RobinError::NotFound(_))));
+    }
+}
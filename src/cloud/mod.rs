@@ -1,7 +1,7 @@
 // Robin Engine 2.0 - Cloud-Native Architecture
 // Phase 6: Global Platform & Scalable Infrastructure
 
-use crate::engine::{RobinResult, RobinError};
+use crate::engine::error::{RobinResult, RobinError};
 use nalgebra::{Vector3, Matrix4};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
@@ -11,7 +11,9 @@ pub mod edge_computing;
 pub mod global_matchmaking;
 pub mod content_delivery;
 pub mod analytics_pipeline;
+pub mod compliance;
 pub mod microservices;
+pub mod simulation;
 
 /// Cloud-native platform manager for global deployment
 #[derive(Debug)]
@@ -72,7 +74,7 @@ pub struct DataCenterCapacity {
     pub worlds_capacity: u32,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DeployedService {
     WorldHosting,           // Distributed world simulation
     UserAuthentication,     // User management and auth
@@ -168,6 +170,7 @@ pub struct RegulatoryCompliance {
     pub educational_compliance: Vec<EducationalCompliance>,
     pub content_restrictions: Vec<ContentRestriction>,
     pub audit_requirements: AuditRequirements,
+    pub data_policy: compliance::RegionDataPolicy,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -309,6 +312,7 @@ pub enum TranslationQualityLevel {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RegionPerformanceMetrics {
     pub average_latency_ms: f32,
+    pub latency_p95_ms: f32,
     pub uptime_percentage: f32,
     pub concurrent_users: u32,
     pub data_transfer_volume_gb: f32,
@@ -327,6 +331,24 @@ pub struct EducationalOutcomeMetrics {
     pub skill_development_metrics: HashMap<String, f32>,
 }
 
+/// Service levels a region has committed to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceLevelAgreement {
+    pub uptime_target_percent: f32,
+    pub latency_p95_target_ms: f32,
+    pub error_rate_ceiling_percent: f32,
+}
+
+/// How a region's metrics measure up against its SLA
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SLAReport {
+    pub uptime_compliant: bool,
+    pub latency_compliant: bool,
+    pub error_rate_compliant: bool,
+    pub overall_compliant: bool,
+    pub penalty_credits: f32, // Percentage of the monthly bill owed back
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectivityProfile {
     pub primary_connectivity: ConnectivityType,
@@ -478,6 +500,7 @@ pub struct GlobalConfiguration {
     pub monitoring_configuration: MonitoringConfiguration,
     pub auto_scaling: AutoScalingConfiguration,
     pub content_synchronization: ContentSynchronizationConfiguration,
+    pub service_level_agreement: ServiceLevelAgreement,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -1302,6 +1325,7 @@ impl CloudPlatformManager {
         Ok(deployment_status)
     }
 
+    #[tracing::instrument(skip(self))]
     pub fn update(&mut self, delta_time: f32) -> RobinResult<Vec<CloudEvent>> {
         let mut events = Vec::new();
 
@@ -1341,9 +1365,33 @@ impl CloudPlatformManager {
             global_latency_p95_ms: self.calculate_global_latency_p95(),
             uptime_percentage: self.calculate_global_uptime(),
             cost_efficiency_score: self.calculate_cost_efficiency(),
+            sla_reports: self.deployment_regions.iter()
+                .map(|(region_id, region)| {
+                    let sla = &self.global_configuration.service_level_agreement;
+                    (region_id.clone(), region.performance_metrics.compute_sla_compliance(sla))
+                })
+                .collect(),
         }
     }
 
+    /// Region that should serve a user at `location` (latitude, longitude)
+    /// under the configured load balancing algorithm. Algorithms that don't
+    /// depend on where the user is fall back to geographic proximity.
+    pub fn route_user(&self, location: (f64, f64)) -> Option<String> {
+        let regions = self.deployment_regions.iter();
+        let selected = match self.global_configuration.load_balancing.algorithm {
+            LoadBalancingAlgorithm::LeastResponseTime => regions.min_by(|(_, a), (_, b)| {
+                a.performance_metrics.average_latency_ms
+                    .total_cmp(&b.performance_metrics.average_latency_ms)
+            }),
+            _ => regions.min_by(|(_, a), (_, b)| {
+                great_circle_distance_km(location, a.geographic_location.coordinates)
+                    .total_cmp(&great_circle_distance_km(location, b.geographic_location.coordinates))
+            }),
+        };
+        selected.map(|(region_id, _)| region_id.clone())
+    }
+
     // Private helper methods
 
     fn setup_global_regions(&mut self) -> RobinResult<()> {
@@ -1388,6 +1436,16 @@ impl CloudPlatformManager {
     }
 
     fn deploy_to_region(&self, region_id: &str, region: &DeploymentRegion) -> RobinResult<RegionDeploymentDetails> {
+        let violations = compliance::ComplianceChecker::validate_region(region);
+        if compliance::ComplianceChecker::blocks_deployment(&violations) {
+            let reasons: Vec<String> = violations.iter()
+                .map(|v| format!("{:?}: {}", v.regulation, v.description))
+                .collect();
+            return Err(RobinError::DeploymentError(format!(
+                "Region {} fails compliance checks: {}", region_id, reasons.join("; ")
+            )));
+        }
+
         let start_time = std::time::Instant::now();
         let mut services_deployed = 0;
 
@@ -1622,6 +1680,17 @@ impl CloudPlatformManager {
             ],
             content_restrictions: vec![],
             audit_requirements: AuditRequirements::default(),
+            data_policy: compliance::RegionDataPolicy {
+                data_collection: compliance::DataCollectionLevel::Standard,
+                anonymization_level: compliance::AnonymizationLevel::Pseudonymized,
+                minimum_student_age: 5,
+                parental_controls: Some(compliance::ParentalControls {
+                    verifiable_consent: true,
+                    activity_reports: true,
+                }),
+                share_with_researchers: false,
+                research_consent_obtained: false,
+            },
         }
     }
 
@@ -1721,6 +1790,65 @@ impl CloudPlatformManager {
     }
 }
 
+/// Haversine distance in kilometres between two (latitude, longitude) points
+pub fn great_circle_distance_km(a: (f64, f64), b: (f64, f64)) -> f64 {
+    const EARTH_RADIUS_KM: f64 = 6371.0;
+    let (lat_a, lat_b) = (a.0.to_radians(), b.0.to_radians());
+    let d_lat = lat_b - lat_a;
+    let d_lon = (b.1 - a.1).to_radians();
+    let h = (d_lat / 2.0).sin().powi(2) + lat_a.cos() * lat_b.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+/// Credit owed, as a percentage of the monthly bill, for each time a metric
+/// misses its target by the full allowance the SLA gives it
+pub const SLA_CREDIT_PER_BREACH_RATIO: f32 = 10.0;
+/// Most any one metric can add to the penalty credits
+pub const SLA_MAX_CREDIT_PER_METRIC: f32 = 25.0;
+
+impl RegionPerformanceMetrics {
+    /// Checks each metric against `sla`. A metric exactly on its target is
+    /// compliant. Each breach earns credits in proportion to how far past
+    /// the target it is, relative to what the target allows: the downtime
+    /// budget for uptime, and the target itself for latency and error rate.
+    pub fn compute_sla_compliance(&self, sla: &ServiceLevelAgreement) -> SLAReport {
+        let uptime_compliant = self.uptime_percentage >= sla.uptime_target_percent;
+        let latency_compliant = self.latency_p95_ms <= sla.latency_p95_target_ms;
+        let error_rate_compliant = self.error_rate_percentage <= sla.error_rate_ceiling_percent;
+
+        let penalty_credits = sla_breach_credits(
+            sla.uptime_target_percent - self.uptime_percentage,
+            100.0 - sla.uptime_target_percent,
+        ) + sla_breach_credits(
+            self.latency_p95_ms - sla.latency_p95_target_ms,
+            sla.latency_p95_target_ms,
+        ) + sla_breach_credits(
+            self.error_rate_percentage - sla.error_rate_ceiling_percent,
+            sla.error_rate_ceiling_percent,
+        );
+
+        SLAReport {
+            uptime_compliant,
+            latency_compliant,
+            error_rate_compliant,
+            overall_compliant: uptime_compliant && latency_compliant && error_rate_compliant,
+            penalty_credits,
+        }
+    }
+}
+
+/// Credits for a metric that is `excess` past its target, where the SLA
+/// allows `allowance`. With no allowance at all any breach earns the maximum.
+fn sla_breach_credits(excess: f32, allowance: f32) -> f32 {
+    if excess <= 0.0 {
+        0.0
+    } else if allowance <= 0.0 {
+        SLA_MAX_CREDIT_PER_METRIC
+    } else {
+        (excess / allowance * SLA_CREDIT_PER_BREACH_RATIO).min(SLA_MAX_CREDIT_PER_METRIC)
+    }
+}
+
 /// Global deployment status
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlobalDeploymentStatus {
@@ -1756,6 +1884,7 @@ pub struct GlobalPlatformStatus {
     pub global_latency_p95_ms: f32,
     pub uptime_percentage: f32,
     pub cost_efficiency_score: f32,
+    pub sla_reports: HashMap<String, SLAReport>,
 }
 
 // Default implementations
@@ -1770,6 +1899,7 @@ impl Default for GlobalConfiguration {
             monitoring_configuration: MonitoringConfiguration::default(),
             auto_scaling: AutoScalingConfiguration::default(),
             content_synchronization: ContentSynchronizationConfiguration::default(),
+            service_level_agreement: ServiceLevelAgreement::default(),
         }
     }
 }
@@ -2036,6 +2166,7 @@ impl Default for RegionPerformanceMetrics {
     fn default() -> Self {
         Self {
             average_latency_ms: 25.0,
+            latency_p95_ms: 45.0,
             uptime_percentage: 99.9,
             concurrent_users: 0,
             data_transfer_volume_gb: 0.0,
@@ -2053,6 +2184,16 @@ impl Default for RegionPerformanceMetrics {
     }
 }
 
+impl Default for ServiceLevelAgreement {
+    fn default() -> Self {
+        Self {
+            uptime_target_percent: 99.9,
+            latency_p95_target_ms: 100.0,
+            error_rate_ceiling_percent: 1.0,
+        }
+    }
+}
+
 impl Default for ConnectivityProfile {
     fn default() -> Self {
         Self {
@@ -2182,22 +2323,121 @@ impl From<global_matchmaking::MatchmakingEvent> for CloudEvent {
 
 impl From<content_delivery::CDNEvent> for CloudEvent {
     fn from(event: content_delivery::CDNEvent) -> Self {
-        CloudEvent::PerformanceAlert {
-            region_id: "unknown".to_string(),
-            metric_name: "cdn_metric".to_string(),
-            current_value: 0.0,
-            threshold: 0.0,
+        match event {
+            content_delivery::CDNEvent::LowCacheHitRatio { node_id, hit_ratio } => {
+                // Edge nodes are named "<region>-edge-<n>"
+                let region_id = node_id.rsplit_once("-edge-").map_or(node_id.as_str(), |(region, _)| region);
+                CloudEvent::PerformanceAlert {
+                    region_id: region_id.to_string(),
+                    metric_name: "cache_hit_ratio".to_string(),
+                    current_value: hit_ratio,
+                    threshold: content_delivery::LOW_CACHE_HIT_RATIO,
+                }
+            }
         }
     }
 }
 
-impl From<analytics_pipeline::AnalyticsEvent> for CloudEvent {
-    fn from(event: analytics_pipeline::AnalyticsEvent) -> Self {
-        CloudEvent::PerformanceAlert {
-            region_id: "unknown".to_string(),
-            metric_name: "analytics_metric".to_string(),
-            current_value: 0.0,
-            threshold: 0.0,
+impl From<analytics_pipeline::PipelineEvent> for CloudEvent {
+    fn from(event: analytics_pipeline::PipelineEvent) -> Self {
+        match event {
+            analytics_pipeline::PipelineEvent::RecordsExpired { count } => CloudEvent::PerformanceAlert {
+                region_id: "global".to_string(),
+                metric_name: "analytics_records_expired".to_string(),
+                current_value: count as f32,
+                threshold: 0.0,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_low_cache_hit_ratio_surfaces_as_performance_alert() {
+        let mut manager = CloudPlatformManager::new();
+        for i in 0..10 {
+            manager.content_delivery.record_request("asset", i < 4, "eu-west-1-edge-2", 30.0);
+        }
+
+        let events = manager.update(0.016).unwrap();
+        let alert = events.iter().find_map(|event| match event {
+            CloudEvent::PerformanceAlert { region_id, metric_name, current_value, threshold }
+                if metric_name == "cache_hit_ratio" =>
+            {
+                Some((region_id.clone(), *current_value, *threshold))
+            }
+            _ => None,
+        });
+
+        let (region_id, current_value, threshold) = alert.expect("no cache hit ratio alert");
+        assert_eq!(region_id, "eu-west-1");
+        assert!((current_value - 0.4).abs() < 1e-6);
+        assert_eq!(threshold, content_delivery::LOW_CACHE_HIT_RATIO);
+    }
+
+    fn metrics(uptime: f32, latency_p95: f32, error_rate: f32) -> RegionPerformanceMetrics {
+        RegionPerformanceMetrics {
+            uptime_percentage: uptime,
+            latency_p95_ms: latency_p95,
+            error_rate_percentage: error_rate,
+            ..RegionPerformanceMetrics::default()
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_metrics_exactly_at_sla_targets_are_compliant() {
+        let sla = ServiceLevelAgreement::default();
+        let report = metrics(99.9, 100.0, 1.0).compute_sla_compliance(&sla);
+        assert!(report.uptime_compliant && report.latency_compliant && report.error_rate_compliant);
+        assert!(report.overall_compliant);
+        assert_eq!(report.penalty_credits, 0.0);
+
+        // Just past each target breaches only that metric
+        let report = metrics(99.89, 100.0, 1.0).compute_sla_compliance(&sla);
+        assert!(!report.uptime_compliant && report.latency_compliant && report.error_rate_compliant);
+        assert!(!report.overall_compliant);
+        assert!(report.penalty_credits > 0.0);
+
+        let report = metrics(99.9, 100.01, 1.0).compute_sla_compliance(&sla);
+        assert!(report.uptime_compliant && !report.latency_compliant && report.error_rate_compliant);
+
+        let report = metrics(99.9, 100.0, 1.01).compute_sla_compliance(&sla);
+        assert!(report.uptime_compliant && report.latency_compliant && !report.error_rate_compliant);
+    }
+
+    #[test]
+    fn test_penalty_credits_scale_with_breach_and_are_capped() {
+        let sla = ServiceLevelAgreement {
+            uptime_target_percent: 99.0,
+            latency_p95_target_ms: 100.0,
+            error_rate_ceiling_percent: 1.0,
+        };
+        // Half the downtime budget over, and p95 latency at one and a half
+        // times the target
+        let report = metrics(98.5, 150.0, 0.5).compute_sla_compliance(&sla);
+        assert!((report.penalty_credits - 2.0 * 0.5 * SLA_CREDIT_PER_BREACH_RATIO).abs() < 1e-3);
+
+        let report = metrics(0.0, 10_000.0, 100.0).compute_sla_compliance(&sla);
+        assert_eq!(report.penalty_credits, 3.0 * SLA_MAX_CREDIT_PER_METRIC);
+
+        // A perfect uptime target leaves no downtime budget to scale against
+        let sla = ServiceLevelAgreement { uptime_target_percent: 100.0, ..sla };
+        assert_eq!(metrics(100.0, 100.0, 1.0).compute_sla_compliance(&sla).penalty_credits, 0.0);
+        assert_eq!(metrics(99.99, 100.0, 1.0).compute_sla_compliance(&sla).penalty_credits, SLA_MAX_CREDIT_PER_METRIC);
+    }
+
+    #[test]
+    fn test_global_status_reports_sla_per_region() {
+        let mut manager = CloudPlatformManager::new();
+        manager.setup_global_regions().unwrap();
+        manager.deployment_regions.get_mut("eu-west-1").unwrap().performance_metrics.latency_p95_ms = 250.0;
+
+        let status = manager.get_global_status();
+        assert_eq!(status.sla_reports.len(), status.total_regions);
+        assert!(!status.sla_reports["eu-west-1"].latency_compliant);
+        assert!(status.sla_reports["us-east-1"].overall_compliant);
+    }
+}
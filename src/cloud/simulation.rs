@@ -0,0 +1,127 @@
+// Robin Engine 2.0 - Cloud Network Simulation
+// Deterministic inter-region latency and packet loss for testing routing decisions
+
+use super::{great_circle_distance_km, DeploymentRegion};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Light in fibre covers roughly 200 km per millisecond
+const FIBRE_KM_PER_MS: f64 = 200.0;
+
+/// Fixed per-request overhead for routing, queuing and TLS
+const BASE_LATENCY_MS: f64 = 5.0;
+
+/// Outcome of one simulated request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimulatedResponse {
+    /// False when the request was lost or no route is configured
+    pub delivered: bool,
+    /// Round-trip time; zero for undelivered requests
+    pub latency: Duration,
+}
+
+/// Simulated network between regions (or named client locations). Latencies
+/// are symmetric; packet loss applies to requests arriving at a region.
+#[derive(Debug, Clone)]
+pub struct CloudSimulator {
+    pub latency_table: HashMap<(String, String), Duration>,
+    pub packet_loss_table: HashMap<String, f32>,
+    rng: StdRng,
+}
+
+impl CloudSimulator {
+    /// Seeded so packet loss is reproducible across test runs
+    pub fn new(seed: u64) -> Self {
+        Self {
+            latency_table: HashMap::new(),
+            packet_loss_table: HashMap::new(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Simulator with a round-trip latency between every pair of regions,
+    /// estimated from their great-circle distance
+    pub fn from_regions(regions: &HashMap<String, DeploymentRegion>, seed: u64) -> Self {
+        let mut simulator = Self::new(seed);
+        for (from_id, from) in regions {
+            for (to_id, to) in regions {
+                let distance = great_circle_distance_km(
+                    from.geographic_location.coordinates,
+                    to.geographic_location.coordinates,
+                );
+                simulator.set_latency(from_id, to_id, estimated_round_trip(distance));
+            }
+        }
+        simulator
+    }
+
+    pub fn set_latency(&mut self, from: &str, to: &str, latency: Duration) {
+        self.latency_table.insert((from.to_string(), to.to_string()), latency);
+    }
+
+    /// Probability in [0, 1] that a request to `region` is dropped
+    pub fn set_packet_loss(&mut self, region: &str, probability: f32) {
+        self.packet_loss_table
+            .insert(region.to_string(), probability.clamp(0.0, 1.0));
+    }
+
+    pub fn latency(&self, from: &str, to: &str) -> Option<Duration> {
+        self.latency_table
+            .get(&(from.to_string(), to.to_string()))
+            .or_else(|| self.latency_table.get(&(to.to_string(), from.to_string())))
+            .copied()
+    }
+
+    pub fn simulate_request(&mut self, from_region: &str, to_region: &str) -> SimulatedResponse {
+        let Some(latency) = self.latency(from_region, to_region) else {
+            return SimulatedResponse { delivered: false, latency: Duration::ZERO };
+        };
+
+        let loss = self.packet_loss_table.get(to_region).copied().unwrap_or(0.0);
+        if self.rng.gen::<f32>() < loss {
+            return SimulatedResponse { delivered: false, latency: Duration::ZERO };
+        }
+        SimulatedResponse { delivered: true, latency }
+    }
+}
+
+fn estimated_round_trip(distance_km: f64) -> Duration {
+    Duration::from_secs_f64((BASE_LATENCY_MS + 2.0 * distance_km / FIBRE_KM_PER_MS) / 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_is_symmetric_and_unknown_routes_fail() {
+        let mut simulator = CloudSimulator::new(1);
+        simulator.set_latency("a", "b", Duration::from_millis(40));
+
+        assert_eq!(simulator.simulate_request("b", "a").latency, Duration::from_millis(40));
+        assert!(!simulator.simulate_request("a", "c").delivered);
+    }
+
+    #[test]
+    fn test_packet_loss_rate() {
+        let mut simulator = CloudSimulator::new(42);
+        simulator.set_latency("a", "b", Duration::from_millis(10));
+        simulator.set_packet_loss("b", 0.25);
+
+        let delivered = (0..2000).filter(|_| simulator.simulate_request("a", "b").delivered).count();
+        // 1500 expected
+        assert!((1400..1600).contains(&delivered));
+
+        // Loss is per destination
+        assert!((0..100).all(|_| simulator.simulate_request("b", "a").delivered));
+    }
+
+    #[test]
+    fn test_estimated_round_trip_grows_with_distance() {
+        assert_eq!(estimated_round_trip(0.0), Duration::from_millis(5));
+        // ~15,500 km Singapore to N. Virginia is well over 100 ms
+        assert!(estimated_round_trip(15_500.0) > Duration::from_millis(100));
+    }
+}
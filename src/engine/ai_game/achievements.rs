@@ -0,0 +1,304 @@
+// Robin Game Engine - Achievements
+// Long-term goals checked against a player's profile, each unlocking a reward
+// the first time its condition is met
+
+use super::PlayerProfile;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+/// Key into `PlayerProfile::skill_levels`
+pub type SkillDomain = String;
+
+/// What a player has to do to unlock an achievement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AchievementCondition {
+    VoxelsPlaced(u32),
+    /// Current level in the domain at or above the threshold
+    SkillReached(SkillDomain, f32),
+    SessionsCompleted(u32),
+    CollaborationMinutes(u32),
+}
+
+/// Content or points an achievement grants
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Reward {
+    UnlockVoxelType(String),
+    UnlockTemplate(String),
+    BonusPoints(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Achievement {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub condition: AchievementCondition,
+    pub reward: Reward,
+}
+
+/// When an achievement was unlocked and what it granted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnlockRecord {
+    pub unlocked_at: DateTime<Utc>,
+    pub reward: Reward,
+}
+
+/// The achievements players can earn. What each player has unlocked lives
+/// on their `PlayerProfile`, in `unlocked_achievements`, so it's saved with
+/// the profile.
+#[derive(Debug, Clone)]
+pub struct AchievementSystem {
+    pub achievements: Vec<Achievement>,
+}
+
+impl AchievementSystem {
+    pub fn new(achievements: Vec<Achievement>) -> Self {
+        Self { achievements }
+    }
+
+    /// Unlocks every achievement `profile` now meets that isn't unlocked
+    /// already, recording it on the profile, and returns those in the order
+    /// they're defined
+    pub fn check_all(&self, profile: &mut PlayerProfile) -> Vec<Achievement> {
+        let now = Utc::now();
+        let mut newly_unlocked = Vec::new();
+        for achievement in &self.achievements {
+            if profile.unlocked_achievements.contains_key(&achievement.id) || !is_met(&achievement.condition, profile) {
+                continue;
+            }
+            profile.unlocked_achievements.insert(achievement.id.clone(), UnlockRecord {
+                unlocked_at: now,
+                reward: achievement.reward.clone(),
+            });
+            newly_unlocked.push(achievement.clone());
+        }
+        newly_unlocked
+    }
+}
+
+impl PlayerProfile {
+    pub fn is_achievement_unlocked(&self, achievement_id: &str) -> bool {
+        self.unlocked_achievements.contains_key(achievement_id)
+    }
+
+    /// Voxel types granted by unlocked achievements
+    pub fn unlocked_voxel_types(&self) -> Vec<&str> {
+        self.unlocked_achievements.values()
+            .filter_map(|record| match &record.reward {
+                Reward::UnlockVoxelType(voxel_type) => Some(voxel_type.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Templates granted by unlocked achievements
+    pub fn unlocked_templates(&self) -> Vec<&str> {
+        self.unlocked_achievements.values()
+            .filter_map(|record| match &record.reward {
+                Reward::UnlockTemplate(template) => Some(template.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Total bonus points granted by unlocked achievements
+    pub fn bonus_points(&self) -> u32 {
+        self.unlocked_achievements.values()
+            .map(|record| match record.reward {
+                Reward::BonusPoints(points) => points,
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+impl Default for AchievementSystem {
+    /// The engine's built-in achievements
+    fn default() -> Self {
+        let achievement = |id: &str, name: &str, description: &str, condition, reward| Achievement {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            condition,
+            reward,
+        };
+        Self::new(vec![
+            achievement(
+                "first_structure", "First Structure", "Place 100 voxels",
+                AchievementCondition::VoxelsPlaced(100),
+                Reward::UnlockVoxelType("glass".to_string()),
+            ),
+            achievement(
+                "master_builder", "Master Builder", "Place 10,000 voxels",
+                AchievementCondition::VoxelsPlaced(10_000),
+                Reward::UnlockTemplate("castle".to_string()),
+            ),
+            achievement(
+                "bridge_engineer", "Bridge Engineer", "Reach 80% skill in bridge building",
+                AchievementCondition::SkillReached("bridges".to_string(), 0.8),
+                Reward::UnlockTemplate("suspension_bridge".to_string()),
+            ),
+            achievement(
+                "regular", "Regular", "Complete 10 play sessions",
+                AchievementCondition::SessionsCompleted(10),
+                Reward::BonusPoints(500),
+            ),
+            achievement(
+                "team_player", "Team Player", "Build with others for an hour",
+                AchievementCondition::CollaborationMinutes(60),
+                Reward::BonusPoints(1000),
+            ),
+        ])
+    }
+}
+
+fn is_met(condition: &AchievementCondition, profile: &PlayerProfile) -> bool {
+    let history = &profile.play_history;
+    match condition {
+        AchievementCondition::VoxelsPlaced(count) => history.voxels_placed >= *count,
+        AchievementCondition::SkillReached(domain, level) => profile.skill_levels
+            .get(domain)
+            .is_some_and(|skill| skill.current_level >= *level),
+        AchievementCondition::SessionsCompleted(count) => history.sessions_completed >= *count,
+        // Collaboration time is kept in hours
+        AchievementCondition::CollaborationMinutes(minutes) => history.collaboration_time * 60.0 >= *minutes as f32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::ai_game::{GameAIManager, InteractionResult, InteractionType, PlayerInteraction, SkillLevel, VOXELS_PLACED_KEY};
+    use crate::engine::error::RobinResult;
+
+    fn system_with(condition: AchievementCondition, reward: Reward) -> AchievementSystem {
+        AchievementSystem::new(vec![Achievement {
+            id: "goal".to_string(),
+            name: "Goal".to_string(),
+            description: String::new(),
+            condition,
+            reward,
+        }])
+    }
+
+    #[test]
+    fn test_voxels_placed_unlocks_once() {
+        let system = system_with(AchievementCondition::VoxelsPlaced(100), Reward::UnlockVoxelType("glass".to_string()));
+        let mut profile = PlayerProfile::default();
+        profile.play_history.voxels_placed = 99;
+        assert!(system.check_all(&mut profile).is_empty());
+
+        profile.play_history.voxels_placed = 100;
+        let unlocked = system.check_all(&mut profile);
+        assert_eq!(unlocked.len(), 1);
+        assert_eq!(unlocked[0].id, "goal");
+        assert_eq!(profile.unlocked_voxel_types(), ["glass"]);
+
+        // Already unlocked, so not reported again
+        assert!(system.check_all(&mut profile).is_empty());
+    }
+
+    #[test]
+    fn test_skill_reached_needs_the_named_domain() {
+        let system = system_with(
+            AchievementCondition::SkillReached("bridges".to_string(), 0.8),
+            Reward::UnlockTemplate("suspension_bridge".to_string()),
+        );
+        let skill = |current_level| SkillLevel {
+            current_level,
+            progression_rate: 0.1,
+            consistency: 0.5,
+            peak_performance: current_level,
+            practice_time: 1.0,
+            last_assessment: Utc::now(),
+            stability: 1.0,
+            last_reviewed: std::time::SystemTime::now(),
+        };
+        let mut profile = PlayerProfile::default();
+        profile.skill_levels.insert("wiring".to_string(), skill(0.95));
+        profile.skill_levels.insert("bridges".to_string(), skill(0.7));
+        assert!(system.check_all(&mut profile).is_empty());
+
+        profile.skill_levels.insert("bridges".to_string(), skill(0.8));
+        assert_eq!(system.check_all(&mut profile).len(), 1);
+        assert_eq!(profile.unlocked_templates(), ["suspension_bridge"]);
+    }
+
+    #[test]
+    fn test_sessions_completed() {
+        let system = system_with(AchievementCondition::SessionsCompleted(10), Reward::BonusPoints(500));
+        let mut profile = PlayerProfile::default();
+        profile.play_history.sessions_completed = 9;
+        assert!(system.check_all(&mut profile).is_empty());
+
+        profile.play_history.sessions_completed = 12;
+        assert_eq!(system.check_all(&mut profile).len(), 1);
+        assert_eq!(profile.bonus_points(), 500);
+    }
+
+    #[test]
+    fn test_collaboration_minutes_counts_hours_of_collaboration() {
+        let system = system_with(AchievementCondition::CollaborationMinutes(90), Reward::BonusPoints(1000));
+        let mut profile = PlayerProfile::default();
+        profile.play_history.collaboration_time = 1.0;
+        assert!(system.check_all(&mut profile).is_empty());
+
+        profile.play_history.collaboration_time = 1.5;
+        assert_eq!(system.check_all(&mut profile).len(), 1);
+        assert!(profile.is_achievement_unlocked("goal"));
+    }
+
+    #[test]
+    fn test_interactions_and_sessions_count_toward_achievements() -> RobinResult<()> {
+        let mut manager = GameAIManager::new();
+        let mut profile = PlayerProfile::default();
+        profile.player_id = "ada".to_string();
+        manager.add_player_profile(profile);
+        let interaction = |interaction_type, duration, voxels_placed: Option<f32>| PlayerInteraction {
+            timestamp: Utc::now(),
+            interaction_type,
+            duration,
+            context_data: voxels_placed.map(|count| (VOXELS_PLACED_KEY.to_string(), count)).into_iter().collect(),
+            result: InteractionResult::Success,
+        };
+
+        manager.process_interaction("ada", interaction(InteractionType::Building, 30.0, Some(60.0)))?;
+        assert!(manager.check_achievements("ada").is_empty());
+        manager.process_interaction("ada", interaction(InteractionType::Building, 30.0, Some(40.0)))?;
+        // An hour of collaborating, reported in two halves
+        manager.process_interaction("ada", interaction(InteractionType::Collaborating, 1800.0, None))?;
+        manager.process_interaction("ada", interaction(InteractionType::Collaborating, 1800.0, None))?;
+        for _ in 0..10 {
+            manager.player_analytics.start_session("ada")?;
+            manager.end_player_session("ada")?;
+        }
+        // Ending a session that was never started doesn't count
+        manager.end_player_session("ada")?;
+
+        let history = &manager.get_player_profile("ada").unwrap().play_history;
+        assert_eq!(history.voxels_placed, 100);
+        assert_eq!(history.sessions_completed, 10);
+        let unlocked: Vec<String> = manager.check_achievements("ada").into_iter().map(|a| a.id).collect();
+        assert_eq!(unlocked, ["first_structure", "regular", "team_player"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unlocks_are_saved_with_the_profile() {
+        let mut manager = GameAIManager::new();
+        let mut profile = PlayerProfile::default();
+        profile.player_id = "ada".to_string();
+        profile.play_history.voxels_placed = 150;
+        manager.add_player_profile(profile);
+
+        let unlocked = manager.check_achievements("ada");
+        assert_eq!(unlocked.iter().map(|a| a.id.as_str()).collect::<Vec<_>>(), ["first_structure"]);
+
+        // Reloaded into a fresh manager, the unlock isn't earned a second time
+        let saved = serde_json::to_string(manager.get_player_profile("ada").unwrap()).unwrap();
+        let mut reloaded = GameAIManager::new();
+        reloaded.add_player_profile(serde_json::from_str(&saved).unwrap());
+        assert!(reloaded.check_achievements("ada").is_empty());
+        assert!(reloaded.get_player_profile("ada").unwrap().unlocked_achievements.contains_key("first_structure"));
+    }
+}
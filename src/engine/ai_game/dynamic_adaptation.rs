@@ -71,6 +71,49 @@ pub enum DifficultyChangeType {
     CustomAdjust(String),
 }
 
+/// Success rate the difficulty PID controller steers players towards
+pub const TARGET_SUCCESS_RATE: f32 = 0.70;
+
+/// Bound on the accumulated error so long streaks don't wind the integral up
+const PID_INTEGRAL_LIMIT: f32 = 2.0;
+
+/// PID controller over the gap between observed and target success rate
+#[derive(Debug, Clone)]
+pub struct DifficultyPid {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    integral: f32,
+    previous_error: Option<f32>,
+}
+
+impl DifficultyPid {
+    pub fn new(kp: f32, ki: f32, kd: f32) -> Self {
+        Self { kp, ki, kd, integral: 0.0, previous_error: None }
+    }
+
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.previous_error = None;
+    }
+
+    /// Control output for one step; positive when the player is succeeding
+    /// more than the target and difficulty should rise
+    pub fn step(&mut self, error: f32) -> f32 {
+        self.integral = (self.integral + error).clamp(-PID_INTEGRAL_LIMIT, PID_INTEGRAL_LIMIT);
+        let derivative = self.previous_error.map_or(0.0, |previous| error - previous);
+        self.previous_error = Some(error);
+
+        self.kp * error + self.ki * self.integral + self.kd * derivative
+    }
+}
+
+impl Default for DifficultyPid {
+    fn default() -> Self {
+        Self::new(0.5, 0.05, 0.1)
+    }
+}
+
 impl Default for DifficultyAdjustment {
     fn default() -> Self {
         Self {
@@ -240,6 +283,29 @@ impl DynamicAdaptation {
         self.player_models.get(player_id).map(|model| model.get_adaptation_status())
     }
 
+    /// Next difficulty (0.0-1.0) given the player's recent success rates (each
+    /// 0.0-1.0), steering towards `TARGET_SUCCESS_RATE`
+    pub fn adjust(&mut self, performance_window: &[f32], current_difficulty: f32) -> f32 {
+        if performance_window.is_empty() {
+            return current_difficulty;
+        }
+
+        let success_rate = performance_window.iter().sum::<f32>() / performance_window.len() as f32;
+        let error = success_rate.clamp(0.0, 1.0) - TARGET_SUCCESS_RATE;
+        let output = self.difficulty_controller.pid.step(error);
+
+        let difficulty = (current_difficulty + output).clamp(0.0, 1.0);
+        self.difficulty_controller.current_difficulty = difficulty;
+        difficulty
+    }
+
+    pub fn set_pid_gains(&mut self, kp: f32, ki: f32, kd: f32) {
+        let pid = &mut self.difficulty_controller.pid;
+        pid.kp = kp;
+        pid.ki = ki;
+        pid.kd = kd;
+    }
+
     pub fn set_adaptation_config(&mut self, config: AdaptationConfig) {
         self.adaptation_config = config;
         self.difficulty_controller.update_config(&self.adaptation_config);
@@ -533,6 +599,7 @@ struct DifficultyController {
     target_flow_state: f32,
     adjustment_rate: f32,
     stability_threshold: f32,
+    pid: DifficultyPid,
 }
 
 impl DifficultyController {
@@ -542,6 +609,7 @@ impl DifficultyController {
             target_flow_state: 0.7,
             adjustment_rate: 0.1,
             stability_threshold: 0.05,
+            pid: DifficultyPid::default(),
         }
     }
 
@@ -783,4 +851,68 @@ impl Default for AdaptationConfig {
             rollback_enabled: true,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Chance a player of the given skill succeeds at a difficulty
+    fn success_probability(skill: f32, difficulty: f32) -> f32 {
+        (0.5 + (skill - difficulty) * 1.5).clamp(0.0, 1.0)
+    }
+
+    #[test]
+    fn test_pid_converges_to_target_success_rate() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut adaptation = DynamicAdaptation::new();
+        let skill = 0.6;
+        let mut difficulty = 0.9;
+        let mut success_rates = Vec::new();
+
+        for _ in 0..100 {
+            let p = success_probability(skill, difficulty);
+            let window: Vec<f32> = (0..10)
+                .map(|_| if rng.gen::<f32>() < p { 1.0 } else { 0.0 })
+                .collect();
+            difficulty = adaptation.adjust(&window, difficulty);
+            success_rates.push(success_probability(skill, difficulty));
+        }
+
+        let settled = &success_rates[60..];
+        let average = settled.iter().sum::<f32>() / settled.len() as f32;
+        assert!((average - TARGET_SUCCESS_RATE).abs() <= 0.05, "settled at {}", average);
+    }
+
+    #[test]
+    fn test_integral_corrects_sustained_error() {
+        let mut proportional_only = DifficultyPid::new(0.5, 0.0, 0.0);
+        let mut with_integral = DifficultyPid::new(0.5, 0.1, 0.0);
+        let mut p_output = 0.0;
+        let mut pi_output = 0.0;
+        for _ in 0..5 {
+            p_output = proportional_only.step(-0.2);
+            pi_output = with_integral.step(-0.2);
+        }
+        assert!((p_output + 0.1).abs() < 1e-6);
+        assert!(pi_output < p_output);
+    }
+
+    #[test]
+    fn test_derivative_damps_rapid_change() {
+        let mut pid = DifficultyPid::new(0.0, 0.0, 0.5);
+        assert_eq!(pid.step(0.3), 0.0);
+        // Error shrinking quickly pulls the output the other way
+        assert!(pid.step(0.1) < 0.0);
+    }
+
+    #[test]
+    fn test_empty_window_keeps_difficulty() {
+        let mut adaptation = DynamicAdaptation::new();
+        assert_eq!(adaptation.adjust(&[], 0.42), 0.42);
+        assert!(adaptation.adjust(&[1.0; 5], 0.42) > 0.42);
+        assert!(adaptation.adjust(&[0.0; 5], 1.0) < 1.0);
+    }
+}
@@ -1,7 +1,7 @@
 // Robin Game Engine - Game Balancing System
 // AI-driven game balance optimization with machine learning analytics
 
-use crate::engine::error::RobinResult;
+use crate::engine::error::{RobinError, RobinResult};
 use super::{PlayerProfile, GameAIEvent, GameAIRecommendation, RecommendationType, Priority, ExpectedImpact, PlayerInteraction};
 use std::collections::{HashMap, VecDeque};
 use std::time::{Duration, Instant, SystemTime};
@@ -22,6 +22,10 @@ pub struct GameBalancing {
     optimization_history: VecDeque<OptimizationEvent>,
     last_optimization: Instant,
     optimization_interval: Duration,
+    /// Difficulty parameters outside any experiment
+    parameters: HashMap<String, f32>,
+    experiments: HashMap<String, Experiment>,
+    experiment_outcomes: HashMap<String, ExperimentOutcomes>,
 }
 
 impl GameBalancing {
@@ -39,6 +43,9 @@ impl GameBalancing {
             optimization_history: VecDeque::new(),
             last_optimization: Instant::now(),
             optimization_interval: Duration::from_secs(300), // 5 minutes
+            parameters: HashMap::new(),
+            experiments: HashMap::new(),
+            experiment_outcomes: HashMap::new(),
         }
     }
 
@@ -210,6 +217,288 @@ impl GameBalancing {
         self.progression_optimizer.optimize_progression_curves()?;
         Ok(())
     }
+
+    /// Starts an A/B test, replacing any earlier experiment of the same name
+    /// along with its outcomes. A parameter can only be under one experiment
+    /// at a time, so every player sees a single value for it.
+    pub fn add_experiment(&mut self, experiment: Experiment) -> RobinResult<()> {
+        if !(0.0..=100.0).contains(&experiment.allocation_percent) {
+            return Err(RobinError::RangeError {
+                parameter: "allocation_percent".to_string(),
+                value: experiment.allocation_percent as f64,
+                min: 0.0,
+                max: 100.0,
+            });
+        }
+        if let Some(existing) = self.experiments.values()
+            .find(|existing| existing.parameter == experiment.parameter && existing.name != experiment.name)
+        {
+            return Err(RobinError::InvalidData {
+                field: "parameter".to_string(),
+                reason: format!("{} is already under experiment {}", experiment.parameter, existing.name),
+            });
+        }
+
+        self.experiment_outcomes.insert(experiment.name.clone(), ExperimentOutcomes::default());
+        self.experiments.insert(experiment.name.clone(), experiment);
+        Ok(())
+    }
+
+    pub fn get_experiment(&self, name: &str) -> Option<&Experiment> {
+        self.experiments.get(name)
+    }
+
+    /// Sets the value players get for a parameter no experiment is varying
+    pub fn set_parameter(&mut self, param: &str, value: f32) {
+        self.parameters.insert(param.to_string(), value);
+    }
+
+    /// Group the player falls in for an experiment. Players are hashed with
+    /// the experiment's name, so the same player always lands in the same
+    /// group of an experiment but groups don't line up across experiments.
+    pub fn assign_experiment(player_id: &str, experiment: &Experiment) -> ExperimentGroup {
+        let bucket = stable_hash(&[&experiment.name, player_id]) % ASSIGNMENT_BUCKETS;
+        let treatment_buckets = experiment.allocation_percent / 100.0 * ASSIGNMENT_BUCKETS as f32;
+        if (bucket as f32) < treatment_buckets {
+            ExperimentGroup::Treatment
+        } else {
+            ExperimentGroup::Control
+        }
+    }
+
+    /// The player's value for a difficulty parameter: their group's value if
+    /// an experiment is varying it, otherwise the value set with
+    /// `set_parameter`, or 0.0 if it was never set
+    pub fn get_parameter(&self, player_id: &str, param: &str) -> f32 {
+        match self.experiments.values().find(|experiment| experiment.parameter == param) {
+            Some(experiment) => experiment.value_for(Self::assign_experiment(player_id, experiment)),
+            None => self.parameters.get(param).copied().unwrap_or(0.0),
+        }
+    }
+
+    /// Records a metric (completion rate, session length, ...) the player
+    /// reached under the experiment, for `analyze`
+    pub fn record_outcome(&mut self, player_id: &str, experiment: &str, metric: f32) -> RobinResult<()> {
+        let definition = self.experiments.get(experiment)
+            .ok_or_else(|| RobinError::NotFound(format!("experiment {}", experiment)))?;
+        let group = Self::assign_experiment(player_id, definition);
+
+        let outcomes = self.experiment_outcomes.entry(experiment.to_string()).or_default();
+        match group {
+            ExperimentGroup::Control => outcomes.control.push(metric),
+            ExperimentGroup::Treatment => outcomes.treatment.push(metric),
+        }
+        Ok(())
+    }
+
+    /// Compares the groups' recorded outcomes with Welch's two-sample t-test.
+    /// With fewer than two outcomes in either group there's nothing to test,
+    /// and the result is reported as not significant.
+    pub fn analyze(&self, experiment: &str) -> RobinResult<ExperimentResult> {
+        let outcomes = self.experiment_outcomes.get(experiment)
+            .ok_or_else(|| RobinError::NotFound(format!("experiment {}", experiment)))?;
+        let (control_mean, control_variance) = mean_and_variance(&outcomes.control);
+        let (treatment_mean, treatment_variance) = mean_and_variance(&outcomes.treatment);
+        let control_n = outcomes.control.len() as f64;
+        let treatment_n = outcomes.treatment.len() as f64;
+
+        let (t_statistic, p_value) = if control_n < 2.0 || treatment_n < 2.0 {
+            (0.0, 1.0)
+        } else {
+            let control_error = control_variance / control_n;
+            let treatment_error = treatment_variance / treatment_n;
+            let standard_error = (control_error + treatment_error).sqrt();
+            let difference = treatment_mean - control_mean;
+            if standard_error == 0.0 {
+                // Every outcome in each group is identical
+                if difference == 0.0 { (0.0, 1.0) } else { (f64::INFINITY.copysign(difference), 0.0) }
+            } else {
+                // Welch-Satterthwaite approximation of the degrees of freedom
+                let degrees_of_freedom = (control_error + treatment_error).powi(2)
+                    / (control_error.powi(2) / (control_n - 1.0) + treatment_error.powi(2) / (treatment_n - 1.0));
+                let t = difference / standard_error;
+                (t, students_t_two_tailed_p(t, degrees_of_freedom))
+            }
+        };
+
+        Ok(ExperimentResult {
+            experiment: experiment.to_string(),
+            control_mean: control_mean as f32,
+            treatment_mean: treatment_mean as f32,
+            control_samples: outcomes.control.len(),
+            treatment_samples: outcomes.treatment.len(),
+            t_statistic: t_statistic as f32,
+            p_value: p_value as f32,
+            significant: p_value < SIGNIFICANCE_LEVEL as f64,
+        })
+    }
+}
+
+/// Buckets players are hashed into, so allocations are honoured to a
+/// hundredth of a percent
+const ASSIGNMENT_BUCKETS: u64 = 10_000;
+
+/// p-value below which `analyze` reports a difference as significant
+pub const SIGNIFICANCE_LEVEL: f32 = 0.05;
+
+/// A difficulty parameter tried at two values on two groups of players
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Experiment {
+    pub name: String,
+    pub parameter: String,
+    pub control_value: f32,
+    pub treatment_value: f32,
+    /// Share of players in the treatment group, from 0 to 100
+    pub allocation_percent: f32,
+}
+
+impl Experiment {
+    pub fn value_for(&self, group: ExperimentGroup) -> f32 {
+        match group {
+            ExperimentGroup::Control => self.control_value,
+            ExperimentGroup::Treatment => self.treatment_value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExperimentGroup {
+    Control,
+    Treatment,
+}
+
+/// Outcome of comparing an experiment's groups
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentResult {
+    pub experiment: String,
+    pub control_mean: f32,
+    pub treatment_mean: f32,
+    pub control_samples: usize,
+    pub treatment_samples: usize,
+    /// Positive when the treatment group's mean is higher
+    pub t_statistic: f32,
+    /// Two-tailed chance of a difference this large with no real effect
+    pub p_value: f32,
+    pub significant: bool,
+}
+
+#[derive(Debug, Default)]
+struct ExperimentOutcomes {
+    control: Vec<f32>,
+    treatment: Vec<f32>,
+}
+
+/// FNV-1a over the parts, which unlike `DefaultHasher` is guaranteed the same
+/// on every platform and Rust release
+fn stable_hash(parts: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        // Separated so ("ab", "c") and ("a", "bc") differ
+        for &byte in part.as_bytes().iter().chain(&[0xff]) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+/// Mean and sample variance
+fn mean_and_variance(samples: &[f32]) -> (f64, f64) {
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = samples.len() as f64;
+    let mean = samples.iter().map(|&x| x as f64).sum::<f64>() / n;
+    if samples.len() < 2 {
+        return (mean, 0.0);
+    }
+    let variance = samples.iter().map(|&x| (x as f64 - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, variance)
+}
+
+/// Chance of a Student's t statistic at least as far from zero as `t`
+fn students_t_two_tailed_p(t: f64, degrees_of_freedom: f64) -> f64 {
+    let x = degrees_of_freedom / (degrees_of_freedom + t * t);
+    regularized_incomplete_beta(degrees_of_freedom / 2.0, 0.5, x).clamp(0.0, 1.0)
+}
+
+/// I_x(a, b), by its continued fraction (Numerical Recipes' betai)
+fn regularized_incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The fraction converges quickly on this side; use the symmetry otherwise
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 200;
+    const EPSILON: f64 = 1e-12;
+    const TINY: f64 = 1e-300;
+
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut fraction = d;
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        // Even step, then odd step
+        for numerator in [
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ] {
+            d = 1.0 + numerator * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            fraction *= d * c;
+        }
+        if (d * c - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    fraction
+}
+
+/// ln Γ(x) for x > 0, by the Lanczos approximation
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflection formula
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS[1..].iter().enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, &c)| sum + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
 }
 
 /// Balance Analysis subsystem
@@ -1444,4 +1733,103 @@ impl RetentionAnalyzer {
         let sessions = self.player_sessions.entry(player_id.to_string()).or_insert_with(Vec::new);
         sessions.push(SystemTime::now());
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn experiment(allocation_percent: f32) -> Experiment {
+        Experiment {
+            name: "gentler_enemies".to_string(),
+            parameter: "enemy_damage".to_string(),
+            control_value: 1.0,
+            treatment_value: 0.8,
+            allocation_percent,
+        }
+    }
+
+    #[test]
+    fn test_assignment_is_stable_per_player() {
+        let split = experiment(30.0);
+        let players: Vec<String> = (0..1000).map(|i| format!("player_{}", i)).collect();
+
+        let first: Vec<ExperimentGroup> = players.iter()
+            .map(|player| GameBalancing::assign_experiment(player, &split))
+            .collect();
+        let second: Vec<ExperimentGroup> = players.iter()
+            .map(|player| GameBalancing::assign_experiment(player, &split))
+            .collect();
+        assert_eq!(first, second);
+
+        let treated = first.iter().filter(|&&group| group == ExperimentGroup::Treatment).count();
+        assert!((250..=350).contains(&treated), "{} of 1000 players treated at 30%", treated);
+
+        assert!(players.iter().all(|player| GameBalancing::assign_experiment(player, &experiment(0.0)) == ExperimentGroup::Control));
+        assert!(players.iter().all(|player| GameBalancing::assign_experiment(player, &experiment(100.0)) == ExperimentGroup::Treatment));
+    }
+
+    #[test]
+    fn test_get_parameter_follows_assignment() -> RobinResult<()> {
+        let mut balancing = GameBalancing::new();
+        balancing.set_parameter("enemy_damage", 1.5);
+        balancing.set_parameter("spawn_rate", 2.0);
+        assert_eq!(balancing.get_parameter("player_1", "enemy_damage"), 1.5);
+
+        let experiment = experiment(50.0);
+        balancing.add_experiment(experiment.clone())?;
+        for i in 0..50 {
+            let player = format!("player_{}", i);
+            let expected = experiment.value_for(GameBalancing::assign_experiment(&player, &experiment));
+            assert_eq!(balancing.get_parameter(&player, "enemy_damage"), expected);
+            assert_eq!(balancing.get_parameter(&player, "spawn_rate"), 2.0);
+        }
+        assert_eq!(balancing.get_parameter("player_1", "unknown"), 0.0);
+
+        // One experiment per parameter
+        let rival = Experiment { name: "harsher_enemies".to_string(), ..experiment.clone() };
+        assert!(balancing.add_experiment(rival).is_err());
+        assert!(balancing.add_experiment(Experiment { allocation_percent: 120.0, ..experiment }).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_analyze_runs_welch_t_test() -> RobinResult<()> {
+        let mut balancing = GameBalancing::new();
+        let experiment = experiment(50.0);
+        balancing.add_experiment(experiment.clone())?;
+
+        let mut players = (0..).map(|i| format!("player_{}", i));
+        let mut next_in = |group| players.by_ref().find(|player| GameBalancing::assign_experiment(player, &experiment) == group).unwrap();
+        for metric in [1.0, 2.0, 3.0, 4.0] {
+            balancing.record_outcome(&next_in(ExperimentGroup::Control), "gentler_enemies", metric)?;
+        }
+        for metric in [3.0, 4.0, 5.0, 6.0] {
+            balancing.record_outcome(&next_in(ExperimentGroup::Treatment), "gentler_enemies", metric)?;
+        }
+
+        // Means 2.5 and 4.5, variances 5/3, so t = 2 / sqrt(5/6) on 6 degrees of freedom
+        let result = balancing.analyze("gentler_enemies")?;
+        assert_eq!((result.control_samples, result.treatment_samples), (4, 4));
+        assert_eq!((result.control_mean, result.treatment_mean), (2.5, 4.5));
+        assert!((result.t_statistic - 2.190890).abs() < 1e-4, "t = {}", result.t_statistic);
+        assert!((result.p_value - 0.070988).abs() < 1e-4, "p = {}", result.p_value);
+        assert!(!result.significant);
+
+        // Many more of the same separate the groups clearly
+        for _ in 0..10 {
+            for metric in [1.0, 2.0, 3.0, 4.0] {
+                balancing.record_outcome(&next_in(ExperimentGroup::Control), "gentler_enemies", metric)?;
+            }
+            for metric in [3.0, 4.0, 5.0, 6.0] {
+                balancing.record_outcome(&next_in(ExperimentGroup::Treatment), "gentler_enemies", metric)?;
+            }
+        }
+        let result = balancing.analyze("gentler_enemies")?;
+        assert!(result.significant && result.p_value < 0.001, "p = {}", result.p_value);
+
+        assert!(balancing.record_outcome("player_1", "unknown", 1.0).is_err());
+        assert!(balancing.analyze("unknown").is_err());
+        Ok(())
+    }
+}
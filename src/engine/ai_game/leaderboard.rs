@@ -0,0 +1,310 @@
+// Robin Game Engine - Skill-Adjusted Leaderboard
+// Normalised rankings with anomaly detection to discourage score exploits
+
+use super::PlayerProfile;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Scores this many standard deviations above a player's rolling average are held for review
+pub const SUSPICIOUS_SCORE_SIGMA: f32 = 3.0;
+
+/// Sessions shorter than this fraction of the player's typical session are penalised
+const SHORT_SESSION_FRACTION: f32 = 0.25;
+
+/// Recent scores needed before anomaly detection kicks in
+const MIN_HISTORY_FOR_ANOMALY: usize = 5;
+
+/// Leaderboard standing of a submitted score
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ScoreStatus {
+    Ranked,
+    /// Excluded from the public leaderboard pending review
+    SuspiciousScore,
+}
+
+/// A raw score normalised for skill, session length and the cohort
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdjustedScore {
+    pub raw_score: f32,
+    /// Raw score scaled by the session penalty and divided by relative skill
+    pub skill_adjusted: f32,
+    /// Multiplier in [0, 1]; below 1 for anomalously short sessions
+    pub session_penalty: f32,
+    /// Standard score of `skill_adjusted` across the ranked cohort
+    pub z_score: f32,
+    pub status: ScoreStatus,
+    pub submitted_at: DateTime<Utc>,
+}
+
+/// Skill-adjusted rankings keyed by player id
+#[derive(Debug)]
+pub struct Leaderboard {
+    pub rankings: HashMap<String, AdjustedScore>,
+    pub pending_review: HashMap<String, AdjustedScore>,
+    /// Number of accepted raw scores kept per player for the rolling average
+    pub history_length: usize,
+    score_history: HashMap<String, VecDeque<f32>>,
+}
+
+impl Leaderboard {
+    pub fn new() -> Self {
+        Self {
+            rankings: HashMap::new(),
+            pending_review: HashMap::new(),
+            history_length: 20,
+            score_history: HashMap::new(),
+        }
+    }
+
+    /// Scores a submission from a player with the given profile (if known).
+    /// Suspicious scores go to `pending_review`; otherwise the player's best
+    /// adjusted score is kept in `rankings`.
+    pub fn submit(&mut self, player_id: &str, raw_score: f32, profile: Option<&PlayerProfile>, now: DateTime<Utc>) -> AdjustedScore {
+        let skill = profile.map_or(0.5, domain_skill);
+        let session_penalty = profile.map_or(1.0, |profile| session_penalty(profile, now));
+        let status = if self.is_anomalous(player_id, raw_score) {
+            ScoreStatus::SuspiciousScore
+        } else {
+            ScoreStatus::Ranked
+        };
+
+        let mut score = AdjustedScore {
+            raw_score,
+            skill_adjusted: raw_score * session_penalty / (0.5 + skill),
+            session_penalty,
+            z_score: 0.0,
+            status,
+            submitted_at: now,
+        };
+
+        match status {
+            ScoreStatus::SuspiciousScore => {
+                score.z_score = self.z_score(score.skill_adjusted);
+                self.pending_review.insert(player_id.to_string(), score.clone());
+            }
+            ScoreStatus::Ranked => {
+                self.record_history(player_id, raw_score);
+                let is_best = self.rankings
+                    .get(player_id)
+                    .is_none_or(|best| score.skill_adjusted > best.skill_adjusted);
+                if is_best {
+                    self.rankings.insert(player_id.to_string(), score.clone());
+                }
+                self.refresh_z_scores();
+                score.z_score = self.z_score(score.skill_adjusted);
+            }
+        }
+        score
+    }
+
+    /// Accepts a score held for review into the public rankings
+    pub fn approve(&mut self, player_id: &str) -> Option<AdjustedScore> {
+        let mut score = self.pending_review.remove(player_id)?;
+        score.status = ScoreStatus::Ranked;
+        self.record_history(player_id, score.raw_score);
+        let is_best = self.rankings
+            .get(player_id)
+            .is_none_or(|best| score.skill_adjusted > best.skill_adjusted);
+        if is_best {
+            self.rankings.insert(player_id.to_string(), score.clone());
+        }
+        self.refresh_z_scores();
+        score.z_score = self.z_score(score.skill_adjusted);
+        Some(score)
+    }
+
+    /// Highest-ranked players, best first
+    pub fn top_n(&self, n: usize) -> Vec<(String, AdjustedScore)> {
+        let mut ranked: Vec<(String, AdjustedScore)> = self.rankings
+            .iter()
+            .map(|(player_id, score)| (player_id.clone(), score.clone()))
+            .collect();
+        ranked.sort_by(|(a_id, a), (b_id, b)| {
+            b.skill_adjusted.total_cmp(&a.skill_adjusted).then_with(|| a_id.cmp(b_id))
+        });
+        ranked.truncate(n);
+        ranked
+    }
+
+    fn is_anomalous(&self, player_id: &str, raw_score: f32) -> bool {
+        let Some(history) = self.score_history.get(player_id) else {
+            return false;
+        };
+        if history.len() < MIN_HISTORY_FOR_ANOMALY {
+            return false;
+        }
+
+        let (mean, std_dev) = mean_and_std_dev(history.iter().copied());
+        // A perfectly consistent player would otherwise flag any improvement
+        let std_dev = std_dev.max(mean.abs() * 0.05).max(f32::EPSILON);
+        raw_score > mean + SUSPICIOUS_SCORE_SIGMA * std_dev
+    }
+
+    fn record_history(&mut self, player_id: &str, raw_score: f32) {
+        let history = self.score_history.entry(player_id.to_string()).or_default();
+        if history.len() >= self.history_length {
+            history.pop_front();
+        }
+        history.push_back(raw_score);
+    }
+
+    fn z_score(&self, skill_adjusted: f32) -> f32 {
+        let (mean, std_dev) = mean_and_std_dev(self.rankings.values().map(|score| score.skill_adjusted));
+        if std_dev > f32::EPSILON {
+            (skill_adjusted - mean) / std_dev
+        } else {
+            0.0
+        }
+    }
+
+    fn refresh_z_scores(&mut self) {
+        let (mean, std_dev) = mean_and_std_dev(self.rankings.values().map(|score| score.skill_adjusted));
+        for score in self.rankings.values_mut() {
+            score.z_score = if std_dev > f32::EPSILON {
+                (score.skill_adjusted - mean) / std_dev
+            } else {
+                0.0
+            };
+        }
+    }
+}
+
+impl Default for Leaderboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Skill in the player's current activity, or their average skill when that
+/// activity has no assessment yet
+fn domain_skill(profile: &PlayerProfile) -> f32 {
+    let activity = &profile.current_state.session_progress.current_activity;
+    if let Some(skill) = profile.skill_levels.get(activity) {
+        return skill.current_level.clamp(0.0, 1.0);
+    }
+    if profile.skill_levels.is_empty() {
+        return 0.5;
+    }
+    let total: f32 = profile.skill_levels.values().map(|skill| skill.current_level).sum();
+    (total / profile.skill_levels.len() as f32).clamp(0.0, 1.0)
+}
+
+/// Scales scores from sessions much shorter than the player's average down
+/// towards zero
+fn session_penalty(profile: &PlayerProfile, now: DateTime<Utc>) -> f32 {
+    let history = &profile.play_history;
+    if history.sessions_completed == 0 || history.total_play_time <= 0.0 {
+        return 1.0;
+    }
+
+    let typical_hours = history.total_play_time / history.sessions_completed as f32;
+    let session_hours = (now - profile.current_state.session_progress.session_start)
+        .num_seconds()
+        .max(0) as f32 / 3600.0;
+    (session_hours / (typical_hours * SHORT_SESSION_FRACTION)).clamp(0.0, 1.0)
+}
+
+fn mean_and_std_dev(values: impl Iterator<Item = f32> + Clone) -> (f32, f32) {
+    let count = values.clone().count();
+    if count == 0 {
+        return (0.0, 0.0);
+    }
+    let mean = values.clone().sum::<f32>() / count as f32;
+    let variance = values.map(|value| (value - mean).powi(2)).sum::<f32>() / count as f32;
+    (mean, variance.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::ai_game::SkillLevel;
+    use chrono::Duration;
+
+    fn profile(skill: f32, session_minutes: i64, now: DateTime<Utc>) -> PlayerProfile {
+        let mut profile = PlayerProfile::default();
+        profile.current_state.session_progress.current_activity = "bridges".to_string();
+        profile.current_state.session_progress.session_start = now - Duration::minutes(session_minutes);
+        profile.play_history.sessions_completed = 10;
+        profile.play_history.total_play_time = 10.0; // one hour per session
+        profile.skill_levels.insert("bridges".to_string(), SkillLevel {
+            current_level: skill,
+            progression_rate: 0.1,
+            consistency: 0.8,
+            peak_performance: skill,
+            practice_time: 5.0,
+            last_assessment: now,
+            stability: 1.0,
+            last_reviewed: std::time::SystemTime::now(),
+        });
+        profile
+    }
+
+    #[test]
+    fn test_skill_and_short_session_adjustment() {
+        let now = Utc::now();
+        let mut leaderboard = Leaderboard::new();
+
+        let novice = leaderboard.submit("novice", 100.0, Some(&profile(0.2, 60, now)), now);
+        let expert = leaderboard.submit("expert", 100.0, Some(&profile(0.9, 60, now)), now);
+        assert!(novice.skill_adjusted > expert.skill_adjusted);
+        assert_eq!(novice.session_penalty, 1.0);
+
+        // 3 minutes against a one-hour average is a fifth of the 15 minute cutoff
+        let rushed = leaderboard.submit("rushed", 100.0, Some(&profile(0.2, 3, now)), now);
+        assert!((rushed.session_penalty - 0.2).abs() < 1e-3);
+        assert!(rushed.skill_adjusted < novice.skill_adjusted);
+    }
+
+    #[test]
+    fn test_top_n_orders_by_adjusted_score_with_z_scores() {
+        let now = Utc::now();
+        let mut leaderboard = Leaderboard::new();
+        for (player_id, raw) in [("a", 40.0), ("b", 90.0), ("c", 60.0), ("d", 75.0)] {
+            leaderboard.submit(player_id, raw, None, now);
+        }
+        // A worse later attempt doesn't replace the best one
+        leaderboard.submit("b", 10.0, None, now);
+
+        let top: Vec<(String, AdjustedScore)> = leaderboard.top_n(3);
+        let ids: Vec<&str> = top.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["b", "d", "c"]);
+        assert!(top[0].1.z_score > 1.0);
+        let z_sum: f32 = leaderboard.rankings.values().map(|score| score.z_score).sum();
+        assert!(z_sum.abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_outlier_is_held_for_review() {
+        let now = Utc::now();
+        let mut leaderboard = Leaderboard::new();
+        for raw in [48.0, 52.0, 50.0, 49.0, 51.0, 50.0] {
+            leaderboard.submit("player", raw, None, now);
+        }
+        leaderboard.submit("rival", 70.0, None, now);
+
+        let spike = leaderboard.submit("player", 500.0, None, now);
+        assert_eq!(spike.status, ScoreStatus::SuspiciousScore);
+        assert!(leaderboard.pending_review.contains_key("player"));
+        assert_eq!(leaderboard.top_n(1)[0].0, "rival");
+        assert_eq!(leaderboard.rankings["player"].raw_score, 52.0);
+
+        // A plausible improvement is still ranked
+        let improved = leaderboard.submit("player", 56.0, None, now);
+        assert_eq!(improved.status, ScoreStatus::Ranked);
+
+        leaderboard.approve("player").unwrap();
+        assert_eq!(leaderboard.top_n(1)[0].0, "player");
+        assert!(leaderboard.pending_review.is_empty());
+    }
+
+    #[test]
+    fn test_new_players_are_not_flagged() {
+        let now = Utc::now();
+        let mut leaderboard = Leaderboard::new();
+        for raw in [10.0, 10.0, 10.0, 10.0] {
+            leaderboard.submit("player", raw, None, now);
+        }
+        assert_eq!(leaderboard.submit("player", 1000.0, None, now).status, ScoreStatus::Ranked);
+    }
+}
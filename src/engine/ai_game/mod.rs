@@ -4,12 +4,17 @@
 use crate::engine::error::RobinResult;
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
+use std::time::SystemTime;
 
 pub mod player_analytics;
 pub mod dynamic_adaptation;
 pub mod player_state_analysis;
 pub mod procedural_generation;
+pub mod objective_evaluator;
 pub mod game_balancing;
+pub mod leaderboard;
+pub mod profile_encryption;
+pub mod achievements;
 
 /// Main Game AI coordinator for the Robin Engine
 #[derive(Debug)]
@@ -19,6 +24,10 @@ pub struct GameAIManager {
     pub player_state: player_state_analysis::PlayerStateAnalysis,
     pub procedural_gen: procedural_generation::ProceduralGeneration,
     pub game_balancing: game_balancing::GameBalancing,
+    pub leaderboard: leaderboard::Leaderboard,
+    /// Achievements every player can unlock. Holds no unlocks of its own
+    /// between calls; each player's are kept on their profile.
+    pub achievements: achievements::AchievementSystem,
     pub player_profiles: HashMap<String, PlayerProfile>,
     pub game_config: GameAIConfiguration,
     pub performance_metrics: GamePerformanceMetrics,
@@ -37,6 +46,9 @@ pub struct PlayerProfile {
     pub building_style: BuildingStyle,
     pub social_preferences: SocialPreferences,
     pub performance_trends: PerformanceTrends,
+    /// Achievements unlocked so far, keyed by achievement id
+    #[serde(default)]
+    pub unlocked_achievements: HashMap<String, achievements::UnlockRecord>,
 }
 
 /// Play styles for different types of players
@@ -73,6 +85,54 @@ pub struct SkillLevel {
     pub peak_performance: f32,   // Best recorded performance
     pub practice_time: f32,      // Time spent practicing this skill
     pub last_assessment: chrono::DateTime<chrono::Utc>,
+    /// Days the skill takes to fade to 1/e of its peak; grows with each review
+    #[serde(default = "default_skill_stability")]
+    pub stability: f32,
+    #[serde(default = "SystemTime::now")]
+    pub last_reviewed: SystemTime,
+}
+
+/// Days a skill can go unreviewed before `GameAIManager::update` decays it
+pub const SKILL_DECAY_GRACE_DAYS: f32 = 3.0;
+
+/// Fraction of its peak a skill never decays below
+const SKILL_DECAY_FLOOR: f32 = 0.1;
+
+/// Stability a skill gains each time it's reviewed
+const SKILL_REVIEW_STABILITY_GAIN: f32 = 0.2;
+
+const SECONDS_PER_DAY: f32 = 86_400.0;
+
+fn default_skill_stability() -> f32 {
+    1.0
+}
+
+impl SkillLevel {
+    /// Fades the skill along its forgetting curve, `e^(-days / stability)`
+    /// of its peak, but never below a tenth of the peak. Returns the new
+    /// current level.
+    pub fn apply_decay(&mut self, days_elapsed: f32) -> f32 {
+        self.peak_performance = self.peak_performance.max(self.current_level);
+        let retention = (-days_elapsed.max(0.0) / self.stability).exp();
+        self.current_level = self
+            .current_level
+            .min(self.peak_performance * retention)
+            .max(self.peak_performance * SKILL_DECAY_FLOOR);
+        self.current_level
+    }
+
+    /// Records a successful review, restarting the forgetting curve and
+    /// making the skill slower to fade
+    pub fn review(&mut self) {
+        self.last_reviewed = SystemTime::now();
+        self.stability += SKILL_REVIEW_STABILITY_GAIN;
+    }
+
+    /// Days since the skill was last reviewed
+    pub fn days_since_review(&self, now: SystemTime) -> f32 {
+        now.duration_since(self.last_reviewed)
+            .map_or(0.0, |idle| idle.as_secs_f32() / SECONDS_PER_DAY)
+    }
 }
 
 /// Game preferences for adaptation
@@ -163,7 +223,9 @@ pub struct PlayHistory {
     pub sessions_completed: u32,       // Number of play sessions
     pub projects_built: u32,          // Number of building projects
     pub challenges_completed: u32,     // Number of challenges finished
-    pub collaboration_time: f32,      // Time spent in multiplayer
+    pub collaboration_time: f32,      // Hours spent in multiplayer
+    #[serde(default)]
+    pub voxels_placed: u32,           // Voxels placed across all sessions
     pub favorite_activities: Vec<String>, // Most engaged activities
     pub achievement_progress: HashMap<String, f32>, // Achievement completion
     pub play_patterns: Vec<PlayPattern>,
@@ -360,6 +422,10 @@ pub struct GameAIConfiguration {
     pub privacy_level: PrivacyLevel,
     pub data_retention_days: u32,
     pub adaptation_sensitivity: f32,
+    /// Difficulty PID gains (proportional, integral, derivative)
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
 }
 
 /// Privacy levels for game AI
@@ -435,6 +501,12 @@ pub enum GameAIEvent {
         milestone_type: String,
         achievement: String,
     },
+    /// The player could use a nudge from the AI
+    InterventionTriggered {
+        player_id: String,
+        reason: String,
+        progress: f32,
+    },
 }
 
 /// Game AI recommendations
@@ -483,6 +555,10 @@ pub struct ExpectedImpact {
     pub retention_improvement: f32,
 }
 
+/// `PlayerInteraction::context_data` key for how many voxels the interaction
+/// placed
+pub const VOXELS_PLACED_KEY: &str = "voxels_placed";
+
 /// Player interaction data for AI processing
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerInteraction {
@@ -701,6 +777,8 @@ impl GameAIManager {
             player_state: player_state_analysis::PlayerStateAnalysis::new(),
             procedural_gen: procedural_generation::ProceduralGeneration::new(),
             game_balancing: game_balancing::GameBalancing::new(),
+            leaderboard: leaderboard::Leaderboard::new(),
+            achievements: achievements::AchievementSystem::default(),
             player_profiles: HashMap::new(),
             game_config: GameAIConfiguration {
                 adaptation_enabled: true,
@@ -711,6 +789,9 @@ impl GameAIManager {
                 privacy_level: PrivacyLevel::Standard,
                 data_retention_days: 90,
                 adaptation_sensitivity: 0.7,
+                kp: 0.5,
+                ki: 0.05,
+                kd: 0.1,
             },
             performance_metrics: GamePerformanceMetrics {
                 player_satisfaction: 0.8,
@@ -728,6 +809,7 @@ impl GameAIManager {
     pub fn initialize(&mut self) -> RobinResult<()> {
         self.player_analytics.initialize()?;
         self.dynamic_adaptation.initialize()?;
+        self.dynamic_adaptation.set_pid_gains(self.game_config.kp, self.game_config.ki, self.game_config.kd);
         self.player_state.initialize()?;
         self.procedural_gen.initialize()?;
         self.game_balancing.initialize()?;
@@ -747,15 +829,65 @@ impl GameAIManager {
         events.extend(self.procedural_gen.update(delta_time)?);
         events.extend(self.game_balancing.update(delta_time)?);
 
+        let shifted_players = self.detect_play_style_shifts(&mut events);
+
         // Update player profiles based on new data
         self.update_player_profiles(&events)?;
 
+        // Players whose style changed get recommendations for the new style
+        for player_id in shifted_players {
+            for recommendation in self.get_content_recommendations(&player_id) {
+                events.push(GameAIEvent::ContentRecommendation {
+                    player_id: player_id.clone(),
+                    content_type: "play_style_refresh".to_string(),
+                    recommendation,
+                    confidence: 0.8,
+                });
+            }
+        }
+
+        self.decay_unreviewed_skills();
+
         // Update performance metrics
         self.update_performance_metrics()?;
 
         Ok(events)
     }
 
+    /// Fades every skill left unreviewed for longer than
+    /// `SKILL_DECAY_GRACE_DAYS`
+    fn decay_unreviewed_skills(&mut self) {
+        let now = SystemTime::now();
+        let skills = self.player_profiles.values_mut().flat_map(|profile| profile.skill_levels.values_mut());
+        for skill in skills {
+            let days = skill.days_since_review(now);
+            if days > SKILL_DECAY_GRACE_DAYS {
+                skill.apply_decay(days);
+            }
+        }
+    }
+
+    /// Emit `PlayStyleDetected` for players whose recent sessions show a new
+    /// play style, returning their ids
+    fn detect_play_style_shifts(&self, events: &mut Vec<GameAIEvent>) -> Vec<String> {
+        let mut shifted_players = Vec::new();
+        for (player_id, profile) in &self.player_profiles {
+            let shift = player_analytics::PlayerAnalytics::detect_play_style_shift(
+                &profile.play_history.play_patterns,
+                player_analytics::PLAY_STYLE_WINDOW,
+            );
+            if let Some(shift) = shift.filter(|shift| shift.to != profile.play_style.primary_style) {
+                events.push(GameAIEvent::PlayStyleDetected {
+                    player_id: player_id.clone(),
+                    new_style: shift.to,
+                    confidence: shift.confidence,
+                });
+                shifted_players.push(player_id.clone());
+            }
+        }
+        shifted_players
+    }
+
     /// Update player profiles based on AI events
     fn update_player_profiles(&mut self, events: &[GameAIEvent]) -> RobinResult<()> {
         for event in events {
@@ -827,6 +959,7 @@ impl GameAIManager {
     /// Process player interaction data
     pub fn process_interaction(&mut self, player_id: &str, interaction: PlayerInteraction) -> RobinResult<Vec<GameAIEvent>> {
         let mut events = Vec::new();
+        self.record_play_history(player_id, &interaction);
 
         // Process interaction through various AI systems
         events.extend(self.player_analytics.process_interaction(player_id, &interaction)?);
@@ -836,6 +969,23 @@ impl GameAIManager {
         Ok(events)
     }
 
+    /// Adds the interaction to the player's lifetime counters: the voxels it
+    /// reports placing under `VOXELS_PLACED_KEY`, and its duration if it was
+    /// spent collaborating
+    fn record_play_history(&mut self, player_id: &str, interaction: &PlayerInteraction) {
+        let Some(profile) = self.player_profiles.get_mut(player_id) else {
+            return;
+        };
+        let history = &mut profile.play_history;
+        if let Some(&placed) = interaction.context_data.get(VOXELS_PLACED_KEY) {
+            history.voxels_placed = history.voxels_placed.saturating_add(placed.max(0.0) as u32);
+        }
+        if matches!(interaction.interaction_type, InteractionType::Collaborating) {
+            // Durations are in seconds, collaboration time in hours
+            history.collaboration_time += interaction.duration.max(0.0) / 3600.0;
+        }
+    }
+
     /// Get optimal difficulty for a player
     pub fn get_optimal_difficulty(&self, player_id: &str) -> f32 {
         if let Some(profile) = self.player_profiles.get(player_id) {
@@ -888,6 +1038,27 @@ impl GameAIManager {
         }
     }
 
+    /// Score a submission against the player's skill, session length and the
+    /// cohort, and record it on the leaderboard
+    pub fn compute_adjusted_ranking(&mut self, player_id: &str, raw_score: f32) -> leaderboard::AdjustedScore {
+        let profile = self.player_profiles.get(player_id);
+        self.leaderboard.submit(player_id, raw_score, profile, chrono::Utc::now())
+    }
+
+    /// Public leaderboard, best first; scores pending review are excluded
+    pub fn top_n(&self, n: usize) -> Vec<(String, leaderboard::AdjustedScore)> {
+        self.leaderboard.top_n(n)
+    }
+
+    /// Unlocks the achievements the player has newly earned, recording them
+    /// on their profile, and returns them. Unknown players earn nothing.
+    pub fn check_achievements(&mut self, player_id: &str) -> Vec<achievements::Achievement> {
+        match self.player_profiles.get_mut(player_id) {
+            Some(profile) => self.achievements.check_all(profile),
+            None => Vec::new(),
+        }
+    }
+
     /// Calculate compatibility between two players
     fn calculate_compatibility(&self, player1: &PlayerProfile, player2: &PlayerProfile) -> f32 {
         let style_compatibility = match (&player1.play_style.primary_style, &player2.play_style.primary_style) {
@@ -919,7 +1090,11 @@ impl GameAIManager {
 
     /// End a player session
     pub fn end_player_session(&mut self, player_id: &str) -> RobinResult<PlayerProfile> {
-        self.player_analytics.end_session(player_id)?;
+        if self.player_analytics.end_session(player_id)? {
+            if let Some(profile) = self.player_profiles.get_mut(player_id) {
+                profile.play_history.sessions_completed += 1;
+            }
+        }
         Ok(self.player_profiles.get(player_id).cloned().unwrap_or_default())
     }
 
@@ -1179,6 +1354,7 @@ impl Default for PlayerProfile {
                 projects_built: 0,
                 challenges_completed: 0,
                 collaboration_time: 0.0,
+                voxels_placed: 0,
                 favorite_activities: Vec::new(),
                 achievement_progress: HashMap::new(),
                 play_patterns: Vec::new(),
@@ -1242,6 +1418,58 @@ impl Default for PlayerProfile {
                 growth_areas: Vec::new(),
                 strength_areas: Vec::new(),
             },
+            unlocked_achievements: HashMap::new(),
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn manager_with_skill(level: f32, days_since_review: u64) -> GameAIManager {
+        let mut profile = PlayerProfile::default();
+        profile.skill_levels.insert("bridges".to_string(), SkillLevel {
+            current_level: level,
+            progression_rate: 0.1,
+            consistency: 0.8,
+            peak_performance: level,
+            practice_time: 5.0,
+            last_assessment: chrono::Utc::now(),
+            stability: default_skill_stability(),
+            last_reviewed: SystemTime::now() - Duration::from_secs(days_since_review * 86_400),
+        });
+        let mut manager = GameAIManager::new();
+        manager.player_profiles.insert("player".to_string(), profile);
+        manager
+    }
+
+    fn skill(manager: &mut GameAIManager) -> &mut SkillLevel {
+        manager.player_profiles.get_mut("player").unwrap().skill_levels.get_mut("bridges").unwrap()
+    }
+
+    #[test]
+    fn test_unpractised_skill_decays_to_its_floor() {
+        let mut manager = manager_with_skill(0.9, 30);
+        manager.update(0.016).unwrap();
+        let level = skill(&mut manager).current_level;
+        assert!(level < 0.5, "{}", level);
+        assert!(level >= 0.9 * SKILL_DECAY_FLOOR - f32::EPSILON);
+
+        // Decaying the same stretch again doesn't lose any more
+        manager.update(0.016).unwrap();
+        assert_eq!(skill(&mut manager).current_level, level);
+    }
+
+    #[test]
+    fn test_reviewing_before_decay_resets_the_timer() {
+        let mut manager = manager_with_skill(0.9, 30);
+        skill(&mut manager).review();
+        manager.update(0.016).unwrap();
+
+        let skill = skill(&mut manager);
+        assert_eq!(skill.current_level, 0.9);
+        assert!(skill.days_since_review(SystemTime::now()) < SKILL_DECAY_GRACE_DAYS);
+        assert!((skill.stability - 1.2).abs() < 1e-6);
+    }
+}
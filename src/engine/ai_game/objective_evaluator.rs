@@ -0,0 +1,391 @@
+// Robin Game Engine - Building Objective Evaluation
+// Checks challenge objectives against what a player has actually built, and
+// scores partial progress so the AI can tell how close they are
+
+use crate::engine::generation::voxel_system::{VoxelType, VoxelWorld};
+use crate::engine::math::Vec3;
+use super::procedural_generation::{Axis, BuildingObjective, CrystalColor};
+use super::GameAIEvent;
+use std::collections::{HashSet, VecDeque};
+
+/// Overall progress at which a player still building gets a nudge to finish
+pub const NEARLY_COMPLETE: f32 = 0.9;
+/// Seconds between re-evaluations of a monitored challenge
+pub const EVALUATION_INTERVAL: f32 = 2.0;
+
+/// Outcome of checking one objective against a world
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvaluationResult {
+    pub passed: bool,
+    /// Progress towards the objective, from 0 to 1; 1 when passed
+    pub partial_score: f32,
+    /// What's still wrong, in words a player can act on
+    pub failing_constraints: Vec<String>,
+}
+
+impl EvaluationResult {
+    fn pass() -> Self {
+        Self { passed: true, partial_score: 1.0, failing_constraints: Vec::new() }
+    }
+
+    fn fail(partial_score: f32, reason: String) -> Self {
+        Self { passed: false, partial_score: partial_score.clamp(0.0, 1.0), failing_constraints: vec![reason] }
+    }
+}
+
+/// Evaluates `BuildingObjective`s against a voxel world
+pub struct ObjectiveEvaluator;
+
+impl ObjectiveEvaluator {
+    pub fn evaluate(world: &VoxelWorld, objective: &BuildingObjective) -> EvaluationResult {
+        match *objective {
+            BuildingObjective::ConnectPoints { from, to } => Self::evaluate_connection(world, from, to, |_| true),
+            BuildingObjective::WireCircuit { color, source, sink } => Self::evaluate_connection(world, source, sink, |voxel_type| {
+                CrystalColor::from_voxel_type(voxel_type) == Some(color)
+            }),
+            BuildingObjective::MinHeight(height) => Self::evaluate_min_height(world, height),
+            BuildingObjective::MaxBlocks(count, voxel_type) => Self::evaluate_max_blocks(world, count, voxel_type),
+            BuildingObjective::NoFloatingVoxels => Self::evaluate_no_floating_voxels(world),
+            BuildingObjective::SymmetryAxis(axis, tolerance) => Self::evaluate_symmetry(world, axis, tolerance),
+        }
+    }
+
+    /// Mean partial score across `objectives`, and whether all of them passed
+    pub fn overall_progress(world: &VoxelWorld, objectives: &[BuildingObjective]) -> (f32, bool) {
+        if objectives.is_empty() {
+            return (1.0, true);
+        }
+        let results: Vec<EvaluationResult> = objectives.iter().map(|objective| Self::evaluate(world, objective)).collect();
+        let progress = results.iter().map(|result| result.partial_score).sum::<f32>() / results.len() as f32;
+        (progress, results.iter().all(|result| result.passed))
+    }
+
+    /// Flood fills from `from` through voxels accepted by `passable`. Partial
+    /// credit is how much closer to `to` the filled region gets than `from`.
+    fn evaluate_connection(
+        world: &VoxelWorld,
+        from: (i32, i32, i32),
+        to: (i32, i32, i32),
+        passable: impl Fn(VoxelType) -> bool,
+    ) -> EvaluationResult {
+        let reached = match grid_position(from) {
+            Some(start) => world.flood_fill_reachable(start, |voxel| {
+                voxel.is_some_and(|voxel_type| voxel_type != VoxelType::Air && passable(voxel_type))
+            }),
+            None => HashSet::new(),
+        };
+        if grid_position(to).is_some_and(|to| reached.contains(&to)) {
+            return EvaluationResult::pass();
+        }
+
+        let distance = manhattan(from, to).max(1) as f32;
+        let closest = reached
+            .iter()
+            .map(|&(x, y, z)| manhattan((x as i32, y as i32, z as i32), to))
+            .min();
+        let partial_score = closest.map_or(0.0, |closest| 1.0 - closest as f32 / distance);
+        EvaluationResult::fail(partial_score, format!("{:?} is not connected to {:?}", from, to))
+    }
+
+    fn evaluate_min_height(world: &VoxelWorld, height: i32) -> EvaluationResult {
+        let top = solid_voxels(world).into_iter().map(|((_, y, _), _)| y).max();
+        match top {
+            Some(top) if top >= height => EvaluationResult::pass(),
+            _ => {
+                let reached = top.map_or(0, |top| top + 1);
+                EvaluationResult::fail(
+                    reached as f32 / (height + 1) as f32,
+                    format!("The structure is {} blocks tall and needs to reach y = {}", reached, height),
+                )
+            }
+        }
+    }
+
+    fn evaluate_max_blocks(world: &VoxelWorld, count: usize, voxel_type: VoxelType) -> EvaluationResult {
+        let used = solid_voxels(world).into_iter().filter(|&(_, placed)| placed == voxel_type).count();
+        if used <= count {
+            return EvaluationResult::pass();
+        }
+        EvaluationResult::fail(
+            count as f32 / used as f32,
+            format!("{} {:?} blocks are used, but at most {} are allowed", used, voxel_type, count),
+        )
+    }
+
+    /// Every voxel must be joined to one on the ground (y = 0) through
+    /// face-adjacent voxels
+    fn evaluate_no_floating_voxels(world: &VoxelWorld) -> EvaluationResult {
+        let voxels: Vec<(i32, i32, i32)> = solid_voxels(world).into_iter().map(|(position, _)| position).collect();
+        let ground = voxels.iter().copied().filter(|&(_, y, _)| y == 0).collect();
+        let grounded = flood_fill(ground, |position| solid_voxel(world, position).is_some());
+
+        let floating: Vec<_> = voxels.iter().filter(|position| !grounded.contains(position)).collect();
+        match floating.first() {
+            None => EvaluationResult::pass(),
+            Some(example) => EvaluationResult::fail(
+                grounded.len() as f32 / voxels.len() as f32,
+                format!("{} blocks aren't supported from the ground, such as {:?}", floating.len(), example),
+            ),
+        }
+    }
+
+    /// Mirrors the structure across the middle of its bounding box on `axis`.
+    /// Passes if no more than `tolerance` of its voxels lack a matching
+    /// voxel of the same type on the other side.
+    fn evaluate_symmetry(world: &VoxelWorld, axis: Axis, tolerance: f32) -> EvaluationResult {
+        let voxels = solid_voxels(world);
+        if voxels.is_empty() {
+            return EvaluationResult::pass();
+        }
+        let coordinate = |(x, y, z): (i32, i32, i32)| match axis {
+            Axis::X => x,
+            Axis::Y => y,
+            Axis::Z => z,
+        };
+        let low = voxels.iter().map(|&(position, _)| coordinate(position)).min().unwrap();
+        let high = voxels.iter().map(|&(position, _)| coordinate(position)).max().unwrap();
+        let mirror = |(x, y, z): (i32, i32, i32)| match axis {
+            Axis::X => (low + high - x, y, z),
+            Axis::Y => (x, low + high - y, z),
+            Axis::Z => (x, y, low + high - z),
+        };
+
+        let matched = voxels
+            .iter()
+            .filter(|&&(position, voxel_type)| solid_voxel(world, mirror(position)) == Some(voxel_type))
+            .count();
+        let symmetry = matched as f32 / voxels.len() as f32;
+        if 1.0 - symmetry <= tolerance {
+            return EvaluationResult::pass();
+        }
+        EvaluationResult::fail(
+            symmetry,
+            format!(
+                "{:.0}% of blocks have no mirror image across the {:?} axis; at most {:.0}% may differ",
+                (1.0 - symmetry) * 100.0,
+                axis,
+                tolerance * 100.0
+            ),
+        )
+    }
+}
+
+/// Re-evaluates a player's challenge as they build, and fires
+/// `GameAIEvent::InterventionTriggered` once when they're `NEARLY_COMPLETE`
+/// but haven't finished, to encourage them over the line
+#[derive(Debug, Clone)]
+pub struct ChallengeMonitor {
+    player_id: String,
+    objectives: Vec<BuildingObjective>,
+    time_since_evaluation: f32,
+    encouraged: bool,
+    progress: f32,
+}
+
+impl ChallengeMonitor {
+    pub fn new(player_id: &str, objectives: Vec<BuildingObjective>) -> Self {
+        Self {
+            player_id: player_id.to_string(),
+            objectives,
+            // Evaluate on the first update
+            time_since_evaluation: EVALUATION_INTERVAL,
+            encouraged: false,
+            progress: 0.0,
+        }
+    }
+
+    /// Overall progress at the last evaluation
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    pub fn update(&mut self, world: &VoxelWorld, delta_time: f32) -> Option<GameAIEvent> {
+        self.time_since_evaluation += delta_time;
+        if self.time_since_evaluation < EVALUATION_INTERVAL {
+            return None;
+        }
+        self.time_since_evaluation = 0.0;
+
+        let (progress, complete) = ObjectiveEvaluator::overall_progress(world, &self.objectives);
+        self.progress = progress;
+        if complete || progress < NEARLY_COMPLETE || self.encouraged {
+            return None;
+        }
+        self.encouraged = true;
+        Some(GameAIEvent::InterventionTriggered {
+            player_id: self.player_id.clone(),
+            reason: format!("Challenge {:.0}% complete; encourage the player to finish", progress * 100.0),
+            progress,
+        })
+    }
+}
+
+/// The non-air voxel at `position`; anything outside the world is empty
+fn solid_voxel(world: &VoxelWorld, (x, y, z): (i32, i32, i32)) -> Option<VoxelType> {
+    let (size_x, size_y, size_z) = world.world_size;
+    let in_bounds = x >= 0 && y >= 0 && z >= 0 && (x as usize) < size_x && (y as usize) < size_y && (z as usize) < size_z;
+    if !in_bounds {
+        return None;
+    }
+    world
+        .get_voxel(Vec3::new(x as f32, y as f32, z as f32))
+        .filter(|voxel_type| *voxel_type != VoxelType::Air)
+}
+
+/// `position` as a world grid position, if no coordinate is negative
+fn grid_position((x, y, z): (i32, i32, i32)) -> Option<(usize, usize, usize)> {
+    Some((usize::try_from(x).ok()?, usize::try_from(y).ok()?, usize::try_from(z).ok()?))
+}
+
+fn solid_voxels(world: &VoxelWorld) -> Vec<((i32, i32, i32), VoxelType)> {
+    let (size_x, size_y, size_z) = world.world_size;
+    let mut voxels = Vec::new();
+    for x in 0..size_x as i32 {
+        for y in 0..size_y as i32 {
+            for z in 0..size_z as i32 {
+                if let Some(voxel_type) = solid_voxel(world, (x, y, z)) {
+                    voxels.push(((x, y, z), voxel_type));
+                }
+            }
+        }
+    }
+    voxels
+}
+
+/// Every position reachable from `starts` through face-adjacent positions
+/// accepted by `passable`
+fn flood_fill(starts: Vec<(i32, i32, i32)>, passable: impl Fn((i32, i32, i32)) -> bool) -> HashSet<(i32, i32, i32)> {
+    let mut visited: HashSet<_> = starts.iter().copied().collect();
+    let mut frontier = VecDeque::from(starts);
+    while let Some((x, y, z)) = frontier.pop_front() {
+        for neighbor in [(x + 1, y, z), (x - 1, y, z), (x, y + 1, z), (x, y - 1, z), (x, y, z + 1), (x, y, z - 1)] {
+            if passable(neighbor) && visited.insert(neighbor) {
+                frontier.push_back(neighbor);
+            }
+        }
+    }
+    visited
+}
+
+fn manhattan(a: (i32, i32, i32), b: (i32, i32, i32)) -> i32 {
+    (a.0 - b.0).abs() + (a.1 - b.1).abs() + (a.2 - b.2).abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world_with(voxels: &[((i32, i32, i32), VoxelType)]) -> VoxelWorld {
+        let mut world = VoxelWorld::new("objective_test".to_string(), (8, 8, 8));
+        for &((x, y, z), voxel_type) in voxels {
+            world.set_voxel(Vec3::new(x as f32, y as f32, z as f32), voxel_type);
+        }
+        world
+    }
+
+    /// A column of stone from the ground up to `height`, at (x, z)
+    fn column(x: i32, z: i32, height: i32) -> Vec<((i32, i32, i32), VoxelType)> {
+        (0..=height).map(|y| ((x, y, z), VoxelType::Stone)).collect()
+    }
+
+    #[test]
+    fn test_min_height() {
+        let objective = BuildingObjective::MinHeight(5);
+        assert!(ObjectiveEvaluator::evaluate(&world_with(&column(2, 2, 5)), &objective).passed);
+
+        let result = ObjectiveEvaluator::evaluate(&world_with(&column(2, 2, 2)), &objective);
+        assert!(!result.passed);
+        assert!((result.partial_score - 0.5).abs() < 1e-6);
+        assert_eq!(result.failing_constraints.len(), 1);
+    }
+
+    #[test]
+    fn test_max_blocks() {
+        let mut voxels = column(0, 0, 3);
+        voxels.push(((1, 0, 0), VoxelType::Glass));
+        let world = world_with(&voxels);
+
+        assert!(ObjectiveEvaluator::evaluate(&world, &BuildingObjective::MaxBlocks(4, VoxelType::Stone)).passed);
+        assert!(ObjectiveEvaluator::evaluate(&world, &BuildingObjective::MaxBlocks(1, VoxelType::Glass)).passed);
+        let result = ObjectiveEvaluator::evaluate(&world, &BuildingObjective::MaxBlocks(2, VoxelType::Stone));
+        assert!(!result.passed);
+        assert!((result.partial_score - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_must_connect() {
+        let objective = BuildingObjective::ConnectPoints { from: (0, 0, 0), to: (4, 0, 0) };
+        let bridge: Vec<_> = (0..=4).map(|x| ((x, 0, 0), VoxelType::Wood)).collect();
+        assert!(ObjectiveEvaluator::evaluate(&world_with(&bridge), &objective).passed);
+
+        // One plank short of the far side
+        let mut gap = bridge.clone();
+        gap.remove(3);
+        let result = ObjectiveEvaluator::evaluate(&world_with(&gap), &objective);
+        assert!(!result.passed);
+        assert!((result.partial_score - 0.5).abs() < 1e-6);
+
+        // Nothing to start from
+        let result = ObjectiveEvaluator::evaluate(&world_with(&bridge[1..]), &objective);
+        assert_eq!(result.partial_score, 0.0);
+    }
+
+    #[test]
+    fn test_no_floating_voxels() {
+        let mut voxels = column(1, 1, 3);
+        voxels.push(((2, 3, 1), VoxelType::Wood));
+        assert!(ObjectiveEvaluator::evaluate(&world_with(&voxels), &BuildingObjective::NoFloatingVoxels).passed);
+
+        voxels.push(((5, 4, 5), VoxelType::Wood));
+        let result = ObjectiveEvaluator::evaluate(&world_with(&voxels), &BuildingObjective::NoFloatingVoxels);
+        assert!(!result.passed);
+        assert!((result.partial_score - 5.0 / 6.0).abs() < 1e-6);
+        assert!(result.failing_constraints[0].contains("(5, 4, 5)"));
+    }
+
+    #[test]
+    fn test_symmetry_axis() {
+        // An arch: two columns joined across the top
+        let mut arch = column(1, 0, 3);
+        arch.extend(column(5, 0, 3));
+        arch.extend((2..5).map(|x| ((x, 3, 0), VoxelType::Stone)));
+        let objective = BuildingObjective::SymmetryAxis(Axis::X, 0.0);
+        assert!(ObjectiveEvaluator::evaluate(&world_with(&arch), &objective).passed);
+
+        // A block inside one leg breaks exact symmetry across x, but is
+        // within a loose tolerance
+        arch.push(((2, 0, 0), VoxelType::Wood));
+        let world = world_with(&arch);
+        let result = ObjectiveEvaluator::evaluate(&world, &objective);
+        assert!(!result.passed);
+        assert!((result.partial_score - 11.0 / 12.0).abs() < 1e-6);
+        assert!(ObjectiveEvaluator::evaluate(&world, &BuildingObjective::SymmetryAxis(Axis::X, 0.1)).passed);
+        assert!(!ObjectiveEvaluator::evaluate(&world, &BuildingObjective::SymmetryAxis(Axis::Y, 0.1)).passed);
+    }
+
+    #[test]
+    fn test_monitor_encourages_once_when_nearly_complete() {
+        let objectives = vec![
+            BuildingObjective::MinHeight(9),
+            BuildingObjective::NoFloatingVoxels,
+        ];
+        let mut monitor = ChallengeMonitor::new("student", objectives);
+
+        // Halfway up: 0.5 and 1.0 average to 0.75
+        assert!(monitor.update(&world_with(&column(3, 3, 4)), 0.1).is_none());
+        assert!((monitor.progress() - 0.75).abs() < 1e-6);
+
+        // Nine tenths of the way: 0.8 and 1.0 average to 0.9, but it's too
+        // soon since the last evaluation
+        let nearly = world_with(&column(3, 3, 7));
+        assert!(monitor.update(&nearly, 0.1).is_none());
+        let event = monitor.update(&nearly, EVALUATION_INTERVAL);
+        assert!(matches!(event, Some(GameAIEvent::InterventionTriggered { ref player_id, .. }) if player_id == "student"));
+        assert!(monitor.update(&nearly, EVALUATION_INTERVAL).is_none());
+
+        // Finishing doesn't need encouragement
+        let mut finished = ChallengeMonitor::new("student", vec![BuildingObjective::MinHeight(7)]);
+        assert!(finished.update(&nearly, 0.0).is_none());
+        assert_eq!(finished.progress(), 1.0);
+    }
+}
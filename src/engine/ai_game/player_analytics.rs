@@ -2,10 +2,17 @@
 // Basic gameplay pattern analysis and performance tracking
 
 use crate::engine::error::RobinResult;
-use super::{PlayerProfile, PlayerInteraction, GameAIEvent, GameAIRecommendation, RecommendationType, Priority};
+use super::{PlayerProfile, PlayerInteraction, GameAIEvent, GameAIRecommendation, RecommendationType, Priority, PlayPattern, PrimaryPlayStyle};
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
+/// Number of recent sessions compared against the baseline for style shifts
+pub const PLAY_STYLE_WINDOW: usize = 10;
+
+/// Recent activity less similar than this to the baseline counts as a shift
+const STYLE_SHIFT_SIMILARITY: f32 = 0.6;
+
 /// Player Analytics system for tracking gameplay patterns
 #[derive(Debug)]
 pub struct PlayerAnalytics {
@@ -103,11 +110,44 @@ impl PlayerAnalytics {
         Ok(())
     }
 
-    pub fn end_session(&mut self, player_id: &str) -> RobinResult<()> {
-        if let Some(_session) = self.player_sessions.remove(player_id) {
+    /// Ends the player's session, returning whether one was open
+    pub fn end_session(&mut self, player_id: &str) -> RobinResult<bool> {
+        let ended = self.player_sessions.remove(player_id).is_some();
+        if ended {
             self.global_metrics.record_session_end();
         }
-        Ok(())
+        Ok(ended)
+    }
+
+    /// Compares the activity mix of the last `window_size` sessions in
+    /// `history` (oldest first, one pattern per session weighted by its
+    /// frequency) against the sessions before them, and reports a shift when
+    /// their cosine similarity drops below 0.6 and the dominant style changed
+    pub fn detect_play_style_shift(history: &[PlayPattern], window_size: usize) -> Option<PlayStyleShift> {
+        if window_size == 0 || history.len() < window_size * 2 {
+            return None;
+        }
+
+        let (baseline, recent) = history.split_at(history.len() - window_size);
+        let baseline = activity_distribution(baseline);
+        let recent = activity_distribution(recent);
+        let similarity = cosine_similarity(&baseline, &recent);
+        if similarity >= STYLE_SHIFT_SIMILARITY {
+            return None;
+        }
+
+        let from = dominant_style(&baseline)?;
+        let to = dominant_style(&recent)?;
+        if from == to {
+            return None;
+        }
+
+        Some(PlayStyleShift {
+            from,
+            to,
+            confidence: (1.0 - similarity).clamp(0.0, 1.0),
+            detected_at: Utc::now(),
+        })
     }
 
     pub fn analyze_patterns(&self, player_id: &str) -> RobinResult<super::BehaviorAnalysis> {
@@ -132,6 +172,52 @@ impl PlayerAnalytics {
     }
 }
 
+/// Share of play time per activity type
+fn activity_distribution(patterns: &[PlayPattern]) -> HashMap<String, f32> {
+    let mut distribution = HashMap::new();
+    for pattern in patterns {
+        *distribution.entry(pattern.activity_type.to_lowercase()).or_insert(0.0) += pattern.frequency.max(0.0);
+    }
+    let total: f32 = distribution.values().sum();
+    if total > 0.0 {
+        distribution.values_mut().for_each(|share| *share /= total);
+    }
+    distribution
+}
+
+fn cosine_similarity(a: &HashMap<String, f32>, b: &HashMap<String, f32>) -> f32 {
+    let dot: f32 = a.iter().map(|(activity, share)| share * b.get(activity).unwrap_or(&0.0)).sum();
+    let norm_a = a.values().map(|share| share * share).sum::<f32>().sqrt();
+    let norm_b = b.values().map(|share| share * share).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 1.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Play style of the largest share of activity that maps to one
+fn dominant_style(distribution: &HashMap<String, f32>) -> Option<PrimaryPlayStyle> {
+    distribution
+        .iter()
+        .filter_map(|(activity, share)| style_for_activity(activity).map(|style| (style, *share)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(style, _)| style)
+}
+
+fn style_for_activity(activity: &str) -> Option<PrimaryPlayStyle> {
+    let style = match activity {
+        "building" | "construction" => PrimaryPlayStyle::Builder,
+        "exploration" | "exploring" => PrimaryPlayStyle::Explorer,
+        "engineering" | "optimization" | "puzzle" => PrimaryPlayStyle::Engineer,
+        "decorating" | "art" | "design" => PrimaryPlayStyle::Artist,
+        "collaboration" | "multiplayer" => PrimaryPlayStyle::Collaborator,
+        "competition" | "challenge" => PrimaryPlayStyle::Competitor,
+        "experimentation" | "sandbox" => PrimaryPlayStyle::Experimenter,
+        _ => return None,
+    };
+    Some(style)
+}
+
 // Supporting structures for player analytics
 
 #[derive(Debug, Clone)]
@@ -216,4 +302,81 @@ pub struct SessionSummary {
     pub duration: Duration,
     pub total_interactions: usize,
     pub engagement_score: f32,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayStyleShift {
+    pub from: PrimaryPlayStyle,
+    pub to: PrimaryPlayStyle,
+    pub confidence: f32,
+    pub detected_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::ai_game::GameAIManager;
+
+    fn session(activity_type: &str) -> PlayPattern {
+        PlayPattern {
+            activity_type: activity_type.to_string(),
+            frequency: 1.0,
+            average_duration: 30.0,
+            success_rate: 0.7,
+            engagement_level: 0.8,
+        }
+    }
+
+    /// Ten sessions at 80% building followed by ten at 80% exploration
+    fn builder_turned_explorer() -> Vec<PlayPattern> {
+        let mut history = Vec::new();
+        for i in 0..10 {
+            history.push(session(if i < 8 { "building" } else { "exploration" }));
+        }
+        for i in 0..10 {
+            history.push(session(if i < 8 { "exploration" } else { "building" }));
+        }
+        history
+    }
+
+    #[test]
+    fn test_detects_builder_to_explorer_shift() {
+        let shift = PlayerAnalytics::detect_play_style_shift(&builder_turned_explorer(), 10).unwrap();
+        assert_eq!(shift.from, PrimaryPlayStyle::Builder);
+        assert_eq!(shift.to, PrimaryPlayStyle::Explorer);
+        // Cosine similarity of (0.8, 0.2) and (0.2, 0.8) is 0.47
+        assert!((shift.confidence - 0.53).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_stable_or_short_history_has_no_shift() {
+        let stable: Vec<PlayPattern> = (0..20).map(|i| session(if i % 5 == 0 { "exploration" } else { "building" })).collect();
+        assert!(PlayerAnalytics::detect_play_style_shift(&stable, 10).is_none());
+
+        let history = builder_turned_explorer();
+        assert!(PlayerAnalytics::detect_play_style_shift(&history[5..], 10).is_none());
+    }
+
+    #[test]
+    fn test_update_refreshes_recommendations_after_shift() {
+        let mut manager = GameAIManager::new();
+        let mut profile = PlayerProfile::default();
+        profile.player_id = "p1".to_string();
+        profile.play_history.play_patterns = builder_turned_explorer();
+        manager.add_player_profile(profile);
+
+        let events = manager.update(0.016).unwrap();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            GameAIEvent::PlayStyleDetected { player_id, new_style: PrimaryPlayStyle::Explorer, .. } if player_id == "p1"
+        )));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            GameAIEvent::ContentRecommendation { recommendation, .. } if recommendation == "Hidden Area Discovery"
+        )));
+        assert_eq!(manager.get_player_profile("p1").unwrap().play_style.primary_style, PrimaryPlayStyle::Explorer);
+
+        // Already applied, so the next update doesn't fire again
+        assert!(manager.update(0.016).unwrap().is_empty());
+    }
+}
@@ -2,8 +2,12 @@
 // AI-driven content creation and world generation for Engineer Build Mode
 
 use crate::engine::error::RobinResult;
+use crate::engine::generation::voxel_system::{VoxelType, VoxelWorld};
+use crate::engine::math::Vec3;
+use cgmath::InnerSpace;
 use super::{GameAIEvent, PlayerProfile, RecommendationType, Priority, ExpectedImpact, GameAIRecommendation};
-use std::collections::{HashMap, VecDeque};
+use super::objective_evaluator::{ChallengeMonitor, ObjectiveEvaluator};
+use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
 use rand::{Rng, SeedableRng};
 use rand::rngs::StdRng;
@@ -22,6 +26,7 @@ pub struct ProceduralGeneration {
     rng: StdRng,
     generation_cache: GenerationCache,
     content_metrics: ContentMetrics,
+    challenge_monitors: HashMap<String, ChallengeMonitor>,
 }
 
 impl ProceduralGeneration {
@@ -43,6 +48,7 @@ impl ProceduralGeneration {
             rng: StdRng::seed_from_u64(seed),
             generation_cache: GenerationCache::new(),
             content_metrics: ContentMetrics::new(),
+            challenge_monitors: HashMap::new(),
         }
     }
 
@@ -103,6 +109,27 @@ impl ProceduralGeneration {
         self.challenge_generator.create_challenge(profile, difficulty)
     }
 
+    pub fn generate_challenge_world(&self, seed: u64, difficulty: f32, skill_domain: SkillDomain) -> ChallengeWorld {
+        self.challenge_generator.generate_challenge_world(seed, difficulty, skill_domain)
+    }
+
+    /// Starts re-evaluating `challenge`'s objectives as `player_id` builds,
+    /// replacing any challenge they were already being monitored on
+    pub fn monitor_challenge(&mut self, player_id: &str, challenge: &ChallengeWorld) {
+        self.challenge_monitors
+            .insert(player_id.to_string(), ChallengeMonitor::new(player_id, challenge.objectives.clone()));
+    }
+
+    /// Checks the player's monitored challenge against their world, raising
+    /// `GameAIEvent::InterventionTriggered` once they're nearly done
+    pub fn update_challenge_progress(&mut self, player_id: &str, world: &VoxelWorld, delta_time: f32) -> Vec<GameAIEvent> {
+        self.challenge_monitors
+            .get_mut(player_id)
+            .and_then(|monitor| monitor.update(world, delta_time))
+            .into_iter()
+            .collect()
+    }
+
     pub fn design_tool(&self, specifications: &ToolSpecifications) -> RobinResult<CustomTool> {
         self.tool_creator.design_tool(specifications)
     }
@@ -119,6 +146,13 @@ impl ProceduralGeneration {
         self.terrain_sculptor.sculpt_terrain(parameters)
     }
 
+    /// Carves a network of caves `cave_width` voxels wide beneath the
+    /// terrain, along the edges of a Voronoi diagram of `cave_count` random
+    /// points, with a shaft up to the surface. See `TerrainSculptor::carve_caves`.
+    pub fn generate_caves(world: &mut VoxelWorld, seed: u64, cave_count: u32, cave_width: f32) -> CaveSystem {
+        TerrainSculptor::carve_caves(world, seed, cave_count, cave_width)
+    }
+
     pub fn generate_recommendations(&self, profile: &PlayerProfile) -> RobinResult<Vec<GameAIRecommendation>> {
         let mut recommendations = Vec::new();
 
@@ -574,6 +608,176 @@ impl ChallengeGenerator {
             objectives: vec!["build_structure".to_string()],
         })
     }
+    /// Builds a puzzle world for the skill domain. The complete solution is
+    /// built first and then pieces are removed, so every world is solvable
+    /// with `ChallengeWorld::reference_solution`.
+    pub fn generate_challenge_world(&self, seed: u64, difficulty: f32, skill_domain: SkillDomain) -> ChallengeWorld {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let difficulty = difficulty.clamp(0.0, 1.0);
+        match skill_domain {
+            SkillDomain::SpatialReasoning => Self::generate_bridge_world(&mut rng, difficulty),
+            SkillDomain::LogicalThinking => Self::generate_wiring_world(&mut rng, difficulty),
+        }
+    }
+
+    /// Cliffs separated by one gap (two above 0.6 difficulty) that the player
+    /// has to bridge at deck height
+    fn generate_bridge_world(rng: &mut StdRng, difficulty: f32) -> ChallengeWorld {
+        const CLIFF_WIDTH: i32 = 6;
+        const ISLAND_WIDTH: i32 = 3;
+        const DECK_Y: i32 = 5;
+        const DECK_Z: i32 = 2;
+        const DEPTH: i32 = 5;
+
+        let gap_count = if difficulty > 0.6 { 2 } else { 1 };
+        let min_gap = 3 + (difficulty * 5.0).round() as i32;
+        let gaps: Vec<i32> = (0..gap_count).map(|_| rng.gen_range(min_gap..=min_gap + 2)).collect();
+
+        // Alternating solid and gap spans along x
+        let mut solid_spans = Vec::new();
+        let mut gap_spans = Vec::new();
+        let mut x = 0;
+        for (i, gap) in gaps.iter().enumerate() {
+            let width = if i == 0 { CLIFF_WIDTH } else { ISLAND_WIDTH };
+            solid_spans.push(x..x + width);
+            gap_spans.push(x + width..x + width + gap);
+            x += width + gap;
+        }
+        solid_spans.push(x..x + CLIFF_WIDTH);
+        let length = x + CLIFF_WIDTH;
+
+        let mut voxel_data = VoxelWorld::new("bridge_challenge".to_string(), (length as usize, 8, DEPTH as usize));
+        for span in &solid_spans {
+            for x in span.clone() {
+                for y in 0..=DECK_Y {
+                    for z in 0..DEPTH {
+                        voxel_data.set_voxel(Vec3::new(x as f32, y as f32, z as f32), VoxelType::Stone);
+                    }
+                }
+            }
+        }
+
+        // The solution is a plank across each gap; easier worlds keep a few
+        // planks from the near edge as a starting point
+        let mut reference_solution = Vec::new();
+        for span in &gap_spans {
+            let kept = ((1.0 - difficulty) * (span.len() / 2) as f32) as usize;
+            for (i, x) in span.clone().enumerate() {
+                let position = (x, DECK_Y, DECK_Z);
+                if i < kept {
+                    voxel_data.set_voxel(Vec3::new(x as f32, DECK_Y as f32, DECK_Z as f32), VoxelType::Wood);
+                } else {
+                    reference_solution.push((position, VoxelType::Wood));
+                }
+            }
+        }
+
+        let slack = ((1.0 - difficulty) * 4.0).round() as usize;
+        ChallengeWorld {
+            voxel_data,
+            objectives: vec![BuildingObjective::ConnectPoints {
+                from: (1, DECK_Y, DECK_Z),
+                to: (length - 2, DECK_Y, DECK_Z),
+            }],
+            constraints: vec![
+                BuildingConstraint::MaxBlocks(reference_solution.len() + slack),
+                BuildingConstraint::AllowedMaterials(vec![VoxelType::Wood, VoxelType::Metal]),
+            ],
+            hint_sequence: vec![
+                "Find the edges of each gap at the top of the cliffs".to_string(),
+                "A bridge needs an unbroken line of blocks from one side to the other".to_string(),
+                format!("You only have {} spare blocks, so build straight across", slack),
+            ],
+            reference_solution,
+        }
+    }
+
+    /// Pairs of same-coloured crystals on a floor, each in its own lane, to
+    /// be joined by crystal wiring without touching other colours
+    fn generate_wiring_world(rng: &mut StdRng, difficulty: f32) -> ChallengeWorld {
+        const WIRE_Y: i32 = 1;
+        const LANE_WIDTH: i32 = 3;
+
+        let colors = &CrystalColor::ALL[..1 + (difficulty * 3.0).round() as usize];
+        let width = 8 + (difficulty * 8.0).round() as i32;
+        // Each lane is followed by an empty separator row
+        let depth = colors.len() as i32 * (LANE_WIDTH + 1) + 1;
+
+        let mut voxel_data = VoxelWorld::new("wiring_challenge".to_string(), (width as usize, 3, depth as usize));
+        for x in 0..width {
+            for z in 0..depth {
+                voxel_data.set_voxel(Vec3::new(x as f32, 0.0, z as f32), VoxelType::Stone);
+            }
+        }
+
+        let mut objectives = Vec::new();
+        let mut reference_solution = Vec::new();
+        for (lane, &color) in colors.iter().enumerate() {
+            let lane_start = lane as i32 * (LANE_WIDTH + 1) + 1;
+            let lane_rows = lane_start..lane_start + LANE_WIDTH;
+
+            // Walk the wire across the lane, drifting between its rows
+            let mut z = rng.gen_range(lane_rows.clone());
+            let mut path = vec![(0, WIRE_Y, z)];
+            for x in 1..width {
+                if rng.gen_bool(0.35) {
+                    let next_z = if rng.gen_bool(0.5) { z + 1 } else { z - 1 };
+                    if lane_rows.contains(&next_z) {
+                        z = next_z;
+                        path.push((x - 1, WIRE_Y, z));
+                    }
+                }
+                path.push((x, WIRE_Y, z));
+            }
+
+            let source = path[0];
+            let sink = path[path.len() - 1];
+            let interior = &path[1..path.len() - 1];
+            let mut removed: Vec<(i32, i32, i32)> = interior
+                .iter()
+                .copied()
+                .filter(|_| !rng.gen_bool(((1.0 - difficulty) * 0.3) as f64))
+                .collect();
+            if removed.is_empty() {
+                removed.push(interior[interior.len() / 2]);
+            }
+
+            for &position in &path {
+                if !removed.contains(&position) {
+                    voxel_data.set_voxel(Vec3::new(position.0 as f32, position.1 as f32, position.2 as f32), color.voxel_type());
+                }
+            }
+            reference_solution.extend(removed.into_iter().map(|position| (position, color.voxel_type())));
+
+            // Block some of the lane's free cells so the route isn't obvious
+            for x in 0..width {
+                for z in lane_rows.clone() {
+                    if !path.contains(&(x, WIRE_Y, z)) && rng.gen_bool((difficulty * 0.4) as f64) {
+                        voxel_data.set_voxel(Vec3::new(x as f32, WIRE_Y as f32, z as f32), VoxelType::Stone);
+                    }
+                }
+            }
+
+            objectives.push(BuildingObjective::WireCircuit { color, source, sink });
+        }
+
+        let slack = ((1.0 - difficulty) * 6.0).round() as usize;
+        ChallengeWorld {
+            voxel_data,
+            objectives,
+            constraints: vec![
+                BuildingConstraint::MaxBlocks(reference_solution.len() + slack),
+                BuildingConstraint::AllowedMaterials(colors.iter().map(|color| color.voxel_type()).collect()),
+                BuildingConstraint::NoCrossedWires,
+            ],
+            hint_sequence: vec![
+                "Each crystal has to be wired to the crystal of the same colour".to_string(),
+                "Wires of different colours may not touch, even at a corner of a lane".to_string(),
+                "Work around the stone blocks; every circuit fits inside its own lane".to_string(),
+            ],
+            reference_solution,
+        }
+    }
 }
 
 /// Tool Creation subsystem
@@ -832,6 +1036,135 @@ impl TerrainSculptor {
 
         Ok(feature_set)
     }
+
+    /// Carves caves along the Voronoi edges between `cave_count` seed points
+    /// (at least two) scattered underground: every voxel nearer than half of
+    /// `cave_width` to the plane halfway between its nearest seed and another
+    /// is removed. `CAVE_ROOF` voxels below the surface and the bedrock layer
+    /// at y = 0 are left alone. Pockets the edges leave disconnected are
+    /// joined to the rest by tunnels, and a shaft rises from the highest
+    /// cave to the surface, so every cave can be reached from the entrance.
+    pub fn carve_caves(world: &mut VoxelWorld, seed: u64, cave_count: u32, cave_width: f32) -> CaveSystem {
+        let (size_x, size_y, size_z) = world.world_size;
+        let (size_x, size_y, size_z) = (size_x as i32, size_y as i32, size_z as i32);
+        let solid_at = |world: &VoxelWorld, (x, y, z): (i32, i32, i32)| {
+            world
+                .get_voxel(Vec3::new(x as f32, y as f32, z as f32))
+                .is_some_and(|voxel_type| voxel_type != VoxelType::Air)
+        };
+
+        // Highest solid voxel of each column
+        let mut surface = HashMap::new();
+        for x in 0..size_x {
+            for z in 0..size_z {
+                if let Some(y) = (0..size_y).rev().find(|&y| solid_at(world, (x, y, z))) {
+                    surface.insert((x, z), y);
+                }
+            }
+        }
+        let lowest_roof = surface.values().map(|&y| y - CAVE_ROOF).max().unwrap_or(0);
+        if lowest_roof < 1 {
+            return CaveSystem::default();
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let seeds: Vec<Vec3> = (0..cave_count.max(2))
+            .map(|_| {
+                Vec3::new(
+                    rng.gen_range(0.0..size_x as f32),
+                    rng.gen_range(1.0..lowest_roof as f32 + 1.0),
+                    rng.gen_range(0.0..size_z as f32),
+                )
+            })
+            .collect();
+        let distance_to_edge = |point: Vec3| {
+            let nearest = seeds
+                .iter()
+                .min_by(|a, b| (point - **a).magnitude2().total_cmp(&(point - **b).magnitude2()))
+                .unwrap();
+            seeds
+                .iter()
+                .filter(|&other| other != nearest)
+                .map(|&other| ((point - other).magnitude2() - (point - *nearest).magnitude2()) / (2.0 * (other - *nearest).magnitude()))
+                .fold(f32::INFINITY, f32::min)
+        };
+
+        let mut carved = Vec::new();
+        for x in 0..size_x {
+            for z in 0..size_z {
+                let Some(&top) = surface.get(&(x, z)) else {
+                    continue;
+                };
+                for y in 1..=top - CAVE_ROOF {
+                    let center = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5);
+                    if solid_at(world, (x, y, z)) && distance_to_edge(center) < cave_width / 2.0 {
+                        carved.push((x, y, z));
+                    }
+                }
+            }
+        }
+        if carved.is_empty() {
+            return CaveSystem::default();
+        }
+
+        // Join every pocket to the first, tunnelling from one of its voxels
+        // to the nearest voxel already joined
+        let mut remaining: HashSet<(i32, i32, i32)> = carved.iter().copied().collect();
+        let mut joined = Vec::new();
+        let mut tunnels = Vec::new();
+        for &start in &carved {
+            if !remaining.contains(&start) {
+                continue;
+            }
+            let mut pocket = vec![start];
+            remaining.remove(&start);
+            let mut next = 0;
+            while next < pocket.len() {
+                for neighbor in neighbors(pocket[next]) {
+                    if remaining.remove(&neighbor) {
+                        pocket.push(neighbor);
+                    }
+                }
+                next += 1;
+            }
+            if let Some(&target) = joined.iter().min_by_key(|&&cell| manhattan_distance(cell, start)) {
+                tunnels.extend(axis_path(start, target));
+            }
+            joined.extend(pocket);
+        }
+        carved.extend(tunnels);
+
+        // Shaft from the highest cave voxel up through the roof
+        let &(shaft_x, highest, shaft_z) = carved.iter().max_by_key(|&&(_, y, _)| y).unwrap();
+        let top = surface[&(shaft_x, shaft_z)];
+        carved.extend((highest + 1..=top).map(|y| (shaft_x, y, shaft_z)));
+
+        carved.sort_unstable();
+        carved.dedup();
+        for &(x, y, z) in &carved {
+            world.set_voxel(Vec3::new(x as f32, y as f32, z as f32), VoxelType::Air);
+        }
+
+        CaveSystem {
+            seeds: seeds.into_iter().map(|seed| (seed.x, seed.y, seed.z)).collect(),
+            entrance: Some((shaft_x, top.max(highest), shaft_z)),
+            carved,
+        }
+    }
+}
+
+/// Solid voxels kept between the terrain surface and the caves beneath it
+pub const CAVE_ROOF: i32 = 3;
+
+/// Caves carved by `ProceduralGeneration::generate_caves`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CaveSystem {
+    /// Voronoi seed points the caves run between
+    pub seeds: Vec<(f32, f32, f32)>,
+    /// Top of the shaft, open to the sky; `None` if nothing was carved
+    pub entrance: Option<(i32, i32, i32)>,
+    /// Every voxel carved, including tunnels and the shaft
+    pub carved: Vec<(i32, i32, i32)>,
 }
 
 /// Generation caching system
@@ -1121,6 +1454,171 @@ pub struct ChallengeTemplate {
     pub common_constraints: Vec<String>,
 }
 
+/// Skill a generated challenge world is built to exercise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SkillDomain {
+    SpatialReasoning,  // Bridging gaps between terrain
+    LogicalThinking,   // Wiring crystal circuits
+}
+
+/// First custom material id used for coloured crystal voxels
+pub const CRYSTAL_MATERIAL_BASE: u8 = 16;
+
+/// Colours of the crystal voxels used as wiring in logic puzzles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CrystalColor {
+    Red,
+    Green,
+    Blue,
+    Yellow,
+}
+
+impl CrystalColor {
+    pub const ALL: [CrystalColor; 4] = [CrystalColor::Red, CrystalColor::Green, CrystalColor::Blue, CrystalColor::Yellow];
+
+    pub fn voxel_type(self) -> VoxelType {
+        VoxelType::Custom(CRYSTAL_MATERIAL_BASE + self as u8)
+    }
+
+    pub fn from_voxel_type(voxel_type: VoxelType) -> Option<Self> {
+        match voxel_type {
+            VoxelType::Custom(id) => Self::ALL.get(id.checked_sub(CRYSTAL_MATERIAL_BASE)? as usize).copied(),
+            _ => None,
+        }
+    }
+}
+
+/// Goal a challenge world is complete once satisfied
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildingObjective {
+    /// The two points must be joined by connected solid voxels
+    ConnectPoints { from: (i32, i32, i32), to: (i32, i32, i32) },
+    /// The two crystals must be joined by crystals of the same colour
+    WireCircuit { color: CrystalColor, source: (i32, i32, i32), sink: (i32, i32, i32) },
+    /// Some voxel must be built at or above this y
+    MinHeight(i32),
+    /// No more than this many voxels of the type may be used
+    MaxBlocks(usize, VoxelType),
+    /// Every voxel must be connected to the ground
+    NoFloatingVoxels,
+    /// The structure must mirror itself across the axis, with at most the
+    /// given fraction of voxels out of place
+    SymmetryAxis(Axis, f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+/// Rule every placement in a challenge world must respect
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildingConstraint {
+    MaxBlocks(usize),
+    AllowedMaterials(Vec<VoxelType>),
+    /// Crystals of different colours may not be adjacent
+    NoCrossedWires,
+}
+
+/// Generated puzzle world with its objectives and constraints
+#[derive(Debug, Clone)]
+pub struct ChallengeWorld {
+    pub voxel_data: VoxelWorld,
+    pub objectives: Vec<BuildingObjective>,
+    pub constraints: Vec<BuildingConstraint>,
+    pub hint_sequence: Vec<String>,
+    /// Pieces removed from the generated solution; placing them all solves the world
+    pub reference_solution: Vec<((i32, i32, i32), VoxelType)>,
+}
+
+impl ChallengeWorld {
+    /// Whether placing `placements` into the world's empty cells satisfies
+    /// every constraint and objective
+    pub fn is_solution(&self, placements: &[((i32, i32, i32), VoxelType)]) -> bool {
+        let mut placed = HashMap::new();
+        for &(position, voxel_type) in placements {
+            if !self.in_bounds(position) || self.existing_voxel(position).is_some() || placed.insert(position, voxel_type).is_some() {
+                return false;
+            }
+        }
+
+        let constraints_met = self.constraints.iter().all(|constraint| match constraint {
+            BuildingConstraint::MaxBlocks(max) => placements.len() <= *max,
+            BuildingConstraint::AllowedMaterials(materials) => placements.iter().all(|(_, voxel_type)| materials.contains(voxel_type)),
+            BuildingConstraint::NoCrossedWires => placed.keys().all(|&position| {
+                let color = CrystalColor::from_voxel_type(placed[&position]);
+                neighbors(position).into_iter().all(|neighbor| {
+                    let neighbor_color = self.voxel_at(&placed, neighbor).and_then(CrystalColor::from_voxel_type);
+                    color.is_none() || neighbor_color.is_none() || neighbor_color == color
+                })
+            }),
+        });
+
+        if !constraints_met {
+            return false;
+        }
+
+        let mut built = self.voxel_data.clone();
+        for (&(x, y, z), &voxel_type) in &placed {
+            built.set_voxel(Vec3::new(x as f32, y as f32, z as f32), voxel_type);
+        }
+        self.objectives.iter().all(|objective| ObjectiveEvaluator::evaluate(&built, objective).passed)
+    }
+
+    fn in_bounds(&self, (x, y, z): (i32, i32, i32)) -> bool {
+        let (size_x, size_y, size_z) = self.voxel_data.world_size;
+        x >= 0 && y >= 0 && z >= 0 && (x as usize) < size_x && (y as usize) < size_y && (z as usize) < size_z
+    }
+
+    fn existing_voxel(&self, position: (i32, i32, i32)) -> Option<VoxelType> {
+        if !self.in_bounds(position) {
+            return None;
+        }
+        let (x, y, z) = position;
+        self.voxel_data
+            .get_voxel(Vec3::new(x as f32, y as f32, z as f32))
+            .filter(|voxel_type| *voxel_type != VoxelType::Air)
+    }
+
+    fn voxel_at(&self, placed: &HashMap<(i32, i32, i32), VoxelType>, position: (i32, i32, i32)) -> Option<VoxelType> {
+        placed.get(&position).copied().or_else(|| self.existing_voxel(position))
+    }
+}
+
+fn neighbors((x, y, z): (i32, i32, i32)) -> [(i32, i32, i32); 6] {
+    [(x + 1, y, z), (x - 1, y, z), (x, y + 1, z), (x, y - 1, z), (x, y, z + 1), (x, y, z - 1)]
+}
+
+fn manhattan_distance(a: (i32, i32, i32), b: (i32, i32, i32)) -> i32 {
+    (a.0 - b.0).abs() + (a.1 - b.1).abs() + (a.2 - b.2).abs()
+}
+
+/// Face-connected path from `from` to `to` that drops to the lower of their
+/// heights, runs along x and then z, and climbs to `to`
+fn axis_path(from: (i32, i32, i32), to: (i32, i32, i32)) -> Vec<(i32, i32, i32)> {
+    let mut path = vec![from];
+    let mut current = from;
+    while current.1 > to.1 {
+        current.1 -= 1;
+        path.push(current);
+    }
+    while current.0 != to.0 {
+        current.0 += (to.0 - current.0).signum();
+        path.push(current);
+    }
+    while current.2 != to.2 {
+        current.2 += (to.2 - current.2).signum();
+        path.push(current);
+    }
+    while current.1 < to.1 {
+        current.1 += 1;
+        path.push(current);
+    }
+    path
+}
+
 /// Custom tool design
 #[derive(Debug, Clone)]
 pub struct CustomTool {
@@ -1348,4 +1846,130 @@ pub struct Landmark {
     pub scale: f32,
     pub visibility_range: f32,
     pub interactive: bool,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTIES: [f32; 4] = [0.0, 0.4, 0.7, 1.0];
+
+    #[test]
+    fn test_challenge_worlds_are_solvable() {
+        let generator = ChallengeGenerator::new(0);
+        for seed in 0..40 {
+            for difficulty in DIFFICULTIES {
+                for domain in [SkillDomain::SpatialReasoning, SkillDomain::LogicalThinking] {
+                    let world = generator.generate_challenge_world(seed, difficulty, domain);
+                    assert!(!world.reference_solution.is_empty());
+                    assert!(world.is_solution(&world.reference_solution), "seed {} difficulty {} {:?}", seed, difficulty, domain);
+                    // Nothing is solved until the player builds
+                    assert!(!world.is_solution(&[]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_bridge_world_rejects_incomplete_or_wrong_material() {
+        let world = ChallengeGenerator::new(0).generate_challenge_world(3, 0.5, SkillDomain::SpatialReasoning);
+        let solution = &world.reference_solution;
+
+        assert!(!world.is_solution(&solution[1..]));
+        let glass: Vec<_> = solution.iter().map(|&(position, _)| (position, VoxelType::Glass)).collect();
+        assert!(!world.is_solution(&glass));
+        let metal: Vec<_> = solution.iter().map(|&(position, _)| (position, VoxelType::Metal)).collect();
+        assert!(world.is_solution(&metal));
+    }
+
+    #[test]
+    fn test_wiring_world_rejects_crossed_wires() {
+        let world = ChallengeGenerator::new(0).generate_challenge_world(11, 1.0, SkillDomain::LogicalThinking);
+        assert_eq!(world.objectives.len(), 4);
+
+        // Recolouring one wire piece breaks its circuit and touches the right colour's neighbours
+        let mut wrong = world.reference_solution.clone();
+        let (position, voxel_type) = wrong[0];
+        let other = CrystalColor::ALL.iter().find(|color| color.voxel_type() != voxel_type).unwrap();
+        wrong[0] = (position, other.voxel_type());
+        assert!(!world.is_solution(&wrong));
+    }
+
+    #[test]
+    fn test_generation_is_deterministic_per_seed() {
+        let generator = ChallengeGenerator::new(0);
+        let a = generator.generate_challenge_world(99, 0.8, SkillDomain::LogicalThinking);
+        let b = generator.generate_challenge_world(99, 0.8, SkillDomain::LogicalThinking);
+        assert_eq!(a.reference_solution, b.reference_solution);
+        assert_eq!(a.objectives, b.objectives);
+    }
+
+    /// A 32x24x32 world of stone up to `height(x, z)`
+    fn stone_world(height: impl Fn(i32, i32) -> i32) -> VoxelWorld {
+        let mut world = VoxelWorld::new("cave_test".to_string(), (32, 24, 32));
+        for x in 0..32 {
+            for z in 0..32 {
+                let height = height(x, z);
+                for y in 0..=height {
+                    world.set_voxel(Vec3::new(x as f32, y as f32, z as f32), VoxelType::Stone);
+                }
+            }
+        }
+        world
+    }
+
+    #[test]
+    fn test_caves_are_reachable_from_the_entrance() {
+        for seed in 0..6 {
+            // Hills from 10 to 17 voxels high
+            let mut world = stone_world(|x, z| 10 + (x + z) / 8);
+            let caves = ProceduralGeneration::generate_caves(&mut world, seed, 6, 2.0);
+            assert_eq!(caves.seeds.len(), 6);
+            assert!(caves.carved.len() > 100, "seed {} carved only {} voxels", seed, caves.carved.len());
+
+            let is_air = |(x, y, z): (i32, i32, i32)| world.get_voxel(Vec3::new(x as f32, y as f32, z as f32)) != Some(VoxelType::Stone);
+            assert!(caves.carved.iter().all(|&cell| is_air(cell)));
+            // Bedrock stays whole
+            assert!(caves.carved.iter().all(|&(_, y, _)| y > 0));
+
+            // The entrance opens onto the sky
+            let (x, y, z) = caves.entrance.unwrap();
+            assert!((y + 1..24).all(|above| is_air((x, above, z))));
+
+            // Every carved voxel is reachable from the entrance through carved voxels
+            let carved: HashSet<_> = caves.carved.iter().copied().collect();
+            let mut reached = HashSet::from([(x, y, z)]);
+            let mut frontier = vec![(x, y, z)];
+            while let Some(cell) = frontier.pop() {
+                for neighbor in neighbors(cell) {
+                    if carved.contains(&neighbor) && reached.insert(neighbor) {
+                        frontier.push(neighbor);
+                    }
+                }
+            }
+            assert_eq!(reached.len(), carved.len(), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_only_the_shaft_breaks_the_roof() {
+        let mut world = stone_world(|_, _| 14);
+        let caves = ProceduralGeneration::generate_caves(&mut world, 42, 8, 1.5);
+        let (shaft_x, top, shaft_z) = caves.entrance.unwrap();
+        assert_eq!(top, 14);
+        for &(x, y, z) in &caves.carved {
+            assert!(y <= 14 - CAVE_ROOF || (x, z) == (shaft_x, shaft_z), "({}, {}, {}) breaks the roof", x, y, z);
+        }
+        // Deterministic per seed
+        assert_eq!(ProceduralGeneration::generate_caves(&mut stone_world(|_, _| 14), 42, 8, 1.5), caves);
+    }
+
+    #[test]
+    fn test_crystal_color_round_trip() {
+        for color in CrystalColor::ALL {
+            assert_eq!(CrystalColor::from_voxel_type(color.voxel_type()), Some(color));
+        }
+        assert_eq!(CrystalColor::from_voxel_type(VoxelType::Custom(2)), None);
+        assert_eq!(CrystalColor::from_voxel_type(VoxelType::Stone), None);
+    }
+}
@@ -0,0 +1,209 @@
+// Robin Game Engine - Player Profile Encryption
+// Stores the fields that identify a player encrypted, leaving the rest of the
+// profile readable for analytics queries
+
+use super::PlayerProfile;
+use crate::engine::error::{RobinError, RobinResult};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use rand::RngCore;
+use serde_json::Value;
+
+/// Profile fields that identify a player. `player_id` comes first, since the
+/// rest are bound to it when encrypted.
+pub const PII_FIELDS: [&str; 2] = ["player_id", "username"];
+
+const NONCE_LEN: usize = 12;
+/// Length of the GCM authentication tag at the end of each ciphertext
+const TAG_LEN: usize = 16;
+
+impl PlayerProfile {
+    /// Serializes the profile to JSON with each of `PII_FIELDS` replaced by
+    /// hex of a random 12-byte nonce followed by the field's AES-256-GCM
+    /// ciphertext. Every field gets a fresh nonce, so the same profile
+    /// encrypts differently each time. Each ciphertext is authenticated
+    /// with its field name, and every field but `player_id` with the player
+    /// id too, so fields can't be swapped with each other or between
+    /// profiles.
+    pub fn serialize_encrypted(&self, key: &[u8; 32]) -> RobinResult<Vec<u8>> {
+        let mut json = serde_json::to_value(self).map_err(serialization_error)?;
+        let cipher = Aes256Gcm::new(key.into());
+
+        for field in PII_FIELDS {
+            let Some(Value::String(plaintext)) = json.get_mut(field) else {
+                continue;
+            };
+            let mut sealed = vec![0u8; NONCE_LEN];
+            rand::thread_rng().fill_bytes(&mut sealed);
+            let aad = associated_data(field, &self.player_id);
+            let payload = Payload { msg: plaintext.as_bytes(), aad: &aad };
+            let ciphertext = cipher
+                .encrypt(Nonce::from_slice(&sealed), payload)
+                .map_err(|_| invalid_field(field, "encryption failed"))?;
+            sealed.extend_from_slice(&ciphertext);
+            *plaintext = to_hex(&sealed);
+        }
+
+        serde_json::to_vec(&json).map_err(serialization_error)
+    }
+
+    /// Reads a profile written by `serialize_encrypted`. A wrong key, a
+    /// tampered field or one moved from another field or profile fails GCM
+    /// authentication and is reported as `RobinError::InvalidData` for that
+    /// field.
+    pub fn deserialize_encrypted(data: &[u8], key: &[u8; 32]) -> RobinResult<Self> {
+        let mut json: Value = serde_json::from_slice(data).map_err(serialization_error)?;
+        let cipher = Aes256Gcm::new(key.into());
+        let mut player_id = String::new();
+
+        for field in PII_FIELDS {
+            let Some(Value::String(encrypted)) = json.get_mut(field) else {
+                continue;
+            };
+            let sealed = from_hex(encrypted).ok_or_else(|| invalid_field(field, "not hex"))?;
+            if sealed.len() < NONCE_LEN + TAG_LEN {
+                return Err(invalid_field(field, "too short to hold a nonce and tag"));
+            }
+            let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+            let aad = associated_data(field, &player_id);
+            let plaintext = cipher
+                .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: &aad })
+                .map_err(|_| invalid_field(field, "decryption failed; wrong key or tampered data"))?;
+            *encrypted = String::from_utf8(plaintext).map_err(|_| invalid_field(field, "not UTF-8"))?;
+            if field == "player_id" {
+                player_id = encrypted.clone();
+            }
+        }
+
+        serde_json::from_value(json).map_err(serialization_error)
+    }
+}
+
+/// Associated data authenticated with `field`'s ciphertext: the field name,
+/// followed for every field but `player_id` itself by the player id
+fn associated_data(field: &str, player_id: &str) -> Vec<u8> {
+    let mut aad = field.as_bytes().to_vec();
+    if field != "player_id" {
+        aad.push(0);
+        aad.extend_from_slice(player_id.as_bytes());
+    }
+    aad
+}
+
+fn serialization_error(error: serde_json::Error) -> RobinError {
+    RobinError::SerializationError {
+        object_type: "PlayerProfile".to_string(),
+        reason: error.to_string(),
+    }
+}
+
+fn invalid_field(field: &str, reason: &str) -> RobinError {
+    RobinError::InvalidData {
+        field: field.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    fn profile() -> PlayerProfile {
+        let mut profile = PlayerProfile::default();
+        profile.player_id = "player-8f3a2c".to_string();
+        profile.username = "BlockBuilder42".to_string();
+        profile.play_style.creativity = 0.75;
+        profile
+    }
+
+    #[test]
+    fn test_pii_fields_are_not_stored_in_plaintext() -> RobinResult<()> {
+        let profile = profile();
+        let data = profile.serialize_encrypted(&KEY)?;
+        let text = String::from_utf8(data.clone()).unwrap();
+        assert!(!text.contains("player-8f3a2c"));
+        assert!(!text.contains("BlockBuilder42"));
+
+        // Analytics can still read everything else
+        let json: Value = serde_json::from_slice(&data).unwrap();
+        assert_eq!(json["play_style"]["creativity"], 0.75);
+
+        let restored = PlayerProfile::deserialize_encrypted(&data, &KEY)?;
+        assert_eq!(restored.player_id, "player-8f3a2c");
+        assert_eq!(restored.username, "BlockBuilder42");
+        assert_eq!(serde_json::to_value(&restored).unwrap(), serde_json::to_value(&profile).unwrap());
+
+        // Fresh nonces every time
+        assert_ne!(profile.serialize_encrypted(&KEY)?, data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrong_key_is_invalid_data() -> RobinResult<()> {
+        let data = profile().serialize_encrypted(&KEY)?;
+        let result = PlayerProfile::deserialize_encrypted(&data, &[8; 32]);
+        assert!(matches!(result, Err(RobinError::InvalidData { ref field, .. }) if field == "player_id"));
+
+        // Tampering with a ciphertext fails authentication the same way
+        let mut json: Value = serde_json::from_slice(&data).unwrap();
+        let username = json["username"].as_str().unwrap().to_string();
+        let flipped = if username.ends_with('0') { "1" } else { "0" };
+        json["username"] = Value::String(format!("{}{}", &username[..username.len() - 1], flipped));
+        let tampered = serde_json::to_vec(&json).unwrap();
+        assert!(matches!(
+            PlayerProfile::deserialize_encrypted(&tampered, &KEY),
+            Err(RobinError::InvalidData { ref field, .. }) if field == "username"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fields_moved_between_fields_or_profiles_are_rejected() -> RobinResult<()> {
+        let data = profile().serialize_encrypted(&KEY)?;
+        let json: Value = serde_json::from_slice(&data).unwrap();
+
+        // The player id's ciphertext in place of the username
+        let mut swapped = json.clone();
+        swapped["username"] = json["player_id"].clone();
+        assert!(matches!(
+            PlayerProfile::deserialize_encrypted(&serde_json::to_vec(&swapped).unwrap(), &KEY),
+            Err(RobinError::InvalidData { ref field, .. }) if field == "username"
+        ));
+
+        // Another player's username, encrypted under the same key
+        let mut other = profile();
+        other.player_id = "player-11b7d0".to_string();
+        let other: Value = serde_json::from_slice(&other.serialize_encrypted(&KEY)?).unwrap();
+        let mut swapped = json.clone();
+        swapped["username"] = other["username"].clone();
+        assert!(matches!(
+            PlayerProfile::deserialize_encrypted(&serde_json::to_vec(&swapped).unwrap(), &KEY),
+            Err(RobinError::InvalidData { ref field, .. }) if field == "username"
+        ));
+
+        // Or their player id, which no longer matches this profile's username
+        let mut swapped = json;
+        swapped["player_id"] = other["player_id"].clone();
+        assert!(matches!(
+            PlayerProfile::deserialize_encrypted(&serde_json::to_vec(&swapped).unwrap(), &KEY),
+            Err(RobinError::InvalidData { ref field, .. }) if field == "username"
+        ));
+        Ok(())
+    }
+}
@@ -630,6 +630,9 @@ pub struct GroupDynamics {
     pub conflict_level: f32,
     pub cooperation_level: f32,
     pub leadership_structure: LeadershipStructure,
+    /// How much each member has contributed, keyed by member id
+    #[serde(default)]
+    pub participation_levels: HashMap<String, f32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -640,6 +643,91 @@ pub enum LeadershipStructure {
     Anarchic,
 }
 
+/// Role a member takes on to even out who contributes to a group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GroupRole {
+    /// Runs the discussion, so has to speak up
+    Facilitator,
+    /// Reports the group's work back
+    Presenter,
+    /// Keeps notes, leaving room for the others to talk
+    Recorder,
+}
+
+/// Gini coefficient above which a group's participation counts as imbalanced
+pub const PARTICIPATION_IMBALANCE_THRESHOLD: f32 = 0.4;
+
+/// Seconds a group can stay imbalanced before an intervention is raised
+pub const PARTICIPATION_IMBALANCE_GRACE_SECS: f32 = 300.0;
+
+impl GroupDynamics {
+    /// Gini coefficient over `participation_levels`: 0.0 when everyone
+    /// contributes equally, approaching 1.0 as one member does it all
+    pub fn compute_participation_imbalance(&self) -> f32 {
+        let levels: Vec<f32> = self.participation_levels.values().copied().collect();
+        let total: f32 = levels.iter().sum();
+        if levels.len() < 2 || total <= 0.0 {
+            return 0.0;
+        }
+
+        let pairwise_difference: f32 = levels
+            .iter()
+            .flat_map(|a| levels.iter().map(move |b| (a - b).abs()))
+            .sum();
+        pairwise_difference / (2.0 * levels.len() as f32 * total)
+    }
+
+    /// Roles that would pull quieter members into the group: the quietest
+    /// facilitates, others below the average present, and everyone above
+    /// the average records. Empty when participation is already even.
+    pub fn suggest_rebalancing_roles(&self) -> Vec<(String, GroupRole)> {
+        if self.compute_participation_imbalance() == 0.0 {
+            return Vec::new();
+        }
+
+        let members = self.members_by_participation();
+        let average = members.iter().map(|(_, level)| level).sum::<f32>() / members.len() as f32;
+        members
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, level))| *level != average)
+            .map(|(rank, (member, level))| {
+                let role = if *level > average {
+                    GroupRole::Recorder
+                } else if rank == 0 {
+                    GroupRole::Facilitator
+                } else {
+                    GroupRole::Presenter
+                };
+                (member.to_string(), role)
+            })
+            .collect()
+    }
+
+    /// Members from the quietest to the most dominant, ties broken by id
+    fn members_by_participation(&self) -> Vec<(&str, f32)> {
+        let mut members: Vec<(&str, f32)> = self
+            .participation_levels
+            .iter()
+            .map(|(member, level)| (member.as_str(), *level))
+            .collect();
+        members.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(b.0)));
+        members
+    }
+}
+
+/// Nudge raised by the social dynamics system for a group
+#[derive(Debug, Clone, PartialEq)]
+pub enum GroupIntervention {
+    /// Asks `recipient`, who dominates the group, to bring `invitee` in
+    SocialSupport {
+        group_id: String,
+        recipient: String,
+        invitee: String,
+        message: String,
+    },
+}
+
 pub struct InfluenceNetwork {
     pub influence_edges: Vec<InfluenceEdge>,
     pub influence_scores: HashMap<Uuid, f32>,
@@ -1559,6 +1647,10 @@ pub struct SocialDynamicsSystem {
     pub social_influence: SocialInfluenceModel,
     pub norm_emergence: NormEmergenceSystem,
     pub collective_behavior: CollectiveBehaviorSystem,
+    /// Seconds each group has been over `PARTICIPATION_IMBALANCE_THRESHOLD`
+    pub imbalance_durations: HashMap<String, f32>,
+    /// Raised but not yet taken by `NPCIntelligenceSystem::take_interventions`
+    pub interventions: Vec<GroupIntervention>,
 }
 
 pub struct SocialInfluenceModel {
@@ -2309,6 +2401,8 @@ impl NPCIntelligenceSystem {
                         voting_systems: Vec::new(),
                     },
                 },
+                imbalance_durations: HashMap::new(),
+                interventions: Vec::new(),
             },
             config: NPCIntelligenceConfig::default(),
         })
@@ -2370,11 +2464,41 @@ impl NPCIntelligenceSystem {
         Ok(())
     }
 
-    fn update_social_dynamics(&mut self, _delta_time: f32) -> RobinResult<()> {
-        // Update social influence and group dynamics
+    fn update_social_dynamics(&mut self, delta_time: f32) -> RobinResult<()> {
+        let social = &mut self.social_dynamics;
+        for (group_id, dynamics) in &social.group_dynamics {
+            if dynamics.compute_participation_imbalance() <= PARTICIPATION_IMBALANCE_THRESHOLD {
+                social.imbalance_durations.remove(group_id);
+                continue;
+            }
+
+            let imbalanced_for = social.imbalance_durations.entry(group_id.clone()).or_insert(0.0);
+            *imbalanced_for += delta_time;
+            if *imbalanced_for <= PARTICIPATION_IMBALANCE_GRACE_SECS {
+                continue;
+            }
+            *imbalanced_for = 0.0;
+
+            let members = dynamics.members_by_participation();
+            let (Some((invitee, _)), Some((recipient, _))) = (members.first(), members.last()) else {
+                continue;
+            };
+            social.interventions.push(GroupIntervention::SocialSupport {
+                group_id: group_id.clone(),
+                recipient: recipient.to_string(),
+                invitee: invitee.to_string(),
+                message: format!("{} hasn't had much chance to contribute yet; why not invite them in?", invitee),
+            });
+        }
+        social.imbalance_durations.retain(|group_id, _| social.group_dynamics.contains_key(group_id));
         Ok(())
     }
 
+    /// Interventions raised since the last call
+    pub fn take_interventions(&mut self) -> Vec<GroupIntervention> {
+        std::mem::take(&mut self.social_dynamics.interventions)
+    }
+
     pub fn create_npc(&mut self, npc_class: NPCClass, intelligence_level: IntelligenceLevel, position: [f32; 3]) -> RobinResult<Uuid> {
         let npc_id = Uuid::new_v4();
         
@@ -2516,4 +2640,115 @@ impl NPCIntelligenceSystem {
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::ai_systems::{PerformanceBudget, QualitySettings};
+
+    fn dynamics(levels: &[f32]) -> GroupDynamics {
+        GroupDynamics {
+            cohesion: 0.5,
+            hierarchy_strength: 0.5,
+            conflict_level: 0.0,
+            cooperation_level: 0.5,
+            leadership_structure: LeadershipStructure::Democratic,
+            participation_levels: levels
+                .iter()
+                .enumerate()
+                .map(|(i, level)| (format!("student-{}", i), *level))
+                .collect(),
+        }
+    }
+
+    fn system_with_group(levels: &[f32]) -> NPCIntelligenceSystem {
+        let config = AISystemConfig {
+            enable_npc_intelligence: true,
+            enable_procedural_generation: false,
+            enable_adaptive_difficulty: false,
+            enable_content_generation: false,
+            enable_ai_assistance: false,
+            performance_budget: PerformanceBudget::default(),
+            quality_settings: QualitySettings::default(),
+        };
+        let mut system = NPCIntelligenceSystem::new(&config).unwrap();
+        system.social_dynamics.group_dynamics.insert("table-1".to_string(), dynamics(levels));
+        system
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-5, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn test_imbalance_of_a_pair() {
+        assert_close(dynamics(&[0.5, 0.5]).compute_participation_imbalance(), 0.0);
+        assert_close(dynamics(&[1.0, 0.0]).compute_participation_imbalance(), 0.5);
+        assert_close(dynamics(&[3.0, 1.0]).compute_participation_imbalance(), 0.25);
+        assert!(dynamics(&[0.5, 0.5]).suggest_rebalancing_roles().is_empty());
+
+        let roles = dynamics(&[3.0, 1.0]).suggest_rebalancing_roles();
+        assert_eq!(roles, [
+            ("student-1".to_string(), GroupRole::Facilitator),
+            ("student-0".to_string(), GroupRole::Recorder),
+        ]);
+    }
+
+    #[test]
+    fn test_imbalance_of_four() {
+        assert_close(dynamics(&[1.0, 1.0, 1.0, 1.0]).compute_participation_imbalance(), 0.0);
+        assert_close(dynamics(&[1.0, 0.0, 0.0, 0.0]).compute_participation_imbalance(), 0.75);
+        assert_close(dynamics(&[4.0, 3.0, 2.0, 1.0]).compute_participation_imbalance(), 0.25);
+
+        let roles = dynamics(&[4.0, 3.0, 2.0, 1.0]).suggest_rebalancing_roles();
+        assert_eq!(roles, [
+            ("student-3".to_string(), GroupRole::Facilitator),
+            ("student-2".to_string(), GroupRole::Presenter),
+            ("student-1".to_string(), GroupRole::Recorder),
+            ("student-0".to_string(), GroupRole::Recorder),
+        ]);
+    }
+
+    #[test]
+    fn test_imbalance_of_six() {
+        assert_close(dynamics(&[2.0; 6]).compute_participation_imbalance(), 0.0);
+        assert_close(dynamics(&[5.0, 1.0, 0.0, 0.0, 0.0, 0.0]).compute_participation_imbalance(), 7.0 / 9.0);
+
+        // Members sitting exactly on the average keep their roles
+        let roles = dynamics(&[3.0, 2.0, 2.0, 2.0, 2.0, 1.0]).suggest_rebalancing_roles();
+        assert_eq!(roles, [
+            ("student-5".to_string(), GroupRole::Facilitator),
+            ("student-0".to_string(), GroupRole::Recorder),
+        ]);
+    }
+
+    #[test]
+    fn test_sustained_imbalance_asks_the_dominant_member_to_invite_the_quietest() {
+        let mut system = system_with_group(&[0.9, 0.05, 0.05, 0.0]);
+        for _ in 0..10 {
+            system.update(PARTICIPATION_IMBALANCE_GRACE_SECS / 10.0).unwrap();
+        }
+        assert!(system.take_interventions().is_empty());
+
+        system.update(1.0).unwrap();
+        let interventions = system.take_interventions();
+        assert_eq!(interventions.len(), 1);
+        let GroupIntervention::SocialSupport { group_id, recipient, invitee, .. } = &interventions[0];
+        assert_eq!(group_id, "table-1");
+        assert_eq!(recipient, "student-0");
+        assert_eq!(invitee, "student-3");
+
+        // The clock restarts once the intervention has been raised
+        system.update(1.0).unwrap();
+        assert!(system.take_interventions().is_empty());
+    }
+
+    #[test]
+    fn test_balanced_group_raises_no_interventions() {
+        let mut system = system_with_group(&[0.4, 0.3, 0.3, 0.2, 0.3, 0.3]);
+        system.update(PARTICIPATION_IMBALANCE_GRACE_SECS * 2.0).unwrap();
+        assert!(system.take_interventions().is_empty());
+        assert!(system.social_dynamics.imbalance_durations.is_empty());
+    }
+}
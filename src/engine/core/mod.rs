@@ -14,7 +14,8 @@ pub struct Engine {
 
 impl Engine {
     pub async fn new() -> Self {
-        env_logger::init();
+        // A no-op when main has already routed `log` through tracing
+        let _ = env_logger::try_init();
 
         let event_loop = EventLoop::new().unwrap();
         let window = WindowBuilder::new()
@@ -6,7 +6,7 @@ use std::path::PathBuf;
 pub type RobinResult<T> = Result<T, RobinError>;
 
 /// Comprehensive error type for the Robin Engine
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub enum RobinError {
     // === GRAPHICS ERRORS ===
     GraphicsInitError(String),
@@ -224,6 +224,7 @@ pub enum RobinError {
         endpoint: String,
         reason: String,
     },
+    ServiceUnavailable(String),
     
     // === MULTIPLAYER ERRORS ===
     MultiplayerError(String),
@@ -246,6 +247,20 @@ pub enum RobinError {
         planned_version: Option<String>,
     },
     InternalError(String),
+
+    // === STRUCTURED ERRORS ===
+    Io(std::io::Error),
+    Wgpu(wgpu::SurfaceError),
+    InvalidData {
+        field: String,
+        reason: String,
+    },
+    NotFound(String),
+    Unsupported(String),
+    UnsupportedVersion {
+        found: u8,
+        expected: u8,
+    },
     
     // === ADDITIONAL ERROR VARIANTS ===
     BuildError(String),
@@ -465,6 +480,9 @@ impl fmt::Display for RobinError {
             RobinError::NetworkError { operation, endpoint, reason } => {
                 write!(f, "Network error during {} to '{}': {}", operation, endpoint, reason)
             }
+            RobinError::ServiceUnavailable(service) => {
+                write!(f, "Service unavailable: {}", service)
+            }
             
             // Multiplayer errors
             RobinError::MultiplayerError(reason) => {
@@ -496,6 +514,26 @@ impl fmt::Display for RobinError {
             RobinError::InternalError(reason) => {
                 write!(f, "Internal engine error: {}", reason)
             }
+
+            // Structured errors
+            RobinError::Io(error) => {
+                write!(f, "I/O error: {}", error)
+            }
+            RobinError::Wgpu(error) => {
+                write!(f, "GPU surface error: {}", error)
+            }
+            RobinError::InvalidData { field, reason } => {
+                write!(f, "Invalid data in '{}': {}", field, reason)
+            }
+            RobinError::NotFound(what) => {
+                write!(f, "Not found: {}", what)
+            }
+            RobinError::Unsupported(what) => {
+                write!(f, "Unsupported: {}", what)
+            }
+            RobinError::UnsupportedVersion { found, expected } => {
+                write!(f, "Unsupported format version {} (expected {})", found, expected)
+            }
             
             // Additional error variants
             RobinError::BuildError(reason) => {
@@ -609,23 +647,24 @@ impl fmt::Display for RobinError {
 
 impl StdError for RobinError {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
-        None
+        match self {
+            RobinError::Io(error) => Some(error),
+            RobinError::Wgpu(error) => Some(error),
+            _ => None,
+        }
     }
 }
 
 // Conversion implementations for common error types
 impl From<std::io::Error> for RobinError {
     fn from(error: std::io::Error) -> Self {
-        match error.kind() {
-            std::io::ErrorKind::NotFound => {
-                // Try to extract path from error if possible
-                RobinError::InternalError(format!("I/O Error: {}", error))
-            }
-            std::io::ErrorKind::PermissionDenied => {
-                RobinError::InternalError(format!("Permission denied: {}", error))
-            }
-            _ => RobinError::InternalError(format!("I/O Error: {}", error)),
-        }
+        RobinError::Io(error)
+    }
+}
+
+impl From<wgpu::SurfaceError> for RobinError {
+    fn from(error: wgpu::SurfaceError) -> Self {
+        RobinError::Wgpu(error)
     }
 }
 
@@ -891,6 +930,56 @@ mod tests {
         assert!(display.contains("assets/player.png"));
     }
 
+    #[test]
+    fn test_from_io_error() {
+        let io_error = std::io::Error::new(std::io::ErrorKind::NotFound, "world.bin missing");
+        let error: RobinError = io_error.into();
+
+        match &error {
+            RobinError::Io(inner) => assert_eq!(inner.kind(), std::io::ErrorKind::NotFound),
+            _ => panic!("Wrong error type"),
+        }
+        assert_eq!(format!("{}", error), "I/O error: world.bin missing");
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn test_from_surface_error() {
+        let error: RobinError = wgpu::SurfaceError::Lost.into();
+
+        assert!(matches!(error, RobinError::Wgpu(wgpu::SurfaceError::Lost)));
+        assert!(format!("{}", error).starts_with("GPU surface error: "));
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn test_question_mark_propagates_typed_errors() {
+        fn read_missing() -> RobinResult<String> {
+            Ok(std::fs::read_to_string("/nonexistent/robin/file.bin")?)
+        }
+
+        assert!(matches!(read_missing(), Err(RobinError::Io(_))));
+    }
+
+    #[test]
+    fn test_structured_error_display() {
+        let invalid = RobinError::InvalidData {
+            field: "chunk_size".to_string(),
+            reason: "must be a power of two".to_string(),
+        };
+        assert_eq!(format!("{}", invalid), "Invalid data in 'chunk_size': must be a power of two");
+
+        let not_found = RobinError::NotFound("save slot 3".to_string());
+        assert_eq!(format!("{}", not_found), "Not found: save slot 3");
+
+        let unsupported = RobinError::Unsupported("compressed textures".to_string());
+        assert_eq!(format!("{}", unsupported), "Unsupported: compressed textures");
+
+        let version = RobinError::UnsupportedVersion { found: 3, expected: 1 };
+        assert_eq!(format!("{}", version), "Unsupported format version 3 (expected 1)");
+        assert!(version.source().is_none());
+    }
+
     #[test]
     fn test_error_context() {
         let result: RobinResult<()> = Err(RobinError::InternalError("test error".to_string()));
@@ -61,6 +61,7 @@ pub mod noise;
 pub mod destruction;
 pub mod ui_generation;
 pub mod runtime_tools;
+pub mod pathfinding;
 
 // Import specific types to avoid ambiguity
 pub use voxel_system::{
@@ -0,0 +1,251 @@
+/*!
+ * Voxel Pathfinding
+ *
+ * Walkable positions in a voxel world and the moves between them, for
+ * AI-controlled entities to plan routes over. A position is walkable when
+ * the agent can stand there: solid ground beneath it and enough empty cells
+ * above for its height. Agents move one cell sideways at a time, stepping up
+ * or down at most one cell.
+ */
+
+use super::voxel_system::{VoxelType, VoxelWorld};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+type Position = (usize, usize, usize);
+
+/// Sideways moves on the x and z axes
+const HORIZONTAL_STEPS: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+/// Whether an agent can stand on, and not pass through, `voxel`
+fn is_solid(voxel: Option<VoxelType>) -> bool {
+    matches!(voxel, Some(voxel_type) if !matches!(voxel_type, VoxelType::Air | VoxelType::Liquid | VoxelType::Gas))
+}
+
+fn manhattan(a: Position, b: Position) -> usize {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1) + a.2.abs_diff(b.2)
+}
+
+/// Walkable positions of a world, each mapped to the walkable positions an
+/// agent can move to from it in one step
+#[derive(Debug, Clone)]
+pub struct VisibilityGraph {
+    pub edges: HashMap<Position, Vec<Position>>,
+    /// Empty cells an agent needs above the ground it stands on
+    pub agent_height: usize,
+}
+
+impl VisibilityGraph {
+    pub fn build(world: &VoxelWorld, agent_height: usize) -> Self {
+        let mut graph = Self {
+            edges: HashMap::new(),
+            agent_height,
+        };
+        let (size_x, size_y, size_z) = world.world_size;
+        if size_x > 0 && size_y > 0 && size_z > 0 {
+            graph.update_area(world, (0, 0, 0), (size_x - 1, size_y - 1, size_z - 1));
+        }
+        graph
+    }
+
+    /// Whether an agent could stand at `position`. Cells above the top of the
+    /// world are open sky.
+    fn is_walkable(&self, world: &VoxelWorld, (x, y, z): Position) -> bool {
+        world.contains((x, y, z))
+            && y > 0
+            && is_solid(world.voxel_at((x, y - 1, z)))
+            && (y..y + self.agent_height).all(|head| !is_solid(world.voxel_at((x, head, z))))
+    }
+
+    /// Walkable positions one step from `position`. Stepping up a cell needs
+    /// an extra cell of headroom where the agent starts, and stepping down
+    /// one where it lands, so every move can be made in either direction.
+    fn neighbours(&self, world: &VoxelWorld, (x, y, z): Position) -> Vec<Position> {
+        let mut neighbours = Vec::new();
+        for (dx, dz) in HORIZONTAL_STEPS {
+            let (Some(nx), Some(nz)) = (x.checked_add_signed(dx), z.checked_add_signed(dz)) else {
+                continue;
+            };
+            for ny in [y, y + 1, y.wrapping_sub(1)] {
+                if !self.edges.contains_key(&(nx, ny, nz)) {
+                    continue;
+                }
+                let clear = match ny {
+                    ny if ny > y => !is_solid(world.voxel_at((x, y + self.agent_height, z))),
+                    ny if ny < y => !is_solid(world.voxel_at((nx, y + self.agent_height - 1, nz))),
+                    _ => true,
+                };
+                if clear {
+                    neighbours.push((nx, ny, nz));
+                }
+            }
+        }
+        neighbours
+    }
+
+    /// Rebuilds the graph around the box from `min` to `max` inclusive, after
+    /// the voxels inside it have changed. Positions standing on or under the
+    /// box, and the moves into them from outside it, are recomputed too.
+    pub fn update_area(&mut self, world: &VoxelWorld, min: Position, max: Position) {
+        // A voxel change affects the positions standing on it, the ones whose
+        // headroom (or step headroom) it sits in, and the moves next to those
+        let low = (
+            min.0.saturating_sub(1),
+            min.1.saturating_sub(self.agent_height + 1),
+            min.2.saturating_sub(1),
+        );
+        let high = (max.0 + 1, max.1 + 1, max.2 + 1);
+        let in_area = |(x, y, z): Position, margin: usize| {
+            (low.0.saturating_sub(margin)..=high.0 + margin).contains(&x)
+                && (low.1.saturating_sub(margin)..=high.1 + margin).contains(&y)
+                && (low.2.saturating_sub(margin)..=high.2 + margin).contains(&z)
+        };
+
+        self.edges.retain(|&position, _| !in_area(position, 0));
+        for x in low.0..=high.0 {
+            for y in low.1..=high.1 {
+                for z in low.2..=high.2 {
+                    if self.is_walkable(world, (x, y, z)) {
+                        self.edges.insert((x, y, z), Vec::new());
+                    }
+                }
+            }
+        }
+
+        let affected: Vec<Position> = self.edges.keys().copied().filter(|&position| in_area(position, 1)).collect();
+        for position in affected {
+            let neighbours = self.neighbours(world, position);
+            self.edges.insert(position, neighbours);
+        }
+    }
+
+    /// Shortest route from `start` to `goal`, both included, found with A*.
+    /// Each move costs the Manhattan distance it covers, so the Manhattan
+    /// heuristic never overestimates. `None` if either end isn't walkable or
+    /// no route connects them.
+    pub fn find_path(&self, start: Position, goal: Position) -> Option<Vec<Position>> {
+        if !self.edges.contains_key(&start) || !self.edges.contains_key(&goal) {
+            return None;
+        }
+
+        let mut open = BinaryHeap::from([Reverse((manhattan(start, goal), 0, start))]);
+        let mut came_from: HashMap<Position, Position> = HashMap::new();
+        let mut cost: HashMap<Position, usize> = HashMap::from([(start, 0)]);
+
+        while let Some(Reverse((_, current_cost, current))) = open.pop() {
+            if current == goal {
+                let mut path = vec![goal];
+                while let Some(&previous) = came_from.get(path.last().unwrap()) {
+                    path.push(previous);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if current_cost > cost[&current] {
+                // Already reached more cheaply
+                continue;
+            }
+
+            for &next in &self.edges[&current] {
+                let next_cost = current_cost + manhattan(current, next);
+                if cost.get(&next).is_none_or(|&known| next_cost < known) {
+                    cost.insert(next, next_cost);
+                    came_from.insert(next, current);
+                    open.push(Reverse((next_cost + manhattan(next, goal), next_cost, next)));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::math::Vec3;
+
+    fn place(world: &mut VoxelWorld, (x, y, z): Position, voxel_type: VoxelType) {
+        world.set_voxel(Vec3::new(x as f32, y as f32, z as f32), voxel_type);
+    }
+
+    /// A 10x10 stone floor at y = 0 split by a wall three cells high along
+    /// x = 5, with a one-cell gap at z = 7
+    fn walled_world() -> VoxelWorld {
+        let mut world = VoxelWorld::new("walled".to_string(), (10, 6, 10));
+        for x in 0..10 {
+            for z in 0..10 {
+                place(&mut world, (x, 0, z), VoxelType::Stone);
+            }
+        }
+        for z in (0..10).filter(|&z| z != 7) {
+            for y in 1..=3 {
+                place(&mut world, (5, y, z), VoxelType::Stone);
+            }
+        }
+        world
+    }
+
+    fn assert_connected(path: &[Position]) {
+        for step in path.windows(2) {
+            assert_eq!(step[0].0.abs_diff(step[1].0) + step[0].2.abs_diff(step[1].2), 1, "{:?}", step);
+            assert!(step[0].1.abs_diff(step[1].1) <= 1, "{:?}", step);
+        }
+    }
+
+    #[test]
+    fn test_path_goes_through_the_gap_in_the_wall() {
+        let world = walled_world();
+        let graph = world.compute_visibility_graph(2);
+        assert!(graph.edges.contains_key(&(5, 1, 7)));
+        assert!(!graph.edges.contains_key(&(5, 1, 3)));
+        // Agents could stand on top of the wall, if they could get there
+        assert!(graph.edges.contains_key(&(5, 4, 3)));
+
+        let path = graph.find_path((1, 1, 1), (8, 1, 1)).unwrap();
+        assert_connected(&path);
+        assert!(path.contains(&(5, 1, 7)));
+        // Shortest route: 4 + 6 moves to the gap, then 3 + 6 beyond it
+        assert_eq!(path.len(), 20);
+        assert_eq!((path[0], path[19]), ((1, 1, 1), (8, 1, 1)));
+    }
+
+    #[test]
+    fn test_closing_the_gap_disconnects_the_two_sides() {
+        let mut world = walled_world();
+        let mut graph = VisibilityGraph::build(&world, 2);
+        for y in 1..=3 {
+            place(&mut world, (5, y, 7), VoxelType::Stone);
+        }
+        graph.update_area(&world, (5, 1, 7), (5, 3, 7));
+        assert!(!graph.edges.contains_key(&(5, 1, 7)));
+        assert_eq!(graph.find_path((1, 1, 1), (8, 1, 1)), None);
+        assert!(graph.find_path((1, 1, 1), (4, 1, 9)).is_some());
+
+        // Reopening it matches a graph built from scratch
+        for y in 1..=3 {
+            place(&mut world, (5, y, 7), VoxelType::Air);
+        }
+        graph.update_area(&world, (5, 1, 7), (5, 3, 7));
+        let rebuilt = VisibilityGraph::build(&world, 2);
+        for (position, neighbours) in &rebuilt.edges {
+            let mut expected = neighbours.clone();
+            let mut actual = graph.edges[position].clone();
+            expected.sort();
+            actual.sort();
+            assert_eq!(actual, expected, "{:?}", position);
+        }
+        assert_eq!(graph.edges.len(), rebuilt.edges.len());
+    }
+
+    #[test]
+    fn test_agents_step_up_one_cell_but_not_two() {
+        let mut world = walled_world();
+        // A single step in front of the wall, at (4, 1, 2)
+        place(&mut world, (4, 1, 2), VoxelType::Stone);
+        let graph = VisibilityGraph::build(&world, 2);
+        assert!(graph.edges[&(3, 1, 2)].contains(&(4, 2, 2)));
+        assert!(graph.edges[&(4, 2, 2)].contains(&(3, 1, 2)));
+        // The wall is still two cells higher than the step
+        assert!(!graph.edges[&(4, 2, 2)].iter().any(|&(x, _, _)| x == 5));
+    }
+}
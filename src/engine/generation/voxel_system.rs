@@ -13,9 +13,10 @@ use crate::engine::{
 };
 use cgmath::InnerSpace;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use super::content_generators::{EnvironmentType, WeatherPattern, MaterialProperties};
 use super::noise::SurfaceProperties;
+use super::pathfinding::VisibilityGraph;
 
 // Voxel-specific type definitions to avoid ambiguity
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1002,6 +1003,7 @@ impl VoxelSystem {
     fn apply_color_variations(&mut self, grid: &mut VoxelGrid, color_variations: &Vec<String>) -> RobinResult<()> { Ok(()) }
 
     /// Generate mesh from voxel world
+    #[tracing::instrument(skip_all, fields(world = %voxel_world.name, chunks = voxel_world.chunks.len()))]
     pub fn generate_mesh(&mut self, voxel_world: &VoxelWorld) -> RobinResult<crate::engine::gpu::integration::VoxelMesh> {
         // Simple mesh generation from voxel world
         let mut vertices = Vec::new();
@@ -1286,6 +1288,89 @@ impl VoxelWorld {
         chunk.grid.set_voxel(local_pos.0, local_pos.1, local_pos.2, voxel);
         chunk.dirty = true;
     }
+
+    pub(super) fn voxel_at(&self, (x, y, z): (usize, usize, usize)) -> Option<VoxelType> {
+        self.get_voxel(Vec3::new(x as f32, y as f32, z as f32))
+    }
+
+    pub(super) fn contains(&self, (x, y, z): (usize, usize, usize)) -> bool {
+        x < self.world_size.0 && y < self.world_size.1 && z < self.world_size.2
+    }
+
+    /// The six face-adjacent positions of a cell, including any outside the world
+    fn face_neighbors((x, y, z): (usize, usize, usize)) -> [(usize, usize, usize); 6] {
+        // Stepping below zero wraps to usize::MAX, which is never inside the world
+        [
+            (x.wrapping_sub(1), y, z),
+            (x + 1, y, z),
+            (x, y.wrapping_sub(1), z),
+            (x, y + 1, z),
+            (x, y, z.wrapping_sub(1)),
+            (x, y, z + 1),
+        ]
+    }
+
+    /// Every cell reachable from `start` through face-adjacent cells whose
+    /// voxel satisfies `predicate`, `start` included. Empty cells are `None`.
+    /// The result is empty if `start` is outside the world or fails `predicate`.
+    pub fn flood_fill_reachable(
+        &self,
+        start: (usize, usize, usize),
+        predicate: impl Fn(Option<VoxelType>) -> bool,
+    ) -> HashSet<(usize, usize, usize)> {
+        let mut reached = HashSet::new();
+        if !self.contains(start) || !predicate(self.voxel_at(start)) {
+            return reached;
+        }
+
+        reached.insert(start);
+        let mut frontier = VecDeque::from([start]);
+        while let Some(cell) = frontier.pop_front() {
+            for neighbor in Self::face_neighbors(cell) {
+                if self.contains(neighbor) && !reached.contains(&neighbor) && predicate(self.voxel_at(neighbor)) {
+                    reached.insert(neighbor);
+                    frontier.push_back(neighbor);
+                }
+            }
+        }
+        reached
+    }
+
+    /// Walkable positions and the moves between them for an agent
+    /// `agent_height` cells tall; see `VisibilityGraph`
+    pub fn compute_visibility_graph(&self, agent_height: usize) -> VisibilityGraph {
+        VisibilityGraph::build(self, agent_height)
+    }
+
+    /// Number of separate face-connected regions of cells satisfying `predicate`
+    pub fn count_connected_components(&self, predicate: impl Fn(Option<VoxelType>) -> bool) -> usize {
+        let (size_x, size_y, size_z) = self.world_size;
+        let mut seen = HashSet::new();
+        let mut components = 0;
+        for x in 0..size_x {
+            for y in 0..size_y {
+                for z in 0..size_z {
+                    if seen.contains(&(x, y, z)) || !predicate(self.voxel_at((x, y, z))) {
+                        continue;
+                    }
+                    seen.extend(self.flood_fill_reachable((x, y, z), &predicate));
+                    components += 1;
+                }
+            }
+        }
+        components
+    }
+
+    /// Whether every cell touching `region` across a face, and not part of
+    /// it, holds a solid voxel. A region reaching the edge of the world is
+    /// open to the outside and never enclosed.
+    pub fn is_enclosed(&self, region: &HashSet<(usize, usize, usize)>) -> bool {
+        region.iter().all(|&cell| {
+            Self::face_neighbors(cell).into_iter().filter(|neighbor| !region.contains(neighbor)).all(|neighbor| {
+                self.contains(neighbor) && self.voxel_at(neighbor).is_some_and(|voxel_type| voxel_type != VoxelType::Air)
+            })
+        })
+    }
 }
 
 /// A chunk of voxels for efficient storage and rendering
@@ -1487,6 +1572,41 @@ mod tests {
         assert_eq!(retrieved.voxel_type, VoxelType::Solid);
     }
 
+    #[test]
+    fn test_flood_fill_finds_enclosed_cavity() {
+        // A stone shell from 2 to 7 on every axis around a 4x4x4 cavity
+        let mut world = VoxelWorld::new("cavity".to_string(), (10, 10, 10));
+        for x in 2..=7 {
+            for y in 2..=7 {
+                for z in 2..=7 {
+                    if [x, y, z].iter().any(|&c| c == 2 || c == 7) {
+                        world.set_voxel(Vec3::new(x as f32, y as f32, z as f32), VoxelType::Stone);
+                    }
+                }
+            }
+        }
+        let empty = |voxel: Option<VoxelType>| voxel.is_none_or(|voxel_type| voxel_type == VoxelType::Air);
+
+        let cavity = world.flood_fill_reachable((4, 4, 4), empty);
+        assert_eq!(cavity.len(), 64);
+        assert!(cavity.contains(&(3, 3, 3)) && cavity.contains(&(6, 6, 6)));
+        assert!(world.is_enclosed(&cavity));
+
+        let outside = world.flood_fill_reachable((0, 0, 0), empty);
+        assert_eq!(outside.len(), 1000 - 216);
+        assert!(!world.is_enclosed(&outside));
+        assert_eq!(world.count_connected_components(empty), 2);
+        assert_eq!(world.count_connected_components(|voxel| voxel.is_some()), 1);
+        assert!(world.flood_fill_reachable((2, 2, 2), empty).is_empty());
+
+        // Knocking a hole in the shell joins the cavity to the outside
+        world.set_voxel(Vec3::new(7.0, 4.0, 4.0), VoxelType::Air);
+        let cavity = world.flood_fill_reachable((4, 4, 4), empty);
+        assert_eq!(cavity.len(), 1000 - 216 + 64 + 1);
+        assert!(!world.is_enclosed(&cavity));
+        assert_eq!(world.count_connected_components(empty), 1);
+    }
+
     #[test]
     fn test_voxel_system_creation() {
         let config = VoxelConfig::default();
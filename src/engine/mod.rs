@@ -12,6 +12,7 @@ pub mod game_builder;
 pub mod error;
 pub mod logging;
 pub mod diagnostics;
+pub mod telemetry;
 pub mod save_system;
 pub mod prelude;
 pub mod generation;
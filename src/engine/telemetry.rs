@@ -0,0 +1,255 @@
+// Robin Game Engine - Structured Tracing
+// Installs the `tracing` subscriber the engine's spans and logs go through,
+// and keeps the most recent closed spans in memory so their timings can be
+// exported for profiling
+
+use crate::engine::error::{RobinError, RobinResult};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer};
+
+/// Closed spans `EngineMetrics::default` keeps
+pub const DEFAULT_SPAN_CAPACITY: usize = 1024;
+
+/// Filter used when `RUST_LOG` isn't set
+const DEFAULT_FILTER: &str = "info";
+
+/// One closed span, as written by `EngineMetrics::export_spans`
+#[derive(Debug, Clone, Serialize)]
+pub struct SpanRecord {
+    pub name: String,
+    pub target: String,
+    pub started_at: DateTime<Utc>,
+    pub duration_us: u64,
+    pub fields: Map<String, Value>,
+}
+
+/// Start time and fields of a span that's still open, kept in the span's
+/// extensions until it closes
+struct OpenSpan {
+    started_at: DateTime<Utc>,
+    opened: Instant,
+    fields: Map<String, Value>,
+}
+
+/// Collects span fields as JSON values
+struct FieldVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_string(), Value::from(format!("{:?}", value)));
+    }
+}
+
+/// Subscriber layer keeping the last `capacity` closed spans. Clones share
+/// the same spans, so one can be installed while another is kept for export.
+#[derive(Debug, Clone)]
+pub struct EngineMetrics {
+    spans: Arc<Mutex<VecDeque<SpanRecord>>>,
+    capacity: usize,
+}
+
+impl EngineMetrics {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            spans: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// The kept spans, in the order they closed
+    pub fn spans(&self) -> Vec<SpanRecord> {
+        self.spans.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Writes the kept spans to `path` as newline-delimited JSON, in the
+    /// order they closed, replacing any existing file
+    pub fn export_spans(&self, path: &Path) -> RobinResult<()> {
+        let save_error = |reason: String| RobinError::FileSaveError {
+            path: path.to_path_buf(),
+            reason,
+        };
+        let mut writer = BufWriter::new(File::create(path).map_err(|e| save_error(e.to_string()))?);
+        for span in self.spans() {
+            serde_json::to_writer(&mut writer, &span).map_err(|e| save_error(e.to_string()))?;
+            writer.write_all(b"\n").map_err(|e| save_error(e.to_string()))?;
+        }
+        writer.flush().map_err(|e| save_error(e.to_string()))
+    }
+}
+
+impl Default for EngineMetrics {
+    fn default() -> Self {
+        Self::new(DEFAULT_SPAN_CAPACITY)
+    }
+}
+
+impl<S> Layer<S> for EngineMetrics
+where
+    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = Map::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        span.extensions_mut().insert(OpenSpan {
+            started_at: Utc::now(),
+            opened: Instant::now(),
+            fields,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(open) = extensions.get_mut::<OpenSpan>() {
+            values.record(&mut FieldVisitor(&mut open.fields));
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(open) = span.extensions_mut().remove::<OpenSpan>() else {
+            return;
+        };
+        let record = SpanRecord {
+            name: span.name().to_string(),
+            target: span.metadata().target().to_string(),
+            started_at: open.started_at,
+            duration_us: open.opened.elapsed().as_micros() as u64,
+            fields: open.fields,
+        };
+
+        let mut spans = self.spans.lock().unwrap();
+        if spans.len() == self.capacity {
+            spans.pop_front();
+        }
+        spans.push_back(record);
+    }
+}
+
+/// Installs the global subscriber and returns the metrics layer it records
+/// spans into. Events and closed spans are logged to stderr, filtered by
+/// `RUST_LOG` (`info` when unset); given `json_log`, they're also appended
+/// to that file as JSON lines for production deployments. Records from the
+/// `log` crate are forwarded through the same filter.
+pub fn init_tracing(json_log: Option<&Path>) -> RobinResult<EngineMetrics> {
+    let json_layer = match json_log {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| RobinError::FileSaveError {
+                    path: path.to_path_buf(),
+                    reason: e.to_string(),
+                })?;
+            Some(
+                fmt::layer()
+                    .json()
+                    .with_span_events(FmtSpan::CLOSE)
+                    .with_writer(Mutex::new(file)),
+            )
+        }
+        None => None,
+    };
+
+    let metrics = EngineMetrics::default();
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER)))
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(json_layer)
+        .with(metrics.clone())
+        .try_init()
+        .map_err(|e| RobinError::InitializationError {
+            subsystem: "tracing".to_string(),
+            reason: e.to_string(),
+        })?;
+    Ok(metrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::generation::voxel_system::{VoxelConfig, VoxelSystem, VoxelWorld};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_generate_mesh_span_is_exported() -> RobinResult<()> {
+        let metrics = EngineMetrics::new(16);
+        let subscriber = tracing_subscriber::registry().with(metrics.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            let mut system = VoxelSystem::new(VoxelConfig::default());
+            let world = VoxelWorld::new("traced".to_string(), (32, 32, 32));
+            system.generate_mesh(&world).map(|_| ())
+        })?;
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("spans.ndjson");
+        metrics.export_spans(&path)?;
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<Value> = contents.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["name"], "generate_mesh");
+        assert!(lines[0]["duration_us"].is_u64());
+        assert_eq!(lines[0]["fields"]["world"], "traced");
+        Ok(())
+    }
+
+    #[test]
+    fn test_only_the_latest_spans_are_kept() {
+        let metrics = EngineMetrics::new(2);
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(metrics.clone()), || {
+            for frame in 0..3u64 {
+                let _span = tracing::info_span!("frame", frame).entered();
+            }
+        });
+
+        let frames: Vec<Value> = metrics.spans().iter().map(|span| span.fields["frame"].clone()).collect();
+        assert_eq!(frames, [Value::from(1u64), Value::from(2u64)]);
+    }
+}
@@ -3,7 +3,8 @@ use std::time::Instant;
 
 pub async fn run_magical_demo() {
     println!("DEBUG: Starting run_magical_demo");
-    env_logger::init();
+    // A no-op when main has already routed `log` through tracing
+    let _ = env_logger::try_init();
     println!("DEBUG: env_logger initialized");
     log::info!("Starting magical demo with particles, lighting, and animations");
     println!("DEBUG: About to create GameBuilder");
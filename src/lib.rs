@@ -1,5 +1,6 @@
 pub mod engine;
 pub mod examples;
+pub mod cloud;
 // pub mod research; // Temporarily disabled to focus on core engine
 
 // Re-export commonly used types for convenience
@@ -5,6 +5,12 @@ use examples::{run_basic_window, run_magical_demo};
 
 #[tokio::main]
 async fn main() {
+    // Set ROBIN_LOG_JSON to a file path to also write JSON logs there
+    let json_log = std::env::var_os("ROBIN_LOG_JSON").map(std::path::PathBuf::from);
+    if let Err(e) = engine::telemetry::init_tracing(json_log.as_deref()) {
+        eprintln!("Tracing disabled: {}", e);
+    }
+
     // Choose which demo to run
     let demo = std::env::args().nth(1).unwrap_or_else(|| "magical".to_string());
     
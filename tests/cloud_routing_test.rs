@@ -0,0 +1,81 @@
+// Regional routing tests for the cloud platform
+// Uses the network simulator to check that routing decisions minimise latency
+
+use robin::cloud::simulation::CloudSimulator;
+use robin::cloud::*;
+use std::time::Duration;
+
+const SINGAPORE: (f64, f64) = (1.2903, 103.8520);
+
+fn initialized_manager() -> CloudPlatformManager {
+    let mut manager = CloudPlatformManager::new();
+    manager.initialize().expect("cloud platform failed to initialize");
+    manager
+}
+
+/// Simulator covering every deployed region plus a client at `location`
+fn simulator_with_client(manager: &CloudPlatformManager, client: &str, location: (f64, f64)) -> CloudSimulator {
+    let mut simulator = CloudSimulator::from_regions(&manager.deployment_regions, 7);
+    for (region_id, region) in &manager.deployment_regions {
+        let distance = great_circle_distance_km(location, region.geographic_location.coordinates);
+        // 5 ms overhead plus the round trip at ~200 km/ms through fibre
+        let latency = Duration::from_secs_f64((5.0 + distance / 100.0) / 1000.0);
+        simulator.set_latency(client, region_id, latency);
+    }
+    simulator
+}
+
+#[test]
+fn test_geographic_proximity_routes_singapore_to_ap_southeast() {
+    let manager = initialized_manager();
+    assert!(matches!(
+        manager.global_configuration.load_balancing.algorithm,
+        LoadBalancingAlgorithm::GeographicProximity
+    ));
+
+    let routed = manager.route_user(SINGAPORE).expect("no region selected");
+    assert_eq!(routed, "ap-southeast-1");
+
+    let mut simulator = simulator_with_client(&manager, "singapore-user", SINGAPORE);
+    let local = simulator.simulate_request("singapore-user", &routed);
+    let remote = simulator.simulate_request("singapore-user", "us-east-1");
+    assert!(local.delivered && remote.delivered);
+    assert!(local.latency < Duration::from_millis(10));
+    assert!(remote.latency > Duration::from_millis(100));
+}
+
+#[test]
+fn test_routed_region_has_lowest_simulated_latency() {
+    let manager = initialized_manager();
+    let users = [
+        ("dublin-user", (53.3331, -6.2489)),
+        ("sao-paulo-user", (-23.5489, -46.6388)),
+        ("tokyo-user", (35.6895, 139.6917)),
+        ("seattle-user", (47.6062, -122.3321)),
+    ];
+
+    for (user, location) in users {
+        let mut simulator = simulator_with_client(&manager, user, location);
+        let routed = manager.route_user(location).unwrap();
+        let routed_latency = simulator.simulate_request(user, &routed).latency;
+
+        for region_id in manager.deployment_regions.keys() {
+            let latency = simulator.simulate_request(user, region_id).latency;
+            assert!(routed_latency <= latency, "{} routed to {} but {} is faster", user, routed, region_id);
+        }
+    }
+}
+
+#[test]
+fn test_packet_loss_only_affects_lossy_region() {
+    let manager = initialized_manager();
+    let mut simulator = simulator_with_client(&manager, "singapore-user", SINGAPORE);
+    simulator.set_packet_loss("ap-southeast-1", 0.1);
+
+    let lost = (0..1000)
+        .filter(|_| !simulator.simulate_request("singapore-user", "ap-southeast-1").delivered)
+        .count();
+    assert!((60..140).contains(&lost), "lost {} of 1000", lost);
+
+    assert!((0..100).all(|_| simulator.simulate_request("singapore-user", "ap-south-1").delivered));
+}
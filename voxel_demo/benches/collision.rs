@@ -0,0 +1,54 @@
+// Collision query benchmarks
+// Times the spatial hash query the physics step makes for the player's box,
+// with 1000 filled voxels scattered within 10 voxels of the player. The target
+// is under 1 µs per query.
+//
+//     cargo bench --bench collision
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::collections::HashSet;
+use voxel_demo::registry;
+use voxel_demo::spatial_hash::{SpatialHash, DEFAULT_CELL_SIZE};
+
+const FILLED_VOXELS: usize = 1000;
+const RADIUS: i32 = 10;
+/// The player's box, standing at the origin
+const PLAYER_MIN: [f32; 3] = [-0.3, 0.0, -0.3];
+const PLAYER_MAX: [f32; 3] = [0.3, 1.8, 0.3];
+
+/// `FILLED_VOXELS` distinct voxels at random positions within `RADIUS` of
+/// the origin
+fn surrounded_player() -> SpatialHash {
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut next = move |range: i32| {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % (2 * range as u64 + 1)) as i32 - range
+    };
+
+    let mut positions = HashSet::new();
+    while positions.len() < FILLED_VOXELS {
+        let p = [next(RADIUS), next(RADIUS), next(RADIUS)];
+        if p.iter().map(|c| c * c).sum::<i32>() <= RADIUS * RADIUS {
+            positions.insert(p);
+        }
+    }
+
+    let mut hash = SpatialHash::new(DEFAULT_CELL_SIZE);
+    for p in positions {
+        hash.insert(p, registry::STONE);
+    }
+    hash
+}
+
+fn bench_query_aabb(c: &mut Criterion) {
+    let hash = surrounded_player();
+    c.bench_function("collision/query_aabb", |b| {
+        b.iter(|| hash.query_aabb(black_box(PLAYER_MIN), black_box(PLAYER_MAX)))
+    });
+}
+
+criterion_group!(benches, bench_query_aabb);
+criterion_main!(benches);
@@ -0,0 +1,102 @@
+// Mesh generation benchmarks
+// Times world generation and meshing at several world sizes. Each meshing
+// benchmark runs on flat ground, the best case for the greedy mesher, and on
+// a 3D checkerboard, where every face of every voxel is exposed and nothing
+// can be merged. Throughput is reported in vertices: criterion prints it per
+// second, so divide by 1000 for vertices/ms.
+//
+// Changes smaller than `REGRESSION_THRESHOLD` are treated as noise. To check a
+// branch against main:
+//
+//     cargo bench --bench mesh_generation -- --save-baseline main   # on main
+//     cargo bench --bench mesh_generation -- --baseline main        # on the branch
+//
+// and look for "Performance has regressed" in the output.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::time::Duration;
+use voxel_demo::{registry, VoxelWorld};
+
+/// Relative slowdown that counts as a regression
+const REGRESSION_THRESHOLD: f64 = 0.20;
+
+const WORLD_SIZES: [usize; 3] = [16, 32, 64];
+
+/// Builds a benchmark world of the given size
+type WorldBuilder = fn(usize) -> VoxelWorld;
+
+/// Solid stone up to half the world's height
+fn flat_world(size: usize) -> VoxelWorld {
+    let mut world = VoxelWorld::empty(size);
+    for x in 0..size {
+        for y in 0..size / 2 {
+            for z in 0..size {
+                world.set_voxel(x, y, z, Some(registry::STONE));
+            }
+        }
+    }
+    world
+}
+
+/// Every other voxel filled, alternating types, so no two faces can merge
+fn checkerboard_world(size: usize) -> VoxelWorld {
+    let mut world = VoxelWorld::empty(size);
+    for x in 0..size {
+        for y in 0..size {
+            for z in 0..size {
+                if (x + y + z) % 2 == 0 {
+                    let voxel = if x % 2 == 0 { registry::STONE } else { registry::DIRT };
+                    world.set_voxel(x, y, z, Some(voxel));
+                }
+            }
+        }
+    }
+    world
+}
+
+fn bench_world_generation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("world_generation");
+    for size in WORLD_SIZES {
+        group.throughput(Throughput::Elements(size.pow(3) as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter(|| VoxelWorld::new(black_box(size)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_mesh_generation(c: &mut Criterion) {
+    let terrains: [(&str, WorldBuilder); 2] = [("flat", flat_world), ("checkerboard", checkerboard_world)];
+    for (terrain, build) in terrains {
+        let mut group = c.benchmark_group(format!("mesh_generation/{}", terrain));
+        for size in WORLD_SIZES {
+            let world = build(size);
+
+            group.throughput(Throughput::Elements(world.generate_mesh().0.len() as u64));
+            group.bench_with_input(BenchmarkId::new("greedy", size), &world, |b, world| {
+                b.iter(|| world.generate_mesh())
+            });
+
+            group.throughput(Throughput::Elements(world.generate_mesh_naive().0.len() as u64));
+            group.bench_with_input(BenchmarkId::new("naive", size), &world, |b, world| {
+                b.iter(|| world.generate_mesh_naive())
+            });
+        }
+        group.finish();
+    }
+}
+
+fn config() -> Criterion {
+    Criterion::default()
+        .noise_threshold(REGRESSION_THRESHOLD)
+        // A 64³ checkerboard takes around half a second to mesh
+        .sample_size(20)
+        .measurement_time(Duration::from_secs(10))
+}
+
+criterion_group! {
+    name = benches;
+    config = config();
+    targets = bench_world_generation, bench_mesh_generation
+}
+criterion_main!(benches);
@@ -0,0 +1,93 @@
+// SDF font baker
+// Regenerates `src/fonts/mono_sdf.r8`, the atlas `text::TextRenderer` draws
+// HUD labels with, from a monospaced TrueType font. The checked-in atlas is
+// baked from DejaVu Sans Mono:
+//
+//     cargo run --release --example bake_sdf_font -- /usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf
+
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use voxel_demo::text::{
+    ADVANCE, ATLAS_COLUMNS, ATLAS_ROWS, BASELINE, CELL_HEIGHT, CELL_WIDTH, FIRST_CHAR, FONT_SIZE, GLYPH_COUNT, ORIGIN_X,
+    SDF_SPREAD,
+};
+
+/// Each glyph is rasterized at this many times the atlas resolution, and the
+/// distance to its edge measured at that resolution
+const SUPERSAMPLE: u32 = 8;
+
+fn main() {
+    let path = std::env::args().nth(1).expect("usage: bake_sdf_font <font.ttf>");
+    let data = std::fs::read(&path).expect("couldn't read the font");
+    let font = FontRef::try_from_slice(&data).expect("not a TrueType font");
+
+    let advance = font.as_scaled(PxScale::from(FONT_SIZE)).h_advance(font.glyph_id('M'));
+    assert!(
+        (advance - ADVANCE).abs() < 0.5,
+        "font advance {} doesn't match text::ADVANCE; is it monospaced?",
+        advance
+    );
+
+    let atlas_width = (ATLAS_COLUMNS * CELL_WIDTH) as usize;
+    let mut atlas = vec![0u8; atlas_width * (ATLAS_ROWS * CELL_HEIGHT) as usize];
+    for index in 0..GLYPH_COUNT {
+        let c = char::from_u32(FIRST_CHAR + index).unwrap();
+        let left = ((index % ATLAS_COLUMNS) * CELL_WIDTH) as usize;
+        let top = ((index / ATLAS_COLUMNS) * CELL_HEIGHT) as usize;
+        let field = glyph_field(&font, c);
+        for y in 0..CELL_HEIGHT as usize {
+            let row = (top + y) * atlas_width + left;
+            atlas[row..row + CELL_WIDTH as usize].copy_from_slice(&field[y * CELL_WIDTH as usize..][..CELL_WIDTH as usize]);
+        }
+    }
+
+    let output = concat!(env!("CARGO_MANIFEST_DIR"), "/src/fonts/mono_sdf.r8");
+    std::fs::write(output, &atlas).expect("couldn't write the atlas");
+    println!("Wrote {} ({} bytes)", output, atlas.len());
+}
+
+/// One cell of the distance field for `c`: 0.5 on the edge, rising to 1 at
+/// `SDF_SPREAD` texels inside and falling to 0 as far outside
+fn glyph_field(font: &FontRef, c: char) -> Vec<u8> {
+    let (width, height) = ((CELL_WIDTH * SUPERSAMPLE) as i32, (CELL_HEIGHT * SUPERSAMPLE) as i32);
+    let mut inside = vec![false; (width * height) as usize];
+    let glyph = font
+        .glyph_id(c)
+        .with_scale_and_position(FONT_SIZE * SUPERSAMPLE as f32, point(ORIGIN_X * SUPERSAMPLE as f32, BASELINE * SUPERSAMPLE as f32));
+    if let Some(outline) = font.outline_glyph(glyph) {
+        let bounds = outline.px_bounds();
+        outline.draw(|x, y, coverage| {
+            let (x, y) = (bounds.min.x as i32 + x as i32, bounds.min.y as i32 + y as i32);
+            if (0..width).contains(&x) && (0..height).contains(&y) && coverage >= 0.5 {
+                inside[(y * width + x) as usize] = true;
+            }
+        });
+    }
+
+    let reach = (SDF_SPREAD * SUPERSAMPLE as f32) as i32;
+    let mut field = Vec::with_capacity((CELL_WIDTH * CELL_HEIGHT) as usize);
+    for y in 0..CELL_HEIGHT as i32 {
+        for x in 0..CELL_WIDTH as i32 {
+            // Sample at the texel centre, looking for the nearest
+            // high-resolution pixel on the other side of the edge
+            let (cx, cy) = (x * SUPERSAMPLE as i32 + SUPERSAMPLE as i32 / 2, y * SUPERSAMPLE as i32 + SUPERSAMPLE as i32 / 2);
+            let here = inside[(cy * width + cx) as usize];
+            let mut nearest = reach as f32;
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    let (sx, sy) = (cx + dx, cy + dy);
+                    // Outside the cell counts as outside the glyph
+                    let there = (0..width).contains(&sx)
+                        && (0..height).contains(&sy)
+                        && inside[(sy * width + sx) as usize];
+                    if there != here {
+                        nearest = nearest.min(((dx * dx + dy * dy) as f32).sqrt());
+                    }
+                }
+            }
+            let distance = nearest / SUPERSAMPLE as f32 * if here { 1.0 } else { -1.0 };
+            let value = (0.5 + distance / (2.0 * SDF_SPREAD)).clamp(0.0, 1.0);
+            field.push((value * 255.0).round() as u8);
+        }
+    }
+    field
+}
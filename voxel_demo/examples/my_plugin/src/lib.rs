@@ -0,0 +1,72 @@
+// Example voxel_demo plugin
+// Adds a glowing lava voxel and a `lava X Y Z` console command that pours it.
+// Poured lava cools to stone a few seconds later.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use voxel_demo::console::DevConsole;
+use voxel_demo::plugin::RobinPlugin;
+use voxel_demo::registry::{self, VoxelDefinition, VoxelRegistry};
+use voxel_demo::VoxelWorld;
+
+/// Seconds poured lava stays molten
+const COOLING_TIME: f32 = 5.0;
+
+/// Lava poured by the command and the seconds until each cools, shared with
+/// the command handler
+type MoltenLava = Rc<RefCell<Vec<([usize; 3], f32)>>>;
+
+#[derive(Default)]
+pub struct LavaPlugin {
+    molten: MoltenLava,
+}
+
+impl RobinPlugin for LavaPlugin {
+    fn name(&self) -> &str {
+        "lava"
+    }
+
+    fn register_voxels(&self, registry: &mut VoxelRegistry) {
+        registry.register(VoxelDefinition::new("lava", [1.0, 0.35, 0.05], true, false, 0.0));
+    }
+
+    fn register_commands(&self, console: &mut DevConsole) {
+        let molten = self.molten.clone();
+        console.register_command(
+            "lava",
+            Box::new(move |args, world| {
+                let Some(lava) = world.registry().find("lava") else {
+                    return "This world has no lava type".to_string();
+                };
+                let coords: Vec<usize> = args.iter().filter_map(|arg| arg.parse().ok()).collect();
+                let [x, y, z] = coords[..] else {
+                    return "usage: lava X Y Z".to_string();
+                };
+                if x >= world.size() || y >= world.size() || z >= world.size() {
+                    return format!("({}, {}, {}) is outside the world", x, y, z);
+                }
+
+                world.set_voxel(x, y, z, Some(lava));
+                molten.borrow_mut().push(([x, y, z], COOLING_TIME));
+                format!("Poured lava at ({}, {}, {})", x, y, z)
+            }),
+        );
+    }
+
+    fn on_update(&mut self, world: &mut VoxelWorld, dt: f32) {
+        let lava = world.registry().find("lava");
+        self.molten.borrow_mut().retain_mut(|([x, y, z], remaining)| {
+            *remaining -= dt;
+            if *remaining > 0.0 {
+                return true;
+            }
+            // Leave it alone if it was dug out or built over in the meantime
+            if lava.is_some() && world.get(*x, *y, *z) == lava {
+                world.set_voxel(*x, *y, *z, Some(registry::STONE));
+            }
+            false
+        });
+    }
+}
+
+voxel_demo::export_plugin!(LavaPlugin::default);
@@ -0,0 +1,216 @@
+// World annotations
+// Labels a teacher pins to points in the world, optionally with an arrow to
+// something worth looking at. They're drawn over the scene at their
+// position, always facing the camera, and shared with everyone in a
+// multiplayer session.
+
+use crate::error::RobinResult;
+use crate::multiplayer::MultiplayerSession;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+/// Annotations further than this from the camera aren't drawn
+pub const VISIBLE_DISTANCE: f32 = 20.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorldAnnotation {
+    pub world_position: [f32; 3],
+    pub text: String,
+    /// Where an arrow from the label points, if it has one
+    pub arrow_target: Option<[f32; 3]>,
+    pub author_id: String,
+    pub color: [f32; 4],
+    pub created_at: SystemTime,
+}
+
+impl WorldAnnotation {
+    /// An author never creates two annotations at the same instant, so the
+    /// pair identifies an annotation across clients
+    fn is(&self, author_id: &str, created_at: SystemTime) -> bool {
+        self.author_id == author_id && self.created_at == created_at
+    }
+}
+
+/// An annotation added or removed, as passed between clients
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AnnotationChange {
+    Added(WorldAnnotation),
+    Removed { author_id: String, created_at: SystemTime },
+}
+
+#[derive(Default)]
+pub struct AnnotationLayer {
+    pub annotations: Vec<WorldAnnotation>,
+    /// Changes made here that haven't been sent yet, oldest first
+    outgoing: Vec<AnnotationChange>,
+}
+
+impl AnnotationLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, annotation: WorldAnnotation) {
+        self.outgoing.push(AnnotationChange::Added(annotation.clone()));
+        self.annotations.push(annotation);
+    }
+
+    /// Removes the annotation `author_id` created at `created_at`, returning
+    /// whether there was one
+    pub fn remove(&mut self, author_id: &str, created_at: SystemTime) -> bool {
+        let change = AnnotationChange::Removed {
+            author_id: author_id.to_string(),
+            created_at,
+        };
+        let removed = self.apply(change.clone());
+        if removed {
+            self.outgoing.push(change);
+        }
+        removed
+    }
+
+    /// Makes a change received from another client, without sending it on.
+    /// Returns whether anything changed.
+    pub fn apply(&mut self, change: AnnotationChange) -> bool {
+        match change {
+            AnnotationChange::Added(annotation) => {
+                if self.annotations.iter().any(|a| a.is(&annotation.author_id, annotation.created_at)) {
+                    return false;
+                }
+                self.annotations.push(annotation);
+                true
+            }
+            AnnotationChange::Removed { author_id, created_at } => {
+                let count = self.annotations.len();
+                self.annotations.retain(|a| !a.is(&author_id, created_at));
+                self.annotations.len() < count
+            }
+        }
+    }
+
+    /// Annotations close enough to `camera` to draw
+    pub fn visible(&self, camera: [f32; 3]) -> impl Iterator<Item = &WorldAnnotation> {
+        self.annotations.iter().filter(move |a| distance(a.world_position, camera) <= VISIBLE_DISTANCE)
+    }
+
+    /// The annotation nearest `position`, if any is within `radius`
+    pub fn nearest(&self, position: [f32; 3], radius: f32) -> Option<&WorldAnnotation> {
+        self.annotations
+            .iter()
+            .map(|a| (distance(a.world_position, position), a))
+            .filter(|&(d, _)| d <= radius)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, a)| a)
+    }
+
+    /// Applies the changes other clients have made and broadcasts the ones
+    /// made here. Changes only arrive while something is calling
+    /// `MultiplayerSession::receive`; a change that fails to send is kept
+    /// for the next sync.
+    pub async fn sync(&mut self, ws_session: &mut MultiplayerSession) -> RobinResult<()> {
+        for change in ws_session.take_annotation_changes() {
+            self.apply(change);
+        }
+        while let Some(change) = self.outgoing.first() {
+            ws_session.send_annotation(change.clone()).await?;
+            self.outgoing.remove(0);
+        }
+        Ok(())
+    }
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    (0..3).map(|axis| (a[axis] - b[axis]).powi(2)).sum::<f32>().sqrt()
+}
+
+/// Pixel position of world-space `position` on a `width` by `height` screen
+/// drawn with `view_proj`, from the top left corner. Points behind the
+/// camera have no position.
+pub fn project_to_screen(view_proj: [[f32; 4]; 4], position: [f32; 3], width: f32, height: f32) -> Option<[f32; 2]> {
+    let clip: [f32; 4] = std::array::from_fn(|row| {
+        (0..3).map(|col| view_proj[col][row] * position[col]).sum::<f32>() + view_proj[3][row]
+    });
+    if clip[3] <= 0.0 {
+        return None;
+    }
+    let (ndc_x, ndc_y) = (clip[0] / clip[3], clip[1] / clip[3]);
+    Some([(ndc_x + 1.0) / 2.0 * width, (1.0 - ndc_y) / 2.0 * height])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{multiply_matrices, projection_matrix, Camera};
+
+    fn annotation(author_id: &str, world_position: [f32; 3]) -> WorldAnnotation {
+        WorldAnnotation {
+            world_position,
+            text: "Look here".to_string(),
+            arrow_target: None,
+            author_id: author_id.to_string(),
+            color: [1.0; 4],
+            created_at: SystemTime::now(),
+        }
+    }
+
+    fn assert_near(actual: Option<[f32; 2]>, expected: [f32; 2]) {
+        let actual = actual.expect("the point should be in front of the camera");
+        assert!(
+            (actual[0] - expected[0]).abs() <= 2.0 && (actual[1] - expected[1]).abs() <= 2.0,
+            "label at {:?}, expected {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_label_projects_to_expected_pixel() {
+        let (width, height) = (800.0, 600.0);
+        let projection = projection_matrix(width / height);
+
+        // Looking down -z from the origin, with a 60 degree vertical field
+        // of view: half a screen right of centre is tan(30°) * aspect * 0.5
+        // units out per unit of depth, and the same for height
+        let camera = Camera { position: [0.0; 3], yaw: 0.0, pitch: 0.0 };
+        let view_proj = multiply_matrices(projection, camera.view_matrix());
+        let half = 30.0f32.to_radians().tan() * 0.5;
+        assert_near(project_to_screen(view_proj, [0.0, 0.0, -10.0], width, height), [400.0, 300.0]);
+        assert_near(
+            project_to_screen(view_proj, [10.0 * half * width / height, 0.0, -10.0], width, height),
+            [600.0, 300.0],
+        );
+        assert_near(project_to_screen(view_proj, [0.0, -10.0 * half, -10.0], width, height), [400.0, 450.0]);
+        assert_eq!(project_to_screen(view_proj, [0.0, 0.0, 10.0], width, height), None);
+
+        // Turned to face +x from elsewhere in the world
+        let camera = Camera { position: [5.0, 8.0, 5.0], yaw: -90.0f32.to_radians(), pitch: 0.0 };
+        let view_proj = multiply_matrices(projection, camera.view_matrix());
+        assert_near(project_to_screen(view_proj, [15.0, 8.0, 5.0], width, height), [400.0, 300.0]);
+        assert_near(project_to_screen(view_proj, [15.0, 8.0 + 10.0 * half, 5.0], width, height), [400.0, 150.0]);
+    }
+
+    #[test]
+    fn test_changes_are_queued_and_applied_once() {
+        let mut teacher = AnnotationLayer::new();
+        let mut student = AnnotationLayer::new();
+        let near = annotation("teacher", [1.0, 2.0, 3.0]);
+        let far = WorldAnnotation {
+            created_at: near.created_at + std::time::Duration::from_secs(1),
+            ..annotation("teacher", [40.0, 2.0, 3.0])
+        };
+        teacher.add(near.clone());
+        teacher.add(far.clone());
+        assert!(teacher.remove("teacher", far.created_at));
+        assert!(!teacher.remove("student", near.created_at));
+
+        for change in std::mem::take(&mut teacher.outgoing) {
+            student.apply(change);
+        }
+        assert_eq!(student.annotations, vec![near.clone()]);
+        assert!(!student.apply(AnnotationChange::Added(near.clone())));
+
+        assert_eq!(student.visible([0.0; 3]).count(), 1);
+        assert_eq!(student.visible([30.0, 2.0, 3.0]).count(), 0);
+        assert_eq!(student.nearest([1.5, 2.0, 3.0], 1.0), Some(&near));
+    }
+}
@@ -0,0 +1,400 @@
+// Texture atlas
+// Packs a 16×16 tile for each voxel type into one 128×128 texture. Tiles are
+// loaded from `<type name>.png` in a texture directory when present, and
+// otherwise generated as checkerboards so the demo (and its tests) run
+// without any image files. Tiles are multiplied by the voxel's color in the
+// shader, so the built-in checkerboards are grayscale.
+
+use crate::error::{RobinError, RobinResult};
+use crate::registry::{VoxelId, VoxelRegistry};
+use image::imageops::FilterType;
+use std::path::Path;
+use wgpu::util::DeviceExt;
+
+/// Edge length of one tile in texels
+pub const TILE_SIZE: u32 = 16;
+/// Edge length of the atlas texture in texels
+pub const ATLAS_SIZE: u32 = 128;
+pub const TILES_PER_ROW: u32 = ATLAS_SIZE / TILE_SIZE;
+pub const MAX_TILES: u32 = TILES_PER_ROW * TILES_PER_ROW;
+/// A plain white tile for geometry that shows only its vertex color
+pub const BLANK_TILE: u32 = 0;
+
+/// Texels per checker square in generated tiles
+const CHECKER_SIZE: u32 = 4;
+/// Brightness of the dark checker squares
+const CHECKER_SHADE: u8 = 205;
+
+/// Maps a mesh vertex's tile-space UV to atlas coordinates. The UV counts
+/// tiles, so a quad several voxels across repeats its tile once per voxel.
+pub const ATLAS_WGSL: &str = r#"
+const ATLAS_TILES_PER_ROW: u32 = 8u;
+
+fn atlas_uv(uv: vec2<f32>, tile: u32) -> vec2<f32> {
+    let origin = vec2<f32>(f32(tile % ATLAS_TILES_PER_ROW), f32(tile / ATLAS_TILES_PER_ROW));
+    return (origin + fract(uv)) / f32(ATLAS_TILES_PER_ROW);
+}
+"#;
+
+/// The tile drawn on faces of a voxel type. Types past the end of the atlas
+/// fall back to the blank tile.
+pub fn tile_for(id: VoxelId) -> u32 {
+    let tile = id as u32 + 1;
+    if tile < MAX_TILES {
+        tile
+    } else {
+        BLANK_TILE
+    }
+}
+
+pub struct TextureAtlas {
+    /// RGBA8 texels, `ATLAS_SIZE` rows of `ATLAS_SIZE`
+    pixels: Vec<u8>,
+}
+
+impl TextureAtlas {
+    /// An atlas holding only the blank tile
+    pub fn new() -> Self {
+        let mut atlas = Self { pixels: vec![0; (ATLAS_SIZE * ATLAS_SIZE * 4) as usize] };
+        atlas.set_tile(BLANK_TILE, &[255; (TILE_SIZE * TILE_SIZE * 4) as usize]);
+        atlas
+    }
+
+    /// Fills a tile for every registered type, from `directory` where an
+    /// image exists and with a checkerboard otherwise
+    pub fn for_registry(registry: &VoxelRegistry, directory: &Path) -> Self {
+        let mut atlas = Self::new();
+        for id in 0..registry.len() as VoxelId {
+            let tile = tile_for(id);
+            if tile == BLANK_TILE {
+                continue;
+            }
+            let Some(definition) = registry.get(id) else {
+                continue;
+            };
+            let path = directory.join(format!("{}.png", definition.name));
+            let texels = match load_tile(&path) {
+                Ok(texels) => texels,
+                Err(RobinError::Io(_)) => checkerboard_tile(),
+                Err(e) => {
+                    println!("⚠️  Using a plain tile for {}: {}", definition.name, e);
+                    checkerboard_tile()
+                }
+            };
+            atlas.set_tile(tile, &texels);
+        }
+        atlas
+    }
+
+    /// Top-left texel of a tile
+    pub fn tile_origin(tile: u32) -> [u32; 2] {
+        [tile % TILES_PER_ROW * TILE_SIZE, tile / TILES_PER_ROW * TILE_SIZE]
+    }
+
+    /// Copies `TILE_SIZE`² RGBA8 texels, row by row, into a tile
+    pub fn set_tile(&mut self, tile: u32, texels: &[u8]) {
+        let [left, top] = Self::tile_origin(tile);
+        let row_bytes = (TILE_SIZE * 4) as usize;
+        for (row, source) in texels.chunks(row_bytes).take(TILE_SIZE as usize).enumerate() {
+            let start = (((top + row as u32) * ATLAS_SIZE + left) * 4) as usize;
+            self.pixels[start..start + row_bytes].copy_from_slice(source);
+        }
+    }
+
+    /// RGBA of one atlas texel
+    #[cfg(test)]
+    pub fn texel(&self, x: u32, y: u32) -> [u8; 4] {
+        let start = ((y * ATLAS_SIZE + x) * 4) as usize;
+        self.pixels[start..start + 4].try_into().unwrap()
+    }
+
+    /// Uploads the atlas, returning a view and a nearest-neighbour sampler
+    /// for binding alongside it
+    pub fn upload(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> (wgpu::TextureView, wgpu::Sampler) {
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Texture Atlas"),
+                size: wgpu::Extent3d { width: ATLAS_SIZE, height: ATLAS_SIZE, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &self.pixels,
+        );
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Texture Atlas Sampler"),
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        (texture.create_view(&wgpu::TextureViewDescriptor::default()), sampler)
+    }
+}
+
+/// Reads a tile image, scaling it to `TILE_SIZE` if needed
+fn load_tile(path: &Path) -> RobinResult<Vec<u8>> {
+    let bytes = std::fs::read(path)?;
+    let image = image::load_from_memory(&bytes).map_err(|e| RobinError::InvalidData {
+        field: path.display().to_string(),
+        reason: e.to_string(),
+    })?;
+    Ok(image.resize_exact(TILE_SIZE, TILE_SIZE, FilterType::Nearest).to_rgba8().into_raw())
+}
+
+fn checkerboard_tile() -> Vec<u8> {
+    let mut texels = Vec::with_capacity((TILE_SIZE * TILE_SIZE * 4) as usize);
+    for y in 0..TILE_SIZE {
+        for x in 0..TILE_SIZE {
+            let light = (x / CHECKER_SIZE + y / CHECKER_SIZE).is_multiple_of(2);
+            let shade = if light { 255 } else { CHECKER_SHADE };
+            texels.extend([shade, shade, shade, 255]);
+        }
+    }
+    texels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add_face, registry, Vertex};
+
+    #[test]
+    fn test_fallback_tiles_are_checkerboards() {
+        let registry = VoxelRegistry::with_builtin_types();
+        let atlas = TextureAtlas::for_registry(&registry, Path::new("no/such/directory"));
+
+        let [left, top] = TextureAtlas::tile_origin(BLANK_TILE);
+        assert_eq!(atlas.texel(left + 5, top + 9), [255; 4]);
+
+        let [left, top] = TextureAtlas::tile_origin(tile_for(registry::SNOW));
+        assert_eq!([left, top], [112, 0]);
+        assert_eq!(atlas.texel(left, top), [255; 4]);
+        assert_eq!(atlas.texel(left + CHECKER_SIZE, top), [CHECKER_SHADE, CHECKER_SHADE, CHECKER_SHADE, 255]);
+        assert_eq!(atlas.texel(left + CHECKER_SIZE, top + CHECKER_SIZE), [255; 4]);
+
+        // Tiles past the registered types stay empty
+        let [left, top] = TextureAtlas::tile_origin(tile_for(registry::ICE) + 1);
+        assert_eq!(atlas.texel(left, top), [0; 4]);
+        assert_eq!(tile_for(200), BLANK_TILE);
+    }
+
+    /// One tile with a differently colored quadrant in each corner
+    fn quadrant_tile() -> Vec<u8> {
+        const QUADRANTS: [[u8; 4]; 4] = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 0, 255]];
+        let half = TILE_SIZE / 2;
+        (0..TILE_SIZE)
+            .flat_map(|y| (0..TILE_SIZE).map(move |x| (x, y)))
+            .flat_map(|(x, y)| QUADRANTS[((y >= half) as usize) * 2 + (x >= half) as usize])
+            .collect()
+    }
+
+    const TEST_SHADER: &str = r#"
+struct View {
+    right: vec4<f32>,
+    up: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> view: View;
+@group(0) @binding(1)
+var atlas_texture: texture_2d<f32>;
+@group(0) @binding(2)
+var atlas_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) tile: u32,
+}
+
+// Looks at the unit cube head-on along the face normal, with `right` and
+// `up` as the screen axes
+@vertex
+fn vs_main(
+    @location(0) position: vec3<f32>,
+    @location(4) uv: vec2<f32>,
+    @location(5) tile: u32,
+) -> VertexOutput {
+    let centered = position - vec3<f32>(0.5);
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(dot(centered, view.right.xyz) * 2.0, dot(centered, view.up.xyz) * 2.0, 0.5, 1.0);
+    out.uv = uv;
+    out.tile = tile;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(atlas_texture, atlas_sampler, atlas_uv(in.uv, in.tile));
+}
+"#;
+
+    /// Renders each face of a textured unit cube as seen from outside and
+    /// checks that every quadrant of the tile lands where it should, so no
+    /// face is flipped, mirrored or rotated
+    #[test]
+    fn test_cube_faces_show_tile_upright() {
+        let instance = wgpu::Instance::default();
+        let Some(adapter) = pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(&instance, None))
+        else {
+            eprintln!("No GPU adapter available, skipping atlas test");
+            return;
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap();
+
+        let mut atlas = TextureAtlas::new();
+        let tile = 9;
+        atlas.set_tile(tile, &quadrant_tile());
+        let (atlas_view, atlas_sampler) = atlas.upload(&device, &queue);
+
+        // Screen right and up for a viewer outside each face, in `add_face` order
+        let views: [([f32; 3], [f32; 3]); 6] = [
+            ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),  // Front, looking towards -z
+            ([-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]), // Back, looking towards +z
+            ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0]), // Right, looking towards -x
+            ([0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),  // Left, looking towards +x
+            ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0]), // Top, looking down with -z up
+            ([1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),  // Bottom, looking up with +z up
+        ];
+
+        const TARGET_SIZE: u32 = 64;
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d { width: TARGET_SIZE, height: TARGET_SIZE, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let target_view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let view_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 32,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(format!("{}{}", ATLAS_WGSL, TEST_SHADER).into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::TextureFormat::Rgba8Unorm.into())],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: view_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&atlas_view) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&atlas_sampler) },
+            ],
+        });
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (TARGET_SIZE * TARGET_SIZE * 4) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let expected_quadrants = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 0, 255]];
+        for (face, (right, up)) in views.into_iter().enumerate() {
+            let (mut vertices, mut indices) = (Vec::new(), Vec::new());
+            add_face(&mut vertices, &mut indices, [0.0; 3], [1.0; 3], [1.0; 4], tile, face);
+            let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+            let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(&indices),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+            let view = [right[0], right[1], right[2], 0.0, up[0], up[1], up[2], 0.0];
+            queue.write_buffer(&view_buffer, 0, bytemuck::cast_slice(&view));
+
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            {
+                let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: None,
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &target_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::BLACK), store: wgpu::StoreOp::Store },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+                pass.set_pipeline(&pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                pass.draw_indexed(0..indices.len() as u32, 0, 0..1);
+            }
+            encoder.copy_texture_to_buffer(
+                target.as_image_copy(),
+                wgpu::ImageCopyBuffer {
+                    buffer: &readback,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(TARGET_SIZE * 4),
+                        rows_per_image: None,
+                    },
+                },
+                target.size(),
+            );
+            queue.submit(std::iter::once(encoder.finish()));
+
+            readback.slice(..).map_async(wgpu::MapMode::Read, |result| result.unwrap());
+            device.poll(wgpu::Maintain::Wait);
+            {
+                let pixels = readback.slice(..).get_mapped_range();
+                let quarter = TARGET_SIZE / 4;
+                for (quadrant, expected) in expected_quadrants.iter().enumerate() {
+                    let x = quarter + (quadrant as u32 % 2) * 2 * quarter;
+                    let y = quarter + (quadrant as u32 / 2) * 2 * quarter;
+                    let start = ((y * TARGET_SIZE + x) * 4) as usize;
+                    assert_eq!(&pixels[start..start + 4], expected, "face {} quadrant {}", face, quadrant);
+                }
+            }
+            readback.unmap();
+        }
+    }
+
+    #[test]
+    fn test_merged_quad_repeats_tile() {
+        let (mut vertices, mut indices) = (Vec::<Vertex>::new(), Vec::new());
+        add_face(&mut vertices, &mut indices, [2.0, 0.0, 5.0], [3.0, 1.0, 2.0], [1.0; 4], 4, 4);
+        // A 3×2 top face spans three tiles along x and two along z
+        let mut uvs: Vec<[f32; 2]> = vertices.iter().map(|v| v.uv).collect();
+        uvs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(uvs, [[0.0, 0.0], [0.0, 2.0], [3.0, 0.0], [3.0, 2.0]]);
+        assert!(vertices.iter().all(|v| v.tile == 4));
+    }
+}
@@ -0,0 +1,213 @@
+// Biomes
+// Temperature and humidity noise pick a biome for every column, and each
+// biome sets the ground's surface voxels, hilliness and crystal density.
+// Near a boundary the neighbouring biomes are weighted by how much of the
+// surrounding area they cover, so heights and surfaces blend over a
+// transition zone instead of changing abruptly.
+
+use crate::registry::{self, VoxelId};
+use crate::terrain::TerrainGenerator;
+
+/// World units per cycle of the climate noise
+const CLIMATE_SCALE: f32 = 96.0;
+/// Width, in voxels, of the zone over which neighbouring biomes blend
+pub const TRANSITION_WIDTH: usize = 8;
+/// Spacing of the climate samples averaged into a column's biome weights
+const WEIGHT_SAMPLE_STEP: usize = 2;
+
+// Indices of the biomes in `BiomeClassifier::biomes` and in biome weights
+pub const DESERT: usize = 0;
+pub const TUNDRA: usize = 1;
+pub const GRASSLAND: usize = 2;
+pub const FOREST: usize = 3;
+pub const OCEAN: usize = 4;
+pub const BIOME_COUNT: usize = 5;
+
+/// Fraction of the surrounding area each biome covers, indexed by biome
+pub type BiomeWeights = [f32; BIOME_COUNT];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Biome {
+    pub name: String,
+    /// Top voxel of a dry column
+    pub surface_type: VoxelId,
+    /// The few layers under the surface, and the top of submerged columns
+    pub subsurface_type: VoxelId,
+    /// Multiplies the height noise: 0 is flat, 1 is the full amplitude
+    pub height_scale: f32,
+    /// Chance of a crystal on a high-ground column
+    pub crystal_frequency: f32,
+    /// Mean ground level relative to sea level, as a fraction of the world size
+    pub elevation: f32,
+}
+
+impl Biome {
+    fn new(name: &str, surface_type: VoxelId, subsurface_type: VoxelId, height_scale: f32, crystal_frequency: f32, elevation: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            surface_type,
+            subsurface_type,
+            height_scale,
+            crystal_frequency,
+            elevation,
+        }
+    }
+}
+
+/// Maps climate to biomes. Temperature and humidity come from their own
+/// noise fields, seeded from the terrain seed, so they vary independently of
+/// the height field and of each other.
+pub struct BiomeClassifier {
+    biomes: [Biome; BIOME_COUNT],
+    temperature: TerrainGenerator,
+    humidity: TerrainGenerator,
+}
+
+impl BiomeClassifier {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            biomes: [
+                Biome::new("desert", registry::SAND, registry::SAND, 0.3, 0.005, 0.08),
+                Biome::new("tundra", registry::SNOW, registry::DIRT, 0.9, 0.04, 0.08),
+                Biome::new("grassland", registry::GRASS, registry::DIRT, 0.5, 0.02, 0.04),
+                Biome::new("forest", registry::GRASS, registry::DIRT, 0.8, 0.03, 0.06),
+                Biome::new("ocean", registry::SAND, registry::SAND, 0.3, 0.0, -0.15),
+            ],
+            temperature: TerrainGenerator::new(seed ^ 0x7E3A_0001, 3, 0.5, 2.0),
+            humidity: TerrainGenerator::new(seed ^ 0x4B1D_0002, 3, 0.5, 2.0),
+        }
+    }
+
+    pub fn biome(&self, index: usize) -> &Biome {
+        &self.biomes[index]
+    }
+
+    /// Biome for a temperature and humidity, each roughly in [-1, 1]
+    #[cfg(test)]
+    pub fn classify(&self, temperature: f32, humidity: f32) -> Biome {
+        self.biomes[biome_index(temperature, humidity)].clone()
+    }
+
+    /// (temperature, humidity) at a world column
+    pub fn climate_at(&self, x: f32, z: f32) -> (f32, f32) {
+        (
+            self.temperature.height_at(x / CLIMATE_SCALE, z / CLIMATE_SCALE),
+            self.humidity.height_at(x / CLIMATE_SCALE, z / CLIMATE_SCALE),
+        )
+    }
+
+    /// Share of each biome among climate samples across a `TRANSITION_WIDTH`
+    /// square centered on the column. Deep inside a biome its weight is 1.
+    pub fn weights_at(&self, x: usize, z: usize) -> BiomeWeights {
+        let radius = (TRANSITION_WIDTH / 2) as i64;
+        let step = WEIGHT_SAMPLE_STEP as i64;
+        let mut weights = [0.0; BIOME_COUNT];
+        let mut samples = 0;
+        for dx in (-radius..=radius).step_by(step as usize) {
+            for dz in (-radius..=radius).step_by(step as usize) {
+                let (temperature, humidity) = self.climate_at((x as i64 + dx) as f32, (z as i64 + dz) as f32);
+                weights[biome_index(temperature, humidity)] += 1.0;
+                samples += 1;
+            }
+        }
+        weights.map(|weight| weight / samples as f32)
+    }
+
+    /// The biome a column belongs to: the one with the largest weight
+    pub fn dominant(weights: &BiomeWeights) -> usize {
+        (0..BIOME_COUNT)
+            .max_by(|&a, &b| weights[a].total_cmp(&weights[b]).then(b.cmp(&a)))
+            .unwrap()
+    }
+
+    /// Picks the biome whose voxels cover a column, with each biome's chance
+    /// equal to its weight, so boundaries dither across the transition zone
+    pub fn surface_biome(&self, weights: &BiomeWeights, x: usize, z: usize) -> &Biome {
+        let roll = self.temperature.feature_at(x as i32, z as i32);
+        let mut total = 0.0;
+        for (index, weight) in weights.iter().enumerate() {
+            total += weight;
+            if roll < total {
+                return &self.biomes[index];
+            }
+        }
+        &self.biomes[Self::dominant(weights)]
+    }
+}
+
+fn biome_index(temperature: f32, humidity: f32) -> usize {
+    if humidity > 0.18 {
+        OCEAN
+    } else if temperature < -0.15 {
+        TUNDRA
+    } else if temperature > 0.1 && humidity < -0.05 {
+        DESERT
+    } else if humidity > 0.05 {
+        FOREST
+    } else {
+        GRASSLAND
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VoxelWorld;
+
+    #[test]
+    fn test_classify_covers_every_biome() {
+        let classifier = BiomeClassifier::new(1);
+        assert_eq!(classifier.classify(0.0, 0.6).name, "ocean");
+        assert_eq!(classifier.classify(-0.5, 0.0).name, "tundra");
+        assert_eq!(classifier.classify(0.5, -0.5).name, "desert");
+        assert_eq!(classifier.classify(0.0, 0.15).name, "forest");
+        assert_eq!(classifier.classify(0.0, 0.0).name, "grassland");
+        assert_eq!(classifier.classify(0.5, -0.5).surface_type, registry::SAND);
+    }
+
+    #[test]
+    fn test_weights_blend_across_boundaries() {
+        let classifier = BiomeClassifier::new(2024);
+        let (mut pure, mut blended) = (0, 0);
+        for x in 0..64 {
+            for z in 0..64 {
+                let weights = classifier.weights_at(x * 4, z * 4);
+                assert!((weights.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+                if weights.contains(&1.0) {
+                    pure += 1;
+                } else {
+                    blended += 1;
+                }
+            }
+        }
+        assert!(pure > 0 && blended > 0, "{} pure and {} blended columns", pure, blended);
+    }
+
+    #[test]
+    fn test_oceans_are_flooded_and_deserts_dry() {
+        let (size, sea_level) = (128, 32);
+        let generator = TerrainGenerator::new(1, 4, 0.5, 2.0);
+        let world = VoxelWorld::generate(size, &generator, sea_level);
+        let classifier = BiomeClassifier::new(generator.seed());
+
+        let (mut oceans, mut deserts) = (0, 0);
+        for x in 0..size {
+            for z in 0..size {
+                let at_sea_level = world.voxels[x][sea_level][z];
+                match BiomeClassifier::dominant(&classifier.weights_at(x, z)) {
+                    OCEAN => {
+                        assert_eq!(at_sea_level, Some(registry::WATER), "dry ocean at ({}, {})", x, z);
+                        oceans += 1;
+                    }
+                    DESERT => {
+                        assert_ne!(at_sea_level, Some(registry::WATER), "wet desert at ({}, {})", x, z);
+                        assert!((0..size).all(|y| world.voxels[x][y][z] != Some(registry::WATER)));
+                        deserts += 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        assert!(oceans > 0 && deserts > 0, "{} ocean and {} desert columns", oceans, deserts);
+    }
+}
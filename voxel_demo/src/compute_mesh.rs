@@ -0,0 +1,520 @@
+// GPU mesh generation
+// A compute shader counterpart of `VoxelWorld::generate_mesh_naive`: one
+// thread per voxel emits a quad for each exposed face, so meshing a large
+// world doesn't stall the render thread.
+
+use crate::{atlas, registry, VoxelWorld};
+use wgpu::util::DeviceExt;
+
+/// Floats per emitted vertex; matches the layout of `Vertex`
+const VERTEX_FLOATS: u64 = 14;
+/// Two triangles per face, without an index buffer
+const VERTICES_PER_FACE: u64 = 6;
+/// Edge length of the cubic compute workgroup
+const WORKGROUP_SIZE: u32 = 4;
+
+const FLAG_TRANSPARENT: u32 = 1;
+/// Types such as crystals that are rendered separately rather than meshed
+const FLAG_UNMESHED: u32 = 2;
+
+const MESH_SHADER: &str = r#"
+struct Params {
+    size: u32,
+    // 0 only counts exposed faces; 1 also writes their vertices
+    emit: u32,
+}
+
+struct VoxelType {
+    color: vec4<f32>,
+    flags: u32,
+    tile: u32,
+}
+
+const FLAG_TRANSPARENT: u32 = 1u;
+const FLAG_UNMESHED: u32 = 2u;
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+// Voxel id + 1 per cell, 0 for empty, indexed [x][y][z]
+@group(0) @binding(1)
+var<storage, read> voxels: array<u32>;
+@group(0) @binding(2)
+var<storage, read> types: array<VoxelType>;
+@group(0) @binding(3)
+var<storage, read_write> face_count: atomic<u32>;
+// Interleaved position, normal, color, ao, uv and tile; 14 words per vertex
+@group(0) @binding(4)
+var<storage, read_write> vertices: array<f32>;
+
+// Cells outside the world read as empty
+fn voxel_at(p: vec3<i32>) -> u32 {
+    let size = i32(params.size);
+    if any(p < vec3<i32>(0)) || any(p >= vec3<i32>(size)) {
+        return 0u;
+    }
+    return voxels[(p.x * size + p.y) * size + p.z];
+}
+
+fn is_opaque(p: vec3<i32>) -> bool {
+    let cell = voxel_at(p);
+    return cell != 0u && (types[cell - 1u].flags & (FLAG_TRANSPARENT | FLAG_UNMESHED)) == 0u;
+}
+
+fn is_exposed(p: vec3<i32>, normal: vec3<i32>, cell: u32) -> bool {
+    let neighbour = voxel_at(p + normal);
+    if neighbour == 0u {
+        return true;
+    }
+    let flags = types[neighbour - 1u].flags;
+    if (flags & FLAG_UNMESHED) != 0u {
+        return true;
+    }
+    return (flags & FLAG_TRANSPARENT) != 0u && neighbour != cell;
+}
+
+// Tile-space uv of a unit face's corner, as in `face_uv`
+fn face_uv(face: u32, offset: vec3<f32>) -> vec2<f32> {
+    switch face {
+        case 0u: { return vec2<f32>(offset.x, 1.0 - offset.y); }
+        case 1u: { return vec2<f32>(1.0 - offset.x, 1.0 - offset.y); }
+        case 2u: { return vec2<f32>(1.0 - offset.z, 1.0 - offset.y); }
+        case 3u: { return vec2<f32>(offset.z, 1.0 - offset.y); }
+        case 4u: { return vec2<f32>(offset.x, offset.z); }
+        default: { return vec2<f32>(offset.x, 1.0 - offset.z); }
+    }
+}
+
+fn write_vertex(index: u32, position: vec3<f32>, normal: vec3<f32>, color: vec4<f32>, ao: f32, uv: vec2<f32>, tile: u32) {
+    let base = index * 14u;
+    vertices[base] = position.x;
+    vertices[base + 1u] = position.y;
+    vertices[base + 2u] = position.z;
+    vertices[base + 3u] = normal.x;
+    vertices[base + 4u] = normal.y;
+    vertices[base + 5u] = normal.z;
+    vertices[base + 6u] = color.r;
+    vertices[base + 7u] = color.g;
+    vertices[base + 8u] = color.b;
+    vertices[base + 9u] = color.a;
+    vertices[base + 10u] = ao;
+    vertices[base + 11u] = uv.x;
+    vertices[base + 12u] = uv.y;
+    vertices[base + 13u] = bitcast<f32>(tile);
+}
+
+@compute @workgroup_size(4, 4, 4)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id >= vec3<u32>(params.size)) {
+        return;
+    }
+    let p = vec3<i32>(id);
+    let cell = voxel_at(p);
+    if cell == 0u || (types[cell - 1u].flags & FLAG_UNMESHED) != 0u {
+        return;
+    }
+    let color = types[cell - 1u].color;
+    let tile = types[cell - 1u].tile;
+
+    // Face order, normals and corner winding match `add_face`
+    var normals = array<vec3<i32>, 6>(
+        vec3<i32>(0, 0, 1), vec3<i32>(0, 0, -1),
+        vec3<i32>(1, 0, 0), vec3<i32>(-1, 0, 0),
+        vec3<i32>(0, 1, 0), vec3<i32>(0, -1, 0),
+    );
+    // The two axes spanning each face plane, as in `face_axes`
+    var u_axes = array<vec3<i32>, 6>(
+        vec3<i32>(1, 0, 0), vec3<i32>(1, 0, 0),
+        vec3<i32>(0, 0, 1), vec3<i32>(0, 0, 1),
+        vec3<i32>(1, 0, 0), vec3<i32>(1, 0, 0),
+    );
+    var v_axes = array<vec3<i32>, 6>(
+        vec3<i32>(0, 1, 0), vec3<i32>(0, 1, 0),
+        vec3<i32>(0, 1, 0), vec3<i32>(0, 1, 0),
+        vec3<i32>(0, 0, 1), vec3<i32>(0, 0, 1),
+    );
+    var corners = array<vec3<i32>, 24>(
+        vec3<i32>(0, 0, 1), vec3<i32>(1, 0, 1), vec3<i32>(1, 1, 1), vec3<i32>(0, 1, 1),
+        vec3<i32>(0, 0, 0), vec3<i32>(0, 1, 0), vec3<i32>(1, 1, 0), vec3<i32>(1, 0, 0),
+        vec3<i32>(1, 0, 0), vec3<i32>(1, 1, 0), vec3<i32>(1, 1, 1), vec3<i32>(1, 0, 1),
+        vec3<i32>(0, 0, 0), vec3<i32>(0, 0, 1), vec3<i32>(0, 1, 1), vec3<i32>(0, 1, 0),
+        vec3<i32>(0, 1, 0), vec3<i32>(0, 1, 1), vec3<i32>(1, 1, 1), vec3<i32>(1, 1, 0),
+        vec3<i32>(0, 0, 0), vec3<i32>(1, 0, 0), vec3<i32>(1, 0, 1), vec3<i32>(0, 0, 1),
+    );
+    var triangle_corners = array<u32, 6>(0u, 1u, 2u, 0u, 2u, 3u);
+
+    for (var face = 0u; face < 6u; face++) {
+        let normal = normals[face];
+        if !is_exposed(p, normal, cell) {
+            continue;
+        }
+        let slot = atomicAdd(&face_count, 1u);
+        if params.emit == 0u {
+            continue;
+        }
+
+        // Occlusion per corner from the layer in front of the face, as in `face_ao`
+        let front = p + normal;
+        let u = u_axes[face];
+        let v = v_axes[face];
+        var ao: array<f32, 4>;
+        for (var corner = 0u; corner < 4u; corner++) {
+            let offset = corners[face * 4u + corner];
+            let su = select(-1, 1, dot(offset, u) > 0);
+            let sv = select(-1, 1, dot(offset, v) > 0);
+            let side1 = is_opaque(front + u * su);
+            let side2 = is_opaque(front + v * sv);
+            var count = u32(side1) + u32(side2) + u32(is_opaque(front + u * su + v * sv));
+            if side1 && side2 {
+                count = 3u;
+            }
+            ao[corner] = f32(count) / 3.0;
+        }
+
+        for (var k = 0u; k < 6u; k++) {
+            let corner = triangle_corners[k];
+            let offset = vec3<f32>(corners[face * 4u + corner]);
+            let position = vec3<f32>(p) + offset;
+            write_vertex(slot * 6u + k, position, vec3<f32>(normal), color, ao[corner], face_uv(face, offset), tile);
+        }
+    }
+}
+"#;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct MeshParams {
+    size: u32,
+    emit: u32,
+    _padding: [u32; 2],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GpuVoxelType {
+    pub color: [f32; 4],
+    pub flags: u32,
+    pub tile: u32,
+    _padding: [u32; 2],
+}
+
+/// A world packed into the flat layout the mesh shader reads
+pub struct VoxelWorldGPU {
+    pub size: u32,
+    /// Voxel id + 1 per cell, 0 for empty, indexed [x][y][z]
+    pub voxels: Vec<u32>,
+    /// Indexed by voxel id
+    pub types: Vec<GpuVoxelType>,
+}
+
+impl VoxelWorldGPU {
+    pub fn from_world(world: &VoxelWorld) -> Self {
+        let voxels = world
+            .voxels
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|voxel| voxel.map_or(0, |id| id as u32 + 1))
+            .collect();
+
+        let types = (0..world.registry.len() as registry::VoxelId)
+            .map(|id| {
+                let mut flags = 0;
+                if world.registry.is_transparent(id) {
+                    flags |= FLAG_TRANSPARENT;
+                }
+                if id == registry::CRYSTAL {
+                    flags |= FLAG_UNMESHED;
+                }
+                GpuVoxelType {
+                    color: world.registry.rgba(id),
+                    flags,
+                    tile: atlas::tile_for(id),
+                    _padding: [0; 2],
+                }
+            })
+            .collect();
+
+        Self { size: world.size as u32, voxels, types }
+    }
+}
+
+/// Vertices of a mesh built on the GPU, as a non-indexed triangle list
+pub struct GpuMesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub vertex_count: u32,
+}
+
+pub struct ComputeMeshGenerator {
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl ComputeMeshGenerator {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Mesh Compute Shader"),
+            source: wgpu::ShaderSource::Wgsl(MESH_SHADER.into()),
+        });
+
+        let storage = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Mesh Compute Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage(1, true),
+                storage(2, true),
+                storage(3, false),
+                storage(4, false),
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mesh Compute Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Mesh Compute Pipeline"),
+            layout: Some(&layout),
+            module: &shader,
+            entry_point: "cs_main",
+            compilation_options: Default::default(),
+        });
+
+        Self { pipeline, bind_group_layout }
+    }
+
+    /// Meshes the world on the GPU and blocks until the vertex buffer is
+    /// filled. A first pass counts the exposed faces so the vertex buffer can
+    /// be sized exactly; the second writes the vertices.
+    pub fn dispatch(&self, world: &VoxelWorldGPU, device: &wgpu::Device, queue: &wgpu::Queue) -> GpuMesh {
+        let voxel_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Mesh Compute Voxels"),
+            contents: bytemuck::cast_slice(&world.voxels),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let type_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Mesh Compute Voxel Types"),
+            contents: bytemuck::cast_slice(&world.types),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let counter_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Mesh Compute Face Counter"),
+            size: 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Counting pass; the vertex binding only needs a placeholder
+        let placeholder = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Mesh Compute Placeholder Vertices"),
+            size: VERTEX_FLOATS * 4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        self.run_pass(device, queue, world.size, false, [&voxel_buffer, &type_buffer, &counter_buffer, &placeholder]);
+        let face_count = read_counter(device, queue, &counter_buffer);
+
+        let vertex_count = face_count as u64 * VERTICES_PER_FACE;
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Mesh Compute Vertices"),
+            size: (vertex_count * VERTEX_FLOATS * 4).max(VERTEX_FLOATS * 4),
+            // COPY_SRC so the mesh can be read back and compared with the CPU path
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let submission =
+            self.run_pass(device, queue, world.size, true, [&voxel_buffer, &type_buffer, &counter_buffer, &vertex_buffer]);
+        device.poll(wgpu::Maintain::WaitForSubmissionIndex(submission));
+
+        GpuMesh { vertex_buffer, vertex_count: vertex_count as u32 }
+    }
+
+    /// Resets the face counter and runs one dispatch over the whole world
+    fn run_pass(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        size: u32,
+        emit: bool,
+        [voxels, types, counter, vertices]: [&wgpu::Buffer; 4],
+    ) -> wgpu::SubmissionIndex {
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Mesh Compute Params"),
+            contents: bytemuck::cast_slice(&[MeshParams { size, emit: emit as u32, _padding: [0; 2] }]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Mesh Compute Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: voxels.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: types.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: counter.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: vertices.as_entire_binding() },
+            ],
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Mesh Compute Encoder"),
+        });
+        encoder.clear_buffer(counter, 0, None);
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Mesh Compute Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            let groups = size.div_ceil(WORKGROUP_SIZE);
+            pass.dispatch_workgroups(groups, groups, groups);
+        }
+        queue.submit(std::iter::once(encoder.finish()))
+    }
+}
+
+/// Copies the face counter back to the CPU, waiting for the GPU to finish
+fn read_counter(device: &wgpu::Device, queue: &wgpu::Queue, counter: &wgpu::Buffer) -> u32 {
+    let readback = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Mesh Compute Counter Readback"),
+        size: 4,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Mesh Compute Counter Copy"),
+    });
+    encoder.copy_buffer_to_buffer(counter, 0, &readback, 0, 4);
+    queue.submit(std::iter::once(encoder.finish()));
+
+    readback.slice(..).map_async(wgpu::MapMode::Read, |_| {});
+    device.poll(wgpu::Maintain::Wait);
+    let count = bytemuck::cast_slice::<u8, u32>(&readback.slice(..).get_mapped_range())[0];
+    readback.unmap();
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::TerrainGenerator;
+    use crate::Vertex;
+    use std::time::Instant;
+
+    fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(&instance, None))?;
+        Some(pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap())
+    }
+
+    fn read_vertices(device: &wgpu::Device, queue: &wgpu::Queue, mesh: &GpuMesh) -> Vec<Vertex> {
+        let size = mesh.vertex_count as u64 * std::mem::size_of::<Vertex>() as u64;
+        if size == 0 {
+            return Vec::new();
+        }
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(&mesh.vertex_buffer, 0, &readback, 0, size);
+        queue.submit(std::iter::once(encoder.finish()));
+        readback.slice(..).map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        device.poll(wgpu::Maintain::Wait);
+        let vertices = bytemuck::cast_slice(&readback.slice(..).get_mapped_range()).to_vec();
+        vertices
+    }
+
+    /// Triangles as comparable keys, independent of the order faces were emitted in
+    fn sorted_triangles(vertices: &[Vertex]) -> Vec<Vec<u32>> {
+        let mut triangles: Vec<Vec<u32>> = vertices
+            .chunks(3)
+            .map(|triangle| bytemuck::cast_slice::<Vertex, u32>(triangle).to_vec())
+            .collect();
+        triangles.sort();
+        triangles
+    }
+
+    #[test]
+    fn test_compute_mesh_matches_cpu_mesh() {
+        let Some((device, queue)) = device() else {
+            eprintln!("No GPU adapter available, skipping compute mesh test");
+            return;
+        };
+
+        // Size 10 isn't a multiple of the workgroup size, so edge threads are masked off
+        let generator = TerrainGenerator::new(7, 3, 0.5, 2.0);
+        let mut world = VoxelWorld::generate(10, &generator, 3);
+        world.set_voxel(5, 9, 5, Some(registry::CRYSTAL));
+
+        let mesh = ComputeMeshGenerator::new(&device).dispatch(&VoxelWorldGPU::from_world(&world), &device, &queue);
+        let gpu = read_vertices(&device, &queue, &mesh);
+
+        let (cpu_vertices, cpu_indices) = world.generate_mesh_naive();
+        let cpu: Vec<Vertex> = cpu_indices.iter().map(|&i| cpu_vertices[i as usize]).collect();
+        assert_eq!(mesh.vertex_count as usize, cpu.len());
+        assert_eq!(sorted_triangles(&gpu), sorted_triangles(&cpu));
+    }
+
+    #[test]
+    fn test_compute_mesh_of_empty_world() {
+        let Some((device, queue)) = device() else {
+            eprintln!("No GPU adapter available, skipping compute mesh test");
+            return;
+        };
+        let world = VoxelWorldGPU::from_world(&VoxelWorld::empty(4));
+        let mesh = ComputeMeshGenerator::new(&device).dispatch(&world, &device, &queue);
+        assert_eq!(mesh.vertex_count, 0);
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture` to compare timings
+    #[test]
+    #[ignore]
+    fn bench_compute_mesh_against_cpu_64() {
+        let Some((device, queue)) = device() else {
+            eprintln!("No GPU adapter available, skipping benchmark");
+            return;
+        };
+        let generator = TerrainGenerator::new(crate::DEFAULT_TERRAIN_SEED, 4, 0.5, 2.0);
+        let world = VoxelWorld::generate(64, &generator, 16);
+        let compute = ComputeMeshGenerator::new(&device);
+
+        let start = Instant::now();
+        let (cpu_vertices, _) = world.generate_mesh_naive();
+        let cpu_time = start.elapsed();
+
+        let start = Instant::now();
+        let gpu_world = VoxelWorldGPU::from_world(&world);
+        let mesh = compute.dispatch(&gpu_world, &device, &queue);
+        let gpu_time = start.elapsed();
+
+        println!(
+            "64³ world: CPU {:?} for {} quads, GPU {:?} for {} quads",
+            cpu_time,
+            cpu_vertices.len() / 4,
+            gpu_time,
+            mesh.vertex_count / 6
+        );
+    }
+}
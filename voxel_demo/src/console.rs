@@ -0,0 +1,854 @@
+// Developer console
+// A command line for inspecting and editing the live world. Commands are
+// looked up by name in a table, so new ones can be registered alongside the
+// built-ins. The console is drawn over the bottom of the screen with a small
+// bitmap font baked into a texture at startup.
+
+use crate::error::RobinResult;
+use crate::registry::VoxelId;
+use crate::render_graph::RenderGraph;
+use crate::{save_slots, VoxelWorld};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use wgpu::util::DeviceExt;
+
+pub type CommandHandler = Box<dyn Fn(&[&str], &mut VoxelWorld) -> String>;
+
+/// Render graph pass drawing the console
+pub const PASS: &str = "console";
+
+/// Output lines kept for scrolling back
+const MAX_OUTPUT_LINES: usize = 200;
+/// Output lines shown above the input line
+const VISIBLE_LINES: usize = 10;
+
+pub struct DevConsole {
+    commands: HashMap<String, CommandHandler>,
+    /// Every command entered, oldest first
+    history: Vec<String>,
+    /// Position while stepping through `history`; `history.len()` is the new line
+    history_cursor: usize,
+    input: String,
+    output: Vec<String>,
+    /// How many lines the view is scrolled up from the newest output
+    scroll: usize,
+    seed: Rc<Cell<Option<u64>>>,
+    teleport: Rc<Cell<Option<[f32; 3]>>>,
+    /// Shown on its own line at the top of the panel, e.g. frame times
+    status: String,
+    pub visible: bool,
+}
+
+impl DevConsole {
+    /// `seed` is the terrain seed of the current world, if it's known
+    pub fn new(seed: Option<u64>) -> Self {
+        let mut console = Self {
+            commands: HashMap::new(),
+            history: Vec::new(),
+            history_cursor: 0,
+            input: String::new(),
+            output: Vec::new(),
+            scroll: 0,
+            seed: Rc::new(Cell::new(seed)),
+            teleport: Rc::new(Cell::new(None)),
+            status: String::new(),
+            visible: false,
+        };
+        console.register_builtin_commands();
+        console
+    }
+
+    pub fn register_command(&mut self, name: &str, handler: CommandHandler) {
+        self.commands.insert(name.to_string(), handler);
+    }
+
+    fn register_builtin_commands(&mut self) {
+        self.register_command("fill", Box::new(fill_command));
+        self.register_command("info", Box::new(info_command));
+        self.register_command("stats", Box::new(stats_command));
+
+        let seed = self.seed.clone();
+        self.register_command(
+            "seed",
+            Box::new(move |_, _| match seed.get() {
+                Some(seed) => format!("Seed: {}", seed),
+                None => "Seed: unknown (world was loaded from disk)".to_string(),
+            }),
+        );
+
+        let teleport = self.teleport.clone();
+        self.register_command(
+            "tp",
+            Box::new(move |args, _| match parse_args::<f32, 3>(args) {
+                Some(position) => {
+                    teleport.set(Some(position));
+                    format!("Teleported to ({}, {}, {})", position[0], position[1], position[2])
+                }
+                None => "usage: tp X Y Z".to_string(),
+            }),
+        );
+    }
+
+    /// Replaces the seed reported by `seed`, e.g. after loading another world
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed.set(seed);
+    }
+
+    /// Position requested by the last `tp`, if the caller hasn't moved there yet
+    pub fn take_teleport(&mut self) -> Option<[f32; 3]> {
+        self.teleport.take()
+    }
+
+    /// Runs a command line, echoing it and its result to the output
+    pub fn execute(&mut self, line: &str, world: &mut VoxelWorld) -> String {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&name, args)) = words.split_first() else {
+            return String::new();
+        };
+        let result = match self.commands.get(name) {
+            Some(handler) => handler(args, world),
+            None => {
+                let mut names: Vec<&str> = self.commands.keys().map(String::as_str).collect();
+                names.sort_unstable();
+                format!("Unknown command '{}'. Commands: {}", name, names.join(", "))
+            }
+        };
+
+        self.push_output(&format!("> {}", line.trim()));
+        self.push_output(&result);
+        self.history.push(line.trim().to_string());
+        self.history_cursor = self.history.len();
+        result
+    }
+
+    /// Runs the input line. Returns whether a command was run.
+    pub fn submit(&mut self, world: &mut VoxelWorld) -> bool {
+        let line = std::mem::take(&mut self.input);
+        if line.trim().is_empty() {
+            return false;
+        }
+        self.execute(&line, world);
+        true
+    }
+
+    fn push_output(&mut self, text: &str) {
+        self.output.extend(text.lines().map(str::to_string));
+        if self.output.len() > MAX_OUTPUT_LINES {
+            self.output.drain(..self.output.len() - MAX_OUTPUT_LINES);
+        }
+        self.scroll = 0;
+    }
+
+    pub fn type_char(&mut self, c: char) {
+        if !c.is_control() {
+            self.input.push(c);
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        self.input.pop();
+    }
+
+    /// Steps back through earlier commands into the input line
+    pub fn history_previous(&mut self) {
+        if self.history_cursor > 0 {
+            self.history_cursor -= 1;
+            self.input = self.history[self.history_cursor].clone();
+        }
+    }
+
+    /// Steps forward through earlier commands, ending on an empty line
+    pub fn history_next(&mut self) {
+        if self.history_cursor < self.history.len() {
+            self.history_cursor += 1;
+            self.input = self.history.get(self.history_cursor).cloned().unwrap_or_default();
+        }
+    }
+
+    /// Scrolls the output by `lines`, positive towards older output
+    pub fn scroll(&mut self, lines: isize) {
+        let max = self.output.len().saturating_sub(VISIBLE_LINES);
+        self.scroll = self.scroll.saturating_add_signed(lines).min(max);
+    }
+
+    /// Output lines in view, oldest first
+    pub fn visible_output(&self) -> &[String] {
+        let end = self.output.len() - self.scroll;
+        &self.output[end.saturating_sub(VISIBLE_LINES)..end]
+    }
+
+    pub fn status(&self) -> &str {
+        &self.status
+    }
+
+    /// Replaces the status line; an empty one hides it
+    pub fn set_status(&mut self, status: &str) {
+        self.status.clear();
+        self.status.push_str(status);
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+}
+
+fn parse_args<T: std::str::FromStr + Copy + Default, const N: usize>(args: &[&str]) -> Option<[T; N]> {
+    if args.len() != N {
+        return None;
+    }
+    let mut values = [T::default(); N];
+    for (value, arg) in values.iter_mut().zip(args) {
+        *value = arg.parse().ok()?;
+    }
+    Some(values)
+}
+
+/// `fill X1 Y1 Z1 X2 Y2 Z2 TYPE`: sets every voxel in the box, corners
+/// included, to a type name or `air`
+fn fill_command(args: &[&str], world: &mut VoxelWorld) -> String {
+    const USAGE: &str = "usage: fill X1 Y1 Z1 X2 Y2 Z2 TYPE";
+    let (Some((&type_name, coords)), true) = (args.split_last(), args.len() == 7) else {
+        return USAGE.to_string();
+    };
+    let Some([x1, y1, z1, x2, y2, z2]) = parse_args::<usize, 6>(coords) else {
+        return USAGE.to_string();
+    };
+    let voxel = if type_name == "air" {
+        None
+    } else {
+        match world.registry.find(type_name) {
+            Some(id) => Some(id),
+            None => return format!("Unknown voxel type '{}'", type_name),
+        }
+    };
+
+    let last = world.size - 1;
+    let (x_range, y_range, z_range) = (
+        x1.min(x2)..=x1.max(x2).min(last),
+        y1.min(y2)..=y1.max(y2).min(last),
+        z1.min(z2)..=z1.max(z2).min(last),
+    );
+    let mut count = 0;
+    for x in x_range {
+        for y in y_range.clone() {
+            for z in z_range.clone() {
+                world.set_voxel(x, y, z, voxel);
+                count += 1;
+            }
+        }
+    }
+    format!("Filled {} voxels with {}", count, type_name)
+}
+
+/// `info X Y Z`: describes one voxel
+fn info_command(args: &[&str], world: &mut VoxelWorld) -> String {
+    let Some([x, y, z]) = parse_args::<usize, 3>(args) else {
+        return "usage: info X Y Z".to_string();
+    };
+    if x >= world.size || y >= world.size || z >= world.size {
+        return format!("({}, {}, {}) is outside the {3}x{3}x{3} world", x, y, z, world.size);
+    }
+    match world.get(x, y, z) {
+        None => format!("({}, {}, {}): air", x, y, z),
+        Some(id) => match world.registry.get(id) {
+            Some(definition) => format!(
+                "({}, {}, {}): {} (id {}), {}, hardness {}",
+                x,
+                y,
+                z,
+                definition.name,
+                id,
+                if definition.solid { "solid" } else { "not solid" },
+                definition.hardness
+            ),
+            None => format!("({}, {}, {}): unregistered id {}", x, y, z, id),
+        },
+    }
+}
+
+/// `stats`: world size and voxel counts by type
+fn stats_command(_args: &[&str], world: &mut VoxelWorld) -> String {
+    let mut counts = vec![0usize; world.registry.len()];
+    let mut unknown = 0;
+    for id in world.voxels.iter().flatten().flatten().flatten() {
+        match counts.get_mut(*id as usize) {
+            Some(count) => *count += 1,
+            None => unknown += 1,
+        }
+    }
+
+    let total = counts.iter().sum::<usize>() + unknown;
+    let mut lines = vec![format!(
+        "{0}x{0}x{0} world, {1} voxels, chunks: {2}",
+        world.size,
+        total,
+        world.chunks.len()
+    )];
+    for (id, count) in counts.iter().enumerate().filter(|(_, &count)| count > 0) {
+        let name = world.registry.get(id as VoxelId).map_or("?", |definition| definition.name.as_str());
+        lines.push(format!("  {}: {}", name, count));
+    }
+    if unknown > 0 {
+        lines.push(format!("  unregistered: {}", unknown));
+    }
+    lines.join("\n")
+}
+
+/// 3×5 glyphs, `#` for a lit pixel. Lowercase letters are drawn as
+/// uppercase and characters without a glyph as `?`.
+const FONT_GLYPHS: &[(char, [&str; 5])] = &[
+    (' ', ["...", "...", "...", "...", "..."]),
+    ('!', [".#.", ".#.", ".#.", "...", ".#."]),
+    ('"', ["#.#", "#.#", "...", "...", "..."]),
+    ('#', ["#.#", "###", "#.#", "###", "#.#"]),
+    ('%', ["#.#", "..#", ".#.", "#..", "#.#"]),
+    ('\'', [".#.", ".#.", "...", "...", "..."]),
+    ('(', [".#.", "#..", "#..", "#..", ".#."]),
+    (')', [".#.", "..#", "..#", "..#", ".#."]),
+    ('*', ["#.#", ".#.", "#.#", "...", "..."]),
+    ('+', ["...", ".#.", "###", ".#.", "..."]),
+    (',', ["...", "...", "...", ".#.", "#.."]),
+    ('-', ["...", "...", "###", "...", "..."]),
+    ('.', ["...", "...", "...", "...", ".#."]),
+    ('/', ["..#", "..#", ".#.", "#..", "#.."]),
+    ('0', ["###", "#.#", "#.#", "#.#", "###"]),
+    ('1', [".#.", "##.", ".#.", ".#.", "###"]),
+    ('2', ["##.", "..#", ".#.", "#..", "###"]),
+    ('3', ["##.", "..#", ".#.", "..#", "##."]),
+    ('4', ["#.#", "#.#", "###", "..#", "..#"]),
+    ('5', ["###", "#..", "##.", "..#", "##."]),
+    ('6', [".##", "#..", "###", "#.#", "###"]),
+    ('7', ["###", "..#", ".#.", ".#.", ".#."]),
+    ('8', ["###", "#.#", "###", "#.#", "###"]),
+    ('9', ["###", "#.#", "###", "..#", "##."]),
+    (':', ["...", ".#.", "...", ".#.", "..."]),
+    ('<', ["..#", ".#.", "#..", ".#.", "..#"]),
+    ('=', ["...", "###", "...", "###", "..."]),
+    ('>', ["#..", ".#.", "..#", ".#.", "#.."]),
+    ('?', ["##.", "..#", ".#.", "...", ".#."]),
+    ('A', [".#.", "#.#", "###", "#.#", "#.#"]),
+    ('B', ["##.", "#.#", "##.", "#.#", "##."]),
+    ('C', [".##", "#..", "#..", "#..", ".##"]),
+    ('D', ["##.", "#.#", "#.#", "#.#", "##."]),
+    ('E', ["###", "#..", "##.", "#..", "###"]),
+    ('F', ["###", "#..", "##.", "#..", "#.."]),
+    ('G', [".##", "#..", "#.#", "#.#", ".##"]),
+    ('H', ["#.#", "#.#", "###", "#.#", "#.#"]),
+    ('I', ["###", ".#.", ".#.", ".#.", "###"]),
+    ('J', ["..#", "..#", "..#", "#.#", ".#."]),
+    ('K', ["#.#", "#.#", "##.", "#.#", "#.#"]),
+    ('L', ["#..", "#..", "#..", "#..", "###"]),
+    ('M', ["#.#", "###", "###", "#.#", "#.#"]),
+    ('N', ["##.", "#.#", "#.#", "#.#", "#.#"]),
+    ('O', [".#.", "#.#", "#.#", "#.#", ".#."]),
+    ('P', ["##.", "#.#", "##.", "#..", "#.."]),
+    ('Q', [".#.", "#.#", "#.#", "##.", ".##"]),
+    ('R', ["##.", "#.#", "##.", "#.#", "#.#"]),
+    ('S', [".##", "#..", ".#.", "..#", "##."]),
+    ('T', ["###", ".#.", ".#.", ".#.", ".#."]),
+    ('U', ["#.#", "#.#", "#.#", "#.#", "###"]),
+    ('V', ["#.#", "#.#", "#.#", "#.#", ".#."]),
+    ('W', ["#.#", "#.#", "###", "###", "#.#"]),
+    ('X', ["#.#", "#.#", ".#.", "#.#", "#.#"]),
+    ('Y', ["#.#", "#.#", ".#.", ".#.", ".#."]),
+    ('Z', ["###", "..#", ".#.", "#..", "###"]),
+    ('[', ["##.", "#..", "#..", "#..", "##."]),
+    (']', [".##", "..#", "..#", "..#", ".##"]),
+    ('_', ["...", "...", "...", "...", "###"]),
+    ('`', ["#..", ".#.", "...", "...", "..."]),
+];
+
+/// Atlas cell size in texels: a 3×5 glyph plus a column and row of spacing
+const CELL_WIDTH: u32 = 4;
+const CELL_HEIGHT: u32 = 6;
+/// The atlas covers ASCII 32..=126, followed by one fully lit cell used to
+/// draw solid rectangles
+const FIRST_CHAR: u32 = 32;
+const ATLAS_CELLS: u32 = 96;
+const SOLID_CELL: u32 = ATLAS_CELLS - 1;
+/// Screen pixels per font texel
+const FONT_SCALE: u32 = 3;
+/// Gap between the console text and its edges, in pixels
+const CONSOLE_PADDING: f32 = 8.0;
+/// Most characters drawn in one frame, including the background
+const MAX_GLYPHS: usize = 4096;
+
+const BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.65];
+const OUTPUT_COLOR: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+const INPUT_COLOR: [f32; 4] = [1.0, 0.85, 0.3, 1.0];
+const STATUS_COLOR: [f32; 4] = [0.5, 0.85, 1.0, 1.0];
+
+/// Single-channel atlas of every glyph, `CELL_WIDTH` × `CELL_HEIGHT` texels per cell
+fn font_atlas() -> Vec<u8> {
+    let width = (ATLAS_CELLS * CELL_WIDTH) as usize;
+    let mut texels = vec![0u8; width * CELL_HEIGHT as usize];
+    for &(c, rows) in FONT_GLYPHS {
+        let left = ((c as u32 - FIRST_CHAR) * CELL_WIDTH) as usize;
+        for (y, row) in rows.iter().enumerate() {
+            for (x, pixel) in row.bytes().enumerate() {
+                if pixel == b'#' {
+                    texels[y * width + left + x] = 255;
+                }
+            }
+        }
+    }
+    let solid = (SOLID_CELL * CELL_WIDTH) as usize;
+    for y in 0..CELL_HEIGHT as usize {
+        texels[y * width + solid..y * width + solid + CELL_WIDTH as usize].fill(255);
+    }
+    texels
+}
+
+/// Atlas cell for a character
+fn glyph_cell(c: char) -> u32 {
+    let c = c.to_ascii_uppercase();
+    if FONT_GLYPHS.iter().any(|&(glyph, _)| glyph == c) {
+        c as u32 - FIRST_CHAR
+    } else {
+        '?' as u32 - FIRST_CHAR
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct TextVertex {
+    position: [f32; 2],
+    uv: [f32; 2],
+    color: [f32; 4],
+}
+
+const CONSOLE_SHADER: &str = r#"
+@group(0) @binding(0)
+var font: texture_2d<f32>;
+@group(0) @binding(1)
+var font_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(in.position, 0.0, 1.0);
+    out.uv = in.uv;
+    out.color = in.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(font, font_sampler, in.uv).r;
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
+"#;
+
+/// Lays out the console as textured quads, in pixels from the top-left
+struct TextLayout {
+    vertices: Vec<TextVertex>,
+    surface_size: [f32; 2],
+}
+
+impl TextLayout {
+    fn quad(&mut self, rect: [f32; 4], cell: u32, color: [f32; 4]) {
+        if self.vertices.len() + 6 > MAX_GLYPHS * 6 {
+            return;
+        }
+        let [x, y, width, height] = rect;
+        let to_clip = |px: f32, py: f32| [px / self.surface_size[0] * 2.0 - 1.0, 1.0 - py / self.surface_size[1] * 2.0];
+        let atlas_width = (ATLAS_CELLS * CELL_WIDTH) as f32;
+        let (u0, u1, v1) = if cell == SOLID_CELL {
+            // Sample the middle of the lit cell, clear of its neighbours
+            let middle = (cell as f32 + 0.5) * CELL_WIDTH as f32 / atlas_width;
+            (middle, middle, 0.5)
+        } else {
+            let left = (cell * CELL_WIDTH) as f32 / atlas_width;
+            (left, left + 3.0 / atlas_width, 5.0 / CELL_HEIGHT as f32)
+        };
+        let v0 = if cell == SOLID_CELL { 0.5 } else { 0.0 };
+
+        let corners = [
+            (to_clip(x, y), [u0, v0]),
+            (to_clip(x, y + height), [u0, v1]),
+            (to_clip(x + width, y + height), [u1, v1]),
+            (to_clip(x + width, y), [u1, v0]),
+        ];
+        for index in [0, 1, 2, 0, 2, 3] {
+            let (position, uv) = corners[index];
+            self.vertices.push(TextVertex { position, uv, color });
+        }
+    }
+
+    fn text(&mut self, x: f32, y: f32, text: &str, max_chars: usize, color: [f32; 4]) {
+        let advance = (CELL_WIDTH * FONT_SCALE) as f32;
+        let (width, height) = ((3 * FONT_SCALE) as f32, (5 * FONT_SCALE) as f32);
+        for (i, c) in text.chars().take(max_chars).enumerate() {
+            if c != ' ' {
+                self.quad([x + i as f32 * advance, y, width, height], glyph_cell(c), color);
+            }
+        }
+    }
+}
+
+/// Draws a `DevConsole` as a translucent panel along the bottom of the screen
+pub struct ConsoleRenderer {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    vertex_buffer: wgpu::Buffer,
+    vertex_count: u32,
+}
+
+impl ConsoleRenderer {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat) -> Self {
+        let atlas_size = wgpu::Extent3d {
+            width: ATLAS_CELLS * CELL_WIDTH,
+            height: CELL_HEIGHT,
+            depth_or_array_layers: 1,
+        };
+        let atlas = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Console Font Atlas"),
+                size: atlas_size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &font_atlas(),
+        );
+        let view = atlas.create_view(&wgpu::TextureViewDescriptor::default());
+        // Nearest filtering keeps the scaled-up font pixels square
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Console Font Sampler"),
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Console Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Console Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Console Vertex Buffer"),
+            size: (MAX_GLYPHS * 6 * std::mem::size_of::<TextVertex>()) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Console Shader"),
+            source: wgpu::ShaderSource::Wgsl(CONSOLE_SHADER.into()),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Console Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Console Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<TextVertex>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x4],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            // Overlay: drawn over everything, regardless of depth
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: crate::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self { pipeline, bind_group, vertex_buffer, vertex_count: 0 }
+    }
+
+    /// Lays out the console's current text for a `surface_width` ×
+    /// `surface_height` target; call before the pass that draws it
+    pub fn update(&mut self, queue: &wgpu::Queue, console: &DevConsole, surface_width: u32, surface_height: u32) {
+        self.vertex_count = 0;
+        if !console.visible || surface_width == 0 || surface_height == 0 {
+            return;
+        }
+        let (width, height) = (surface_width as f32, surface_height as f32);
+        let line_height = (CELL_HEIGHT * FONT_SCALE) as f32;
+        let status_lines = usize::from(!console.status().is_empty());
+        let panel_height = (VISIBLE_LINES + 1 + status_lines) as f32 * line_height + 2.0 * CONSOLE_PADDING;
+        let max_chars = ((width - 2.0 * CONSOLE_PADDING) / (CELL_WIDTH * FONT_SCALE) as f32).max(0.0) as usize;
+
+        let mut layout = TextLayout { vertices: Vec::new(), surface_size: [width, height] };
+        let top = (height - panel_height).max(0.0);
+        layout.quad([0.0, top, width, height - top], SOLID_CELL, BACKGROUND_COLOR);
+        if status_lines > 0 {
+            layout.text(CONSOLE_PADDING, top + CONSOLE_PADDING, console.status(), max_chars, STATUS_COLOR);
+        }
+
+        // Output fills the panel from the bottom, just above the input line
+        let lines = console.visible_output();
+        let first_line_y = height - CONSOLE_PADDING - (lines.len() + 1) as f32 * line_height;
+        for (i, line) in lines.iter().enumerate() {
+            layout.text(CONSOLE_PADDING, first_line_y + i as f32 * line_height, line, max_chars, OUTPUT_COLOR);
+        }
+        let input = format!("> {}_", console.input());
+        // Keep the end of a long input line, where the cursor is, in view
+        let skip = input.chars().count().saturating_sub(max_chars);
+        let input: String = input.chars().skip(skip).collect();
+        layout.text(CONSOLE_PADDING, height - CONSOLE_PADDING - line_height, &input, max_chars, INPUT_COLOR);
+
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&layout.vertices));
+        self.vertex_count = layout.vertices.len() as u32;
+    }
+
+    /// Draws over the whole target, resetting any viewport and scissor an
+    /// earlier overlay left behind
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, surface_width: u32, surface_height: u32) {
+        if self.vertex_count == 0 {
+            return;
+        }
+        render_pass.set_viewport(0.0, 0.0, surface_width as f32, surface_height as f32, 0.0, 1.0);
+        render_pass.set_scissor_rect(0, 0, surface_width, surface_height);
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..self.vertex_count, 0..1);
+    }
+
+    /// Adds the pass drawing the console over every other overlay
+    pub fn register(renderer: &Rc<RefCell<Self>>, graph: &mut RenderGraph) -> RobinResult<usize> {
+        let renderer = renderer.clone();
+        graph.add_node_after(PASS, &[save_slots::OVERLAY_PASS], move |encoder, context| {
+            let renderer = renderer.borrow();
+            let (width, height) = context.surface_size;
+            let mut render_pass = context.begin_pass(encoder, "Console Pass");
+            renderer.draw(&mut render_pass, width, height);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+
+    #[test]
+    fn test_fill_command() {
+        let mut world = VoxelWorld::empty(16);
+        let mut console = DevConsole::new(None);
+
+        let result = console.execute("fill 3 0 3 1 1 1 stone", &mut world);
+        assert_eq!(result, "Filled 18 voxels with stone");
+        assert_eq!(world.get(1, 0, 1), Some(registry::STONE));
+        assert_eq!(world.get(3, 1, 3), Some(registry::STONE));
+        assert_eq!(world.get(4, 1, 3), None);
+
+        // Boxes running off the world are clipped to it
+        assert_eq!(console.execute("fill 2 0 2 20 0 2 air", &mut world), "Filled 14 voxels with air");
+        assert_eq!(world.get(2, 0, 2), None);
+
+        assert_eq!(console.execute("fill 0 0 0 1 1 1 lava", &mut world), "Unknown voxel type 'lava'");
+        assert!(console.execute("fill 0 0 0 1 1 stone", &mut world).starts_with("usage"));
+    }
+
+    #[test]
+    fn test_tp_command() {
+        let mut world = VoxelWorld::empty(16);
+        let mut console = DevConsole::new(None);
+
+        assert_eq!(console.execute("tp 4 12.5 -3", &mut world), "Teleported to (4, 12.5, -3)");
+        assert_eq!(console.take_teleport(), Some([4.0, 12.5, -3.0]));
+        assert_eq!(console.take_teleport(), None);
+
+        assert!(console.execute("tp 4 up 3", &mut world).starts_with("usage"));
+        assert_eq!(console.take_teleport(), None);
+    }
+
+    #[test]
+    fn test_info_command() {
+        let mut world = VoxelWorld::empty(16);
+        world.set_voxel(2, 3, 4, Some(registry::WATER));
+        let mut console = DevConsole::new(None);
+
+        assert_eq!(
+            console.execute("info 2 3 4", &mut world),
+            "(2, 3, 4): water (id 3), not solid, hardness 0"
+        );
+        assert_eq!(console.execute("info 0 0 0", &mut world), "(0, 0, 0): air");
+        assert_eq!(console.execute("info 0 16 0", &mut world), "(0, 16, 0) is outside the 16x16x16 world");
+    }
+
+    #[test]
+    fn test_seed_command() {
+        let mut world = VoxelWorld::empty(4);
+        let mut console = DevConsole::new(Some(1234));
+        assert_eq!(console.execute("seed", &mut world), "Seed: 1234");
+
+        console.set_seed(None);
+        assert!(console.execute("seed", &mut world).contains("unknown"));
+    }
+
+    #[test]
+    fn test_stats_command() {
+        let mut world = VoxelWorld::empty(16);
+        world.set_voxel(0, 0, 0, Some(registry::GRASS));
+        world.set_voxel(1, 0, 0, Some(registry::GRASS));
+        world.set_voxel(0, 1, 0, Some(registry::CRYSTAL));
+        let mut console = DevConsole::new(None);
+
+        assert_eq!(
+            console.execute("stats", &mut world),
+            "16x16x16 world, 3 voxels, chunks: 1\n  grass: 2\n  crystal: 1"
+        );
+    }
+
+    #[test]
+    fn test_custom_commands_history_and_scrolling() {
+        let mut world = VoxelWorld::empty(4);
+        let mut console = DevConsole::new(None);
+        console.register_command("size", Box::new(|_, world| world.size.to_string()));
+        assert!(console.execute("bogus", &mut world).contains("size"));
+
+        for c in "size".chars() {
+            console.type_char(c);
+        }
+        assert!(console.submit(&mut world));
+        assert_eq!(console.visible_output().last().unwrap(), "4");
+        assert_eq!(console.input(), "");
+
+        console.history_previous();
+        assert_eq!(console.input(), "size");
+        console.history_previous();
+        assert_eq!(console.input(), "bogus");
+        console.history_next();
+        console.history_next();
+        assert_eq!(console.input(), "");
+
+        for _ in 0..20 {
+            console.execute("size", &mut world);
+        }
+        let newest = console.visible_output().to_vec();
+        console.scroll(3);
+        assert_eq!(console.visible_output().len(), VISIBLE_LINES);
+        assert_ne!(console.visible_output(), &newest[..]);
+        console.scroll(-100);
+        assert_eq!(console.visible_output(), &newest[..]);
+    }
+
+    #[test]
+    fn test_font_covers_command_characters() {
+        let atlas = font_atlas();
+        let width = (ATLAS_CELLS * CELL_WIDTH) as usize;
+        for c in "fill 0 9 info tp seed stats > _ x".chars().filter(|&c| c != ' ') {
+            let left = (glyph_cell(c) * CELL_WIDTH) as usize;
+            let lit = (0..5).flat_map(|y| (0..3).map(move |x| (x, y))).filter(|&(x, y)| atlas[y * width + left + x] > 0);
+            assert!(lit.count() > 0, "no glyph for {:?}", c);
+        }
+        // Characters without a glyph fall back to '?'
+        assert_eq!(glyph_cell('³'), glyph_cell('?'));
+    }
+
+    #[test]
+    fn test_console_renderer_lays_out_text() {
+        let instance = wgpu::Instance::default();
+        let adapter = match pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(&instance, None)) {
+            Some(adapter) => adapter,
+            None => {
+                eprintln!("No GPU adapter available, skipping console renderer test");
+                return;
+            }
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap();
+        let mut renderer = ConsoleRenderer::new(&device, &queue, wgpu::TextureFormat::Bgra8UnormSrgb);
+        let mut console = DevConsole::new(Some(7));
+        console.execute("seed", &mut VoxelWorld::empty(4));
+
+        renderer.update(&queue, &console, 800, 600);
+        assert_eq!(renderer.vertex_count, 0, "hidden console still drawn");
+
+        console.visible = true;
+        renderer.update(&queue, &console, 800, 600);
+        // Background, "> seed", "Seed: 7" and the "> _" input line
+        let glyphs = 1 + 5 + 6 + 2;
+        assert_eq!(renderer.vertex_count, glyphs * 6);
+
+        // Plus a status line above the output
+        console.set_status("CPU 16.67 ms");
+        renderer.update(&queue, &console, 800, 600);
+        assert_eq!(renderer.vertex_count, (glyphs + 10) * 6);
+    }
+}
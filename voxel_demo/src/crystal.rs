@@ -0,0 +1,460 @@
+// Instanced crystals
+// Crystal voxels are decorative, so rather than being meshed into chunks they
+// are drawn as small hexagonal prisms that all share one mesh, with every
+// visible crystal rendered by a single instanced draw call.
+
+use crate::error::RobinResult;
+use crate::fog::FOG_WGSL;
+use crate::gpu_timer::GpuTimer;
+use crate::render_graph::RenderGraph;
+use crate::sky;
+use crate::terrain::splitmix64;
+use crate::DEPTH_FORMAT;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wgpu::util::DeviceExt;
+
+/// Render graph pass drawing the crystals
+pub const PASS: &str = "crystals";
+
+/// Prism radius, in voxels, from the axis to each corner
+const PRISM_RADIUS: f32 = 0.3;
+/// Prism height before the per-instance scale is applied
+const PRISM_HEIGHT: f32 = 0.8;
+/// Per-instance height scales fall within this range
+const HEIGHT_SCALE_RANGE: (f32, f32) = (0.75, 1.25);
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CrystalVertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+}
+
+impl CrystalVertex {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<CrystalVertex>() as u64,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: 12,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+            ],
+        }
+    }
+}
+
+/// Per-crystal data, advanced once per instance rather than per vertex
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CrystalInstance {
+    /// World position of the centre of the prism's base
+    pub position: [f32; 3],
+    pub height_scale: f32,
+    /// Radians to rotate the crystal's base color around the hue wheel
+    pub hue_rotation: f32,
+}
+
+impl CrystalInstance {
+    /// A crystal standing on the floor of the voxel cell at `voxel`, with a
+    /// height and hue that vary deterministically from cell to cell
+    pub fn at(voxel: [usize; 3]) -> Self {
+        let [x, y, z] = voxel.map(|c| c as u64);
+        let mut state = (x << 42) ^ (y << 21) ^ z;
+        let mut unit = || (splitmix64(&mut state) >> 40) as f32 / (1u64 << 24) as f32;
+
+        let (min_scale, max_scale) = HEIGHT_SCALE_RANGE;
+        Self {
+            position: [voxel[0] as f32 + 0.5, voxel[1] as f32, voxel[2] as f32 + 0.5],
+            height_scale: min_scale + (max_scale - min_scale) * unit(),
+            // Stay within ±45° so crystals read as variations of one material
+            hue_rotation: (unit() - 0.5) * std::f32::consts::FRAC_PI_2,
+        }
+    }
+
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<CrystalInstance>() as u64,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: 12,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: 16,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32,
+                },
+            ],
+        }
+    }
+}
+
+/// A hexagonal prism with its base centred on the origin, flat-shaded so each
+/// side and cap has its own vertices
+pub fn prism_mesh() -> (Vec<CrystalVertex>, Vec<u16>) {
+    let corner = |i: usize, y: f32| {
+        let angle = i as f32 * std::f32::consts::FRAC_PI_3;
+        [PRISM_RADIUS * angle.cos(), y, PRISM_RADIUS * angle.sin()]
+    };
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for i in 0..6 {
+        let mid_angle = (i as f32 + 0.5) * std::f32::consts::FRAC_PI_3;
+        let normal = [mid_angle.cos(), 0.0, mid_angle.sin()];
+        let start = vertices.len() as u16;
+        for position in [corner(i + 1, 0.0), corner(i, 0.0), corner(i, PRISM_HEIGHT), corner(i + 1, PRISM_HEIGHT)] {
+            vertices.push(CrystalVertex { position, normal });
+        }
+        indices.extend([0, 1, 2, 2, 3, 0].map(|k| start + k));
+    }
+
+    for (y, normal) in [(PRISM_HEIGHT, [0.0, 1.0, 0.0]), (0.0, [0.0, -1.0, 0.0])] {
+        let center = vertices.len() as u16;
+        vertices.push(CrystalVertex { position: [0.0, y, 0.0], normal });
+        for i in 0..6 {
+            vertices.push(CrystalVertex { position: corner(i, y), normal });
+        }
+        for i in 0..6u16 {
+            let (a, b) = (center + 1 + i, center + 1 + (i + 1) % 6);
+            // Counter-clockwise seen from outside the cap
+            if y > 0.0 {
+                indices.extend([center, b, a]);
+            } else {
+                indices.extend([center, a, b]);
+            }
+        }
+    }
+
+    (vertices, indices)
+}
+
+const CRYSTAL_SHADER: &str = r#"
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+    light_pos: vec4<f32>,
+    eye_pos: vec4<f32>,
+    sky_horizon: vec4<f32>,
+    sky_zenith: vec4<f32>,
+    time: f32,
+    fog: FogUniforms,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+struct InstanceInput {
+    @location(2) position: vec3<f32>,
+    @location(3) height_scale: f32,
+    @location(4) hue_rotation: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec3<f32>,
+}
+
+// Rotates a color around the grey axis, which shifts its hue
+fn rotate_hue(color: vec3<f32>, angle: f32) -> vec3<f32> {
+    let k = vec3<f32>(0.57735);
+    let c = cos(angle);
+    return color * c + cross(k, color) * sin(angle) + k * dot(k, color) * (1.0 - c);
+}
+
+@vertex
+fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let local = vec3<f32>(vertex.position.x, vertex.position.y * instance.height_scale, vertex.position.z);
+    let world_position = local + instance.position;
+
+    var out: VertexOutput;
+    out.clip_position = uniforms.view_proj * vec4<f32>(world_position, 1.0);
+    out.world_position = world_position;
+    out.normal = vertex.normal;
+    out.color = clamp(rotate_hue(CRYSTAL_COLOR, instance.hue_rotation), vec3<f32>(0.0), vec3<f32>(1.0));
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let light_dir = normalize(uniforms.light_pos.xyz - in.world_position);
+    let view_dir = normalize(uniforms.eye_pos.xyz - in.world_position);
+
+    // Crystals glow, so they keep a strong base level even in shadow
+    let glow = 0.55 + 0.1 * sin(uniforms.time * 2.0 + in.world_position.x + in.world_position.z);
+    let diffuse = 0.45 * max(dot(in.normal, light_dir), 0.0);
+    let rim = pow(1.0 - max(dot(in.normal, view_dir), 0.0), 3.0);
+
+    let color = in.color * (glow + diffuse) + vec3<f32>(0.4) * rim;
+    let fog = fog_factor(uniforms.fog, distance(uniforms.eye_pos.xyz, in.world_position));
+    return vec4<f32>(mix(color, uniforms.fog.fog_color.rgb, fog), 1.0);
+}
+"#;
+
+/// Draws every crystal in the world from one shared prism mesh. Draw it with
+/// the other opaque geometry, before anything blended; it depth-tests and
+/// writes depth like any other opaque surface.
+pub struct CrystalRenderer {
+    pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    index_count: u32,
+    instance_buffer: Option<wgpu::Buffer>,
+    instance_count: u32,
+    /// When false every crystal gets its own draw call, as a baseline for
+    /// measuring what instancing saves
+    pub instanced: bool,
+    /// Times the crystal draws, when set
+    pub timer: Option<GpuTimer>,
+}
+
+impl CrystalRenderer {
+    /// `layout` must bind the shared `Uniforms` buffer at group 0
+    pub fn new(device: &wgpu::Device, layout: &wgpu::PipelineLayout, format: wgpu::TextureFormat, color: [f32; 3]) -> Self {
+        let color = format!("vec3<f32>({:?}, {:?}, {:?})", color[0], color[1], color[2]);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Crystal Shader"),
+            source: wgpu::ShaderSource::Wgsl(format!("{}{}", FOG_WGSL, CRYSTAL_SHADER.replace("CRYSTAL_COLOR", &color)).into()),
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Crystal Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[CrystalVertex::desc(), CrystalInstance::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        let (vertices, indices) = prism_mesh();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Crystal Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Crystal Index Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Self {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+            instance_buffer: None,
+            instance_count: 0,
+            instanced: true,
+            timer: None,
+        }
+    }
+
+    pub fn instance_count(&self) -> u32 {
+        self.instance_count
+    }
+
+    /// Replaces the instance data, reusing the instance buffer when it is big enough
+    pub fn upload_instances(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, instances: &[CrystalInstance]) {
+        self.instance_count = instances.len() as u32;
+        if instances.is_empty() {
+            return;
+        }
+        self.instance_buffer = Some(crate::write_or_create_buffer(
+            device,
+            queue,
+            self.instance_buffer.take(),
+            bytemuck::cast_slice(instances),
+            // COPY_SRC lets the uploaded instances be read back for inspection
+            wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_SRC,
+            "Crystal Instance Buffer",
+        ));
+    }
+
+    /// Expects the uniform bind group to already be set on the pass
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        let Some(instance_buffer) = self.instance_buffer.as_ref().filter(|_| self.instance_count > 0) else {
+            return;
+        };
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+
+        if self.instanced {
+            render_pass.draw_indexed(0..self.index_count, 0, 0..self.instance_count);
+        } else {
+            for instance in 0..self.instance_count {
+                render_pass.draw_indexed(0..self.index_count, 0, instance..instance + 1);
+            }
+        }
+    }
+
+    /// Adds the pass drawing the crystals, timed by `timer` if it's set
+    pub fn register(renderer: &Rc<RefCell<Self>>, graph: &mut RenderGraph) -> RobinResult<usize> {
+        let renderer = renderer.clone();
+        graph.add_node_after(PASS, &[sky::PASS], move |encoder, context| {
+            let renderer = renderer.borrow();
+            let mut render_pass = context.begin_pass(encoder, "Crystal Pass");
+            render_pass.set_bind_group(0, context.bind_group, &[]);
+            if let Some(timer) = &renderer.timer {
+                timer.begin(&mut render_pass);
+            }
+            renderer.draw(&mut render_pass);
+            if let Some(timer) = &renderer.timer {
+                timer.end(&mut render_pass);
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{registry, VoxelWorld};
+
+    fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+        [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+    }
+
+    #[test]
+    fn test_prism_faces_wind_outward() {
+        let (vertices, indices) = prism_mesh();
+        assert_eq!(vertices.len(), 6 * 4 + 2 * 7);
+        assert_eq!(indices.len(), 6 * 6 + 2 * 18);
+
+        for triangle in indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| vertices[triangle[k] as usize]);
+            let (e1, e2) = (sub(b.position, a.position), sub(c.position, a.position));
+            let cross = [
+                e1[1] * e2[2] - e1[2] * e2[1],
+                e1[2] * e2[0] - e1[0] * e2[2],
+                e1[0] * e2[1] - e1[1] * e2[0],
+            ];
+            let facing: f32 = (0..3).map(|i| cross[i] * a.normal[i]).sum();
+            assert!(facing > 0.0, "triangle {:?} winds against its normal", triangle);
+        }
+    }
+
+    #[test]
+    fn test_instance_buffer_holds_crystal_positions() {
+        let instance = wgpu::Instance::default();
+        let adapter = match pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(&instance, None)) {
+            Some(adapter) => adapter,
+            None => {
+                eprintln!("No GPU adapter available, skipping instance buffer test");
+                return;
+            }
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap();
+
+        let mut world = VoxelWorld::empty(8);
+        for (x, y, z) in [(1, 2, 3), (6, 0, 6), (4, 7, 0)] {
+            world.set_voxel(x, y, z, Some(registry::CRYSTAL));
+        }
+        world.set_voxel(2, 2, 2, Some(registry::STONE));
+        let instances = world.generate_crystal_instances();
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: None,
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            })],
+            push_constant_ranges: &[],
+        });
+        let mut renderer = CrystalRenderer::new(&device, &layout, wgpu::TextureFormat::Rgba8UnormSrgb, [0.8, 0.3, 0.9]);
+        renderer.upload_instances(&device, &queue, &instances);
+        assert_eq!(renderer.instance_count(), 3);
+
+        // Copy the instance buffer back to the CPU
+        let instance_buffer = renderer.instance_buffer.as_ref().unwrap();
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: instance_buffer.size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(instance_buffer, 0, &readback, 0, instance_buffer.size());
+        queue.submit(std::iter::once(encoder.finish()));
+        readback.slice(..).map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        device.poll(wgpu::Maintain::Wait);
+
+        let bytes = readback.slice(..).get_mapped_range();
+        let uploaded: &[CrystalInstance] = bytemuck::cast_slice(&bytes);
+        let mut positions: Vec<[f32; 3]> = uploaded.iter().map(|instance| instance.position).collect();
+        positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(positions, [[1.5, 2.0, 3.5], [4.5, 7.0, 0.5], [6.5, 0.0, 6.5]]);
+        assert!(uploaded.iter().all(|instance| {
+            (HEIGHT_SCALE_RANGE.0..=HEIGHT_SCALE_RANGE.1).contains(&instance.height_scale)
+        }));
+    }
+}
@@ -0,0 +1,130 @@
+// Undo/redo for voxel edits
+// Player edits are recorded with the voxel they replaced, so they can be
+// stepped back and forth. Only the most recent `max_depth` edits are kept.
+
+use crate::registry::VoxelId;
+use crate::VoxelWorld;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Number of edits kept by the demo
+pub const DEFAULT_HISTORY_DEPTH: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VoxelEdit {
+    pub coord: (usize, usize, usize),
+    pub before: Option<VoxelId>,
+    pub after: Option<VoxelId>,
+}
+
+pub struct EditHistory {
+    stack: VecDeque<VoxelEdit>,
+    redo_stack: Vec<VoxelEdit>,
+    max_depth: usize,
+}
+
+impl EditHistory {
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            stack: VecDeque::with_capacity(max_depth),
+            redo_stack: Vec::new(),
+            max_depth,
+        }
+    }
+
+    /// Records an edit that has already been made. Anything undone before
+    /// it can no longer be redone.
+    pub fn push(&mut self, edit: VoxelEdit) {
+        self.redo_stack.clear();
+        self.stack.push_back(edit);
+        while self.stack.len() > self.max_depth {
+            self.stack.pop_front();
+        }
+    }
+
+    /// Reverts the most recent edit, returning it
+    pub fn undo(&mut self, world: &mut VoxelWorld) -> Option<VoxelEdit> {
+        let edit = self.stack.pop_back()?;
+        let (x, y, z) = edit.coord;
+        world.set_voxel(x, y, z, edit.before);
+        self.redo_stack.push(edit);
+        Some(edit)
+    }
+
+    /// Reapplies the most recently undone edit, returning it
+    pub fn redo(&mut self, world: &mut VoxelWorld) -> Option<VoxelEdit> {
+        let edit = self.redo_stack.pop()?;
+        let (x, y, z) = edit.coord;
+        world.set_voxel(x, y, z, edit.after);
+        self.stack.push_back(edit);
+        Some(edit)
+    }
+
+    /// Forgets every edit, e.g. after loading a different world
+    pub fn clear(&mut self) {
+        self.stack.clear();
+        self.redo_stack.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+
+    /// Sets a voxel and records the change, like a player edit
+    fn edit(world: &mut VoxelWorld, history: &mut EditHistory, coord: (usize, usize, usize), voxel: Option<VoxelId>) {
+        let (x, y, z) = coord;
+        history.push(VoxelEdit { coord, before: world.get(x, y, z), after: voxel });
+        world.set_voxel(x, y, z, voxel);
+    }
+
+    #[test]
+    fn test_undo_and_redo_edits() {
+        let mut world = VoxelWorld::empty(8);
+        world.set_voxel(0, 0, 0, Some(registry::DIRT));
+        let mut history = EditHistory::new(10);
+
+        edit(&mut world, &mut history, (1, 0, 0), Some(registry::STONE));
+        edit(&mut world, &mut history, (2, 0, 0), Some(registry::GRASS));
+        edit(&mut world, &mut history, (0, 0, 0), None);
+        edit(&mut world, &mut history, (1, 0, 0), Some(registry::CRYSTAL));
+        edit(&mut world, &mut history, (3, 0, 0), Some(registry::WATER));
+
+        for _ in 0..3 {
+            assert!(history.undo(&mut world).is_some());
+        }
+        assert_eq!(world.get(3, 0, 0), None);
+        assert_eq!(world.get(1, 0, 0), Some(registry::STONE));
+        assert_eq!(world.get(0, 0, 0), Some(registry::DIRT));
+        assert_eq!(world.get(2, 0, 0), Some(registry::GRASS));
+
+        for _ in 0..2 {
+            assert!(history.redo(&mut world).is_some());
+        }
+        assert_eq!(world.get(0, 0, 0), None);
+        assert_eq!(world.get(1, 0, 0), Some(registry::CRYSTAL));
+        assert_eq!(world.get(2, 0, 0), Some(registry::GRASS));
+        assert_eq!(world.get(3, 0, 0), None);
+
+        // A new edit discards the one edit still waiting to be redone
+        edit(&mut world, &mut history, (4, 0, 0), Some(registry::STONE));
+        assert!(history.redo(&mut world).is_none());
+        assert_eq!(world.get(3, 0, 0), None);
+    }
+
+    #[test]
+    fn test_history_is_trimmed_to_max_depth() {
+        let mut world = VoxelWorld::empty(8);
+        let mut history = EditHistory::new(3);
+        for x in 0..5 {
+            edit(&mut world, &mut history, (x, 0, 0), Some(registry::STONE));
+        }
+
+        while history.undo(&mut world).is_some() {}
+        // The two oldest edits fell off the end of the history
+        assert_eq!(world.get(0, 0, 0), Some(registry::STONE));
+        assert_eq!(world.get(1, 0, 0), Some(registry::STONE));
+        assert!((2..5).all(|x| world.get(x, 0, 0).is_none()));
+    }
+}
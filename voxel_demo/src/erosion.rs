@@ -0,0 +1,312 @@
+// Hydraulic erosion
+// Particle-based erosion run over generated terrain to break up the
+// regularity of pure noise. Each droplet lands on a random point of the
+// surface height field and rolls downhill with some inertia, picking up
+// sediment while it speeds up and dropping it as it slows, which cuts
+// valleys along its paths and builds fans where they flatten out. The eroded
+// heights are then written back into the world's columns.
+
+use crate::registry::{self, VoxelId};
+use crate::terrain::splitmix64;
+use crate::VoxelWorld;
+
+/// Droplets are dropped in the same places on every run
+const EROSION_SEED: u64 = 0xE205_1011;
+/// How much of its previous direction a droplet keeps each step, against the
+/// pull of the slope
+const INERTIA: f32 = 0.05;
+/// Downhill acceleration
+const GRAVITY: f32 = 4.0;
+/// Steps a droplet survives before it is dropped
+const MAX_LIFETIME: usize = 30;
+/// Sediment a droplet can always carry, so it keeps cutting on gentle slopes
+const MIN_SEDIMENT_CAPACITY: f32 = 0.01;
+/// Fractions of the spare capacity picked up, and of the excess sediment
+/// dropped, per step
+const ERODE_SPEED: f32 = 0.3;
+const DEPOSIT_SPEED: f32 = 0.3;
+/// Droplets wear away the ground within this many cells of their position,
+/// which keeps them from digging single-cell pits
+const BRUSH_RADIUS: i32 = 2;
+/// Sediment laid down on raised ground
+const SEDIMENT_TYPE: VoxelId = registry::DIRT;
+
+pub struct HydraulicErosion;
+
+impl HydraulicErosion {
+    /// Runs `iterations` droplets over the terrain's surface and rebuilds
+    /// every column to its eroded height. Each droplet starts with
+    /// `rain_amount` water and loses `evaporation` of it per step; its
+    /// capacity for sediment is its speed and water, times the slope it's
+    /// descending, times `sediment_capacity`.
+    ///
+    /// Lowered columns expose what lay beneath their surface and raised ones
+    /// are topped with dirt. Water is ignored while eroding and refilled to
+    /// its old level afterwards; crystals on a column that changed height are
+    /// washed away.
+    pub fn erode(world: &mut VoxelWorld, iterations: u32, rain_amount: f32, evaporation: f32, sediment_capacity: f32) {
+        let size = world.size;
+        let columns: Vec<(usize, usize)> = (0..size).flat_map(|x| (0..size).map(move |z| (x, z))).collect();
+        let before: Vec<usize> = columns.iter().map(|&(x, z)| terrain_height(world, x, z)).collect();
+
+        let mut heights = HeightField {
+            size,
+            heights: before.iter().map(|&h| h as f32).collect(),
+        };
+        heights.erode(iterations, rain_amount, evaporation, sediment_capacity);
+
+        for (index, &(x, z)) in columns.iter().enumerate() {
+            // Bedrock stays put
+            let after = (heights.heights[index].round() as usize).clamp(1, size);
+            if after != before[index] {
+                rebuild_column(world, x, z, before[index], after);
+            }
+        }
+    }
+}
+
+/// One past the highest voxel of the column at (x, z) that isn't water or a
+/// crystal
+fn terrain_height(world: &VoxelWorld, x: usize, z: usize) -> usize {
+    (0..world.size)
+        .rev()
+        .find(|&y| world.voxels[x][y][z].is_some_and(|id| id != registry::WATER && id != registry::CRYSTAL))
+        .map_or(0, |y| y + 1)
+}
+
+/// Moves the top of the column at (x, z) from `before` to `after`, keeping
+/// any water over it at the level it was
+fn rebuild_column(world: &mut VoxelWorld, x: usize, z: usize, before: usize, after: usize) {
+    let water_top = (before..world.size).take_while(|&y| world.voxels[x][y][z] == Some(registry::WATER)).last();
+    for y in before.min(after)..world.size {
+        let voxel = if y < after {
+            Some(SEDIMENT_TYPE).filter(|_| y >= before).or(world.voxels[x][y][z])
+        } else if water_top.is_some_and(|top| y <= top) {
+            Some(registry::WATER)
+        } else {
+            None
+        };
+        if world.voxels[x][y][z] != voxel {
+            world.set_voxel(x, y, z, voxel);
+        }
+    }
+}
+
+/// Mean absolute difference between neighbouring gradients of a square
+/// height field, along both axes. Smooth slopes score zero however steep
+/// they are; it grows with every bend, ridge and gully, so it's used as a
+/// measure of how much visual variety the terrain has.
+#[cfg(test)]
+pub fn mean_gradient_difference(heights: &[f32], size: usize) -> f32 {
+    let at = |x: usize, z: usize| heights[x * size + z];
+    let mut total = 0.0;
+    let mut count = 0;
+    for x in 1..size.saturating_sub(1) {
+        for z in 1..size.saturating_sub(1) {
+            total += (at(x + 1, z) - 2.0 * at(x, z) + at(x - 1, z)).abs();
+            total += (at(x, z + 1) - 2.0 * at(x, z) + at(x, z - 1)).abs();
+            count += 2;
+        }
+    }
+    if count == 0 {
+        0.0
+    } else {
+        total / count as f32
+    }
+}
+
+/// Continuous surface heights, indexed `x * size + z`, that droplets flow
+/// over before they're rounded back to voxels
+struct HeightField {
+    size: usize,
+    heights: Vec<f32>,
+}
+
+impl HeightField {
+    /// Height at (x, z), bilinearly interpolated between the four
+    /// surrounding cells, and its gradient
+    fn sample(&self, x: f32, z: f32) -> (f32, [f32; 2]) {
+        let (cx, cz) = (x as usize, z as usize);
+        let (fx, fz) = (x - cx as f32, z - cz as f32);
+        let index = cx * self.size + cz;
+        let (h00, h01) = (self.heights[index], self.heights[index + 1]);
+        let (h10, h11) = (self.heights[index + self.size], self.heights[index + self.size + 1]);
+
+        let gradient = [
+            (h10 - h00) * (1.0 - fz) + (h11 - h01) * fz,
+            (h01 - h00) * (1.0 - fx) + (h11 - h10) * fx,
+        ];
+        let height = h00 * (1.0 - fx) * (1.0 - fz) + h10 * fx * (1.0 - fz) + h01 * (1.0 - fx) * fz + h11 * fx * fz;
+        (height, gradient)
+    }
+
+    /// Adds `amount` spread bilinearly over the four cells around (x, z)
+    fn deposit(&mut self, x: f32, z: f32, amount: f32) {
+        let (cx, cz) = (x as usize, z as usize);
+        let (fx, fz) = (x - cx as f32, z - cz as f32);
+        let index = cx * self.size + cz;
+        self.heights[index] += amount * (1.0 - fx) * (1.0 - fz);
+        self.heights[index + self.size] += amount * fx * (1.0 - fz);
+        self.heights[index + 1] += amount * (1.0 - fx) * fz;
+        self.heights[index + self.size + 1] += amount * fx * fz;
+    }
+
+    /// Removes up to `amount` from the cells within `BRUSH_RADIUS` of
+    /// (x, z), weighted towards the centre and never digging through
+    /// bedrock. Returns how much was actually removed.
+    fn erode_around(&mut self, x: f32, z: f32, amount: f32) -> f32 {
+        let (cx, cz) = (x as i32, z as i32);
+        let mut cells = Vec::new();
+        for dx in -BRUSH_RADIUS..=BRUSH_RADIUS {
+            for dz in -BRUSH_RADIUS..=BRUSH_RADIUS {
+                let (px, pz) = (cx + dx, cz + dz);
+                let weight = BRUSH_RADIUS as f32 - ((dx * dx + dz * dz) as f32).sqrt();
+                if weight > 0.0 && px >= 0 && pz >= 0 && (px as usize) < self.size && (pz as usize) < self.size {
+                    cells.push((px as usize * self.size + pz as usize, weight));
+                }
+            }
+        }
+
+        let total_weight: f32 = cells.iter().map(|(_, weight)| weight).sum();
+        let mut removed = 0.0;
+        for (index, weight) in cells {
+            let take = (amount * weight / total_weight).min(self.heights[index] - 1.0).max(0.0);
+            self.heights[index] -= take;
+            removed += take;
+        }
+        removed
+    }
+
+    fn erode(&mut self, iterations: u32, rain_amount: f32, evaporation: f32, sediment_capacity: f32) {
+        if self.size < 2 {
+            return;
+        }
+        // Droplets stay where all four cells they sample exist
+        let limit = (self.size - 1) as f32;
+        let mut state = EROSION_SEED;
+        let mut random = || (splitmix64(&mut state) >> 40) as f32 / (1u64 << 24) as f32 * limit;
+
+        for _ in 0..iterations {
+            let (mut x, mut z) = (random(), random());
+            let mut direction = [0.0f32; 2];
+            let (mut speed, mut water, mut sediment) = (1.0f32, rain_amount, 0.0f32);
+
+            for _ in 0..MAX_LIFETIME {
+                let (height, gradient) = self.sample(x, z);
+                direction = [0, 1].map(|axis| direction[axis] * INERTIA - gradient[axis] * (1.0 - INERTIA));
+                let length = (direction[0] * direction[0] + direction[1] * direction[1]).sqrt();
+                if length == 0.0 {
+                    // Resting in a flat hollow
+                    break;
+                }
+                let (next_x, next_z) = (x + direction[0] / length, z + direction[1] / length);
+                if !(0.0..limit).contains(&next_x) || !(0.0..limit).contains(&next_z) {
+                    break;
+                }
+
+                let delta = self.sample(next_x, next_z).0 - height;
+                let capacity = (-delta * speed * water * sediment_capacity).max(MIN_SEDIMENT_CAPACITY);
+                if sediment > capacity || delta > 0.0 {
+                    // Uphill, it fills the hollow it's leaving; otherwise it
+                    // drops part of what it can no longer carry
+                    let amount = if delta > 0.0 {
+                        delta.min(sediment)
+                    } else {
+                        (sediment - capacity) * DEPOSIT_SPEED
+                    };
+                    sediment -= amount;
+                    self.deposit(x, z, amount);
+                } else {
+                    let amount = ((capacity - sediment) * ERODE_SPEED).min(-delta);
+                    sediment += self.erode_around(x, z, amount);
+                }
+
+                speed = (speed * speed - delta * GRAVITY).max(0.0).sqrt();
+                water *= 1.0 - evaporation;
+                (x, z) = (next_x, next_z);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::TerrainGenerator;
+
+    /// A `size` by `size` patch of the noise heights `VoxelWorld::generate`
+    /// starts from, between 8 and 40 voxels high
+    fn noise_patch(size: usize) -> HeightField {
+        let generator = TerrainGenerator::new(1234, 4, 0.5, 2.0);
+        let heights = (0..size)
+            .flat_map(|x| (0..size).map(move |z| (x, z)))
+            .map(|(x, z)| (24.0 + 16.0 * generator.height_at(x as f32 / 48.0, z as f32 / 48.0)).round())
+            .collect();
+        HeightField { size, heights }
+    }
+
+    #[test]
+    fn test_droplets_carry_ground_downhill() {
+        // A ramp rising along x
+        let size = 16;
+        let mut field = HeightField {
+            size,
+            heights: (0..size * size).map(|i| 2.0 + (i / size) as f32).collect(),
+        };
+        let total_before: f32 = field.heights.iter().sum();
+        field.erode(500, 1.0, 0.02, 0.5);
+
+        // Ground is only moved, or carried off the edge, never created
+        let total_after: f32 = field.heights.iter().sum();
+        assert!(total_after < total_before - size as f32, "{} -> {}", total_before, total_after);
+        assert!(field.heights.iter().all(|&h| h >= 1.0));
+
+        // Worn down, but still a ramp
+        let row_mean = |x: usize| field.heights[x * size..(x + 1) * size].iter().sum::<f32>() / size as f32;
+        assert!((1..size).all(|x| row_mean(x) > row_mean(x - 1)));
+    }
+
+    #[test]
+    fn test_erosion_varies_a_noise_patch() {
+        let mut field = noise_patch(64);
+        let before = mean_gradient_difference(&field.heights, 64);
+        field.erode(50_000, 1.0, 0.02, 0.5);
+        // Compared as whole voxels, the way the world stores them
+        let voxel_heights: Vec<f32> = field.heights.iter().map(|h| h.round()).collect();
+        let after = mean_gradient_difference(&voxel_heights, 64);
+        assert!(after > 1.25 * before, "before {}, after {}", before, after);
+    }
+
+    #[test]
+    fn test_eroded_world_keeps_its_layers() {
+        let sea_level = 12;
+        let mut world = VoxelWorld::generate(48, &TerrainGenerator::new(1234, 4, 0.5, 2.0), sea_level);
+        let original = world.voxels.clone();
+        HydraulicErosion::erode(&mut world, 20_000, 1.0, 0.02, 0.5);
+        assert_ne!(world.voxels, original);
+
+        let column_at = |voxels: &Vec<Vec<Vec<Option<VoxelId>>>>, x: usize, z: usize| -> Vec<Option<VoxelId>> {
+            voxels[x].iter().map(|row| row[z]).collect()
+        };
+        let mut raised = 0;
+        for x in 0..world.size {
+            for z in 0..world.size {
+                let column = column_at(&world.voxels, x, z);
+                let original_column = column_at(&original, x, z);
+                let top = column.iter().rposition(|v| v.is_some()).unwrap();
+                // Still solid from the bedrock up, and still flooded to sea
+                // level wherever it was before
+                assert!(column[..=top].iter().all(|v| v.is_some()));
+                assert_eq!(column[0], Some(registry::STONE));
+                if original_column[sea_level] == Some(registry::WATER) {
+                    assert!(column[sea_level].is_some());
+                }
+                if terrain_height(&world, x, z) > original_column.iter().filter(|v| v.is_some_and(|id| id != registry::WATER && id != registry::CRYSTAL)).count() {
+                    raised += 1;
+                    assert_eq!(world.voxels[x][terrain_height(&world, x, z) - 1][z], Some(SEDIMENT_TYPE));
+                }
+            }
+        }
+        assert!(raised > 0);
+    }
+}
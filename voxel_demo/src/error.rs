@@ -0,0 +1,112 @@
+// Error type for the voxel demo
+// A trimmed-down counterpart of the engine's RobinError, since this demo
+// builds without the Robin library.
+
+use std::error::Error as StdError;
+use std::fmt;
+
+pub type RobinResult<T> = Result<T, RobinError>;
+
+#[derive(Debug)]
+pub enum RobinError {
+    Io(std::io::Error),
+    InvalidData { field: String, reason: String },
+    UnsupportedVersion { found: u8, expected: u8 },
+}
+
+impl fmt::Display for RobinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RobinError::Io(error) => write!(f, "I/O error: {}", error),
+            RobinError::InvalidData { field, reason } => {
+                write!(f, "Invalid data in '{}': {}", field, reason)
+            }
+            RobinError::UnsupportedVersion { found, expected } => {
+                write!(f, "Unsupported format version {} (expected {})", found, expected)
+            }
+        }
+    }
+}
+
+impl StdError for RobinError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            RobinError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for RobinError {
+    fn from(error: std::io::Error) -> Self {
+        RobinError::Io(error)
+    }
+}
+
+// bincode only sees in-memory buffers here, so even its I/O errors mean a
+// truncated or corrupt payload rather than a filesystem problem
+impl From<bincode::Error> for RobinError {
+    fn from(error: bincode::Error) -> Self {
+        RobinError::InvalidData {
+            field: "world".to_string(),
+            reason: error.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for RobinError {
+    fn from(error: serde_json::Error) -> Self {
+        if error.is_io() {
+            return RobinError::Io(error.into());
+        }
+        RobinError::InvalidData {
+            field: "template".to_string(),
+            reason: error.to_string(),
+        }
+    }
+}
+
+impl From<image::ImageError> for RobinError {
+    fn from(error: image::ImageError) -> Self {
+        match error {
+            image::ImageError::IoError(error) => RobinError::Io(error),
+            other => RobinError::InvalidData {
+                field: "thumbnail".to_string(),
+                reason: other.to_string(),
+            },
+        }
+    }
+}
+
+impl From<png::EncodingError> for RobinError {
+    fn from(error: png::EncodingError) -> Self {
+        match error {
+            png::EncodingError::IoError(error) => RobinError::Io(error),
+            other => RobinError::InvalidData {
+                field: "screenshot".to_string(),
+                reason: other.to_string(),
+            },
+        }
+    }
+}
+
+impl From<libloading::Error> for RobinError {
+    fn from(error: libloading::Error) -> Self {
+        RobinError::InvalidData {
+            field: "plugin".to_string(),
+            reason: error.to_string(),
+        }
+    }
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for RobinError {
+    fn from(error: tokio_tungstenite::tungstenite::Error) -> Self {
+        match error {
+            tokio_tungstenite::tungstenite::Error::Io(error) => RobinError::Io(error),
+            other => RobinError::InvalidData {
+                field: "session".to_string(),
+                reason: other.to_string(),
+            },
+        }
+    }
+}
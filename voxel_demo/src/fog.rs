@@ -0,0 +1,254 @@
+// Distance fog
+// Fades surfaces into the horizon colour with distance, so chunks streaming
+// in at the edge of the loaded area appear out of the fog instead of popping
+// into view. The voxel and crystal shaders share the `fog_factor` function in
+// `FOG_WGSL`; `FogSettings::fog_factor` is its CPU counterpart.
+
+use crate::console::CommandHandler;
+use crate::CHUNK_SIZE;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// How much of the fog's distance the view stays clear for, in linear mode
+const CLEAR_FRACTION: f32 = 0.5;
+/// Fog factor the exponential modes reach at the end of the fog's distance.
+/// They only approach 1, so this is as hidden as pop-in gets with them.
+#[cfg(test)]
+const END_FACTOR: f32 = 0.99;
+/// -ln(1 - END_FACTOR): exponential fog with density d reaches END_FACTOR at
+/// this / d
+const END_FACTOR_EXPONENT: f32 = 4.6051702;
+
+/// The `FogUniforms` struct and `fog_factor` function. Shaders embed
+/// `FogUniforms` in their uniform block.
+pub const FOG_WGSL: &str = r#"
+struct FogUniforms {
+    fog_color: vec4<f32>,
+    fog_start: f32,
+    fog_end: f32,
+    fog_density: f32,
+    // 0 = linear, 1 = exponential, 2 = exponential squared
+    fog_mode: u32,
+}
+
+// How much of a surface `dist` away is hidden by fog, from 0 to 1
+fn fog_factor(fog: FogUniforms, dist: f32) -> f32 {
+    var factor: f32;
+    switch fog.fog_mode {
+        case 1u: {
+            factor = 1.0 - exp(-fog.fog_density * dist);
+        }
+        case 2u: {
+            let scaled = fog.fog_density * dist;
+            factor = 1.0 - exp(-scaled * scaled);
+        }
+        default: {
+            factor = (dist - fog.fog_start) / max(fog.fog_end - fog.fog_start, 0.0001);
+        }
+    }
+    return clamp(factor, 0.0, 1.0);
+}
+"#;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FogMode {
+    /// Clear up to the start distance, then thickening evenly to the end
+    Linear = 0,
+    /// Thickening from the camera outwards, fastest nearby
+    Exponential = 1,
+    /// Clear nearby, then closing in quickly
+    ExponentialSquared = 2,
+}
+
+impl FogMode {
+    /// Accepts the names the `fog` command uses: linear, exp and exp2
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(FogMode::Linear),
+            "exp" => Some(FogMode::Exponential),
+            "exp2" => Some(FogMode::ExponentialSquared),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FogMode::Linear => "linear",
+            FogMode::Exponential => "exp",
+            FogMode::ExponentialSquared => "exp2",
+        }
+    }
+}
+
+/// The uniform block behind `FOG_WGSL`'s `FogUniforms`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct FogUniforms {
+    pub fog_color: [f32; 4],
+    pub fog_start: f32,
+    pub fog_end: f32,
+    pub fog_density: f32,
+    pub fog_mode: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FogSettings {
+    color: [f32; 3],
+    mode: FogMode,
+    /// Distances the linear mode runs between
+    start: f32,
+    end: f32,
+    /// Thickness of the exponential modes
+    density: f32,
+}
+
+impl Default for FogSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FogSettings {
+    /// Grey linear fog from 32 to 64 voxels away
+    pub fn new() -> Self {
+        Self {
+            color: [0.7, 0.75, 0.8],
+            mode: FogMode::Linear,
+            start: 32.0,
+            end: 64.0,
+            density: END_FACTOR_EXPONENT / 64.0,
+        }
+    }
+
+    pub fn with_color(mut self, color: [f32; 3]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: FogMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_range(mut self, start: f32, end: f32) -> Self {
+        self.start = start;
+        self.end = end;
+        self
+    }
+
+    pub fn with_density(mut self, density: f32) -> Self {
+        self.density = density;
+        self
+    }
+
+    /// Fits the fog to a streamer loading chunks within `load_radius` chunks
+    /// of the camera. The world is always loaded at least that far out in
+    /// every direction, so fog that's thick by then hides chunks arriving or
+    /// leaving, in every mode.
+    pub fn with_load_radius(self, load_radius: u32) -> Self {
+        let end = (load_radius as usize * CHUNK_SIZE) as f32;
+        let density = match self.mode {
+            FogMode::ExponentialSquared => END_FACTOR_EXPONENT.sqrt() / end,
+            _ => END_FACTOR_EXPONENT / end,
+        };
+        self.with_range(end * CLEAR_FRACTION, end).with_density(density)
+    }
+
+    pub fn mode(&self) -> FogMode {
+        self.mode
+    }
+
+    /// How much of a surface `distance` away the fog hides, from 0 to 1
+    #[cfg(test)]
+    pub fn fog_factor(&self, distance: f32) -> f32 {
+        let factor = match self.mode {
+            FogMode::Linear => (distance - self.start) / (self.end - self.start).max(0.0001),
+            FogMode::Exponential => 1.0 - (-self.density * distance).exp(),
+            FogMode::ExponentialSquared => 1.0 - (-(self.density * distance).powi(2)).exp(),
+        };
+        factor.clamp(0.0, 1.0)
+    }
+
+    pub fn uniforms(&self) -> FogUniforms {
+        let [r, g, b] = self.color;
+        FogUniforms {
+            fog_color: [r, g, b, 1.0],
+            fog_start: self.start,
+            fog_end: self.end,
+            fog_density: self.density,
+            fog_mode: self.mode as u32,
+        }
+    }
+}
+
+/// `fog linear|exp|exp2` switches the mode of the fog in `settings`, keeping
+/// it fitted to `load_radius`; `fog` on its own reports the current mode
+pub fn fog_command(settings: Rc<Cell<FogSettings>>, load_radius: u32) -> CommandHandler {
+    Box::new(move |args, _| match args {
+        [] => format!("Fog: {}", settings.get().mode().name()),
+        [name] => match FogMode::from_name(name) {
+            Some(mode) => {
+                settings.set(settings.get().with_mode(mode).with_load_radius(load_radius));
+                format!("Fog set to {}", mode.name())
+            }
+            None => "usage: fog [linear|exp|exp2]".to_string(),
+        },
+        _ => "usage: fog [linear|exp|exp2]".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-5, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn test_fog_factor_formulas() {
+        let linear = FogSettings::new().with_range(10.0, 50.0);
+        assert_close(linear.fog_factor(5.0), 0.0);
+        assert_close(linear.fog_factor(20.0), 0.25);
+        assert_close(linear.fog_factor(50.0), 1.0);
+        assert_close(linear.fog_factor(80.0), 1.0);
+
+        let exponential = FogSettings::new().with_mode(FogMode::Exponential).with_density(0.05);
+        assert_close(exponential.fog_factor(0.0), 0.0);
+        assert_close(exponential.fog_factor(20.0), 1.0 - (-1.0f32).exp());
+        assert_close(exponential.fog_factor(40.0), 1.0 - (-2.0f32).exp());
+
+        let squared = FogSettings::new().with_mode(FogMode::ExponentialSquared).with_density(0.05);
+        assert_close(squared.fog_factor(20.0), 1.0 - (-1.0f32).exp());
+        assert_close(squared.fog_factor(40.0), 1.0 - (-4.0f32).exp());
+        // Clearer than plain exponential fog up close
+        assert!(squared.fog_factor(10.0) < exponential.fog_factor(10.0));
+    }
+
+    #[test]
+    fn test_load_radius_hides_the_edge_in_every_mode() {
+        for mode in [FogMode::Linear, FogMode::Exponential, FogMode::ExponentialSquared] {
+            let fog = FogSettings::new().with_mode(mode).with_load_radius(3);
+            let edge = (3 * CHUNK_SIZE) as f32;
+            assert!(fog.fog_factor(edge) >= END_FACTOR - 1e-4, "{:?} fog is {} at the edge", mode, fog.fog_factor(edge));
+            assert!(fog.fog_factor(edge / 4.0) < 0.75, "{:?} fog is too thick nearby", mode);
+        }
+
+        let uniforms = FogSettings::new().with_load_radius(3).with_color([0.1, 0.2, 0.3]).uniforms();
+        assert_eq!(uniforms.fog_color, [0.1, 0.2, 0.3, 1.0]);
+        assert_eq!((uniforms.fog_start, uniforms.fog_end, uniforms.fog_mode), (24.0, 48.0, 0));
+    }
+
+    #[test]
+    fn test_fog_command_switches_mode() {
+        let settings = Rc::new(Cell::new(FogSettings::new().with_load_radius(2)));
+        let command = fog_command(settings.clone(), 2);
+        let mut world = crate::VoxelWorld::empty(4);
+
+        assert_eq!(command(&[], &mut world), "Fog: linear");
+        assert_eq!(command(&["exp2"], &mut world), "Fog set to exp2");
+        assert_eq!(settings.get().mode(), FogMode::ExponentialSquared);
+        assert!(settings.get().fog_factor(32.0) >= END_FACTOR - 1e-4);
+        assert!(command(&["thick"], &mut world).starts_with("usage"));
+    }
+}
@@ -0,0 +1,218 @@
+// GPU timing
+// Measures how long a span of GPU work takes with timestamp queries, either
+// between commands inside one render pass or across every pass encoded
+// between `start` and `stop`. Readback is asynchronous, so results lag a few
+// frames.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Device features `GpuTimer` can use; request whichever the adapter has.
+/// `start` and `stop` need only `TIMESTAMP_QUERY`, while `begin` and `end`
+/// also need `TIMESTAMP_QUERY_INSIDE_PASSES`.
+pub const TIMER_FEATURES: wgpu::Features =
+    wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES);
+
+const QUERY_BYTES: u64 = 2 * std::mem::size_of::<u64>() as u64;
+
+pub struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    /// Nanoseconds per timestamp tick
+    period: f32,
+    /// Set once the queries have been copied into the readback buffer and
+    /// not yet mapped
+    copy_pending: bool,
+    /// Set while the readback buffer is being mapped; the flag flips once the
+    /// data is ready to read
+    mapping: Option<Arc<AtomicBool>>,
+    last: Option<Duration>,
+}
+
+impl GpuTimer {
+    /// None when the device wasn't created with `TIMESTAMP_QUERY`. The queue
+    /// only supplies the length of a timestamp tick.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("GPU Timer Queries"),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2,
+        });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("GPU Timer Resolve Buffer"),
+            size: QUERY_BYTES,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("GPU Timer Readback Buffer"),
+            size: QUERY_BYTES,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Some(Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            period: queue.get_timestamp_period(),
+            copy_pending: false,
+            mapping: None,
+            last: None,
+        })
+    }
+
+    /// Starts the span inside a pass; needs `TIMESTAMP_QUERY_INSIDE_PASSES`
+    pub fn begin(&self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.write_timestamp(&self.query_set, 0);
+    }
+
+    pub fn end(&self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.write_timestamp(&self.query_set, 1);
+    }
+
+    /// Starts the span before the next pass `encoder` records, with an empty
+    /// pass that only writes the timestamp
+    pub fn start(&self, encoder: &mut wgpu::CommandEncoder) {
+        self.mark(encoder, Some(0), None);
+    }
+
+    /// Ends the span after the last pass `encoder` recorded
+    pub fn stop(&self, encoder: &mut wgpu::CommandEncoder) {
+        self.mark(encoder, None, Some(1));
+    }
+
+    fn mark(&self, encoder: &mut wgpu::CommandEncoder, beginning: Option<u32>, end: Option<u32>) {
+        encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("GPU Timer Mark"),
+            timestamp_writes: Some(wgpu::ComputePassTimestampWrites {
+                query_set: &self.query_set,
+                beginning_of_pass_write_index: beginning,
+                end_of_pass_write_index: end,
+            }),
+        });
+    }
+
+    /// Call after the timed pass ends. Skipped while an earlier measurement is
+    /// still being read back.
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        if self.copy_pending || self.mapping.is_some() {
+            return;
+        }
+        encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve_buffer, 0, &self.readback_buffer, 0, QUERY_BYTES);
+        self.copy_pending = true;
+    }
+
+    /// Call after submitting the encoder passed to `resolve`
+    pub fn request_readback(&mut self) {
+        if !self.copy_pending {
+            return;
+        }
+        let ready = Arc::new(AtomicBool::new(false));
+        let flag = ready.clone();
+        self.readback_buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            if result.is_ok() {
+                flag.store(true, Ordering::Release);
+            }
+        });
+        self.copy_pending = false;
+        self.mapping = Some(ready);
+    }
+
+    /// The most recent completed measurement, if any
+    pub fn read(&mut self, device: &wgpu::Device) -> Option<Duration> {
+        device.poll(wgpu::Maintain::Poll);
+        if self.mapping.as_ref().is_some_and(|ready| ready.load(Ordering::Acquire)) {
+            {
+                let bytes = self.readback_buffer.slice(..).get_mapped_range();
+                let timestamps: &[u64] = bytemuck::cast_slice(&bytes);
+                let ticks = timestamps[1].saturating_sub(timestamps[0]);
+                self.last = Some(Duration::from_nanos((ticks as f64 * self.period as f64) as u64));
+            }
+            self.readback_buffer.unmap();
+            self.mapping = None;
+        }
+        self.last
+    }
+
+    /// The most recent measurement `read` picked up, in milliseconds; 0 until
+    /// the first one completes
+    pub fn last_frame_ms(&self) -> f32 {
+        self.last.map_or(0.0, |elapsed| elapsed.as_secs_f32() * 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_measures_passes() {
+        let instance = wgpu::Instance::default();
+        let adapter = match pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(&instance, None)) {
+            Some(adapter) => adapter,
+            None => {
+                eprintln!("No GPU adapter available, skipping GPU timer test");
+                return;
+            }
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                required_features: adapter.features() & TIMER_FEATURES,
+                ..Default::default()
+            },
+            None,
+        ))
+        .unwrap();
+        let Some(mut timer) = GpuTimer::new(&device, &queue) else {
+            eprintln!("Adapter has no timestamp queries, skipping GPU timer test");
+            return;
+        };
+        assert_eq!(timer.last_frame_ms(), 0.0);
+
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Timer Test Target"),
+            size: wgpu::Extent3d { width: 1024, height: 1024, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        timer.start(&mut encoder);
+        for _ in 0..2 {
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Timer Test Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::RED),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+        }
+        timer.stop(&mut encoder);
+        timer.resolve(&mut encoder);
+        queue.submit(std::iter::once(encoder.finish()));
+        timer.request_readback();
+
+        device.poll(wgpu::Maintain::Wait);
+        assert!(timer.read(&device).is_some());
+        assert!(timer.last_frame_ms() > 0.0);
+    }
+}
@@ -0,0 +1,358 @@
+// First-person hand
+// A small block of the selected voxel type held in the lower-right of the
+// view. It's placed with a fixed model matrix rather than the camera's, so it
+// stays put on screen however the player looks around, and it's drawn over
+// the world without depth testing.
+
+use crate::error::RobinResult;
+use crate::registry::{VoxelId, VoxelRegistry};
+use crate::render_graph::RenderGraph;
+use crate::{multiply_matrices, projection_matrix, wireframe};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wgpu::util::DeviceExt;
+
+/// Render graph pass drawing the held block
+pub const PASS: &str = "hand";
+
+/// Where the block's centre sits in view space; +x is right, -z is forward
+const HAND_OFFSET: [f32; 3] = [0.6, -0.5, -1.4];
+/// Edge length of the held block, in view-space units
+const HAND_SCALE: f32 = 0.35;
+/// Turned towards the centre of the view and tipped back to show its top
+const HAND_YAW: f32 = 25.0 * std::f32::consts::PI / 180.0;
+const HAND_PITCH: f32 = 20.0 * std::f32::consts::PI / 180.0;
+/// Height of the idle bob, in view-space units
+const BOB_AMPLITUDE: f32 = 0.02;
+/// Bob speed in radians per second
+const BOB_FREQUENCY: f32 = 2.0;
+
+const HAND_SHADER: &str = r#"
+struct HandUniforms {
+    clip_from_model: mat4x4<f32>,
+    view_from_model: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> hand: HandUniforms;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = hand.clip_from_model * vec4<f32>(in.position, 1.0);
+    out.normal = (hand.view_from_model * vec4<f32>(in.normal, 0.0)).xyz;
+    out.color = in.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Lit from over the player's shoulder, independent of the world's light
+    let light_dir = normalize(vec3<f32>(-0.4, 0.8, 0.6));
+    let shade = 0.45 + 0.55 * max(dot(normalize(in.normal), light_dir), 0.0);
+    return vec4<f32>(in.color.rgb * shade, in.color.a);
+}
+"#;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct HandVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub color: [f32; 4],
+}
+
+impl HandVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x4];
+
+    fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<HandVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct HandUniforms {
+    clip_from_model: [[f32; 4]; 4],
+    view_from_model: [[f32; 4]; 4],
+}
+
+/// Two triangles per face of a unit cube centred on the origin, wound
+/// counter-clockwise from outside, coloured with `voxel`'s registry color
+pub fn hand_mesh(registry: &VoxelRegistry, voxel: VoxelId) -> Vec<HandVertex> {
+    let color = registry.rgba(voxel);
+    let mut vertices = Vec::with_capacity(36);
+    for (corners, normal) in crate::FACE_CORNERS.iter().zip(crate::FACE_NORMALS) {
+        for corner in [0, 1, 2, 0, 2, 3] {
+            vertices.push(HandVertex {
+                position: corners[corner].map(|c| c - 0.5),
+                normal,
+                color,
+            });
+        }
+    }
+    vertices
+}
+
+fn translation(offset: [f32; 3]) -> [[f32; 4]; 4] {
+    [
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [offset[0], offset[1], offset[2], 1.0],
+    ]
+}
+
+fn rotation_y(angle: f32) -> [[f32; 4]; 4] {
+    let (sin, cos) = angle.sin_cos();
+    [
+        [cos, 0.0, -sin, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [sin, 0.0, cos, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
+fn rotation_x(angle: f32) -> [[f32; 4]; 4] {
+    let (sin, cos) = angle.sin_cos();
+    [
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, cos, sin, 0.0],
+        [0.0, -sin, cos, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
+fn scale(factor: f32) -> [[f32; 4]; 4] {
+    [
+        [factor, 0.0, 0.0, 0.0],
+        [0.0, factor, 0.0, 0.0],
+        [0.0, 0.0, factor, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
+/// View-space placement of the block `time` seconds in, bobbing by
+/// `sin(time * 2) * 0.02`
+pub fn hand_model_matrix(time: f32) -> [[f32; 4]; 4] {
+    let bob = (time * BOB_FREQUENCY).sin() * BOB_AMPLITUDE;
+    let [x, y, z] = HAND_OFFSET;
+    let placed = multiply_matrices(translation([x, y + bob, z]), rotation_y(HAND_YAW));
+    multiply_matrices(multiply_matrices(placed, rotation_x(HAND_PITCH)), scale(HAND_SCALE))
+}
+
+/// Draws the held block after the rest of the scene
+pub struct HandRenderer {
+    pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    vertex_count: u32,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    /// Type the vertex buffer is currently coloured for
+    voxel: Option<VoxelId>,
+}
+
+impl HandRenderer {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Hand Vertex Buffer"),
+            size: (36 * std::mem::size_of::<HandVertex>()) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Hand Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[HandUniforms {
+                clip_from_model: [[0.0; 4]; 4],
+                view_from_model: [[0.0; 4]; 4],
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Hand Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Hand Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Hand Shader"),
+            source: wgpu::ShaderSource::Wgsl(HAND_SHADER.into()),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Hand Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Hand Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[HandVertex::layout()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    // Water in hand is see-through like water in the world
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                // Without a depth test, back faces would draw over front ones
+                cull_mode: Some(wgpu::Face::Back),
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            // Never hidden by the world, however close the camera is to a wall
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: crate::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            vertex_buffer,
+            vertex_count: 0,
+            uniform_buffer,
+            bind_group,
+            voxel: None,
+        }
+    }
+
+    /// Shows `voxel` in hand, rebuilding the block only when the type changes,
+    /// and moves it along its bob for `time`
+    pub fn update(&mut self, queue: &wgpu::Queue, registry: &VoxelRegistry, voxel: VoxelId, aspect_ratio: f32, time: f32) {
+        if self.voxel != Some(voxel) {
+            let vertices = hand_mesh(registry, voxel);
+            queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+            self.vertex_count = vertices.len() as u32;
+            self.voxel = Some(voxel);
+        }
+
+        let view_from_model = hand_model_matrix(time);
+        let uniforms = HandUniforms {
+            clip_from_model: multiply_matrices(projection_matrix(aspect_ratio), view_from_model),
+            view_from_model,
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+    }
+
+    /// Draws the block over whatever the pass has drawn so far
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if self.vertex_count == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..self.vertex_count, 0..1);
+    }
+
+    /// Adds the pass drawing the block, after everything placed in the world
+    /// and before any 2D overlays
+    pub fn register(renderer: &Rc<RefCell<Self>>, graph: &mut RenderGraph) -> RobinResult<usize> {
+        let renderer = renderer.clone();
+        graph.add_node_after(PASS, &[crate::OUTLINE_PASS, wireframe::PASS], move |encoder, context| {
+            let renderer = renderer.borrow();
+            let mut render_pass = context.begin_pass(encoder, "Hand Pass");
+            renderer.draw(&mut render_pass);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+
+    #[test]
+    fn test_hand_mesh_uses_voxel_color() {
+        let types = VoxelRegistry::with_builtin_types();
+        for voxel in [registry::STONE, registry::GRASS, registry::DIRT, registry::WATER, registry::CRYSTAL] {
+            let mesh = hand_mesh(&types, voxel);
+            assert_eq!(mesh.len(), 36);
+            assert!(
+                mesh.iter().all(|vertex| vertex.color == types.rgba(voxel)),
+                "{} block has the wrong color",
+                types.get(voxel).unwrap().name
+            );
+        }
+        assert!(hand_mesh(&types, registry::WATER)[0].color[3] < 1.0);
+        assert_ne!(hand_mesh(&types, registry::STONE)[0].color, hand_mesh(&types, registry::GRASS)[0].color);
+    }
+
+    #[test]
+    fn test_hand_sits_lower_right_and_bobs() {
+        let clip = |time: f32| {
+            let m = multiply_matrices(projection_matrix(16.0 / 9.0), hand_model_matrix(time));
+            // The block's centre is the model origin, so its clip position is
+            // the last column
+            let [x, y, _, w] = m[3];
+            [x / w, y / w]
+        };
+
+        let [x, y] = clip(0.0);
+        assert!(x > 0.2 && x < 1.0, "hand isn't on the right: {}", x);
+        assert!(y < -0.2 && y > -1.0, "hand isn't at the bottom: {}", y);
+
+        // Highest a quarter-cycle in, 0.02 above rest in view space
+        let peak = hand_model_matrix(std::f32::consts::FRAC_PI_4);
+        assert!((peak[3][1] - (HAND_OFFSET[1] + BOB_AMPLITUDE)).abs() < 1e-6);
+        assert!(clip(std::f32::consts::FRAC_PI_4)[1] > y);
+    }
+}
@@ -0,0 +1,4118 @@
+// Standalone Interactive Voxel Demo for macOS
+// This is a self-contained demo that doesn't require the full Robin library.
+// The demo lives in this library, with a thin binary in main.rs, so the
+// benchmarks in `benches/` can link against it.
+
+pub mod annotation;
+mod atlas;
+mod biome;
+pub mod compute_mesh;
+pub mod console;
+mod crystal;
+mod edit_history;
+mod erosion;
+mod error;
+mod fog;
+mod gpu_timer;
+mod hand;
+mod lighting;
+mod minimap;
+pub mod octree;
+pub mod multiplayer;
+mod particles;
+mod persistence;
+mod physics;
+pub mod render_graph;
+pub mod plugin;
+pub mod registry;
+mod replay;
+mod save_slots;
+mod screenshot;
+mod shader_watcher;
+mod sky;
+pub mod spatial_hash;
+mod streaming;
+mod structural;
+mod template;
+mod terrain;
+pub mod text;
+mod transparency;
+mod water;
+mod wireframe;
+
+use annotation::{AnnotationLayer, WorldAnnotation};
+use biome::BiomeClassifier;
+use edit_history::{EditHistory, VoxelEdit, DEFAULT_HISTORY_DEPTH};
+use error::RobinResult;
+use erosion::HydraulicErosion;
+use fog::FogSettings;
+use lighting::{EmissiveLightSource, LightBuffer, PointLight};
+use physics::{PhysicsBody, PhysicsEngine};
+use plugin::PluginManager;
+use registry::{VoxelId, VoxelRegistry};
+use render_graph::{RenderContext, RenderGraph};
+use replay::{ReplayPlayer, ReplayRecorder};
+use save_slots::SaveSlotManager;
+use shader_watcher::ShaderWatcher;
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use terrain::TerrainGenerator;
+use transparency::TransparencyBucket;
+use streaming::{ChunkCoord, VoxelChunk, WorldStreamer};
+use structural::{StructuralAnalyzer, StructuralReport};
+use water::WaterTask;
+use winit::{
+    event::{Event, WindowEvent, DeviceEvent, ElementState, KeyEvent, MouseButton},
+    event_loop::{ControlFlow, EventLoop},
+    keyboard::{KeyCode, PhysicalKey},
+    window::{CursorGrabMode, WindowBuilder},
+};
+use wgpu::util::DeviceExt;
+
+// Math utilities
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    /// RGBA; alpha below 1 marks geometry for the blended pass
+    color: [f32; 4],
+    /// Ambient occlusion in [0, 1], where 1 is a fully occluded corner
+    ao: f32,
+    /// Position within the face in tiles; see `face_uv`
+    uv: [f32; 2],
+    /// Atlas tile drawn on the face
+    tile: u32,
+}
+
+impl Vertex {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as u64,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: 12,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: 24,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: 40,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: 44,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: 52,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Uint32,
+                },
+            ],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Uniforms {
+    view_proj: [[f32; 4]; 4],
+    inv_view_proj: [[f32; 4]; 4],
+    light_pos: [f32; 4],
+    eye_pos: [f32; 4],
+    sky_horizon: [f32; 4],
+    sky_zenith: [f32; 4],
+    time: f32,
+    _padding: [f32; 3],
+    fog: fog::FogUniforms,
+}
+
+// Simple voxel world
+pub struct VoxelWorld {
+    voxels: Vec<Vec<Vec<Option<VoxelId>>>>,
+    registry: Arc<VoxelRegistry>,
+    size: usize,
+    chunks: Vec<Chunk>,
+    chunks_per_axis: usize,
+}
+
+/// Seed for the demo world; change it for a different landscape
+const DEFAULT_TERRAIN_SEED: u64 = 0x5E_ED0F_2081;
+/// World units per cycle of the base terrain octave
+const TERRAIN_SCALE: f32 = 48.0;
+/// Erosion droplets per column of a newly generated world
+const EROSION_DROPLETS_PER_COLUMN: usize = 1;
+
+/// Radians of camera rotation per pixel of mouse movement
+const MOUSE_SENSITIVITY: f32 = 0.003;
+/// Pitch limit (89°) that keeps the camera from flipping over the poles
+const MAX_PITCH: f32 = 89.0 * std::f32::consts::PI / 180.0;
+
+/// Height of the camera above the player's feet
+const EYE_HEIGHT: f32 = 1.6;
+/// Horizontal walking speed in voxels per second
+const WALK_SPEED: f32 = 4.5;
+
+/// Puts a player's feet below the camera, lifted clear of any terrain the
+/// camera happens to be buried in
+fn spawn_body(camera: &Camera, world: &VoxelWorld) -> PhysicsBody {
+    let [x, y, z] = camera.position;
+    let mut feet = y - EYE_HEIGHT;
+    if x >= 0.0 && z >= 0.0 && (x as usize) < world.size && (z as usize) < world.size {
+        if let Some((top, _)) = world.highest_voxel(x as usize, z as usize) {
+            feet = feet.max(top as f32 + 1.0);
+        }
+    }
+    PhysicsBody::new([x, feet, z])
+}
+
+/// Hands `world` over to a streamer writing to `directory`. Chunks near
+/// `camera` stay resident and the rest are emptied until the camera comes
+/// close; chunks that were never edited are read back from a copy of the
+/// world as it is now.
+fn start_streaming(world: &mut VoxelWorld, directory: &Path, camera: [f32; 3]) -> WorldStreamer {
+    let snapshot = Arc::new(VoxelWorld::from_voxels(world.voxels.clone(), world.size, world.registry.clone()));
+    let per_axis = snapshot.chunks_per_axis;
+    let mut streamer = WorldStreamer::new(directory, STREAM_RADIUS, tokio::runtime::Handle::current(), move |coord| {
+        snapshot.chunk_voxels(coord.map(|axis| axis as usize))
+    })
+    .with_bounds([per_axis as i32; 3]);
+
+    let center = streaming::chunk_containing(camera);
+    for cx in 0..per_axis {
+        for cy in 0..per_axis {
+            for cz in 0..per_axis {
+                let coord: ChunkCoord = [cx as i32, cy as i32, cz as i32];
+                if streaming::chunk_distance(coord, center) <= STREAM_RADIUS {
+                    streamer.insert(coord, world.chunk_voxels([cx, cy, cz]));
+                } else {
+                    world.unload_chunk([cx, cy, cz]);
+                }
+            }
+        }
+    }
+    world.take_edited_chunks();
+    streamer
+}
+
+/// Passes this frame's edits to the streamer, then brings the world's
+/// resident chunks in line with what the streamer has loaded around `camera`
+fn update_streaming(world: &mut VoxelWorld, streamer: &mut WorldStreamer, camera: [f32; 3]) {
+    for chunk in world.take_edited_chunks() {
+        let coord = chunk.map(|axis| axis as i32);
+        streamer.update_chunk(coord, world.chunk_voxels(chunk));
+    }
+
+    let events = streamer.update_position(camera);
+    for &coord in &events.unloaded {
+        world.unload_chunk(coord.map(|axis| axis as usize));
+    }
+    for &coord in &events.loaded {
+        if let Some(voxels) = streamer.chunk(coord) {
+            world.load_chunk(coord.map(|axis| axis as usize), voxels);
+        }
+    }
+}
+
+/// Where startup looks for a world saved before the demo had save slots
+const WORLD_SAVE_PATH: &str = "voxel_world.bin";
+/// Directory searched for `<voxel type>.png` tile images
+const TEXTURE_DIRECTORY: &str = "assets/textures";
+/// Directory holding the save slots
+const SAVE_DIRECTORY: &str = "saves";
+/// Directory, inside `SAVE_DIRECTORY`, for the files of chunks edited and
+/// then streamed out. It only lives for one run of the demo.
+const CHUNK_DIRECTORY: &str = "chunks";
+/// Directory searched for plugin libraries at startup
+const PLUGIN_DIRECTORY: &str = "plugins";
+/// Directory the `template` console command saves and stamps structures from
+const TEMPLATE_DIRECTORY: &str = "templates";
+/// Source of the voxel shader, watched for edits while the demo runs
+const VOXEL_SHADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders/voxel.wgsl");
+/// Chunks within this many chunks of the camera are kept in memory and drawn
+const STREAM_RADIUS: u32 = 4;
+/// File, inside `SAVE_DIRECTORY`, the edit recording is written to on entering replay mode
+const REPLAY_FILE: &str = "replay.bin";
+/// Milliseconds of recording scrubbed per second an arrow key is held
+const REPLAY_SCRUB_SPEED: f32 = 4000.0;
+
+/// Which slot menu is open; number keys pick a slot instead of a voxel type
+#[derive(Clone, Copy, PartialEq)]
+enum SlotMenu {
+    Save,
+    Load,
+}
+
+/// Chunks closer than this (in world units) are meshed at full resolution
+const LOD1_DISTANCE: f32 = 32.0;
+/// Chunks closer than this use LOD 1; anything further uses LOD 2
+const LOD2_DISTANCE: f32 = 64.0;
+
+fn lod_for_distance(distance: f32) -> u8 {
+    if distance < LOD1_DISTANCE {
+        0
+    } else if distance < LOD2_DISTANCE {
+        1
+    } else {
+        2
+    }
+}
+
+/// How far away, in voxels, the player can pick voxels with the mouse
+const REACH_DISTANCE: f32 = 12.0;
+
+/// Each crystal is a point light; the ones nearest the camera fill the slots
+/// the sun leaves free
+const CRYSTAL_LIGHT_INTENSITY: f32 = 6.0;
+const CRYSTAL_LIGHT_RADIUS: f32 = 8.0;
+
+fn crystal_lights(instances: &[crystal::CrystalInstance], color: [f32; 3]) -> Vec<PointLight> {
+    instances
+        .iter()
+        .map(|instance| {
+            let [x, y, z] = instance.position;
+            PointLight::new([x, y + 0.5 * instance.height_scale, z], color, CRYSTAL_LIGHT_INTENSITY, CRYSTAL_LIGHT_RADIUS)
+        })
+        .collect()
+}
+
+/// The glow demo scatters this much glowstone on the ground around the
+/// camera and switches the sun off, leaving glowing voxels as the only lights
+const GLOW_DEMO_COUNT: usize = 20;
+const GLOW_DEMO_RADIUS: f32 = 16.0;
+/// Particles burst out of each voxel broken or placed
+const PARTICLES_PER_EDIT: u32 = 24;
+
+/// HUD labels in the top-left corner: screen pixels per font atlas texel,
+/// giving text about 16 pixels tall
+const HUD_TEXT_SCALE: f32 = 0.5;
+const HUD_MARGIN: f32 = 12.0;
+const HUD_TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.9];
+
+/// Scale of annotation labels up to `ANNOTATION_FULL_SIZE_DISTANCE` from the
+/// camera; further away they shrink with distance
+const ANNOTATION_TEXT_SCALE: f32 = 0.6;
+const ANNOTATION_FULL_SIZE_DISTANCE: f32 = 5.0;
+const ANNOTATION_COLOR: [f32; 4] = [1.0, 0.85, 0.2, 1.0];
+/// Length of each stroke of an annotation arrow's head
+const ARROW_HEAD_LENGTH: f32 = 0.3;
+/// How close to a label a left click in spectator mode must land to delete it
+const ANNOTATION_PICK_RADIUS: f32 = 1.5;
+/// Author of the annotations made in this window
+const LOCAL_AUTHOR_ID: &str = "teacher";
+
+/// Debug wireframe colors: resident chunk boundaries, the player's physics
+/// box and the solid voxels around it
+const CHUNK_BOUNDS_COLOR: [f32; 4] = [0.2, 0.8, 1.0, 0.6];
+const PHYSICS_BODY_COLOR: [f32; 4] = [0.2, 1.0, 0.3, 1.0];
+const PHYSICS_VOXEL_COLOR: [f32; 4] = [1.0, 0.4, 0.2, 0.8];
+/// Weight of the latest frame in the FPS shown, so it doesn't flicker
+const HUD_FPS_SMOOTHING: f32 = 0.1;
+
+/// Places up to `count` glowstone voxels on solid ground within `radius` of
+/// `center` along x and z, returning the edits made
+fn scatter_glowstone(world: &mut VoxelWorld, center: [f32; 3], radius: f32, count: usize, seed: u64) -> Vec<VoxelEdit> {
+    let mut state = seed;
+    let mut offset = || (terrain::splitmix64(&mut state) as f64 / u64::MAX as f64 * 2.0 - 1.0) as f32 * radius;
+    let mut edits = Vec::new();
+    // Bounded, since many columns can be off the world or under water
+    for _ in 0..count * 8 {
+        if edits.len() == count {
+            break;
+        }
+        let (x, z) = ((center[0] + offset()).floor(), (center[2] + offset()).floor());
+        if x < 0.0 || z < 0.0 {
+            continue;
+        }
+        let (x, z) = (x as usize, z as usize);
+        let Some((top, ground)) = world.highest_voxel(x, z) else {
+            continue;
+        };
+        let solid = world.registry.get(ground).is_some_and(|definition| definition.solid);
+        if !solid || top + 1 >= world.size {
+            continue;
+        }
+        let edit = VoxelEdit { coord: (x, top + 1, z), before: None, after: Some(registry::GLOWSTONE) };
+        world.set_voxel(x, top + 1, z, edit.after);
+        edits.push(edit);
+    }
+    edits
+}
+
+/// Edge length, in voxels, of the cubes the world is meshed and uploaded in
+const CHUNK_SIZE: usize = 16;
+
+/// Tunables for how the world is drawn
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderSettings {
+    /// Chunks with any part closer to the camera than this have their
+    /// transparent faces re-sorted every frame
+    pub transparency_near_distance: f32,
+    /// Chunks closer than this, but not near, are re-sorted as the camera
+    /// moves; chunks any farther away are never sorted
+    pub transparency_far_distance: f32,
+    /// How far the camera moves before mid-range chunks are re-sorted
+    pub transparency_resort_distance: f32,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            transparency_near_distance: 16.0,
+            transparency_far_distance: 64.0,
+            transparency_resort_distance: 2.0,
+        }
+    }
+}
+
+/// A CHUNK_SIZE³ slice of the world with its own GPU vertex buffer. Only dirty
+/// chunks are re-meshed, so a single edit costs at most a handful of chunks
+/// instead of the whole world.
+struct Chunk {
+    origin: [usize; 3],
+    dirty: bool,
+    vertex_buffer: Option<wgpu::Buffer>,
+    index_buffer: Option<wgpu::Buffer>,
+    index_count: u32,
+    transparent_vertex_buffer: Option<wgpu::Buffer>,
+    transparent_index_buffer: Option<wgpu::Buffer>,
+    /// CPU copy of the transparent geometry, kept so its quads can be re-sorted
+    /// back-to-front whenever the camera moves
+    transparent_mesh: (Vec<Vertex>, Vec<u32>),
+    transparent_order_dirty: bool,
+    /// Camera position `transparent_mesh` was last sorted from, or `None`
+    /// if it's still in meshing order
+    transparent_sorted_from: Option<[f32; 3]>,
+    /// Detail level the current vertex buffer was built at (see `lod_for_distance`)
+    lod: u8,
+    /// Whether the streamer has this chunk in memory; only resident chunks are drawn
+    resident: bool,
+    /// Edited since its voxels were last handed to the streamer
+    edited: bool,
+    /// Voxels in the chunk that cast light, as of its last re-mesh
+    emissive_sources: Vec<EmissiveLightSource>,
+}
+
+/// The six faces of a voxel. Discriminants match the face indices used by
+/// `add_face` and the mesher.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FaceDirection {
+    Front = 0,  // z+
+    Back = 1,   // z-
+    Right = 2,  // x+
+    Left = 3,   // x-
+    Top = 4,    // y+
+    Bottom = 5, // y-
+}
+
+impl FaceDirection {
+    /// Unit offset from a voxel to the neighbour behind this face
+    fn normal(&self) -> [i32; 3] {
+        match self {
+            FaceDirection::Front => [0, 0, 1],
+            FaceDirection::Back => [0, 0, -1],
+            FaceDirection::Right => [1, 0, 0],
+            FaceDirection::Left => [-1, 0, 0],
+            FaceDirection::Top => [0, 1, 0],
+            FaceDirection::Bottom => [0, -1, 0],
+        }
+    }
+
+    /// The face a ray crosses when it steps one voxel along `axis` in the
+    /// direction of `step`.
+    fn entered_from(axis: usize, step: i64) -> Self {
+        match (axis, step > 0) {
+            (0, true) => FaceDirection::Left,
+            (0, false) => FaceDirection::Right,
+            (1, true) => FaceDirection::Bottom,
+            (1, false) => FaceDirection::Top,
+            (_, true) => FaceDirection::Back,
+            (_, false) => FaceDirection::Front,
+        }
+    }
+}
+
+impl VoxelWorld {
+    pub fn new(size: usize) -> Self {
+        let generator = TerrainGenerator::new(DEFAULT_TERRAIN_SEED, 4, 0.5, 2.0);
+        let mut world = Self::generate(size, &generator, size / 4);
+        let droplets = (size * size * EROSION_DROPLETS_PER_COLUMN) as u32;
+        HydraulicErosion::erode(&mut world, droplets, 1.0, 0.02, 0.5);
+        // Erosion is part of generating the world, not an edit to stream out
+        world.take_edited_chunks();
+        world
+    }
+
+    /// Builds terrain from the generator's height field, shaped by the biome
+    /// of each column: the biome's surface voxel over a few layers of its
+    /// subsurface voxel over stone, water filling everything at or below
+    /// `sea_level`, and crystals scattered on high ground. Ocean columns are
+    /// always under water and desert columns never are.
+    fn generate(size: usize, generator: &TerrainGenerator, sea_level: usize) -> Self {
+        let mut voxels = vec![vec![vec![None; size]; size]; size];
+        let amplitude = size as f32 * 0.25;
+        let biomes = BiomeClassifier::new(generator.seed());
+
+        for (x, plane) in voxels.iter_mut().enumerate() {
+            for z in 0..size {
+                let weights = biomes.weights_at(x, z);
+                let noise = generator.height_at(x as f32 / TERRAIN_SCALE, z as f32 / TERRAIN_SCALE);
+                // Each biome's own ground level, blended by weight so there are
+                // no cliffs at boundaries
+                let offset: f32 = weights
+                    .iter()
+                    .enumerate()
+                    .map(|(index, weight)| {
+                        let biome = biomes.biome(index);
+                        weight * (biome.elevation * size as f32 + noise * amplitude * biome.height_scale)
+                    })
+                    .sum();
+                let mut height = (sea_level as f32 + offset).round().max(1.0) as usize;
+                match BiomeClassifier::dominant(&weights) {
+                    biome::OCEAN => height = height.min(sea_level),
+                    biome::DESERT => height = height.max(sea_level + 1),
+                    _ => {}
+                }
+                let height = height.min(size);
+                let surface = biomes.surface_biome(&weights, x, z);
+
+                for (y, layer) in plane.iter_mut().enumerate().take(height) {
+                    layer[z] = Some(if y == 0 {
+                        registry::STONE
+                    } else if y + 1 == height && height > sea_level {
+                        surface.surface_type
+                    } else if y + 4 > height {
+                        surface.subsurface_type
+                    } else {
+                        registry::STONE
+                    });
+                }
+                for layer in plane.iter_mut().take(sea_level + 1).skip(height) {
+                    layer[z] = Some(registry::WATER);
+                }
+
+                let high_ground = height > sea_level + 3 && height < size;
+                if high_ground && generator.feature_at(x as i32, z as i32) < surface.crystal_frequency {
+                    plane[height][z] = Some(registry::CRYSTAL);
+                }
+            }
+        }
+
+        Self::from_voxels(voxels, size, Arc::new(VoxelRegistry::with_builtin_types()))
+    }
+
+    pub fn empty(size: usize) -> Self {
+        Self::from_voxels(
+            vec![vec![vec![None; size]; size]; size],
+            size,
+            Arc::new(VoxelRegistry::with_builtin_types()),
+        )
+    }
+
+    fn from_voxels(voxels: Vec<Vec<Vec<Option<VoxelId>>>>, size: usize, registry: Arc<VoxelRegistry>) -> Self {
+        let chunks_per_axis = size.div_ceil(CHUNK_SIZE);
+        let mut chunks = Vec::with_capacity(chunks_per_axis.pow(3));
+        for cx in 0..chunks_per_axis {
+            for cy in 0..chunks_per_axis {
+                for cz in 0..chunks_per_axis {
+                    chunks.push(Chunk {
+                        origin: [cx * CHUNK_SIZE, cy * CHUNK_SIZE, cz * CHUNK_SIZE],
+                        dirty: true,
+                        vertex_buffer: None,
+                        index_buffer: None,
+                        index_count: 0,
+                        transparent_vertex_buffer: None,
+                        transparent_index_buffer: None,
+                        transparent_mesh: (Vec::new(), Vec::new()),
+                        transparent_order_dirty: false,
+                        transparent_sorted_from: None,
+                        lod: 0,
+                        resident: true,
+                        edited: false,
+                        emissive_sources: Vec::new(),
+                    });
+                }
+            }
+        }
+
+        Self { voxels, registry, size, chunks, chunks_per_axis }
+    }
+
+    fn chunk_index(&self, cx: usize, cy: usize, cz: usize) -> usize {
+        (cx * self.chunks_per_axis + cy) * self.chunks_per_axis + cz
+    }
+
+    /// Half-open voxel bounds of a chunk, clamped to the world size
+    fn chunk_bounds(&self, chunk: &Chunk) -> ([usize; 3], [usize; 3]) {
+        let min = chunk.origin;
+        let max = [
+            (min[0] + CHUNK_SIZE).min(self.size),
+            (min[1] + CHUNK_SIZE).min(self.size),
+            (min[2] + CHUNK_SIZE).min(self.size),
+        ];
+        (min, max)
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Bytes the dense voxel grid occupies: every cell, plus the headers of
+    /// the nested vectors holding them
+    pub fn voxel_memory_usage(&self) -> usize {
+        let rows = self.size + self.size * self.size;
+        std::mem::size_of_val(&self.voxels)
+            + rows * std::mem::size_of::<Vec<Option<VoxelId>>>()
+            + self.size.pow(3) * std::mem::size_of::<Option<VoxelId>>()
+    }
+
+    pub fn registry(&self) -> &VoxelRegistry {
+        &self.registry
+    }
+
+    /// The registry for adding voxel types. Copies it first if a mesher or
+    /// the water task still shares it.
+    pub fn registry_mut(&mut self) -> &mut VoxelRegistry {
+        Arc::make_mut(&mut self.registry)
+    }
+
+    /// Changes a single voxel and flags the chunks whose mesh it affects.
+    /// Out-of-range coordinates are ignored.
+    pub fn set_voxel(&mut self, x: usize, y: usize, z: usize, voxel: Option<VoxelId>) {
+        if x >= self.size || y >= self.size || z >= self.size || self.voxels[x][y][z] == voxel {
+            return;
+        }
+        self.voxels[x][y][z] = voxel;
+        self.mark_dirty(x, y, z);
+        let index = self.chunk_index(x / CHUNK_SIZE, y / CHUNK_SIZE, z / CHUNK_SIZE);
+        self.chunks[index].edited = true;
+    }
+
+    /// Copies a chunk's voxels out for the streamer. Cells past the edge of
+    /// the world are empty.
+    fn chunk_voxels(&self, chunk: [usize; 3]) -> VoxelChunk {
+        let origin = chunk.map(|axis| axis * CHUNK_SIZE);
+        let mut voxels = VoxelChunk::empty();
+        for x in origin[0]..(origin[0] + CHUNK_SIZE).min(self.size) {
+            for y in origin[1]..(origin[1] + CHUNK_SIZE).min(self.size) {
+                for z in origin[2]..(origin[2] + CHUNK_SIZE).min(self.size) {
+                    voxels.set(x - origin[0], y - origin[1], z - origin[2], self.voxels[x][y][z]);
+                }
+            }
+        }
+        voxels.dirty = false;
+        voxels
+    }
+
+    /// Fills a chunk with voxels the streamer loaded and queues it, plus the
+    /// neighbours whose border faces it now hides, for meshing
+    fn load_chunk(&mut self, chunk: [usize; 3], voxels: &VoxelChunk) {
+        let origin = chunk.map(|axis| axis * CHUNK_SIZE);
+        for x in origin[0]..(origin[0] + CHUNK_SIZE).min(self.size) {
+            for y in origin[1]..(origin[1] + CHUNK_SIZE).min(self.size) {
+                for z in origin[2]..(origin[2] + CHUNK_SIZE).min(self.size) {
+                    self.voxels[x][y][z] = voxels.get(x - origin[0], y - origin[1], z - origin[2]);
+                }
+            }
+        }
+        let index = self.chunk_index(chunk[0], chunk[1], chunk[2]);
+        self.chunks[index].resident = true;
+        self.chunks[index].edited = false;
+        self.mark_chunk_and_neighbors_dirty(chunk);
+    }
+
+    /// Empties a chunk the streamer unloaded and frees its GPU buffers
+    fn unload_chunk(&mut self, chunk: [usize; 3]) {
+        let origin = chunk.map(|axis| axis * CHUNK_SIZE);
+        for x in origin[0]..(origin[0] + CHUNK_SIZE).min(self.size) {
+            for y in origin[1]..(origin[1] + CHUNK_SIZE).min(self.size) {
+                for z in origin[2]..(origin[2] + CHUNK_SIZE).min(self.size) {
+                    self.voxels[x][y][z] = None;
+                }
+            }
+        }
+        let index = self.chunk_index(chunk[0], chunk[1], chunk[2]);
+        let unloaded = &mut self.chunks[index];
+        unloaded.resident = false;
+        unloaded.edited = false;
+        unloaded.vertex_buffer = None;
+        unloaded.index_buffer = None;
+        unloaded.index_count = 0;
+        unloaded.transparent_vertex_buffer = None;
+        unloaded.transparent_index_buffer = None;
+        unloaded.transparent_mesh = (Vec::new(), Vec::new());
+        unloaded.emissive_sources.clear();
+        self.mark_chunk_and_neighbors_dirty(chunk);
+    }
+
+    fn mark_chunk_and_neighbors_dirty(&mut self, chunk: [usize; 3]) {
+        for axis in 0..3 {
+            for neighbor in [chunk[axis].checked_sub(1), Some(chunk[axis] + 1)].into_iter().flatten() {
+                if neighbor < self.chunks_per_axis {
+                    let mut coord = chunk;
+                    coord[axis] = neighbor;
+                    let index = self.chunk_index(coord[0], coord[1], coord[2]);
+                    self.chunks[index].dirty = true;
+                }
+            }
+        }
+        let index = self.chunk_index(chunk[0], chunk[1], chunk[2]);
+        self.chunks[index].dirty = true;
+    }
+
+    /// Chunks edited since the last call
+    fn take_edited_chunks(&mut self) -> Vec<[usize; 3]> {
+        let mut edited = Vec::new();
+        for chunk in &mut self.chunks {
+            if std::mem::take(&mut chunk.edited) {
+                edited.push(chunk.origin.map(|axis| axis / CHUNK_SIZE));
+            }
+        }
+        edited
+    }
+
+    /// Whether the cell at `p` holds a voxel; anything outside the world is empty
+    fn is_solid(&self, p: [i64; 3]) -> bool {
+        p.iter().all(|&c| c >= 0 && (c as usize) < self.size)
+            && self.voxels[p[0] as usize][p[1] as usize][p[2] as usize].is_some()
+    }
+
+    /// Whether the cell at `p` holds an opaque voxel that casts ambient occlusion.
+    /// Crystals are too thin to shade their neighbours.
+    fn is_opaque(&self, p: [i64; 3]) -> bool {
+        self.is_solid(p) && {
+            let id = self.voxels[p[0] as usize][p[1] as usize][p[2] as usize].unwrap();
+            id != registry::CRYSTAL && !self.registry.is_transparent(id)
+        }
+    }
+
+    /// Occlusion counts for the four corners of the given face of the voxel at
+    /// `p`; see `corner_occlusion`
+    fn face_ao(&self, p: [usize; 3], face: usize) -> [u8; 4] {
+        corner_occlusion(p, face, |q| self.is_opaque(q))
+    }
+
+    /// The topmost filled voxel of the column at (x, z) and its height
+    fn highest_voxel(&self, x: usize, z: usize) -> Option<(usize, VoxelId)> {
+        if x >= self.size || z >= self.size {
+            return None;
+        }
+        (0..self.size).rev().find_map(|y| self.voxels[x][y][z].map(|id| (y, id)))
+    }
+
+    /// The voxel at (x, y, z); anything outside the world is empty
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<VoxelId> {
+        if x >= self.size || y >= self.size || z >= self.size {
+            return None;
+        }
+        self.voxels[x][y][z]
+    }
+
+    /// Casts a ray through the voxel grid using the Amanatides & Woo DDA
+    /// traversal and returns the first filled voxel hit within `max_distance`,
+    /// together with the face the ray entered it through.
+    fn raycast(
+        &self,
+        origin: [f32; 3],
+        direction: [f32; 3],
+        max_distance: f32,
+    ) -> Option<(usize, usize, usize, FaceDirection)> {
+        let length = (direction[0] * direction[0] + direction[1] * direction[1] + direction[2] * direction[2]).sqrt();
+        if length == 0.0 {
+            return None;
+        }
+        let dir = [direction[0] / length, direction[1] / length, direction[2] / length];
+
+        let mut cell = [
+            origin[0].floor() as i64,
+            origin[1].floor() as i64,
+            origin[2].floor() as i64,
+        ];
+        let mut step = [0i64; 3];
+        let mut t_max = [f32::INFINITY; 3];
+        let mut t_delta = [f32::INFINITY; 3];
+        for axis in 0..3 {
+            if dir[axis] > 0.0 {
+                step[axis] = 1;
+                t_max[axis] = (cell[axis] as f32 + 1.0 - origin[axis]) / dir[axis];
+                t_delta[axis] = 1.0 / dir[axis];
+            } else if dir[axis] < 0.0 {
+                step[axis] = -1;
+                t_max[axis] = (origin[axis] - cell[axis] as f32) / -dir[axis];
+                t_delta[axis] = -1.0 / dir[axis];
+            }
+        }
+
+        // If the ray starts inside a voxel, report the face pointing back along the ray
+        let dominant = (0..3)
+            .max_by(|&a, &b| dir[a].abs().partial_cmp(&dir[b].abs()).unwrap())
+            .unwrap();
+        let mut face = FaceDirection::entered_from(dominant, step[dominant]);
+
+        loop {
+            if cell.iter().all(|&c| c >= 0) {
+                let (x, y, z) = (cell[0] as usize, cell[1] as usize, cell[2] as usize);
+                if self.get(x, y, z).is_some() {
+                    return Some((x, y, z, face));
+                }
+            }
+
+            let axis = if t_max[0] < t_max[1] {
+                if t_max[0] < t_max[2] { 0 } else { 2 }
+            } else if t_max[1] < t_max[2] {
+                1
+            } else {
+                2
+            };
+            if t_max[axis] > max_distance {
+                return None;
+            }
+
+            cell[axis] += step[axis];
+            t_max[axis] += t_delta[axis];
+            face = FaceDirection::entered_from(axis, step[axis]);
+        }
+    }
+
+    /// Marks the chunk containing (x, y, z) dirty, plus any neighbouring chunk
+    /// that shares a face with the voxel (its culled faces may now be exposed).
+    fn mark_dirty(&mut self, x: usize, y: usize, z: usize) {
+        let pos = [x, y, z];
+        let chunk = [x / CHUNK_SIZE, y / CHUNK_SIZE, z / CHUNK_SIZE];
+        let index = self.chunk_index(chunk[0], chunk[1], chunk[2]);
+        self.chunks[index].dirty = true;
+
+        for axis in 0..3 {
+            let local = pos[axis] % CHUNK_SIZE;
+            let mut neighbor = chunk;
+            if local == 0 && chunk[axis] > 0 {
+                neighbor[axis] -= 1;
+            } else if local == CHUNK_SIZE - 1 && chunk[axis] + 1 < self.chunks_per_axis {
+                neighbor[axis] += 1;
+            } else {
+                continue;
+            }
+            let index = self.chunk_index(neighbor[0], neighbor[1], neighbor[2]);
+            self.chunks[index].dirty = true;
+        }
+    }
+
+    /// Picks a detail level for every chunk from its distance to the camera and
+    /// marks chunks that crossed a threshold dirty so their buffers get rebuilt.
+    /// Returns the number of chunks that changed level.
+    fn update_chunk_lods(&mut self, camera_position: [f32; 3]) -> usize {
+        let mut changed = 0;
+        for chunk in &mut self.chunks {
+            let center = [
+                chunk.origin[0] as f32 + CHUNK_SIZE as f32 / 2.0,
+                chunk.origin[1] as f32 + CHUNK_SIZE as f32 / 2.0,
+                chunk.origin[2] as f32 + CHUNK_SIZE as f32 / 2.0,
+            ];
+            let distance = ((center[0] - camera_position[0]).powi(2)
+                + (center[1] - camera_position[1]).powi(2)
+                + (center[2] - camera_position[2]).powi(2))
+            .sqrt();
+
+            let lod = lod_for_distance(distance);
+            if lod != chunk.lod {
+                chunk.lod = lod;
+                chunk.dirty = true;
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    /// Re-meshes every dirty chunk and uploads the result, reusing the
+    /// existing GPU buffer when it is large enough. Returns how many chunks
+    /// were rebuilt.
+    fn upload_dirty_chunks(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> usize {
+        let mut rebuilt = 0;
+
+        for index in 0..self.chunks.len() {
+            if !self.chunks[index].dirty {
+                continue;
+            }
+
+            let chunk = &self.chunks[index];
+            let (min, max) = self.chunk_bounds(chunk);
+            let mesh = if chunk.lod == 0 {
+                self.generate_region_layers(min, max)
+            } else {
+                self.generate_lod_layers(chunk.origin, CHUNK_SIZE, chunk.lod)
+            };
+            // From the full-detail voxels, so distant chunks still light their surroundings
+            let emissive_sources = self.emissive_sources(min, max);
+            let chunk = &mut self.chunks[index];
+            chunk.emissive_sources = emissive_sources;
+            let (vertices, indices) = mesh.opaque;
+
+            if indices.is_empty() {
+                chunk.vertex_buffer = None;
+                chunk.index_buffer = None;
+            } else {
+                chunk.vertex_buffer = Some(write_or_create_buffer(
+                    device,
+                    queue,
+                    chunk.vertex_buffer.take(),
+                    bytemuck::cast_slice(&vertices),
+                    wgpu::BufferUsages::VERTEX,
+                    "Chunk Vertex Buffer",
+                ));
+                chunk.index_buffer = Some(write_or_create_buffer(
+                    device,
+                    queue,
+                    chunk.index_buffer.take(),
+                    bytemuck::cast_slice(&indices),
+                    wgpu::BufferUsages::INDEX,
+                    "Chunk Index Buffer",
+                ));
+            }
+            chunk.index_count = indices.len() as u32;
+
+            let (vertices, indices) = &mesh.transparent;
+            if indices.is_empty() {
+                chunk.transparent_vertex_buffer = None;
+                chunk.transparent_index_buffer = None;
+            } else {
+                chunk.transparent_vertex_buffer = Some(write_or_create_buffer(
+                    device,
+                    queue,
+                    chunk.transparent_vertex_buffer.take(),
+                    bytemuck::cast_slice(vertices),
+                    wgpu::BufferUsages::VERTEX,
+                    "Chunk Transparent Vertex Buffer",
+                ));
+                chunk.transparent_index_buffer = Some(write_or_create_buffer(
+                    device,
+                    queue,
+                    chunk.transparent_index_buffer.take(),
+                    bytemuck::cast_slice(indices),
+                    wgpu::BufferUsages::INDEX,
+                    "Chunk Transparent Index Buffer",
+                ));
+            }
+            chunk.transparent_mesh = mesh.transparent;
+            chunk.transparent_order_dirty = false;
+            chunk.transparent_sorted_from = None;
+            chunk.dirty = false;
+            rebuilt += 1;
+        }
+
+        // Push index orders re-sorted by `sort_transparent_faces`
+        for chunk in &mut self.chunks {
+            if chunk.transparent_order_dirty {
+                if let Some(buffer) = &chunk.transparent_index_buffer {
+                    queue.write_buffer(buffer, 0, bytemuck::cast_slice(&chunk.transparent_mesh.1));
+                }
+                chunk.transparent_order_dirty = false;
+            }
+        }
+
+        rebuilt
+    }
+
+    /// Re-sorts chunks' transparent quads back-to-front as seen from
+    /// `camera_pos`, so alpha blending composites them in the right order.
+    /// How often a chunk is sorted depends on its `TransparencyBucket`. The
+    /// new orders are uploaded by the next `upload_dirty_chunks`.
+    fn sort_transparent_faces(&mut self, camera_pos: [f32; 3], settings: &RenderSettings) {
+        for chunk in &mut self.chunks {
+            let (vertices, indices) = &mut chunk.transparent_mesh;
+            if indices.is_empty() {
+                continue;
+            }
+            let min = chunk.origin.map(|c| c as f32);
+            let max = chunk.origin.map(|c| (c + CHUNK_SIZE).min(self.size) as f32);
+            let bucket = TransparencyBucket::for_distance(transparency::distance_to_box(camera_pos, min, max), settings);
+            if !bucket.needs_sort(chunk.transparent_sorted_from, camera_pos, settings) {
+                continue;
+            }
+            if sort_quads_back_to_front(vertices, indices, camera_pos) {
+                chunk.transparent_order_dirty = true;
+            }
+            chunk.transparent_sorted_from = Some(camera_pos);
+        }
+    }
+
+    /// Builds the world mesh with greedy meshing: coplanar exposed faces of the
+    /// same voxel type are merged into the largest rectangles possible before
+    /// being emitted, which cuts the vertex count dramatically on flat terrain.
+    pub fn generate_mesh(&self) -> (Vec<Vertex>, Vec<u32>) {
+        self.generate_region_mesh([0; 3], [self.size; 3])
+    }
+
+    /// Greedy-meshes the voxels in the half-open box `min..max` as a single
+    /// mesh, transparent faces included.
+    fn generate_region_mesh(&self, min: [usize; 3], max: [usize; 3]) -> (Vec<Vertex>, Vec<u32>) {
+        self.generate_region_layers(min, max).merged()
+    }
+
+    /// Greedy-meshes the voxels in the half-open box `min..max`, keeping opaque
+    /// and transparent faces apart for their separate render passes. Faces on
+    /// the region boundary are still culled against voxels outside the region,
+    /// so adjacent regions stitch together without duplicate faces.
+    fn generate_region_layers(&self, min: [usize; 3], max: [usize; 3]) -> RegionMesh {
+        let mut mesh = RegionMesh::default();
+
+        for face in 0..6 {
+            let (axis, u, v) = face_axes(face);
+            let width_u = max[u] - min[u];
+            let width_v = max[v] - min[v];
+            let mut mask: Vec<Option<(VoxelId, [u8; 4])>> = vec![None; width_u * width_v];
+
+            for slice in min[axis]..max[axis] {
+                // Collect the exposed faces of this slice into a 2D mask
+                for j in 0..width_v {
+                    for i in 0..width_u {
+                        let mut p = [0; 3];
+                        p[axis] = slice;
+                        p[u] = min[u] + i;
+                        p[v] = min[v] + j;
+                        mask[j * width_u + i] = self.voxels[p[0]][p[1]][p[2]]
+                            .filter(|&id| id != registry::CRYSTAL && self.is_face_exposed(p[0], p[1], p[2], face))
+                            .map(|id| (id, self.face_ao(p, face)));
+                    }
+                }
+
+                // Greedily grow rectangles of identical voxel types and occlusion,
+                // so merged quads interpolate AO exactly like the faces they replace
+                for j in 0..width_v {
+                    let mut i = 0;
+                    while i < width_u {
+                        let key = match mask[j * width_u + i] {
+                            Some(key) => key,
+                            None => {
+                                i += 1;
+                                continue;
+                            }
+                        };
+
+                        let mut width = 1;
+                        while i + width < width_u && mask[j * width_u + i + width] == Some(key) {
+                            width += 1;
+                        }
+
+                        let mut height = 1;
+                        'grow: while j + height < width_v {
+                            for k in 0..width {
+                                if mask[(j + height) * width_u + i + k] != Some(key) {
+                                    break 'grow;
+                                }
+                            }
+                            height += 1;
+                        }
+
+                        for dj in 0..height {
+                            for di in 0..width {
+                                mask[(j + dj) * width_u + i + di] = None;
+                            }
+                        }
+
+                        let mut pos = [0.0; 3];
+                        pos[axis] = slice as f32;
+                        pos[u] = (min[u] + i) as f32;
+                        pos[v] = (min[v] + j) as f32;
+                        let mut size = [1.0; 3];
+                        size[u] = width as f32;
+                        size[v] = height as f32;
+
+                        let (id, ao) = key;
+                        let (vertices, indices) = if self.registry.is_transparent(id) {
+                            &mut mesh.transparent
+                        } else {
+                            &mut mesh.opaque
+                        };
+                        add_face(vertices, indices, pos, size, self.registry.rgba(id), atlas::tile_for(id), face);
+                        let start = vertices.len() - 4;
+                        apply_face_ao(&mut vertices[start..], face, pos, ao);
+                        i += width;
+                    }
+                }
+            }
+        }
+
+        mesh
+    }
+
+    /// Every voxel in the half-open box `min..max` whose type casts light
+    fn emissive_sources(&self, min: [usize; 3], max: [usize; 3]) -> Vec<EmissiveLightSource> {
+        let mut sources = Vec::new();
+        for x in min[0]..max[0] {
+            for y in min[1]..max[1] {
+                for z in min[2]..max[2] {
+                    let Some(definition) = self.voxels[x][y][z].and_then(|id| self.registry.get(id)) else {
+                        continue;
+                    };
+                    if definition.casts_light() {
+                        sources.push(EmissiveLightSource {
+                            position: [x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5],
+                            color: definition.color,
+                            radius: definition.glow_radius,
+                        });
+                    }
+                }
+            }
+        }
+        sources
+    }
+
+    /// Lights cast by the glowing voxels of every resident chunk
+    fn emissive_lights(&self) -> Vec<PointLight> {
+        self.chunks
+            .iter()
+            .filter(|chunk| chunk.resident)
+            .flat_map(|chunk| chunk.emissive_sources.iter().map(EmissiveLightSource::point_light))
+            .collect()
+    }
+
+    /// One instance per crystal voxel. Crystals are left out of the chunk
+    /// meshes and drawn by `CrystalRenderer` instead.
+    fn generate_crystal_instances(&self) -> Vec<crystal::CrystalInstance> {
+        let mut instances = Vec::new();
+        for x in 0..self.size {
+            for y in 0..self.size {
+                for z in 0..self.size {
+                    if self.voxels[x][y][z] == Some(registry::CRYSTAL) {
+                        instances.push(crystal::CrystalInstance::at([x, y, z]));
+                    }
+                }
+            }
+        }
+        instances
+    }
+
+    /// Reference mesher that emits one quad per exposed voxel face. Kept around
+    /// to measure how much the greedy mesher saves.
+    pub fn generate_mesh_naive(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        for x in 0..self.size {
+            for y in 0..self.size {
+                for z in 0..self.size {
+                    if let Some(id) = self.voxels[x][y][z].filter(|&id| id != registry::CRYSTAL) {
+                        let color = self.registry.rgba(id);
+                        let pos = [x as f32, y as f32, z as f32];
+
+                        for face in 0..6 {
+                            if self.is_face_exposed(x, y, z, face) {
+                                add_face(&mut vertices, &mut indices, pos, [1.0; 3], color, atlas::tile_for(id), face);
+                                let start = vertices.len() - 4;
+                                apply_face_ao(&mut vertices[start..], face, pos, self.face_ao([x, y, z], face));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        (vertices, indices)
+    }
+
+    /// Builds a coarse mesh of the `chunk_size`³ region at `chunk_origin`, where
+    /// each cell covers a block of `2^lod` voxels per axis. A cell is filled when
+    /// at least half of its block is, using the block's most common voxel type,
+    /// so terrain keeps its overall shape while thin details drop out.
+    #[cfg(test)]
+    fn generate_lod_mesh(&self, chunk_origin: [usize; 3], chunk_size: usize, lod: u8) -> (Vec<Vertex>, Vec<u32>) {
+        self.generate_lod_layers(chunk_origin, chunk_size, lod).merged()
+    }
+
+    /// `generate_lod_mesh`, with opaque and transparent faces kept apart
+    fn generate_lod_layers(&self, chunk_origin: [usize; 3], chunk_size: usize, lod: u8) -> RegionMesh {
+        let step = 1usize << lod;
+        let cells = chunk_size.div_ceil(step);
+
+        // Downsample the chunk plus a one-cell margin, so faces against
+        // neighbouring chunks are culled the same way as interior ones.
+        let coarse_size = cells + 2;
+        let mut coarse = vec![vec![vec![None; coarse_size]; coarse_size]; coarse_size];
+        for (cx, plane) in coarse.iter_mut().enumerate() {
+            for (cy, row) in plane.iter_mut().enumerate() {
+                for (cz, cell) in row.iter_mut().enumerate() {
+                    let block_min = [
+                        chunk_origin[0] as i64 + (cx as i64 - 1) * step as i64,
+                        chunk_origin[1] as i64 + (cy as i64 - 1) * step as i64,
+                        chunk_origin[2] as i64 + (cz as i64 - 1) * step as i64,
+                    ];
+                    *cell = self.sample_block(block_min, step);
+                }
+            }
+        }
+        let coarse_world = VoxelWorld::from_voxels(coarse, coarse_size, self.registry.clone());
+
+        let scale = step as f32;
+        let mut mesh = coarse_world.generate_region_layers([1; 3], [cells + 1; 3]);
+        for vertex in mesh.opaque.0.iter_mut().chain(mesh.transparent.0.iter_mut()) {
+            for (position, &origin) in vertex.position.iter_mut().zip(&chunk_origin) {
+                *position = (*position - 1.0) * scale + origin as f32;
+            }
+        }
+        mesh
+    }
+
+    /// The representative voxel of the `step`³ block starting at `min`
+    fn sample_block(&self, min: [i64; 3], step: usize) -> Option<VoxelId> {
+        let mut counts: Vec<(VoxelId, usize)> = Vec::new();
+        let mut filled = 0;
+        for dx in 0..step as i64 {
+            for dy in 0..step as i64 {
+                for dz in 0..step as i64 {
+                    let p = [min[0] + dx, min[1] + dy, min[2] + dz];
+                    if !self.is_solid(p) {
+                        continue;
+                    }
+                    let id = self.voxels[p[0] as usize][p[1] as usize][p[2] as usize].unwrap();
+                    filled += 1;
+                    match counts.iter_mut().find(|(existing, _)| *existing == id) {
+                        Some((_, count)) => *count += 1,
+                        None => counts.push((id, 1)),
+                    }
+                }
+            }
+        }
+
+        if filled * 2 < step.pow(3) {
+            return None;
+        }
+        counts.into_iter().max_by_key(|&(_, count)| count).map(|(id, _)| id)
+    }
+
+    /// Whether the given face of the voxel at (x, y, z) is visible: it borders
+    /// empty space, the edge of the world, a crystal, or a transparent voxel of
+    /// another type. Faces between two voxels of the same transparent type (e.g.
+    /// inside a lake) are hidden.
+    fn is_face_exposed(&self, x: usize, y: usize, z: usize, face: usize) -> bool {
+        let neighbour = match face {
+            // Front face (z+)
+            0 => (z + 1 < self.size).then(|| self.voxels[x][y][z + 1]),
+            // Back face (z-)
+            1 => (z > 0).then(|| self.voxels[x][y][z - 1]),
+            // Right face (x+)
+            2 => (x + 1 < self.size).then(|| self.voxels[x + 1][y][z]),
+            // Left face (x-)
+            3 => (x > 0).then(|| self.voxels[x - 1][y][z]),
+            // Top face (y+)
+            4 => (y + 1 < self.size).then(|| self.voxels[x][y + 1][z]),
+            // Bottom face (y-)
+            _ => (y > 0).then(|| self.voxels[x][y - 1][z]),
+        };
+
+        face_exposed_to(&self.registry, self.voxels[x][y][z], neighbour.flatten())
+    }
+}
+
+/// Whether a face of `voxel` shows against the `neighbour` in front of it;
+/// see `VoxelWorld::is_face_exposed`
+fn face_exposed_to(registry: &VoxelRegistry, voxel: Option<VoxelId>, neighbour: Option<VoxelId>) -> bool {
+    match neighbour {
+        None => true,
+        // Crystals are thin prisms drawn by `CrystalRenderer`, not full cubes
+        Some(registry::CRYSTAL) => true,
+        Some(id) => registry.is_transparent(id) && Some(id) != voxel,
+    }
+}
+
+/// Mesh output split by render pass
+#[derive(Default)]
+struct RegionMesh {
+    opaque: (Vec<Vertex>, Vec<u32>),
+    transparent: (Vec<Vertex>, Vec<u32>),
+}
+
+impl RegionMesh {
+    /// Both layers as one mesh, transparent faces last
+    fn merged(self) -> (Vec<Vertex>, Vec<u32>) {
+        let (mut vertices, mut indices) = self.opaque;
+        let base = vertices.len() as u32;
+        vertices.extend(self.transparent.0);
+        indices.extend(self.transparent.1.into_iter().map(|i| i + base));
+        (vertices, indices)
+    }
+}
+
+/// Reorders the quads (6 indices each) of an indexed mesh from farthest to
+/// nearest. Uses a two-pass LSD radix sort over the top 16 bits of each
+/// quad's squared distance: exact enough for blending order and linear in the
+/// quad count. Returns whether the order changed.
+fn sort_quads_back_to_front(vertices: &[Vertex], indices: &mut [u32], camera_pos: [f32; 3]) -> bool {
+    let quads = indices.len() / 6;
+    let mut keys: Vec<(u16, usize)> = (0..quads)
+        .map(|q| {
+            let corners = [indices[q * 6], indices[q * 6 + 1], indices[q * 6 + 2], indices[q * 6 + 5]];
+            let mut distance = 0.0;
+            for (axis, eye) in camera_pos.iter().enumerate() {
+                let center = corners.iter().map(|&i| vertices[i as usize].position[axis]).sum::<f32>() / 4.0;
+                distance += (center - eye).powi(2);
+            }
+            // Non-negative floats order the same as their bit patterns; invert
+            // so ascending keys put the farthest quads first.
+            (!((distance.to_bits() >> 16) as u16), q)
+        })
+        .collect();
+
+    let mut scratch = keys.clone();
+    for shift in [0, 8] {
+        let mut offsets = [0usize; 257];
+        for &(key, _) in &keys {
+            offsets[((key >> shift) & 0xFF) as usize + 1] += 1;
+        }
+        for bucket in 0..256 {
+            offsets[bucket + 1] += offsets[bucket];
+        }
+        for &entry in &keys {
+            let bucket = ((entry.0 >> shift) & 0xFF) as usize;
+            scratch[offsets[bucket]] = entry;
+            offsets[bucket] += 1;
+        }
+        std::mem::swap(&mut keys, &mut scratch);
+    }
+
+    if keys.iter().enumerate().all(|(position, &(_, q))| position == q) {
+        return false;
+    }
+    let original = indices.to_vec();
+    for (position, &(_, q)) in keys.iter().enumerate() {
+        indices[position * 6..position * 6 + 6].copy_from_slice(&original[q * 6..q * 6 + 6]);
+    }
+    true
+}
+
+/// Corner offsets of each face within its quad, in `add_face` order. The
+/// compute mesher's `corners` table matches this.
+const FACE_CORNERS: [[[f32; 3]; 4]; 6] = [
+    [[0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 1.0], [0.0, 1.0, 1.0]], // Front (z+)
+    [[0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0], [1.0, 0.0, 0.0]], // Back (z-)
+    [[1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [1.0, 1.0, 1.0], [1.0, 0.0, 1.0]], // Right (x+)
+    [[0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 1.0], [0.0, 1.0, 0.0]], // Left (x-)
+    [[0.0, 1.0, 0.0], [0.0, 1.0, 1.0], [1.0, 1.0, 1.0], [1.0, 1.0, 0.0]], // Top (y+)
+    [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 0.0, 1.0], [0.0, 0.0, 1.0]], // Bottom (y-)
+];
+
+const FACE_NORMALS: [[f32; 3]; 6] = [
+    [0.0, 0.0, 1.0],
+    [0.0, 0.0, -1.0],
+    [1.0, 0.0, 0.0],
+    [-1.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0],
+    [0.0, -1.0, 0.0],
+];
+
+/// Tile-space texture coordinates of the corner at `offset` within a quad
+/// `size` voxels across. Side faces are upright and none are mirrored when
+/// seen from outside; the top face has -z at the top of the tile and the
+/// bottom face +z.
+fn face_uv(face: usize, offset: [f32; 3], size: [f32; 3]) -> [f32; 2] {
+    let [x, y, z] = offset;
+    let [w, h, d] = size;
+    match face {
+        0 => [x, h - y],
+        1 => [w - x, h - y],
+        2 => [d - z, h - y],
+        3 => [z, h - y],
+        4 => [x, z],
+        _ => [x, d - z],
+    }
+}
+
+/// Appends the four corners of one quad plus the six indices of its two
+/// triangles (0, 1, 2) and (0, 2, 3). The quad repeats atlas `tile` once per
+/// voxel it spans.
+fn add_face(
+    vertices: &mut Vec<Vertex>,
+    indices: &mut Vec<u32>,
+    pos: [f32; 3],
+    size: [f32; 3],
+    color: [f32; 4],
+    tile: u32,
+    face: usize,
+) {
+    let base = vertices.len() as u32;
+    for corner in FACE_CORNERS[face] {
+        let offset = [corner[0] * size[0], corner[1] * size[1], corner[2] * size[2]];
+        vertices.push(Vertex {
+            position: [pos[0] + offset[0], pos[1] + offset[1], pos[2] + offset[2]],
+            normal: FACE_NORMALS[face],
+            color,
+            ao: 0.0,
+            uv: face_uv(face, offset, size),
+            tile,
+        });
+    }
+    indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+}
+
+/// The axis a face's normal runs along, followed by the two axes spanning the
+/// face plane.
+fn face_axes(face: usize) -> (usize, usize, usize) {
+    match face {
+        0 | 1 => (2, 0, 1),
+        2 | 3 => (0, 2, 1),
+        _ => (1, 0, 2),
+    }
+}
+
+/// Occlusion counts (0-3) for the four corners of the given face of the voxel
+/// at `p`, ordered (-u,-v), (+u,-v), (+u,+v), (-u,+v) in the face plane. Each
+/// corner looks at the two edge neighbours and the diagonal neighbour in the
+/// layer in front of the face, as reported by `is_opaque`; two opaque edge
+/// neighbours fully occlude it.
+fn corner_occlusion(p: [usize; 3], face: usize, is_opaque: impl Fn([i64; 3]) -> bool) -> [u8; 4] {
+    let (axis, u, v) = face_axes(face);
+    let mut front = [p[0] as i64, p[1] as i64, p[2] as i64];
+    front[axis] += if face.is_multiple_of(2) { 1 } else { -1 };
+
+    let mut ao = [0; 4];
+    for (corner, (su, sv)) in [(-1, -1), (1, -1), (1, 1), (-1, 1)].into_iter().enumerate() {
+        let mut side1 = front;
+        side1[u] += su;
+        let mut side2 = front;
+        side2[v] += sv;
+        let mut diagonal = side1;
+        diagonal[v] += sv;
+
+        let (side1, side2) = (is_opaque(side1), is_opaque(side2));
+        ao[corner] = if side1 && side2 {
+            3
+        } else {
+            side1 as u8 + side2 as u8 + is_opaque(diagonal) as u8
+        };
+    }
+    ao
+}
+
+/// Writes per-corner occlusion from `face_ao` onto the four vertices `add_face`
+/// emitted for a quad whose minimum corner is `pos`.
+fn apply_face_ao(vertices: &mut [Vertex], face: usize, pos: [f32; 3], ao: [u8; 4]) {
+    let (_, u, v) = face_axes(face);
+    for vertex in vertices {
+        let high_u = vertex.position[u] > pos[u];
+        let high_v = vertex.position[v] > pos[v];
+        let corner = match (high_u, high_v) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (true, true) => 2,
+            (false, true) => 3,
+        };
+        vertex.ao = ao[corner] as f32 / 3.0;
+    }
+}
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Creates a depth texture matching the given surface dimensions, along with
+/// the view used as the render pass depth attachment.
+fn create_depth_texture(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        label: Some("Depth Texture"),
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+/// Writes `bytes` into `existing` when it is large enough, otherwise allocates
+/// a new buffer. `usage` gets COPY_DST added so the buffer can be reused.
+fn write_or_create_buffer(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    existing: Option<wgpu::Buffer>,
+    bytes: &[u8],
+    usage: wgpu::BufferUsages,
+    label: &str,
+) -> wgpu::Buffer {
+    match existing {
+        Some(buffer) if buffer.size() >= bytes.len() as u64 => {
+            queue.write_buffer(&buffer, 0, bytes);
+            buffer
+        }
+        _ => device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: bytes,
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+        }),
+    }
+}
+
+/// A cube slightly larger than the voxel at `pos`, drawn as a wireframe to
+/// highlight the voxel under the crosshair.
+fn highlight_mesh(pos: [usize; 3]) -> (Vec<Vertex>, Vec<u32>) {
+    outline_mesh(pos, [1.0, 1.0, 0.3, 1.0])
+}
+
+/// Red outlines around every voxel `report` found unstable, for the
+/// structural debug view
+fn unstable_outline_mesh(report: &StructuralReport) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for &(x, y, z) in &report.unstable_voxels {
+        let (outline_vertices, outline_indices) = outline_mesh([x, y, z], [1.0, 0.1, 0.1, 1.0]);
+        let base = vertices.len() as u32;
+        vertices.extend(outline_vertices);
+        indices.extend(outline_indices.into_iter().map(|i| i + base));
+    }
+    (vertices, indices)
+}
+
+/// An arrow from each annotation's label to the point it has one for, as
+/// thin triangles the wireframe pipeline draws as lines
+fn annotation_arrow_mesh<'a>(annotations: impl Iterator<Item = &'a WorldAnnotation>) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut line = |from: [f32; 3], to: [f32; 3], side: [f32; 3], color: [f32; 4]| {
+        // Nudged sideways so the triangle has area and isn't culled
+        let nudged = [0, 1, 2].map(|axis| to[axis] + side[axis] * 0.01);
+        let base = vertices.len() as u32;
+        for position in [from, to, nudged] {
+            vertices.push(Vertex {
+                position,
+                normal: [0.0, 1.0, 0.0],
+                color,
+                ao: 0.0,
+                uv: [0.0; 2],
+                tile: atlas::BLANK_TILE,
+            });
+        }
+        indices.extend([base, base + 1, base + 2]);
+    };
+
+    for annotation in annotations {
+        let Some(tip) = annotation.arrow_target else {
+            continue;
+        };
+        let from = annotation.world_position;
+        let back = [0, 1, 2].map(|axis| from[axis] - tip[axis]);
+        let length = back.iter().map(|c| c * c).sum::<f32>().sqrt();
+        if length < f32::EPSILON {
+            continue;
+        }
+        let back = back.map(|c| c / length);
+        // Horizontal and across the shaft, unless the shaft is vertical
+        let side = if back[0].abs() + back[2].abs() < 1e-3 {
+            [1.0, 0.0, 0.0]
+        } else {
+            let across = [back[2], 0.0, -back[0]];
+            let norm = (across[0] * across[0] + across[2] * across[2]).sqrt();
+            across.map(|c| c / norm)
+        };
+
+        line(from, tip, side, annotation.color);
+        for sign in [1.0, -1.0] {
+            let barb = [0, 1, 2].map(|axis| tip[axis] + (back[axis] + side[axis] * sign * 0.5) * ARROW_HEAD_LENGTH);
+            line(barb, tip, side, annotation.color);
+        }
+    }
+    (vertices, indices)
+}
+
+/// A `color` cube slightly larger than the voxel at `pos`, for drawing with
+/// the wireframe pipeline
+fn outline_mesh(pos: [usize; 3], color: [f32; 4]) -> (Vec<Vertex>, Vec<u32>) {
+    const EXPAND: f32 = 0.02;
+    let origin = [
+        pos[0] as f32 - EXPAND,
+        pos[1] as f32 - EXPAND,
+        pos[2] as f32 - EXPAND,
+    ];
+    let size = [1.0 + 2.0 * EXPAND; 3];
+
+    let mut vertices = Vec::with_capacity(24);
+    let mut indices = Vec::with_capacity(36);
+    for face in 0..6 {
+        add_face(&mut vertices, &mut indices, origin, size, color, atlas::BLANK_TILE, face);
+    }
+    (vertices, indices)
+}
+
+// Camera controller
+struct Camera {
+    position: [f32; 3],
+    yaw: f32,
+    pitch: f32,
+}
+
+impl Camera {
+    fn new() -> Self {
+        Self {
+            position: [15.0, 10.0, 15.0],
+            yaw: -45.0_f32.to_radians(),
+            pitch: -20.0_f32.to_radians(),
+        }
+    }
+
+    /// Applies a mouse movement in pixels. Pitch is clamped short of straight
+    /// up/down so the view never flips over.
+    fn rotate(&mut self, delta: (f64, f64), sensitivity: f32) {
+        self.yaw += delta.0 as f32 * sensitivity;
+        self.pitch = (self.pitch - delta.1 as f32 * sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Unit vector the camera looks along (the negated view-space z axis)
+    fn forward(&self) -> [f32; 3] {
+        [
+            -self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            -self.yaw.cos() * self.pitch.cos(),
+        ]
+    }
+
+    /// Unit vector to the camera's right (the view-space x axis). It's always
+    /// level, since the camera never rolls.
+    fn right(&self) -> [f32; 3] {
+        [self.yaw.cos(), 0.0, -self.yaw.sin()]
+    }
+
+    /// `forward` flattened onto the ground, for walking
+    fn level_forward(&self) -> [f32; 3] {
+        [-self.yaw.sin(), 0.0, -self.yaw.cos()]
+    }
+
+    /// Moves along the ground in the direction the camera faces, so looking
+    /// up or down doesn't change height
+    fn move_forward(&mut self, distance: f32) {
+        let direction = self.level_forward();
+        for (position, step) in self.position.iter_mut().zip(direction) {
+            *position += step * distance;
+        }
+    }
+
+    fn move_right(&mut self, distance: f32) {
+        let direction = self.right();
+        for (position, step) in self.position.iter_mut().zip(direction) {
+            *position += step * distance;
+        }
+    }
+
+    fn move_up(&mut self, distance: f32) {
+        self.position[1] += distance;
+    }
+
+    fn view_matrix(&self) -> [[f32; 4]; 4] {
+        let cos_pitch = self.pitch.cos();
+        let sin_pitch = self.pitch.sin();
+        let cos_yaw = self.yaw.cos();
+        let sin_yaw = self.yaw.sin();
+
+        let xaxis = [cos_yaw, 0.0, -sin_yaw];
+        let yaxis = [sin_yaw * sin_pitch, cos_pitch, cos_yaw * sin_pitch];
+        let zaxis = [sin_yaw * cos_pitch, -sin_pitch, cos_yaw * cos_pitch];
+
+        let x = -(xaxis[0] * self.position[0] + xaxis[1] * self.position[1] + xaxis[2] * self.position[2]);
+        let y = -(yaxis[0] * self.position[0] + yaxis[1] * self.position[1] + yaxis[2] * self.position[2]);
+        let z = -(zaxis[0] * self.position[0] + zaxis[1] * self.position[1] + zaxis[2] * self.position[2]);
+
+        [
+            [xaxis[0], yaxis[0], zaxis[0], 0.0],
+            [xaxis[1], yaxis[1], zaxis[1], 0.0],
+            [xaxis[2], yaxis[2], zaxis[2], 0.0],
+            [x, y, z, 1.0],
+        ]
+    }
+}
+
+fn projection_matrix(aspect_ratio: f32) -> [[f32; 4]; 4] {
+    let fov = 60.0_f32.to_radians();
+    let near = 0.1;
+    let far = 1000.0;
+
+    let f = 1.0 / (fov / 2.0).tan();
+
+    [
+        [f / aspect_ratio, 0.0, 0.0, 0.0],
+        [0.0, f, 0.0, 0.0],
+        [0.0, 0.0, (far + near) / (near - far), -1.0],
+        [0.0, 0.0, (2.0 * far * near) / (near - far), 0.0],
+    ]
+}
+
+/// Computes `a * b` for column-major matrices (`m[column][row]`), matching
+/// the layout WGSL expects for `mat4x4<f32>`.
+fn multiply_matrices(a: [[f32; 4]; 4], b: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    let mut result = [[0.0; 4]; 4];
+    for col in 0..4 {
+        for row in 0..4 {
+            for k in 0..4 {
+                result[col][row] += a[k][row] * b[col][k];
+            }
+        }
+    }
+    result
+}
+
+/// Inverse of a column-major matrix by cofactor expansion. Singular matrices
+/// (which a valid view-projection never is) return all zeros.
+fn invert_matrix(m: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    // 3x3 determinant of `m` without the given column and row
+    let minor = |skip_col: usize, skip_row: usize| {
+        let cols: Vec<usize> = (0..4).filter(|&c| c != skip_col).collect();
+        let rows: Vec<usize> = (0..4).filter(|&r| r != skip_row).collect();
+        let e = |c: usize, r: usize| m[cols[c]][rows[r]];
+        e(0, 0) * (e(1, 1) * e(2, 2) - e(2, 1) * e(1, 2)) - e(1, 0) * (e(0, 1) * e(2, 2) - e(2, 1) * e(0, 2))
+            + e(2, 0) * (e(0, 1) * e(1, 2) - e(1, 1) * e(0, 2))
+    };
+    let sign = |col: usize, row: usize| if (col + row).is_multiple_of(2) { 1.0 } else { -1.0 };
+
+    let determinant: f32 = (0..4).map(|col| m[col][0] * sign(col, 0) * minor(col, 0)).sum();
+    if determinant.abs() < f32::EPSILON {
+        return [[0.0; 4]; 4];
+    }
+
+    // The inverse is the transposed cofactor matrix over the determinant
+    let mut result = [[0.0; 4]; 4];
+    for (col, column) in result.iter_mut().enumerate() {
+        for (row, value) in column.iter_mut().enumerate() {
+            *value = sign(row, col) * minor(row, col) / determinant;
+        }
+    }
+    result
+}
+
+/// The six clip planes of a view-projection matrix, each stored as
+/// `[a, b, c, d]` with the normal pointing into the visible volume.
+#[derive(Clone, Copy, Debug)]
+struct Frustum {
+    planes: [[f32; 4]; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes from a column-major view-projection matrix using the
+    /// Gribb/Hartmann method, with wgpu's 0..1 clip-space depth range.
+    fn from_view_proj(mat: [[f32; 4]; 4]) -> Frustum {
+        let row = |r: usize| [mat[0][r], mat[1][r], mat[2][r], mat[3][r]];
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+        let add = |a: [f32; 4], b: [f32; 4]| [a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]];
+        let sub = |a: [f32; 4], b: [f32; 4]| [a[0] - b[0], a[1] - b[1], a[2] - b[2], a[3] - b[3]];
+
+        let mut planes = [
+            add(r3, r0), // left
+            sub(r3, r0), // right
+            add(r3, r1), // bottom
+            sub(r3, r1), // top
+            r2,          // near
+            sub(r3, r2), // far
+        ];
+        for plane in &mut planes {
+            let length = (plane[0] * plane[0] + plane[1] * plane[1] + plane[2] * plane[2]).sqrt();
+            if length > 0.0 {
+                for value in plane.iter_mut() {
+                    *value /= length;
+                }
+            }
+        }
+
+        Frustum { planes }
+    }
+
+    /// Whether any part of the axis-aligned box lies inside the frustum. Boxes
+    /// straddling a corner outside several planes may be reported as visible,
+    /// which is fine for culling.
+    fn contains_aabb(&self, min: [f32; 3], max: [f32; 3]) -> bool {
+        self.planes.iter().all(|plane| {
+            // The box corner furthest along the plane normal
+            let x = if plane[0] >= 0.0 { max[0] } else { min[0] };
+            let y = if plane[1] >= 0.0 { max[1] } else { min[1] };
+            let z = if plane[2] >= 0.0 { max[2] } else { min[2] };
+            plane[0] * x + plane[1] * y + plane[2] * z + plane[3] >= 0.0
+        })
+    }
+}
+
+/// Render graph passes drawing the voxel chunks and the outlines over them
+const OPAQUE_PASS: &str = "opaque";
+const TRANSPARENT_PASS: &str = "transparent";
+const OUTLINE_PASS: &str = "outlines";
+
+/// The pipelines drawing voxel chunks and the highlight, which share the voxel
+/// shader and are rebuilt together when it's reloaded
+struct VoxelPipelines {
+    opaque: wgpu::RenderPipeline,
+    transparent: wgpu::RenderPipeline,
+    /// Wireframe pipeline for the targeted-voxel highlight, if the adapter
+    /// supports line polygon mode
+    highlight: Option<wgpu::RenderPipeline>,
+}
+
+impl VoxelPipelines {
+    /// Adds the passes drawing the resident chunks the camera can see: their
+    /// opaque meshes over the sky once the shadow map is drawn, then their
+    /// transparent meshes over those and the crystals
+    fn register(pipelines: &Rc<RefCell<Self>>, graph: &mut RenderGraph) -> RobinResult<()> {
+        let opaque = pipelines.clone();
+        graph.add_node_after(OPAQUE_PASS, &[sky::PASS, lighting::SHADOW_PASS], move |encoder, context| {
+            let pipelines = opaque.borrow();
+            let mut render_pass = context.begin_pass(encoder, "Opaque Pass");
+            render_pass.set_bind_group(0, context.bind_group, &[]);
+            render_pass.set_pipeline(&pipelines.opaque);
+            for chunk in visible_chunks(context) {
+                if let (Some(vertex_buffer), Some(index_buffer)) = (&chunk.vertex_buffer, &chunk.index_buffer) {
+                    render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                    render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                    render_pass.draw_indexed(0..chunk.index_count, 0, 0..1);
+                }
+            }
+            Ok(())
+        })?;
+
+        let transparent = pipelines.clone();
+        graph.add_node_after(TRANSPARENT_PASS, &[OPAQUE_PASS, crystal::PASS], move |encoder, context| {
+            // Farthest chunk first; each chunk's faces are already sorted
+            // back-to-front
+            let mut transparent_chunks: Vec<(f32, &Chunk)> = visible_chunks(context)
+                .filter(|chunk| chunk.transparent_index_buffer.is_some())
+                .map(|chunk| {
+                    let (min, max) = context.world.chunk_bounds(chunk);
+                    let distance: f32 = (0..3)
+                        .map(|axis| ((min[axis] + max[axis]) as f32 / 2.0 - context.camera_position[axis]).powi(2))
+                        .sum();
+                    (distance, chunk)
+                })
+                .collect();
+            transparent_chunks.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+            let pipelines = transparent.borrow();
+            let mut render_pass = context.begin_pass(encoder, "Transparent Pass");
+            render_pass.set_bind_group(0, context.bind_group, &[]);
+            render_pass.set_pipeline(&pipelines.transparent);
+            for (_, chunk) in transparent_chunks {
+                if let (Some(vertex_buffer), Some(index_buffer)) =
+                    (&chunk.transparent_vertex_buffer, &chunk.transparent_index_buffer)
+                {
+                    render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                    render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                    render_pass.draw_indexed(0..chunk.transparent_mesh.1.len() as u32, 0, 0..1);
+                }
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+}
+
+/// Resident chunks of the context's world inside the camera's frustum
+fn visible_chunks<'a>(context: &RenderContext<'a>) -> impl Iterator<Item = &'a Chunk> {
+    let world = context.world;
+    let frustum = Frustum::from_view_proj(context.view_proj);
+    world.chunks.iter().filter(move |chunk| {
+        if !chunk.resident {
+            return false;
+        }
+        let (min, max) = world.chunk_bounds(chunk);
+        frustum.contains_aabb(min.map(|c| c as f32), max.map(|c| c as f32))
+    })
+}
+
+/// A mesh of outlines drawn with the highlight pipeline, whose buffers are
+/// reused while they're big enough
+#[derive(Default)]
+struct OutlineMesh {
+    vertex_buffer: Option<wgpu::Buffer>,
+    index_buffer: Option<wgpu::Buffer>,
+    index_count: u32,
+}
+
+impl OutlineMesh {
+    /// Replaces the mesh; an empty one draws nothing
+    fn set(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, (vertices, indices): (Vec<Vertex>, Vec<u32>), label: &str) {
+        self.index_count = indices.len() as u32;
+        if indices.is_empty() {
+            return;
+        }
+        self.vertex_buffer = Some(write_or_create_buffer(
+            device,
+            queue,
+            self.vertex_buffer.take(),
+            bytemuck::cast_slice(&vertices),
+            wgpu::BufferUsages::VERTEX,
+            &format!("{} Vertex Buffer", label),
+        ));
+        self.index_buffer = Some(write_or_create_buffer(
+            device,
+            queue,
+            self.index_buffer.take(),
+            bytemuck::cast_slice(&indices),
+            wgpu::BufferUsages::INDEX,
+            &format!("{} Index Buffer", label),
+        ));
+    }
+
+    fn clear(&mut self) {
+        self.index_count = 0;
+    }
+
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if let (Some(vertex_buffer), Some(index_buffer)) = (&self.vertex_buffer, &self.index_buffer) {
+            if self.index_count > 0 {
+                render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+            }
+        }
+    }
+}
+
+/// The targeted-voxel highlight, the structural debug view's unstable voxels
+/// and annotation arrows, all drawn with the voxel pipelines' highlight
+/// pipeline. Nothing is drawn without line polygon mode.
+struct OutlineRenderer {
+    pipelines: Rc<RefCell<VoxelPipelines>>,
+    highlight: OutlineMesh,
+    structural: OutlineMesh,
+    arrows: OutlineMesh,
+}
+
+impl OutlineRenderer {
+    fn new(pipelines: Rc<RefCell<VoxelPipelines>>) -> Self {
+        Self {
+            pipelines,
+            highlight: OutlineMesh::default(),
+            structural: OutlineMesh::default(),
+            arrows: OutlineMesh::default(),
+        }
+    }
+
+    /// Adds the pass drawing the outlines over the world
+    fn register(renderer: &Rc<RefCell<Self>>, graph: &mut RenderGraph) -> RobinResult<usize> {
+        let renderer = renderer.clone();
+        graph.add_node_after(OUTLINE_PASS, &[TRANSPARENT_PASS], move |encoder, context| {
+            let renderer = renderer.borrow();
+            let pipelines = renderer.pipelines.borrow();
+            let Some(highlight_pipeline) = &pipelines.highlight else {
+                return Ok(());
+            };
+            let mut render_pass = context.begin_pass(encoder, "Outline Pass");
+            render_pass.set_bind_group(0, context.bind_group, &[]);
+            render_pass.set_pipeline(highlight_pipeline);
+            for mesh in [&renderer.highlight, &renderer.structural, &renderer.arrows] {
+                mesh.draw(&mut render_pass);
+            }
+            Ok(())
+        })
+    }
+}
+
+fn create_voxel_pipelines(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    wireframe: bool,
+) -> VoxelPipelines {
+    let opaque = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Render Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[Vertex::desc()],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            compilation_options: Default::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    });
+
+    // Blended pipeline for transparent voxels such as water. Depth is tested
+    // against the opaque scene but not written, and faces are drawn
+    // back-to-front so blending composites correctly. Both sides are drawn so
+    // the surface stays visible from underwater.
+    let transparent = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Transparent Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[Vertex::desc()],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            compilation_options: Default::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    });
+
+    let highlight = if wireframe {
+        Some(device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Highlight Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Line,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        }))
+    } else {
+        None
+    };
+
+    VoxelPipelines {
+        opaque,
+        transparent,
+        highlight,
+    }
+}
+
+pub async fn run() {
+    // Create window
+    let event_loop = EventLoop::new().unwrap();
+    // Shared with the surface, which must not outlive it
+    let window = Arc::new(
+        WindowBuilder::new()
+            .with_title("Robin Voxel Engine - Interactive 3D Demo")
+            .with_inner_size(winit::dpi::LogicalSize::new(1280, 720))
+            .build(&event_loop)
+            .unwrap(),
+    );
+
+    // Create WGPU instance
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::PRIMARY,
+        dx12_shader_compiler: Default::default(),
+        flags: wgpu::InstanceFlags::default(),
+        gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
+    });
+
+    let surface = instance.create_surface(window.clone()).unwrap();
+
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            compatible_surface: Some(&surface),
+        })
+        .await
+        .unwrap();
+
+    // Wireframe highlighting needs line polygon mode, which not every adapter has
+    let supports_wireframe = adapter.features().contains(wgpu::Features::POLYGON_MODE_LINE);
+    // Timestamp queries let the demo report GPU frame times and how long
+    // crystals take to draw; without them it falls back to CPU frame times
+    let timer_features = adapter.features() & gpu_timer::TIMER_FEATURES;
+
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("Device"),
+                required_features: if supports_wireframe {
+                    wgpu::Features::POLYGON_MODE_LINE
+                } else {
+                    wgpu::Features::empty()
+                } | timer_features,
+                required_limits: wgpu::Limits::default(),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+    // Shared with the thread that recompiles the voxel shader when it changes
+    let device = Arc::new(device);
+
+    let size = window.inner_size();
+    // Frames are copied back for save slot thumbnails where the surface allows it
+    let can_capture_frames = surface.get_capabilities(&adapter).usages.contains(wgpu::TextureUsages::COPY_SRC);
+    let surface_usage = if can_capture_frames {
+        wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC
+    } else {
+        wgpu::TextureUsages::RENDER_ATTACHMENT
+    };
+    let surface_config = wgpu::SurfaceConfiguration {
+        usage: surface_usage,
+        format: surface.get_capabilities(&adapter).formats[0],
+        width: size.width,
+        height: size.height,
+        present_mode: wgpu::PresentMode::AutoVsync,
+        alpha_mode: wgpu::CompositeAlphaMode::Auto,
+        view_formats: vec![],
+        desired_maximum_frame_latency: 2,
+    };
+    surface.configure(&device, &surface_config);
+    let (mut _depth_texture, mut depth_view) = create_depth_texture(&device, size.width, size.height);
+
+    // Create shader. The WGSL file is watched while the demo runs, so edits
+    // to it take effect without a rebuild.
+    let shader_prelude = atlas::ATLAS_WGSL.to_string() + lighting::LIGHTING_WGSL + fog::FOG_WGSL;
+    let shader_source = shader_prelude.clone() + include_str!("shaders/voxel.wgsl");
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Voxel Shader"),
+        source: wgpu::ShaderSource::Wgsl(shader_source.into()),
+    });
+
+    // Create voxel world and mesh
+    println!("Generating voxel world...");
+    let save_path = Path::new(WORLD_SAVE_PATH);
+    // The seed is only known for a freshly generated world
+    let (mut world, world_seed) = if save_path.exists() {
+        match VoxelWorld::load(save_path) {
+            Ok(world) => {
+                println!("Loaded saved world from {}", WORLD_SAVE_PATH);
+                (world, None)
+            }
+            Err(e) => {
+                println!("⚠️  Could not load {} ({}), generating a new world", WORLD_SAVE_PATH, e);
+                (VoxelWorld::new(32), Some(DEFAULT_TERRAIN_SEED))
+            }
+        }
+    } else {
+        (VoxelWorld::new(32), Some(DEFAULT_TERRAIN_SEED))
+    };
+
+    // Plugins add their voxel types before anything is built from the registry
+    let mut plugins = PluginManager::new();
+    plugins.load_directory(Path::new(PLUGIN_DIRECTORY));
+    plugins.register_voxels(world.registry_mut());
+
+    // Upload each chunk into its own vertex and index buffers
+    let chunk_count = world.upload_dirty_chunks(&device, &queue);
+    println!("Uploaded {} chunks of {}³ voxels", chunk_count, CHUNK_SIZE);
+
+    // Create uniform buffer
+    let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Uniform Buffer"),
+        size: std::mem::size_of::<Uniforms>() as u64,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    // The sun and up to seven point lights, with the sun's shadow map
+    let light_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Light Buffer"),
+        size: std::mem::size_of::<LightBuffer>() as u64,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let shadow_map = Rc::new(lighting::ShadowMap::new(&device));
+
+    // Voxel textures, with a checkerboard for any type lacking an image
+    let texture_atlas = atlas::TextureAtlas::for_registry(&world.registry, Path::new(TEXTURE_DIRECTORY));
+    let (atlas_view, atlas_sampler) = texture_atlas.upload(&device, &queue);
+
+    // Create bind group layout
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Bind Group Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 5,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                count: None,
+            },
+        ],
+    });
+
+    // Create bind group
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Bind Group"),
+        layout: &bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&atlas_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(&atlas_sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: light_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::TextureView(shadow_map.view()),
+            },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: wgpu::BindingResource::Sampler(shadow_map.sampler()),
+            },
+        ],
+    });
+
+    // Create pipeline
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    // Renderers are shared with the render graph passes that draw them
+    let skybox = Rc::new(sky::Skybox::new(&device, &pipeline_layout, surface_config.format));
+
+    let crystals = Rc::new(RefCell::new(crystal::CrystalRenderer::new(
+        &device,
+        &pipeline_layout,
+        surface_config.format,
+        world.registry.color(registry::CRYSTAL),
+    )));
+    let crystal_instances = world.generate_crystal_instances();
+    crystals.borrow_mut().upload_instances(&device, &queue, &crystal_instances);
+    let mut point_lights = crystal_lights(&crystal_instances, world.registry.color(registry::CRYSTAL));
+    point_lights.extend(world.emissive_lights());
+    println!("Placed {} crystals", crystals.borrow().instance_count());
+    let wireframes = Rc::new(RefCell::new(wireframe::WireframeRenderer::new(
+        &device,
+        &pipeline_layout,
+        surface_config.format,
+    )));
+    if timer_features.contains(wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES) {
+        crystals.borrow_mut().timer = gpu_timer::GpuTimer::new(&device, &queue);
+    }
+    let mut frame_timer = gpu_timer::GpuTimer::new(&device, &queue);
+
+    let minimap = Rc::new(RefCell::new(minimap::MinimapRenderer::new(&device, surface_config.format)));
+    let hand = Rc::new(RefCell::new(hand::HandRenderer::new(&device, surface_config.format)));
+    let particles = Rc::new(RefCell::new(particles::ParticleSystem::new(&device, surface_config.format)));
+    let slot_overlay = Rc::new(RefCell::new(save_slots::SlotPreviewOverlay::new(&device, surface_config.format)));
+    let console_renderer = Rc::new(RefCell::new(console::ConsoleRenderer::new(&device, &queue, surface_config.format)));
+    let text_renderer = Rc::new(RefCell::new(text::TextRenderer::new(
+        device.clone(),
+        &queue,
+        surface_config.format,
+        size.width,
+        size.height,
+    )));
+    let mut hud_fps = 0.0f32;
+    minimap.borrow().update_world(&queue, &world);
+    let mut last_timing_report = Instant::now();
+
+    let voxel_pipelines = Rc::new(RefCell::new(create_voxel_pipelines(
+        &device,
+        &pipeline_layout,
+        &shader,
+        surface_config.format,
+        supports_wireframe,
+    )));
+    if !supports_wireframe {
+        println!("⚠️  Adapter lacks POLYGON_MODE_LINE; voxel highlighting disabled");
+    }
+    let shader_watcher = ShaderWatcher::with_prelude(VOXEL_SHADER_PATH.into(), shader_prelude, device.clone());
+
+    // The targeted-voxel highlight, unstable voxels and annotation arrows
+    let outlines = Rc::new(RefCell::new(OutlineRenderer::new(voxel_pipelines.clone())));
+    // Unstable voxels are outlined whenever the debug view is on, and
+    // re-analysed when the world changes
+    let mut structural_debug = false;
+    let mut structural_rebuild = false;
+
+    // Each renderer adds its own passes, naming the passes it draws after
+    let mut graph = RenderGraph::new();
+    lighting::ShadowMap::register(&shadow_map, &mut graph);
+    sky::Skybox::register(&skybox, &mut graph);
+    crystal::CrystalRenderer::register(&crystals, &mut graph).unwrap();
+    VoxelPipelines::register(&voxel_pipelines, &mut graph).unwrap();
+    OutlineRenderer::register(&outlines, &mut graph).unwrap();
+    wireframe::WireframeRenderer::register(&wireframes, &mut graph).unwrap();
+    hand::HandRenderer::register(&hand, &mut graph).unwrap();
+    minimap::MinimapRenderer::register(&minimap, &mut graph).unwrap();
+    save_slots::SlotPreviewOverlay::register(&slot_overlay, &mut graph).unwrap();
+    console::ConsoleRenderer::register(&console_renderer, &mut graph).unwrap();
+    particles::ParticleSystem::register(&particles, &mut graph).unwrap();
+    text::TextRenderer::register(&text_renderer, &mut graph).unwrap();
+
+    // Debug outlines of resident chunks and of the player's physics box
+    let mut show_chunk_bounds = false;
+    let mut show_physics_bounds = false;
+
+    // Spectator mode flies without editing; right-clicking there starts an
+    // annotation, and typing fills in its text until Return
+    let mut spectator = false;
+    let mut annotations = AnnotationLayer::new();
+    let mut annotation_draft: Option<WorldAnnotation> = None;
+
+    // Camera and input state
+    let mut camera = Camera::new();
+    let mut keys_pressed = std::collections::HashSet::new();
+    let mut selected_voxel = registry::STONE;
+    let mut history = EditHistory::new(DEFAULT_HISTORY_DEPTH);
+    // Every edit is recorded for replay mode, which pauses water and plugins
+    // and has the left/right arrows scrub through the recording
+    let mut recorder = ReplayRecorder::new();
+    let mut replay: Option<ReplayPlayer> = None;
+    let mut mouse_delta = (0.0f64, 0.0f64);
+    let mut cursor_grabbed = false;
+    let start_time = Instant::now();
+
+    // The player walks under gravity unless fly mode is toggled on
+    let mut flying = false;
+    let mut glow_demo = false;
+    let mut body = spawn_body(&camera, &world);
+    let physics = PhysicsEngine::new();
+    let render_settings = RenderSettings::default();
+    let mut last_frame = Instant::now();
+
+    // Water flows on a background task; its changes are applied each frame
+    let mut water = WaterTask::spawn(&world);
+
+    // Only chunks near the camera stay in memory. Each world gets its own
+    // directory so writes still in flight for the last one can't clobber it.
+    let chunk_root = Path::new(SAVE_DIRECTORY).join(CHUNK_DIRECTORY);
+    std::fs::remove_dir_all(&chunk_root).ok();
+    let mut stream_generation = 0u32;
+    let mut streamer = start_streaming(&mut world, &chunk_root.join(stream_generation.to_string()), camera.position);
+    println!(
+        "Streaming chunks within {} chunks of the camera ({} resident)",
+        streamer.load_radius(),
+        streamer.loaded_chunks().len()
+    );
+
+    let mut save_slots = SaveSlotManager::new(SAVE_DIRECTORY, "Robin World");
+    let mut slot_menu: Option<SlotMenu> = None;
+    // Saving waits for a frame rendered with the slot menu closed
+    let mut pending_save: Option<u8> = None;
+    // Screenshots are taken from the next frame once it's rendered
+    let mut pending_screenshot = false;
+
+    // While the console is open, keys go to it instead of the game
+    let mut console = console::DevConsole::new(world_seed);
+    console.register_command("template", template::template_command(TEMPLATE_DIRECTORY.into()));
+    // Distance fog hides chunks streaming in; `fog` switches between its modes
+    let fog_settings = std::rc::Rc::new(std::cell::Cell::new(FogSettings::new().with_load_radius(STREAM_RADIUS)));
+    console.register_command("fog", fog::fog_command(fog_settings.clone(), STREAM_RADIUS));
+    plugins.register_commands(&mut console);
+
+    println!("\n🎮 Controls:");
+    println!("   WASD        - Move camera");
+    println!("   Mouse       - Look around");
+    println!("   Arrow Keys  - Look around");
+    println!("   Space       - Jump (fly mode: move up)");
+    println!("   Shift       - Move down (fly mode)");
+    println!("   G           - Toggle fly mode");
+    println!("   T           - Toggle spectator mode (right-click to annotate)");
+    println!("   Left Click  - Remove targeted voxel");
+    println!("   Right Click - Place selected voxel (water keeps flowing)");
+    println!("   1-6         - Select voxel type");
+    println!("   Ctrl+Z/Y    - Undo / redo voxel edit");
+    println!("   Ctrl+D      - Outline voxels with nothing holding them up");
+    println!("   Ctrl+B      - Outline chunk boundaries");
+    println!("   Ctrl+P      - Outline physics bounding boxes");
+    println!("   F5 / F9     - Save / load a slot, then 1-4 to pick it");
+    println!("   F12         - Save a screenshot");
+    println!("   R           - Toggle replay mode (left/right arrows scrub)");
+    println!("   I           - Toggle instanced crystal rendering");
+    println!("   L           - Toggle glow demo (scatters glowstone, sun off)");
+    println!("   M           - Toggle minimap");
+    println!("   `           - Toggle developer console");
+    println!("   Middle Btn  - Reset camera");
+    println!("   ESC         - Release mouse / Exit");
+    println!("\n✨ Voxel world ready! Enjoy the interactive 3D experience!");
+
+    // Event loop
+    event_loop.set_control_flow(ControlFlow::Poll);
+    event_loop.run(move |event, target| {
+        match event {
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::CloseRequested => target.exit(),
+                WindowEvent::KeyboardInput {
+                    event: KeyEvent {
+                        physical_key: PhysicalKey::Code(keycode),
+                        state,
+                        text,
+                        ..
+                    },
+                    ..
+                } => {
+                    match state {
+                        ElementState::Pressed if annotation_draft.is_some() => match keycode {
+                            KeyCode::Enter => {
+                                if let Some(mut draft) = annotation_draft.take() {
+                                    if !draft.text.trim().is_empty() {
+                                        draft.created_at = SystemTime::now();
+                                        annotations.add(draft);
+                                    }
+                                }
+                            }
+                            KeyCode::Escape => annotation_draft = None,
+                            KeyCode::Backspace => {
+                                if let Some(draft) = &mut annotation_draft {
+                                    draft.text.pop();
+                                }
+                            }
+                            _ => {}
+                        },
+                        ElementState::Pressed if console.visible || keycode == KeyCode::Backquote => {
+                            match keycode {
+                                KeyCode::Backquote | KeyCode::Escape => {
+                                    console.visible = !console.visible;
+                                    keys_pressed.clear();
+                                }
+                                KeyCode::Enter => {
+                                    if console.submit(&mut world) {
+                                        // Commands can change any voxel, so restart the
+                                        // water simulation from the edited world
+                                        water = WaterTask::spawn(&world);
+                                    }
+                                    if let Some(position) = console.take_teleport() {
+                                        camera.position = position;
+                                        body = spawn_body(&camera, &world);
+                                    }
+                                }
+                                KeyCode::Backspace => console.backspace(),
+                                KeyCode::ArrowUp => console.history_previous(),
+                                KeyCode::ArrowDown => console.history_next(),
+                                KeyCode::PageUp => console.scroll(5),
+                                KeyCode::PageDown => console.scroll(-5),
+                                _ => {}
+                            }
+                        }
+                        ElementState::Pressed => {
+                            keys_pressed.insert(keycode);
+                            if keycode == KeyCode::Escape {
+                                if slot_menu.is_some() {
+                                    slot_menu = None;
+                                } else if cursor_grabbed {
+                                    window.set_cursor_grab(CursorGrabMode::None).ok();
+                                    window.set_cursor_visible(true);
+                                    cursor_grabbed = false;
+                                } else {
+                                    target.exit();
+                                }
+                            }
+                            if keycode == KeyCode::F5 || keycode == KeyCode::F9 {
+                                let menu = if keycode == KeyCode::F5 { SlotMenu::Save } else { SlotMenu::Load };
+                                slot_menu = if slot_menu == Some(menu) { None } else { Some(menu) };
+                                if slot_menu.is_some() {
+                                    let slots = save_slots.list_slots();
+                                    slot_overlay.borrow().update(&queue, &slots);
+                                    for info in &slots {
+                                        println!(
+                                            "   Slot {}: {} ({}m played)",
+                                            info.slot + 1,
+                                            info.world_name,
+                                            info.play_time_seconds / 60
+                                        );
+                                    }
+                                }
+                            }
+                            if keycode == KeyCode::F12 {
+                                if can_capture_frames {
+                                    pending_screenshot = true;
+                                } else {
+                                    println!("⚠️  This surface can't be copied, so screenshots can't be taken");
+                                }
+                            }
+                            if keycode == KeyCode::KeyR {
+                                if let Some(mut player) = replay.take() {
+                                    // Back to the live world before edits resume
+                                    player.step_to(&mut world, u64::MAX);
+                                    water = WaterTask::spawn(&world);
+                                    println!("⏹️  Left replay mode");
+                                } else {
+                                    let path = Path::new(SAVE_DIRECTORY).join(REPLAY_FILE);
+                                    match recorder.save(&path).and_then(|()| ReplayPlayer::new(&path)) {
+                                        Ok(mut player) => {
+                                            player.resume_at_end();
+                                            println!(
+                                                "⏪ Replaying {} edits over {:.1}s, ←/→ to scrub",
+                                                player.len(),
+                                                player.duration_ms() as f32 / 1000.0
+                                            );
+                                            replay = Some(player);
+                                        }
+                                        Err(e) => println!("⚠️  Failed to start replay: {}", e),
+                                    }
+                                }
+                            }
+                            if keycode == KeyCode::KeyT {
+                                spectator = !spectator;
+                                flying = spectator;
+                                body = spawn_body(&camera, &world);
+                                println!(
+                                    "👁️  Spectator mode {}",
+                                    if spectator { "on: right-click to annotate, left-click to delete" } else { "off" }
+                                );
+                            }
+                            if keycode == KeyCode::KeyG && !spectator {
+                                flying = !flying;
+                                body = spawn_body(&camera, &world);
+                                println!("🕊️  Fly mode {}", if flying { "on" } else { "off" });
+                            }
+                            let ctrl = keys_pressed.contains(&KeyCode::ControlLeft)
+                                || keys_pressed.contains(&KeyCode::ControlRight);
+                            if ctrl && replay.is_none() && (keycode == KeyCode::KeyZ || keycode == KeyCode::KeyY) {
+                                // Undo is recorded as the edit that reverses it
+                                let edit = if keycode == KeyCode::KeyZ {
+                                    history.undo(&mut world).map(|edit| VoxelEdit { before: edit.after, after: edit.before, ..edit })
+                                } else {
+                                    history.redo(&mut world)
+                                };
+                                if let Some(edit @ VoxelEdit { coord: (x, y, z), after, .. }) = edit {
+                                    recorder.record_edit(edit);
+                                    water.notify_edit([x, y, z], after);
+                                }
+                            }
+                            if ctrl && keycode == KeyCode::KeyD {
+                                structural_debug = !structural_debug;
+                                structural_rebuild = structural_debug;
+                                if !structural_debug {
+                                    outlines.borrow_mut().structural.clear();
+                                }
+                                println!("🏗️  Structural debug view {}", if structural_debug { "on" } else { "off" });
+                            }
+                            if ctrl && keycode == KeyCode::KeyB {
+                                show_chunk_bounds = !show_chunk_bounds;
+                                println!("🧱 Chunk boundaries {}", if show_chunk_bounds { "on" } else { "off" });
+                            }
+                            if ctrl && keycode == KeyCode::KeyP {
+                                show_physics_bounds = !show_physics_bounds;
+                                println!("📦 Physics bounds {}", if show_physics_bounds { "on" } else { "off" });
+                            }
+                            if keycode == KeyCode::KeyL {
+                                glow_demo = !glow_demo;
+                                if glow_demo && replay.is_none() {
+                                    let seed = start_time.elapsed().as_nanos() as u64;
+                                    for edit in scatter_glowstone(&mut world, camera.position, GLOW_DEMO_RADIUS, GLOW_DEMO_COUNT, seed) {
+                                        let (x, y, z) = edit.coord;
+                                        history.push(edit);
+                                        recorder.record_edit(edit);
+                                        water.notify_edit([x, y, z], edit.after);
+                                    }
+                                }
+                                println!("✨ Glow demo {}", if glow_demo { "on, the sun is off" } else { "off" });
+                            }
+                            if keycode == KeyCode::KeyM {
+                                let mut minimap = minimap.borrow_mut();
+                                minimap.visible = !minimap.visible;
+                            }
+                            if keycode == KeyCode::KeyI {
+                                let mut crystals = crystals.borrow_mut();
+                                crystals.instanced = !crystals.instanced;
+                                println!(
+                                    "💎 Crystals now drawn {}",
+                                    if crystals.instanced { "in one instanced call" } else { "one call per crystal" }
+                                );
+                            }
+                            let slot = match keycode {
+                                KeyCode::Digit1 => Some(0),
+                                KeyCode::Digit2 => Some(1),
+                                KeyCode::Digit3 => Some(2),
+                                KeyCode::Digit4 => Some(3),
+                                _ => None,
+                            };
+                            match (slot_menu, slot) {
+                                (Some(SlotMenu::Save), Some(slot)) => {
+                                    slot_menu = None;
+                                    if can_capture_frames {
+                                        pending_save = Some(slot);
+                                    } else {
+                                        println!("⚠️  This surface can't be copied, so slots can't be saved");
+                                    }
+                                }
+                                (Some(SlotMenu::Load), Some(slot)) => {
+                                    slot_menu = None;
+                                    match save_slots.load_slot(slot) {
+                                        Ok((loaded_world, loaded_camera)) => {
+                                            world = loaded_world;
+                                            plugins.register_voxels(world.registry_mut());
+                                            camera = loaded_camera;
+                                            body = spawn_body(&camera, &world);
+                                            water = WaterTask::spawn(&world);
+                                            stream_generation += 1;
+                                            streamer = start_streaming(
+                                                &mut world,
+                                                &chunk_root.join(stream_generation.to_string()),
+                                                camera.position,
+                                            );
+                                            history.clear();
+                                            recorder = ReplayRecorder::new();
+                                            replay = None;
+                                            console.set_seed(None);
+                                            println!("📂 Loaded slot {}", slot + 1);
+                                        }
+                                        Err(e) => println!("⚠️  Failed to load slot {}: {}", slot + 1, e),
+                                    }
+                                }
+                                _ => {
+                                    selected_voxel = match keycode {
+                                        KeyCode::Digit1 => registry::STONE,
+                                        KeyCode::Digit2 => registry::GRASS,
+                                        KeyCode::Digit3 => registry::DIRT,
+                                        KeyCode::Digit4 => registry::WATER,
+                                        KeyCode::Digit5 => registry::CRYSTAL,
+                                        KeyCode::Digit6 => registry::ICE,
+                                        _ => selected_voxel,
+                                    };
+                                }
+                            }
+                        }
+                        ElementState::Released => {
+                            keys_pressed.remove(&keycode);
+                        }
+                    }
+
+                    // Typed text goes to the console while it's open, or else
+                    // to an annotation being written
+                    let typed = text.filter(|_| state == ElementState::Pressed);
+                    for c in typed.iter().flat_map(|text| text.chars()) {
+                        if console.visible && c != '`' {
+                            console.type_char(c);
+                        } else if let Some(draft) = annotation_draft.as_mut().filter(|_| !c.is_control()) {
+                            draft.text.push(c);
+                        }
+                    }
+                }
+                WindowEvent::Focused(true) => {
+                    // Locked isn't available everywhere (e.g. Windows), so fall back to Confined
+                    cursor_grabbed = window
+                        .set_cursor_grab(CursorGrabMode::Locked)
+                        .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+                        .is_ok();
+                    window.set_cursor_visible(!cursor_grabbed);
+                }
+                WindowEvent::Focused(false) => {
+                    window.set_cursor_grab(CursorGrabMode::None).ok();
+                    window.set_cursor_visible(true);
+                    cursor_grabbed = false;
+                }
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button: MouseButton::Middle,
+                    ..
+                } => {
+                    camera = Camera::new();
+                    body = spawn_body(&camera, &world);
+                }
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button,
+                    ..
+                } if spectator => {
+                    let hit = world.raycast(camera.position, camera.forward(), REACH_DISTANCE);
+                    match (button, hit) {
+                        (MouseButton::Left, Some((x, y, z, _))) if annotation_draft.is_none() => {
+                            let center = [x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5];
+                            let found = annotations
+                                .nearest(center, ANNOTATION_PICK_RADIUS)
+                                .map(|a| (a.author_id.clone(), a.created_at));
+                            if let Some((author_id, created_at)) = found {
+                                annotations.remove(&author_id, created_at);
+                            }
+                        }
+                        (MouseButton::Right, Some((x, y, z, face))) if annotation_draft.is_none() => {
+                            // The label floats in the cell in front of the
+                            // clicked face, pointing back at the voxel
+                            let normal = face.normal();
+                            let center = [x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5];
+                            annotation_draft = Some(WorldAnnotation {
+                                world_position: [0, 1, 2].map(|axis| center[axis] + normal[axis] as f32 * 1.5),
+                                text: String::new(),
+                                arrow_target: Some(center),
+                                author_id: LOCAL_AUTHOR_ID.to_string(),
+                                color: ANNOTATION_COLOR,
+                                created_at: SystemTime::now(),
+                            });
+                            keys_pressed.clear();
+                        }
+                        _ => {}
+                    }
+                }
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button,
+                    ..
+                } if replay.is_none() => {
+                    let hit = world.raycast(camera.position, camera.forward(), REACH_DISTANCE);
+                    match (button, hit) {
+                        (MouseButton::Left, Some((x, y, z, _))) => {
+                            let edit = VoxelEdit { coord: (x, y, z), before: world.get(x, y, z), after: None };
+                            if let Some(broken) = edit.before {
+                                let center = [x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5];
+                                particles.borrow_mut().emit(center, world.registry.color(broken), PARTICLES_PER_EDIT);
+                            }
+                            history.push(edit);
+                            recorder.record_edit(edit);
+                            world.set_voxel(x, y, z, None);
+                            water.notify_edit([x, y, z], None);
+                        }
+                        (MouseButton::Right, Some((x, y, z, face))) => {
+                            let normal = face.normal();
+                            let target = [x as i32 + normal[0], y as i32 + normal[1], z as i32 + normal[2]];
+                            if target.iter().all(|&c| c >= 0) {
+                                let (tx, ty, tz) = (target[0] as usize, target[1] as usize, target[2] as usize);
+                                if world.get(tx, ty, tz).is_none() {
+                                    let edit = VoxelEdit {
+                                        coord: (tx, ty, tz),
+                                        before: None,
+                                        after: Some(selected_voxel),
+                                    };
+                                    history.push(edit);
+                                    recorder.record_edit(edit);
+                                    world.set_voxel(tx, ty, tz, Some(selected_voxel));
+                                    water.notify_edit([tx, ty, tz], Some(selected_voxel));
+                                    let center = [tx as f32 + 0.5, ty as f32 + 0.5, tz as f32 + 0.5];
+                                    particles.borrow_mut().emit(center, world.registry.color(selected_voxel), PARTICLES_PER_EDIT);
+                                    if selected_voxel == registry::WATER {
+                                        water.add_source([tx, ty, tz]);
+                                    }
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                WindowEvent::Resized(new_size) if new_size.width > 0 && new_size.height > 0 => {
+                    surface.configure(&device, &wgpu::SurfaceConfiguration {
+                        usage: surface_usage,
+                        format: surface_config.format,
+                        width: new_size.width,
+                        height: new_size.height,
+                        present_mode: wgpu::PresentMode::AutoVsync,
+                        alpha_mode: wgpu::CompositeAlphaMode::Auto,
+                        view_formats: vec![],
+                        desired_maximum_frame_latency: surface_config.desired_maximum_frame_latency,
+                    });
+                    (_depth_texture, depth_view) =
+                        create_depth_texture(&device, new_size.width, new_size.height);
+                    text_renderer.borrow_mut().resize(new_size.width, new_size.height);
+                }
+                _ => {}
+            },
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta },
+                ..
+            } if cursor_grabbed => {
+                mouse_delta.0 += delta.0;
+                mouse_delta.1 += delta.1;
+            }
+            Event::AboutToWait => {
+                // Update camera based on input
+                let speed = 0.5;
+                let turn_speed = 0.05;
+                let now = Instant::now();
+                // Clamped so a stall (e.g. dragging the window) isn't one huge step
+                let dt = (now - last_frame).as_secs_f32().min(0.1);
+                last_frame = now;
+
+                camera.rotate(mouse_delta, MOUSE_SENSITIVITY);
+                mouse_delta = (0.0, 0.0);
+
+                // Horizontal movement relative to where the camera faces
+                let mut forward = 0.0;
+                let mut strafe = 0.0;
+                if keys_pressed.contains(&KeyCode::KeyW) {
+                    forward += 1.0;
+                }
+                if keys_pressed.contains(&KeyCode::KeyS) {
+                    forward -= 1.0;
+                }
+                if keys_pressed.contains(&KeyCode::KeyD) {
+                    strafe += 1.0;
+                }
+                if keys_pressed.contains(&KeyCode::KeyA) {
+                    strafe -= 1.0;
+                }
+
+                if flying {
+                    camera.move_forward(forward * speed);
+                    camera.move_right(strafe * speed);
+                    if keys_pressed.contains(&KeyCode::Space) {
+                        camera.move_up(speed);
+                    }
+                    if keys_pressed.contains(&KeyCode::ShiftLeft) {
+                        camera.move_up(-speed);
+                    }
+                } else {
+                    // Letting go leaves the player to slow down under the
+                    // ground's friction, so they slide a while on ice
+                    if forward != 0.0 || strafe != 0.0 {
+                        let (ahead, right) = (camera.level_forward(), camera.right());
+                        body.velocity[0] = (ahead[0] * forward + right[0] * strafe) * WALK_SPEED;
+                        body.velocity[2] = (ahead[2] * forward + right[2] * strafe) * WALK_SPEED;
+                    }
+                    if keys_pressed.contains(&KeyCode::Space) {
+                        body.jump();
+                    }
+                    physics.step(&mut body, &world, dt);
+
+                    // Walked off the edge of the world: start again
+                    if body.position[1] < -(world.size as f32) {
+                        camera = Camera::new();
+                        body = spawn_body(&camera, &world);
+                    }
+                    camera.position = [body.position[0], body.position[1] + EYE_HEIGHT, body.position[2]];
+                }
+                if let Some(player) = &mut replay {
+                    let mut scrub = 0.0;
+                    if keys_pressed.contains(&KeyCode::ArrowLeft) {
+                        scrub -= REPLAY_SCRUB_SPEED * dt;
+                    }
+                    if keys_pressed.contains(&KeyCode::ArrowRight) {
+                        scrub += REPLAY_SCRUB_SPEED * dt;
+                    }
+                    if scrub != 0.0 {
+                        let target_ms = (player.current_ms() as f32 + scrub).max(0.0) as u64;
+                        player.step_to(&mut world, target_ms);
+                    }
+                } else {
+                    if keys_pressed.contains(&KeyCode::ArrowLeft) {
+                        camera.yaw -= turn_speed;
+                    }
+                    if keys_pressed.contains(&KeyCode::ArrowRight) {
+                        camera.yaw += turn_speed;
+                    }
+                }
+                if keys_pressed.contains(&KeyCode::ArrowUp) {
+                    camera.pitch = (camera.pitch + turn_speed).min(MAX_PITCH);
+                }
+                if keys_pressed.contains(&KeyCode::ArrowDown) {
+                    camera.pitch = (camera.pitch - turn_speed).max(-MAX_PITCH);
+                }
+
+                if replay.is_none() {
+                    plugins.update(&mut world, dt);
+                    water.apply_changes(&mut world);
+                }
+
+                // Stream chunks in and out and swap detail levels as the camera
+                // moves, then re-mesh only the chunks touched since the last frame
+                update_streaming(&mut world, &mut streamer, camera.position);
+                world.update_chunk_lods(camera.position);
+                world.sort_transparent_faces(camera.position, &render_settings);
+                if world.upload_dirty_chunks(&device, &queue) > 0 {
+                    // An edit may have added or removed crystals and glowing voxels
+                    let crystal_instances = world.generate_crystal_instances();
+                    crystals.borrow_mut().upload_instances(&device, &queue, &crystal_instances);
+                    point_lights = crystal_lights(&crystal_instances, world.registry.color(registry::CRYSTAL));
+                    point_lights.extend(world.emissive_lights());
+                    minimap.borrow().update_world(&queue, &world);
+                    structural_rebuild = structural_debug;
+                }
+                if structural_rebuild {
+                    structural_rebuild = false;
+                    let report = StructuralAnalyzer::analyze(&world);
+                    outlines.borrow_mut().structural.set(
+                        &device,
+                        &queue,
+                        unstable_outline_mesh(&report),
+                        "Structural Outline",
+                    );
+                }
+
+                if let Some(shader) = shader_watcher.try_recv() {
+                    // A shader that compiles can still disagree with the
+                    // pipelines' bindings or vertex layout
+                    device.push_error_scope(wgpu::ErrorFilter::Validation);
+                    let reloaded = create_voxel_pipelines(
+                        &device,
+                        &pipeline_layout,
+                        &shader,
+                        surface_config.format,
+                        supports_wireframe,
+                    );
+                    match pollster::block_on(device.pop_error_scope()) {
+                        None => {
+                            *voxel_pipelines.borrow_mut() = reloaded;
+                            println!("🔄 Reloaded {}", shader_watcher.path().display());
+                        }
+                        Some(e) => println!("⚠️  Keeping the old shader: {}", e),
+                    }
+                }
+
+                let mut outlines = outlines.borrow_mut();
+                outlines.arrows.set(
+                    &device,
+                    &queue,
+                    annotation_arrow_mesh(annotations.visible(camera.position).chain(&annotation_draft)),
+                    "Annotation Arrow",
+                );
+
+                let mut wireframes = wireframes.borrow_mut();
+                if show_chunk_bounds {
+                    for chunk in world.chunks.iter().filter(|chunk| chunk.resident) {
+                        let (min, max) = world.chunk_bounds(chunk);
+                        wireframes.draw_aabb((min.map(|c| c as f32), max.map(|c| c as f32)), CHUNK_BOUNDS_COLOR);
+                    }
+                }
+                if show_physics_bounds {
+                    wireframes.draw_aabb(body.bounds(), PHYSICS_BODY_COLOR);
+                    // The solid voxels the body is touching or about to touch
+                    let (min, max) = body.bounds();
+                    for x in min[0].floor() as i64 - 1..=max[0].floor() as i64 + 1 {
+                        for y in min[1].floor() as i64 - 1..=max[1].floor() as i64 + 1 {
+                            for z in min[2].floor() as i64 - 1..=max[2].floor() as i64 + 1 {
+                                if world.is_solid([x, y, z]) {
+                                    let cell = [x as f32, y as f32, z as f32];
+                                    wireframes.draw_aabb((cell, cell.map(|c| c + 1.0)), PHYSICS_VOXEL_COLOR);
+                                }
+                            }
+                        }
+                    }
+                }
+                wireframes.upload(&device, &queue);
+                drop(wireframes);
+
+                // Find the voxel under the crosshair for highlighting
+                match world.raycast(camera.position, camera.forward(), REACH_DISTANCE) {
+                    Some((x, y, z, _)) => outlines.highlight.set(&device, &queue, highlight_mesh([x, y, z]), "Highlight"),
+                    None => outlines.highlight.clear(),
+                }
+                drop(outlines);
+
+                // Render
+                let output = surface.get_current_texture().unwrap();
+                let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                });
+
+                // Update uniforms
+                let aspect_ratio = window.inner_size().width as f32 / window.inner_size().height as f32;
+                let view_proj = multiply_matrices(projection_matrix(aspect_ratio), camera.view_matrix());
+                let time = start_time.elapsed().as_secs_f32();
+                let palette = sky::palette_at(time);
+                let [hr, hg, hb] = palette.horizon;
+                let [zr, zg, zb] = palette.zenith;
+
+                // The sun's shadow map covers the whole world
+                let half_size = world.size as f32 / 2.0;
+                let world_center = [half_size; 3];
+                let sun_view_proj = lighting::sun_view_proj(lighting::SUN_DIRECTION, world_center, half_size * 3.0f32.sqrt());
+                let sun_intensity = if glow_demo { 0.0 } else { 1.0 };
+                let sun = PointLight::directional(lighting::SUN_DIRECTION, lighting::SUN_COLOR, sun_intensity);
+                let mut lights = LightBuffer::new(sun, sun_view_proj);
+                lights.add_nearest(&point_lights, camera.position);
+                queue.write_buffer(&light_buffer, 0, bytemuck::cast_slice(&[lights]));
+                shadow_map.update(&queue, sun_view_proj);
+                // Shaders without the light buffer treat the sun as a point far along its direction
+                let sun_position = [0, 1, 2].map(|axis| world_center[axis] + sun.position[axis] * 1000.0);
+
+                let uniforms = Uniforms {
+                    view_proj,
+                    inv_view_proj: invert_matrix(view_proj),
+                    light_pos: [sun_position[0], sun_position[1], sun_position[2], 1.0],
+                    eye_pos: [camera.position[0], camera.position[1], camera.position[2], 1.0],
+                    sky_horizon: [hr, hg, hb, 1.0],
+                    sky_zenith: [zr, zg, zb, 1.0],
+                    time,
+                    _padding: [0.0; 3],
+                    // The horizon colour, so distant terrain fades into the sky
+                    fog: fog_settings.get().with_color(palette.horizon).uniforms(),
+                };
+
+                queue.write_buffer(&uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+                minimap.borrow().update_camera(&queue, camera.position, camera.yaw, world.size);
+                hand.borrow_mut().update(&queue, &world.registry, selected_voxel, aspect_ratio, time);
+                particles.borrow().update_camera(&queue, view_proj, camera.view_matrix());
+                particles.borrow_mut().update(&queue, &mut encoder, dt);
+
+                let gpu_frame = match &frame_timer {
+                    Some(timer) => format!("{:.2} ms", timer.last_frame_ms()),
+                    None => "n/a".to_string(),
+                };
+                console.set_status(&format!("CPU {:.2} ms  GPU {}", dt * 1000.0, gpu_frame));
+                let window_size = window.inner_size();
+                console_renderer.borrow_mut().update(&queue, &console, window_size.width, window_size.height);
+
+                slot_overlay.borrow_mut().visible = slot_menu.is_some();
+                if dt > 0.0 {
+                    hud_fps += (1.0 / dt - hud_fps) * HUD_FPS_SMOOTHING;
+                }
+                let [x, y, z] = camera.position;
+                let tool = world.registry.get(selected_voxel).map_or("unknown", |def| def.name.as_str());
+                let hud = format!("FPS {:.0}\nPos {:.1} {:.1} {:.1}\nTool: place {}", hud_fps, x, y, z, tool);
+                let mut text = text_renderer.borrow_mut();
+                text.queue_text(&hud, HUD_MARGIN, HUD_MARGIN, HUD_TEXT_SCALE, HUD_TEXT_COLOR);
+
+                // Labels are drawn flat on the screen, centred on where their
+                // anchor projects, so they always face the camera
+                let draft_label = annotation_draft.as_ref().map(|draft| (draft, format!("{}_", draft.text)));
+                let labels = annotations
+                    .visible(camera.position)
+                    .map(|a| (a, a.text.clone()))
+                    .chain(draft_label);
+                for (annotation, label) in labels {
+                    let Some([sx, sy]) = annotation::project_to_screen(
+                        view_proj,
+                        annotation.world_position,
+                        window_size.width as f32,
+                        window_size.height as f32,
+                    ) else {
+                        continue;
+                    };
+                    let distance = (0..3)
+                        .map(|axis| (annotation.world_position[axis] - camera.position[axis]).powi(2))
+                        .sum::<f32>()
+                        .sqrt();
+                    let scale = ANNOTATION_TEXT_SCALE * (ANNOTATION_FULL_SIZE_DISTANCE / distance).min(1.0);
+                    let width = label.chars().count() as f32 * text::ADVANCE * scale;
+                    let height = text::CELL_HEIGHT as f32 * scale;
+                    text.queue_text(&label, sx - width / 2.0, sy - height / 2.0, scale, annotation.color);
+                }
+                drop(text);
+
+                let context = RenderContext {
+                    view: &view,
+                    depth_view: &depth_view,
+                    uniform_buffer: &uniform_buffer,
+                    light_buffer: &light_buffer,
+                    bind_group: &bind_group,
+                    world: &world,
+                    camera_position: camera.position,
+                    view_proj,
+                    clear_color: wgpu::Color {
+                        r: hr as f64,
+                        g: hg as f64,
+                        b: hb as f64,
+                        a: 1.0,
+                    },
+                    surface_size: (window_size.width, window_size.height),
+                };
+                // The frame's GPU time covers every pass in the graph
+                if let Some(timer) = &frame_timer {
+                    timer.start(&mut encoder);
+                }
+                if let Err(e) = graph.execute(&mut encoder, &context) {
+                    println!("⚠️  Frame not rendered: {}", e);
+                }
+                if let Some(timer) = &frame_timer {
+                    timer.stop(&mut encoder);
+                }
+
+                let mut crystals = crystals.borrow_mut();
+                for timer in [&mut crystals.timer, &mut frame_timer].into_iter().flatten() {
+                    timer.resolve(&mut encoder);
+                }
+                queue.submit(std::iter::once(encoder.finish()));
+                if let Some(slot) = pending_save.take() {
+                    match save_slots.save_to_slot(slot, &world, &camera, &output.texture, &device, &queue) {
+                        Ok(()) => println!("💾 Saved to slot {}", slot + 1),
+                        Err(e) => println!("⚠️  Failed to save slot {}: {}", slot + 1, e),
+                    }
+                }
+                if pending_screenshot {
+                    pending_screenshot = false;
+                    let path = screenshot::timestamped_path();
+                    let (width, height) = (output.texture.width(), output.texture.height());
+                    match screenshot::capture_screenshot(&device, &queue, &output.texture, width, height, &path) {
+                        Ok(()) => println!("📸 Saved screenshot to {}", path.display()),
+                        Err(e) => println!("⚠️  Failed to save screenshot: {}", e),
+                    }
+                }
+                output.present();
+
+                if let Some(timer) = &mut frame_timer {
+                    timer.request_readback();
+                    timer.read(&device);
+                }
+                let crystal_time = crystals.timer.as_mut().and_then(|timer| {
+                    timer.request_readback();
+                    timer.read(&device)
+                });
+                if let Some(elapsed) = crystal_time {
+                    if last_timing_report.elapsed() >= Duration::from_secs(2) {
+                        println!(
+                            "💎 {} crystals ({}): {:.3} ms GPU",
+                            crystals.instance_count(),
+                            if crystals.instanced { "instanced" } else { "per-crystal draws" },
+                            elapsed.as_secs_f64() * 1000.0
+                        );
+                        last_timing_report = Instant::now();
+                    }
+                }
+            }
+            _ => {}
+        }
+    })
+    .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flat_grass_world(size: usize) -> VoxelWorld {
+        let mut world = VoxelWorld::empty(size);
+        for x in 0..size {
+            for z in 0..size {
+                world.voxels[x][0][z] = Some(registry::GRASS);
+            }
+        }
+        world
+    }
+
+    /// Expands an indexed mesh into a plain triangle list
+    fn triangles(mesh: &(Vec<Vertex>, Vec<u32>)) -> Vec<Vertex> {
+        let (vertices, indices) = mesh;
+        indices.iter().map(|&i| vertices[i as usize]).collect()
+    }
+
+    /// Sums the area of every triangle in the mesh, grouped by face normal.
+    fn area_by_normal(vertices: &[Vertex]) -> Vec<([f32; 3], f32)> {
+        let mut areas: Vec<([f32; 3], f32)> = Vec::new();
+        for tri in vertices.chunks(3) {
+            let a = tri[0].position;
+            let b = tri[1].position;
+            let c = tri[2].position;
+            let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let cross = [
+                ab[1] * ac[2] - ab[2] * ac[1],
+                ab[2] * ac[0] - ab[0] * ac[2],
+                ab[0] * ac[1] - ab[1] * ac[0],
+            ];
+            let area = 0.5 * (cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2]).sqrt();
+            match areas.iter_mut().find(|(normal, _)| *normal == tri[0].normal) {
+                Some((_, total)) => *total += area,
+                None => areas.push((tri[0].normal, area)),
+            }
+        }
+        areas
+    }
+
+    #[test]
+    fn test_greedy_mesh_flat_surface() {
+        let world = flat_grass_world(16);
+
+        // One quad per face direction: top, bottom and the four sides
+        let (vertices, indices) = world.generate_mesh();
+        assert_eq!(vertices.len(), 6 * 4);
+        assert_eq!(indices.len(), 6 * 6);
+
+        // 256 top + 256 bottom + 4 * 16 side faces
+        let (vertices, indices) = world.generate_mesh_naive();
+        assert_eq!(vertices.len(), (256 + 256 + 64) * 4);
+        assert_eq!(indices.len(), (256 + 256 + 64) * 6);
+    }
+
+    #[test]
+    fn test_greedy_mesh_preserves_normals_and_colors() {
+        let world = flat_grass_world(16);
+        let grass = world.registry.rgba(registry::GRASS);
+
+        for tri in triangles(&world.generate_mesh()).chunks(3) {
+            assert!(tri.iter().all(|v| v.color == grass));
+            assert!(tri.iter().all(|v| v.normal == tri[0].normal));
+
+            // Winding must still agree with the face normal for back-face culling
+            let a = tri[0].position;
+            let b = tri[1].position;
+            let c = tri[2].position;
+            let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let cross = [
+                ab[1] * ac[2] - ab[2] * ac[1],
+                ab[2] * ac[0] - ab[0] * ac[2],
+                ab[0] * ac[1] - ab[1] * ac[0],
+            ];
+            let n = tri[0].normal;
+            assert!(cross[0] * n[0] + cross[1] * n[1] + cross[2] * n[2] > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_greedy_mesh_covers_same_area_as_naive() {
+        let world = VoxelWorld::new(32);
+
+        let greedy = triangles(&world.generate_mesh());
+        let naive = triangles(&world.generate_mesh_naive());
+        assert!(greedy.len() < naive.len());
+
+        let greedy_areas = area_by_normal(&greedy);
+        let naive_areas = area_by_normal(&naive);
+        assert_eq!(greedy_areas.len(), naive_areas.len());
+        for (normal, area) in naive_areas {
+            let (_, greedy_area) = greedy_areas.iter().find(|(n, _)| *n == normal).unwrap();
+            assert!((area - greedy_area).abs() < 1e-3, "{:?}: {} vs {}", normal, area, greedy_area);
+        }
+    }
+
+    fn clear_dirty(world: &mut VoxelWorld) {
+        for chunk in &mut world.chunks {
+            chunk.dirty = false;
+        }
+    }
+
+    fn dirty_chunks(world: &VoxelWorld) -> Vec<[usize; 3]> {
+        world.chunks.iter().filter(|c| c.dirty).map(|c| c.origin).collect()
+    }
+
+    #[test]
+    fn test_chunk_layout() {
+        let world = VoxelWorld::empty(40);
+
+        // 40 voxels need three 16-voxel chunks per axis, the last one partial
+        assert_eq!(world.chunks_per_axis, 3);
+        assert_eq!(world.chunks.len(), 27);
+        assert!(world.chunks.iter().all(|c| c.dirty));
+
+        let last = &world.chunks[world.chunk_index(2, 2, 2)];
+        assert_eq!(last.origin, [32, 32, 32]);
+        assert_eq!(world.chunk_bounds(last), ([32, 32, 32], [40, 40, 40]));
+    }
+
+    #[test]
+    fn test_unload_and_reload_chunk() {
+        let mut world = VoxelWorld::new(32);
+        let before = world.voxels.clone();
+        let saved = world.chunk_voxels([1, 0, 0]);
+        assert_eq!(saved.get(0, 0, 0), Some(registry::STONE));
+        clear_dirty(&mut world);
+
+        world.unload_chunk([1, 0, 0]);
+        let chunk = &world.chunks[world.chunk_index(1, 0, 0)];
+        assert!(!chunk.resident);
+        assert!(chunk.vertex_buffer.is_none());
+        assert!((16..32).all(|x| (0..16).all(|y| (0..16).all(|z| world.voxels[x][y][z].is_none()))));
+        // The neighbour across x = 16 now shows its border faces
+        assert!(dirty_chunks(&world).contains(&[0, 0, 0]));
+
+        world.load_chunk([1, 0, 0], &saved);
+        assert!(world.chunks[world.chunk_index(1, 0, 0)].resident);
+        assert_eq!(world.voxels, before);
+        // Loading isn't an edit, so it isn't handed back to the streamer
+        assert!(world.take_edited_chunks().is_empty());
+
+        world.set_voxel(20, 20, 20, Some(registry::CRYSTAL));
+        assert_eq!(world.take_edited_chunks(), vec![[1, 1, 1]]);
+        assert!(world.take_edited_chunks().is_empty());
+    }
+
+    #[test]
+    fn test_set_voxel_marks_only_affected_chunks() {
+        let mut world = VoxelWorld::empty(48);
+        clear_dirty(&mut world);
+
+        // Interior voxel only touches its own chunk
+        world.set_voxel(20, 20, 20, Some(registry::STONE));
+        assert_eq!(dirty_chunks(&world), vec![[16, 16, 16]]);
+
+        // A voxel on a chunk face also dirties the neighbour across that face
+        clear_dirty(&mut world);
+        world.set_voxel(31, 20, 20, Some(registry::STONE));
+        assert_eq!(dirty_chunks(&world), vec![[16, 16, 16], [32, 16, 16]]);
+
+        // A corner voxel touches three neighbours
+        clear_dirty(&mut world);
+        world.set_voxel(16, 16, 16, Some(registry::STONE));
+        assert_eq!(dirty_chunks(&world).len(), 4);
+
+        // Re-setting the same value is a no-op
+        clear_dirty(&mut world);
+        world.set_voxel(16, 16, 16, Some(registry::STONE));
+        assert!(dirty_chunks(&world).is_empty());
+
+        // World edges have no neighbour to dirty, and out-of-range is ignored
+        world.set_voxel(0, 0, 0, Some(registry::DIRT));
+        assert_eq!(dirty_chunks(&world), vec![[0, 0, 0]]);
+        world.set_voxel(48, 0, 0, Some(registry::DIRT));
+        assert_eq!(dirty_chunks(&world), vec![[0, 0, 0]]);
+    }
+
+    #[test]
+    fn test_chunk_meshes_stitch_into_world_mesh() {
+        let world = VoxelWorld::new(32);
+
+        let mut chunked = Vec::new();
+        for chunk in &world.chunks {
+            let (min, max) = world.chunk_bounds(chunk);
+            chunked.extend(triangles(&world.generate_region_mesh(min, max)));
+        }
+
+        let whole = area_by_normal(&triangles(&world.generate_mesh_naive()));
+        let stitched = area_by_normal(&chunked);
+        for (normal, area) in whole {
+            let (_, chunk_area) = stitched.iter().find(|(n, _)| *n == normal).unwrap();
+            assert!((area - chunk_area).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_raycast_hits_top_face_from_above() {
+        let world = flat_grass_world(16);
+
+        let hit = world.raycast([4.5, 10.0, 7.5], [0.0, -1.0, 0.0], 20.0);
+        assert_eq!(hit, Some((4, 0, 7, FaceDirection::Top)));
+
+        // Too short to reach the ground
+        assert_eq!(world.raycast([4.5, 10.0, 7.5], [0.0, -1.0, 0.0], 5.0), None);
+    }
+
+    #[test]
+    fn test_raycast_reports_entry_face() {
+        let mut world = VoxelWorld::empty(16);
+        world.set_voxel(8, 8, 8, Some(registry::STONE));
+
+        let cases = [
+            ([2.5, 8.5, 8.5], [1.0, 0.0, 0.0], FaceDirection::Left),
+            ([14.5, 8.5, 8.5], [-1.0, 0.0, 0.0], FaceDirection::Right),
+            ([8.5, 2.5, 8.5], [0.0, 1.0, 0.0], FaceDirection::Bottom),
+            ([8.5, 14.5, 8.5], [0.0, -1.0, 0.0], FaceDirection::Top),
+            ([8.5, 8.5, 2.5], [0.0, 0.0, 1.0], FaceDirection::Back),
+            ([8.5, 8.5, 14.5], [0.0, 0.0, -1.0], FaceDirection::Front),
+        ];
+        for (origin, direction, face) in cases {
+            assert_eq!(world.raycast(origin, direction, 20.0), Some((8, 8, 8, face)));
+        }
+    }
+
+    #[test]
+    fn test_raycast_diagonal_and_outside_world() {
+        let mut world = VoxelWorld::empty(16);
+        world.set_voxel(5, 5, 5, Some(registry::CRYSTAL));
+
+        // Diagonal ray from outside the world bounds, unnormalized direction
+        let hit = world.raycast([-2.5, -2.5, 5.5], [2.0, 2.0, 0.0], 30.0);
+        assert!(matches!(hit, Some((5, 5, 5, FaceDirection::Left | FaceDirection::Bottom))));
+
+        // Pointing away from every voxel
+        assert_eq!(world.raycast([5.5, 8.5, 5.5], [0.0, 1.0, 0.0], 30.0), None);
+        assert_eq!(world.raycast([5.5, 8.5, 5.5], [0.0, 0.0, 0.0], 30.0), None);
+    }
+
+    #[test]
+    fn test_place_adjacent_to_hit_face() {
+        let mut world = flat_grass_world(16);
+
+        let (x, y, z, face) = world.raycast([3.5, 6.0, 3.5], [0.0, -1.0, 0.0], 10.0).unwrap();
+        let n = face.normal();
+        let target = [x as i32 + n[0], y as i32 + n[1], z as i32 + n[2]];
+        assert_eq!(target, [3, 1, 3]);
+
+        world.set_voxel(3, 1, 3, Some(registry::STONE));
+        assert_eq!(
+            world.raycast([3.5, 6.0, 3.5], [0.0, -1.0, 0.0], 10.0),
+            Some((3, 1, 3, FaceDirection::Top))
+        );
+    }
+
+    #[test]
+    fn test_highlight_mesh_encloses_voxel() {
+        let (vertices, indices) = highlight_mesh([2, 3, 4]);
+        assert_eq!(vertices.len(), 24);
+        assert_eq!(indices.len(), 36);
+        for vertex in &vertices {
+            let p = vertex.position;
+            assert!(p[0] < 2.0 || p[0] > 3.0);
+            assert!(p[1] < 3.0 || p[1] > 4.0);
+            assert!(p[2] < 4.0 || p[2] > 5.0);
+        }
+    }
+
+    fn camera_frustum(position: [f32; 3], yaw_degrees: f32) -> Frustum {
+        let camera = Camera {
+            position,
+            yaw: yaw_degrees.to_radians(),
+            pitch: 0.0,
+        };
+        Frustum::from_view_proj(multiply_matrices(projection_matrix(16.0 / 9.0), camera.view_matrix()))
+    }
+
+    #[test]
+    fn test_multiply_matrices_applies_right_operand_first() {
+        let translate = [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [5.0, 0.0, 0.0, 1.0],
+        ];
+        let scale = [
+            [2.0, 0.0, 0.0, 0.0],
+            [0.0, 2.0, 0.0, 0.0],
+            [0.0, 0.0, 2.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        // scale * translate moves by 5 first, then doubles: origin ends up at x = 10
+        assert_eq!(multiply_matrices(scale, translate)[3][0], 10.0);
+        assert_eq!(multiply_matrices(translate, scale)[3][0], 5.0);
+    }
+
+    #[test]
+    fn test_invert_matrix_recovers_view_direction() {
+        let camera = Camera::new();
+        let view_proj = multiply_matrices(projection_matrix(16.0 / 9.0), camera.view_matrix());
+        let inverse = invert_matrix(view_proj);
+
+        let identity = multiply_matrices(view_proj, inverse);
+        for col in 0..4 {
+            for row in 0..4 {
+                let expected = if col == row { 1.0 } else { 0.0 };
+                assert!((identity[col][row] - expected).abs() < 1e-3, "{:?}", identity);
+            }
+        }
+
+        // The centre of the far plane lies straight along the look direction,
+        // as the sky shader assumes when reconstructing view rays
+        let far: Vec<f32> = (0..4).map(|row| inverse[2][row] + inverse[3][row]).collect();
+        let point = [far[0] / far[3], far[1] / far[3], far[2] / far[3]];
+        let offset = [0, 1, 2].map(|i| point[i] - camera.position[i]);
+        let length = offset.iter().map(|v| v * v).sum::<f32>().sqrt();
+        let look = camera.forward();
+        for i in 0..3 {
+            assert!((offset[i] / length - look[i]).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_frustum_contains_aabb() {
+        // yaw 0 looks down -z
+        let frustum = camera_frustum([0.0, 0.0, 0.0], 0.0);
+
+        assert!(frustum.contains_aabb([-1.0, -1.0, -11.0], [1.0, 1.0, -9.0]));
+        // Behind the camera
+        assert!(!frustum.contains_aabb([-1.0, -1.0, 9.0], [1.0, 1.0, 11.0]));
+        // Far off to the side
+        assert!(!frustum.contains_aabb([100.0, -1.0, -11.0], [102.0, 1.0, -9.0]));
+        // Beyond the far plane
+        assert!(!frustum.contains_aabb([-1.0, -1.0, -1200.0], [1.0, 1.0, -1100.0]));
+        // Straddling the left plane
+        assert!(frustum.contains_aabb([-50.0, -1.0, -11.0], [0.0, 1.0, -9.0]));
+        // Containing the camera
+        assert!(frustum.contains_aabb([-1.0, -1.0, -1.0], [1.0, 1.0, 1.0]));
+    }
+
+    #[test]
+    fn test_frustum_culls_most_chunks_around_camera() {
+        // 16-chunk radius around a camera at the centre of the world
+        const RADIUS: i32 = 16;
+        let extent = (RADIUS * CHUNK_SIZE as i32) as f32;
+        let frustum = camera_frustum([extent, extent, extent], -45.0);
+
+        let mut total = 0;
+        let mut visible = 0;
+        for cx in 0..RADIUS * 2 {
+            for cy in 0..RADIUS * 2 {
+                for cz in 0..RADIUS * 2 {
+                    let min = [
+                        (cx * CHUNK_SIZE as i32) as f32,
+                        (cy * CHUNK_SIZE as i32) as f32,
+                        (cz * CHUNK_SIZE as i32) as f32,
+                    ];
+                    let max = [min[0] + CHUNK_SIZE as f32, min[1] + CHUNK_SIZE as f32, min[2] + CHUNK_SIZE as f32];
+                    total += 1;
+                    if frustum.contains_aabb(min, max) {
+                        visible += 1;
+                    }
+                }
+            }
+        }
+
+        println!("frustum culling: drawing {} of {} chunks", visible, total);
+        assert!(visible > 0);
+        assert!(visible * 4 < total);
+    }
+
+    #[test]
+    fn test_ambient_occlusion_darkens_concave_corner() {
+        // A floor with walls along x = 0 and z = 0 meeting in a corner
+        let mut world = VoxelWorld::empty(8);
+        for a in 0..8 {
+            for b in 0..8 {
+                world.set_voxel(a, 0, b, Some(registry::STONE));
+            }
+            world.set_voxel(0, 1, a, Some(registry::STONE));
+            world.set_voxel(a, 1, 0, Some(registry::STONE));
+        }
+
+        for (mesh, _) in [world.generate_mesh(), world.generate_mesh_naive()] {
+            let floor_vertex = |position: [f32; 3]| {
+                mesh.iter()
+                    .find(|v| v.position == position && v.normal == [0.0, 1.0, 0.0])
+                    .map(|v| v.ao)
+                    .unwrap()
+            };
+
+            // Where the floor meets both walls
+            assert!(floor_vertex([1.0, 1.0, 1.0]) > 0.5);
+            // Open floor far from the walls
+            assert_eq!(floor_vertex([8.0, 1.0, 8.0]), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_mesh_uses_registered_type_colors() {
+        let mut types = VoxelRegistry::with_builtin_types();
+        let lava = types.register(registry::VoxelDefinition::new("lava", [1.0, 0.4, 0.0], true, false, 0.0));
+        let mut world = VoxelWorld::from_voxels(vec![vec![vec![None; 4]; 4]; 4], 4, Arc::new(types));
+        world.set_voxel(1, 1, 1, Some(lava));
+
+        let (vertices, _) = world.generate_mesh();
+        assert_eq!(vertices.len(), 24);
+        assert!(vertices.iter().all(|v| v.color == [1.0, 0.4, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn test_crystals_are_instanced_instead_of_meshed() {
+        let mut world = flat_grass_world(8);
+        let flat_vertices = world.generate_mesh().0.len();
+        world.set_voxel(3, 1, 3, Some(registry::CRYSTAL));
+
+        // The grass under the crystal keeps its top face and no cube is added
+        assert_eq!(world.generate_mesh().0.len(), flat_vertices);
+        assert_eq!(world.generate_mesh_naive().0.len(), (8 * 8 + 8 * 8 + 4 * 8) * 4);
+        assert_eq!(world.generate_crystal_instances(), [crystal::CrystalInstance::at([3, 1, 3])]);
+    }
+
+    /// Rolling hills with per-column noise, so meshes have real surface detail
+    fn bumpy_world(size: usize) -> VoxelWorld {
+        let mut world = VoxelWorld::empty(size);
+        for x in 0..size {
+            for z in 0..size {
+                let noise = (x * 7 + z * 13 + (x * z) % 5) % 3;
+                let height = 6 + ((x as f32 * 0.4).sin() * 3.0 + (z as f32 * 0.3).cos() * 3.0) as usize + noise;
+                for y in 0..height.min(size) {
+                    world.voxels[x][y][z] = Some(if y + 1 == height { registry::GRASS } else { registry::STONE });
+                }
+            }
+        }
+        world
+    }
+
+    #[test]
+    fn test_lod_meshes_have_fewer_vertices() {
+        let world = bumpy_world(48);
+        let origin = [16, 0, 16];
+
+        let (full, _) = world.generate_lod_mesh(origin, CHUNK_SIZE, 0);
+        let (lod1, _) = world.generate_lod_mesh(origin, CHUNK_SIZE, 1);
+        let (lod2, _) = world.generate_lod_mesh(origin, CHUNK_SIZE, 2);
+
+        assert!(!lod2.is_empty());
+        assert!(lod1.len() < full.len());
+        assert!(lod2.len() < lod1.len());
+
+        // Coarse meshes stay inside the chunk
+        for vertex in lod1.iter().chain(&lod2) {
+            for (&position, &min) in vertex.position.iter().zip(&origin) {
+                assert!(position >= min as f32);
+                assert!(position <= (min + CHUNK_SIZE) as f32);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lod_zero_matches_full_resolution() {
+        let world = VoxelWorld::new(32);
+        let (min, max) = world.chunk_bounds(&world.chunks[0]);
+
+        let full = triangles(&world.generate_region_mesh(min, max));
+        let lod0 = triangles(&world.generate_lod_mesh(world.chunks[0].origin, CHUNK_SIZE, 0));
+        assert_eq!(area_by_normal(&lod0), area_by_normal(&full));
+    }
+
+    #[test]
+    fn test_lod_preserves_flat_surface_height() {
+        let world = flat_grass_world(32);
+        let (mesh, _) = world.generate_lod_mesh([0; 3], CHUNK_SIZE, 2);
+
+        // A one-voxel floor is under half of each 4³ block, so it drops out entirely
+        assert!(mesh.is_empty());
+
+        let mut thick = VoxelWorld::empty(32);
+        for x in 0..32 {
+            for z in 0..32 {
+                for y in 0..8 {
+                    thick.set_voxel(x, y, z, Some(registry::STONE));
+                }
+            }
+        }
+        let (mesh, _) = thick.generate_lod_mesh([0; 3], CHUNK_SIZE, 2);
+        let top = mesh.iter().filter(|v| v.normal == [0.0, 1.0, 0.0]);
+        assert!(top.into_iter().all(|v| v.position[1] == 8.0));
+    }
+
+    #[test]
+    fn test_chunk_lods_follow_camera_distance() {
+        let mut world = VoxelWorld::new(128);
+        clear_dirty(&mut world);
+
+        assert!(world.update_chunk_lods([8.0, 8.0, 8.0]) > 0);
+        assert_eq!(world.chunks[world.chunk_index(0, 0, 0)].lod, 0);
+        assert_eq!(world.chunks[world.chunk_index(3, 0, 0)].lod, 1);
+        assert_eq!(world.chunks[world.chunk_index(7, 7, 7)].lod, 2);
+        assert!(world.chunks[world.chunk_index(7, 7, 7)].dirty);
+
+        // Standing still doesn't trigger rebuilds
+        clear_dirty(&mut world);
+        assert_eq!(world.update_chunk_lods([8.0, 8.0, 8.0]), 0);
+        assert!(dirty_chunks(&world).is_empty());
+    }
+
+    #[test]
+    fn test_generated_worlds_are_deterministic() {
+        let a = VoxelWorld::generate(48, &TerrainGenerator::new(1234, 4, 0.5, 2.0), 12);
+        let b = VoxelWorld::generate(48, &TerrainGenerator::new(1234, 4, 0.5, 2.0), 12);
+        let c = VoxelWorld::generate(48, &TerrainGenerator::new(4321, 4, 0.5, 2.0), 12);
+
+        assert_eq!(a.voxels, b.voxels);
+        assert_ne!(a.voxels, c.voxels);
+    }
+
+    #[test]
+    fn test_generated_terrain_layers() {
+        let sea_level = 12;
+        let world = VoxelWorld::generate(48, &TerrainGenerator::new(1234, 4, 0.5, 2.0), sea_level);
+
+        for x in 0..world.size {
+            for z in 0..world.size {
+                let column: Vec<Option<VoxelId>> = (0..world.size).map(|y| world.voxels[x][y][z]).collect();
+                let top = column.iter().rposition(|v| v.is_some()).unwrap();
+
+                // Solid ground from the bottom up with nothing floating above
+                assert!(column[..=top].iter().all(|v| v.is_some()));
+                assert_eq!(column[0], Some(registry::STONE));
+
+                // Water only at or below sea level, and always filled up to it
+                for (y, voxel) in column.iter().enumerate() {
+                    if *voxel == Some(registry::WATER) {
+                        assert!(y <= sea_level);
+                    }
+                }
+                assert!(column[sea_level].is_some());
+
+                // Grass only on the surface, never below the waterline
+                for (y, voxel) in column.iter().enumerate() {
+                    if *voxel == Some(registry::GRASS) {
+                        assert!(y >= sea_level);
+                        assert!(y == top || (y + 1 == top && column[top] == Some(registry::CRYSTAL)));
+                    }
+                }
+            }
+        }
+    }
+
+    fn assert_vector(actual: [f32; 3], expected: [f32; 3]) {
+        for axis in 0..3 {
+            assert!((actual[axis] - expected[axis]).abs() < 1e-5, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_camera_vectors_at_canonical_orientations() {
+        let cases = [
+            (0.0_f32, [0.0, 0.0, -1.0], [1.0, 0.0, 0.0]),
+            (90.0, [-1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+            (-90.0, [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+            (180.0, [0.0, 0.0, 1.0], [-1.0, 0.0, 0.0]),
+        ];
+        for (yaw, forward, right) in cases {
+            let mut camera = Camera { position: [0.0; 3], yaw: yaw.to_radians(), pitch: 0.0 };
+            assert_vector(camera.forward(), forward);
+            assert_vector(camera.right(), right);
+
+            // At either pitch limit forward tips almost vertical but stays a
+            // unit vector; right stays level and square to it
+            for pitch in [MAX_PITCH, -MAX_PITCH] {
+                camera.pitch = pitch;
+                let look = camera.forward();
+                assert!((look.iter().map(|v| v * v).sum::<f32>() - 1.0).abs() < 1e-5);
+                assert!((look[1] - pitch.sin()).abs() < 1e-6);
+                assert_vector(camera.right(), right);
+                assert!((0..3).map(|axis| look[axis] * right[axis]).sum::<f32>().abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_camera_moves_relative_to_facing() {
+        let mut camera = Camera { position: [0.0; 3], yaw: 90.0_f32.to_radians(), pitch: -MAX_PITCH };
+        // Looking almost straight down still walks level
+        camera.move_forward(2.0);
+        assert_vector(camera.position, [-2.0, 0.0, 0.0]);
+        camera.move_right(3.0);
+        assert_vector(camera.position, [-2.0, 0.0, -3.0]);
+        camera.move_up(1.5);
+        assert_vector(camera.position, [-2.0, 1.5, -3.0]);
+    }
+
+    #[test]
+    fn test_mouse_look_clamps_pitch() {
+        let mut camera = Camera::new();
+        let yaw = camera.yaw;
+
+        camera.rotate((100.0, 0.0), 0.01);
+        assert!((camera.yaw - (yaw + 1.0)).abs() < 1e-5);
+
+        // Dragging the mouse far up or down stops just short of vertical
+        camera.rotate((0.0, -100_000.0), 0.01);
+        assert_eq!(camera.pitch, MAX_PITCH);
+        camera.rotate((0.0, 100_000.0), 0.01);
+        assert_eq!(camera.pitch, -MAX_PITCH);
+        assert!(camera.forward()[1] > -1.0);
+    }
+
+    #[test]
+    fn test_indexed_mesh_shares_quad_corners() {
+        let mut world = VoxelWorld::empty(4);
+        world.set_voxel(1, 1, 1, Some(registry::STONE));
+
+        // An isolated voxel exposes all 6 faces: 4 corners and 6 indices each
+        let (vertices, indices) = world.generate_mesh();
+        assert_eq!(vertices.len(), 6 * 4);
+        assert_eq!(indices.len(), 6 * 6);
+
+        for (face, quad) in indices.chunks(6).enumerate() {
+            let base = (face * 4) as u32;
+            assert_eq!(quad, [base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+    }
+
+    #[test]
+    fn test_greedy_mesh_does_not_merge_different_types() {
+        let mut world = VoxelWorld::empty(4);
+        world.voxels[0][0][0] = Some(registry::STONE);
+        world.voxels[1][0][0] = Some(registry::DIRT);
+
+        // The two voxels share no coplanar same-type faces, so nothing merges:
+        // 5 exposed faces each
+        assert_eq!(world.generate_mesh().1.len(), 10 * 6);
+    }
+
+    /// A 4x4 pool of water two voxels deep on a stone floor
+    fn pond_world() -> VoxelWorld {
+        let mut world = VoxelWorld::empty(8);
+        for x in 0..4 {
+            for z in 0..4 {
+                world.voxels[x][0][z] = Some(registry::STONE);
+                world.voxels[x][1][z] = Some(registry::WATER);
+                world.voxels[x][2][z] = Some(registry::WATER);
+            }
+        }
+        world
+    }
+
+    #[test]
+    fn test_water_meshes_into_transparent_layer() {
+        let world = pond_world();
+        let mesh = world.generate_region_layers([0; 3], [8; 3]);
+
+        assert!(mesh.opaque.0.iter().all(|v| v.color[3] == 1.0));
+        assert!(!mesh.transparent.0.is_empty());
+        assert!(mesh.transparent.0.iter().all(|v| v.color == world.registry.rgba(registry::WATER)));
+
+        // Water faces inside the pool and against the stone floor are hidden,
+        // leaving one merged quad for the surface and one per side
+        assert_eq!(mesh.transparent.1.len(), 5 * 6);
+
+        // The stone floor shows through the water: its top face is meshed
+        let stone_tops = triangles(&mesh.opaque)
+            .iter()
+            .filter(|v| v.normal == [0.0, 1.0, 0.0] && v.position[1] == 1.0)
+            .count();
+        assert!(stone_tops > 0);
+    }
+
+    #[test]
+    fn test_transparent_faces_sort_back_to_front() {
+        let mut world = VoxelWorld::empty(16);
+        for x in 0..16 {
+            world.voxels[x][0][x] = Some(registry::WATER);
+        }
+        let camera = [0.0, 2.0, 0.0];
+        let (vertices, mut indices) = world.generate_region_layers([0; 3], [16; 3]).transparent;
+
+        assert!(sort_quads_back_to_front(&vertices, &mut indices, camera));
+        let distances: Vec<f32> = indices
+            .chunks(6)
+            .map(|quad| {
+                let corners = [quad[0], quad[1], quad[2], quad[5]];
+                (0..3)
+                    .map(|axis| {
+                        let center = corners.iter().map(|&i| vertices[i as usize].position[axis]).sum::<f32>() / 4.0;
+                        (center - camera[axis]).powi(2)
+                    })
+                    .sum()
+            })
+            .collect();
+        assert!(distances.windows(2).all(|pair| pair[0] >= pair[1]));
+
+        // Already sorted, so a second pass changes nothing
+        assert!(!sort_quads_back_to_front(&vertices, &mut indices, camera));
+    }
+
+    /// Meshes the transparent faces of every chunk, as `upload_dirty_chunks`
+    /// does without needing a device
+    fn mesh_transparent_chunks(world: &mut VoxelWorld) {
+        for index in 0..world.chunks.len() {
+            let (min, max) = world.chunk_bounds(&world.chunks[index]);
+            world.chunks[index].transparent_mesh = world.generate_region_layers(min, max).transparent;
+        }
+    }
+
+    #[test]
+    fn test_transparent_chunks_sort_by_distance_bucket() {
+        // Water in a chunk around the camera, one 32 voxels away and one 80
+        // voxels away
+        let mut world = VoxelWorld::empty(96);
+        for x in [4, 36, 84] {
+            world.voxels[x][2][4] = Some(registry::WATER);
+            world.voxels[x + 1][2][4] = Some(registry::WATER);
+        }
+        mesh_transparent_chunks(&mut world);
+        let settings = RenderSettings::default();
+        let sorted_from = |world: &VoxelWorld, x: usize| world.chunks[world.chunk_index(x / CHUNK_SIZE, 0, 0)].transparent_sorted_from;
+
+        let camera = [6.0, 3.0, 6.0];
+        world.sort_transparent_faces(camera, &settings);
+        assert_eq!(sorted_from(&world, 4), Some(camera));
+        assert_eq!(sorted_from(&world, 36), Some(camera));
+        assert_eq!(sorted_from(&world, 84), None);
+
+        // A small step only re-sorts the nearby chunk
+        let nudged = [7.0, 3.0, 6.0];
+        world.sort_transparent_faces(nudged, &settings);
+        assert_eq!(sorted_from(&world, 4), Some(nudged));
+        assert_eq!(sorted_from(&world, 36), Some(camera));
+
+        let moved = [9.0, 3.0, 6.0];
+        world.sort_transparent_faces(moved, &settings);
+        assert_eq!(sorted_from(&world, 36), Some(moved));
+        assert_eq!(sorted_from(&world, 84), None);
+
+        // Everything is near once the near distance covers the world
+        let settings = RenderSettings { transparency_near_distance: 200.0, ..settings };
+        world.sort_transparent_faces(moved, &settings);
+        assert_eq!(sorted_from(&world, 84), Some(moved));
+    }
+
+    /// Draws two transparent voxels on the camera's line of sight, the far
+    /// one water and the near one red, and checks the near one ends up on top
+    #[test]
+    fn test_nearer_transparent_voxel_is_drawn_on_top() {
+        let instance = wgpu::Instance::default();
+        let Some(adapter) = pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(&instance, None))
+        else {
+            eprintln!("No GPU adapter available, skipping transparency test");
+            return;
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap();
+
+        let mut world = VoxelWorld::empty(16);
+        let red = world
+            .registry_mut()
+            .register(registry::VoxelDefinition::new("red water", [1.0, 0.0, 0.0], false, false, 0.0).with_alpha(0.65));
+        // Looking along +z, so the near voxel is meshed first and drawing in
+        // meshing order would put the water on top
+        world.set_voxel(8, 8, 3, Some(red));
+        world.set_voxel(8, 8, 7, Some(registry::WATER));
+        let camera = Camera { position: [8.5, 8.5, 0.5], yaw: std::f32::consts::PI, pitch: 0.0 };
+        world.upload_dirty_chunks(&device, &queue);
+        world.sort_transparent_faces(camera.position, &RenderSettings::default());
+        world.upload_dirty_chunks(&device, &queue);
+
+        let (width, height) = (32, 32);
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view_proj = multiply_matrices(projection_matrix(1.0), camera.view_matrix());
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&[view_proj]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        // Flat colours, blended the same way as the transparent voxel pipeline
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(
+                r#"
+@group(0) @binding(0)
+var<uniform> view_proj: mat4x4<f32>;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>, @location(2) color: vec4<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = view_proj * vec4<f32>(position, 1.0);
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
+"#
+                .into(),
+            ),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: target.format(),
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[wgpu::BindGroupEntry { binding: 0, resource: uniform_buffer.as_entire_binding() }],
+        });
+
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            let chunk = &world.chunks[0];
+            pass.set_vertex_buffer(0, chunk.transparent_vertex_buffer.as_ref().unwrap().slice(..));
+            pass.set_index_buffer(chunk.transparent_index_buffer.as_ref().unwrap().slice(..), wgpu::IndexFormat::Uint32);
+            pass.draw_indexed(0..chunk.transparent_mesh.1.len() as u32, 0, 0..1);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let pixels = screenshot::read_texture_rgba(&target, width, height, &device, &queue).unwrap();
+        let centre = ((height / 2 * width + width / 2) * 4) as usize;
+        let [r, _, b, _] = [0, 1, 2, 3].map(|channel| pixels[centre + channel]);
+        assert!(r > 2 * b, "centre pixel {:?}", &pixels[centre..centre + 4]);
+    }
+
+    #[test]
+    fn test_glowstone_near_camera_fills_light_buffer() {
+        let mut world = VoxelWorld::empty(32);
+        // More glowing voxels far away than there are light slots, plus a
+        // crystal, which lights the world through its instance instead
+        for x in 0..12 {
+            world.set_voxel(x * 2, 0, 31, Some(registry::GLOWSTONE));
+        }
+        world.set_voxel(6, 6, 6, Some(registry::CRYSTAL));
+        world.set_voxel(5, 5, 5, Some(registry::GLOWSTONE));
+        let eye = [5.5, 6.5, 7.0];
+
+        let sources = world.emissive_sources([0; 3], [32; 3]);
+        assert_eq!(sources.len(), 13);
+        let near: Vec<_> = sources
+            .iter()
+            .filter(|source| (0..3).map(|axis| (source.position[axis] - eye[axis]).powi(2)).sum::<f32>() < 4.0)
+            .collect();
+        assert_eq!(near.len(), 1);
+        assert_eq!(near[0].radius, world.registry.get(registry::GLOWSTONE).unwrap().glow_radius);
+
+        let sun = PointLight::directional(lighting::SUN_DIRECTION, lighting::SUN_COLOR, 0.0);
+        let mut lights = LightBuffer::new(sun, [[0.0; 4]; 4]);
+        let candidates: Vec<PointLight> = sources.iter().map(EmissiveLightSource::point_light).collect();
+        lights.add_nearest(&candidates, eye);
+        assert_eq!(lights.lights().len(), lighting::MAX_LIGHTS);
+        let glowstone = lights.lights().iter().find(|light| light.position[..3] == [5.5, 5.5, 5.5]).unwrap();
+        assert_eq!(glowstone.color[..3], world.registry.color(registry::GLOWSTONE));
+    }
+
+    #[test]
+    fn test_scatter_glowstone_lands_on_solid_ground() {
+        let mut world = VoxelWorld::empty(32);
+        for x in 0..32 {
+            for z in 0..32 {
+                world.set_voxel(x, 0, z, Some(if x < 16 { registry::STONE } else { registry::WATER }));
+            }
+        }
+
+        let edits = scatter_glowstone(&mut world, [8.0, 4.0, 16.0], 16.0, GLOW_DEMO_COUNT, 3);
+        assert_eq!(edits.len(), GLOW_DEMO_COUNT);
+        for edit in &edits {
+            let (x, y, z) = edit.coord;
+            assert_eq!(world.get(x, y, z), Some(registry::GLOWSTONE));
+            // Stacked on earlier glowstone at worst, never floating on water
+            assert!(matches!(world.get(x, y - 1, z), Some(registry::STONE | registry::GLOWSTONE)), "{:?}", edit.coord);
+        }
+    }
+}
@@ -0,0 +1,556 @@
+// Lighting
+// Up to eight lights shade the voxel world. The first is always the sun, a
+// directional light that also casts shadows: each frame the opaque chunks are
+// rendered from the sun's point of view into a depth-only shadow map, which
+// the voxel passes sample with 3×3 percentage-closer filtering. The remaining
+// slots hold point lights, falling off with the inverse square of distance:
+// crystals, and voxels of emissive types with a glow radius.
+
+use crate::render_graph::RenderGraph;
+use crate::{multiply_matrices, Vertex};
+use bytemuck::Zeroable;
+use std::rc::Rc;
+
+pub const MAX_LIGHTS: usize = 8;
+/// Edge length of the sun's shadow map in texels
+pub const SHADOW_MAP_SIZE: u32 = 1024;
+pub const SHADOW_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+/// Render graph pass drawing the shadow map
+pub const SHADOW_PASS: &str = "shadows";
+
+/// Points from the world towards the sun; normalized where it's used
+pub const SUN_DIRECTION: [f32; 3] = [0.4, 1.0, 0.3];
+pub const SUN_COLOR: [f32; 3] = [1.0, 0.96, 0.88];
+/// Brightness of the light cast by each glowing voxel
+pub const EMISSIVE_INTENSITY: f32 = 5.0;
+
+/// Light structs and the `shade_lights` function used by the voxel shader.
+/// Binds the lights at group 0 binding 3 and the shadow map and its
+/// comparison sampler at bindings 4 and 5.
+pub const LIGHTING_WGSL: &str = r#"
+const MAX_LIGHTS: u32 = 8u;
+
+struct PointLight {
+    // w = 0 marks a directional light, with xyz pointing towards it
+    position: vec4<f32>,
+    color: vec4<f32>,
+    intensity: f32,
+    radius: f32,
+}
+
+struct Lights {
+    sun_view_proj: mat4x4<f32>,
+    lights: array<PointLight, MAX_LIGHTS>,
+    count: u32,
+}
+
+@group(0) @binding(3)
+var<uniform> lights: Lights;
+@group(0) @binding(4)
+var shadow_map: texture_depth_2d;
+@group(0) @binding(5)
+var shadow_sampler: sampler_comparison;
+
+// Fraction of the sun reaching `position`, averaged over the 3×3 shadow map
+// texels around it so shadow edges are soft rather than stair-stepped
+fn sun_visibility(position: vec3<f32>, normal: vec3<f32>) -> f32 {
+    // Nudged off the surface so faces don't shadow themselves
+    let clip = lights.sun_view_proj * vec4<f32>(position + normal * 0.05, 1.0);
+    let ndc = clip.xyz / clip.w;
+    let uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) || ndc.z > 1.0 {
+        return 1.0;
+    }
+
+    let texel = 1.0 / vec2<f32>(textureDimensions(shadow_map));
+    var visible = 0.0;
+    for (var x = -1; x <= 1; x++) {
+        for (var y = -1; y <= 1; y++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel;
+            visible += textureSampleCompareLevel(shadow_map, shadow_sampler, uv + offset, ndc.z);
+        }
+    }
+    return visible / 9.0;
+}
+
+fn shade_light(light: PointLight, albedo: vec3<f32>, position: vec3<f32>, normal: vec3<f32>, view_dir: vec3<f32>) -> vec3<f32> {
+    var light_dir = normalize(light.position.xyz);
+    var attenuation = 1.0;
+    if light.position.w != 0.0 {
+        let offset = light.position.xyz - position;
+        let distance = length(offset);
+        light_dir = offset / max(distance, 0.0001);
+        // Inverse-square falloff, windowed to reach zero at the light's radius
+        let window = clamp(1.0 - pow(distance / light.radius, 4.0), 0.0, 1.0);
+        attenuation = window * window / (distance * distance + 1.0);
+    }
+
+    let diff = max(dot(normal, light_dir), 0.0);
+    let reflect_dir = reflect(-light_dir, normal);
+    let spec = select(0.0, pow(max(dot(view_dir, reflect_dir), 0.0), 32.0), diff > 0.0);
+    return (diff * albedo + vec3<f32>(0.3) * spec) * light.color.rgb * light.intensity * attenuation;
+}
+
+// Diffuse and specular light from every light, with the sun shadowed
+fn shade_lights(albedo: vec3<f32>, position: vec3<f32>, normal: vec3<f32>, view_dir: vec3<f32>) -> vec3<f32> {
+    var total = vec3<f32>(0.0);
+    for (var i = 0u; i < min(lights.count, MAX_LIGHTS); i++) {
+        var contribution = shade_light(lights.lights[i], albedo, position, normal, view_dir);
+        if i == 0u {
+            contribution *= sun_visibility(position, normal);
+        }
+        total += contribution;
+    }
+    return total;
+}
+"#;
+
+const SHADOW_SHADER: &str = r#"
+@group(0) @binding(0)
+var<uniform> sun_view_proj: mat4x4<f32>;
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+    return sun_view_proj * vec4<f32>(position, 1.0);
+}
+"#;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PointLight {
+    /// World position with w = 1, or the direction towards a directional
+    /// light with w = 0
+    pub position: [f32; 4],
+    pub color: [f32; 4],
+    pub intensity: f32,
+    /// Distance at which the light has faded out completely
+    pub radius: f32,
+    /// Rounds the struct up to the 16-byte stride of a uniform array
+    _padding: [f32; 2],
+}
+
+impl PointLight {
+    pub fn new(position: [f32; 3], color: [f32; 3], intensity: f32, radius: f32) -> Self {
+        let [x, y, z] = position;
+        let [r, g, b] = color;
+        Self {
+            position: [x, y, z, 1.0],
+            color: [r, g, b, 1.0],
+            intensity,
+            radius,
+            _padding: [0.0; 2],
+        }
+    }
+
+    /// A light infinitely far away in `direction`, unattenuated
+    pub fn directional(direction: [f32; 3], color: [f32; 3], intensity: f32) -> Self {
+        let [x, y, z] = normalize(direction);
+        Self {
+            position: [x, y, z, 0.0],
+            radius: f32::INFINITY,
+            ..Self::new([0.0; 3], color, intensity, 0.0)
+        }
+    }
+
+    fn is_directional(&self) -> bool {
+        self.position[3] == 0.0
+    }
+}
+
+/// A voxel that lights its surroundings, recorded when its chunk is meshed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EmissiveLightSource {
+    /// Centre of the voxel
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+    /// The voxel type's glow radius
+    pub radius: f32,
+}
+
+impl EmissiveLightSource {
+    pub fn point_light(&self) -> PointLight {
+        PointLight::new(self.position, self.color, EMISSIVE_INTENSITY, self.radius)
+    }
+}
+
+/// The uniform block behind `LIGHTING_WGSL`'s `lights`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightBuffer {
+    /// Clip space of the sun's shadow map
+    pub sun_view_proj: [[f32; 4]; 4],
+    lights: [PointLight; MAX_LIGHTS],
+    count: u32,
+    _padding: [u32; 3],
+}
+
+impl LightBuffer {
+    /// Lights holding just `sun`, which stays first and is the only light
+    /// that casts shadows
+    pub fn new(sun: PointLight, sun_view_proj: [[f32; 4]; 4]) -> Self {
+        let mut lights = [PointLight::zeroed(); MAX_LIGHTS];
+        lights[0] = sun;
+        Self {
+            sun_view_proj,
+            lights,
+            count: 1,
+            _padding: [0; 3],
+        }
+    }
+
+    /// Adds a light, returning false if all `MAX_LIGHTS` slots are taken
+    pub fn add_light(&mut self, light: PointLight) -> bool {
+        let Some(slot) = self.lights.get_mut(self.count as usize) else {
+            return false;
+        };
+        *slot = light;
+        self.count += 1;
+        true
+    }
+
+    /// Fills the free slots from `candidates`, nearest to `eye` first.
+    /// Directional candidates count as infinitely far.
+    pub fn add_nearest(&mut self, candidates: &[PointLight], eye: [f32; 3]) {
+        let distance = |light: &PointLight| {
+            if light.is_directional() {
+                f32::INFINITY
+            } else {
+                (0..3).map(|axis| (light.position[axis] - eye[axis]).powi(2)).sum()
+            }
+        };
+        let mut candidates: Vec<(f32, &PointLight)> = candidates.iter().map(|light| (distance(light), light)).collect();
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (_, &light) in candidates {
+            if !self.add_light(light) {
+                break;
+            }
+        }
+    }
+
+    #[cfg(test)]
+    pub fn lights(&self) -> &[PointLight] {
+        &self.lights[..self.count as usize]
+    }
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    v.map(|c| c / length)
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+/// Orthographic view-projection looking along `-direction` at a sphere of
+/// `radius` around `center`, which it fits exactly: the sphere spans clip x
+/// and y from -1 to 1 and depth from 0 (nearest the sun) to 1.
+pub fn sun_view_proj(direction: [f32; 3], center: [f32; 3], radius: f32) -> [[f32; 4]; 4] {
+    let back = normalize(direction);
+    // Any up vector not parallel to the view will do
+    let up = if back[1].abs() > 0.99 { [0.0, 0.0, 1.0] } else { [0.0, 1.0, 0.0] };
+    let right = normalize(cross(up, back));
+    let up = cross(back, right);
+    let eye = [0, 1, 2].map(|axis| center[axis] + back[axis] * radius);
+    let dot = |a: [f32; 3], b: [f32; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+
+    let view = [
+        [right[0], up[0], back[0], 0.0],
+        [right[1], up[1], back[1], 0.0],
+        [right[2], up[2], back[2], 0.0],
+        [-dot(right, eye), -dot(up, eye), -dot(back, eye), 1.0],
+    ];
+    // The eye sits on the sphere's surface, so depth runs from 0 to 2 × radius
+    let projection = [
+        [1.0 / radius, 0.0, 0.0, 0.0],
+        [0.0, 1.0 / radius, 0.0, 0.0],
+        [0.0, 0.0, -0.5 / radius, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ];
+    multiply_matrices(projection, view)
+}
+
+/// The sun's depth map, rendered before the voxel passes each frame
+pub struct ShadowMap {
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    view: wgpu::TextureView,
+    sampler: wgpu::Sampler,
+}
+
+impl ShadowMap {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Map"),
+            size: wgpu::Extent3d {
+                width: SHADOW_MAP_SIZE,
+                height: SHADOW_MAP_SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: SHADOW_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        // Linear filtering blends the four nearest comparisons, smoothing the
+        // 3×3 kernel a little further
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shadow Uniform Buffer"),
+            size: std::mem::size_of::<[[f32; 4]; 4]>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Shadow Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shadow Shader"),
+            source: wgpu::ShaderSource::Wgsl(SHADOW_SHADER.into()),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shadow Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc()],
+                compilation_options: Default::default(),
+            },
+            // Depth only
+            fragment: None,
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: SHADOW_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                // Pushes stored depths back, more so on surfaces at a grazing
+                // angle to the sun, so lit faces don't fall in their own shadow
+                bias: wgpu::DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            uniform_buffer,
+            bind_group,
+            view,
+            sampler,
+        }
+    }
+
+    /// The depth texture, for binding as `shadow_map` in the voxel passes
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    /// The comparison sampler to bind as `shadow_sampler`
+    pub fn sampler(&self) -> &wgpu::Sampler {
+        &self.sampler
+    }
+
+    /// Moves the sun's camera; pass the `sun_view_proj` the voxel passes sample with
+    pub fn update(&self, queue: &wgpu::Queue, sun_view_proj: [[f32; 4]; 4]) {
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[sun_view_proj]));
+    }
+
+    /// Starts a pass that clears the shadow map, ready to draw shadow-casting
+    /// geometry with `Vertex` buffers. End it before any pass samples the map.
+    pub fn begin_pass<'a>(&'a self, encoder: &'a mut wgpu::CommandEncoder) -> wgpu::RenderPass<'a> {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Shadow Pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass
+    }
+
+    /// Adds the pass that renders every resident chunk's opaque mesh into the
+    /// shadow map, whether or not the camera sees it
+    pub fn register(shadow_map: &Rc<Self>, graph: &mut RenderGraph) -> usize {
+        let shadow_map = shadow_map.clone();
+        graph.add_node(SHADOW_PASS, move |encoder, context| {
+            let mut pass = shadow_map.begin_pass(encoder);
+            for chunk in context.world.chunks.iter().filter(|chunk| chunk.resident) {
+                if let (Some(vertex_buffer), Some(index_buffer)) = (&chunk.vertex_buffer, &chunk.index_buffer) {
+                    pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                    pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                    pass.draw_indexed(0..chunk.index_count, 0, 0..1);
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::util::DeviceExt;
+
+    #[test]
+    fn test_light_buffer_matches_uniform_layout() {
+        // WGSL rounds PointLight up to 48 bytes and Lights to a multiple of 16
+        assert_eq!(std::mem::size_of::<PointLight>(), 48);
+        assert_eq!(std::mem::size_of::<LightBuffer>(), 64 + 48 * MAX_LIGHTS + 16);
+
+        let sun = PointLight::directional(SUN_DIRECTION, SUN_COLOR, 1.0);
+        let mut lights = LightBuffer::new(sun, [[0.0; 4]; 4]);
+        let candidates: Vec<PointLight> =
+            (0..10).rev().map(|i| PointLight::new([i as f32 * 4.0, 0.0, 0.0], [1.0; 3], 2.0, 8.0)).collect();
+        lights.add_nearest(&candidates, [0.0; 3]);
+
+        assert_eq!(lights.lights().len(), MAX_LIGHTS);
+        assert_eq!(lights.lights()[0], sun);
+        // The nearest seven, in order, with the three farthest left out
+        let xs: Vec<f32> = lights.lights()[1..].iter().map(|light| light.position[0]).collect();
+        assert_eq!(xs, [0.0, 4.0, 8.0, 12.0, 16.0, 20.0, 24.0]);
+        assert!(!lights.add_light(candidates[0]));
+    }
+
+    #[test]
+    fn test_shadow_pass_renders_voxel_mesh() {
+        let instance = wgpu::Instance::default();
+        let Some(adapter) = pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(&instance, None))
+        else {
+            eprintln!("No GPU adapter available, skipping shadow map test");
+            return;
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap();
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let mut world = crate::VoxelWorld::empty(8);
+        for x in 0..8 {
+            for z in 0..8 {
+                world.set_voxel(x, 0, z, Some(crate::registry::STONE));
+            }
+        }
+        world.set_voxel(3, 4, 3, Some(crate::registry::DIRT));
+        let (vertices, indices) = world.generate_mesh();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let shadow_map = ShadowMap::new(&device);
+        shadow_map.update(&queue, sun_view_proj(SUN_DIRECTION, [4.0; 3], 4.0 * 3.0f32.sqrt()));
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = shadow_map.begin_pass(&mut encoder);
+            pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            pass.draw_indexed(0..indices.len() as u32, 0, 0..1);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+        device.poll(wgpu::Maintain::Wait);
+
+        let error = pollster::block_on(device.pop_error_scope());
+        assert!(error.is_none(), "shadow pass failed validation: {:?}", error);
+    }
+
+    #[test]
+    fn test_sun_view_proj_fits_world() {
+        let center = [16.0, 16.0, 16.0];
+        let radius = 16.0 * 3.0f32.sqrt();
+        let m = sun_view_proj(SUN_DIRECTION, center, radius);
+        let project = |p: [f32; 3]| {
+            let clip: [f32; 4] = [0, 1, 2, 3].map(|row| m[0][row] * p[0] + m[1][row] * p[1] + m[2][row] * p[2] + m[3][row]);
+            [clip[0] / clip[3], clip[1] / clip[3], clip[2] / clip[3]]
+        };
+
+        let middle = project(center);
+        assert!(middle[0].abs() < 1e-5 && middle[1].abs() < 1e-5);
+        assert!((middle[2] - 0.5).abs() < 1e-5);
+
+        // Every corner of the world lands inside the shadow map
+        for corner in 0..8 {
+            let p = [0, 1, 2].map(|axis| if corner & (1 << axis) != 0 { 32.0 } else { 0.0 });
+            let [x, y, z] = project(p);
+            assert!(x.abs() <= 1.0 && y.abs() <= 1.0, "{:?} falls outside the map", p);
+            assert!((0.0..=1.0).contains(&z), "{:?} is clipped at depth {}", p, z);
+        }
+
+        // Closer to the sun means nearer in the map
+        let sun = normalize(SUN_DIRECTION);
+        let raised = project([0, 1, 2].map(|axis| center[axis] + sun[axis] * 4.0));
+        assert!(raised[2] < middle[2]);
+        assert!(raised[0].abs() < 1e-5 && raised[1].abs() < 1e-5);
+
+        // Straight overhead still gives a valid basis
+        let overhead = sun_view_proj([0.0, 1.0, 0.0], center, radius);
+        assert!(overhead.iter().flatten().all(|value| value.is_finite()));
+    }
+}
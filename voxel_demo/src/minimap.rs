@@ -0,0 +1,382 @@
+// Minimap overlay
+// A top-down map of the world drawn in the upper-right corner, colored by the
+// highest voxel in each column, with an arrow marking the camera.
+
+use crate::error::RobinResult;
+use crate::render_graph::RenderGraph;
+use crate::{hand, VoxelWorld};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wgpu::util::DeviceExt;
+
+/// Render graph pass drawing the map
+pub const PASS: &str = "minimap";
+
+/// Width and height of the map texture and of its on-screen square, in pixels
+pub const MINIMAP_SIZE: u32 = 128;
+/// Gap between the map and the window edges, in pixels
+const MINIMAP_MARGIN: u32 = 16;
+/// Map color of columns with no voxels at all
+const EMPTY_COLOR: [u8; 4] = [20, 20, 30, 255];
+
+const MINIMAP_SHADER: &str = r#"
+struct MinimapUniforms {
+    // Camera position in map coordinates, [0, 1] across the world
+    camera: vec2<f32>,
+    yaw: f32,
+}
+
+@group(0) @binding(0)
+var map_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var map_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> minimap: MinimapUniforms;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// One triangle covering the viewport, which is set to the map's square
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Thin frame around the map
+    let edge = min(min(in.uv.x, in.uv.y), min(1.0 - in.uv.x, 1.0 - in.uv.y));
+    if edge < 0.015 {
+        return vec4<f32>(0.9, 0.9, 0.9, 1.0);
+    }
+
+    // Camera arrow, pointing along the view direction
+    let forward = -vec2<f32>(sin(minimap.yaw), cos(minimap.yaw));
+    let right = vec2<f32>(forward.y, -forward.x);
+    let local = in.uv - minimap.camera;
+    let along = dot(local, forward);
+    let side = abs(dot(local, right));
+    if along > -0.03 && along < 0.05 && side < 0.03 * (0.05 - along) / 0.08 {
+        return vec4<f32>(1.0, 0.2, 0.2, 1.0);
+    }
+
+    return textureSample(map_texture, map_sampler, in.uv);
+}
+"#;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct MinimapUniforms {
+    camera: [f32; 2],
+    yaw: f32,
+    _padding: f32,
+}
+
+/// RGBA8 pixels of the map, row by row from z = 0, each sampling the column
+/// under its centre
+pub fn minimap_pixels(world: &VoxelWorld) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity((MINIMAP_SIZE * MINIMAP_SIZE * 4) as usize);
+    for row in 0..MINIMAP_SIZE {
+        for column in 0..MINIMAP_SIZE {
+            let x = ((column as f32 + 0.5) / MINIMAP_SIZE as f32 * world.size as f32) as usize;
+            let z = ((row as f32 + 0.5) / MINIMAP_SIZE as f32 * world.size as f32) as usize;
+            let color = match world.highest_voxel(x, z) {
+                Some((_, id)) => {
+                    let [r, g, b] = world.registry.color(id).map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+                    [r, g, b, 255]
+                }
+                None => EMPTY_COLOR,
+            };
+            pixels.extend(color);
+        }
+    }
+    pixels
+}
+
+/// Draws the minimap in its own viewport over the finished frame
+pub struct MinimapRenderer {
+    pipeline: wgpu::RenderPipeline,
+    texture: wgpu::Texture,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    pub visible: bool,
+}
+
+impl MinimapRenderer {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Minimap Texture"),
+            size: wgpu::Extent3d {
+                width: MINIMAP_SIZE,
+                height: MINIMAP_SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // Linear, so the map shows the same values the voxel shader is given
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        // Nearest filtering keeps voxel columns crisp
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Minimap Sampler"),
+            ..Default::default()
+        });
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Minimap Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[MinimapUniforms { camera: [0.5; 2], yaw: 0.0, _padding: 0.0 }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Minimap Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Minimap Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Minimap Shader"),
+            source: wgpu::ShaderSource::Wgsl(MINIMAP_SHADER.into()),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Minimap Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Minimap Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            // Overlay: drawn over everything, regardless of depth
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: crate::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self { pipeline, texture, uniform_buffer, bind_group, visible: true }
+    }
+
+    /// Redraws the map texture; call whenever the world changes
+    pub fn update_world(&self, queue: &wgpu::Queue, world: &VoxelWorld) {
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &minimap_pixels(world),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(MINIMAP_SIZE * 4),
+                rows_per_image: Some(MINIMAP_SIZE),
+            },
+            wgpu::Extent3d {
+                width: MINIMAP_SIZE,
+                height: MINIMAP_SIZE,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    pub fn update_camera(&self, queue: &wgpu::Queue, position: [f32; 3], yaw: f32, world_size: usize) {
+        let uniforms = MinimapUniforms {
+            camera: [position[0] / world_size as f32, position[2] / world_size as f32],
+            yaw,
+            _padding: 0.0,
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+    }
+
+    /// Draws the map into the upper-right corner of a `surface_width` ×
+    /// `surface_height` target, leaving the pass's viewport and scissor
+    /// restricted to the map
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, surface_width: u32, surface_height: u32) {
+        if !self.visible || surface_width < MINIMAP_SIZE + MINIMAP_MARGIN || surface_height < MINIMAP_SIZE + MINIMAP_MARGIN {
+            return;
+        }
+        let x = surface_width - MINIMAP_SIZE - MINIMAP_MARGIN;
+        let y = MINIMAP_MARGIN;
+        render_pass.set_viewport(x as f32, y as f32, MINIMAP_SIZE as f32, MINIMAP_SIZE as f32, 0.0, 1.0);
+        render_pass.set_scissor_rect(x, y, MINIMAP_SIZE, MINIMAP_SIZE);
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    /// Adds the pass drawing the map over the world and the held block
+    pub fn register(renderer: &Rc<RefCell<Self>>, graph: &mut RenderGraph) -> RobinResult<usize> {
+        let renderer = renderer.clone();
+        graph.add_node_after(PASS, &[hand::PASS], move |encoder, context| {
+            let renderer = renderer.borrow();
+            if !renderer.visible {
+                return Ok(());
+            }
+            let (width, height) = context.surface_size;
+            let mut render_pass = context.begin_pass(encoder, "Minimap Pass");
+            renderer.draw(&mut render_pass, width, height);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+
+    #[test]
+    fn test_minimap_texture_shows_top_voxel_colors() {
+        let instance = wgpu::Instance::default();
+        let adapter = match pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(&instance, None)) {
+            Some(adapter) => adapter,
+            None => {
+                eprintln!("No GPU adapter available, skipping minimap texture test");
+                return;
+            }
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap();
+
+        // Four columns per map pixel row/column on a 32-voxel world: stone
+        // everywhere, grass on the x < 16 half, and water over one corner
+        let mut world = VoxelWorld::empty(32);
+        for x in 0..32 {
+            for z in 0..32 {
+                world.set_voxel(x, 0, z, Some(registry::STONE));
+                if x < 16 {
+                    world.set_voxel(x, 1, z, Some(registry::GRASS));
+                }
+            }
+        }
+        world.set_voxel(31, 5, 31, Some(registry::WATER));
+        world.set_voxel(20, 0, 2, None);
+
+        let minimap = MinimapRenderer::new(&device, wgpu::TextureFormat::Rgba8UnormSrgb);
+        minimap.update_world(&queue, &world);
+
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (MINIMAP_SIZE * MINIMAP_SIZE * 4) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_texture_to_buffer(
+            minimap.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(MINIMAP_SIZE * 4),
+                    rows_per_image: Some(MINIMAP_SIZE),
+                },
+            },
+            minimap.texture.size(),
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+        readback.slice(..).map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        device.poll(wgpu::Maintain::Wait);
+        let pixels = readback.slice(..).get_mapped_range().to_vec();
+
+        let pixel = |column: u32, row: u32| {
+            let start = ((row * MINIMAP_SIZE + column) * 4) as usize;
+            [pixels[start], pixels[start + 1], pixels[start + 2], pixels[start + 3]]
+        };
+        let expected = |id| {
+            let [r, g, b] = world.registry.color(id).map(|c| (c * 255.0).round() as u8);
+            [r, g, b, 255]
+        };
+        assert_eq!(pixel(10, 60), expected(registry::GRASS));
+        assert_eq!(pixel(100, 60), expected(registry::STONE));
+        assert_eq!(pixel(127, 127), expected(registry::WATER));
+        // Voxel column (20, 2) spans map pixels 80..84 by 8..12
+        assert_eq!(pixel(81, 9), EMPTY_COLOR);
+    }
+}
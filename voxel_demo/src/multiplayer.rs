@@ -0,0 +1,297 @@
+// Multiplayer sessions
+// Lets several players edit one world through a server over WebSocket. Each
+// client applies its own edits straight away and sends them on; the server
+// puts every edit in a single order, acknowledges it to the sender and
+// forwards it to everyone else. Messages are JSON text frames.
+//
+// Edits made at the same time are reconciled with operational transforms.
+// A voxel edit only touches one position, so two edits either touch
+// different voxels and don't interact, or the same one, where the edit the
+// server orders last wins. Transforming just corrects the replaced voxel the
+// later edit records, keeping edits undoable after a conflict.
+//
+// Annotations aren't part of the world and need no ordering: the server
+// just relays them to everyone else, outside the edit revisions.
+
+use crate::annotation::AnnotationChange;
+use crate::edit_history::VoxelEdit;
+use crate::error::{RobinError, RobinResult};
+use crate::VoxelWorld;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ClientMessage {
+    /// An edit made after the client had seen `revision` server edits
+    Edit { revision: u32, edit: VoxelEdit },
+    /// An annotation added or removed, for the server to pass on
+    Annotate(AnnotationChange),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ServerMessage {
+    /// The server has ordered the client's oldest unacknowledged edit
+    Ack,
+    /// Another client's edit, already transformed against every edit the
+    /// server ordered before it
+    Transform(VoxelEdit),
+    /// Another client's annotation change
+    Annotation(AnnotationChange),
+}
+
+/// Adjusts `op` to apply after `concurrent`, an edit made without knowledge
+/// of it that the server ordered first. Edits to different voxels are
+/// independent; on the same voxel `op` is the last writer and wins, now
+/// replacing what `concurrent` left there.
+pub fn transform(op: &VoxelEdit, concurrent: &VoxelEdit) -> VoxelEdit {
+    if op.coord == concurrent.coord {
+        VoxelEdit { before: concurrent.after, ..*op }
+    } else {
+        *op
+    }
+}
+
+pub struct MultiplayerSession {
+    ws_stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    /// Edits sent but not yet acknowledged, oldest first. The server orders
+    /// them after any edit it sends before their acks.
+    pending_ops: Vec<VoxelEdit>,
+    /// Number of server edits seen, the client's own acknowledged ones included
+    server_revision: u32,
+    /// Annotation changes received and not yet taken by an `AnnotationLayer`
+    annotation_changes: Vec<AnnotationChange>,
+}
+
+impl MultiplayerSession {
+    /// Joins the session served at `url`, e.g. "ws://localhost:9001". The
+    /// local world should match the server's before any edits are made.
+    pub async fn connect(url: &str) -> RobinResult<Self> {
+        let (ws_stream, _) = tokio_tungstenite::connect_async(url).await?;
+        Ok(Self {
+            ws_stream,
+            pending_ops: Vec::new(),
+            server_revision: 0,
+            annotation_changes: Vec::new(),
+        })
+    }
+
+    pub fn pending_ops(&self) -> &[VoxelEdit] {
+        &self.pending_ops
+    }
+
+    pub fn server_revision(&self) -> u32 {
+        self.server_revision
+    }
+
+    /// Sends an edit that has already been made to the local world
+    pub async fn send_edit(&mut self, edit: VoxelEdit) -> RobinResult<()> {
+        let message = ClientMessage::Edit {
+            revision: self.server_revision,
+            edit,
+        };
+        self.send(&message).await?;
+        self.pending_ops.push(edit);
+        Ok(())
+    }
+
+    /// Sends a change to this client's annotations
+    pub async fn send_annotation(&mut self, change: AnnotationChange) -> RobinResult<()> {
+        self.send(&ClientMessage::Annotate(change)).await
+    }
+
+    /// Annotation changes other clients have made since the last call,
+    /// oldest first
+    pub fn take_annotation_changes(&mut self) -> Vec<AnnotationChange> {
+        std::mem::take(&mut self.annotation_changes)
+    }
+
+    async fn send(&mut self, message: &ClientMessage) -> RobinResult<()> {
+        let text = serde_json::to_string(message).map_err(|e| invalid_message(e.to_string()))?;
+        self.ws_stream.send(Message::Text(text)).await?;
+        Ok(())
+    }
+
+    /// Waits for the next message from the server and brings `world` up to
+    /// date with it. Returns the edit made to `world`, if any: acks change
+    /// nothing, and neither do remote edits to a voxel one of the pending
+    /// edits will overwrite once the server orders it. Annotation changes
+    /// are kept for `take_annotation_changes`.
+    pub async fn receive(&mut self, world: &mut VoxelWorld) -> RobinResult<Option<VoxelEdit>> {
+        let text = loop {
+            match self.ws_stream.next().await {
+                Some(Ok(Message::Text(text))) => break text,
+                Some(Ok(Message::Close(_))) | None => {
+                    return Err(RobinError::Io(std::io::Error::new(
+                        std::io::ErrorKind::ConnectionAborted,
+                        "the server closed the session",
+                    )));
+                }
+                // Pings are answered by tungstenite itself
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(e.into()),
+            }
+        };
+        let message: ServerMessage = serde_json::from_str(&text).map_err(|e| invalid_message(e.to_string()))?;
+        match message {
+            // Not a server edit, so it doesn't count towards the revision
+            ServerMessage::Annotation(change) => {
+                self.annotation_changes.push(change);
+                Ok(None)
+            }
+            ServerMessage::Ack => {
+                self.server_revision += 1;
+                if self.pending_ops.is_empty() {
+                    return Err(invalid_message("ack without a pending edit".to_string()));
+                }
+                self.pending_ops.remove(0);
+                Ok(None)
+            }
+            ServerMessage::Transform(edit) => {
+                self.server_revision += 1;
+                let overwritten = self.pending_ops.iter().any(|pending| pending.coord == edit.coord);
+                for pending in &mut self.pending_ops {
+                    *pending = transform(pending, &edit);
+                }
+                if overwritten {
+                    return Ok(None);
+                }
+                let (x, y, z) = edit.coord;
+                world.set_voxel(x, y, z, edit.after);
+                Ok(Some(edit))
+            }
+        }
+    }
+}
+
+fn invalid_message(reason: String) -> RobinError {
+    RobinError::InvalidData {
+        field: "message".to_string(),
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    /// Orders edits from `clients` connections the way a real server would,
+    /// stopping after `edits` of them. Returns the server's copy of the world.
+    async fn fake_server(listener: TcpListener, clients: usize, edits: usize) -> VoxelWorld {
+        let (edit_sender, mut incoming) = mpsc::unbounded_channel();
+        let mut outgoing = Vec::new();
+        for client in 0..clients {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (mut write, mut read) = tokio_tungstenite::accept_async(stream).await.unwrap().split();
+
+            let (message_sender, mut messages) = mpsc::unbounded_channel::<ServerMessage>();
+            outgoing.push(message_sender);
+            tokio::spawn(async move {
+                while let Some(message) = messages.recv().await {
+                    let text = serde_json::to_string(&message).unwrap();
+                    if write.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+            });
+
+            let edit_sender = edit_sender.clone();
+            tokio::spawn(async move {
+                while let Some(Ok(Message::Text(text))) = read.next().await {
+                    if let ClientMessage::Edit { revision, edit } = serde_json::from_str(&text).unwrap() {
+                        edit_sender.send((client, revision, edit)).ok();
+                    }
+                }
+            });
+        }
+
+        let mut world = VoxelWorld::empty(4);
+        let mut history: Vec<VoxelEdit> = Vec::new();
+        for _ in 0..edits {
+            let (client, revision, edit) = incoming.recv().await.unwrap();
+            let edit = history[revision as usize..]
+                .iter()
+                .fold(edit, |edit, concurrent| transform(&edit, concurrent));
+            let (x, y, z) = edit.coord;
+            world.set_voxel(x, y, z, edit.after);
+            history.push(edit);
+            for (other, sender) in outgoing.iter().enumerate() {
+                let message = if other == client {
+                    ServerMessage::Ack
+                } else {
+                    ServerMessage::Transform(edit)
+                };
+                sender.send(message).ok();
+            }
+        }
+        world
+    }
+
+    /// Makes `edits` locally and sends them without waiting for the server,
+    /// then follows the session until all `total` edits are ordered
+    async fn play_client(url: &str, edits: &[VoxelEdit], total: u32) -> VoxelWorld {
+        let mut world = VoxelWorld::empty(4);
+        let mut session = MultiplayerSession::connect(url).await.unwrap();
+        for &edit in edits {
+            let (x, y, z) = edit.coord;
+            let edit = VoxelEdit { before: world.get(x, y, z), ..edit };
+            world.set_voxel(x, y, z, edit.after);
+            session.send_edit(edit).await.unwrap();
+        }
+        while session.server_revision() < total {
+            session.receive(&mut world).await.unwrap();
+        }
+        assert!(session.pending_ops().is_empty());
+        world
+    }
+
+    fn edit(coord: (usize, usize, usize), after: Option<registry::VoxelId>) -> VoxelEdit {
+        VoxelEdit { coord, before: None, after }
+    }
+
+    #[test]
+    fn test_transform_is_last_writer_wins_by_position() {
+        let op = VoxelEdit { coord: (1, 2, 3), before: None, after: Some(registry::STONE) };
+        let elsewhere = VoxelEdit { coord: (3, 2, 1), before: None, after: Some(registry::SAND) };
+        assert_eq!(transform(&op, &elsewhere), op);
+
+        let same_voxel = VoxelEdit { coord: (1, 2, 3), before: None, after: Some(registry::SAND) };
+        let transformed = transform(&op, &same_voxel);
+        assert_eq!(transformed.after, Some(registry::STONE));
+        assert_eq!(transformed.before, Some(registry::SAND));
+    }
+
+    #[test]
+    fn test_concurrent_edits_converge() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("ws://{}", listener.local_addr().unwrap());
+
+            // Both clients write (1, 1, 1) before hearing from each other
+            let first = [edit((1, 1, 1), Some(registry::STONE)), edit((0, 0, 0), Some(registry::DIRT))];
+            let second = [
+                edit((1, 1, 1), Some(registry::CRYSTAL)),
+                edit((2, 0, 2), Some(registry::SAND)),
+                edit((0, 0, 0), None),
+            ];
+            let total = (first.len() + second.len()) as u32;
+            let (server, first, second) = tokio::join!(
+                fake_server(listener, 2, total as usize),
+                play_client(&url, &first, total),
+                play_client(&url, &second, total),
+            );
+
+            assert_eq!(first.voxels, server.voxels);
+            assert_eq!(second.voxels, server.voxels);
+            let winner = server.get(1, 1, 1);
+            assert!(winner == Some(registry::STONE) || winner == Some(registry::CRYSTAL));
+            assert_eq!(server.get(2, 0, 2), Some(registry::SAND));
+        });
+    }
+}
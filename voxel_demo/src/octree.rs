@@ -0,0 +1,299 @@
+// Sparse voxel octree
+// An alternative to `VoxelWorld`'s dense grid for large, mostly empty
+// worlds. Any cube of space holding a single voxel type, empty space
+// included, is stored as one leaf, so memory grows with the amount of
+// surface in the world rather than with its volume.
+
+use crate::registry::{self, VoxelId, VoxelRegistry};
+use crate::{add_face, apply_face_ao, atlas, corner_occlusion, face_axes, face_exposed_to, Vertex, VoxelWorld};
+use std::sync::Arc;
+
+pub enum OctreeNode {
+    /// A cube filled with a single voxel type, or empty
+    Leaf(Option<VoxelId>),
+    /// Eight half-size octants, indexed by `octant`
+    Interior(Box<[OctreeNode; 8]>),
+}
+
+/// Index of the child octant holding `p`, relative to the corner of a node
+/// whose children are `half` voxels across: bit 0 is x, bit 1 y and bit 2 z
+fn octant(p: [usize; 3], half: usize) -> usize {
+    (p[0] >= half) as usize | ((p[1] >= half) as usize) << 1 | ((p[2] >= half) as usize) << 2
+}
+
+impl OctreeNode {
+    /// Sets the voxel at `p` within this node of side `size`, splitting leaves
+    /// on the way down and merging octants that end up identical on the way
+    /// back up
+    fn set(&mut self, size: usize, p: [usize; 3], voxel: Option<VoxelId>) {
+        if let OctreeNode::Leaf(current) = *self {
+            if current == voxel {
+                return;
+            }
+            if size == 1 {
+                *self = OctreeNode::Leaf(voxel);
+                return;
+            }
+            *self = OctreeNode::Interior(Box::new(std::array::from_fn(|_| OctreeNode::Leaf(current))));
+        }
+        let OctreeNode::Interior(children) = self else {
+            unreachable!("leaves were split above");
+        };
+
+        let half = size / 2;
+        children[octant(p, half)].set(half, p.map(|c| c % half), voxel);
+
+        let merged = match children[0] {
+            OctreeNode::Leaf(first) if children.iter().all(|child| matches!(child, OctreeNode::Leaf(v) if *v == first)) => {
+                Some(first)
+            }
+            _ => None,
+        };
+        if let Some(voxel) = merged {
+            *self = OctreeNode::Leaf(voxel);
+        }
+    }
+
+    /// Calls `visit` with the corner, side and contents of every leaf
+    fn for_each_leaf(&self, origin: [usize; 3], size: usize, visit: &mut impl FnMut([usize; 3], usize, Option<VoxelId>)) {
+        match self {
+            OctreeNode::Leaf(voxel) => visit(origin, size, *voxel),
+            OctreeNode::Interior(children) => {
+                let half = size / 2;
+                for (index, child) in children.iter().enumerate() {
+                    let corner = [0, 1, 2].map(|axis| origin[axis] + ((index >> axis) & 1) * half);
+                    child.for_each_leaf(corner, half, visit);
+                }
+            }
+        }
+    }
+
+    /// Interior nodes at or below this one
+    fn interior_count(&self) -> usize {
+        match self {
+            OctreeNode::Leaf(_) => 0,
+            OctreeNode::Interior(children) => 1 + children.iter().map(OctreeNode::interior_count).sum::<usize>(),
+        }
+    }
+}
+
+pub struct SparseVoxelOctree {
+    pub root: OctreeNode,
+    /// Voxels per side of the world; the root covers the next power of two
+    pub world_size: usize,
+    registry: Arc<VoxelRegistry>,
+}
+
+impl SparseVoxelOctree {
+    /// An empty world using the built-in voxel types
+    pub fn new(world_size: usize) -> Self {
+        Self {
+            root: OctreeNode::Leaf(None),
+            world_size,
+            registry: Arc::new(VoxelRegistry::with_builtin_types()),
+        }
+    }
+
+    /// The same voxels as `world`, sharing its voxel types
+    pub fn from_world(world: &VoxelWorld) -> Self {
+        let mut octree = Self {
+            registry: world.registry.clone(),
+            ..Self::new(world.size)
+        };
+        for x in 0..world.size {
+            for y in 0..world.size {
+                for z in 0..world.size {
+                    if let Some(voxel) = world.voxels[x][y][z] {
+                        octree.set(x, y, z, Some(voxel));
+                    }
+                }
+            }
+        }
+        octree
+    }
+
+    /// Side of the cube the root node covers
+    fn root_size(&self) -> usize {
+        self.world_size.next_power_of_two()
+    }
+
+    /// The voxel at (x, y, z); anything outside the world is empty
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<VoxelId> {
+        if x >= self.world_size || y >= self.world_size || z >= self.world_size {
+            return None;
+        }
+        let mut p = [x, y, z];
+        let mut size = self.root_size();
+        let mut node = &self.root;
+        loop {
+            match node {
+                OctreeNode::Leaf(voxel) => return *voxel,
+                OctreeNode::Interior(children) => {
+                    size /= 2;
+                    node = &children[octant(p, size)];
+                    p = p.map(|c| c % size);
+                }
+            }
+        }
+    }
+
+    /// Sets or clears the voxel at (x, y, z). Positions outside the world are
+    /// ignored.
+    pub fn set(&mut self, x: usize, y: usize, z: usize, voxel: Option<VoxelId>) {
+        if x >= self.world_size || y >= self.world_size || z >= self.world_size {
+            return;
+        }
+        let size = self.root_size();
+        self.root.set(size, [x, y, z], voxel);
+    }
+
+    /// Bytes the tree occupies, counting each interior node's allocation of
+    /// eight children
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.root.interior_count() * std::mem::size_of::<[OctreeNode; 8]>()
+    }
+
+    fn get_signed(&self, p: [i64; 3]) -> Option<VoxelId> {
+        if p.iter().any(|&c| c < 0) {
+            return None;
+        }
+        self.get(p[0] as usize, p[1] as usize, p[2] as usize)
+    }
+
+    /// Mirrors `VoxelWorld::is_opaque`
+    fn is_opaque(&self, p: [i64; 3]) -> bool {
+        self.get_signed(p).is_some_and(|id| id != registry::CRYSTAL && !self.registry.is_transparent(id))
+    }
+
+    /// One quad per exposed voxel face, shaded and occluded the same way as
+    /// `VoxelWorld::generate_mesh_naive`. Only the voxels on the outside of
+    /// each filled leaf can have exposed faces, so the inside of a large
+    /// solid leaf is never visited.
+    pub fn generate_mesh(&self) -> (Vec<Vertex>, Vec<u32>) {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        self.root.for_each_leaf([0; 3], self.root_size(), &mut |origin, size, voxel| {
+            let Some(id) = voxel.filter(|&id| id != registry::CRYSTAL) else {
+                return;
+            };
+            let color = self.registry.rgba(id);
+
+            for face in 0..6 {
+                let (axis, u, v) = face_axes(face);
+                let step: i64 = if face % 2 == 0 { 1 } else { -1 };
+                let mut p = origin;
+                if step > 0 {
+                    p[axis] += size - 1;
+                }
+                for i in 0..size {
+                    for j in 0..size {
+                        p[u] = origin[u] + i;
+                        p[v] = origin[v] + j;
+                        let mut neighbour = p.map(|c| c as i64);
+                        neighbour[axis] += step;
+                        if !face_exposed_to(&self.registry, voxel, self.get_signed(neighbour)) {
+                            continue;
+                        }
+
+                        let pos = p.map(|c| c as f32);
+                        add_face(&mut vertices, &mut indices, pos, [1.0; 3], color, atlas::tile_for(id), face);
+                        let start = vertices.len() - 4;
+                        let ao = corner_occlusion(p, face, |q| self.is_opaque(q));
+                        apply_face_ao(&mut vertices[start..], face, pos, ao);
+                    }
+                }
+            }
+        });
+
+        (vertices, indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each quad's vertices, formatted so meshes emitted in different orders
+    /// can be compared
+    fn sorted_quads(vertices: &[Vertex]) -> Vec<String> {
+        let mut quads: Vec<String> = vertices.chunks(4).map(|quad| format!("{:?}", quad)).collect();
+        quads.sort();
+        quads
+    }
+
+    #[test]
+    fn test_set_and_get_prune_uniform_octants() {
+        let mut octree = SparseVoxelOctree::new(16);
+        octree.set(3, 4, 5, Some(registry::STONE));
+        assert_eq!(octree.get(3, 4, 5), Some(registry::STONE));
+        assert_eq!(octree.get(3, 4, 6), None);
+        assert_eq!(octree.get(16, 0, 0), None);
+        // One interior node per level down to the single voxel
+        assert_eq!(octree.root.interior_count(), 4);
+
+        octree.set(3, 4, 5, None);
+        assert!(matches!(octree.root, OctreeNode::Leaf(None)));
+
+        // Filling an 8³ octant collapses it into one leaf
+        for x in 8..16 {
+            for y in 0..8 {
+                for z in 0..8 {
+                    octree.set(x, y, z, Some(registry::DIRT));
+                }
+            }
+        }
+        assert_eq!(octree.root.interior_count(), 1);
+        assert_eq!(octree.get(12, 7, 0), Some(registry::DIRT));
+    }
+
+    #[test]
+    fn test_mesh_matches_dense_world() {
+        // Not a power of two, so the root covers space outside the world
+        let mut world = VoxelWorld::empty(12);
+        for x in 0..12 {
+            for z in 0..12 {
+                for y in 0..(x + z) % 5 + 1 {
+                    world.set_voxel(x, y, z, Some(if y == 0 { registry::STONE } else { registry::DIRT }));
+                }
+            }
+        }
+        world.set_voxel(6, 6, 6, Some(registry::CRYSTAL));
+        for x in 2..5 {
+            world.set_voxel(x, 5, 3, Some(registry::WATER));
+        }
+
+        let octree = SparseVoxelOctree::from_world(&world);
+        for (x, y, z) in [(0, 0, 0), (6, 6, 6), (3, 5, 3), (11, 11, 11)] {
+            assert_eq!(octree.get(x, y, z), world.get(x, y, z));
+        }
+
+        let (dense_vertices, dense_indices) = world.generate_mesh_naive();
+        let (vertices, indices) = octree.generate_mesh();
+        assert_eq!(indices.len(), dense_indices.len());
+        assert_eq!(sorted_quads(&vertices), sorted_quads(&dense_vertices));
+    }
+
+    #[test]
+    fn test_sparse_world_uses_a_quarter_of_the_dense_memory() {
+        // Rolling ground filling 10% of a 128³ world, topped with grass
+        let size = 128;
+        let mut world = VoxelWorld::empty(size);
+        let mut filled = 0;
+        for x in 0..size {
+            for z in 0..size {
+                let height = (12.8 + 3.0 * (x as f32 / 9.0).sin() + 3.0 * (z as f32 / 13.0).cos()) as usize;
+                for y in 0..height {
+                    world.set_voxel(x, y, z, Some(if y + 1 == height { registry::GRASS } else { registry::STONE }));
+                }
+                filled += height;
+            }
+        }
+        let fill = filled as f32 / size.pow(3) as f32;
+        assert!((0.09..0.11).contains(&fill), "fill {}", fill);
+
+        let octree = SparseVoxelOctree::from_world(&world);
+        let (dense, sparse) = (world.voxel_memory_usage(), octree.memory_usage());
+        assert!(dense >= 4 * sparse, "dense {} bytes, octree {} bytes", dense, sparse);
+    }
+}
@@ -0,0 +1,539 @@
+// Voxel particles
+// Small bursts of debris where voxels are broken or placed. The particles
+// live entirely on the GPU: a compute pass moves them and ages them out each
+// frame, and a render pass draws each one as a camera-facing quad that
+// shrinks as it expires. The CPU only writes new particles, into a staging
+// buffer that's copied over the oldest slots of a fixed ring.
+
+use crate::error::RobinResult;
+use crate::render_graph::RenderGraph;
+use crate::{console, terrain};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wgpu::util::DeviceExt;
+
+/// Render graph pass drawing the particles
+pub const PASS: &str = "particles";
+
+/// Particles alive at once; emitting more replaces the oldest
+pub const MAX_PARTICLES: u32 = 4096;
+/// Longest a particle lives, in seconds. Each one lives between half and all
+/// of this.
+pub const PARTICLE_LIFETIME: f32 = 1.0;
+/// Half the width of a newly spawned particle, in voxels
+const PARTICLE_SIZE: f32 = 0.08;
+/// Downward acceleration, in voxels per second squared
+const GRAVITY: f32 = 9.8;
+/// Sideways and upward launch speeds, in voxels per second
+const SPREAD_SPEED: f32 = 2.0;
+const MIN_RISE_SPEED: f32 = 1.5;
+const MAX_RISE_SPEED: f32 = 4.0;
+const WORKGROUP_SIZE: u32 = 64;
+
+/// The particle layout shared by both shaders
+const PARTICLE_WGSL: &str = r#"
+struct Particle {
+    position: vec3<f32>,
+    // Seconds left to live; 0 for an empty slot
+    lifetime: f32,
+    velocity: vec3<f32>,
+    color: vec3<f32>,
+}
+"#;
+
+const SIMULATE_SHADER: &str = r#"
+struct SimulationParams {
+    dt: f32,
+    gravity: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: SimulationParams;
+@group(0) @binding(1)
+var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(2)
+var<storage, read_write> live_count: atomic<u32>;
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= arrayLength(&particles) {
+        return;
+    }
+    var particle = particles[index];
+    if particle.lifetime <= 0.0 {
+        return;
+    }
+
+    particle.velocity.y -= params.gravity * params.dt;
+    particle.position += particle.velocity * params.dt;
+    particle.lifetime -= params.dt;
+    if particle.lifetime <= 0.0 {
+        // Expired; clear the slot so it draws nothing until it's reused
+        particle.position = vec3<f32>(0.0);
+        particle.velocity = vec3<f32>(0.0);
+        particle.lifetime = 0.0;
+    } else {
+        atomicAdd(&live_count, 1u);
+    }
+    particles[index] = particle;
+}
+"#;
+
+const RENDER_SHADER: &str = r#"
+struct ParticleCamera {
+    view_proj: mat4x4<f32>,
+    // World-space directions of the screen's x and y axes
+    right: vec4<f32>,
+    up: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: ParticleCamera;
+@group(0) @binding(1)
+var<storage, read> particles: array<Particle>;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+    @location(1) corner: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, -1.0), vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, 1.0), vec2<f32>(-1.0, 1.0),
+    );
+    let particle = particles[instance_index];
+    let corner = corners[vertex_index];
+    // Shrinks as it ages; empty slots collapse to a point and draw nothing
+    let size = PARTICLE_SIZE * clamp(particle.lifetime / PARTICLE_LIFETIME, 0.0, 1.0);
+    let offset = (camera.right.xyz * corner.x + camera.up.xyz * corner.y) * size;
+
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(particle.position + offset, 1.0);
+    out.color = particle.color;
+    out.corner = corner;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Round sprites rather than squares
+    if dot(in.corner, in.corner) > 1.0 {
+        discard;
+    }
+    return vec4<f32>(in.color, 1.0);
+}
+"#;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Particle {
+    pub position: [f32; 3],
+    pub lifetime: f32,
+    pub velocity: [f32; 3],
+    _padding: f32,
+    pub color: [f32; 3],
+    _padding2: f32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct SimulationParams {
+    dt: f32,
+    gravity: f32,
+    _padding: [f32; 2],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ParticleCamera {
+    view_proj: [[f32; 4]; 4],
+    right: [f32; 4],
+    up: [f32; 4],
+}
+
+const PARTICLE_BYTES: u64 = std::mem::size_of::<Particle>() as u64;
+
+pub struct ParticleSystem {
+    /// Every slot of the ring, alive or not
+    particles: wgpu::Buffer,
+    /// Particles still alive after the last update
+    count_buffer: wgpu::Buffer,
+    /// New particles, copied into their slots at the next update
+    staging: wgpu::Buffer,
+    params_buffer: wgpu::Buffer,
+    camera_buffer: wgpu::Buffer,
+    simulate_pipeline: wgpu::ComputePipeline,
+    simulate_bind_group: wgpu::BindGroup,
+    render_pipeline: wgpu::RenderPipeline,
+    render_bind_group: wgpu::BindGroup,
+    /// Emitted since the last update
+    pending: Vec<Particle>,
+    /// Slot the next new particle replaces
+    next_slot: u32,
+    rng_state: u64,
+}
+
+impl ParticleSystem {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let particles = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Buffer"),
+            contents: bytemuck::cast_slice(&vec![Particle::default(); MAX_PARTICLES as usize]),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+        let count_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Count Buffer"),
+            size: 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Staging Buffer"),
+            size: MAX_PARTICLES as u64 * PARTICLE_BYTES,
+            usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Simulation Params"),
+            size: std::mem::size_of::<SimulationParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Camera Buffer"),
+            size: std::mem::size_of::<ParticleCamera>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let entry = |binding, visibility, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let read_write = wgpu::BufferBindingType::Storage { read_only: false };
+
+        let simulate_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Particle Simulation Bind Group Layout"),
+            entries: &[
+                entry(0, wgpu::ShaderStages::COMPUTE, wgpu::BufferBindingType::Uniform),
+                entry(1, wgpu::ShaderStages::COMPUTE, read_write),
+                entry(2, wgpu::ShaderStages::COMPUTE, read_write),
+            ],
+        });
+        let simulate_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Particle Simulation Bind Group"),
+            layout: &simulate_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: particles.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: count_buffer.as_entire_binding() },
+            ],
+        });
+        let simulate_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Simulation Shader"),
+            source: wgpu::ShaderSource::Wgsl(format!("{}{}", PARTICLE_WGSL, SIMULATE_SHADER).into()),
+        });
+        let simulate_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Simulation Pipeline Layout"),
+            bind_group_layouts: &[&simulate_layout],
+            push_constant_ranges: &[],
+        });
+        let simulate_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Particle Simulation Pipeline"),
+            layout: Some(&simulate_pipeline_layout),
+            module: &simulate_shader,
+            entry_point: "cs_main",
+            compilation_options: Default::default(),
+        });
+
+        let render_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Particle Render Bind Group Layout"),
+            entries: &[
+                entry(0, wgpu::ShaderStages::VERTEX, wgpu::BufferBindingType::Uniform),
+                entry(1, wgpu::ShaderStages::VERTEX, wgpu::BufferBindingType::Storage { read_only: true }),
+            ],
+        });
+        let render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Particle Render Bind Group"),
+            layout: &render_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: camera_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: particles.as_entire_binding() },
+            ],
+        });
+        let render_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Render Shader"),
+            source: wgpu::ShaderSource::Wgsl(render_shader_source().into()),
+        });
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Render Pipeline Layout"),
+            bind_group_layouts: &[&render_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Particle Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &render_shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &render_shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            // Hidden behind voxels, but too small and brief to need writing depth
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: crate::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self {
+            particles,
+            count_buffer,
+            staging,
+            params_buffer,
+            camera_buffer,
+            simulate_pipeline,
+            simulate_bind_group,
+            render_pipeline,
+            render_bind_group,
+            pending: Vec::new(),
+            next_slot: 0,
+            rng_state: 0x5EED,
+        }
+    }
+
+    /// Bursts `count` particles of `color` out of `position`, flung up and
+    /// outwards. They appear at the next `update`.
+    pub fn emit(&mut self, position: [f32; 3], color: [f32; 3], count: u32) {
+        let mut random = || (terrain::splitmix64(&mut self.rng_state) as f64 / u64::MAX as f64) as f32;
+        for _ in 0..count {
+            let velocity = [
+                (random() * 2.0 - 1.0) * SPREAD_SPEED,
+                MIN_RISE_SPEED + random() * (MAX_RISE_SPEED - MIN_RISE_SPEED),
+                (random() * 2.0 - 1.0) * SPREAD_SPEED,
+            ];
+            self.pending.push(Particle {
+                position,
+                lifetime: PARTICLE_LIFETIME * (0.5 + random() * 0.5),
+                velocity,
+                color,
+                ..Default::default()
+            });
+        }
+        // Any older than a full ring would be overwritten straight away
+        let excess = self.pending.len().saturating_sub(MAX_PARTICLES as usize);
+        self.pending.drain(..excess);
+    }
+
+    /// Faces the particles towards a camera with view matrix `view`
+    pub fn update_camera(&self, queue: &wgpu::Queue, view_proj: [[f32; 4]; 4], view: [[f32; 4]; 4]) {
+        // The rows of the view matrix's rotation are the camera's axes
+        let camera = ParticleCamera {
+            view_proj,
+            right: [view[0][0], view[1][0], view[2][0], 0.0],
+            up: [view[0][1], view[1][1], view[2][1], 0.0],
+        };
+        queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[camera]));
+    }
+
+    /// Spawns the particles emitted since the last call and advances every
+    /// particle by `dt` seconds. Records into `encoder`, which should be
+    /// submitted before the next update since the queue writes it makes
+    /// would otherwise overwrite each other.
+    pub fn update(&mut self, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder, dt: f32) {
+        if !self.pending.is_empty() {
+            queue.write_buffer(&self.staging, 0, bytemuck::cast_slice(&self.pending));
+            // Copied in up to two runs, when the spawns wrap around the ring
+            let count = self.pending.len() as u32;
+            let first_run = count.min(MAX_PARTICLES - self.next_slot);
+            let slot_offset = |slot: u32| slot as u64 * PARTICLE_BYTES;
+            encoder.copy_buffer_to_buffer(
+                &self.staging,
+                0,
+                &self.particles,
+                slot_offset(self.next_slot),
+                slot_offset(first_run),
+            );
+            if first_run < count {
+                encoder.copy_buffer_to_buffer(
+                    &self.staging,
+                    slot_offset(first_run),
+                    &self.particles,
+                    0,
+                    slot_offset(count - first_run),
+                );
+            }
+            self.next_slot = (self.next_slot + count) % MAX_PARTICLES;
+            self.pending.clear();
+        }
+
+        let params = SimulationParams {
+            dt,
+            gravity: GRAVITY,
+            _padding: [0.0; 2],
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
+        encoder.clear_buffer(&self.count_buffer, 0, None);
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Particle Simulation Pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.simulate_pipeline);
+        pass.set_bind_group(0, &self.simulate_bind_group, &[]);
+        pass.dispatch_workgroups(MAX_PARTICLES.div_ceil(WORKGROUP_SIZE), 1, 1);
+    }
+
+    /// Draws the particles in a pass of their own over the finished frame in
+    /// `color_view`, hidden by anything nearer in `depth_view`
+    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, color_view: &wgpu::TextureView, depth_view: &wgpu::TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Particle Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: color_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.render_bind_group, &[]);
+        render_pass.draw(0..6, 0..MAX_PARTICLES);
+    }
+
+    /// Adds the pass drawing the particles, after the console
+    pub fn register(system: &Rc<RefCell<Self>>, graph: &mut RenderGraph) -> RobinResult<usize> {
+        let system = system.clone();
+        graph.add_node_after(PASS, &[console::PASS], move |encoder, context| {
+            system.borrow().draw(encoder, context.view, context.depth_view);
+            Ok(())
+        })
+    }
+}
+
+fn render_shader_source() -> String {
+    format!(
+        "const PARTICLE_SIZE: f32 = {:?};\nconst PARTICLE_LIFETIME: f32 = {:?};\n{}{}",
+        PARTICLE_SIZE, PARTICLE_LIFETIME, PARTICLE_WGSL, RENDER_SHADER
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(&instance, None))?;
+        Some(pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap())
+    }
+
+    /// Runs one update of `dt` seconds and reads back how many particles
+    /// survived it
+    fn step(system: &mut ParticleSystem, device: &wgpu::Device, queue: &wgpu::Queue, dt: f32) -> u32 {
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 4,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        system.update(queue, &mut encoder, dt);
+        encoder.copy_buffer_to_buffer(&system.count_buffer, 0, &readback, 0, 4);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        readback.slice(..).map_async(wgpu::MapMode::Read, |_| {});
+        device.poll(wgpu::Maintain::Wait);
+        let count = bytemuck::cast_slice::<u8, u32>(&readback.slice(..).get_mapped_range())[0];
+        readback.unmap();
+        count
+    }
+
+    #[test]
+    fn test_particles_expire_after_their_lifetime() {
+        let Some((device, queue)) = device() else {
+            eprintln!("No GPU adapter available, skipping particle test");
+            return;
+        };
+        let mut system = ParticleSystem::new(&device, wgpu::TextureFormat::Bgra8UnormSrgb);
+        system.emit([4.0, 8.0, 4.0], [1.0, 0.5, 0.0], 100);
+        system.emit([0.0, 2.0, 0.0], [0.2, 0.2, 0.2], 20);
+
+        let dt = 1.0 / 60.0;
+        assert_eq!(step(&mut system, &device, &queue, dt), 120);
+
+        let mut elapsed = dt;
+        while elapsed < PARTICLE_LIFETIME {
+            step(&mut system, &device, &queue, dt);
+            elapsed += dt;
+        }
+        assert_eq!(step(&mut system, &device, &queue, dt), 0);
+    }
+
+    #[test]
+    fn test_emitting_past_the_ring_keeps_the_newest() {
+        let Some((device, queue)) = device() else {
+            eprintln!("No GPU adapter available, skipping particle test");
+            return;
+        };
+        let mut system = ParticleSystem::new(&device, wgpu::TextureFormat::Bgra8UnormSrgb);
+        system.emit([0.0; 3], [1.0; 3], MAX_PARTICLES - 10);
+        assert_eq!(step(&mut system, &device, &queue, 0.01), MAX_PARTICLES - 10);
+
+        // Wraps around, replacing 20 of the first burst
+        system.emit([0.0; 3], [1.0; 3], 30);
+        assert_eq!(step(&mut system, &device, &queue, 0.01), MAX_PARTICLES);
+        assert_eq!(system.next_slot, 20);
+
+        system.emit([0.0; 3], [1.0; 3], MAX_PARTICLES + 5);
+        assert_eq!(system.pending.len(), MAX_PARTICLES as usize);
+    }
+}
@@ -0,0 +1,150 @@
+// World save/load
+// Worlds are stored as a format version byte followed by a bincode payload
+// holding the size, the voxel registry and the run-length-encoded voxel grid.
+
+use crate::error::{RobinError, RobinResult};
+use crate::registry::{VoxelId, VoxelRegistry};
+use crate::VoxelWorld;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Bump whenever the on-disk layout changes
+/// 2: voxel definitions gained an alpha channel
+/// 3: voxel definitions gained a glow radius
+pub const FORMAT_VERSION: u8 = 3;
+
+#[derive(Serialize, Deserialize)]
+struct WorldFile {
+    size: u32,
+    registry: VoxelRegistry,
+    /// (run length, voxel) pairs in x, y, z order
+    runs: Vec<(u32, Option<VoxelId>)>,
+}
+
+impl VoxelWorld {
+    pub fn save(&self, path: &Path) -> RobinResult<()> {
+        let file = WorldFile {
+            size: self.size as u32,
+            registry: (*self.registry).clone(),
+            runs: encode_runs(self.voxels.iter().flatten().flatten().copied()),
+        };
+
+        let mut bytes = vec![FORMAT_VERSION];
+        bytes.extend(bincode::serialize(&file)?);
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> RobinResult<Self> {
+        let bytes = std::fs::read(path)?;
+        let (&version, payload) = bytes.split_first().ok_or_else(|| RobinError::InvalidData {
+            field: "version".to_string(),
+            reason: "file is empty".to_string(),
+        })?;
+        if version != FORMAT_VERSION {
+            return Err(RobinError::UnsupportedVersion {
+                found: version,
+                expected: FORMAT_VERSION,
+            });
+        }
+
+        let file: WorldFile = bincode::deserialize(payload)?;
+        let size = file.size as usize;
+        let total: u64 = file.runs.iter().map(|&(length, _)| length as u64).sum();
+        if total != (size as u64).pow(3) {
+            return Err(RobinError::InvalidData {
+                field: "runs".to_string(),
+                reason: format!("{} voxels encoded for a {}³ world", total, size),
+            });
+        }
+
+        let mut voxels = vec![vec![vec![None; size]; size]; size];
+        let mut cells = voxels.iter_mut().flatten().flatten();
+        for (length, voxel) in file.runs {
+            for cell in cells.by_ref().take(length as usize) {
+                *cell = voxel;
+            }
+        }
+
+        Ok(Self::from_voxels(voxels, size, Arc::new(file.registry)))
+    }
+}
+
+pub(crate) fn encode_runs(voxels: impl Iterator<Item = Option<VoxelId>>) -> Vec<(u32, Option<VoxelId>)> {
+    let mut runs: Vec<(u32, Option<VoxelId>)> = Vec::new();
+    for voxel in voxels {
+        match runs.last_mut() {
+            Some((length, current)) if *current == voxel && *length < u32::MAX => *length += 1,
+            _ => runs.push((1, voxel)),
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("robin_voxel_{}_{}.bin", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_save_load_round_trip() {
+        let world = VoxelWorld::new(32);
+        let path = temp_path("round_trip");
+
+        world.save(&path).unwrap();
+        let loaded = VoxelWorld::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.size, world.size);
+        assert_eq!(*loaded.registry, *world.registry);
+        assert_eq!(loaded.voxels, world.voxels);
+    }
+
+    #[test]
+    fn test_runs_compress_terrain() {
+        let world = VoxelWorld::new(32);
+        let runs = encode_runs(world.voxels.iter().flatten().flatten().copied());
+
+        assert!(runs.len() < 32 * 32 * 32 / 4);
+        assert_eq!(runs.iter().map(|&(length, _)| length as usize).sum::<usize>(), 32 * 32 * 32);
+    }
+
+    #[test]
+    fn test_load_rejects_future_version() {
+        let path = temp_path("future_version");
+        VoxelWorld::empty(4).save(&path).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[0] = FORMAT_VERSION + 1;
+        std::fs::write(&path, bytes).unwrap();
+
+        let result = VoxelWorld::load(&path);
+        std::fs::remove_file(&path).ok();
+
+        match result {
+            Err(RobinError::UnsupportedVersion { found, expected }) => {
+                assert_eq!(found, FORMAT_VERSION + 1);
+                assert_eq!(expected, FORMAT_VERSION);
+            }
+            Err(other) => panic!("Wrong error type: {}", other),
+            Ok(_) => panic!("Loaded a world with an unknown format version"),
+        }
+    }
+
+    #[test]
+    fn test_load_rejects_truncated_data() {
+        let path = temp_path("truncated");
+        VoxelWorld::new(16).save(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+
+        let result = VoxelWorld::load(&path);
+        std::fs::remove_file(&path).ok();
+
+        assert!(matches!(result, Err(RobinError::InvalidData { .. })));
+    }
+}
@@ -0,0 +1,519 @@
+// Player physics
+// Gravity and collision response for an axis-aligned box moving through the
+// voxel grid. Each step hashes the solid voxels around the body's path once,
+// so the collision checks in its substeps only test voxels the box overlaps.
+// Bodies and voxel types each have a physics material deciding how they
+// slide, bounce and float against each other.
+
+use crate::registry::{self, VoxelId};
+use crate::spatial_hash::{SpatialHash, DEFAULT_CELL_SIZE};
+use crate::VoxelWorld;
+use std::collections::HashMap;
+
+/// Downward acceleration in voxels (metres) per second squared
+pub const GRAVITY: f32 = -9.8;
+/// Player bounding box: width, height and depth
+pub const PLAYER_SIZE: [f32; 3] = [0.6, 1.8, 0.6];
+/// Upward speed given by a jump, enough to clear a one-voxel step
+pub const JUMP_SPEED: f32 = 5.0;
+/// Falling speed is capped so a long drop can't tunnel through the floor
+const TERMINAL_VELOCITY: f32 = 50.0;
+/// Largest distance a body may move between collision checks
+const MAX_STEP_DISTANCE: f32 = 0.25;
+/// Voxels within this distance of the box's path through a step are hashed,
+/// enough to cover the neighbours of any voxel it's pushed out of
+const HASH_MARGIN: i64 = 2;
+/// How far below its feet a body looks for ground
+const GROUND_EPSILON: f32 = 0.01;
+/// Slower impacts than this come to rest instead of bouncing, so bodies
+/// settle on the ground rather than jittering on it
+const BOUNCE_THRESHOLD: f32 = 1.0;
+/// Fraction of a submerged body's velocity water takes away per second
+const WATER_DRAG: f32 = 1.5;
+
+/// How a body or voxel type behaves on contact. Friction coefficients scale
+/// the contact's normal force, `restitution` is the fraction of the impact
+/// speed a bounce gives back and `density` is in kilograms per cubic metre.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicsMaterial {
+    pub static_friction: f32,
+    pub dynamic_friction: f32,
+    pub restitution: f32,
+    pub density: f32,
+}
+
+impl PhysicsMaterial {
+    pub const fn new(static_friction: f32, dynamic_friction: f32, restitution: f32, density: f32) -> Self {
+        Self { static_friction, dynamic_friction, restitution, density }
+    }
+
+    /// Players dig their heels in to stop, so they grip far harder than a
+    /// sliding block would, and never bounce
+    pub const PLAYER: Self = Self::new(4.0, 4.0, 0.0, 1100.0);
+
+    /// Friction and restitution for a contact between two materials: the
+    /// geometric mean of their friction, so either one being slippery makes
+    /// the contact slippery, and the product of their restitution
+    fn combine(&self, other: &Self) -> Self {
+        Self {
+            static_friction: (self.static_friction * other.static_friction).sqrt(),
+            dynamic_friction: (self.dynamic_friction * other.dynamic_friction).sqrt(),
+            restitution: self.restitution * other.restitution,
+            density: self.density,
+        }
+    }
+}
+
+/// Used for voxel types without a material of their own, such as ones added
+/// by plugins
+const DEFAULT_MATERIAL: PhysicsMaterial = PhysicsMaterial::new(0.6, 0.5, 0.2, 2000.0);
+
+/// The physics material of each voxel type
+#[derive(Clone, Debug, Default)]
+pub struct VoxelPhysicsRegistry {
+    materials: HashMap<VoxelId, PhysicsMaterial>,
+}
+
+impl VoxelPhysicsRegistry {
+    /// Materials for the built-in voxel types
+    pub fn with_builtin_materials() -> Self {
+        let mut materials = Self::default();
+        materials.set(registry::STONE, PhysicsMaterial::new(0.9, 0.7, 0.2, 2600.0));
+        materials.set(registry::GRASS, PhysicsMaterial::new(0.6, 0.5, 0.5, 1300.0));
+        materials.set(registry::DIRT, PhysicsMaterial::new(0.7, 0.6, 0.1, 1500.0));
+        materials.set(registry::WATER, PhysicsMaterial::new(0.0, 0.0, 0.0, 1000.0));
+        materials.set(registry::CRYSTAL, PhysicsMaterial::new(0.4, 0.3, 0.8, 2650.0));
+        materials.set(registry::SAND, PhysicsMaterial::new(0.6, 0.5, 0.05, 1600.0));
+        materials.set(registry::SNOW, PhysicsMaterial::new(0.3, 0.2, 0.1, 300.0));
+        materials.set(registry::GLOWSTONE, PhysicsMaterial::new(0.5, 0.4, 0.3, 2000.0));
+        materials.set(registry::ICE, PhysicsMaterial::new(0.1, 0.03, 0.1, 917.0));
+        materials
+    }
+
+    pub fn set(&mut self, voxel: VoxelId, material: PhysicsMaterial) {
+        self.materials.insert(voxel, material);
+    }
+
+    pub fn get(&self, voxel: VoxelId) -> PhysicsMaterial {
+        self.materials.get(&voxel).copied().unwrap_or(DEFAULT_MATERIAL)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicsBody {
+    /// Centre of the bottom face of the bounding box
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+    pub on_ground: bool,
+    /// Width, height and depth of the bounding box
+    pub size: [f32; 3],
+    pub material: PhysicsMaterial,
+}
+
+impl PhysicsBody {
+    /// A player standing at `position`
+    pub fn new(position: [f32; 3]) -> Self {
+        Self {
+            position,
+            velocity: [0.0; 3],
+            on_ground: false,
+            size: PLAYER_SIZE,
+            material: PhysicsMaterial::PLAYER,
+        }
+    }
+
+    /// A loose voxel-sized block of `material` resting on `position`
+    #[cfg(test)]
+    pub fn block(position: [f32; 3], material: PhysicsMaterial) -> Self {
+        Self { size: [1.0; 3], material, ..Self::new(position) }
+    }
+
+    /// Minimum and maximum corners of the bounding box
+    pub fn bounds(&self) -> ([f32; 3], [f32; 3]) {
+        let [x, y, z] = self.position;
+        let [w, h, d] = self.size;
+        ([x - w / 2.0, y, z - d / 2.0], [x + w / 2.0, y + h, z + d / 2.0])
+    }
+
+    /// Starts a jump if the body is standing on something
+    pub fn jump(&mut self) -> bool {
+        if !self.on_ground {
+            return false;
+        }
+        self.velocity[1] = JUMP_SPEED;
+        self.on_ground = false;
+        true
+    }
+}
+
+pub struct PhysicsEngine {
+    pub materials: VoxelPhysicsRegistry,
+}
+
+impl PhysicsEngine {
+    pub fn new() -> Self {
+        Self { materials: VoxelPhysicsRegistry::with_builtin_materials() }
+    }
+
+    /// Advances the body by `dt` seconds: applies gravity and buoyancy,
+    /// integrates velocity and pushes the body out of any solid voxels it
+    /// ends up in, bouncing off them by the two materials' restitution.
+    /// Large steps are split up so fast bodies can't pass through walls.
+    /// Friction from the ground slows the body's horizontal movement.
+    pub fn step(&self, body: &mut PhysicsBody, world: &VoxelWorld, dt: f32) {
+        body.velocity[1] = (body.velocity[1] + GRAVITY * dt).max(-TERMINAL_VELOCITY);
+        self.apply_buoyancy(body, world, dt);
+
+        let speed = body.velocity.iter().map(|v| v * v).sum::<f32>().sqrt();
+        let substeps = ((speed * dt / MAX_STEP_DISTANCE).ceil() as usize).max(1);
+        let sub_dt = dt / substeps as f32;
+        let hash = blocking_voxels_along(body, world, dt);
+        for _ in 0..substeps {
+            for axis in 0..3 {
+                body.position[axis] += body.velocity[axis] * sub_dt;
+            }
+            self.resolve_collisions(body, &hash);
+        }
+
+        let ground = (body.velocity[1] <= 0.0)
+            .then(|| {
+                let (mut min, max) = body.bounds();
+                min[1] -= GROUND_EPSILON;
+                let contacts = hash.query_aabb(min, [max[0], body.position[1], max[2]]);
+                // The voxel under the body's centre, or failing that any it rests on
+                let [x, _, z] = body.position;
+                contacts
+                    .iter()
+                    .find(|(cell, _)| cell[0] == x.floor() as i32 && cell[2] == z.floor() as i32)
+                    .or(contacts.first())
+                    .map(|&(_, voxel)| voxel)
+            })
+            .flatten();
+        body.on_ground = ground.is_some();
+        if let Some(voxel) = ground {
+            apply_friction(body, &body.material.combine(&self.materials.get(voxel)), dt);
+        }
+    }
+
+    /// Pushes the body up by the weight of the water it displaces and slows
+    /// it by the water's drag, both in proportion to how much of it is
+    /// submerged. Bodies less dense than water float.
+    fn apply_buoyancy(&self, body: &mut PhysicsBody, world: &VoxelWorld, dt: f32) {
+        let submerged = submerged_fraction(body, world);
+        if submerged == 0.0 {
+            return;
+        }
+        let water = self.materials.get(registry::WATER).density;
+        body.velocity[1] -= GRAVITY * water / body.material.density * submerged * dt;
+        let drag = 1.0 / (1.0 + WATER_DRAG * submerged * dt);
+        body.velocity = body.velocity.map(|v| v * drag);
+    }
+
+    /// Pushes the body out of each solid voxel it overlaps, along the axis that
+    /// needs the smallest displacement. Faces shared with another solid voxel are
+    /// skipped, so sliding along a floor never snags on the seams between blocks.
+    fn resolve_collisions(&self, body: &mut PhysicsBody, hash: &SpatialHash) {
+        let (min, max) = body.bounds();
+        for (cell, voxel) in hash.query_aabb(min, max) {
+            let (min, max) = body.bounds();
+
+            // Displacement that moves the box out of the cell along each axis and direction
+            let mut pushes: Vec<(f32, usize, i32)> = Vec::with_capacity(6);
+            for axis in 0..3 {
+                let cell_min = cell[axis] as f32;
+                let cell_max = cell_min + 1.0;
+                if max[axis] <= cell_min || min[axis] >= cell_max {
+                    // Already separated on this axis, e.g. after an earlier push
+                    pushes.clear();
+                    break;
+                }
+                pushes.push((max[axis] - cell_min, axis, -1));
+                pushes.push((cell_max - min[axis], axis, 1));
+            }
+            pushes.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let exit = pushes.into_iter().find(|&(_, axis, direction)| {
+                let mut neighbour = cell;
+                neighbour[axis] += direction;
+                hash.get(neighbour).is_none()
+            });
+            if let Some((depth, axis, direction)) = exit {
+                body.position[axis] += depth * direction as f32;
+                // Bounce off the surface, or stop moving into it, but keep
+                // moving away from it
+                let impact = -body.velocity[axis] * direction as f32;
+                if impact > 0.0 {
+                    let restitution = body.material.combine(&self.materials.get(voxel)).restitution;
+                    body.velocity[axis] = if impact > BOUNCE_THRESHOLD {
+                        impact * restitution * direction as f32
+                    } else {
+                        0.0
+                    };
+                }
+            }
+        }
+    }
+}
+
+impl Default for PhysicsEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Slows the body's horizontal movement by sliding friction for `dt`
+/// seconds of contact, bringing it to rest once static friction can hold it
+fn apply_friction(body: &mut PhysicsBody, contact: &PhysicsMaterial, dt: f32) {
+    let speed = body.velocity[0].hypot(body.velocity[2]);
+    if speed <= contact.static_friction * -GRAVITY * dt {
+        body.velocity[0] = 0.0;
+        body.velocity[2] = 0.0;
+        return;
+    }
+    let slowed = (speed - contact.dynamic_friction * -GRAVITY * dt).max(0.0) / speed;
+    body.velocity[0] *= slowed;
+    body.velocity[2] *= slowed;
+}
+
+/// Fraction of the body's volume inside water voxels
+fn submerged_fraction(body: &PhysicsBody, world: &VoxelWorld) -> f32 {
+    let (min, max) = body.bounds();
+    let size = world.size() as i64;
+    let low = min.map(|c| (c.floor() as i64).max(0));
+    let high = max.map(|c| (c.ceil() as i64).min(size));
+
+    let mut volume = 0.0;
+    for x in low[0]..high[0] {
+        for y in low[1]..high[1] {
+            for z in low[2]..high[2] {
+                if world.voxels[x as usize][y as usize][z as usize] == Some(registry::WATER) {
+                    let cell = [x, y, z];
+                    volume += (0..3)
+                        .map(|axis| (max[axis].min(cell[axis] as f32 + 1.0) - min[axis].max(cell[axis] as f32)).max(0.0))
+                        .product::<f32>();
+                }
+            }
+        }
+    }
+    volume / body.size.iter().product::<f32>()
+}
+
+/// The voxel at `p` if it blocks movement. The world's edges aren't walls,
+/// so a body can walk off and fall.
+fn blocking_voxel(world: &VoxelWorld, p: [i64; 3]) -> Option<VoxelId> {
+    if !world.is_solid(p) {
+        return None;
+    }
+    let id = world.voxels[p[0] as usize][p[1] as usize][p[2] as usize].unwrap();
+    world.registry.get(id).is_none_or(|definition| definition.solid).then_some(id)
+}
+
+/// Hashes the blocking voxels within `HASH_MARGIN` of the box swept from the
+/// body's position to where its velocity takes it in `dt`
+fn blocking_voxels_along(body: &PhysicsBody, world: &VoxelWorld, dt: f32) -> SpatialHash {
+    let (mut min, mut max) = body.bounds();
+    for axis in 0..3 {
+        let travel = body.velocity[axis] * dt;
+        if travel < 0.0 {
+            min[axis] += travel;
+        } else {
+            max[axis] += travel;
+        }
+    }
+    let size = world.size() as i64;
+    let low = min.map(|c| (c.floor() as i64 - HASH_MARGIN).max(0));
+    let high = max.map(|c| (c.ceil() as i64 + HASH_MARGIN).min(size));
+
+    let mut hash = SpatialHash::new(DEFAULT_CELL_SIZE);
+    for x in low[0]..high[0] {
+        for y in low[1]..high[1] {
+            for z in low[2]..high[2] {
+                if let Some(voxel) = blocking_voxel(world, [x, y, z]) {
+                    hash.insert([x as i32, y as i32, z as i32], voxel);
+                }
+            }
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+
+    /// A 16³ world with a stone floor at y = 0
+    fn floor_world() -> VoxelWorld {
+        floor_world_of(registry::STONE)
+    }
+
+    fn floor_world_of(floor: VoxelId) -> VoxelWorld {
+        let mut world = VoxelWorld::empty(16);
+        for x in 0..16 {
+            for z in 0..16 {
+                world.set_voxel(x, 0, z, Some(floor));
+            }
+        }
+        world
+    }
+
+    fn simulate(body: &mut PhysicsBody, world: &VoxelWorld, seconds: f32) {
+        let engine = PhysicsEngine::new();
+        for _ in 0..(seconds * 60.0) as usize {
+            engine.step(body, world, 1.0 / 60.0);
+        }
+    }
+
+    #[test]
+    fn test_falls_and_lands_on_floor() {
+        let world = floor_world();
+        let mut body = PhysicsBody::new([8.0, 10.0, 8.0]);
+        PhysicsEngine::new().step(&mut body, &world, 0.1);
+        assert!(body.velocity[1] < 0.0 && !body.on_ground);
+
+        simulate(&mut body, &world, 3.0);
+        assert!(body.on_ground);
+        assert!((body.position[1] - 1.0).abs() < 1e-4, "resting at {:?}", body.position);
+        assert_eq!(body.velocity[1], 0.0);
+    }
+
+    #[test]
+    fn test_large_step_does_not_tunnel() {
+        let world = floor_world();
+        let mut body = PhysicsBody::new([8.0, 6.0, 8.0]);
+        body.velocity[1] = -40.0;
+        PhysicsEngine::new().step(&mut body, &world, 0.5);
+        assert!(body.position[1] >= 1.0 - 1e-4);
+        assert!(body.on_ground);
+    }
+
+    #[test]
+    fn test_walks_along_floor_seams_and_stops_at_wall() {
+        let mut world = floor_world();
+        for y in 1..4 {
+            for z in 0..16 {
+                world.set_voxel(12, y, z, Some(registry::STONE));
+            }
+        }
+        let mut body = PhysicsBody::new([2.5, 1.0, 8.0]);
+        simulate(&mut body, &world, 0.1);
+
+        for _ in 0..180 {
+            body.velocity[0] = 4.0;
+            PhysicsEngine::new().step(&mut body, &world, 1.0 / 60.0);
+            assert!(body.on_ground, "lost the floor at {:?}", body.position);
+            assert!((body.position[1] - 1.0).abs() < 1e-3);
+        }
+        // Flush against the wall face at x = 12
+        assert!((body.position[0] - (12.0 - PLAYER_SIZE[0] / 2.0)).abs() < 1e-3);
+        assert!((body.position[2] - 8.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_jump_only_from_ground() {
+        let world = floor_world();
+        let mut body = PhysicsBody::new([8.0, 1.0, 8.0]);
+        simulate(&mut body, &world, 0.1);
+
+        assert!(body.jump());
+        assert!(!body.jump());
+        let mut peak: f32 = 0.0;
+        for _ in 0..60 {
+            PhysicsEngine::new().step(&mut body, &world, 1.0 / 60.0);
+            peak = peak.max(body.position[1]);
+        }
+        // v² / 2g ≈ 1.28 voxels, enough to clear a one-voxel step
+        assert!(peak > 2.2, "peak {}", peak);
+
+        simulate(&mut body, &world, 1.0);
+        assert!(body.on_ground);
+        assert!((body.position[1] - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_water_is_not_solid() {
+        let mut world = floor_world();
+        for y in 1..4 {
+            world.set_voxel(8, y, 8, Some(registry::WATER));
+        }
+        let mut body = PhysicsBody::new([8.5, 5.0, 8.5]);
+        simulate(&mut body, &world, 2.0);
+        assert!((body.position[1] - 1.0).abs() < 1e-4);
+    }
+
+    /// How far a stone block pushed at 4 m/s across a floor of `floor`
+    /// slides before it stops
+    fn slide_distance(floor: VoxelId) -> f32 {
+        let world = floor_world_of(floor);
+        let stone = PhysicsEngine::new().materials.get(registry::STONE);
+        let mut body = PhysicsBody::block([1.5, 1.0, 8.0], stone);
+        simulate(&mut body, &world, 0.1);
+        body.velocity[0] = 4.0;
+        simulate(&mut body, &world, 4.0);
+        assert_eq!(body.velocity[0], 0.0, "still sliding on {}", floor);
+        body.position[0] - 1.5
+    }
+
+    #[test]
+    fn test_blocks_slide_further_on_ice_than_stone() {
+        let (ice, stone) = (slide_distance(registry::ICE), slide_distance(registry::STONE));
+        assert!(ice > 2.0 * stone, "ice {}, stone {}", ice, stone);
+        // v² / 2μg with μ = 0.7 on stone
+        assert!((stone - 16.0 / (2.0 * 0.7 * 9.8)).abs() < 0.2, "stone {}", stone);
+    }
+
+    /// Highest a crystal block dropped from 5 voxels up onto a floor of
+    /// `floor` rises after its first bounce
+    fn bounce_height(floor: VoxelId) -> f32 {
+        let world = floor_world_of(floor);
+        let crystal = PhysicsEngine::new().materials.get(registry::CRYSTAL);
+        let mut body = PhysicsBody::block([8.0, 6.0, 8.0], crystal);
+        let engine = PhysicsEngine::new();
+        while body.velocity[1] <= 0.0 {
+            engine.step(&mut body, &world, 1.0 / 60.0);
+        }
+        let mut peak = body.position[1];
+        while body.velocity[1] > 0.0 {
+            engine.step(&mut body, &world, 1.0 / 60.0);
+            peak = peak.max(body.position[1]);
+        }
+        peak - 1.0
+    }
+
+    #[test]
+    fn test_crystal_bounces_higher_off_grass_than_dirt() {
+        let (grass, dirt) = (bounce_height(registry::GRASS), bounce_height(registry::DIRT));
+        assert!(grass > dirt, "grass {}, dirt {}", grass, dirt);
+        // Restitution 0.8 × 0.5 keeps 0.16 of the drop height
+        assert!((grass - 0.8).abs() < 0.15, "grass {}", grass);
+
+        // Players land without bouncing at all
+        let world = floor_world_of(registry::GRASS);
+        let mut player = PhysicsBody::new([8.0, 6.0, 8.0]);
+        simulate(&mut player, &world, 1.5);
+        assert!(player.on_ground);
+        assert!((player.position[1] - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_ice_floats_and_stone_sinks() {
+        let mut world = floor_world();
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 1..8 {
+                    world.set_voxel(x, y, z, Some(registry::WATER));
+                }
+            }
+        }
+        let engine = PhysicsEngine::new();
+        let mut ice = PhysicsBody::block([8.0, 4.0, 8.0], engine.materials.get(registry::ICE));
+        let mut stone = PhysicsBody::block([5.0, 4.0, 5.0], engine.materials.get(registry::STONE));
+        simulate(&mut ice, &world, 20.0);
+        simulate(&mut stone, &world, 20.0);
+
+        // Ice settles with 917 / 1000 of itself below the surface at y = 8
+        assert!((ice.position[1] - (8.0 - 0.917)).abs() < 0.05, "ice at {:?}", ice.position);
+        assert!(stone.on_ground);
+        assert!((stone.position[1] - 1.0).abs() < 1e-4);
+    }
+}
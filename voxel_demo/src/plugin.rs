@@ -0,0 +1,270 @@
+// Plugins
+// Lets third-party code add voxel types, console commands and per-frame
+// behaviour without forking the demo. Plugins are either linked in and added
+// directly, or built as dynamic libraries and loaded at startup.
+//
+// ABI stability: a plugin library hands back a `Box<dyn RobinPlugin>`, a Rust
+// trait object, and Rust has no stable ABI. A library only works with a demo
+// built by the same compiler version, against the same version of this crate,
+// with the same dependency versions and features. Any mismatch is undefined
+// behaviour on the first call rather than a clean error. `PLUGIN_API_VERSION`
+// catches plugins built before the trait last changed, but nothing can check
+// the compiler or struct layouts, so rebuild plugins alongside the demo.
+//
+// Libraries are never unloaded: the console and the world keep closures and
+// data created by plugin code for as long as the demo runs.
+
+use crate::console::DevConsole;
+use crate::error::{RobinError, RobinResult};
+use crate::registry::{VoxelId, VoxelRegistry};
+use crate::VoxelWorld;
+use std::path::Path;
+
+/// Bump whenever `RobinPlugin` or anything it passes to plugins changes shape
+pub const PLUGIN_API_VERSION: u8 = 1;
+
+/// Symbol every plugin library exports, returning its plugin
+pub const CREATE_SYMBOL: &[u8] = b"create_plugin";
+/// Symbol returning the `PLUGIN_API_VERSION` the library was built against
+pub const API_VERSION_SYMBOL: &[u8] = b"robin_plugin_api_version";
+
+pub trait RobinPlugin {
+    fn name(&self) -> &str;
+
+    /// Adds the plugin's voxel types. Ids can differ from the ones `register`
+    /// returns here (a saved world may already hold the type), so look types
+    /// up by name from the world's registry when they're needed.
+    fn register_voxels(&self, registry: &mut VoxelRegistry);
+
+    fn register_commands(&self, console: &mut DevConsole);
+
+    /// Called once per frame with the seconds since the last one
+    fn on_update(&mut self, world: &mut VoxelWorld, dt: f32);
+}
+
+/// Exports a plugin's constructor from a `cdylib` so `PluginManager` can load
+/// it, e.g. `voxel_demo::export_plugin!(LavaPlugin::default);`
+#[macro_export]
+macro_rules! export_plugin {
+    ($constructor:path) => {
+        #[no_mangle]
+        pub fn create_plugin() -> Box<dyn $crate::plugin::RobinPlugin> {
+            Box::new($constructor())
+        }
+
+        #[no_mangle]
+        pub fn robin_plugin_api_version() -> u8 {
+            $crate::plugin::PLUGIN_API_VERSION
+        }
+    };
+}
+
+#[derive(Default)]
+pub struct PluginManager {
+    plugins: Vec<Box<dyn RobinPlugin>>,
+}
+
+impl PluginManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a plugin compiled into the demo
+    pub fn add(&mut self, plugin: Box<dyn RobinPlugin>) {
+        self.plugins.push(plugin);
+    }
+
+    /// Loads a plugin from a dynamic library exporting `create_plugin`,
+    /// normally via `export_plugin!`. See the ABI notes at the top of this file.
+    pub fn load_plugin(&mut self, path: &Path) -> RobinResult<()> {
+        // SAFETY: loading runs the library's initialisers and the calls below
+        // trust its exports to have the signatures `export_plugin!` gives them.
+        // The version check guards against stale plugins but not against a
+        // different compiler; that's the caller's responsibility.
+        unsafe {
+            let library = libloading::Library::new(path)?;
+            let version = library
+                .get::<fn() -> u8>(API_VERSION_SYMBOL)
+                .map_err(|_| RobinError::InvalidData {
+                    field: "plugin".to_string(),
+                    reason: format!("{} doesn't export a Robin plugin", path.display()),
+                })?();
+            if version != PLUGIN_API_VERSION {
+                return Err(RobinError::UnsupportedVersion {
+                    found: version,
+                    expected: PLUGIN_API_VERSION,
+                });
+            }
+
+            let create = library.get::<fn() -> Box<dyn RobinPlugin>>(CREATE_SYMBOL)?;
+            self.plugins.push(create());
+            // The plugin's code has to stay mapped for as long as anything it
+            // created is alive, which in practice is the rest of the run
+            std::mem::forget(library);
+        }
+        Ok(())
+    }
+
+    /// Loads every dynamic library in `directory`, reporting the ones that
+    /// fail. A missing directory just means no plugins. Returns how many
+    /// were loaded.
+    pub fn load_directory(&mut self, directory: &Path) -> usize {
+        let Ok(entries) = std::fs::read_dir(directory) else {
+            return 0;
+        };
+        let mut paths: Vec<_> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == std::env::consts::DLL_EXTENSION))
+            .collect();
+        paths.sort();
+
+        let mut loaded = 0;
+        for path in paths {
+            match self.load_plugin(&path) {
+                Ok(()) => {
+                    println!("🔌 Loaded plugin '{}' from {}", self.plugins[self.plugins.len() - 1].name(), path.display());
+                    loaded += 1;
+                }
+                Err(e) => println!("⚠️  Skipping plugin {}: {}", path.display(), e),
+            }
+        }
+        loaded
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.plugins.iter().map(|plugin| plugin.name())
+    }
+
+    pub fn len(&self) -> usize {
+        self.plugins.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Lets every plugin add its voxel types. Types the registry already has
+    /// by name, e.g. from a world saved with the plugin loaded, are skipped
+    /// so registering again after loading a world doesn't duplicate them.
+    pub fn register_voxels(&self, registry: &mut VoxelRegistry) {
+        for plugin in &self.plugins {
+            let mut extended = registry.clone();
+            let known = extended.len();
+            plugin.register_voxels(&mut extended);
+            for id in known..extended.len() {
+                if let Some(definition) = extended.get(id as VoxelId) {
+                    if registry.find(&definition.name).is_none() {
+                        registry.register(definition.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn register_commands(&self, console: &mut DevConsole) {
+        for plugin in &self.plugins {
+            plugin.register_commands(console);
+        }
+    }
+
+    /// Runs every plugin's per-frame update, in load order
+    pub fn update(&mut self, world: &mut VoxelWorld, dt: f32) {
+        for plugin in &mut self.plugins {
+            plugin.on_update(world, dt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{self, VoxelDefinition};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Registers "ember", a `spark X Y Z` command, and turns every ember in
+    /// the bottom layer to stone on update
+    struct EmberPlugin {
+        updates: Rc<Cell<u32>>,
+    }
+
+    impl RobinPlugin for EmberPlugin {
+        fn name(&self) -> &str {
+            "ember"
+        }
+
+        fn register_voxels(&self, registry: &mut VoxelRegistry) {
+            registry.register(VoxelDefinition::new("ember", [0.9, 0.3, 0.1], true, true, 0.1));
+        }
+
+        fn register_commands(&self, console: &mut DevConsole) {
+            console.register_command(
+                "spark",
+                Box::new(|args, world| {
+                    let ember = world.registry().find("ember");
+                    let coords: Vec<usize> = args.iter().filter_map(|arg| arg.parse().ok()).collect();
+                    world.set_voxel(coords[0], coords[1], coords[2], ember);
+                    "sparked".to_string()
+                }),
+            );
+        }
+
+        fn on_update(&mut self, world: &mut VoxelWorld, _dt: f32) {
+            self.updates.set(self.updates.get() + 1);
+            let ember = world.registry().find("ember");
+            for x in 0..world.size() {
+                for z in 0..world.size() {
+                    if world.get(x, 0, z) == ember {
+                        world.set_voxel(x, 0, z, Some(registry::STONE));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_plugin_extends_registry_console_and_update() {
+        let updates = Rc::new(Cell::new(0));
+        let mut plugins = PluginManager::new();
+        plugins.add(Box::new(EmberPlugin { updates: updates.clone() }));
+        assert_eq!(plugins.names().collect::<Vec<_>>(), ["ember"]);
+
+        let mut registry = VoxelRegistry::with_builtin_types();
+        plugins.register_voxels(&mut registry);
+        let ember = registry.find("ember").unwrap();
+        assert_eq!(ember as usize, registry.len() - 1);
+        // Registering again, as after loading a world saved with the plugin, is a no-op
+        plugins.register_voxels(&mut registry);
+        assert_eq!(ember as usize, registry.len() - 1);
+
+        let mut world = VoxelWorld::empty(4);
+        plugins.register_voxels(world.registry_mut());
+        let mut console = DevConsole::new(None);
+        plugins.register_commands(&mut console);
+        assert_eq!(console.execute("spark 1 2 3", &mut world), "sparked");
+        assert_eq!(console.execute("spark 2 0 2", &mut world), "sparked");
+        assert_eq!(world.get(1, 2, 3), Some(ember));
+
+        plugins.update(&mut world, 0.016);
+        assert_eq!(updates.get(), 1);
+        assert_eq!(world.get(1, 2, 3), Some(ember));
+        assert_eq!(world.get(2, 0, 2), Some(registry::STONE));
+    }
+
+    #[test]
+    fn test_load_plugin_rejects_non_libraries() {
+        let mut plugins = PluginManager::new();
+        let directory = std::env::temp_dir().join(format!("robin_voxel_plugins_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let fake = directory.join(format!("fake.{}", std::env::consts::DLL_EXTENSION));
+        std::fs::write(&fake, b"not a library").unwrap();
+
+        assert!(plugins.load_plugin(&fake).is_err());
+        assert!(plugins.load_plugin(&directory.join("missing.so")).is_err());
+        assert_eq!(plugins.load_directory(&directory), 0);
+        assert_eq!(plugins.load_directory(&directory.join("nowhere")), 0);
+        assert!(plugins.is_empty());
+        std::fs::remove_dir_all(&directory).ok();
+    }
+}
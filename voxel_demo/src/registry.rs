@@ -0,0 +1,184 @@
+// Voxel type registry
+// Maps compact numeric ids to voxel definitions so new block types can be
+// added at runtime instead of by extending an enum.
+
+use serde::{Deserialize, Serialize};
+
+/// Compact identifier stored per voxel in the world grid
+pub type VoxelId = u16;
+
+// Ids of the built-in types, in the order `VoxelRegistry::with_builtin_types` registers them
+pub const STONE: VoxelId = 0;
+pub const GRASS: VoxelId = 1;
+pub const DIRT: VoxelId = 2;
+pub const WATER: VoxelId = 3;
+pub const CRYSTAL: VoxelId = 4;
+pub const SAND: VoxelId = 5;
+pub const SNOW: VoxelId = 6;
+pub const GLOWSTONE: VoxelId = 7;
+pub const ICE: VoxelId = 8;
+
+/// Color used for ids the registry doesn't know about, so they stand out
+const MISSING_COLOR: [f32; 3] = [1.0, 0.0, 1.0];
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VoxelDefinition {
+    pub name: String,
+    pub color: [f32; 3],
+    pub emissive: bool,
+    pub solid: bool,
+    pub hardness: f32,
+    /// Opacity in [0, 1]; anything below 1 is drawn in the blended pass
+    pub alpha: f32,
+    /// Reach of the point light each voxel of an emissive type casts, or 0
+    /// for types that don't light their surroundings
+    pub glow_radius: f32,
+}
+
+impl VoxelDefinition {
+    pub fn new(name: &str, color: [f32; 3], emissive: bool, solid: bool, hardness: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            color,
+            emissive,
+            solid,
+            hardness,
+            alpha: 1.0,
+            glow_radius: 0.0,
+        }
+    }
+
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    pub fn with_glow_radius(mut self, glow_radius: f32) -> Self {
+        self.glow_radius = glow_radius;
+        self
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.alpha < 1.0
+    }
+
+    /// Whether each voxel of this type is a point light
+    pub fn casts_light(&self) -> bool {
+        self.emissive && self.glow_radius > 0.0
+    }
+}
+
+/// Every voxel type known to a world. Ids are assigned sequentially on
+/// registration and never reused, so they stay stable for saved worlds.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct VoxelRegistry {
+    definitions: Vec<VoxelDefinition>,
+}
+
+impl VoxelRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry holding the demo's built-in block types under the `STONE`..`ICE` ids
+    pub fn with_builtin_types() -> Self {
+        let mut registry = Self::new();
+        registry.register(VoxelDefinition::new("stone", [0.5, 0.5, 0.5], false, true, 1.5));
+        registry.register(VoxelDefinition::new("grass", [0.2, 0.8, 0.2], false, true, 0.6));
+        registry.register(VoxelDefinition::new("dirt", [0.4, 0.3, 0.1], false, true, 0.5));
+        registry.register(VoxelDefinition::new("water", [0.2, 0.4, 0.8], false, false, 0.0).with_alpha(0.65));
+        registry.register(VoxelDefinition::new("crystal", [0.8, 0.3, 0.9], true, true, 3.0));
+        registry.register(VoxelDefinition::new("sand", [0.86, 0.8, 0.55], false, true, 0.4));
+        registry.register(VoxelDefinition::new("snow", [0.95, 0.96, 0.98], false, true, 0.2));
+        registry.register(VoxelDefinition::new("glowstone", [1.0, 0.78, 0.4], true, true, 0.3).with_glow_radius(10.0));
+        registry.register(VoxelDefinition::new("ice", [0.7, 0.87, 0.97], false, true, 0.5));
+        registry
+    }
+
+    /// Adds a voxel type and returns its id
+    pub fn register(&mut self, def: VoxelDefinition) -> VoxelId {
+        assert!(
+            self.definitions.len() < VoxelId::MAX as usize,
+            "voxel registry is full"
+        );
+        self.definitions.push(def);
+        (self.definitions.len() - 1) as VoxelId
+    }
+
+    pub fn get(&self, id: VoxelId) -> Option<&VoxelDefinition> {
+        self.definitions.get(id as usize)
+    }
+
+    /// Looks up a type by name, e.g. to resolve ids in config files
+    pub fn find(&self, name: &str) -> Option<VoxelId> {
+        self.definitions
+            .iter()
+            .position(|def| def.name == name)
+            .map(|index| index as VoxelId)
+    }
+
+    /// Display color for `id`, or a loud magenta when it isn't registered
+    pub fn color(&self, id: VoxelId) -> [f32; 3] {
+        self.get(id).map_or(MISSING_COLOR, |def| def.color)
+    }
+
+    /// Color with the type's opacity in the alpha channel, as uploaded per vertex
+    pub fn rgba(&self, id: VoxelId) -> [f32; 4] {
+        let [r, g, b] = self.color(id);
+        [r, g, b, self.get(id).map_or(1.0, |def| def.alpha)]
+    }
+
+    /// Whether `id` is drawn in the blended pass. Unknown ids render opaque.
+    pub fn is_transparent(&self, id: VoxelId) -> bool {
+        self.get(id).is_some_and(|def| def.is_transparent())
+    }
+
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_ids_match_constants() {
+        let registry = VoxelRegistry::with_builtin_types();
+        assert_eq!(registry.len(), 9);
+        assert_eq!(registry.find("stone"), Some(STONE));
+        assert_eq!(registry.find("grass"), Some(GRASS));
+        assert_eq!(registry.find("dirt"), Some(DIRT));
+        assert_eq!(registry.find("water"), Some(WATER));
+        assert_eq!(registry.find("crystal"), Some(CRYSTAL));
+        assert_eq!(registry.find("sand"), Some(SAND));
+        assert_eq!(registry.find("snow"), Some(SNOW));
+        assert_eq!(registry.find("glowstone"), Some(GLOWSTONE));
+        assert_eq!(registry.find("ice"), Some(ICE));
+        assert!(registry.get(CRYSTAL).unwrap().emissive);
+        // Crystals light the world through their own instances instead
+        assert!(!registry.get(CRYSTAL).unwrap().casts_light());
+        assert!(registry.get(GLOWSTONE).unwrap().casts_light());
+        assert!(!registry.get(WATER).unwrap().solid);
+        assert!(registry.is_transparent(WATER));
+        assert_eq!(registry.rgba(WATER)[3], 0.65);
+        assert!(!registry.is_transparent(STONE));
+        assert_eq!(registry.rgba(STONE)[3], 1.0);
+    }
+
+    #[test]
+    fn test_register_custom_type() {
+        let mut registry = VoxelRegistry::with_builtin_types();
+        let lava = registry.register(VoxelDefinition::new("lava", [1.0, 0.4, 0.0], true, false, 0.0));
+
+        assert_eq!(lava, ICE + 1);
+        assert_eq!(registry.get(lava).unwrap().name, "lava");
+        assert_eq!(registry.color(lava), [1.0, 0.4, 0.0]);
+        assert_eq!(registry.get(42), None);
+        assert_eq!(registry.color(42), MISSING_COLOR);
+    }
+}
@@ -0,0 +1,225 @@
+// Render graph
+// Each pass of a frame is a named node, and edges say which passes must run
+// before which. Renderers add their own passes when the demo sets them up,
+// each naming the passes it has to follow, and the graph works out an order
+// that respects every edge each time it runs. A new pass only states what it
+// depends on instead of being slotted by hand into a fixed sequence of
+// encoder calls.
+
+use crate::error::{RobinError, RobinResult};
+use crate::VoxelWorld;
+
+/// What every pass of a frame draws into and reads from
+pub struct RenderContext<'a> {
+    pub view: &'a wgpu::TextureView,
+    pub depth_view: &'a wgpu::TextureView,
+    pub uniform_buffer: &'a wgpu::Buffer,
+    pub light_buffer: &'a wgpu::Buffer,
+    /// Binds the uniform and light buffers at group 0
+    pub bind_group: &'a wgpu::BindGroup,
+    /// The world whose resident chunks are drawn
+    pub world: &'a VoxelWorld,
+    pub camera_position: [f32; 3],
+    pub view_proj: [[f32; 4]; 4],
+    /// What the first pass clears the frame to
+    pub clear_color: wgpu::Color,
+    /// Width and height of `view`
+    pub surface_size: (u32, u32),
+}
+
+impl RenderContext<'_> {
+    /// Starts a pass that draws over the frame so far, keeping its colour and
+    /// depth
+    pub fn begin_pass<'p>(&'p self, encoder: &'p mut wgpu::CommandEncoder, label: &str) -> wgpu::RenderPass<'p> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: self.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: self.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        })
+    }
+}
+
+pub type RenderFn = Box<dyn FnMut(&mut wgpu::CommandEncoder, &RenderContext) -> RobinResult<()>>;
+
+pub struct RenderNode {
+    pub name: String,
+    pub execute: RenderFn,
+}
+
+/// Passes, and `(before, after)` pairs of node indices
+#[derive(Default)]
+pub struct RenderGraph {
+    pub nodes: Vec<RenderNode>,
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl RenderGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a pass and returns its index, for use in `add_dependency`
+    pub fn add_node(
+        &mut self,
+        name: &str,
+        execute: impl FnMut(&mut wgpu::CommandEncoder, &RenderContext) -> RobinResult<()> + 'static,
+    ) -> usize {
+        self.nodes.push(RenderNode { name: name.to_string(), execute: Box::new(execute) });
+        self.nodes.len() - 1
+    }
+
+    /// Adds a pass that runs after each of the passes named in `after`,
+    /// which must already have been added, and returns its index
+    pub fn add_node_after(
+        &mut self,
+        name: &str,
+        after: &[&str],
+        execute: impl FnMut(&mut wgpu::CommandEncoder, &RenderContext) -> RobinResult<()> + 'static,
+    ) -> RobinResult<usize> {
+        let dependencies = after
+            .iter()
+            .map(|&dependency| {
+                self.find(dependency).ok_or_else(|| RobinError::InvalidData {
+                    field: "render graph".to_string(),
+                    reason: format!("{} runs after {}, which hasn't been added", name, dependency),
+                })
+            })
+            .collect::<RobinResult<Vec<usize>>>()?;
+        let node = self.add_node(name, execute);
+        for dependency in dependencies {
+            self.add_dependency(dependency, node);
+        }
+        Ok(node)
+    }
+
+    /// Index of the pass called `name`
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|node| node.name == name)
+    }
+
+    /// Makes the pass at `before` run before the one at `after`
+    pub fn add_dependency(&mut self, before: usize, after: usize) {
+        self.edges.push((before, after));
+    }
+
+    /// Node indices in an order that runs every node after all the nodes it
+    /// depends on. Nodes free to run at the same point run in the order they
+    /// were added. Fails if the edges form a cycle, or name a missing node.
+    pub fn execution_order(&self) -> RobinResult<Vec<usize>> {
+        let count = self.nodes.len();
+        if let Some(&(before, after)) = self.edges.iter().find(|&&(before, after)| before >= count || after >= count) {
+            return Err(RobinError::InvalidData {
+                field: "render graph".to_string(),
+                reason: format!("edge {} -> {} names a node that doesn't exist", before, after),
+            });
+        }
+
+        let mut waiting_on = vec![0; count];
+        for &(_, after) in &self.edges {
+            waiting_on[after] += 1;
+        }
+        let mut order = Vec::with_capacity(count);
+        let mut done = vec![false; count];
+        while let Some(next) = (0..count).find(|&node| !done[node] && waiting_on[node] == 0) {
+            done[next] = true;
+            order.push(next);
+            for &(_, after) in self.edges.iter().filter(|&&(before, _)| before == next) {
+                waiting_on[after] -= 1;
+            }
+        }
+
+        if order.len() < count {
+            // Whatever never became ready is on a cycle or waits on one
+            let stuck: Vec<&str> = (0..count).filter(|&node| !done[node]).map(|node| self.nodes[node].name.as_str()).collect();
+            return Err(RobinError::InvalidData {
+                field: "render graph".to_string(),
+                reason: format!("dependency cycle among {}", stuck.join(", ")),
+            });
+        }
+        Ok(order)
+    }
+
+    /// Runs every pass in dependency order, stopping at the first that fails
+    pub fn execute(&mut self, encoder: &mut wgpu::CommandEncoder, context: &RenderContext) -> RobinResult<()> {
+        for node in self.execution_order()? {
+            (self.nodes[node].execute)(encoder, context)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph_of(names: &[&str], edges: &[(usize, usize)]) -> RenderGraph {
+        let mut graph = RenderGraph::new();
+        for name in names {
+            graph.add_node(name, |_, _| Ok(()));
+        }
+        for &(before, after) in edges {
+            graph.add_dependency(before, after);
+        }
+        graph
+    }
+
+    #[test]
+    fn test_nodes_run_after_their_dependencies() {
+        // Added out of order: text over particles over the scene, which
+        // needs the shadow map
+        let graph = graph_of(&["text", "scene", "shadows", "particles"], &[(2, 1), (1, 3), (3, 0)]);
+        assert_eq!(graph.execution_order().unwrap(), [2, 1, 3, 0]);
+
+        // Independent nodes keep the order they were added in
+        let graph = graph_of(&["a", "b", "c"], &[]);
+        assert_eq!(graph.execution_order().unwrap(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_passes_added_after_others_follow_them() {
+        let mut graph = RenderGraph::new();
+        graph.add_node("sky", |_, _| Ok(()));
+        graph.add_node("shadows", |_, _| Ok(()));
+        let text = graph.add_node_after("text", &["opaque"], |_, _| Ok(()));
+        assert!(matches!(text, Err(RobinError::InvalidData { reason, .. }) if reason.contains("opaque")));
+        assert_eq!(graph.nodes.len(), 2);
+
+        let opaque = graph.add_node_after("opaque", &["sky", "shadows"], |_, _| Ok(())).unwrap();
+        let text = graph.add_node_after("text", &["opaque"], |_, _| Ok(())).unwrap();
+        assert_eq!(graph.find("opaque"), Some(opaque));
+        assert_eq!(graph.execution_order().unwrap(), [0, 1, opaque, text]);
+    }
+
+    #[test]
+    fn test_cycles_are_an_error() {
+        let graph = graph_of(&["shadows", "scene", "post", "text"], &[(0, 1), (1, 2), (2, 1), (2, 3)]);
+        match graph.execution_order() {
+            Err(RobinError::InvalidData { reason, .. }) => {
+                assert!(reason.contains("scene, post, text"), "{}", reason);
+                assert!(!reason.contains("shadows"), "{}", reason);
+            }
+            other => panic!("expected a cycle error, got {:?}", other),
+        }
+
+        let graph = graph_of(&["only"], &[(0, 0)]);
+        assert!(graph.execution_order().is_err());
+        let graph = graph_of(&["only"], &[(0, 1)]);
+        assert!(graph.execution_order().is_err());
+    }
+}
@@ -0,0 +1,235 @@
+// Edit replays
+// Records each player edit with the time it was made, so a build can be
+// played back step by step. Replays are stored like world saves: a format
+// version byte followed by a bincode payload.
+//
+// Every edit carries the voxel it replaced, so a player can seek backwards by
+// undoing edits as well as forwards by reapplying them. The world passed to
+// `ReplayPlayer::step_to` has to be in the state the player last left it in.
+
+use crate::edit_history::VoxelEdit;
+use crate::error::{RobinError, RobinResult};
+use crate::VoxelWorld;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Instant;
+
+/// Bump whenever the on-disk layout changes
+pub const REPLAY_FORMAT_VERSION: u8 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TimestampedEdit {
+    /// Milliseconds since recording started
+    pub timestamp_ms: u64,
+    pub edit: VoxelEdit,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReplayFile {
+    /// Oldest first, with non-decreasing timestamps
+    edits: Vec<TimestampedEdit>,
+}
+
+pub struct ReplayRecorder {
+    start: Instant,
+    edits: Vec<TimestampedEdit>,
+}
+
+impl Default for ReplayRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReplayRecorder {
+    /// Starts the recording clock
+    pub fn new() -> Self {
+        Self { start: Instant::now(), edits: Vec::new() }
+    }
+
+    /// Records an edit that has just been made
+    pub fn record_edit(&mut self, edit: VoxelEdit) {
+        let timestamp_ms = self.start.elapsed().as_millis() as u64;
+        self.record_edit_at(edit, timestamp_ms);
+    }
+
+    /// Records an edit made `timestamp_ms` into the recording. Timestamps
+    /// earlier than the last edit's are moved up to it, keeping the log in order.
+    pub fn record_edit_at(&mut self, edit: VoxelEdit, timestamp_ms: u64) {
+        let timestamp_ms = timestamp_ms.max(self.edits.last().map_or(0, |last| last.timestamp_ms));
+        self.edits.push(TimestampedEdit { timestamp_ms, edit });
+    }
+
+    pub fn save(&self, path: &Path) -> RobinResult<()> {
+        let file = ReplayFile { edits: self.edits.clone() };
+        let mut bytes = vec![REPLAY_FORMAT_VERSION];
+        bytes.extend(bincode::serialize(&file)?);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+}
+
+pub struct ReplayPlayer {
+    edits: Vec<TimestampedEdit>,
+    /// Number of edits currently applied to the world
+    position: usize,
+    current_ms: u64,
+}
+
+impl ReplayPlayer {
+    /// Loads a replay, positioned before its first edit
+    pub fn new(path: &Path) -> RobinResult<Self> {
+        let bytes = std::fs::read(path)?;
+        let (&version, payload) = bytes.split_first().ok_or_else(|| RobinError::InvalidData {
+            field: "version".to_string(),
+            reason: "file is empty".to_string(),
+        })?;
+        if version != REPLAY_FORMAT_VERSION {
+            return Err(RobinError::UnsupportedVersion {
+                found: version,
+                expected: REPLAY_FORMAT_VERSION,
+            });
+        }
+
+        let file: ReplayFile = bincode::deserialize(payload)?;
+        if file.edits.windows(2).any(|pair| pair[1].timestamp_ms < pair[0].timestamp_ms) {
+            return Err(RobinError::InvalidData {
+                field: "edits".to_string(),
+                reason: "timestamps go backwards".to_string(),
+            });
+        }
+        Ok(Self { edits: file.edits, position: 0, current_ms: 0 })
+    }
+
+    /// Treats every edit as already applied, for a world that was edited
+    /// live while the replay was recorded
+    pub fn resume_at_end(&mut self) {
+        self.position = self.edits.len();
+        self.current_ms = self.duration_ms();
+    }
+
+    /// Timestamp of the last edit
+    pub fn duration_ms(&self) -> u64 {
+        self.edits.last().map_or(0, |last| last.timestamp_ms)
+    }
+
+    pub fn current_ms(&self) -> u64 {
+        self.current_ms
+    }
+
+    pub fn len(&self) -> usize {
+        self.edits.len()
+    }
+
+    /// Brings `world` to its state at `target_ms`: edits made at or before it
+    /// are applied and later ones undone. Returns how many edits changed.
+    pub fn step_to(&mut self, world: &mut VoxelWorld, target_ms: u64) -> usize {
+        let target = self.edits.partition_point(|edit| edit.timestamp_ms <= target_ms);
+        let changed = self.position.abs_diff(target);
+
+        while self.position < target {
+            let VoxelEdit { coord: (x, y, z), after, .. } = self.edits[self.position].edit;
+            world.set_voxel(x, y, z, after);
+            self.position += 1;
+        }
+        while self.position > target {
+            self.position -= 1;
+            let VoxelEdit { coord: (x, y, z), before, .. } = self.edits[self.position].edit;
+            world.set_voxel(x, y, z, before);
+        }
+
+        self.current_ms = target_ms.min(self.duration_ms());
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{self, VoxelId};
+    use crate::terrain::splitmix64;
+    use std::path::PathBuf;
+
+    /// A copy of `VoxelWorld::voxels`
+    type Snapshot = Vec<Vec<Vec<Option<VoxelId>>>>;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("robin_voxel_replay_{}_{}.bin", name, std::process::id()))
+    }
+
+    /// Makes 100 pseudo-random edits 25 ms apart, many of them to the same
+    /// few cells, returning the recording and the world before the first
+    /// edit and after each one
+    fn record_session(world: &mut VoxelWorld) -> (ReplayRecorder, Vec<Snapshot>) {
+        let types = [None, Some(registry::STONE), Some(registry::DIRT), Some(registry::CRYSTAL)];
+        let mut recorder = ReplayRecorder::new();
+        let mut snapshots = vec![world.voxels.clone()];
+        let mut state = 99;
+        for step in 1..=100u64 {
+            let mut coordinate = || (splitmix64(&mut state) % 4) as usize;
+            let (x, y, z) = (coordinate(), coordinate(), coordinate());
+            let after = types[(splitmix64(&mut state) % 4) as usize];
+            recorder.record_edit_at(VoxelEdit { coord: (x, y, z), before: world.get(x, y, z), after }, step * 25);
+            world.set_voxel(x, y, z, after);
+            snapshots.push(world.voxels.clone());
+        }
+        (recorder, snapshots)
+    }
+
+    #[test]
+    fn test_replay_reproduces_final_world() {
+        let mut live = VoxelWorld::new(16);
+        let (recorder, snapshots) = record_session(&mut live);
+        let path = temp_path("final");
+        recorder.save(&path).unwrap();
+
+        let mut player = ReplayPlayer::new(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(player.len(), 100);
+        assert_eq!(player.duration_ms(), 2500);
+
+        let mut replayed = VoxelWorld::new(16);
+        assert_eq!(player.step_to(&mut replayed, u64::MAX), 100);
+        assert_eq!(replayed.voxels, live.voxels);
+        assert_eq!(player.current_ms(), 2500);
+
+        // Seeking all the way back restores the world the recording started from
+        assert_eq!(player.step_to(&mut replayed, 0), 100);
+        assert_eq!(replayed.voxels, snapshots[0]);
+        assert_eq!(replayed.voxels, VoxelWorld::new(16).voxels);
+    }
+
+    #[test]
+    fn test_scrub_back_and_forth() {
+        let mut live = VoxelWorld::new(16);
+        let (recorder, snapshots) = record_session(&mut live);
+        let path = temp_path("scrub");
+        recorder.save(&path).unwrap();
+        let mut player = ReplayPlayer::new(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        // Picks up from the live world, as the demo does
+        player.resume_at_end();
+        for target_ms in [1000, 2000, 10, 1250, 1260, 2475, 0, 3000] {
+            player.step_to(&mut live, target_ms);
+            let applied = (target_ms as usize / 25).min(100);
+            assert_eq!(live.voxels, snapshots[applied], "wrong world at {} ms", target_ms);
+        }
+    }
+
+    #[test]
+    fn test_load_rejects_future_version() {
+        let path = temp_path("future_version");
+        ReplayRecorder::new().save(&path).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[0] = REPLAY_FORMAT_VERSION + 1;
+        std::fs::write(&path, bytes).unwrap();
+
+        let result = ReplayPlayer::new(&path);
+        std::fs::remove_file(&path).ok();
+        assert!(matches!(result, Err(RobinError::UnsupportedVersion { .. })));
+    }
+}
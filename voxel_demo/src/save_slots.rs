@@ -0,0 +1,544 @@
+// Save slots
+// Numbered save slots, each a directory holding the world file, a metadata
+// file with the camera and play time, and a JPEG thumbnail of the frame that
+// was on screen when the slot was saved. Slot previews are shown in an
+// overlay while picking a slot.
+
+use crate::error::{RobinError, RobinResult};
+use crate::render_graph::RenderGraph;
+use crate::{minimap, screenshot};
+use crate::{Camera, VoxelWorld};
+use image::imageops::FilterType;
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Instant;
+
+/// Render graph pass drawing the slot previews
+pub const OVERLAY_PASS: &str = "slot previews";
+
+/// Number of save slots offered to the player
+pub const SLOT_COUNT: u8 = 4;
+/// Thumbnail dimensions in pixels
+pub const THUMBNAIL_WIDTH: u32 = 128;
+pub const THUMBNAIL_HEIGHT: u32 = 64;
+/// Bump whenever the slot metadata layout changes
+const SLOT_FORMAT_VERSION: u8 = 1;
+const JPEG_QUALITY: u8 = 85;
+
+const WORLD_FILE: &str = "world.bin";
+const SLOT_FILE: &str = "slot.bin";
+const THUMBNAIL_FILE: &str = "thumbnail.jpg";
+
+/// On-screen previews are the thumbnail scaled up by this factor
+const PREVIEW_SCALE: u32 = 2;
+/// Gap between neighbouring previews, in pixels
+const PREVIEW_GAP: u32 = 16;
+/// Preview color of a slot with nothing saved in it
+const EMPTY_SLOT_COLOR: [u8; 4] = [40, 40, 50, 255];
+
+#[derive(Serialize, Deserialize)]
+struct SlotFile {
+    world_name: String,
+    play_time_seconds: u64,
+    camera_position: [f32; 3],
+    camera_yaw: f32,
+    camera_pitch: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveSlotInfo {
+    pub slot: u8,
+    pub world_name: String,
+    pub play_time_seconds: u64,
+    pub thumbnail_path: PathBuf,
+}
+
+pub struct SaveSlotManager {
+    directory: PathBuf,
+    pub world_name: String,
+    /// Play time carried over from the slot the current world was loaded from
+    previous_play_time: u64,
+    session_start: Instant,
+}
+
+impl SaveSlotManager {
+    pub fn new(directory: impl Into<PathBuf>, world_name: impl Into<String>) -> Self {
+        Self {
+            directory: directory.into(),
+            world_name: world_name.into(),
+            previous_play_time: 0,
+            session_start: Instant::now(),
+        }
+    }
+
+    /// Total time spent in the current world, across saves and loads
+    pub fn play_time_seconds(&self) -> u64 {
+        self.previous_play_time + self.session_start.elapsed().as_secs()
+    }
+
+    fn slot_directory(&self, slot: u8) -> RobinResult<PathBuf> {
+        if slot >= SLOT_COUNT {
+            return Err(RobinError::InvalidData {
+                field: "slot".to_string(),
+                reason: format!("slot {} is out of range (0-{})", slot, SLOT_COUNT - 1),
+            });
+        }
+        Ok(self.directory.join(format!("slot_{}", slot)))
+    }
+
+    /// Saves the world, the camera and a thumbnail of `target`, which must be
+    /// the frame that was just rendered and have `COPY_SRC` usage
+    pub fn save_to_slot(
+        &self,
+        slot: u8,
+        world: &VoxelWorld,
+        camera: &Camera,
+        target: &wgpu::Texture,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> RobinResult<()> {
+        let thumbnail = capture_thumbnail(target, device, queue)?;
+        self.write_slot(slot, world, camera, &thumbnail)
+    }
+
+    fn write_slot(&self, slot: u8, world: &VoxelWorld, camera: &Camera, thumbnail: &RgbImage) -> RobinResult<()> {
+        let directory = self.slot_directory(slot)?;
+        std::fs::create_dir_all(&directory)?;
+        world.save(&directory.join(WORLD_FILE))?;
+
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY).encode_image(thumbnail)?;
+        std::fs::write(directory.join(THUMBNAIL_FILE), jpeg)?;
+
+        // Written last, so a slot only shows up once everything else is on disk
+        let file = SlotFile {
+            world_name: self.world_name.clone(),
+            play_time_seconds: self.play_time_seconds(),
+            camera_position: camera.position,
+            camera_yaw: camera.yaw,
+            camera_pitch: camera.pitch,
+        };
+        let mut bytes = vec![SLOT_FORMAT_VERSION];
+        bytes.extend(bincode::serialize(&file)?);
+        std::fs::write(directory.join(SLOT_FILE), bytes)?;
+        Ok(())
+    }
+
+    fn read_slot_file(&self, slot: u8) -> RobinResult<SlotFile> {
+        let bytes = std::fs::read(self.slot_directory(slot)?.join(SLOT_FILE))?;
+        let (&version, payload) = bytes.split_first().ok_or_else(|| RobinError::InvalidData {
+            field: "version".to_string(),
+            reason: "slot file is empty".to_string(),
+        })?;
+        if version != SLOT_FORMAT_VERSION {
+            return Err(RobinError::UnsupportedVersion {
+                found: version,
+                expected: SLOT_FORMAT_VERSION,
+            });
+        }
+        Ok(bincode::deserialize(payload)?)
+    }
+
+    /// Every slot with a readable save in it, in slot order
+    pub fn list_slots(&self) -> Vec<SaveSlotInfo> {
+        (0..SLOT_COUNT)
+            .filter_map(|slot| {
+                let file = self.read_slot_file(slot).ok()?;
+                Some(SaveSlotInfo {
+                    slot,
+                    world_name: file.world_name,
+                    play_time_seconds: file.play_time_seconds,
+                    thumbnail_path: self.slot_directory(slot).ok()?.join(THUMBNAIL_FILE),
+                })
+            })
+            .collect()
+    }
+
+    /// Loads the world and camera saved in `slot`. Play time continues from
+    /// the slot's total.
+    pub fn load_slot(&mut self, slot: u8) -> RobinResult<(VoxelWorld, Camera)> {
+        let file = self.read_slot_file(slot)?;
+        let world = VoxelWorld::load(&self.slot_directory(slot)?.join(WORLD_FILE))?;
+        let camera = Camera {
+            position: file.camera_position,
+            yaw: file.camera_yaw,
+            pitch: file.camera_pitch,
+        };
+
+        self.world_name = file.world_name;
+        self.previous_play_time = file.play_time_seconds;
+        self.session_start = Instant::now();
+        Ok((world, camera))
+    }
+}
+
+/// Copies `target` back from the GPU and scales it down to a thumbnail,
+/// cropping the center to the thumbnail's aspect ratio first
+pub fn capture_thumbnail(target: &wgpu::Texture, device: &wgpu::Device, queue: &wgpu::Queue) -> RobinResult<RgbImage> {
+    let (width, height) = (target.width(), target.height());
+    let pixels = screenshot::read_texture_rgba(target, width, height, device, queue)?;
+    let frame = RgbImage::from_fn(width, height, |x, y| {
+        let start = ((y * width + x) * 4) as usize;
+        image::Rgb([pixels[start], pixels[start + 1], pixels[start + 2]])
+    });
+
+    let crop_width = width.min(height * THUMBNAIL_WIDTH / THUMBNAIL_HEIGHT).max(1);
+    let crop_height = height.min(width * THUMBNAIL_HEIGHT / THUMBNAIL_WIDTH).max(1);
+    let cropped = image::imageops::crop_imm(
+        &frame,
+        (width - crop_width) / 2,
+        (height - crop_height) / 2,
+        crop_width,
+        crop_height,
+    );
+    Ok(image::imageops::resize(&*cropped, THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT, FilterType::Triangle))
+}
+
+/// Reads a thumbnail back as RGBA pixels, scaled to the thumbnail size
+pub fn load_thumbnail(path: &Path) -> RobinResult<Vec<u8>> {
+    let image = image::open(path)?.to_rgba8();
+    Ok(image::imageops::resize(&image, THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT, FilterType::Triangle).into_raw())
+}
+
+const SLOT_PREVIEW_SHADER: &str = r#"
+@group(0) @binding(0)
+var thumbnail: texture_2d<f32>;
+@group(0) @binding(1)
+var thumbnail_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// One triangle covering the viewport, which is set to a preview's rectangle
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Thin frame around each preview
+    let edge = min(min(in.uv.x, 1.0 - in.uv.x) * 2.0, min(in.uv.y, 1.0 - in.uv.y));
+    if edge < 0.02 {
+        return vec4<f32>(0.9, 0.9, 0.9, 1.0);
+    }
+    return textureSample(thumbnail, thumbnail_sampler, in.uv);
+}
+"#;
+
+/// Draws a row of slot thumbnails across the middle of the screen
+pub struct SlotPreviewOverlay {
+    pipeline: wgpu::RenderPipeline,
+    textures: Vec<wgpu::Texture>,
+    bind_groups: Vec<wgpu::BindGroup>,
+    pub visible: bool,
+}
+
+impl SlotPreviewOverlay {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        // Thumbnails hold the same encoded values the surface did
+        let texture_format = if format.is_srgb() {
+            wgpu::TextureFormat::Rgba8UnormSrgb
+        } else {
+            wgpu::TextureFormat::Rgba8Unorm
+        };
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Slot Preview Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Slot Preview Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let mut textures = Vec::with_capacity(SLOT_COUNT as usize);
+        let mut bind_groups = Vec::with_capacity(SLOT_COUNT as usize);
+        for _ in 0..SLOT_COUNT {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Slot Preview Texture"),
+                size: wgpu::Extent3d {
+                    width: THUMBNAIL_WIDTH,
+                    height: THUMBNAIL_HEIGHT,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: texture_format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            bind_groups.push(device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Slot Preview Bind Group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                ],
+            }));
+            textures.push(texture);
+        }
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Slot Preview Shader"),
+            source: wgpu::ShaderSource::Wgsl(SLOT_PREVIEW_SHADER.into()),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Slot Preview Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Slot Preview Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            // Overlay: drawn over everything, regardless of depth
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: crate::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self { pipeline, textures, bind_groups, visible: false }
+    }
+
+    /// Uploads the thumbnails of `slots`; slots missing from the list, or
+    /// whose thumbnail can't be read, are shown blank
+    pub fn update(&self, queue: &wgpu::Queue, slots: &[SaveSlotInfo]) {
+        let blank: Vec<u8> = EMPTY_SLOT_COLOR.repeat((THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT) as usize);
+        for (slot, texture) in self.textures.iter().enumerate() {
+            let pixels = slots
+                .iter()
+                .find(|info| info.slot as usize == slot)
+                .and_then(|info| load_thumbnail(&info.thumbnail_path).ok());
+            queue.write_texture(
+                texture.as_image_copy(),
+                pixels.as_deref().unwrap_or(&blank),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(THUMBNAIL_WIDTH * 4),
+                    rows_per_image: Some(THUMBNAIL_HEIGHT),
+                },
+                texture.size(),
+            );
+        }
+    }
+
+    /// Draws the previews centered in a `surface_width` × `surface_height`
+    /// target, leaving the pass's viewport and scissor on the last preview
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, surface_width: u32, surface_height: u32) {
+        let (width, height) = (THUMBNAIL_WIDTH * PREVIEW_SCALE, THUMBNAIL_HEIGHT * PREVIEW_SCALE);
+        let row_width = width * SLOT_COUNT as u32 + PREVIEW_GAP * (SLOT_COUNT as u32 - 1);
+        if !self.visible || surface_width < row_width || surface_height < height {
+            return;
+        }
+        let left = (surface_width - row_width) / 2;
+        let y = (surface_height - height) / 2;
+        render_pass.set_pipeline(&self.pipeline);
+        for (slot, bind_group) in self.bind_groups.iter().enumerate() {
+            let x = left + slot as u32 * (width + PREVIEW_GAP);
+            render_pass.set_viewport(x as f32, y as f32, width as f32, height as f32, 0.0, 1.0);
+            render_pass.set_scissor_rect(x, y, width, height);
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+
+    /// Adds the pass drawing the previews over the minimap while the overlay
+    /// is visible
+    pub fn register(overlay: &Rc<RefCell<Self>>, graph: &mut RenderGraph) -> RobinResult<usize> {
+        let overlay = overlay.clone();
+        graph.add_node_after(OVERLAY_PASS, &[minimap::PASS], move |encoder, context| {
+            let overlay = overlay.borrow();
+            if !overlay.visible {
+                return Ok(());
+            }
+            let (width, height) = context.surface_size;
+            let mut render_pass = context.begin_pass(encoder, "Slot Preview Pass");
+            overlay.draw(&mut render_pass, width, height);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+
+    fn temp_directory(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("robin_voxel_slots_{}_{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_slot_round_trip() {
+        let directory = temp_directory("round_trip");
+        let mut manager = SaveSlotManager::new(&directory, "Crystal Valley");
+        manager.previous_play_time = 3600;
+
+        let mut world = VoxelWorld::empty(8);
+        world.set_voxel(1, 2, 3, Some(registry::CRYSTAL));
+        let camera = Camera { position: [4.0, 9.5, -2.0], yaw: 1.25, pitch: -0.5 };
+        let thumbnail = RgbImage::from_pixel(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT, image::Rgb([200, 40, 40]));
+        manager.write_slot(2, &world, &camera, &thumbnail).unwrap();
+
+        let slots = manager.list_slots();
+        let mut loader = SaveSlotManager::new(&directory, "Untitled");
+        let loaded = loader.load_slot(2);
+        let pixels = load_thumbnail(&slots[0].thumbnail_path);
+        std::fs::remove_dir_all(&directory).ok();
+
+        assert_eq!(
+            slots,
+            vec![SaveSlotInfo {
+                slot: 2,
+                world_name: "Crystal Valley".to_string(),
+                play_time_seconds: 3600,
+                thumbnail_path: directory.join("slot_2").join(THUMBNAIL_FILE),
+            }]
+        );
+        let (loaded_world, loaded_camera) = loaded.unwrap();
+        assert_eq!(loaded_world.voxels, world.voxels);
+        assert_eq!(loaded_camera.position, camera.position);
+        assert_eq!((loaded_camera.yaw, loaded_camera.pitch), (camera.yaw, camera.pitch));
+        assert_eq!(loader.world_name, "Crystal Valley");
+        assert_eq!(loader.play_time_seconds(), 3600);
+
+        // JPEG is lossy, so only roughly the same color comes back
+        let pixels = pixels.unwrap();
+        assert_eq!(pixels.len(), (THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT * 4) as usize);
+        assert!(pixels[0] > 180 && pixels[1] < 70 && pixels[2] < 70, "{:?}", &pixels[..4]);
+    }
+
+    #[test]
+    fn test_slot_out_of_range_is_rejected() {
+        let manager = SaveSlotManager::new(temp_directory("out_of_range"), "World");
+        let thumbnail = RgbImage::new(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT);
+        let camera = Camera::new();
+        let result = manager.write_slot(SLOT_COUNT, &VoxelWorld::empty(4), &camera, &thumbnail);
+        assert!(matches!(result, Err(RobinError::InvalidData { .. })));
+        assert!(manager.list_slots().is_empty());
+    }
+
+    #[test]
+    fn test_thumbnail_capture_crops_and_swizzles() {
+        let instance = wgpu::Instance::default();
+        let adapter = match pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(&instance, None)) {
+            Some(adapter) => adapter,
+            None => {
+                eprintln!("No GPU adapter available, skipping thumbnail capture test");
+                return;
+            }
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap();
+
+        // A 4:3 BGRA frame: red, with blue bands at the top and bottom that
+        // the crop to 2:1 removes
+        let (width, height) = (400, 300);
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Bgra8Unorm,
+            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let mut texels = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            let bgra = if (50..250).contains(&y) { [0, 0, 255, 255] } else { [255, 0, 0, 255] };
+            for _ in 0..width {
+                texels.extend(bgra);
+            }
+        }
+        queue.write_texture(
+            target.as_image_copy(),
+            &texels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(width * 4),
+                rows_per_image: Some(height),
+            },
+            target.size(),
+        );
+
+        let thumbnail = capture_thumbnail(&target, &device, &queue).unwrap();
+        assert_eq!(thumbnail.dimensions(), (THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT));
+        for (x, y) in [(0, 0), (64, 32), (127, 63)] {
+            assert_eq!(thumbnail.get_pixel(x, y).0, [255, 0, 0], "pixel ({}, {})", x, y);
+        }
+
+        // The preview overlay draws to the same kind of target
+        let overlay = SlotPreviewOverlay::new(&device, wgpu::TextureFormat::Bgra8Unorm);
+        overlay.update(&queue, &[]);
+    }
+}
@@ -0,0 +1,250 @@
+// Screenshots
+// Copies a rendered frame back from the GPU and writes it out as a PNG.
+// The readback is shared with the save slot thumbnails.
+
+use crate::error::{RobinError, RobinResult};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where F12 screenshots go unless `SCREENSHOT_DIRECTORY_VAR` names another
+/// directory
+pub const DEFAULT_SCREENSHOT_DIRECTORY: &str = "screenshots";
+pub const SCREENSHOT_DIRECTORY_VAR: &str = "VOXEL_DEMO_SCREENSHOT_DIR";
+
+/// Copies the top-left `width` × `height` texels of `target` to the CPU as
+/// tightly packed RGBA8 rows, swizzling BGRA targets. Blocks until the GPU
+/// has finished with the frame.
+pub fn read_texture_rgba(
+    target: &wgpu::Texture,
+    width: u32,
+    height: u32,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) -> RobinResult<Vec<u8>> {
+    let bgra = match target.format() {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        other => {
+            return Err(RobinError::InvalidData {
+                field: "frame".to_string(),
+                reason: format!("can't capture a {:?} render target", other),
+            })
+        }
+    };
+    if width == 0 || height == 0 || width > target.width() || height > target.height() {
+        return Err(RobinError::InvalidData {
+            field: "frame".to_string(),
+            reason: format!(
+                "{}×{} doesn't fit the {}×{} render target",
+                width,
+                height,
+                target.width(),
+                target.height()
+            ),
+        });
+    }
+    let unpadded_row = width * 4;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let padded_row = unpadded_row.div_ceil(align) * align;
+
+    let readback = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Frame Readback Buffer"),
+        size: (padded_row * height) as u64,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Frame Readback Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        target.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &readback,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row),
+                rows_per_image: Some(height),
+            },
+        },
+        wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = readback.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        sender.send(result).ok();
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver
+        .recv()
+        .ok()
+        .and_then(|result| result.ok())
+        .ok_or_else(|| RobinError::InvalidData {
+            field: "frame".to_string(),
+            reason: "failed to read back the render target".to_string(),
+        })?;
+
+    let mut pixels = Vec::with_capacity((unpadded_row * height) as usize);
+    {
+        let data = slice.get_mapped_range();
+        for row in data.chunks(padded_row as usize).take(height as usize) {
+            for texel in row[..unpadded_row as usize].chunks(4) {
+                if bgra {
+                    pixels.extend([texel[2], texel[1], texel[0], texel[3]]);
+                } else {
+                    pixels.extend_from_slice(texel);
+                }
+            }
+        }
+    }
+    readback.unmap();
+    Ok(pixels)
+}
+
+/// Saves the top-left `width` × `height` of `texture` as an RGBA PNG,
+/// creating the parent directory if needed. The texture needs `COPY_SRC`
+/// usage, which a swapchain texture only has if the surface was configured
+/// with it.
+pub fn capture_screenshot(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    width: u32,
+    height: u32,
+    path: &Path,
+) -> RobinResult<()> {
+    let pixels = read_texture_rgba(texture, width, height, device, queue)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&pixels)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// A path in the screenshot directory named after the current time, e.g.
+/// `screenshots/screenshot-1718000000123.png`
+pub fn timestamped_path() -> PathBuf {
+    let directory = std::env::var_os(SCREENSHOT_DIRECTORY_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SCREENSHOT_DIRECTORY));
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_millis());
+    directory.join(format!("screenshot-{}.png", millis))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders one frame offscreen, with a clear and a single triangle, and
+    /// checks the PNG written from it
+    #[test]
+    fn test_capture_screenshot_writes_frame() {
+        let instance = wgpu::Instance::default();
+        let Some(adapter) = pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(&instance, None))
+        else {
+            eprintln!("No GPU adapter available, skipping screenshot test");
+            return;
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap();
+
+        let (width, height) = (96, 40);
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Bgra8Unorm,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(
+                r#"
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    var corners = array<vec2<f32>, 3>(vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, -1.0), vec2<f32>(-1.0, 1.0));
+    return vec4<f32>(corners[index], 0.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 0.6, 0.0, 1.0);
+}
+"#
+                .into(),
+            ),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(target.format().into())],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color { r: 0.2, g: 0.4, b: 0.6, a: 1.0 }),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&pipeline);
+            pass.draw(0..3, 0..1);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let path = std::env::temp_dir()
+            .join(format!("voxel_demo_screenshot_test_{}", std::process::id()))
+            .join("frame.png");
+        capture_screenshot(&device, &queue, &target, width, height, &path).unwrap();
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+
+        assert_eq!((info.width, info.height), (width, height));
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+        assert!(pixels.chunks(4).any(|texel| texel[..3] != [0, 0, 0]), "screenshot is entirely black");
+        // The triangle covers the bottom-left corner, in RGB order despite the BGRA target
+        let bottom_left = ((height - 1) * width * 4) as usize;
+        assert_eq!(pixels[bottom_left..bottom_left + 4], [255, 153, 0, 255]);
+        assert_eq!(pixels[(width as usize - 1) * 4..width as usize * 4], [51, 102, 153, 255]);
+    }
+}
@@ -0,0 +1,190 @@
+// Shader hot reload
+// Watches a WGSL file and recompiles it whenever it's saved, so shader edits
+// show up in the running demo without rebuilding the crate. Compilation runs
+// on a background thread; the render loop picks up finished modules with
+// `try_recv` and rebuilds its pipelines from them. Files that fail to parse
+// or validate are reported and skipped, so the old pipelines stay in use.
+
+use crate::error::{RobinError, RobinResult};
+use crossbeam_channel::Receiver;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub struct ShaderWatcher {
+    path: PathBuf,
+    modules: Receiver<wgpu::ShaderModule>,
+    /// Stops watching when dropped, which also ends the compile thread.
+    /// None if the file couldn't be watched.
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl ShaderWatcher {
+    #[cfg(test)]
+    pub fn new(shader_path: PathBuf, device: Arc<wgpu::Device>) -> Self {
+        Self::with_prelude(shader_path, String::new(), device)
+    }
+
+    /// Like `new`, but compiles `prelude` followed by the file, for shaders
+    /// that build on WGSL kept in Rust constants
+    pub fn with_prelude(shader_path: PathBuf, prelude: String, device: Arc<wgpu::Device>) -> Self {
+        let (change_sender, changes) = crossbeam_channel::unbounded();
+        let (module_sender, modules) = crossbeam_channel::unbounded();
+
+        let file_name = shader_path.file_name().map(|name| name.to_owned());
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                && event.paths.iter().any(|path| path.file_name() == file_name.as_deref())
+            {
+                change_sender.send(()).ok();
+            }
+        })
+        .and_then(|mut watcher| {
+            // The directory rather than the file, since many editors save by
+            // writing a new file and renaming it over the old one
+            let directory = shader_path.parent().filter(|parent| !parent.as_os_str().is_empty());
+            watcher.watch(directory.unwrap_or(Path::new(".")), RecursiveMode::NonRecursive)?;
+            Ok(watcher)
+        });
+        let watcher = match watcher {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                println!("⚠️  Can't watch {} for changes: {}", shader_path.display(), e);
+                None
+            }
+        };
+
+        let path = shader_path.clone();
+        std::thread::spawn(move || {
+            while changes.recv().is_ok() {
+                // One save can fire several events; compile once for all of them
+                while changes.try_recv().is_ok() {}
+                match compile(&device, &path, &prelude) {
+                    Ok(module) => {
+                        if module_sender.send(module).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => println!("⚠️  Keeping the old shader: {}", e),
+                }
+            }
+        });
+
+        Self {
+            path: shader_path,
+            modules,
+            _watcher: watcher,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The newest module compiled since the last call, if any
+    pub fn try_recv(&self) -> Option<wgpu::ShaderModule> {
+        self.modules.try_iter().last()
+    }
+}
+
+/// Parses and validates the shader with naga before handing it to wgpu,
+/// which would otherwise report a broken shader as a device error
+fn compile(device: &wgpu::Device, path: &Path, prelude: &str) -> RobinResult<wgpu::ShaderModule> {
+    let source = format!("{}{}", prelude, std::fs::read_to_string(path)?);
+    let invalid = |reason: String| RobinError::InvalidData {
+        field: path.display().to_string(),
+        reason,
+    };
+
+    let module = wgpu::naga::front::wgsl::parse_str(&source).map_err(|e| invalid(e.emit_to_string(&source)))?;
+    // Most likely caught halfway through being saved
+    if module.entry_points.is_empty() {
+        return Err(invalid("no entry points".to_string()));
+    }
+    wgpu::naga::valid::Validator::new(
+        wgpu::naga::valid::ValidationFlags::all(),
+        wgpu::naga::valid::Capabilities::default(),
+    )
+    .validate(&module)
+    .map_err(|e| invalid(e.emit_to_string(&source)))?;
+
+    Ok(device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Hot Reloaded Shader"),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    const SHADER: &str = "@fragment\nfn fs_main() -> @location(0) vec4<f32> {\n    return vec4<f32>(1.0);\n}\n";
+
+    fn test_device() -> Option<Arc<wgpu::Device>> {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(&instance, None))?;
+        let (device, _queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).ok()?;
+        Some(Arc::new(device))
+    }
+
+    fn shader_path(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("robin_voxel_shaders_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        directory.join("test.wgsl")
+    }
+
+    /// Polls `watcher` until a module arrives or `timeout` passes
+    fn wait_for_module(watcher: &ShaderWatcher, timeout: Duration) -> Option<wgpu::ShaderModule> {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if let Some(module) = watcher.try_recv() {
+                return Some(module);
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        None
+    }
+
+    #[test]
+    fn test_reloads_modified_shader_within_500ms() {
+        let Some(device) = test_device() else {
+            eprintln!("No GPU adapter available, skipping shader reload test");
+            return;
+        };
+        let path = shader_path("reload");
+        std::fs::write(&path, SHADER).unwrap();
+        let watcher = ShaderWatcher::new(path.clone(), device);
+        assert!(watcher.try_recv().is_none());
+
+        std::fs::write(&path, SHADER.replace("vec4<f32>(1.0)", "vec4<f32>(0.5)")).unwrap();
+        let reloaded = wait_for_module(&watcher, Duration::from_millis(500));
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+        assert!(reloaded.is_some(), "no module within 500ms of the change");
+    }
+
+    #[test]
+    fn test_broken_shader_is_skipped() {
+        let Some(device) = test_device() else {
+            eprintln!("No GPU adapter available, skipping shader reload test");
+            return;
+        };
+        let path = shader_path("broken");
+        std::fs::write(&path, SHADER).unwrap();
+        let watcher = ShaderWatcher::with_prelude(path.clone(), "const BRIGHTNESS: f32 = 0.8;\n".to_string(), device);
+
+        // Doesn't parse, then doesn't validate: a vec4 returned as f32
+        std::fs::write(&path, SHADER.replace("{", "{{")).unwrap();
+        std::fs::write(&path, SHADER.replace("vec4<f32>(1.0)", "BRIGHTNESS")).unwrap();
+        assert!(wait_for_module(&watcher, Duration::from_millis(300)).is_none());
+
+        // Fixing it, using the prelude, loads again
+        std::fs::write(&path, SHADER.replace("vec4<f32>(1.0)", "vec4<f32>(BRIGHTNESS)")).unwrap();
+        let reloaded = wait_for_module(&watcher, Duration::from_millis(500));
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+        assert!(reloaded.is_some());
+    }
+}
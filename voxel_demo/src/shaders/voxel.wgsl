@@ -0,0 +1,68 @@
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+    light_pos: vec4<f32>,
+    eye_pos: vec4<f32>,
+    sky_horizon: vec4<f32>,
+    sky_zenith: vec4<f32>,
+    time: f32,
+    fog: FogUniforms,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+@group(0) @binding(1)
+var atlas_texture: texture_2d<f32>;
+@group(0) @binding(2)
+var atlas_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) ao: f32,
+    @location(4) uv: vec2<f32>,
+    @location(5) tile: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) ao: f32,
+    @location(4) uv: vec2<f32>,
+    @location(5) @interpolate(flat) tile: u32,
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    let world_pos = vec4<f32>(in.position, 1.0);
+    out.clip_position = uniforms.view_proj * world_pos;
+    out.world_position = in.position;
+    out.normal = in.normal;
+    out.color = in.color;
+    out.ao = in.ao;
+    out.uv = in.uv;
+    out.tile = in.tile;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let view_dir = normalize(uniforms.eye_pos.xyz - in.world_position);
+    let albedo = in.color.rgb * textureSample(atlas_texture, atlas_sampler, atlas_uv(in.uv, in.tile)).rgb;
+
+    // Ambient
+    let ambient = 0.3 * albedo * (1.0 - in.ao * 0.6);
+
+    // Diffuse and specular from the sun and nearby point lights
+    let lit_color = ambient + shade_lights(albedo, in.world_position, in.normal, view_dir);
+
+    // Faded into the fog with distance, hiding chunks as they stream in
+    let dist = distance(uniforms.eye_pos.xyz, in.world_position);
+    let final_color = mix(lit_color, uniforms.fog.fog_color.rgb, fog_factor(uniforms.fog, dist));
+
+    return vec4<f32>(final_color, in.color.a);
+}
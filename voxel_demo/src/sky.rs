@@ -0,0 +1,236 @@
+// Procedural sky
+// A fullscreen gradient from horizon to zenith whose colors follow a
+// day/night cycle, drawn behind all voxel geometry.
+
+use crate::render_graph::RenderGraph;
+use crate::DEPTH_FORMAT;
+use std::rc::Rc;
+
+/// Render graph pass that clears the frame and draws the sky
+pub const PASS: &str = "sky";
+
+/// Seconds for one full sunrise-to-sunrise cycle
+pub const DAY_LENGTH_SECS: f32 = 240.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SkyPalette {
+    pub horizon: [f32; 3],
+    pub zenith: [f32; 3],
+}
+
+pub const SUNRISE: SkyPalette = SkyPalette {
+    horizon: [0.95, 0.6, 0.4],
+    zenith: [0.35, 0.45, 0.75],
+};
+pub const DAY: SkyPalette = SkyPalette {
+    horizon: [0.7, 0.85, 1.0],
+    zenith: [0.25, 0.5, 0.95],
+};
+pub const SUNSET: SkyPalette = SkyPalette {
+    horizon: [1.0, 0.45, 0.25],
+    zenith: [0.3, 0.25, 0.55],
+};
+pub const NIGHT: SkyPalette = SkyPalette {
+    horizon: [0.08, 0.1, 0.2],
+    zenith: [0.01, 0.01, 0.05],
+};
+
+/// Palettes at evenly spaced points of the cycle, starting at sunrise
+const KEYFRAMES: [SkyPalette; 4] = [SUNRISE, DAY, SUNSET, NIGHT];
+
+/// Sky colors `time` seconds into the demo, blending smoothly between the
+/// neighbouring keyframe palettes
+pub fn palette_at(time: f32) -> SkyPalette {
+    let phase = (time / DAY_LENGTH_SECS).rem_euclid(1.0) * KEYFRAMES.len() as f32;
+    let index = (phase as usize).min(KEYFRAMES.len() - 1);
+    let from = KEYFRAMES[index];
+    let to = KEYFRAMES[(index + 1) % KEYFRAMES.len()];
+
+    // Smoothstep so palettes ease in and out rather than changing linearly
+    let t = phase - index as f32;
+    let t = t * t * (3.0 - 2.0 * t);
+    let mix = |a: [f32; 3], b: [f32; 3]| [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t);
+
+    SkyPalette {
+        horizon: mix(from.horizon, to.horizon),
+        zenith: mix(from.zenith, to.zenith),
+    }
+}
+
+const SKY_SHADER: &str = r#"
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+    light_pos: vec4<f32>,
+    eye_pos: vec4<f32>,
+    sky_horizon: vec4<f32>,
+    sky_zenith: vec4<f32>,
+    time: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+// One triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    let ndc = uv * 2.0 - 1.0;
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 1.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // World-space view direction through this pixel
+    let far_point = uniforms.inv_view_proj * vec4<f32>(in.ndc, 1.0, 1.0);
+    let direction = normalize(far_point.xyz / far_point.w - uniforms.eye_pos.xyz);
+
+    let height = clamp(direction.y, 0.0, 1.0);
+    var color = mix(uniforms.sky_horizon.rgb, uniforms.sky_zenith.rgb, sqrt(height));
+    // Fade slightly below the horizon so the ground side isn't a flat band
+    color = color * (1.0 - 0.4 * clamp(-direction.y, 0.0, 1.0));
+    return vec4<f32>(color, 1.0);
+}
+"#;
+
+/// Renders the sky gradient. Its pass runs first: it ignores and never writes
+/// depth, so all voxel geometry ends up in front of it.
+pub struct Skybox {
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl Skybox {
+    /// `layout` must bind the shared `Uniforms` buffer at group 0
+    pub fn new(device: &wgpu::Device, layout: &wgpu::PipelineLayout, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Sky Shader"),
+            source: wgpu::ShaderSource::Wgsl(SKY_SHADER.into()),
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Sky Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self { pipeline }
+    }
+
+    /// Expects the uniform bind group to already be set on the pass
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    /// Adds the frame's first pass over the surface, which clears it and its
+    /// depth buffer and draws the sky. Every other pass drawing to the
+    /// surface runs after it.
+    pub fn register(skybox: &Rc<Self>, graph: &mut RenderGraph) -> usize {
+        let skybox = skybox.clone();
+        graph.add_node(PASS, move |encoder, context| {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Sky Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: context.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(context.clear_color),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: context.depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            render_pass.set_bind_group(0, context.bind_group, &[]);
+            skybox.draw(&mut render_pass);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: SkyPalette, b: SkyPalette) {
+        for i in 0..3 {
+            assert!((a.horizon[i] - b.horizon[i]).abs() < 1e-5, "{:?} != {:?}", a, b);
+            assert!((a.zenith[i] - b.zenith[i]).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_palette_hits_keyframes() {
+        let quarter = DAY_LENGTH_SECS / 4.0;
+        assert_close(palette_at(0.0), SUNRISE);
+        assert_close(palette_at(quarter), DAY);
+        assert_close(palette_at(2.0 * quarter), SUNSET);
+        assert_close(palette_at(3.0 * quarter), NIGHT);
+    }
+
+    #[test]
+    fn test_palette_wraps_from_night_to_sunrise() {
+        assert_close(palette_at(DAY_LENGTH_SECS), SUNRISE);
+        assert_close(palette_at(DAY_LENGTH_SECS * 2.25), DAY);
+
+        // Halfway between night and the next sunrise
+        let dawn = palette_at(DAY_LENGTH_SECS * 0.875);
+        for i in 0..3 {
+            let midpoint = (NIGHT.horizon[i] + SUNRISE.horizon[i]) / 2.0;
+            assert!((dawn.horizon[i] - midpoint).abs() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+// Spatial hash
+// Buckets filled voxels by coarse cell, so a box query only looks at the few
+// voxels in the cells it touches rather than at every grid position around
+// the box.
+
+use crate::registry::VoxelId;
+use smallvec::SmallVec;
+use std::collections::HashMap;
+
+/// Cell edge length, in voxels, that suits the player's box: it never
+/// touches more than 2x3x2 cells
+pub const DEFAULT_CELL_SIZE: f32 = 2.0;
+
+/// The voxels whose minimum corner falls in a cell, with their positions. A
+/// 2-voxel cell holds at most 8 of them.
+type Cell = SmallVec<[([i32; 3], VoxelId); 8]>;
+
+pub struct SpatialHash {
+    cells: HashMap<(i32, i32, i32), Cell>,
+    cell_size: f32,
+    len: usize,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self { cells: HashMap::new(), cell_size, len: 0 }
+    }
+
+    /// Key of the cell containing world-space point `p`
+    fn cell_key(&self, p: [f32; 3]) -> (i32, i32, i32) {
+        let [x, y, z] = p.map(|c| (c / self.cell_size).floor() as i32);
+        (x, y, z)
+    }
+
+    /// Fills the voxel at `position`, replacing whatever was there
+    pub fn insert(&mut self, position: [i32; 3], voxel: VoxelId) {
+        let key = self.cell_key(position.map(|c| c as f32));
+        let cell = self.cells.entry(key).or_default();
+        match cell.iter_mut().find(|(p, _)| *p == position) {
+            Some(entry) => entry.1 = voxel,
+            None => {
+                cell.push((position, voxel));
+                self.len += 1;
+            }
+        }
+    }
+
+    /// Empties the voxel at `position`, returning what was there
+    pub fn remove(&mut self, position: [i32; 3]) -> Option<VoxelId> {
+        let key = self.cell_key(position.map(|c| c as f32));
+        let cell = self.cells.get_mut(&key)?;
+        let index = cell.iter().position(|(p, _)| *p == position)?;
+        let (_, voxel) = cell.swap_remove(index);
+        if cell.is_empty() {
+            self.cells.remove(&key);
+        }
+        self.len -= 1;
+        Some(voxel)
+    }
+
+    pub fn get(&self, position: [i32; 3]) -> Option<VoxelId> {
+        let key = self.cell_key(position.map(|c| c as f32));
+        self.cells.get(&key)?.iter().find(|(p, _)| *p == position).map(|&(_, voxel)| voxel)
+    }
+
+    /// Number of filled voxels
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Filled voxels overlapping the box from `min` to `max`, in x, then y,
+    /// then z order. Voxels that only touch the box's faces don't overlap it.
+    pub fn query_aabb(&self, min: [f32; 3], max: [f32; 3]) -> Vec<([i32; 3], VoxelId)> {
+        // A voxel overlaps the box if its minimum corner lies within one
+        // voxel below `min` and `max`
+        let low = self.cell_key(min.map(|c| c - 1.0));
+        let high = self.cell_key(max);
+        let mut found = Vec::new();
+        for x in low.0..=high.0 {
+            for y in low.1..=high.1 {
+                for z in low.2..=high.2 {
+                    let Some(cell) = self.cells.get(&(x, y, z)) else {
+                        continue;
+                    };
+                    found.extend(cell.iter().filter(|(p, _)| {
+                        (0..3).all(|axis| {
+                            let voxel_min = p[axis] as f32;
+                            voxel_min < max[axis] && voxel_min + 1.0 > min[axis]
+                        })
+                    }));
+                }
+            }
+        }
+        found.sort_unstable_by_key(|&(p, _)| p);
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+
+    #[test]
+    fn test_query_returns_only_overlapping_voxels() {
+        let mut hash = SpatialHash::new(DEFAULT_CELL_SIZE);
+        for x in -3..3 {
+            for z in -3..3 {
+                hash.insert([x, 0, z], registry::STONE);
+            }
+        }
+        hash.insert([0, 1, 0], registry::DIRT);
+        assert_eq!(hash.len(), 37);
+
+        // A player-sized box standing on the floor, overlapping the dirt
+        let found = hash.query_aabb([-0.3, 1.0, -0.3], [0.3, 2.8, 0.3]);
+        assert_eq!(found, vec![([0, 1, 0], registry::DIRT)]);
+
+        // Sunk into the floor across a cell boundary at negative coordinates
+        let found = hash.query_aabb([-1.5, 0.5, -0.5], [-0.5, 2.3, 0.5]);
+        let positions: Vec<[i32; 3]> = found.iter().map(|&(p, _)| p).collect();
+        assert_eq!(positions, vec![[-2, 0, -1], [-2, 0, 0], [-1, 0, -1], [-1, 0, 0]]);
+
+        assert!(hash.query_aabb([10.0, 0.0, 10.0], [11.0, 2.0, 11.0]).is_empty());
+    }
+
+    #[test]
+    fn test_insert_replaces_and_remove_empties() {
+        let mut hash = SpatialHash::new(DEFAULT_CELL_SIZE);
+        hash.insert([4, 5, -6], registry::STONE);
+        hash.insert([4, 5, -6], registry::GRASS);
+        assert_eq!(hash.len(), 1);
+        assert_eq!(hash.get([4, 5, -6]), Some(registry::GRASS));
+
+        assert_eq!(hash.remove([4, 5, -6]), Some(registry::GRASS));
+        assert_eq!(hash.remove([4, 5, -6]), None);
+        assert!(hash.is_empty());
+        assert!(hash.cells.is_empty());
+    }
+}
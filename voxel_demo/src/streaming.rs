@@ -0,0 +1,464 @@
+// World streaming
+// Keeps only the chunks around the camera in memory. Every chunk is stored in
+// its own file, using the same version byte + bincode run-length layout as
+// whole-world saves, and all disk access happens on a tokio runtime so a
+// frame never waits on I/O.
+
+use crate::error::{RobinError, RobinResult};
+use crate::persistence::{encode_runs, FORMAT_VERSION};
+use crate::registry::VoxelId;
+use crate::CHUNK_SIZE;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Chunk position in chunks, not voxels
+pub type ChunkCoord = [i32; 3];
+
+/// Extra rings of chunks kept loaded beyond the load radius, so walking back
+/// and forth over a chunk boundary doesn't reload the same chunks every frame
+pub const UNLOAD_HYSTERESIS: u32 = 2;
+
+const CHUNK_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+/// The voxels of one chunk, in x, y, z order
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoxelChunk {
+    voxels: Vec<Option<VoxelId>>,
+    /// Changed since it was read from disk, so it has to be written back on unload
+    pub dirty: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChunkFile {
+    coord: ChunkCoord,
+    /// (run length, voxel) pairs in x, y, z order
+    runs: Vec<(u32, Option<VoxelId>)>,
+}
+
+impl VoxelChunk {
+    pub fn empty() -> Self {
+        Self { voxels: vec![None; CHUNK_VOLUME], dirty: false }
+    }
+
+    /// Chunk-local coordinates, each below `CHUNK_SIZE`
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<VoxelId> {
+        self.voxels[Self::index(x, y, z)]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, voxel: Option<VoxelId>) {
+        let index = Self::index(x, y, z);
+        if self.voxels[index] != voxel {
+            self.voxels[index] = voxel;
+            self.dirty = true;
+        }
+    }
+
+    fn index(x: usize, y: usize, z: usize) -> usize {
+        (x * CHUNK_SIZE + y) * CHUNK_SIZE + z
+    }
+
+    pub fn to_bytes(&self, coord: ChunkCoord) -> RobinResult<Vec<u8>> {
+        let file = ChunkFile {
+            coord,
+            runs: encode_runs(self.voxels.iter().copied()),
+        };
+        let mut bytes = vec![FORMAT_VERSION];
+        bytes.extend(bincode::serialize(&file)?);
+        Ok(bytes)
+    }
+
+    /// Parses a chunk file, checking that it holds the chunk at `coord`
+    pub fn from_bytes(bytes: &[u8], coord: ChunkCoord) -> RobinResult<Self> {
+        let (&version, payload) = bytes.split_first().ok_or_else(|| RobinError::InvalidData {
+            field: "version".to_string(),
+            reason: "file is empty".to_string(),
+        })?;
+        if version != FORMAT_VERSION {
+            return Err(RobinError::UnsupportedVersion {
+                found: version,
+                expected: FORMAT_VERSION,
+            });
+        }
+
+        let file: ChunkFile = bincode::deserialize(payload)?;
+        if file.coord != coord {
+            return Err(RobinError::InvalidData {
+                field: "coord".to_string(),
+                reason: format!("expected chunk {:?}, found {:?}", coord, file.coord),
+            });
+        }
+        let total: u64 = file.runs.iter().map(|&(length, _)| length as u64).sum();
+        if total != CHUNK_VOLUME as u64 {
+            return Err(RobinError::InvalidData {
+                field: "runs".to_string(),
+                reason: format!("{} voxels encoded for a {}³ chunk", total, CHUNK_SIZE),
+            });
+        }
+
+        let mut voxels = Vec::with_capacity(CHUNK_VOLUME);
+        for (length, voxel) in file.runs {
+            voxels.extend(std::iter::repeat_n(voxel, length as usize));
+        }
+        Ok(Self { voxels, dirty: false })
+    }
+}
+
+/// Where the chunk at `coord` is stored inside `directory`
+pub fn chunk_path(directory: &Path, coord: ChunkCoord) -> PathBuf {
+    directory.join(format!("chunk_{}_{}_{}.bin", coord[0], coord[1], coord[2]))
+}
+
+/// The chunk a world-space position falls in
+pub fn chunk_containing(position: [f32; 3]) -> ChunkCoord {
+    position.map(|axis| (axis / CHUNK_SIZE as f32).floor() as i32)
+}
+
+/// Distance between two chunks in chunks, along the axis where they're
+/// furthest apart, so the chunks within `r` of a chunk form a cube
+pub fn chunk_distance(a: ChunkCoord, b: ChunkCoord) -> u32 {
+    (0..3).map(|axis| a[axis].abs_diff(b[axis])).max().unwrap_or(0)
+}
+
+/// Builds a chunk that has never been written to disk
+pub type ChunkGenerator = dyn Fn(ChunkCoord) -> VoxelChunk + Send + Sync;
+
+/// Chunks that arrived in or left memory during one `update_position`
+#[derive(Debug, Default)]
+pub struct StreamEvents {
+    pub loaded: Vec<ChunkCoord>,
+    pub unloaded: Vec<ChunkCoord>,
+}
+
+/// Loads the chunks within `load_radius` of the camera from per-chunk files
+/// and unloads them once they're more than `load_radius + UNLOAD_HYSTERESIS`
+/// away, writing back any that changed
+pub struct WorldStreamer {
+    loaded_chunks: HashMap<ChunkCoord, VoxelChunk>,
+    load_radius: u32,
+    io_pool: tokio::runtime::Handle,
+    directory: PathBuf,
+    generator: Arc<ChunkGenerator>,
+    /// Exclusive upper corner of the chunks that exist, if the world is finite
+    bounds: Option<ChunkCoord>,
+    /// Chunks with a load in flight
+    loading: HashSet<ChunkCoord>,
+    /// Writes of unloaded chunks that may still be running; reloading one of
+    /// these chunks waits for its write first
+    saving: HashMap<ChunkCoord, JoinHandle<()>>,
+    sender: mpsc::UnboundedSender<(ChunkCoord, VoxelChunk)>,
+    receiver: mpsc::UnboundedReceiver<(ChunkCoord, VoxelChunk)>,
+}
+
+impl WorldStreamer {
+    /// Chunks missing from `directory` are built by `generator`
+    pub fn new(
+        directory: impl Into<PathBuf>,
+        load_radius: u32,
+        io_pool: tokio::runtime::Handle,
+        generator: impl Fn(ChunkCoord) -> VoxelChunk + Send + Sync + 'static,
+    ) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            loaded_chunks: HashMap::new(),
+            load_radius,
+            io_pool,
+            directory: directory.into(),
+            generator: Arc::new(generator),
+            bounds: None,
+            loading: HashSet::new(),
+            saving: HashMap::new(),
+            sender,
+            receiver,
+        }
+    }
+
+    /// Limits streaming to chunks from the origin up to (not including) `bounds`
+    pub fn with_bounds(mut self, bounds: ChunkCoord) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    pub fn load_radius(&self) -> u32 {
+        self.load_radius
+    }
+
+    pub fn loaded_chunks(&self) -> &HashMap<ChunkCoord, VoxelChunk> {
+        &self.loaded_chunks
+    }
+
+    #[cfg(test)]
+    pub fn is_loaded(&self, coord: ChunkCoord) -> bool {
+        self.loaded_chunks.contains_key(&coord)
+    }
+
+    pub fn chunk(&self, coord: ChunkCoord) -> Option<&VoxelChunk> {
+        self.loaded_chunks.get(&coord)
+    }
+
+    /// Number of loads still in flight
+    #[cfg(test)]
+    pub fn pending_loads(&self) -> usize {
+        self.loading.len()
+    }
+
+    /// Adds a chunk that is already in memory, e.g. while handing over a
+    /// world that was generated in one piece
+    pub fn insert(&mut self, coord: ChunkCoord, chunk: VoxelChunk) {
+        self.loading.remove(&coord);
+        self.loaded_chunks.insert(coord, chunk);
+    }
+
+    /// Replaces a loaded chunk's voxels and marks it for writing on unload.
+    /// Returns false, changing nothing, if the chunk isn't loaded.
+    pub fn update_chunk(&mut self, coord: ChunkCoord, mut chunk: VoxelChunk) -> bool {
+        match self.loaded_chunks.get_mut(&coord) {
+            Some(loaded) => {
+                chunk.dirty = true;
+                *loaded = chunk;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Takes in the loads that finished since the last call, unloads chunks
+    /// that are now too far from `camera` and starts loading the missing ones
+    /// within `load_radius`. Never blocks on disk.
+    pub fn update_position(&mut self, camera: [f32; 3]) -> StreamEvents {
+        let center = chunk_containing(camera);
+        let keep_radius = self.load_radius + UNLOAD_HYSTERESIS;
+        let mut events = StreamEvents::default();
+
+        // Loads for chunks the camera has already moved away from are dropped
+        // here; they were read from disk, so nothing is lost
+        while let Ok((coord, chunk)) = self.receiver.try_recv() {
+            if self.loading.remove(&coord) && chunk_distance(coord, center) <= keep_radius {
+                self.loaded_chunks.insert(coord, chunk);
+                events.loaded.push(coord);
+            }
+        }
+
+        let far: Vec<ChunkCoord> = self
+            .loaded_chunks
+            .keys()
+            .filter(|&&coord| chunk_distance(coord, center) > keep_radius)
+            .copied()
+            .collect();
+        for coord in far {
+            if let Some(chunk) = self.loaded_chunks.remove(&coord) {
+                if chunk.dirty {
+                    self.spawn_save(coord, chunk);
+                }
+                events.unloaded.push(coord);
+            }
+        }
+        self.saving.retain(|_, write| !write.is_finished());
+
+        let radius = self.load_radius as i32;
+        for dx in -radius..=radius {
+            for dy in -radius..=radius {
+                for dz in -radius..=radius {
+                    let coord = [center[0] + dx, center[1] + dy, center[2] + dz];
+                    if self.in_bounds(coord) && !self.loaded_chunks.contains_key(&coord) && !self.loading.contains(&coord) {
+                        self.spawn_load(coord);
+                    }
+                }
+            }
+        }
+
+        events
+    }
+
+    fn in_bounds(&self, coord: ChunkCoord) -> bool {
+        match self.bounds {
+            Some(bounds) => (0..3).all(|axis| coord[axis] >= 0 && coord[axis] < bounds[axis]),
+            None => true,
+        }
+    }
+
+    fn spawn_load(&mut self, coord: ChunkCoord) {
+        self.loading.insert(coord);
+        let pending_write = self.saving.remove(&coord);
+        let path = chunk_path(&self.directory, coord);
+        let generator = self.generator.clone();
+        let sender = self.sender.clone();
+
+        self.io_pool.spawn(async move {
+            if let Some(write) = pending_write {
+                write.await.ok();
+            }
+            let chunk = match tokio::fs::read(&path).await {
+                Ok(bytes) => VoxelChunk::from_bytes(&bytes, coord).unwrap_or_else(|e| {
+                    println!("⚠️  Regenerating chunk {:?}, {} is unreadable: {}", coord, path.display(), e);
+                    generator(coord)
+                }),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => generator(coord),
+                Err(e) => {
+                    println!("⚠️  Regenerating chunk {:?}, couldn't read {}: {}", coord, path.display(), e);
+                    generator(coord)
+                }
+            };
+            // The streamer may have been dropped meanwhile
+            sender.send((coord, chunk)).ok();
+        });
+    }
+
+    fn spawn_save(&mut self, coord: ChunkCoord, chunk: VoxelChunk) {
+        let path = chunk_path(&self.directory, coord);
+        let directory = self.directory.clone();
+        let write = self.io_pool.spawn(async move {
+            let result = match chunk.to_bytes(coord) {
+                Ok(bytes) => match tokio::fs::create_dir_all(&directory).await {
+                    Ok(()) => tokio::fs::write(&path, bytes).await.map_err(RobinError::from),
+                    Err(e) => Err(e.into()),
+                },
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                println!("⚠️  Lost changes to chunk {:?}, couldn't write {}: {}", coord, path.display(), e);
+            }
+        });
+        self.saving.insert(coord, write);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+    use std::time::{Duration, Instant};
+
+    fn temp_directory(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("robin_voxel_stream_{}_{}", name, std::process::id()))
+    }
+
+    /// Ground up to y = 0 in every column, so each chunk at cy = 0 has
+    /// stone in its bottom layer
+    fn ground(coord: ChunkCoord) -> VoxelChunk {
+        let mut chunk = VoxelChunk::empty();
+        if coord[1] == 0 {
+            for x in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    chunk.set(x, 0, z, Some(registry::STONE));
+                }
+            }
+        }
+        chunk.dirty = false;
+        chunk
+    }
+
+    /// Calls `update_position` until every load has landed
+    fn settle(streamer: &mut WorldStreamer, camera: [f32; 3]) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        streamer.update_position(camera);
+        while streamer.pending_loads() > 0 {
+            assert!(Instant::now() < deadline, "chunk loads never finished");
+            std::thread::sleep(Duration::from_millis(1));
+            streamer.update_position(camera);
+        }
+    }
+
+    #[test]
+    fn test_chunk_bytes_round_trip() {
+        let mut chunk = ground([0, 0, 0]);
+        chunk.set(3, 7, 11, Some(registry::CRYSTAL));
+        let bytes = chunk.to_bytes([4, 0, -2]).unwrap();
+        assert_eq!(bytes[0], FORMAT_VERSION);
+
+        let loaded = VoxelChunk::from_bytes(&bytes, [4, 0, -2]).unwrap();
+        assert_eq!(loaded.voxels, chunk.voxels);
+        assert!(!loaded.dirty);
+        assert!(matches!(
+            VoxelChunk::from_bytes(&bytes, [4, 0, -1]),
+            Err(RobinError::InvalidData { .. })
+        ));
+    }
+
+    #[test]
+    fn test_chunk_distance_is_chebyshev() {
+        assert_eq!(chunk_distance([0, 0, 0], [3, -1, 2]), 3);
+        assert_eq!(chunk_distance([-2, 5, 0], [-2, 5, 0]), 0);
+        assert_eq!(chunk_containing([-0.5, 15.9, 16.0]), [-1, 0, 1]);
+    }
+
+    #[test]
+    fn test_streaming_stress_keeps_chunks_near_camera() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let directory = temp_directory("stress");
+        let mut streamer = WorldStreamer::new(&directory, 2, runtime.handle().clone(), ground);
+
+        let mut camera = [8.0, 8.0, 8.0];
+        settle(&mut streamer, camera);
+        assert_eq!(streamer.loaded_chunks().len(), 5 * 5 * 5);
+
+        // 1000 units along a diagonal, in steps small enough that loads are
+        // usually still in flight when the camera moves on
+        let step = 1000.0 / 3f32.sqrt() / 2000.0;
+        for frame in 0..2000 {
+            camera = camera.map(|axis| axis + step);
+            streamer.update_position(camera);
+            if frame % 50 == 0 {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+        settle(&mut streamer, camera);
+
+        let center = chunk_containing(camera);
+        let limit = streamer.load_radius() + UNLOAD_HYSTERESIS;
+        for &coord in streamer.loaded_chunks().keys() {
+            assert!(
+                chunk_distance(coord, center) <= limit,
+                "chunk {:?} is {} chunks from the camera",
+                coord,
+                chunk_distance(coord, center)
+            );
+        }
+        for dx in -2..=2 {
+            for dy in -2..=2 {
+                for dz in -2..=2 {
+                    assert!(streamer.is_loaded([center[0] + dx, center[1] + dy, center[2] + dz]));
+                }
+            }
+        }
+
+        drop(streamer);
+        drop(runtime);
+        std::fs::remove_dir_all(&directory).ok();
+    }
+
+    #[test]
+    fn test_edited_chunk_survives_unload() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let directory = temp_directory("edit");
+        let mut streamer = WorldStreamer::new(&directory, 1, runtime.handle().clone(), ground);
+
+        let home = [8.0, 8.0, 8.0];
+        settle(&mut streamer, home);
+        let mut chunk = streamer.chunk([0, 0, 0]).unwrap().clone();
+        chunk.set(5, 5, 5, Some(registry::CRYSTAL));
+        assert!(streamer.update_chunk([0, 0, 0], chunk));
+
+        let away = [8.0 + 16.0 * 10.0, 8.0, 8.0];
+        let events = streamer.update_position(away);
+        assert!(events.unloaded.contains(&[0, 0, 0]));
+        assert!(!streamer.is_loaded([0, 0, 0]));
+
+        // Coming straight back has to wait for the write, not read a stale file
+        settle(&mut streamer, home);
+        let reloaded = streamer.chunk([0, 0, 0]).unwrap();
+        assert_eq!(reloaded.get(5, 5, 5), Some(registry::CRYSTAL));
+        assert_eq!(reloaded.get(5, 0, 5), Some(registry::STONE));
+        assert!(!reloaded.dirty);
+        assert!(chunk_path(&directory, [0, 0, 0]).exists());
+        // Untouched chunks are regenerated rather than written
+        assert!(!chunk_path(&directory, [1, 0, 0]).exists());
+
+        drop(streamer);
+        drop(runtime);
+        std::fs::remove_dir_all(&directory).ok();
+    }
+}
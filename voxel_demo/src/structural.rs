@@ -0,0 +1,209 @@
+// Structural analysis
+// A simplified vertical load model: every voxel weighs one unit and hands
+// its load, plus whatever rests on it, down to the voxels holding it up. A
+// voxel is held by the voxels directly or diagonally below it, or failing
+// that by a held voxel beside it no more than `MAX_CANTILEVER` voxels away.
+// Anything without such a path down to the bottom of the world is unstable.
+
+use crate::VoxelWorld;
+use std::collections::{HashMap, VecDeque};
+
+type Position = (usize, usize, usize);
+
+/// How far, in voxels, a voxel with nothing beneath it may reach sideways
+/// for a supported neighbour
+pub const MAX_CANTILEVER: usize = 2;
+
+/// Weight every filled voxel contributes
+const VOXEL_WEIGHT: f32 = 1.0;
+
+#[derive(Debug, Clone, Default)]
+pub struct StructuralReport {
+    /// Filled voxels with no load path to the ground, in x, then y, then z
+    /// order
+    pub unstable_voxels: Vec<Position>,
+    /// Load each filled voxel carries, its own weight included. Unstable
+    /// voxels keep what rests on them rather than passing it on.
+    pub load_distribution: HashMap<Position, f32>,
+}
+
+impl StructuralReport {
+    #[cfg(test)]
+    pub fn is_stable(&self) -> bool {
+        self.unstable_voxels.is_empty()
+    }
+}
+
+/// Where a stable voxel sends its load
+enum Support {
+    /// Resting on the bottom of the world
+    Ground,
+    /// Shared evenly among the stable voxels directly and diagonally below
+    Below(Vec<Position>),
+    /// Passed to the neighbour in the same layer it hangs from, `reach`
+    /// voxels out from a supported one
+    Beside { neighbour: Position, reach: usize },
+}
+
+pub struct StructuralAnalyzer;
+
+impl StructuralAnalyzer {
+    pub fn analyze(world: &VoxelWorld) -> StructuralReport {
+        let size = world.size();
+        let filled = |x: usize, y: usize, z: usize| world.get(x, y, z).is_some();
+
+        // Bottom up: a layer's supports are only known once the layer
+        // beneath it has been settled
+        let mut supports: HashMap<Position, Support> = HashMap::new();
+        for y in 0..size {
+            let mut queue = VecDeque::new();
+            for x in 0..size {
+                for z in 0..size {
+                    if !filled(x, y, z) {
+                        continue;
+                    }
+                    let support = if y == 0 {
+                        Support::Ground
+                    } else {
+                        let below: Vec<Position> = neighbourhood(x, z, size)
+                            .map(|(nx, nz)| (nx, y - 1, nz))
+                            .filter(|p| supports.contains_key(p))
+                            .collect();
+                        if below.is_empty() {
+                            continue;
+                        }
+                        Support::Below(below)
+                    };
+                    supports.insert((x, y, z), support);
+                    queue.push_back(((x, y, z), 0));
+                }
+            }
+
+            // Breadth first, so each overhanging voxel hangs from the
+            // nearest supported one
+            while let Some(((x, y, z), reach)) = queue.pop_front() {
+                if reach == MAX_CANTILEVER {
+                    continue;
+                }
+                let sideways = [
+                    (x.wrapping_sub(1), z),
+                    (x + 1, z),
+                    (x, z.wrapping_sub(1)),
+                    (x, z + 1),
+                ];
+                for (nx, nz) in sideways {
+                    let position = (nx, y, nz);
+                    if nx < size && nz < size && filled(nx, y, nz) && !supports.contains_key(&position) {
+                        supports.insert(
+                            position,
+                            Support::Beside {
+                                neighbour: (x, y, z),
+                                reach: reach + 1,
+                            },
+                        );
+                        queue.push_back((position, reach + 1));
+                    }
+                }
+            }
+        }
+
+        let mut report = StructuralReport::default();
+        for x in 0..size {
+            for y in 0..size {
+                for z in 0..size {
+                    if filled(x, y, z) {
+                        report.load_distribution.insert((x, y, z), VOXEL_WEIGHT);
+                        if !supports.contains_key(&(x, y, z)) {
+                            report.unstable_voxels.push((x, y, z));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Top down, and within a layer from the far end of each overhang
+        // inwards, so every voxel has received its load before passing it on
+        let mut order: Vec<(&Position, &Support)> = supports.iter().collect();
+        order.sort_by_key(|&(&(x, y, z), support)| {
+            let reach = match support {
+                Support::Beside { reach, .. } => *reach,
+                _ => 0,
+            };
+            (std::cmp::Reverse(y), std::cmp::Reverse(reach), x, z)
+        });
+        for (position, support) in order {
+            let load = report.load_distribution[position];
+            match support {
+                Support::Ground => {}
+                Support::Below(below) => {
+                    let share = load / below.len() as f32;
+                    for p in below {
+                        *report.load_distribution.get_mut(p).unwrap() += share;
+                    }
+                }
+                Support::Beside { neighbour, .. } => {
+                    *report.load_distribution.get_mut(neighbour).unwrap() += load;
+                }
+            }
+        }
+        report
+    }
+}
+
+/// Columns (x, z) directly and diagonally around (x, z) that lie in the world
+fn neighbourhood(x: usize, z: usize, size: usize) -> impl Iterator<Item = (usize, usize)> {
+    (x.saturating_sub(1)..=(x + 1).min(size - 1))
+        .flat_map(move |nx| (z.saturating_sub(1)..=(z + 1).min(size - 1)).map(move |nz| (nx, nz)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+
+    #[test]
+    fn test_pillar_is_stable_and_carries_its_weight_down() {
+        let mut world = VoxelWorld::empty(8);
+        for y in 0..5 {
+            world.set_voxel(3, y, 3, Some(registry::STONE));
+        }
+
+        let report = StructuralAnalyzer::analyze(&world);
+        assert!(report.is_stable());
+        assert_eq!(report.load_distribution.len(), 5);
+        for y in 0..5 {
+            assert_eq!(report.load_distribution[&(3, y, 3)], (5 - y) as f32);
+        }
+    }
+
+    #[test]
+    fn test_floating_block_is_unstable() {
+        let mut world = VoxelWorld::empty(8);
+        world.set_voxel(2, 0, 2, Some(registry::STONE));
+        world.set_voxel(5, 4, 5, Some(registry::STONE));
+
+        let report = StructuralAnalyzer::analyze(&world);
+        assert_eq!(report.unstable_voxels, vec![(5, 4, 5)]);
+        assert_eq!(report.load_distribution[&(5, 4, 5)], 1.0);
+    }
+
+    #[test]
+    fn test_overhang_is_held_up_to_two_voxels_out() {
+        let mut world = VoxelWorld::empty(8);
+        for y in 0..3 {
+            world.set_voxel(1, y, 1, Some(registry::STONE));
+        }
+        // A diagonal step, then a ledge reaching out along x
+        world.set_voxel(2, 3, 2, Some(registry::STONE));
+        for x in 3..6 {
+            world.set_voxel(x, 3, 2, Some(registry::STONE));
+        }
+
+        let report = StructuralAnalyzer::analyze(&world);
+        assert_eq!(report.unstable_voxels, vec![(5, 3, 2)]);
+        // The ledge's two held voxels rest on the step, which rests on the
+        // pillar; the unstable end passes nothing on
+        assert_eq!(report.load_distribution[&(2, 3, 2)], 3.0);
+        assert_eq!(report.load_distribution[&(1, 0, 1)], 6.0);
+    }
+}
@@ -0,0 +1,373 @@
+// World templates
+// Structures copied out of a world so they can be stamped down again anywhere,
+// turned in quarter steps about the vertical axis. Templates are saved as JSON
+// that names each voxel's type rather than its id, so they carry over between
+// worlds whose registries number their types differently.
+
+use crate::console::CommandHandler;
+use crate::edit_history::VoxelEdit;
+use crate::error::{RobinError, RobinResult};
+use crate::registry::{VoxelId, VoxelRegistry};
+use crate::VoxelWorld;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Bump whenever the JSON layout changes
+pub const TEMPLATE_FORMAT_VERSION: u8 = 1;
+
+/// A turn about the vertical axis, counter-clockwise seen from above, so a
+/// quarter turn takes +x to -z
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rotation90 {
+    None,
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
+
+impl Rotation90 {
+    /// Accepts 0, 90, 180 and 270
+    pub fn from_degrees(degrees: u32) -> Option<Self> {
+        match degrees {
+            0 => Some(Rotation90::None),
+            90 => Some(Rotation90::Quarter),
+            180 => Some(Rotation90::Half),
+            270 => Some(Rotation90::ThreeQuarters),
+            _ => None,
+        }
+    }
+
+    pub fn apply(self, (x, y, z): (i32, i32, i32)) -> (i32, i32, i32) {
+        match self {
+            Rotation90::None => (x, y, z),
+            Rotation90::Quarter => (z, y, -x),
+            Rotation90::Half => (-x, y, -z),
+            Rotation90::ThreeQuarters => (-z, y, x),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorldTemplate {
+    /// Voxels by offset from the corner of the captured box; air is left
+    /// out, so stamping never clears anything
+    pub voxels: HashMap<(i32, i32, i32), VoxelId>,
+    /// Offset that lands on the stamp position and that rotations turn about
+    pub origin: [i32; 3],
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TemplateFile {
+    version: u8,
+    name: String,
+    origin: [i32; 3],
+    voxels: Vec<TemplateVoxel>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TemplateVoxel {
+    offset: [i32; 3],
+    voxel: String,
+}
+
+impl WorldTemplate {
+    /// Copies the box between `min` and `max`, both corners included and
+    /// clamped to the world. The origin is the middle of the box's floor.
+    pub fn capture(world: &VoxelWorld, min: [usize; 3], max: [usize; 3]) -> Self {
+        let last = world.size().saturating_sub(1);
+        let low = [0, 1, 2].map(|axis| min[axis].min(max[axis]).min(last));
+        let high = [0, 1, 2].map(|axis| min[axis].max(max[axis]).min(last));
+
+        let mut voxels = HashMap::new();
+        for x in low[0]..=high[0] {
+            for y in low[1]..=high[1] {
+                for z in low[2]..=high[2] {
+                    if let Some(voxel) = world.get(x, y, z) {
+                        let offset = ((x - low[0]) as i32, (y - low[1]) as i32, (z - low[2]) as i32);
+                        voxels.insert(offset, voxel);
+                    }
+                }
+            }
+        }
+
+        Self {
+            voxels,
+            origin: [(high[0] - low[0]) as i32 / 2, 0, (high[2] - low[2]) as i32 / 2],
+            name: String::new(),
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// World position each voxel lands on when stamped at `position`
+    pub fn placements(&self, position: [i32; 3], rotation: Rotation90) -> impl Iterator<Item = ([i32; 3], VoxelId)> + '_ {
+        self.voxels.iter().map(move |(&(x, y, z), &voxel)| {
+            let [ox, oy, oz] = self.origin;
+            let (x, y, z) = rotation.apply((x - ox, y - oy, z - oz));
+            ([position[0] + x, position[1] + y, position[2] + z], voxel)
+        })
+    }
+
+    /// Places the template with its origin at `position`, turned by
+    /// `rotation`. Voxels falling outside the world are dropped. Returns the
+    /// edits made, leaving out voxels that already held the right type.
+    pub fn stamp(&self, world: &mut VoxelWorld, position: [i32; 3], rotation: Rotation90) -> Vec<VoxelEdit> {
+        let size = world.size() as i32;
+        let mut edits = Vec::new();
+        for ([x, y, z], voxel) in self.placements(position, rotation) {
+            if [x, y, z].iter().any(|&c| c < 0 || c >= size) {
+                continue;
+            }
+            let coord = (x as usize, y as usize, z as usize);
+            let before = world.get(coord.0, coord.1, coord.2);
+            if before != Some(voxel) {
+                world.set_voxel(coord.0, coord.1, coord.2, Some(voxel));
+                edits.push(VoxelEdit { coord, before, after: Some(voxel) });
+            }
+        }
+        edits
+    }
+
+    pub fn save(&self, path: &Path, registry: &VoxelRegistry) -> RobinResult<()> {
+        let mut voxels: Vec<TemplateVoxel> = self
+            .voxels
+            .iter()
+            .map(|(&(x, y, z), &voxel)| {
+                let definition = registry.get(voxel).ok_or_else(|| RobinError::InvalidData {
+                    field: "template".to_string(),
+                    reason: format!("voxel id {} isn't registered", voxel),
+                })?;
+                Ok(TemplateVoxel { offset: [x, y, z], voxel: definition.name.clone() })
+            })
+            .collect::<RobinResult<_>>()?;
+        // Stable output, so saving the same template twice gives the same file
+        voxels.sort_by_key(|voxel| voxel.offset);
+
+        let file = TemplateFile {
+            version: TEMPLATE_FORMAT_VERSION,
+            name: self.name.clone(),
+            origin: self.origin,
+            voxels,
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&file)?)?;
+        Ok(())
+    }
+
+    /// Loads a template, looking its voxel types up by name in `registry`
+    pub fn load(path: &Path, registry: &VoxelRegistry) -> RobinResult<Self> {
+        let file: TemplateFile = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if file.version != TEMPLATE_FORMAT_VERSION {
+            return Err(RobinError::UnsupportedVersion {
+                found: file.version,
+                expected: TEMPLATE_FORMAT_VERSION,
+            });
+        }
+
+        let mut voxels = HashMap::new();
+        for TemplateVoxel { offset: [x, y, z], voxel } in file.voxels {
+            let id = registry.find(&voxel).ok_or_else(|| RobinError::InvalidData {
+                field: "template".to_string(),
+                reason: format!("unknown voxel type '{}'", voxel),
+            })?;
+            voxels.insert((x, y, z), id);
+        }
+        Ok(Self {
+            voxels,
+            origin: file.origin,
+            name: file.name,
+        })
+    }
+}
+
+/// Template names become file names, so they're kept to letters, digits,
+/// `-` and `_`
+fn template_path(directory: &Path, name: &str) -> Option<PathBuf> {
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| directory.join(format!("{}.json", name)))
+}
+
+/// Names of the templates saved in `directory`, sorted
+pub fn list_templates(directory: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// The `template` console command, browsing templates saved in `directory`:
+///
+/// - `template list`
+/// - `template save NAME X1 Y1 Z1 X2 Y2 Z2`
+/// - `template stamp NAME X Y Z [0|90|180|270]`
+pub fn template_command(directory: PathBuf) -> CommandHandler {
+    const USAGE: &str =
+        "usage: template list | template save NAME X1 Y1 Z1 X2 Y2 Z2 | template stamp NAME X Y Z [0|90|180|270]";
+    Box::new(move |args, world| {
+        let parse = |values: &[&str]| values.iter().map(|value| value.parse().ok()).collect::<Option<Vec<i64>>>();
+        match args {
+            ["list"] => {
+                let names = list_templates(&directory);
+                if names.is_empty() {
+                    format!("No templates in {}", directory.display())
+                } else {
+                    format!("Templates: {}", names.join(", "))
+                }
+            }
+            ["save", name, coords @ ..] if coords.len() == 6 => {
+                let Some(path) = template_path(&directory, name) else {
+                    return format!("Invalid template name '{}'", name);
+                };
+                let Some(coords) = parse(coords).filter(|coords| coords.iter().all(|&c| c >= 0)) else {
+                    return USAGE.to_string();
+                };
+                let corner = |start: usize| [0, 1, 2].map(|axis| coords[start + axis] as usize);
+                let template = WorldTemplate::capture(world, corner(0), corner(3)).with_name(name);
+                match template.save(&path, world.registry()) {
+                    Ok(()) => format!("Saved '{}' ({} voxels)", name, template.voxels.len()),
+                    Err(e) => format!("Failed to save '{}': {}", name, e),
+                }
+            }
+            ["stamp", name, rest @ ..] if rest.len() == 3 || rest.len() == 4 => {
+                let Some(path) = template_path(&directory, name) else {
+                    return format!("Invalid template name '{}'", name);
+                };
+                let (Some(values), rotation) = (parse(rest), rest.get(3)) else {
+                    return USAGE.to_string();
+                };
+                let rotation = match rotation {
+                    None => Rotation90::None,
+                    Some(_) => match Rotation90::from_degrees(values[3] as u32) {
+                        Some(rotation) => rotation,
+                        None => return "Rotation must be 0, 90, 180 or 270".to_string(),
+                    },
+                };
+                let position = [0, 1, 2].map(|axis| values[axis] as i32);
+                match WorldTemplate::load(&path, world.registry()) {
+                    Ok(template) => {
+                        let edits = template.stamp(world, position, rotation);
+                        format!("Stamped '{}', changing {} voxels", name, edits.len())
+                    }
+                    Err(e) => format!("Failed to load '{}': {}", name, e),
+                }
+            }
+            _ => USAGE.to_string(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::DevConsole;
+    use crate::registry;
+
+    /// A 3×3×3 cube of stone with a dirt column along its -x, -z edge and a
+    /// crystal on top of that column, so every rotation looks different
+    fn marked_cube(world: &mut VoxelWorld) {
+        for x in 0..3 {
+            for y in 0..3 {
+                for z in 0..3 {
+                    let voxel = if x == 0 && z == 0 { registry::DIRT } else { registry::STONE };
+                    world.set_voxel(x, y, z, Some(voxel));
+                }
+            }
+        }
+        world.set_voxel(0, 3, 0, Some(registry::CRYSTAL));
+    }
+
+    #[test]
+    fn test_stamp_rotated_quarter_turn() {
+        let mut source = VoxelWorld::empty(16);
+        marked_cube(&mut source);
+        let template = WorldTemplate::capture(&source, [0, 0, 0], [2, 2, 2]);
+        assert_eq!(template.voxels.len(), 27);
+        assert_eq!(template.origin, [1, 0, 1]);
+
+        let mut world = VoxelWorld::empty(16);
+        let edits = template.stamp(&mut world, [10, 5, 10], Rotation90::Quarter);
+        assert_eq!(edits.len(), 27);
+
+        // The origin (1, 0, 1) lands on (10, 5, 10); offsets from it turn
+        // (x, z) -> (z, -x), filling x and z from 9 to 11
+        for x in 9..12 {
+            for y in 5..8 {
+                for z in 9..12 {
+                    assert!(world.get(x, y, z).is_some(), "({}, {}, {}) is empty", x, y, z);
+                }
+            }
+        }
+        assert_eq!(world.get(9, 8, 11), None, "the crystal above the cube wasn't captured");
+        // The dirt column at offset (-1, -1) from the origin turns to (-1, 1)
+        for y in 5..8 {
+            assert_eq!(world.get(9, y, 11), Some(registry::DIRT));
+            assert_eq!(world.get(9, y, 9), Some(registry::STONE));
+        }
+        let dirt: Vec<(usize, usize, usize)> =
+            edits.iter().filter(|edit| edit.after == Some(registry::DIRT)).map(|edit| edit.coord).collect();
+        assert_eq!(dirt.len(), 3);
+        assert!(dirt.iter().all(|&(x, _, z)| (x, z) == (9, 11)));
+
+        // Stamping again changes nothing
+        assert!(template.stamp(&mut world, [10, 5, 10], Rotation90::Quarter).is_empty());
+        // Four quarter turns come back round
+        for rotation in [Rotation90::None, Rotation90::Quarter, Rotation90::Half, Rotation90::ThreeQuarters] {
+            let mut turned = (2, 7, -5);
+            for _ in 0..4 {
+                turned = rotation.apply(turned);
+            }
+            assert_eq!(turned, (2, 7, -5));
+        }
+    }
+
+    #[test]
+    fn test_stamp_clips_to_world() {
+        let mut source = VoxelWorld::empty(16);
+        marked_cube(&mut source);
+        let template = WorldTemplate::capture(&source, [2, 3, 2], [0, 0, 0]);
+        assert_eq!(template.voxels.len(), 28);
+
+        let mut world = VoxelWorld::empty(16);
+        let edits = template.stamp(&mut world, [0, 14, 15], Rotation90::None);
+        // x = -1, y from 16 and z = 16 fall outside, leaving a 2 × 2 × 2 corner
+        assert_eq!(edits.len(), 8);
+        assert!(edits.iter().all(|edit| edit.before.is_none()));
+    }
+
+    #[test]
+    fn test_save_load_and_console_command() {
+        let directory = std::env::temp_dir().join(format!("robin_voxel_templates_{}", std::process::id()));
+        let mut world = VoxelWorld::empty(16);
+        marked_cube(&mut world);
+        let mut console = DevConsole::new(None);
+        console.register_command("template", template_command(directory.clone()));
+
+        assert!(console.execute("template list", &mut world).starts_with("No templates"));
+        assert_eq!(console.execute("template save tower 0 0 0 2 3 2", &mut world), "Saved 'tower' (28 voxels)");
+        assert_eq!(console.execute("template save ../escape 0 0 0 1 1 1", &mut world), "Invalid template name '../escape'");
+        assert_eq!(console.execute("template list", &mut world), "Templates: tower");
+
+        let loaded = WorldTemplate::load(&directory.join("tower.json"), world.registry()).unwrap();
+        assert_eq!(loaded, WorldTemplate::capture(&world, [0, 0, 0], [2, 3, 2]).with_name("tower"));
+
+        assert_eq!(console.execute("template stamp tower 8 0 8 180", &mut world), "Stamped 'tower', changing 28 voxels");
+        assert_eq!(world.get(9, 3, 9), Some(registry::CRYSTAL));
+        assert_eq!(console.execute("template stamp tower 8 0 8 45", &mut world), "Rotation must be 0, 90, 180 or 270");
+        assert!(console.execute("template stamp missing 1 1 1", &mut world).starts_with("Failed to load"));
+        std::fs::remove_dir_all(&directory).ok();
+    }
+}
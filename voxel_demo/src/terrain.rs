@@ -0,0 +1,189 @@
+// Procedural terrain
+// Multi-octave 2D Perlin noise built on a seeded permutation table, so the
+// demo doesn't need an external noise crate.
+
+/// Fractal Perlin noise height field. The same seed and parameters always
+/// produce the same terrain.
+#[derive(Clone, Debug)]
+pub struct TerrainGenerator {
+    seed: u64,
+    octaves: u8,
+    persistence: f32,
+    lacunarity: f32,
+    /// Permutation of 0..256, repeated so lookups can index past 255 without wrapping
+    perm: [u8; 512],
+}
+
+impl TerrainGenerator {
+    /// `persistence` scales the amplitude and `lacunarity` the frequency of
+    /// each successive octave.
+    pub fn new(seed: u64, octaves: u8, persistence: f32, lacunarity: f32) -> Self {
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+        let mut state = seed;
+        for i in (1..table.len()).rev() {
+            let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+
+        let mut perm = [0u8; 512];
+        for (i, value) in perm.iter_mut().enumerate() {
+            *value = table[i & 255];
+        }
+
+        Self {
+            seed,
+            octaves: octaves.max(1),
+            persistence,
+            lacunarity,
+            perm,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Fractal noise at (x, z), normalised to roughly [-1, 1]. One unit is one
+    /// cycle of the base octave, so callers scale world coordinates down first.
+    pub fn height_at(&self, x: f32, z: f32) -> f32 {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut max_amplitude = 0.0;
+
+        for _ in 0..self.octaves {
+            total += self.perlin(x * frequency, z * frequency) * amplitude;
+            max_amplitude += amplitude;
+            amplitude *= self.persistence;
+            frequency *= self.lacunarity;
+        }
+
+        total / max_amplitude
+    }
+
+    /// Uniform pseudo-random value in [0, 1) for an integer column, used to
+    /// scatter features such as crystals deterministically.
+    pub fn feature_at(&self, x: i32, z: i32) -> f32 {
+        let mut state = self.seed ^ ((x as u32 as u64) << 32 | z as u32 as u64).wrapping_mul(0x9E3779B97F4A7C15);
+        (splitmix64(&mut state) >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn perlin(&self, x: f32, z: f32) -> f32 {
+        let xi = (x.floor() as i32 & 255) as usize;
+        let zi = (z.floor() as i32 & 255) as usize;
+        let xf = x - x.floor();
+        let zf = z - z.floor();
+        let u = fade(xf);
+        let v = fade(zf);
+
+        let p = &self.perm;
+        let aa = p[p[xi] as usize + zi];
+        let ab = p[p[xi] as usize + zi + 1];
+        let ba = p[p[xi + 1] as usize + zi];
+        let bb = p[p[xi + 1] as usize + zi + 1];
+
+        let x1 = lerp(grad(aa, xf, zf), grad(ba, xf - 1.0, zf), u);
+        let x2 = lerp(grad(ab, xf, zf - 1.0), grad(bb, xf - 1.0, zf - 1.0), u);
+        lerp(x1, x2, v)
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + t * (b - a)
+}
+
+/// Dot product of (x, z) with one of eight gradient directions picked by `hash`
+fn grad(hash: u8, x: f32, z: f32) -> f32 {
+    match hash & 7 {
+        0 => x + z,
+        1 => -x + z,
+        2 => x - z,
+        3 => -x - z,
+        4 => x,
+        5 => -x,
+        6 => z,
+        _ => -z,
+    }
+}
+
+pub fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_is_deterministic() {
+        let a = TerrainGenerator::new(42, 4, 0.5, 2.0);
+        let b = TerrainGenerator::new(42, 4, 0.5, 2.0);
+        let c = TerrainGenerator::new(43, 4, 0.5, 2.0);
+
+        let mut differs = false;
+        for i in 0..200 {
+            let (x, z) = (i as f32 * 0.37, i as f32 * 0.21);
+            assert_eq!(a.height_at(x, z), b.height_at(x, z));
+            differs |= a.height_at(x, z) != c.height_at(x, z);
+        }
+        assert!(differs);
+    }
+
+    #[test]
+    fn test_height_range_and_continuity() {
+        let generator = TerrainGenerator::new(7, 5, 0.5, 2.0);
+        let mut min = f32::MAX;
+        let mut max = f32::MIN;
+
+        for i in 0..100 {
+            for j in 0..100 {
+                let (x, z) = (i as f32 * 0.05, j as f32 * 0.05);
+                let h = generator.height_at(x, z);
+                min = min.min(h);
+                max = max.max(h);
+
+                // Small steps give small changes
+                assert!((generator.height_at(x + 0.001, z) - h).abs() < 0.05);
+            }
+        }
+
+        assert!(min >= -1.0 && max <= 1.0);
+        // Not a flat plane
+        assert!(max - min > 0.3);
+    }
+
+    #[test]
+    fn test_integer_lattice_is_zero_for_single_octave() {
+        // Perlin noise vanishes on lattice points, a quick sanity check of the gradients
+        let generator = TerrainGenerator::new(3, 1, 0.5, 2.0);
+        for i in -5..5 {
+            assert_eq!(generator.height_at(i as f32, (i * 3) as f32), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_feature_at_is_uniform_and_deterministic() {
+        let generator = TerrainGenerator::new(99, 4, 0.5, 2.0);
+        let mut below_half = 0;
+        for x in 0..64 {
+            for z in 0..64 {
+                let value = generator.feature_at(x, z);
+                assert!((0.0..1.0).contains(&value));
+                assert_eq!(value, generator.feature_at(x, z));
+                if value < 0.5 {
+                    below_half += 1;
+                }
+            }
+        }
+        // Roughly half of 4096 samples
+        assert!((1800..2300).contains(&below_half));
+    }
+}
@@ -0,0 +1,435 @@
+// SDF text
+// Draws HUD labels with a monospaced font stored as a signed distance field.
+// Each atlas texel holds how far it is from the nearest glyph edge, so the
+// shader can find a sharp edge at any scale instead of magnifying pixels.
+// The atlas is baked into the binary; `examples/bake_sdf_font.rs` regenerates
+// it.
+
+use crate::error::RobinResult;
+use crate::particles;
+use crate::render_graph::RenderGraph;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
+
+/// Render graph pass drawing the queued text
+pub const PASS: &str = "text";
+
+/// The atlas covers printable ASCII, 32..=126, in rows of `ATLAS_COLUMNS`
+pub const FIRST_CHAR: u32 = 32;
+pub const GLYPH_COUNT: u32 = 95;
+pub const ATLAS_COLUMNS: u32 = 16;
+pub const ATLAS_ROWS: u32 = GLYPH_COUNT.div_ceil(ATLAS_COLUMNS);
+/// Atlas cell size in texels, with room around each glyph for its field
+pub const CELL_WIDTH: u32 = 24;
+pub const CELL_HEIGHT: u32 = 40;
+/// Height from the font's ascent to its descent at which it was baked, in texels
+pub const FONT_SIZE: f32 = 32.0;
+/// Distance between one character and the next, in texels
+pub const ADVANCE: f32 = 16.55;
+/// Pen position of each glyph within its cell, centring the advance
+pub const ORIGIN_X: f32 = (CELL_WIDTH as f32 - ADVANCE) / 2.0;
+pub const BASELINE: f32 = 30.0;
+/// Distance from an edge, in texels, at which the field reaches 0 or 1
+pub const SDF_SPREAD: f32 = 4.0;
+
+/// `ATLAS_COLUMNS * CELL_WIDTH` by `ATLAS_ROWS * CELL_HEIGHT` texels, one
+/// byte each, 128 on the glyph edges
+static SDF_ATLAS: &[u8] = include_bytes!("fonts/mono_sdf.r8");
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct TextVertex {
+    position: [f32; 2],
+    uv: [f32; 2],
+    color: [f32; 4],
+}
+
+const TEXT_SHADER: &str = r#"
+@group(0) @binding(0)
+var font: texture_2d<f32>;
+@group(0) @binding(1)
+var font_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(in.position, 0.0, 1.0);
+    out.uv = in.uv;
+    out.color = in.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let distance = textureSample(font, font_sampler, in.uv).r;
+    // Antialias over about a screen pixel, however large the text is drawn
+    let smoothing = max(fwidth(distance) * 0.7, 0.001);
+    let coverage = smoothstep(0.5 - smoothing, 0.5 + smoothing, distance);
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
+"#;
+
+/// Atlas index of a character; characters without a glyph draw as `?`
+fn glyph_index(c: char) -> u32 {
+    match c as u32 {
+        code @ FIRST_CHAR..=126 => code - FIRST_CHAR,
+        _ => '?' as u32 - FIRST_CHAR,
+    }
+}
+
+/// Draws text straight onto a render target, each call batching its glyphs
+/// into a single draw
+pub struct TextRenderer {
+    device: Arc<wgpu::Device>,
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    surface_size: [f32; 2],
+    /// Text and its `draw_text` position, scale and color, drawn by the
+    /// next frame's text pass
+    queued: Vec<(String, f32, f32, f32, [f32; 4])>,
+}
+
+impl TextRenderer {
+    pub fn new(
+        device: Arc<wgpu::Device>,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        surface_width: u32,
+        surface_height: u32,
+    ) -> Self {
+        let atlas = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("SDF Font Atlas"),
+                size: wgpu::Extent3d {
+                    width: ATLAS_COLUMNS * CELL_WIDTH,
+                    height: ATLAS_ROWS * CELL_HEIGHT,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            SDF_ATLAS,
+        );
+        let view = atlas.create_view(&wgpu::TextureViewDescriptor::default());
+        // The field has to be interpolated between texels for smooth edges
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("SDF Font Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Text Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Text Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Text Shader"),
+            source: wgpu::ShaderSource::Wgsl(TEXT_SHADER.into()),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Text Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Text Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<TextVertex>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x4],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            // Drawn in its own pass after the scene, with no depth attachment
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self {
+            device,
+            pipeline,
+            bind_group,
+            surface_size: [surface_width as f32, surface_height as f32],
+            queued: Vec::new(),
+        }
+    }
+
+    /// Size of the targets text will be drawn to, in pixels
+    pub fn resize(&mut self, surface_width: u32, surface_height: u32) {
+        self.surface_size = [surface_width as f32, surface_height as f32];
+    }
+
+    /// Two triangles per visible character. `x`, `y` is the top-left corner
+    /// of the first character's cell and `scale` is screen pixels per atlas
+    /// texel; `\n` starts a new line below.
+    fn layout(&self, text: &str, x: f32, y: f32, scale: f32, color: [f32; 4]) -> Vec<TextVertex> {
+        let to_clip = |px: f32, py: f32| [px / self.surface_size[0] * 2.0 - 1.0, 1.0 - py / self.surface_size[1] * 2.0];
+        let atlas_size = [(ATLAS_COLUMNS * CELL_WIDTH) as f32, (ATLAS_ROWS * CELL_HEIGHT) as f32];
+        let (width, height) = (CELL_WIDTH as f32 * scale, CELL_HEIGHT as f32 * scale);
+
+        let mut vertices = Vec::with_capacity(text.len() * 6);
+        for (row, line) in text.lines().enumerate() {
+            let top = y + row as f32 * height;
+            for (column, c) in line.chars().enumerate() {
+                if c == ' ' {
+                    continue;
+                }
+                let left = x + column as f32 * ADVANCE * scale;
+                let index = glyph_index(c);
+                let u0 = ((index % ATLAS_COLUMNS) * CELL_WIDTH) as f32 / atlas_size[0];
+                let v0 = ((index / ATLAS_COLUMNS) * CELL_HEIGHT) as f32 / atlas_size[1];
+                let (u1, v1) = (u0 + CELL_WIDTH as f32 / atlas_size[0], v0 + CELL_HEIGHT as f32 / atlas_size[1]);
+
+                let corners = [
+                    (to_clip(left, top), [u0, v0]),
+                    (to_clip(left, top + height), [u0, v1]),
+                    (to_clip(left + width, top + height), [u1, v1]),
+                    (to_clip(left + width, top), [u1, v0]),
+                ];
+                for corner in [0, 1, 2, 0, 2, 3] {
+                    let (position, uv) = corners[corner];
+                    vertices.push(TextVertex { position, uv, color });
+                }
+            }
+        }
+        vertices
+    }
+
+    /// Draws `text` over whatever `target` already holds, in a pass of its own
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_text(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        text: &str,
+        x: f32,
+        y: f32,
+        scale: f32,
+        color: [f32; 4],
+    ) {
+        let vertices = self.layout(text, x, y, scale, color);
+        if vertices.is_empty() {
+            return;
+        }
+        let vertex_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Text Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Text Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.draw(0..vertices.len() as u32, 0..1);
+    }
+
+    /// Queues `text` to be drawn as `draw_text` would by the next run of the
+    /// text pass
+    pub fn queue_text(&mut self, text: &str, x: f32, y: f32, scale: f32, color: [f32; 4]) {
+        self.queued.push((text.to_string(), x, y, scale, color));
+    }
+
+    /// Adds the pass drawing the queued text over everything else, which
+    /// empties the queue
+    pub fn register(renderer: &Rc<RefCell<Self>>, graph: &mut RenderGraph) -> RobinResult<usize> {
+        let renderer = renderer.clone();
+        graph.add_node_after(PASS, &[particles::PASS], move |encoder, context| {
+            let mut renderer = renderer.borrow_mut();
+            for (text, x, y, scale, color) in std::mem::take(&mut renderer.queued) {
+                renderer.draw_text(encoder, context.view, &text, x, y, scale, color);
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_atlas_has_a_field_for_every_glyph() {
+        let width = (ATLAS_COLUMNS * CELL_WIDTH) as usize;
+        assert_eq!(SDF_ATLAS.len(), width * (ATLAS_ROWS * CELL_HEIGHT) as usize);
+
+        let cell = |c: char| {
+            let index = glyph_index(c);
+            let (left, top) = ((index % ATLAS_COLUMNS * CELL_WIDTH) as usize, (index / ATLAS_COLUMNS * CELL_HEIGHT) as usize);
+            (0..CELL_HEIGHT as usize).flat_map(move |y| (0..CELL_WIDTH as usize).map(move |x| SDF_ATLAS[(top + y) * width + left + x]))
+        };
+        for c in (FIRST_CHAR..FIRST_CHAR + GLYPH_COUNT).filter_map(char::from_u32).filter(|c| !c.is_whitespace()) {
+            assert!(cell(c).any(|value| value > 128), "no glyph for {:?}", c);
+        }
+        assert!(cell(' ').all(|value| value < 128));
+        assert_eq!(glyph_index('é'), glyph_index('?'));
+    }
+
+    #[test]
+    fn test_draw_text_renders_hello() {
+        let instance = wgpu::Instance::default();
+        let adapter = match pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(&instance, None)) {
+            Some(adapter) => adapter,
+            None => {
+                eprintln!("No GPU adapter available, skipping text renderer test");
+                return;
+            }
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap();
+        let device = Arc::new(device);
+
+        const WIDTH: u32 = 256;
+        const HEIGHT: u32 = 64;
+        let format = wgpu::TextureFormat::Rgba8Unorm;
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d { width: WIDTH, height: HEIGHT, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let renderer = TextRenderer::new(device.clone(), &queue, format, WIDTH, HEIGHT);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        // The target starts out transparent black. Five cells of 24x40 texels at half scale, within x 16..61 and y 8..28
+        renderer.draw_text(&mut encoder, &view, "Hello", 16.0, 8.0, 0.5, [1.0, 1.0, 1.0, 1.0]);
+
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (WIDTH * HEIGHT * 4) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            target.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(WIDTH * 4),
+                    rows_per_image: Some(HEIGHT),
+                },
+            },
+            target.size(),
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+        readback.slice(..).map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        device.poll(wgpu::Maintain::Wait);
+        let pixels = readback.slice(..).get_mapped_range().to_vec();
+
+        let lit = |x: u32, y: u32| pixels[((y * WIDTH + x) * 4) as usize] > 128;
+        let lit_in = |xs: std::ops::Range<u32>, ys: std::ops::Range<u32>| {
+            ys.flat_map(|y| xs.clone().map(move |x| (x, y))).filter(|&(x, y)| lit(x, y)).count()
+        };
+        // Each of the five letters leaves a mark in its own column
+        for letter in 0..5 {
+            let left = 16 + (letter as f32 * ADVANCE * 0.5) as u32;
+            assert!(lit_in(left..left + 8, 8..28) > 5, "letter {} missing", letter);
+        }
+        // The H's left stem runs down from the cap height to the baseline
+        let stem = 16 + (ORIGIN_X * 0.5) as u32 + 1;
+        assert!((13..21).all(|y| lit_in(stem - 1..stem + 2, y..y + 1) > 0));
+        // Nothing outside the text's cells
+        let total = lit_in(0..WIDTH, 0..HEIGHT);
+        assert_eq!(total, lit_in(16..61, 8..28));
+        assert!(total > 100);
+    }
+}
@@ -0,0 +1,96 @@
+// Transparency sorting buckets
+// Sorting every transparent face back-to-front each frame costs more the
+// larger the world gets, and far from the camera a wrong order is hard to
+// see. Each chunk's transparent faces are sorted every frame, when the
+// camera has moved far enough, or never, depending on how far away the
+// chunk is.
+
+use crate::RenderSettings;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransparencyBucket {
+    /// Re-sorted every frame
+    Near,
+    /// Re-sorted once the camera has moved `transparency_resort_distance`
+    /// from where they were last sorted
+    Mid,
+    /// Never sorted; drawn in whatever order they were last left in, with
+    /// only the depth test against opaque geometry
+    Far,
+}
+
+impl TransparencyBucket {
+    /// The bucket for faces `distance` from the camera. Each bucket's range
+    /// includes its lower bound.
+    pub fn for_distance(distance: f32, settings: &RenderSettings) -> Self {
+        if distance < settings.transparency_near_distance {
+            TransparencyBucket::Near
+        } else if distance < settings.transparency_far_distance {
+            TransparencyBucket::Mid
+        } else {
+            TransparencyBucket::Far
+        }
+    }
+
+    /// Whether faces in this bucket, last sorted with the camera at
+    /// `sorted_from` (if ever), need sorting for a camera at `camera`
+    pub fn needs_sort(self, sorted_from: Option<[f32; 3]>, camera: [f32; 3], settings: &RenderSettings) -> bool {
+        match self {
+            TransparencyBucket::Near => true,
+            TransparencyBucket::Mid => sorted_from.is_none_or(|from| {
+                let moved = (0..3).map(|axis| (camera[axis] - from[axis]).powi(2)).sum::<f32>().sqrt();
+                moved > settings.transparency_resort_distance
+            }),
+            TransparencyBucket::Far => false,
+        }
+    }
+}
+
+/// Distance from `point` to the nearest point of the box from `min` to
+/// `max`, or zero inside it
+pub fn distance_to_box(point: [f32; 3], min: [f32; 3], max: [f32; 3]) -> f32 {
+    (0..3)
+        .map(|axis| (min[axis] - point[axis]).max(point[axis] - max[axis]).max(0.0).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_split_at_the_configured_distances() {
+        let settings = RenderSettings::default();
+        let bucket = |distance| TransparencyBucket::for_distance(distance, &settings);
+        assert_eq!(bucket(0.0), TransparencyBucket::Near);
+        assert_eq!(bucket(15.9), TransparencyBucket::Near);
+        assert_eq!(bucket(16.0), TransparencyBucket::Mid);
+        assert_eq!(bucket(63.9), TransparencyBucket::Mid);
+        assert_eq!(bucket(64.0), TransparencyBucket::Far);
+
+        let settings = RenderSettings { transparency_near_distance: 4.0, ..settings };
+        assert_eq!(TransparencyBucket::for_distance(8.0, &settings), TransparencyBucket::Mid);
+    }
+
+    #[test]
+    fn test_mid_range_faces_resort_after_the_camera_moves() {
+        let settings = RenderSettings::default();
+        let step = settings.transparency_resort_distance;
+        let mid = TransparencyBucket::Mid;
+        assert!(mid.needs_sort(None, [0.0; 3], &settings));
+        assert!(!mid.needs_sort(Some([0.0; 3]), [step, 0.0, 0.0], &settings));
+        assert!(mid.needs_sort(Some([0.0; 3]), [step, 0.1, 0.0], &settings));
+
+        assert!(TransparencyBucket::Near.needs_sort(Some([0.0; 3]), [0.0; 3], &settings));
+        assert!(!TransparencyBucket::Far.needs_sort(None, [0.0; 3], &settings));
+    }
+
+    #[test]
+    fn test_distance_to_box() {
+        let (min, max) = ([0.0; 3], [16.0; 3]);
+        assert_eq!(distance_to_box([8.0, 3.0, 15.0], min, max), 0.0);
+        assert_eq!(distance_to_box([20.0, 8.0, 8.0], min, max), 4.0);
+        assert_eq!(distance_to_box([19.0, -4.0, 8.0], min, max), 5.0);
+    }
+}
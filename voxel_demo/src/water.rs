@@ -0,0 +1,289 @@
+// Water simulation
+// A cellular automaton that lets Water voxels fall and spread. The simulation
+// runs on a background task against its own copy of the world and sends the
+// cells it changed back to the main thread.
+
+use crate::registry::{self, VoxelId};
+use crate::terrain::splitmix64;
+use crate::VoxelWorld;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Time between simulation ticks on the background task
+pub const WATER_TICK: Duration = Duration::from_millis(200);
+
+/// Horizontal neighbour offsets, shuffled per cell each tick
+const SIDEWAYS: [[i64; 2]; 4] = [[1, 0], [-1, 0], [0, 1], [0, -1]];
+
+/// One cell changed by a tick, with the value the simulation expected to
+/// replace so stale changes can be detected
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VoxelChange {
+    pub position: [usize; 3],
+    pub before: Option<VoxelId>,
+    pub after: Option<VoxelId>,
+}
+
+pub struct WaterSimulator {
+    /// Cells that are refilled with water at the end of every tick
+    sources: Vec<[usize; 3]>,
+    tick: u64,
+}
+
+impl WaterSimulator {
+    pub fn new() -> Self {
+        Self { sources: Vec::new(), tick: 0 }
+    }
+
+    pub fn add_source(&mut self, position: [usize; 3]) {
+        if !self.sources.contains(&position) {
+            self.sources.push(position);
+        }
+    }
+
+    pub fn remove_source(&mut self, position: [usize; 3]) {
+        self.sources.retain(|&source| source != position);
+    }
+
+    /// Runs one tick. Each water voxel falls one cell if the cell below is
+    /// empty, otherwise it moves to one of its horizontal neighbours, tried
+    /// in random order. Sideways flow only goes where the water can keep
+    /// falling, so pools settle to within one voxel of level and then stop
+    /// changing. No cell moves or is filled more than once per tick.
+    pub fn step(&mut self, world: &mut VoxelWorld) -> Vec<VoxelChange> {
+        let size = world.size;
+        let index = |p: [usize; 3]| (p[0] * size + p[1]) * size + p[2];
+        let mut settled = vec![false; size * size * size];
+        let mut before = HashMap::new();
+
+        // Bottom-up, so a falling column moves as one
+        for y in 0..size {
+            for x in 0..size {
+                for z in 0..size {
+                    let from = [x, y, z];
+                    if world.voxels[x][y][z] != Some(registry::WATER) || settled[index(from)] {
+                        continue;
+                    }
+                    let Some(to) = self.flow_target(world, from) else {
+                        continue;
+                    };
+                    before.entry(from).or_insert(Some(registry::WATER));
+                    before.entry(to).or_insert(None);
+                    world.set_voxel(from[0], from[1], from[2], None);
+                    world.set_voxel(to[0], to[1], to[2], Some(registry::WATER));
+                    settled[index(to)] = true;
+                }
+            }
+        }
+
+        for &[x, y, z] in &self.sources {
+            if x < size && y < size && z < size && world.voxels[x][y][z].is_none() {
+                before.entry([x, y, z]).or_insert(None);
+                world.set_voxel(x, y, z, Some(registry::WATER));
+            }
+        }
+        self.tick += 1;
+
+        before
+            .into_iter()
+            .filter_map(|(position, before)| {
+                let after = world.voxels[position[0]][position[1]][position[2]];
+                (after != before).then_some(VoxelChange { position, before, after })
+            })
+            .collect()
+    }
+
+    /// Where the water at `p` moves this tick, if anywhere
+    fn flow_target(&self, world: &VoxelWorld, p: [usize; 3]) -> Option<[usize; 3]> {
+        let [x, y, z] = p.map(|c| c as i64);
+        if y > 0 && !world.is_solid([x, y - 1, z]) {
+            return Some([p[0], p[1] - 1, p[2]]);
+        }
+
+        let mut state = self.tick ^ (index_hash(p) << 1);
+        let mut directions = SIDEWAYS;
+        for i in (1..directions.len()).rev() {
+            let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+            directions.swap(i, j);
+        }
+        directions.into_iter().find_map(|[dx, dz]| {
+            let target = [x + dx, y, z + dz];
+            let in_world = target.iter().all(|&c| c >= 0 && (c as usize) < world.size);
+            (in_world && y > 0 && !world.is_solid(target) && !world.is_solid([target[0], y - 1, target[2]]))
+                .then(|| target.map(|c| c as usize))
+        })
+    }
+}
+
+fn index_hash(p: [usize; 3]) -> u64 {
+    let mut state = ((p[0] as u64) << 42) ^ ((p[1] as u64) << 21) ^ p[2] as u64;
+    splitmix64(&mut state)
+}
+
+/// Updates sent from the main thread to the simulation task
+#[derive(Debug, PartialEq)]
+enum WaterEdit {
+    Voxel([usize; 3], Option<VoxelId>),
+    Source([usize; 3]),
+}
+
+/// Main-thread handle to a water simulation running on a tokio task
+pub struct WaterTask {
+    edits: mpsc::UnboundedSender<WaterEdit>,
+    changes: mpsc::UnboundedReceiver<Vec<VoxelChange>>,
+}
+
+impl WaterTask {
+    /// Spawns the simulation on the current tokio runtime with a copy of
+    /// `world`'s voxels. The task stops once the handle is dropped.
+    pub fn spawn(world: &VoxelWorld) -> Self {
+        let (edits, mut edit_receiver) = mpsc::unbounded_channel();
+        let (change_sender, changes) = mpsc::unbounded_channel();
+        let mut copy = VoxelWorld::from_voxels(world.voxels.clone(), world.size, world.registry.clone());
+
+        tokio::spawn(async move {
+            let mut simulator = WaterSimulator::new();
+            let mut interval = tokio::time::interval(WATER_TICK);
+            loop {
+                interval.tick().await;
+                loop {
+                    match edit_receiver.try_recv() {
+                        Ok(WaterEdit::Voxel([x, y, z], voxel)) => {
+                            copy.set_voxel(x, y, z, voxel);
+                            if voxel != Some(registry::WATER) {
+                                simulator.remove_source([x, y, z]);
+                            }
+                        }
+                        Ok(WaterEdit::Source(position)) => simulator.add_source(position),
+                        Err(mpsc::error::TryRecvError::Empty) => break,
+                        Err(mpsc::error::TryRecvError::Disconnected) => return,
+                    }
+                }
+                let tick_changes = simulator.step(&mut copy);
+                if !tick_changes.is_empty() && change_sender.send(tick_changes).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self { edits, changes }
+    }
+
+    /// Tells the simulation about a voxel changed on the main thread.
+    /// Replacing a source with anything but water stops it flowing.
+    pub fn notify_edit(&self, position: [usize; 3], voxel: Option<VoxelId>) {
+        self.edits.send(WaterEdit::Voxel(position, voxel)).ok();
+    }
+
+    /// Makes the water at `position` a source that never runs dry
+    pub fn add_source(&self, position: [usize; 3]) {
+        self.edits.send(WaterEdit::Source(position)).ok();
+    }
+
+    /// Applies every tick finished since the last call and returns how many
+    /// cells changed. A change to a cell the player edited in the meantime is
+    /// dropped, and the simulation is told the cell's actual contents.
+    pub fn apply_changes(&mut self, world: &mut VoxelWorld) -> usize {
+        let mut applied = 0;
+        while let Ok(tick_changes) = self.changes.try_recv() {
+            for change in tick_changes {
+                let [x, y, z] = change.position;
+                let current = world.voxels[x][y][z];
+                if current == change.before {
+                    world.set_voxel(x, y, z, change.after);
+                    applied += 1;
+                } else if current != change.after {
+                    self.notify_edit(change.position, current);
+                }
+            }
+        }
+        applied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 9³ world with a stone floor at y = 0
+    fn floor_world() -> VoxelWorld {
+        let mut world = VoxelWorld::empty(9);
+        for x in 0..9 {
+            for z in 0..9 {
+                world.set_voxel(x, 0, z, Some(registry::STONE));
+            }
+        }
+        world
+    }
+
+    fn water_count(world: &VoxelWorld) -> usize {
+        world.voxels.iter().flatten().flatten().filter(|&&v| v == Some(registry::WATER)).count()
+    }
+
+    #[test]
+    fn test_source_fills_column_and_spreads() {
+        let mut world = floor_world();
+        let mut simulator = WaterSimulator::new();
+        world.set_voxel(4, 4, 4, Some(registry::WATER));
+        simulator.add_source([4, 4, 4]);
+
+        for _ in 0..20 {
+            simulator.step(&mut world);
+        }
+
+        assert_eq!(world.get(4, 1, 4), Some(registry::WATER));
+        let spread = SIDEWAYS
+            .iter()
+            .filter(|[dx, dz]| world.get((4 + dx) as usize, 1, (4 + dz) as usize) == Some(registry::WATER))
+            .count();
+        assert!(spread > 0, "no water beside the column");
+        assert_eq!(world.get(4, 0, 4), Some(registry::STONE));
+    }
+
+    #[test]
+    fn test_water_falls_one_cell_per_tick() {
+        let mut world = floor_world();
+        let mut simulator = WaterSimulator::new();
+        world.set_voxel(2, 6, 2, Some(registry::WATER));
+
+        let changes = simulator.step(&mut world);
+        assert_eq!(world.get(2, 5, 2), Some(registry::WATER));
+        assert_eq!(world.get(2, 6, 2), None);
+        assert_eq!(changes.len(), 2);
+        assert!(changes.contains(&VoxelChange { position: [2, 5, 2], before: None, after: Some(registry::WATER) }));
+    }
+
+    #[test]
+    fn test_pile_levels_out_and_settles() {
+        let mut world = floor_world();
+        let mut simulator = WaterSimulator::new();
+        for y in 1..5 {
+            world.set_voxel(4, y, 4, Some(registry::WATER));
+        }
+
+        for _ in 0..40 {
+            simulator.step(&mut world);
+        }
+        assert_eq!(water_count(&world), 4);
+        assert!((1..9).all(|y| (0..9).all(|x| (0..9).all(|z| y == 1 || world.get(x, y, z).is_none()))));
+        assert!(simulator.step(&mut world).is_empty());
+    }
+
+    #[test]
+    fn test_stale_change_is_not_applied() {
+        let (edits, mut edit_receiver) = mpsc::unbounded_channel();
+        let (change_sender, changes) = mpsc::unbounded_channel();
+        let mut task = WaterTask { edits, changes };
+        let mut world = floor_world();
+
+        // Water flowed into a cell the player has since filled with stone
+        world.set_voxel(3, 1, 3, Some(registry::STONE));
+        let flow = VoxelChange { position: [3, 1, 3], before: None, after: Some(registry::WATER) };
+        change_sender.send(vec![flow]).unwrap();
+
+        assert_eq!(task.apply_changes(&mut world), 0);
+        assert_eq!(world.get(3, 1, 3), Some(registry::STONE));
+        assert_eq!(edit_receiver.try_recv().unwrap(), WaterEdit::Voxel([3, 1, 3], Some(registry::STONE)));
+    }
+}
@@ -0,0 +1,236 @@
+// Debug wireframes
+// Outlines of axis-aligned boxes, such as chunk boundaries and physics
+// bodies, drawn as plain coloured lines. Boxes are queued during the frame
+// and all of them go out in a single draw call.
+
+use crate::error::RobinResult;
+use crate::render_graph::RenderGraph;
+use crate::DEPTH_FORMAT;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Render graph pass drawing the uploaded boxes
+pub const PASS: &str = "wireframes";
+
+/// Each of a box's 12 edges is its own pair of vertices in the line list
+pub const VERTICES_PER_AABB: usize = 24;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct WireframeVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+}
+
+impl WireframeVertex {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<WireframeVertex>() as u64,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: 12,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// Line list vertices for the edges of the box from `min` to `max`: the
+/// four edges along x, then along y, then along z
+pub fn aabb_vertices((min, max): ([f32; 3], [f32; 3]), color: [f32; 4]) -> Vec<WireframeVertex> {
+    let mut vertices = Vec::with_capacity(VERTICES_PER_AABB);
+    for axis in 0..3 {
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        for corner in 0..4 {
+            let mut start = min;
+            start[u] = if corner & 1 == 0 { min[u] } else { max[u] };
+            start[v] = if corner & 2 == 0 { min[v] } else { max[v] };
+            let mut end = start;
+            end[axis] = max[axis];
+            vertices.push(WireframeVertex { position: start, color });
+            vertices.push(WireframeVertex { position: end, color });
+        }
+    }
+    vertices
+}
+
+const WIREFRAME_SHADER: &str = r#"
+// Only the leading field of the shared uniforms is needed
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>, @location(1) color: vec4<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = uniforms.view_proj * vec4<f32>(position, 1.0);
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
+"#;
+
+/// Draws queued box outlines. Queue boxes with `draw_aabb` and `upload` them
+/// before the frame is rendered; the wireframe pass then `flush`es them over
+/// the world, depth-testing against it without writing depth.
+pub struct WireframeRenderer {
+    pipeline: wgpu::RenderPipeline,
+    queued: Vec<WireframeVertex>,
+    vertex_buffer: Option<wgpu::Buffer>,
+    vertex_count: u32,
+}
+
+impl WireframeRenderer {
+    /// `layout` must bind the shared `Uniforms` buffer at group 0
+    pub fn new(device: &wgpu::Device, layout: &wgpu::PipelineLayout, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Wireframe Shader"),
+            source: wgpu::ShaderSource::Wgsl(WIREFRAME_SHADER.into()),
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Wireframe Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[WireframeVertex::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                // Polygon mode only applies to triangles; line lists are
+                // rasterised as lines without needing POLYGON_MODE_LINE
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            queued: Vec::new(),
+            vertex_buffer: None,
+            vertex_count: 0,
+        }
+    }
+
+    /// Queues the outline of the box from `aabb.0` to `aabb.1`
+    pub fn draw_aabb(&mut self, aabb: ([f32; 3], [f32; 3]), color: [f32; 4]) {
+        self.queued.extend(aabb_vertices(aabb, color));
+    }
+
+    /// Moves the queued boxes into the vertex buffer for the next `flush`,
+    /// reusing the buffer when it is big enough, and starts a new queue
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.vertex_count = self.queued.len() as u32;
+        if !self.queued.is_empty() {
+            self.vertex_buffer = Some(crate::write_or_create_buffer(
+                device,
+                queue,
+                self.vertex_buffer.take(),
+                bytemuck::cast_slice(&self.queued),
+                wgpu::BufferUsages::VERTEX,
+                "Wireframe Vertex Buffer",
+            ));
+        }
+        self.queued.clear();
+    }
+
+    /// Draws every uploaded box in one call. Expects the uniform bind group
+    /// to already be set on the pass.
+    pub fn flush<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        let Some(vertex_buffer) = self.vertex_buffer.as_ref().filter(|_| self.vertex_count > 0) else {
+            return;
+        };
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.draw(0..self.vertex_count, 0..1);
+    }
+
+    /// Adds the pass drawing the uploaded boxes over the world, blended and
+    /// depth-tested against it
+    pub fn register(renderer: &Rc<RefCell<Self>>, graph: &mut RenderGraph) -> RobinResult<usize> {
+        let renderer = renderer.clone();
+        graph.add_node_after(PASS, &[crate::TRANSPARENT_PASS], move |encoder, context| {
+            let renderer = renderer.borrow();
+            let mut render_pass = context.begin_pass(encoder, "Wireframe Pass");
+            render_pass.set_bind_group(0, context.bind_group, &[]);
+            renderer.flush(&mut render_pass);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_aabb_has_its_twelve_edges() {
+        let color = [1.0, 0.0, 0.0, 1.0];
+        let vertices = aabb_vertices(([0.0; 3], [1.0; 3]), color);
+        assert_eq!(vertices.len(), VERTICES_PER_AABB);
+        assert!(vertices.iter().all(|v| v.color == color));
+
+        let mut edges: Vec<[[f32; 3]; 2]> = vertices.chunks(2).map(|pair| [pair[0].position, pair[1].position]).collect();
+        edges.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mut expected = Vec::new();
+        for a in 0..2 {
+            for b in 0..2 {
+                let (a, b) = (a as f32, b as f32);
+                expected.push([[0.0, a, b], [1.0, a, b]]);
+                expected.push([[a, 0.0, b], [a, 1.0, b]]);
+                expected.push([[a, b, 0.0], [a, b, 1.0]]);
+            }
+        }
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(edges, expected);
+    }
+}